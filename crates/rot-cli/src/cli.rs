@@ -1,8 +1,9 @@
 //! CLI argument and command definitions.
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum, ValueHint};
 use rot_core::config::Config;
 use rot_core::security::{ApprovalPolicy, RuntimeSecurityConfig, SandboxMode};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum ApprovalPolicyArg {
@@ -41,9 +42,27 @@ impl From<SandboxModeArg> for SandboxMode {
 #[derive(Parser)]
 #[command(name = "rot", version, about = "Recursive Operations Tool — AI coding agent")]
 pub struct Cli {
-    /// LLM provider to use.
-    #[arg(long, default_value = "anthropic", global = true)]
-    pub provider: String,
+    /// LLM provider to use (defaults to the configured provider).
+    #[arg(long, global = true)]
+    pub provider: Option<String>,
+
+    /// Run as if started in this directory.
+    #[arg(long, global = true, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub cwd: Option<PathBuf>,
+
+    /// Global config file to use instead of ~/.rot/config.json.
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        env = rot_core::config::CONFIG_ENV
+    )]
+    pub config: Option<PathBuf>,
+
+    /// Named config profile to apply on top of the global config.
+    #[arg(long, global = true, env = rot_core::config::PROFILE_ENV)]
+    pub profile: Option<String>,
 
     /// Built-in agent profile to use.
     #[arg(long, global = true)]
@@ -53,6 +72,10 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub model: Option<String>,
 
+    /// Maximum agent loop iterations per turn (overrides config).
+    #[arg(long, visible_alias = "max-turns", global = true, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_iterations: Option<u64>,
+
     /// Enable verbose logging.
     #[arg(short, long, global = true)]
     pub verbose: bool,
@@ -82,28 +105,74 @@ pub struct Cli {
     )]
     pub dangerously_bypass_approvals_and_sandbox: bool,
 
+    /// Describe what mutating tools would do (diffs, commands) without running them.
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Serve Prometheus metrics at http://127.0.0.1:PORT/metrics while rot runs.
+    #[arg(long, global = true, value_name = "PORT")]
+    pub metrics_port: Option<u16>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+// Parsed once per process; boxing `Exec` would only complicate matching on it.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// Start an interactive chat session (default).
     Chat,
 
     /// Execute a single prompt and exit.
+    ///
+    /// Piped stdin is attached to the prompt as context; with no prompt, or
+    /// `-`, stdin is the prompt.
     Exec {
-        /// The prompt to execute.
-        prompt: String,
+        /// The prompt to execute, or `-` to read it from stdin.
+        prompt: Option<String>,
+
+        /// The prompt to execute, as an alternative to the positional argument.
+        #[arg(short = 'p', long = "prompt", conflicts_with = "prompt")]
+        prompt_flag: Option<String>,
+
+        /// File to include in the prompt (repeatable).
+        #[arg(long = "file", value_name = "PATH", value_hint = ValueHint::FilePath)]
+        files: Vec<String>,
 
         /// Run using the Recursive Language Model (RLM) engine for huge contexts.
         #[arg(long)]
         rlm: bool,
 
         /// External context file to map into the RLM environment (required if --rlm is used)
-        #[arg(long, requires = "rlm")]
+        #[arg(long, requires = "rlm", value_hint = ValueHint::FilePath)]
         context: Option<String>,
 
+        /// Maximum RLM iterations, overriding `rlm.max_iterations`.
+        #[arg(long, value_name = "N", requires = "rlm", value_parser = clap::value_parser!(u64).range(1..))]
+        rlm_max_iterations: Option<u64>,
+
+        /// Stop after N RLM iterations in a row without new REPL output.
+        #[arg(long, value_name = "N", requires = "rlm")]
+        rlm_max_stalled: Option<u64>,
+
+        /// Tokens of REPL output shown to the model per RLM iteration.
+        #[arg(long, value_name = "N", requires = "rlm", value_parser = clap::value_parser!(u64).range(1..))]
+        rlm_iteration_tokens: Option<u64>,
+
+        /// Explore the task in N RLM branches at once and merge their answers.
+        #[arg(long, value_name = "N", requires = "rlm", value_parser = clap::value_parser!(u64).range(1..))]
+        rlm_branches: Option<u64>,
+
+        /// Run RLM even if the same question about the same context was
+        /// answered before, and leave its answer out of the cache.
+        #[arg(long, requires = "rlm")]
+        no_cache: bool,
+
+        /// File whose contents replace the built-in RLM system prompt.
+        #[arg(long, value_name = "PATH", requires = "rlm", value_hint = ValueHint::FilePath)]
+        rlm_system_prompt: Option<String>,
+
         /// Emit JSONL events to stdout.
         #[arg(long, conflicts_with = "final_json")]
         json: bool,
@@ -112,9 +181,131 @@ pub enum Commands {
         #[arg(long, conflicts_with = "json")]
         final_json: bool,
 
+        /// Stream JSONL events to stdout as the run progresses.
+        #[arg(long, conflicts_with_all = ["json", "final_json"])]
+        stream: bool,
+
         /// JSON Schema file used to validate final output JSON shape.
-        #[arg(long)]
+        #[arg(long, value_hint = ValueHint::FilePath)]
         output_schema: Option<String>,
+
+        /// Times to ask the model again when its reply fails --output-schema [default: 2].
+        #[arg(long, value_name = "N", requires = "output_schema")]
+        schema_retries: Option<u64>,
+
+        /// Stop the run after this many seconds (per prompt with --batch).
+        #[arg(long, value_name = "SECS", conflicts_with = "rlm", value_parser = clap::value_parser!(u64).range(1..))]
+        timeout: Option<u64>,
+
+        /// Run the `verify` checks (build, tests, lints) before finishing and
+        /// send failures back to the agent to fix.
+        #[arg(long, conflicts_with = "rlm")]
+        verify: bool,
+
+        /// Continue the most recent session in this directory.
+        ///
+        /// With --rlm, resume its interrupted RLM run instead; no prompt is needed.
+        #[arg(long = "continue", conflicts_with = "resume")]
+        continue_last: bool,
+
+        /// Continue the session with this ID (with --rlm, its interrupted RLM run).
+        #[arg(long, value_name = "ID")]
+        resume: Option<String>,
+
+        /// Run each prompt in this JSONL file as its own session.
+        ///
+        /// Each line is a JSON string or `{"id": ..., "prompt": ...}`.
+        #[arg(
+            long,
+            value_name = "PATH",
+            value_hint = ValueHint::FilePath,
+            conflicts_with_all = [
+                "prompt", "prompt_flag", "files", "rlm", "json", "final_json", "stream",
+                "output_schema", "continue_last", "resume",
+            ]
+        )]
+        batch: Option<String>,
+
+        /// Prompts run at the same time with --batch [default: 4].
+        #[arg(long, requires = "batch", value_parser = clap::value_parser!(u64).range(1..))]
+        parallel: Option<u64>,
+
+        /// Write --batch results to this JSONL file instead of stdout.
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, requires = "batch")]
+        out: Option<String>,
+
+        /// Token budget (input plus output) shared by all --batch prompts.
+        #[arg(long, requires = "batch")]
+        max_total_tokens: Option<usize>,
+    },
+
+    /// Write a starter ROT.md and .rot/config.toml for this repository.
+    Init {
+        /// Build ROT.md from the repository survey without asking a model.
+        #[arg(long)]
+        offline: bool,
+
+        /// Overwrite existing files.
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Write a conventional commit message for the staged changes and commit.
+    Commit {
+        /// Commit without asking for confirmation.
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Split unrelated staged changes into several commits.
+        #[arg(long)]
+        split: bool,
+    },
+
+    /// Review a git diff and print findings.
+    ///
+    /// Exits with 1 when the review fails, 2 when the reply has no findings
+    /// JSON, and 3 when a finding reaches `--fail-on`.
+    Review {
+        /// Review changes since this branch or commit (`<base>...HEAD`).
+        #[arg(long, conflicts_with = "staged")]
+        base: Option<String>,
+
+        /// Review staged changes only.
+        #[arg(long)]
+        staged: bool,
+
+        /// Print the findings as a JSON object.
+        #[arg(long)]
+        json: bool,
+
+        /// Fail when a finding is at least this severe.
+        #[arg(long, value_enum)]
+        fail_on: Option<crate::commands::review::Severity>,
+    },
+
+    /// Review or fix a GitHub pull request, e.g. from GitHub Actions.
+    ///
+    /// Needs `GITHUB_TOKEN` (or `GH_TOKEN`). Exits like `rot review`.
+    Gh {
+        #[command(subcommand)]
+        action: GhAction,
+    },
+
+    /// Serve a local HTTP API with server-sent events for editors and scripts.
+    Serve {
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on.
+        #[arg(long, default_value_t = 7430)]
+        port: u16,
+    },
+
+    /// List, inspect, and scaffold agent profiles.
+    Agent {
+        #[command(subcommand)]
+        action: AgentAction,
     },
 
     /// Manage sessions.
@@ -123,14 +314,132 @@ pub enum Commands {
         action: SessionAction,
     },
 
-    /// Inspect loaded tools.
+    /// Show token usage and spend across all saved sessions.
+    Usage {
+        /// Group requests by UTC day, model, or project directory.
+        #[arg(long, value_enum, default_value = "day")]
+        by: crate::commands::usage::UsageBy,
+
+        /// Only count the last N days, today included; 0 for all time.
+        #[arg(long, default_value_t = 30)]
+        days: u64,
+
+        /// Print JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the audit log of tool calls.
+    Audit {
+        /// Only calls from this session (an ID prefix is enough).
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only calls to this tool.
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Only calls that were refused.
+        #[arg(long)]
+        denied: bool,
+
+        /// Show the last N matching calls; 0 for all.
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        /// Print JSON lines instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Inspect loaded tools, or run one directly.
+    #[command(args_conflicts_with_subcommands = true)]
     Tools {
+        #[command(subcommand)]
+        action: Option<ToolsAction>,
+
         /// Show one tool in detail.
         name: Option<String>,
     },
+
+    /// Manage stored provider API keys.
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+
+    /// Print a shell completion script.
+    ///
+    /// Load it with `source <(rot completions bash)`, or the equivalent for
+    /// zsh, fish, PowerShell, or Elvish. In bash, zsh, and fish, models,
+    /// agents, and session IDs are completed live.
+    Completions {
+        #[arg(value_enum)]
+        shell: crate::commands::completions::Shell,
+    },
+
+    /// Print the rot(1) man page in roff format.
+    Man {
+        /// Write rot.1 and a page for every subcommand to this directory.
+        #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Print completion candidates; used by the completion scripts.
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        values: crate::commands::completions::DynamicValues,
+    },
 }
 
 impl Cli {
+    /// Effective provider name.
+    pub fn resolve_provider(&self, config: &Config) -> String {
+        self.provider
+            .clone()
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| config.provider.clone())
+    }
+
+    /// Effective provider and model after resolving `--model` through
+    /// `aliases`. An alias with a `provider/` prefix also selects the provider.
+    pub fn resolve_model(&self, config: &Config) -> (String, Option<String>) {
+        let provider = self.resolve_provider(config);
+        match self.model.as_deref().filter(|m| !m.is_empty()) {
+            Some(name) => {
+                let resolved = config.resolve_model(name);
+                (resolved.provider.unwrap_or(provider), Some(resolved.model))
+            }
+            None => (provider, None),
+        }
+    }
+
+    /// Like [`Cli::resolve_model`], but an RLM run without `--model` uses
+    /// `rlm.model` when it is set.
+    pub fn resolve_rlm_model(&self, config: &Config) -> (String, Option<String>) {
+        match (&self.model, &config.rlm.model) {
+            (None, Some(name)) => {
+                let resolved = config.resolve_model(name);
+                (
+                    resolved
+                        .provider
+                        .unwrap_or_else(|| self.resolve_provider(config)),
+                    Some(resolved.model),
+                )
+            }
+            _ => self.resolve_model(config),
+        }
+    }
+
+    /// Effective agent loop iteration limit.
+    pub fn resolve_max_iterations(&self, config: &Config) -> usize {
+        self.max_iterations
+            .map(|n| n as usize)
+            .unwrap_or(config.max_iterations)
+            .max(1)
+    }
+
     /// Effective runtime security for interactive chat sessions.
     pub fn resolve_runtime_security(&self, config: &Config) -> RuntimeSecurityConfig {
         let sandbox_mode = if let Some(explicit) = self.sandbox {
@@ -157,6 +466,9 @@ impl Cli {
             approval_policy,
             sandbox_mode,
             sandbox_network_access: config.sandbox_network_access,
+            dry_run: self.dry_run,
+            audit_log: config.audit.log_path(),
+            permissions: config.permissions.clone(),
         }
     }
 
@@ -184,31 +496,67 @@ impl Cli {
 
 #[cfg(test)]
 mod tests {
-    use super::{ApprovalPolicyArg, Cli, Commands, SessionAction};
+    use super::{
+        AgentAction, ApprovalPolicyArg, Cli, Commands, GhAction, KeysAction, SessionAction,
+        ToolsAction,
+    };
     use clap::Parser;
     use rot_core::{ApprovalPolicy, Config};
 
     #[test]
-    fn test_exec_json_flags_conflict() {
+    fn test_dry_run_flag_sets_runtime_security() {
+        let parsed = Cli::try_parse_from(["rot", "exec", "hello", "--dry-run", "--yolo"]).unwrap();
+        let resolved = parsed
+            .resolve_runtime_security_for_exec(&Config::default())
+            .unwrap();
+        assert!(resolved.dry_run);
+
+        let parsed = Cli::try_parse_from(["rot", "exec", "hello"]).unwrap();
+        assert!(!parsed.resolve_runtime_security(&Config::default()).dry_run);
+    }
+
+    #[test]
+    fn test_provider_falls_back_to_config() {
+        let config = Config {
+            provider: "openai".to_string(),
+            ..Config::default()
+        };
+        let parsed = Cli::try_parse_from(["rot"]).unwrap();
+        assert_eq!(parsed.resolve_provider(&config), "openai");
+
+        let parsed = Cli::try_parse_from(["rot", "--provider", "zai", "--profile", "oss"]).unwrap();
+        assert_eq!(parsed.resolve_provider(&config), "zai");
+        assert_eq!(parsed.profile.as_deref(), Some("oss"));
+
         let parsed = Cli::try_parse_from([
             "rot",
-            "exec",
-            "hello",
-            "--json",
-            "--final-json",
-        ]);
+            "session",
+            "list",
+            "--cwd",
+            "/work/repo",
+            "--config",
+            "ci.json",
+        ])
+        .unwrap();
+        assert_eq!(
+            parsed.cwd.as_deref(),
+            Some(std::path::Path::new("/work/repo"))
+        );
+        assert_eq!(
+            parsed.config.as_deref(),
+            Some(std::path::Path::new("ci.json"))
+        );
+    }
+
+    #[test]
+    fn test_exec_json_flags_conflict() {
+        let parsed = Cli::try_parse_from(["rot", "exec", "hello", "--json", "--final-json"]);
         assert!(parsed.is_err());
     }
 
     #[test]
     fn test_full_auto_conflicts_with_explicit_sandbox() {
-        let parsed = Cli::try_parse_from([
-            "rot",
-            "--full-auto",
-            "--sandbox",
-            "read-only",
-            "chat",
-        ]);
+        let parsed = Cli::try_parse_from(["rot", "--full-auto", "--sandbox", "read-only", "chat"]);
         assert!(parsed.is_err());
     }
 
@@ -218,6 +566,69 @@ mod tests {
         assert!(parsed.dangerously_bypass_approvals_and_sandbox);
     }
 
+    #[test]
+    fn test_model_alias_selects_provider() {
+        let config: Config = serde_json::from_str(
+            r#"{"provider": "anthropic", "aliases": {"fast": "zai/glm-4.7"}}"#,
+        )
+        .unwrap();
+
+        let parsed = Cli::try_parse_from(["rot", "--model", "fast", "chat"]).unwrap();
+        assert_eq!(
+            parsed.resolve_model(&config),
+            ("zai".to_string(), Some("glm-4.7".to_string()))
+        );
+
+        let parsed = Cli::try_parse_from(["rot", "--model", "gpt-4o", "chat"]).unwrap();
+        assert_eq!(
+            parsed.resolve_model(&config),
+            ("anthropic".to_string(), Some("gpt-4o".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rlm_model_applies_without_explicit_model() {
+        let config: Config = serde_json::from_str(
+            r#"{"provider": "anthropic", "aliases": {"fast": "zai/glm-4.7"}, "rlm": {"model": "fast"}}"#,
+        )
+        .unwrap();
+
+        let parsed = Cli::try_parse_from(["rot", "exec", "--rlm", "hi"]).unwrap();
+        assert_eq!(
+            parsed.resolve_rlm_model(&config),
+            ("zai".to_string(), Some("glm-4.7".to_string()))
+        );
+
+        let parsed =
+            Cli::try_parse_from(["rot", "--model", "gpt-4o", "exec", "--rlm", "hi"]).unwrap();
+        assert_eq!(
+            parsed.resolve_rlm_model(&config),
+            ("anthropic".to_string(), Some("gpt-4o".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_keys_subcommands_parse() {
+        let parsed = Cli::try_parse_from(["rot", "keys", "set", "openai", "sk-test"]).unwrap();
+        match parsed.command {
+            Some(Commands::Keys {
+                action: KeysAction::Set { provider, key },
+            }) => {
+                assert_eq!(provider, "openai");
+                assert_eq!(key.as_deref(), Some("sk-test"));
+            }
+            _ => panic!("expected keys set"),
+        }
+
+        let parsed = Cli::try_parse_from(["rot", "keys", "migrate"]).unwrap();
+        assert!(matches!(
+            parsed.command,
+            Some(Commands::Keys {
+                action: KeysAction::Migrate
+            })
+        ));
+    }
+
     #[test]
     fn test_exec_command_parses_output_schema() {
         let parsed = Cli::try_parse_from([
@@ -247,18 +658,10 @@ mod tests {
 
     #[test]
     fn test_explicit_prompt_policy_detectable() {
-        let parsed = Cli::try_parse_from([
-            "rot",
-            "--ask-for-approval",
-            "on-request",
-            "exec",
-            "hello",
-        ])
-        .unwrap();
-        assert_eq!(
-            parsed.ask_for_approval,
-            Some(ApprovalPolicyArg::OnRequest)
-        );
+        let parsed =
+            Cli::try_parse_from(["rot", "--ask-for-approval", "on-request", "exec", "hello"])
+                .unwrap();
+        assert_eq!(parsed.ask_for_approval, Some(ApprovalPolicyArg::OnRequest));
     }
 
     #[test]
@@ -271,14 +674,9 @@ mod tests {
 
     #[test]
     fn test_exec_rejects_prompting_policy() {
-        let parsed = Cli::try_parse_from([
-            "rot",
-            "--ask-for-approval",
-            "on-request",
-            "exec",
-            "hello",
-        ])
-        .unwrap();
+        let parsed =
+            Cli::try_parse_from(["rot", "--ask-for-approval", "on-request", "exec", "hello"])
+                .unwrap();
         let cfg = Config::default();
         assert!(parsed.resolve_runtime_security_for_exec(&cfg).is_err());
     }
@@ -289,13 +687,135 @@ mod tests {
         assert_eq!(parsed.agent.as_deref(), Some("plan"));
     }
 
+    #[test]
+    fn test_max_iterations_flag_overrides_config() {
+        let cfg = Config {
+            max_iterations: 20,
+            ..Config::default()
+        };
+        let parsed = Cli::try_parse_from(["rot", "exec", "hello"]).unwrap();
+        assert_eq!(parsed.resolve_max_iterations(&cfg), 20);
+
+        let parsed =
+            Cli::try_parse_from(["rot", "--max-iterations", "5", "exec", "hello"]).unwrap();
+        assert_eq!(parsed.resolve_max_iterations(&cfg), 5);
+
+        assert!(Cli::try_parse_from(["rot", "--max-iterations", "0", "chat"]).is_err());
+    }
+
+    #[test]
+    fn test_agent_create_parses() {
+        let parsed =
+            Cli::try_parse_from(["rot", "agent", "create", "migrations", "--from", "build"])
+                .unwrap();
+        match parsed.command {
+            Some(Commands::Agent {
+                action: AgentAction::Create { name, from, force },
+            }) => {
+                assert_eq!(name, "migrations");
+                assert_eq!(from.as_deref(), Some("build"));
+                assert!(!force);
+            }
+            _ => panic!("expected agent create command"),
+        }
+    }
+
+    #[test]
+    fn test_usage_command_parses() {
+        let parsed =
+            Cli::try_parse_from(["rot", "usage", "--by", "project", "--days", "0"]).unwrap();
+        match parsed.command {
+            Some(Commands::Usage { by, days, json }) => {
+                assert_eq!(by, crate::commands::usage::UsageBy::Project);
+                assert_eq!(days, 0);
+                assert!(!json);
+            }
+            _ => panic!("expected usage command"),
+        }
+        assert!(Cli::try_parse_from(["rot", "usage", "--by", "week"]).is_err());
+    }
+
+    #[test]
+    fn test_gh_command_parses() {
+        let parsed =
+            Cli::try_parse_from(["rot", "gh", "review", "--pr", "12", "--fail-on", "high"])
+                .unwrap();
+        match parsed.command {
+            Some(Commands::Gh {
+                action:
+                    GhAction::Review {
+                        pr,
+                        repo,
+                        no_post,
+                        fail_on,
+                    },
+            }) => {
+                assert_eq!(pr, Some(12));
+                assert_eq!(repo, None);
+                assert!(!no_post);
+                assert_eq!(fail_on, Some(crate::commands::review::Severity::High));
+            }
+            _ => panic!("expected gh review command"),
+        }
+        let parsed = Cli::try_parse_from(["rot", "gh", "fix", "--repo", "o/r", "--push"]).unwrap();
+        assert!(matches!(
+            parsed.command,
+            Some(Commands::Gh {
+                action: GhAction::Fix {
+                    pr: None,
+                    push: true,
+                    ..
+                }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_audit_command_parses() {
+        let parsed =
+            Cli::try_parse_from(["rot", "audit", "--tool", "bash", "--denied", "--limit", "0"])
+                .unwrap();
+        match parsed.command {
+            Some(Commands::Audit {
+                session,
+                tool,
+                denied,
+                limit,
+                json,
+            }) => {
+                assert_eq!(session, None);
+                assert_eq!(tool.as_deref(), Some("bash"));
+                assert!(denied);
+                assert_eq!(limit, 0);
+                assert!(!json);
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn test_completions_command_parses() {
+        let parsed = Cli::try_parse_from(["rot", "completions", "fish"]).unwrap();
+        assert!(matches!(
+            parsed.command,
+            Some(Commands::Completions {
+                shell: crate::commands::completions::Shell::Fish
+            })
+        ));
+        let parsed = Cli::try_parse_from(["rot", "__complete", "sessions"]).unwrap();
+        assert!(matches!(parsed.command, Some(Commands::Complete { .. })));
+    }
+
     #[test]
     fn test_session_tree_command_parses() {
         let parsed = Cli::try_parse_from(["rot", "session", "tree", "abc123"]).unwrap();
         match parsed.command {
             Some(Commands::Session {
-                action: SessionAction::Tree { id },
-            }) => assert_eq!(id.as_deref(), Some("abc123")),
+                action: SessionAction::Tree { id, json },
+            }) => {
+                assert_eq!(id.as_deref(), Some("abc123"));
+                assert!(!json);
+            }
             _ => panic!("expected session tree command"),
         }
     }
@@ -304,10 +824,278 @@ mod tests {
     fn test_tools_command_parses_optional_name() {
         let parsed = Cli::try_parse_from(["rot", "tools", "read"]).unwrap();
         match parsed.command {
-            Some(Commands::Tools { name }) => assert_eq!(name.as_deref(), Some("read")),
+            Some(Commands::Tools { name, .. }) => assert_eq!(name.as_deref(), Some("read")),
             _ => panic!("expected tools command"),
         }
     }
+
+    #[test]
+    fn test_tools_run_parses_args() {
+        let parsed = Cli::try_parse_from([
+            "rot",
+            "tools",
+            "run",
+            "read",
+            "--args",
+            r#"{"path":"src/main.rs"}"#,
+            "--json",
+        ])
+        .unwrap();
+        match parsed.command {
+            Some(Commands::Tools {
+                action: Some(ToolsAction::Run { name, args, json }),
+                ..
+            }) => {
+                assert_eq!(name, "read");
+                assert_eq!(args, r#"{"path":"src/main.rs"}"#);
+                assert!(json);
+            }
+            _ => panic!("expected tools run"),
+        }
+    }
+
+    #[test]
+    fn test_exec_accepts_prompt_flag_and_files() {
+        let parsed = Cli::try_parse_from([
+            "rot",
+            "exec",
+            "-p",
+            "review this",
+            "--file",
+            "a.rs",
+            "--file",
+            "b.rs",
+        ])
+        .unwrap();
+        match parsed.command {
+            Some(Commands::Exec {
+                prompt,
+                prompt_flag,
+                files,
+                ..
+            }) => {
+                assert_eq!(prompt, None);
+                assert_eq!(prompt_flag.as_deref(), Some("review this"));
+                assert_eq!(files, vec!["a.rs", "b.rs"]);
+            }
+            _ => panic!("expected exec"),
+        }
+        assert!(Cli::try_parse_from(["rot", "exec"]).is_ok());
+        assert!(Cli::try_parse_from(["rot", "exec", "hi", "-p", "there"]).is_err());
+    }
+
+    #[test]
+    fn test_exec_continue_and_resume_conflict() {
+        let parsed = Cli::try_parse_from(["rot", "exec", "--continue", "next step"]).unwrap();
+        assert!(matches!(
+            parsed.command,
+            Some(Commands::Exec {
+                continue_last: true,
+                resume: None,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["rot", "exec", "--resume", "01J", "next"]).is_ok());
+        assert!(
+            Cli::try_parse_from(["rot", "exec", "--continue", "--resume", "01J", "x"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["rot", "exec", "--rlm", "--resume", "01J"]).is_ok());
+    }
+
+    #[test]
+    fn test_exec_max_turns_and_timeout() {
+        let parsed = Cli::try_parse_from([
+            "rot",
+            "exec",
+            "fix it",
+            "--max-turns",
+            "5",
+            "--timeout",
+            "60",
+        ])
+        .unwrap();
+        assert_eq!(parsed.max_iterations, Some(5));
+        assert!(matches!(
+            parsed.command,
+            Some(Commands::Exec {
+                timeout: Some(60),
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["rot", "exec", "x", "--timeout", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_exec_rlm_flags_require_rlm() {
+        let parsed = Cli::try_parse_from([
+            "rot",
+            "exec",
+            "--rlm",
+            "x",
+            "--rlm-max-iterations",
+            "5",
+            "--rlm-max-stalled",
+            "2",
+            "--rlm-iteration-tokens",
+            "1000",
+            "--rlm-system-prompt",
+            "rlm.md",
+            "--rlm-branches",
+            "3",
+            "--no-cache",
+        ])
+        .unwrap();
+        assert!(matches!(
+            parsed.command,
+            Some(Commands::Exec {
+                rlm_max_iterations: Some(5),
+                rlm_max_stalled: Some(2),
+                rlm_iteration_tokens: Some(1000),
+                rlm_system_prompt: Some(_),
+                rlm_branches: Some(3),
+                no_cache: true,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["rot", "exec", "x", "--rlm-max-stalled", "2"]).is_err());
+        assert!(Cli::try_parse_from(["rot", "exec", "x", "--no-cache"]).is_err());
+        assert!(
+            Cli::try_parse_from(["rot", "exec", "--rlm", "x", "--rlm-max-iterations", "0"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_exec_batch_flags() {
+        let parsed = Cli::try_parse_from([
+            "rot",
+            "exec",
+            "--batch",
+            "prompts.jsonl",
+            "--parallel",
+            "8",
+            "--out",
+            "results.jsonl",
+        ])
+        .unwrap();
+        match parsed.command {
+            Some(Commands::Exec {
+                batch,
+                parallel,
+                out,
+                ..
+            }) => {
+                assert_eq!(batch.as_deref(), Some("prompts.jsonl"));
+                assert_eq!(parallel, Some(8));
+                assert_eq!(out.as_deref(), Some("results.jsonl"));
+            }
+            _ => panic!("expected exec"),
+        }
+        assert!(Cli::try_parse_from(["rot", "exec", "hi", "--batch", "p.jsonl"]).is_err());
+        assert!(Cli::try_parse_from(["rot", "exec", "--parallel", "2"]).is_err());
+        assert!(
+            Cli::try_parse_from(["rot", "exec", "--batch", "p.jsonl", "--parallel", "0"]).is_err()
+        );
+    }
+}
+
+#[derive(Subcommand)]
+pub enum AgentAction {
+    /// List built-in and project agents.
+    List {
+        /// Print the agents as a JSON array.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show an agent's settings and system prompt.
+    Show {
+        name: String,
+
+        /// Print the agent as a JSON object.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a new agent file to .rot/agents/<name>.toml.
+    Create {
+        name: String,
+
+        /// Built-in agent to copy the prompt and mode from.
+        #[arg(long, value_name = "AGENT")]
+        from: Option<String>,
+
+        /// Overwrite an existing file.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Open a project agent's file in $VISUAL or $EDITOR.
+    Edit { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum KeysAction {
+    /// Save an API key, using the system keyring when available.
+    Set {
+        /// Provider name (e.g. anthropic, openai, zai).
+        provider: String,
+        /// API key. Read from stdin when omitted.
+        key: Option<String>,
+    },
+    /// Move plaintext keys from ~/.rot/config.json into the system keyring.
+    Migrate,
+}
+
+#[derive(Subcommand)]
+pub enum GhAction {
+    /// Review the pull request's diff and post the findings as a review.
+    Review {
+        /// Pull request number. Defaults to the one that triggered the workflow.
+        #[arg(long)]
+        pr: Option<u64>,
+
+        /// Repository as OWNER/NAME. Defaults to `GITHUB_REPOSITORY`.
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Print the findings without posting them.
+        #[arg(long)]
+        no_post: bool,
+
+        /// Fail when a finding is at least this severe.
+        #[arg(long, value_enum)]
+        fail_on: Option<crate::commands::review::Severity>,
+    },
+    /// Review the pull request, fix the findings in the checked-out head
+    /// branch, and commit the fix.
+    Fix {
+        /// Pull request number. Defaults to the one that triggered the workflow.
+        #[arg(long)]
+        pr: Option<u64>,
+
+        /// Repository as OWNER/NAME. Defaults to `GITHUB_REPOSITORY`.
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Push the fix to the pull request's branch and comment on it.
+        #[arg(long)]
+        push: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ToolsAction {
+    /// Run a tool directly under the active sandbox and approval settings.
+    Run {
+        /// Tool name, as listed by `rot tools`.
+        name: String,
+
+        /// Tool arguments as a JSON object.
+        #[arg(long, default_value = "{}")]
+        args: String,
+
+        /// Print the result as a JSON object.
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -317,11 +1105,19 @@ pub enum SessionAction {
         /// Maximum number of sessions to show.
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Print a JSON array of session metadata with token usage and cost.
+        #[arg(long)]
+        json: bool,
     },
     /// Show the parent/child tree for a session or the latest session.
     Tree {
         /// Session ID to focus in the rendered tree. Defaults to the latest session.
         id: Option<String>,
+
+        /// Print the tree as JSON.
+        #[arg(long)]
+        json: bool,
     },
     /// Resume a previous session.
     Resume {
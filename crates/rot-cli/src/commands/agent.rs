@@ -0,0 +1,176 @@
+//! `rot agent`: list, inspect, and scaffold agent profiles.
+
+use rot_core::agent_file::{self, AgentFile, AGENTS_DIR};
+use rot_core::{AgentMode, AgentRegistry};
+use serde::Serialize;
+use std::path::Path;
+
+/// One agent as shown by `list` and `show`.
+#[derive(Debug, Serialize)]
+struct AgentSummary {
+    name: String,
+    /// `builtin` or `project`.
+    source: &'static str,
+    mode: AgentMode,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<String>>,
+    system_prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+}
+
+impl From<AgentFile> for AgentSummary {
+    fn from(agent: AgentFile) -> Self {
+        Self {
+            name: agent.name,
+            source: "project",
+            mode: agent.mode,
+            description: agent.description,
+            model: agent.model,
+            tools: agent.tools,
+            system_prompt: agent.system_prompt.trim().to_string(),
+            path: Some(agent.path.display().to_string()),
+        }
+    }
+}
+
+/// Built-in agents followed by the project's agents.
+fn all_agents(cwd: &Path) -> anyhow::Result<Vec<AgentSummary>> {
+    let mut agents: Vec<AgentSummary> = AgentRegistry::builtins()
+        .iter()
+        .map(|profile| AgentSummary {
+            name: profile.name.to_string(),
+            source: "builtin",
+            mode: profile.mode,
+            description: profile.description.to_string(),
+            model: None,
+            tools: None,
+            system_prompt: profile.system_prompt.to_string(),
+            path: None,
+        })
+        .collect();
+    agents.extend(
+        agent_file::load_project_agents(cwd)?
+            .into_iter()
+            .filter(|agent| !agent_file::is_builtin(&agent.name))
+            .map(AgentSummary::from),
+    );
+    Ok(agents)
+}
+
+fn mode_label(mode: AgentMode) -> &'static str {
+    match mode {
+        AgentMode::Primary => "primary",
+        AgentMode::Subagent => "subagent",
+    }
+}
+
+/// Print every agent, one per line or as a JSON array.
+pub fn list(json: bool) -> anyhow::Result<()> {
+    let agents = all_agents(&std::env::current_dir()?)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&agents)?);
+        return Ok(());
+    }
+    let width = agents
+        .iter()
+        .map(|agent| agent.name.len())
+        .max()
+        .unwrap_or(0);
+    for agent in &agents {
+        println!(
+            "{:width$}  {:8}  {:8}  {}",
+            agent.name,
+            agent.source,
+            mode_label(agent.mode),
+            agent.description
+        );
+    }
+    Ok(())
+}
+
+/// Print one agent's settings and system prompt.
+pub fn show(name: &str, json: bool) -> anyhow::Result<()> {
+    let agent = all_agents(&std::env::current_dir()?)?
+        .into_iter()
+        .find(|agent| agent.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow::anyhow!("Unknown agent '{name}'; see `rot agent list`"))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&agent)?);
+        return Ok(());
+    }
+    println!("name: {}", agent.name);
+    println!("source: {}", agent.source);
+    if let Some(path) = &agent.path {
+        println!("path: {path}");
+    }
+    println!("mode: {}", mode_label(agent.mode));
+    println!("description: {}", agent.description);
+    println!(
+        "model: {}",
+        agent.model.as_deref().unwrap_or("(session model)")
+    );
+    match &agent.tools {
+        Some(tools) => println!("tools: {}", tools.join(", ")),
+        None => println!("tools: (all)"),
+    }
+    println!("system prompt:\n{}", agent.system_prompt);
+    Ok(())
+}
+
+/// Write `.rot/agents/<name>.toml`, starting from the `from` built-in.
+pub fn create(name: &str, from: Option<&str>, force: bool) -> anyhow::Result<()> {
+    agent_file::validate_name(name)?;
+    if agent_file::is_builtin(name) {
+        anyhow::bail!("'{name}' is a built-in agent; pick another name");
+    }
+    let base = AgentRegistry::resolve(from)?;
+    let cwd = std::env::current_dir()?;
+    let dir = agent_file::find_agents_dir(&cwd).unwrap_or_else(|| cwd.join(AGENTS_DIR));
+    let path = dir.join(format!("{name}.toml"));
+    if path.exists() && !force {
+        anyhow::bail!(
+            "{} already exists; pass --force to overwrite it",
+            path.display()
+        );
+    }
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, AgentFile::scaffold(name, &base))
+        .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// Open a project agent's file in `$VISUAL` or `$EDITOR`, then check it parses.
+pub fn edit(name: &str) -> anyhow::Result<()> {
+    let agent = agent_file::load_project_agents(&std::env::current_dir()?)?
+        .into_iter()
+        .find(|agent| agent.name == name);
+    let Some(agent) = agent else {
+        if agent_file::is_builtin(name) {
+            anyhow::bail!(
+                "'{name}' is a built-in agent; copy it with `rot agent create <name> --from {name}`"
+            );
+        }
+        anyhow::bail!("No project agent '{name}'; create it with `rot agent create {name}`");
+    };
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&agent.path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to start editor '{editor}': {e}"))?;
+    if !status.success() {
+        anyhow::bail!("editor '{editor}' exited with {status}");
+    }
+    AgentFile::read(&agent.path)?;
+    Ok(())
+}
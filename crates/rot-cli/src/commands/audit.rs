@@ -0,0 +1,197 @@
+//! `rot audit`: the tool calls recorded in the audit log.
+
+use rot_core::audit::{AuditLog, AuditRecord};
+use rot_core::usage;
+use std::path::Path;
+
+/// Which records `rot audit` shows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    pub session: Option<String>,
+    pub tool: Option<String>,
+    /// Only calls that were refused.
+    pub denied: bool,
+}
+
+impl AuditFilter {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.session
+            .as_ref()
+            .map_or(true, |id| record.session_id.starts_with(id.as_str()))
+            && self.tool.as_ref().map_or(true, |tool| record.tool == *tool)
+            && (!self.denied || !record.approval.allowed())
+    }
+}
+
+/// Print the last `limit` matching records (all when 0) from the log at
+/// `path`, oldest first, as a table or as JSON lines.
+pub fn run(path: &Path, filter: &AuditFilter, limit: usize, json: bool) -> anyhow::Result<()> {
+    let records = select(AuditLog::new(path).read()?, filter, limit);
+    if json {
+        for record in &records {
+            println!("{}", serde_json::to_string(record)?);
+        }
+    } else if records.is_empty() {
+        println!("No tool calls recorded in {}.", path.display());
+    } else {
+        print!("{}", format_table(&records));
+    }
+    Ok(())
+}
+
+fn select(records: Vec<AuditRecord>, filter: &AuditFilter, limit: usize) -> Vec<AuditRecord> {
+    let mut records: Vec<AuditRecord> = records
+        .into_iter()
+        .filter(|record| filter.matches(record))
+        .collect();
+    if limit > 0 && records.len() > limit {
+        records.drain(..records.len() - limit);
+    }
+    records
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in UTC.
+fn format_time(timestamp: u64) -> String {
+    let seconds = timestamp % 86_400;
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        usage::utc_date(timestamp),
+        seconds / 3_600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// How the call ended: not run, ok, error, or its exit status.
+fn outcome(record: &AuditRecord) -> String {
+    if !record.executed {
+        return "not run".to_string();
+    }
+    let outcome = match record.exit_code {
+        Some(code) => format!("exit {code}"),
+        None if record.is_error => "error".to_string(),
+        None => "ok".to_string(),
+    };
+    if record.dry_run {
+        format!("{outcome} (dry run)")
+    } else {
+        outcome
+    }
+}
+
+/// One row per record, with columns aligned and files last.
+fn format_table(records: &[AuditRecord]) -> String {
+    let header = [
+        "TIME", "SESSION", "TOOL", "APPROVAL", "SANDBOX", "RESULT", "FILES",
+    ];
+    let rows: Vec<[String; 7]> = records
+        .iter()
+        .map(|record| {
+            [
+                format_time(record.timestamp),
+                record.session_id.clone(),
+                record.tool.clone(),
+                record.approval.as_str().to_string(),
+                record.sandbox_mode.as_str().to_string(),
+                outcome(record),
+                record.files.join(", "),
+            ]
+        })
+        .collect();
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[&str]| {
+        let mut line = String::new();
+        for (column, cell) in cells.iter().enumerate().take(6) {
+            line.push_str(&format!("{cell:width$}  ", width = widths[column]));
+        }
+        line.push_str(cells[6]);
+        format!("{}\n", line.trim_end())
+    };
+
+    let mut table = line(&header);
+    for row in &rows {
+        let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+        table.push_str(&line(&cells));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rot_core::audit::ApprovalDecision;
+    use rot_core::SandboxMode;
+
+    fn record(session_id: &str, tool: &str, approval: ApprovalDecision) -> AuditRecord {
+        AuditRecord {
+            timestamp: 1_792_108_800 + 3_723,
+            session_id: session_id.to_string(),
+            agent: "build".to_string(),
+            tool: tool.to_string(),
+            call_id: "tc1".to_string(),
+            args_hash: "ab12".to_string(),
+            sandbox_mode: SandboxMode::WorkspaceWrite,
+            dry_run: false,
+            approval,
+            executed: approval.allowed(),
+            is_error: !approval.allowed(),
+            exit_code: None,
+            duration_ms: Some(5),
+            files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_select_filters_and_keeps_latest() {
+        let records = vec![
+            record("01AAA", "bash", ApprovalDecision::Auto),
+            record("01AAA", "edit", ApprovalDecision::Denied),
+            record("01BBB", "bash", ApprovalDecision::Approved),
+            record("01BBB", "bash", ApprovalDecision::Blocked),
+        ];
+        let bash = AuditFilter {
+            tool: Some("bash".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(select(records.clone(), &bash, 0).len(), 3);
+        let last = select(records.clone(), &bash, 2);
+        assert_eq!(last.len(), 2);
+        assert!(last.iter().all(|record| record.session_id == "01BBB"));
+
+        let denied = AuditFilter {
+            session: Some("01A".to_string()),
+            denied: true,
+            ..Default::default()
+        };
+        let refused = select(records, &denied, 0);
+        assert_eq!(refused.len(), 1);
+        assert_eq!(refused[0].tool, "edit");
+    }
+
+    #[test]
+    fn test_format_table_aligns_columns() {
+        let mut bash = record("01AAA", "bash", ApprovalDecision::Approved);
+        bash.exit_code = Some(2);
+        bash.is_error = true;
+        let mut edit = record("01AAA", "edit", ApprovalDecision::Auto);
+        edit.files = vec!["src/main.rs".to_string()];
+        edit.dry_run = true;
+        let denied = record("01AAA", "write", ApprovalDecision::DeniedAlways);
+
+        let table = format_table(&[bash, edit, denied]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("TIME "));
+        assert!(lines[1].starts_with("2026-10-16 01:02:03  01AAA"));
+        assert!(lines[1].ends_with("exit 2"));
+        assert!(lines[2].ends_with("ok (dry run)  src/main.rs"));
+        assert!(lines[3].contains("denied_always") && lines[3].ends_with("not run"));
+        let files = lines[0].find("FILES").unwrap();
+        assert_eq!(lines[2].find("src/main.rs"), Some(files));
+    }
+}
@@ -0,0 +1,346 @@
+//! `rot exec --batch`: run independent prompts from a JSONL file.
+
+use super::exec::{
+    create_provider, failure, save_messages, take_entries, Deadline, ExecExitError,
+    EXIT_BUDGET_EXCEEDED, EXIT_FAILURE,
+};
+use futures::StreamExt;
+use rot_core::hooks::{HookEvent, HookPayload, Hooks};
+use rot_core::usage::UsageSummary;
+use rot_core::{Agent, AgentConfig, AgentRegistry, Message, RuntimeSecurityConfig};
+use rot_session::{SessionEntry, SessionStore};
+use rot_tools::ToolRegistry;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// `rot exec --batch` options.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// JSONL file with one prompt per line.
+    pub input: String,
+    /// File for the results; stdout when unset.
+    pub output: Option<String>,
+    /// Prompts run at the same time.
+    pub parallel: usize,
+    /// Input plus output tokens shared by the whole batch. Prompts not yet
+    /// started when it runs out are skipped.
+    pub max_total_tokens: Option<usize>,
+    pub max_iterations: usize,
+    /// Wall-clock limit for each prompt.
+    pub timeout: Option<Duration>,
+    /// Run the `verify` checks before each prompt finishes.
+    pub verify: bool,
+}
+
+/// One prompt from the batch file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BatchItem {
+    id: String,
+    prompt: String,
+}
+
+/// One line of the results file.
+#[derive(Debug, Serialize)]
+struct BatchResult {
+    id: String,
+    /// `ok`, `error`, or `skipped`.
+    status: &'static str,
+    session_id: Option<String>,
+    final_text: String,
+    usage: UsageSummary,
+    elapsed_ms: u128,
+    error: Option<String>,
+}
+
+/// What every prompt in a batch shares.
+struct BatchRunner {
+    provider_name: String,
+    model: Option<String>,
+    tools: ToolRegistry,
+    config: AgentConfig,
+    runtime_security: RuntimeSecurityConfig,
+    store: SessionStore,
+    cwd: PathBuf,
+    tokens_used: Arc<AtomicUsize>,
+    max_total_tokens: Option<usize>,
+    timeout: Option<Duration>,
+    hooks: Arc<Hooks>,
+}
+
+/// Run every prompt in `options.input` as its own session and write one
+/// JSON result per prompt, in completion order.
+pub async fn run(
+    provider_name: &str,
+    model: Option<&str>,
+    agent_name: Option<&str>,
+    runtime_security: RuntimeSecurityConfig,
+    options: BatchOptions,
+) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(&options.input)
+        .map_err(|e| anyhow::anyhow!("failed to read --batch '{}': {e}", options.input))?;
+    let items = parse_items(&content)?;
+    if items.is_empty() {
+        anyhow::bail!("{} has no prompts", options.input);
+    }
+
+    // Fail fast on a missing API key rather than once per prompt.
+    create_provider(provider_name, model).await?;
+    let profile = AgentRegistry::resolve(agent_name)?;
+    let (rot_config, tools) = super::load_tool_registry(runtime_security.clone(), false).await?;
+    let cwd = std::env::current_dir()?;
+    let runner = BatchRunner {
+        provider_name: provider_name.to_string(),
+        model: model.map(str::to_string),
+        tools,
+        config: AgentConfig {
+            agent_name: profile.name.to_string(),
+            system_prompt: Some(rot_core::project::with_instructions(
+                profile.system_prompt.to_string(),
+                &cwd,
+            )),
+            max_tokens: Some(4096),
+            max_iterations: options.max_iterations,
+            verify: rot_config.verify.clone().enabled_if(options.verify),
+            ..AgentConfig::from_config(&rot_config)
+        },
+        runtime_security,
+        store: SessionStore::new(),
+        cwd,
+        tokens_used: Arc::new(AtomicUsize::new(0)),
+        max_total_tokens: options.max_total_tokens,
+        timeout: options.timeout,
+        hooks: Arc::new(Hooks::new(rot_config.hooks.clone())),
+    };
+
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(
+            std::fs::File::create(path)
+                .map_err(|e| anyhow::anyhow!("failed to create --out '{path}': {e}"))?,
+        ),
+        None => Box::new(std::io::stdout()),
+    };
+
+    let total = items.len();
+    let mut results = futures::stream::iter(items)
+        .map(|item| runner.run_item(item))
+        .buffer_unordered(options.parallel.max(1));
+    let (mut done, mut failed, mut skipped) = (0, 0, 0);
+    while let Some(result) = results.next().await {
+        done += 1;
+        match result.status {
+            "error" => failed += 1,
+            "skipped" => skipped += 1,
+            _ => {}
+        }
+        writeln!(out, "{}", serde_json::to_string(&result)?)?;
+        out.flush()?;
+        eprintln!("[{done}/{total}] {} {}", result.id, result.status);
+    }
+
+    eprintln!(
+        "{} ok, {failed} failed, {skipped} skipped; {} tokens used",
+        total - failed - skipped,
+        runner.tokens_used.load(Ordering::Relaxed)
+    );
+    if failed + skipped > 0 {
+        return Err(anyhow::Error::new(ExecExitError {
+            code: if failed > 0 {
+                EXIT_FAILURE
+            } else {
+                EXIT_BUDGET_EXCEEDED
+            },
+            message: format!("{} of {total} prompts did not complete", failed + skipped),
+        }));
+    }
+    Ok(())
+}
+
+impl BatchRunner {
+    fn budget_left(&self) -> bool {
+        self.max_total_tokens.map_or(true, |limit| {
+            self.tokens_used.load(Ordering::Relaxed) < limit
+        })
+    }
+
+    async fn run_item(&self, item: BatchItem) -> BatchResult {
+        let started = Instant::now();
+        let mut result = BatchResult {
+            id: item.id,
+            status: "skipped",
+            session_id: None,
+            final_text: String::new(),
+            usage: UsageSummary::default(),
+            elapsed_ms: 0,
+            error: None,
+        };
+        if !self.budget_left() {
+            result.error = Some("batch token budget exhausted".to_string());
+            return result;
+        }
+
+        let usage = Arc::new(Mutex::new(UsageSummary::default()));
+        let outcome = self
+            .process(&item.prompt, &usage, &mut result.session_id)
+            .await;
+        result.usage = usage.lock().map(|usage| usage.clone()).unwrap_or_default();
+        result.elapsed_ms = started.elapsed().as_millis();
+        match outcome {
+            Ok(text) => {
+                result.status = "ok";
+                result.final_text = text;
+            }
+            Err(e) => {
+                result.status = "error";
+                result.error = Some(e.to_string());
+            }
+        }
+        result
+    }
+
+    async fn process(
+        &self,
+        prompt: &str,
+        usage: &Arc<Mutex<UsageSummary>>,
+        session_id: &mut Option<String>,
+    ) -> anyhow::Result<String> {
+        let provider = create_provider(&self.provider_name, self.model.as_deref()).await?;
+        let mut session = self
+            .store
+            .create(&self.cwd, provider.current_model(), provider.name())
+            .await?;
+        *session_id = Some(session.id.clone());
+        let pricing = rot_provider::pricing_for(provider.current_model());
+        let hook_model = format!("{}/{}", provider.name(), provider.current_model());
+        let hook_payload = |event: HookEvent| {
+            let usage = usage.lock().map(|usage| usage.clone()).unwrap_or_default();
+            let cost = pricing.map(|pricing| pricing.cost(usage.input_tokens, usage.output_tokens));
+            HookPayload::new(event, session.id.clone(), hook_model.clone()).with_usage(
+                usage.input_tokens,
+                usage.output_tokens,
+                cost,
+            )
+        };
+        self.hooks
+            .fire_in_background(hook_payload(HookEvent::RunStarted).with_summary(prompt));
+
+        let usage_sink = usage.clone();
+        let usage_entries: Arc<Mutex<Vec<SessionEntry>>> = Arc::default();
+        let entry_sink = usage_entries.clone();
+        let usage_agent = self.config.agent_name.clone();
+        let tokens_used = self.tokens_used.clone();
+        let agent = Arc::new(
+            Agent::new(
+                provider,
+                self.tools.clone(),
+                self.config.clone(),
+                self.runtime_security.clone(),
+            )
+            .with_session_id(session.id.clone())
+            .on_usage(Box::new(move |report| {
+                tokens_used.fetch_add(
+                    report.input_tokens + report.output_tokens,
+                    Ordering::Relaxed,
+                );
+                if let Ok(mut totals) = usage_sink.lock() {
+                    totals.record(report);
+                }
+                if let Ok(mut entries) = entry_sink.lock() {
+                    entries.push(rot_core::usage::usage_entry(report, &usage_agent));
+                }
+            })),
+        );
+
+        let mut messages: Vec<Message> = Vec::new();
+        let deadline = Deadline::new(self.timeout);
+        let result = agent
+            .process_cancellable(&mut messages, prompt, Vec::new(), deadline.token.clone())
+            .await;
+        let outcome = match result {
+            Ok(response) => Ok(response.text()),
+            Err(err) => Err(failure(&err, self.timeout).1),
+        };
+        let payload = match &outcome {
+            Ok(text) => hook_payload(HookEvent::RunFinished).with_summary(text),
+            Err(message) => hook_payload(HookEvent::RunFailed).with_summary(message),
+        };
+        self.hooks.fire(&payload).await;
+        save_messages(
+            &self.store,
+            &mut session,
+            &messages,
+            &take_entries(&usage_entries),
+        )
+        .await?;
+        outcome.map_err(|message| anyhow::anyhow!(message))
+    }
+}
+
+/// Prompts in a batch file: each non-blank line is a JSON string, or an
+/// object with `prompt` and an optional `id` (the 1-based line number when
+/// absent).
+fn parse_items(content: &str) -> anyhow::Result<Vec<BatchItem>> {
+    let mut items = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_no = idx + 1;
+        let value: Value = serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("line {line_no}: invalid JSON: {e}"))?;
+        let (id, prompt) = match &value {
+            Value::String(prompt) => (None, Some(prompt.as_str())),
+            Value::Object(fields) => (
+                fields.get("id").map(|id| match id {
+                    Value::String(id) => id.clone(),
+                    other => other.to_string(),
+                }),
+                fields.get("prompt").and_then(Value::as_str),
+            ),
+            _ => (None, None),
+        };
+        let Some(prompt) = prompt.filter(|prompt| !prompt.trim().is_empty()) else {
+            anyhow::bail!("line {line_no}: expected a prompt string or {{\"prompt\": ...}}");
+        };
+        items.push(BatchItem {
+            id: id.unwrap_or_else(|| line_no.to_string()),
+            prompt: prompt.to_string(),
+        });
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_items_accepts_strings_and_objects() {
+        let content = "\"summarize src/\"\n\n{\"id\": \"t2\", \"prompt\": \"list TODOs\"}\n{\"id\": 7, \"prompt\": \"x\"}\n";
+        let items = parse_items(content).unwrap();
+        assert_eq!(
+            items,
+            vec![
+                BatchItem {
+                    id: "1".to_string(),
+                    prompt: "summarize src/".to_string()
+                },
+                BatchItem {
+                    id: "t2".to_string(),
+                    prompt: "list TODOs".to_string()
+                },
+                BatchItem {
+                    id: "7".to_string(),
+                    prompt: "x".to_string()
+                },
+            ]
+        );
+
+        let err = parse_items("\"ok\"\n{\"id\": \"a\"}\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+}
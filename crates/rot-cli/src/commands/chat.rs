@@ -1,5 +1,7 @@
 use rot_core::AgentRegistry;
-use rot_provider::{AnthropicProvider, Provider, new_openai_provider, new_zai_provider};
+use rot_provider::{
+    load_openrouter_provider, new_openai_provider, new_zai_provider, AnthropicProvider, Provider,
+};
 use rot_session::SessionStore;
 
 /// Run interactive chat mode.
@@ -8,10 +10,12 @@ pub async fn run(
     provider_name: &str,
     agent_name: Option<&str>,
     runtime_security: rot_core::RuntimeSecurityConfig,
+    max_iterations: usize,
+    resume: Option<&str>,
 ) -> anyhow::Result<()> {
     let config_store = rot_core::config::ConfigStore::new();
     config_store.hydrate_env();
-    let (config, tools) = super::load_tool_registry(runtime_security.clone()).await?;
+    let (config, tools) = super::load_tool_registry(runtime_security.clone(), true).await?;
     let agent_profile = AgentRegistry::resolve(agent_name)?;
     let system_prompt = if agent_profile.name == "default" {
         AgentRegistry::default_chat_system_prompt().to_string()
@@ -26,21 +30,46 @@ pub async fn run(
         provider_name
     };
 
-    let final_model = model.unwrap_or(&config.model);
-    let provider = create_provider(final_provider, Some(final_model))?;
+    let (final_provider, final_model) = match model {
+        Some(model) => (final_provider.to_string(), model.to_string()),
+        None => {
+            let resolved = config.resolve_model(&config.model);
+            (
+                resolved
+                    .provider
+                    .unwrap_or_else(|| final_provider.to_string()),
+                resolved.model,
+            )
+        }
+    };
+    let provider = create_provider(&final_provider, Some(&final_model)).await?;
     let model_name = provider.current_model().to_string();
 
     let session_store = SessionStore::new();
+    let resume = match resume {
+        Some(id) => {
+            let cwd = std::env::current_dir()?;
+            Some(
+                session_store
+                    .load(&cwd, id)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("failed to resume session {id}: {e}"))?,
+            )
+        }
+        None => None,
+    };
 
     rot_tui::run_tui(
         provider,
         tools,
         session_store,
         &model_name,
-        provider_name,
+        &final_provider,
         agent_profile.name,
         system_prompt,
         runtime_security,
+        max_iterations,
+        resume,
     )
         .await
         .map_err(|e| anyhow::anyhow!("TUI error: {e}"))?;
@@ -48,10 +77,16 @@ pub async fn run(
     Ok(())
 }
 
-fn create_provider(
+async fn create_provider(
     provider_name: &str,
     model: Option<&str>,
 ) -> anyhow::Result<Box<dyn Provider>> {
+    let config = rot_core::ConfigStore::new().load();
+    if let Some(custom) = config.providers.get(provider_name) {
+        return custom
+            .build(provider_name, model)
+            .map_err(|e| anyhow::anyhow!("{e}"));
+    }
     match provider_name {
         "anthropic" => {
             let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_default();
@@ -77,8 +112,20 @@ fn create_provider(
             }
             Ok(Box::new(provider))
         }
+        "openrouter" => {
+            let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
+            let mut provider = if api_key.is_empty() {
+                rot_provider::new_openrouter_provider(api_key, &config.openrouter_routing)
+            } else {
+                load_openrouter_provider(api_key, &config.openrouter_routing).await
+            };
+            if let Some(m) = model {
+                let _ = provider.set_model(m);
+            }
+            Ok(Box::new(provider))
+        }
         other => Err(anyhow::anyhow!(
-            "Unknown provider: {other}. Available: anthropic, zai, openai"
+            "Unknown provider: {other}. Available: anthropic, zai, openai, openrouter"
         )),
     }
 }
@@ -0,0 +1,229 @@
+//! `rot commit`: write commit messages for staged changes and commit them.
+
+use super::init::strip_fence;
+use serde::Deserialize;
+use std::io::IsTerminal;
+
+/// Largest staged diff sent to the model, in bytes.
+const MAX_DIFF_BYTES: usize = 100 * 1024;
+
+const SYSTEM_PROMPT: &str = "You write git commit messages in the Conventional Commits format.";
+
+/// One commit to create: its message and, when splitting, its files.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct PlannedCommit {
+    message: String,
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitPlan {
+    commits: Vec<PlannedCommit>,
+}
+
+/// Generate a message for the staged changes, confirm it, and commit.
+///
+/// With `split`, the model groups staged files into separate commits; files
+/// must not also have unstaged changes, since each group is re-staged whole.
+pub async fn run(
+    provider_name: &str,
+    model: Option<&str>,
+    split: bool,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let staged = staged_files()?;
+    if staged.is_empty() {
+        anyhow::bail!("Nothing staged; stage changes with `git add` first");
+    }
+    if !yes && !std::io::stdin().is_terminal() {
+        anyhow::bail!("Pass --yes to commit without confirmation");
+    }
+    let diff = truncate(&super::git(&["diff", "--staged", "--no-color"])?);
+    let stat = super::git(&["diff", "--staged", "--stat", "--no-color"])?;
+
+    let commits = if split {
+        let unstaged = super::git(&["diff", "--name-only"])?;
+        let partial: Vec<&str> = unstaged
+            .lines()
+            .filter(|file| staged.iter().any(|s| s == file))
+            .collect();
+        if !partial.is_empty() {
+            anyhow::bail!(
+                "--split needs fully staged files, but these also have unstaged changes: {}",
+                partial.join(", ")
+            );
+        }
+        let reply = super::complete_once(
+            provider_name,
+            model,
+            SYSTEM_PROMPT,
+            &split_prompt(&stat, &diff),
+        )
+        .await?;
+        parse_plan(&reply, &staged)?
+    } else {
+        let reply = super::complete_once(
+            provider_name,
+            model,
+            SYSTEM_PROMPT,
+            &message_prompt(&stat, &diff),
+        )
+        .await?;
+        vec![PlannedCommit {
+            message: clean_message(&reply),
+            files: Vec::new(),
+        }]
+    };
+    if commits.iter().any(|commit| commit.message.is_empty()) {
+        anyhow::bail!("The model returned an empty commit message");
+    }
+
+    for (i, commit) in commits.iter().enumerate() {
+        if commits.len() > 1 {
+            eprintln!(
+                "Commit {} of {} ({}):",
+                i + 1,
+                commits.len(),
+                commit.files.join(", ")
+            );
+        }
+        eprintln!("\n{}\n", indent(&commit.message));
+    }
+    let question = if commits.len() > 1 {
+        format!("Create these {} commits?", commits.len())
+    } else {
+        "Create this commit?".to_string()
+    };
+    if !yes && !super::confirm(&question) {
+        anyhow::bail!("Commit cancelled");
+    }
+
+    if commits.len() > 1 {
+        super::git(&["reset", "--quiet"])?;
+    }
+    for commit in &commits {
+        if !commit.files.is_empty() {
+            let mut args = vec!["add", "--"];
+            args.extend(commit.files.iter().map(String::as_str));
+            super::git(&args)?;
+        }
+        super::git(&["commit", "--quiet", "-m", &commit.message])?;
+        let head = super::git(&["log", "-1", "--format=%h %s"])?;
+        println!("{}", head.trim());
+    }
+    Ok(())
+}
+
+fn staged_files() -> anyhow::Result<Vec<String>> {
+    Ok(super::git(&["diff", "--staged", "--name-only"])?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+fn truncate(diff: &str) -> String {
+    let mut end = diff.len().min(MAX_DIFF_BYTES);
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end < diff.len() {
+        format!(
+            "{}\n(diff truncated at {end} of {} bytes)",
+            &diff[..end],
+            diff.len()
+        )
+    } else {
+        diff.to_string()
+    }
+}
+
+const MESSAGE_RULES: &str = "Use `type(scope): summary` with a type such as feat, fix, \
+refactor, docs, test, or chore. Keep the summary under 72 characters, in the imperative mood. \
+Add a short body after a blank line only when the change needs explaining.";
+
+fn message_prompt(stat: &str, diff: &str) -> String {
+    format!(
+        "Write a commit message for these staged changes. {MESSAGE_RULES} \
+         Reply with the message only.\n\n{stat}\n```diff\n{diff}\n```"
+    )
+}
+
+fn split_prompt(stat: &str, diff: &str) -> String {
+    format!(
+        "Group these staged changes into commits, one per unrelated change; a single commit is \
+         fine when everything belongs together. Every file must be in exactly one commit. \
+         {MESSAGE_RULES} Reply with JSON only: \
+         {{\"commits\": [{{\"message\": \"...\", \"files\": [\"path\"]}}]}}\n\n\
+         {stat}\n```diff\n{diff}\n```"
+    )
+}
+
+/// The reply as a commit message, without a fence or surrounding quotes.
+fn clean_message(reply: &str) -> String {
+    strip_fence(reply).trim_matches('"').trim().to_string()
+}
+
+/// Parse the split plan and check it covers every staged file exactly once.
+fn parse_plan(reply: &str, staged: &[String]) -> anyhow::Result<Vec<PlannedCommit>> {
+    let json = reply
+        .find('{')
+        .zip(reply.rfind('}'))
+        .and_then(|(start, end)| reply.get(start..=end))
+        .ok_or_else(|| anyhow::anyhow!("The model did not return a commit plan"))?;
+    let plan: CommitPlan = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("The model returned an invalid commit plan: {e}"))?;
+
+    let mut assigned: Vec<&str> = plan
+        .commits
+        .iter()
+        .flat_map(|commit| commit.files.iter().map(String::as_str))
+        .collect();
+    assigned.sort_unstable();
+    let mut expected: Vec<&str> = staged.iter().map(String::as_str).collect();
+    expected.sort_unstable();
+    if plan.commits.is_empty() || assigned != expected {
+        anyhow::bail!(
+            "The commit plan does not cover each staged file exactly once; try without --split"
+        );
+    }
+
+    Ok(plan
+        .commits
+        .into_iter()
+        .map(|commit| PlannedCommit {
+            message: clean_message(&commit.message),
+            files: commit.files,
+        })
+        .collect())
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plan_requires_every_file_once() {
+        let staged = vec!["src/a.rs".to_string(), "README.md".to_string()];
+        let reply = r#"```json
+{"commits": [
+  {"message": "fix(a): handle empty input", "files": ["src/a.rs"]},
+  {"message": "docs: describe flags", "files": ["README.md"]}
+]}
+```"#;
+        let plan = parse_plan(reply, &staged).unwrap();
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan[1].message, "docs: describe flags");
+
+        let missing = r#"{"commits": [{"message": "fix: a", "files": ["src/a.rs"]}]}"#;
+        assert!(parse_plan(missing, &staged).is_err());
+        assert_eq!(clean_message("```\nfeat: add x\n```"), "feat: add x");
+    }
+}
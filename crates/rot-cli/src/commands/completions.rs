@@ -0,0 +1,467 @@
+//! `rot completions` and `rot man`: shell completion scripts from
+//! `clap_complete` and man pages from `clap_mangen`, both generated from the
+//! clap definitions in [`crate::cli`].
+//!
+//! Values that are only known at runtime (models, agents, session IDs) are
+//! not part of the generated scripts. For bash, zsh, and fish a short hook is
+//! added that completes them by calling the hidden `rot __complete <values>`;
+//! PowerShell and Elvish complete commands, options, and fixed values.
+
+use crate::cli::Cli;
+use clap::{Command, CommandFactory};
+use std::fmt::Write as _;
+use std::path::Path;
+
+pub use clap_complete::Shell;
+
+/// Values printed by `rot __complete`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DynamicValues {
+    /// Built-in and configured model IDs, plus aliases.
+    Models,
+    /// Built-in and project agents.
+    Agents,
+    /// Recent session IDs in the working directory.
+    Sessions,
+    Providers,
+    Profiles,
+}
+
+impl DynamicValues {
+    fn name(self) -> &'static str {
+        match self {
+            DynamicValues::Models => "models",
+            DynamicValues::Agents => "agents",
+            DynamicValues::Sessions => "sessions",
+            DynamicValues::Providers => "providers",
+            DynamicValues::Profiles => "profiles",
+        }
+    }
+}
+
+/// Arguments whose values `rot __complete` lists: the command (`None` for
+/// every command that takes the argument), the argument ID, and the values.
+const LIVE_ARGS: &[(Option<&str>, &str, DynamicValues)] = &[
+    (None, "model", DynamicValues::Models),
+    (None, "agent", DynamicValues::Agents),
+    (None, "provider", DynamicValues::Providers),
+    (None, "profile", DynamicValues::Profiles),
+    (Some("agent create"), "from", DynamicValues::Agents),
+    (Some("agent show"), "name", DynamicValues::Agents),
+    (Some("agent edit"), "name", DynamicValues::Agents),
+    (Some("exec"), "resume", DynamicValues::Sessions),
+    (Some("session resume"), "id", DynamicValues::Sessions),
+    (Some("session tree"), "id", DynamicValues::Sessions),
+];
+
+/// An argument completed with live values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LiveArg {
+    /// Subcommands after `rot`; empty for an option any command may take.
+    path: Vec<String>,
+    /// `--long` and `-s` forms; empty for a positional argument.
+    flags: Vec<String>,
+    values: DynamicValues,
+}
+
+/// Print the completion script for `shell`.
+pub fn completions(shell: Shell) {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut visible_command(), "rot", &mut script);
+    let script = String::from_utf8_lossy(&script);
+    let live = live_args();
+    let script = match shell {
+        Shell::Bash => bash_hook(&script, &live),
+        Shell::Zsh => zsh_hook(&script, &live),
+        Shell::Fish => fish_hook(&script, &live),
+        _ => script.into_owned(),
+    };
+    print!("{script}");
+}
+
+/// Print the `rot(1)` man page, or write it and a page for every
+/// subcommand (`rot-exec(1)` and so on) to `dir`.
+pub fn man(dir: Option<&Path>) -> anyhow::Result<()> {
+    match dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            clap_mangen::generate_to(visible_command(), dir)?;
+            eprintln!("Man pages written to {}", dir.display());
+        }
+        None => clap_mangen::Man::new(visible_command()).render(&mut std::io::stdout())?,
+    }
+    Ok(())
+}
+
+/// Print one completion candidate per line. Failures print nothing, so a
+/// broken config never breaks the shell.
+pub async fn complete(values: DynamicValues) {
+    let mut candidates = match values {
+        DynamicValues::Models => models(),
+        DynamicValues::Agents => agents(),
+        DynamicValues::Sessions => sessions().await,
+        DynamicValues::Providers => {
+            let mut names: Vec<String> = ["anthropic", "openai", "openrouter", "zai"]
+                .into_iter()
+                .map(str::to_string)
+                .collect();
+            names.extend(rot_core::ConfigStore::new().load().providers.into_keys());
+            names
+        }
+        DynamicValues::Profiles => rot_core::ConfigStore::new().profile_names(),
+    };
+    if values != DynamicValues::Sessions {
+        candidates.sort();
+        candidates.dedup();
+    }
+    for candidate in candidates {
+        println!("{candidate}");
+    }
+}
+
+fn models() -> Vec<String> {
+    use rot_provider::{
+        new_openai_provider, new_openrouter_provider, new_zai_provider, AnthropicProvider, Provider,
+    };
+
+    let config = rot_core::ConfigStore::new().load();
+    let builtin: [Box<dyn Provider>; 4] = [
+        Box::new(AnthropicProvider::new(String::new())),
+        Box::new(new_openai_provider(String::new())),
+        Box::new(new_zai_provider(String::new())),
+        Box::new(new_openrouter_provider(
+            String::new(),
+            &config.openrouter_routing,
+        )),
+    ];
+    let mut models: Vec<String> = builtin
+        .iter()
+        .flat_map(|provider| provider.models())
+        .map(|model| model.id)
+        .collect();
+    models.extend(
+        config
+            .providers
+            .values()
+            .flat_map(|provider| provider.models.iter().map(|model| model.id.clone())),
+    );
+    models.extend(config.aliases.into_keys());
+    models
+}
+
+fn agents() -> Vec<String> {
+    let mut names: Vec<String> = rot_core::AgentRegistry::builtins()
+        .iter()
+        .map(|profile| profile.name.to_string())
+        .collect();
+    if let Ok(cwd) = std::env::current_dir() {
+        if let Ok(agents) = rot_core::agent_file::load_project_agents(&cwd) {
+            names.extend(agents.into_iter().map(|agent| agent.name));
+        }
+    }
+    names
+}
+
+/// Most recent first, the order `rot session list` shows.
+async fn sessions() -> Vec<String> {
+    let Ok(cwd) = std::env::current_dir() else {
+        return Vec::new();
+    };
+    rot_session::SessionStore::new()
+        .list_recent(&cwd, 50)
+        .await
+        .map(|sessions| sessions.into_iter().map(|meta| meta.id).collect())
+        .unwrap_or_default()
+}
+
+/// `rot` without its hidden commands, which the generators would otherwise
+/// offer (and which `__complete` trips up in the bash generator).
+fn visible_command() -> Command {
+    let cli = Cli::command();
+    Command::new("rot")
+        .about(cli.get_about().cloned().unwrap_or_default())
+        .version(env!("CARGO_PKG_VERSION"))
+        .args(cli.get_arguments().cloned())
+        .subcommands(
+            cli.get_subcommands()
+                .filter(|sub| !sub.is_hide_set())
+                .cloned(),
+        )
+}
+
+/// Every argument in [`LIVE_ARGS`], with the flags clap defines for it.
+fn live_args() -> Vec<LiveArg> {
+    let mut args = Vec::new();
+    collect_live_args(&visible_command(), &[], &mut args);
+    args
+}
+
+fn collect_live_args(command: &Command, path: &[String], args: &mut Vec<LiveArg>) {
+    let joined = path.join(" ");
+    for arg in command.get_arguments() {
+        let Some(&(scope, _, values)) = LIVE_ARGS.iter().find(|(scope, id, _)| {
+            *id == arg.get_id().as_str() && scope.map_or(true, |scope| scope == joined)
+        }) else {
+            continue;
+        };
+        let flags = arg
+            .get_long_and_visible_aliases()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|long| format!("--{long}"))
+            .chain(arg.get_short().map(|short| format!("-{short}")))
+            .collect();
+        let live = LiveArg {
+            // Positional arguments are only recognised right after their command.
+            path: if scope.is_some() || arg.is_positional() {
+                path.to_vec()
+            } else {
+                Vec::new()
+            },
+            flags,
+            values,
+        };
+        if !args.contains(&live) {
+            args.push(live);
+        }
+    }
+    for sub in command.get_subcommands() {
+        let mut sub_path = path.to_vec();
+        sub_path.push(sub.get_name().to_string());
+        collect_live_args(sub, &sub_path, args);
+    }
+}
+
+/// `case "$cmd,$word"` arms that follow the words typed so far to the
+/// commands in `live`, leaving the path in `cmd`. Shared by bash and zsh.
+fn command_arms(live: &[LiveArg]) -> String {
+    let mut steps: Vec<(String, String)> = Vec::new();
+    for arg in live {
+        for depth in 1..=arg.path.len() {
+            let step = (arg.path[..depth - 1].join(" "), arg.path[depth - 1].clone());
+            if !steps.contains(&step) {
+                steps.push(step);
+            }
+        }
+    }
+    let mut arms = String::new();
+    for (parent, word) in steps {
+        let next = if parent.is_empty() {
+            word.clone()
+        } else {
+            format!("{parent} {word}")
+        };
+        let _ = writeln!(arms, "            \"{parent},{word}\") cmd=\"{next}\" ;;");
+    }
+    arms
+}
+
+/// `case "$cmd,$prev"` arms that set `values` for the argument being typed.
+/// Shared by bash and zsh.
+fn value_arms(live: &[LiveArg]) -> String {
+    let mut arms = String::new();
+    for arg in live {
+        let command = arg.path.join(" ");
+        let patterns: Vec<String> = match (arg.path.last(), arg.flags.is_empty()) {
+            (None, _) => arg.flags.iter().map(|flag| format!("*,{flag}")).collect(),
+            // A positional argument right after its command.
+            (Some(last), true) => vec![format!("\"{command},{last}\"")],
+            (Some(_), false) => arg
+                .flags
+                .iter()
+                .map(|flag| format!("\"{command},{flag}\""))
+                .collect(),
+        };
+        let _ = writeln!(
+            arms,
+            "        {}) values={} ;;",
+            patterns.join("|"),
+            arg.values.name()
+        );
+    }
+    arms
+}
+
+/// Wrap clap's `_rot` in a bash function that answers live arguments first.
+fn bash_hook(script: &str, live: &[LiveArg]) -> String {
+    format!(
+        "{script}\n\
+         # Live values for models, agents, and sessions, from `rot __complete`.\n\
+         _rot_live() {{\n    \
+             local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    \
+             local cmd=\"\" word values=\"\"\n    \
+             for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do\n        \
+                 case \"$cmd,$word\" in\n\
+         {}        esac\n    \
+             done\n    \
+             case \"$cmd,$prev\" in\n\
+         {}    esac\n    \
+             if [[ -n \"$values\" ]]; then\n        \
+                 COMPREPLY=($(compgen -W \"$(rot __complete \"$values\" 2>/dev/null)\" -- \"$cur\"))\n        \
+                 return 0\n    \
+             fi\n    \
+             _rot \"$@\"\n\
+         }}\n\n\
+         complete -F _rot_live -o bashdefault -o default rot\n",
+        command_arms(live),
+        value_arms(live),
+    )
+}
+
+/// The end of clap's zsh script, which registers `_rot` for `rot`.
+const ZSH_REGISTRATION: &str =
+    "if [ \"$funcstack[1]\" = \"_rot\" ]; then\n    _rot \"$@\"\nelse\n    compdef _rot rot\nfi\n";
+
+/// Register a zsh function in place of clap's `_rot` that answers live
+/// arguments first.
+fn zsh_hook(script: &str, live: &[LiveArg]) -> String {
+    let body = script
+        .strip_suffix(ZSH_REGISTRATION)
+        .expect("clap's zsh script ends by registering _rot");
+    format!(
+        "{body}\
+         # Live values for models, agents, and sessions, from `rot __complete`.\n\
+         _rot_live() {{\n    \
+             local cmd=\"\" word values=\"\" prev=\"${{words[CURRENT-1]}}\"\n    \
+             for word in \"${{(@)words[2,CURRENT-1]}}\"; do\n        \
+                 case \"$cmd,$word\" in\n\
+         {}        esac\n    \
+             done\n    \
+             case \"$cmd,$prev\" in\n\
+         {}    esac\n    \
+             if [[ -n \"$values\" ]]; then\n        \
+                 local -a candidates\n        \
+                 candidates=(${{(f)\"$(rot __complete $values 2>/dev/null)\"}})\n        \
+                 compadd -a candidates\n        \
+                 return\n    \
+             fi\n    \
+             _rot \"$@\"\n\
+         }}\n\n\
+         compdef _rot_live rot\n\
+         if [ \"$funcstack[1]\" = \"_rot\" ]; then\n    \
+             _rot_live \"$@\"\n\
+         fi\n",
+        command_arms(live),
+        value_arms(live),
+    )
+}
+
+/// Add fish completions for live arguments; fish merges them with clap's.
+fn fish_hook(script: &str, live: &[LiveArg]) -> String {
+    let mut hook =
+        String::from("\n# Live values for models, agents, and sessions, from `rot __complete`.\n");
+    for arg in live {
+        let mut line = String::from("complete -c rot");
+        if let Some((first, rest)) = arg.path.split_first() {
+            let mut condition = format!("__fish_rot_using_subcommand {first}");
+            for word in rest {
+                let _ = write!(condition, "; and __fish_seen_subcommand_from {word}");
+            }
+            let _ = write!(line, " -n \"{condition}\"");
+        }
+        for flag in &arg.flags {
+            match flag.strip_prefix("--") {
+                Some(long) => {
+                    let _ = write!(line, " -l {long}");
+                }
+                None => {
+                    let _ = write!(line, " -s {}", flag.trim_start_matches('-'));
+                }
+            }
+        }
+        let _ = writeln!(line, " -x -a '(rot __complete {})'", arg.values.name());
+        hook.push_str(&line);
+    }
+    format!("{script}{hook}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(shell: Shell) -> String {
+        let mut script = Vec::new();
+        clap_complete::generate(shell, &mut visible_command(), "rot", &mut script);
+        String::from_utf8(script).unwrap()
+    }
+
+    #[test]
+    fn test_live_args_follow_the_cli() {
+        let live = live_args();
+        let find = |path: &[&str], values: DynamicValues| {
+            live.iter()
+                .find(|arg| arg.path == path && arg.values == values)
+                .map(|arg| arg.flags.clone())
+        };
+        assert_eq!(
+            find(&[], DynamicValues::Models),
+            Some(vec!["--model".to_string()])
+        );
+        assert_eq!(
+            find(&["exec"], DynamicValues::Sessions),
+            Some(vec!["--resume".to_string()])
+        );
+        assert_eq!(
+            find(&["session", "resume"], DynamicValues::Sessions),
+            Some(vec![])
+        );
+        assert_eq!(
+            live.iter()
+                .filter(|arg| arg.values == DynamicValues::Models)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_scripts_add_live_values_to_clap_output() {
+        let live = live_args();
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::PowerShell,
+            Shell::Elvish,
+        ] {
+            assert!(
+                !generate(shell).contains("__complete"),
+                "{shell} offers hidden commands"
+            );
+        }
+
+        let bash = bash_hook(&generate(Shell::Bash), &live);
+        assert!(bash.contains("        *,--model) values=models ;;"));
+        assert!(bash.contains("\"session,resume\") cmd=\"session resume\" ;;"));
+        assert!(bash.contains("\"session resume,resume\") values=sessions ;;"));
+        assert!(bash.ends_with("complete -F _rot_live -o bashdefault -o default rot\n"));
+
+        let zsh = zsh_hook(&generate(Shell::Zsh), &live);
+        assert!(zsh.starts_with("#compdef rot\n"));
+        assert!(zsh.contains("\"exec,--resume\") values=sessions ;;"));
+        assert!(!zsh.contains("compdef _rot rot"));
+        assert!(zsh.ends_with("    _rot_live \"$@\"\nfi\n"));
+
+        let fish = fish_hook(&generate(Shell::Fish), &live);
+        assert!(fish.contains(
+            "complete -c rot -n \"__fish_rot_using_subcommand agent; and __fish_seen_subcommand_from show\" -x -a '(rot __complete agents)'"
+        ));
+        assert!(fish.contains("complete -c rot -l model -x -a '(rot __complete models)'"));
+    }
+
+    #[test]
+    fn test_man_pages_cover_subcommands() {
+        let mut page = Vec::new();
+        clap_mangen::Man::new(visible_command())
+            .render(&mut page)
+            .unwrap();
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains(".TH rot 1"));
+        assert!(page.contains("rot\\-session"));
+        assert!(!page.contains("__complete"));
+
+        let dir = tempfile::tempdir().unwrap();
+        man(Some(dir.path())).unwrap();
+        assert!(dir.path().join("rot.1").is_file());
+        assert!(dir.path().join("rot-session-list.1").is_file());
+    }
+}
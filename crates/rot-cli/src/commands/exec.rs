@@ -1,20 +1,111 @@
 //! Single-shot exec command.
 
-use rot_core::{Agent, AgentConfig, AgentRegistry, ApprovalPolicy, ContentBlock, Message, RuntimeSecurityConfig, SandboxMode};
-use rot_provider::{AnthropicProvider, Provider, new_openai_provider, new_zai_provider};
-use rot_session::SessionStore;
+use rot_core::config::RlmSettings;
+use rot_core::hooks::{HookEvent, HookPayload, Hooks};
+use rot_core::output_schema::{self, OutputSchema, OutputSchemaError};
+use rot_core::run_summary::RunSummary;
+use rot_core::usage::UsageSummary;
+use rot_core::{
+    Agent, AgentConfig, AgentProcessError, AgentRegistry, CancellationToken, ContentBlock, Message,
+    RuntimeSecurityConfig,
+};
+use rot_provider::{
+    load_openrouter_provider, new_openai_provider, new_zai_provider, AnthropicProvider, Provider,
+};
+use rot_session::{SessionEntry, SessionStore};
 use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::io::{IsTerminal, Read};
 use std::path::Path;
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-/// `rot exec` output mode options.
+/// Exit code for runtime failures not covered by a more specific code.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code when the reply fails `--output-schema` validation.
+pub const EXIT_SCHEMA: i32 = 2;
+/// Exit code when `--timeout` or the batch token budget runs out.
+pub const EXIT_BUDGET_EXCEEDED: i32 = 3;
+/// Exit code when the agent reaches `--max-turns` without finishing.
+pub const EXIT_MAX_TURNS: i32 = 4;
+/// Exit code when the provider request fails.
+pub const EXIT_PROVIDER: i32 = 5;
+/// Exit code when a tool call was denied by the approval policy or sandbox.
+pub const EXIT_TOOL_DENIED: i32 = 6;
+
+/// Saved session an exec run continues.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResumeFrom {
+    /// The most recent session in the working directory.
+    Latest,
+    Id(String),
+}
+
+/// `rot exec` output mode and session options.
 #[derive(Debug, Clone)]
 pub struct ExecOptions {
     pub json: bool,
     pub final_json: bool,
+    /// Print JSONL events as the run progresses rather than at the end.
+    pub stream: bool,
     pub output_schema: Option<String>,
+    /// Times the model is asked again when its reply fails `output_schema`.
+    pub schema_retries: usize,
+    pub max_iterations: usize,
+    /// Continue a saved session instead of starting a new one.
+    pub resume: Option<ResumeFrom>,
+    /// Wall-clock limit for the run.
+    pub timeout: Option<Duration>,
+    /// Run the `verify` checks before finishing, even if the config leaves them off.
+    pub verify: bool,
+    /// Flags overriding the `rlm` config section for `--rlm` runs.
+    pub rlm: RlmOverrides,
+}
+
+/// `rot exec --rlm-*` flags; each replaces its `rlm` config setting when given.
+#[derive(Debug, Clone, Default)]
+pub struct RlmOverrides {
+    pub max_iterations: Option<usize>,
+    pub max_stalled_iterations: Option<usize>,
+    pub iteration_output_tokens: Option<usize>,
+    pub system_prompt: Option<String>,
+    pub branches: Option<usize>,
+    /// Run even when an earlier answer is cached, and don't cache this one.
+    pub no_cache: bool,
+}
+
+impl RlmOverrides {
+    /// `settings` with the given flags applied.
+    pub fn apply(&self, settings: &RlmSettings) -> RlmSettings {
+        let mut settings = settings.clone();
+        if let Some(max_iterations) = self.max_iterations {
+            settings.max_iterations = max_iterations;
+        }
+        if let Some(max_stalled) = self.max_stalled_iterations {
+            settings.max_stalled_iterations = max_stalled;
+        }
+        if let Some(tokens) = self.iteration_output_tokens {
+            settings.iteration_output_tokens = tokens;
+        }
+        if let Some(prompt) = &self.system_prompt {
+            settings.system_prompt = Some(prompt.clone());
+        }
+        if let Some(branches) = self.branches {
+            settings.branches = branches;
+        }
+        if self.no_cache {
+            settings.cache = false;
+        }
+        settings
+    }
+}
+
+impl ExecOptions {
+    /// Whether stdout carries JSON rather than the reply text.
+    pub fn machine_output(&self) -> bool {
+        self.json || self.final_json || self.stream
+    }
 }
 
 /// Typed error used to propagate deterministic process exit codes.
@@ -25,16 +116,125 @@ pub struct ExecExitError {
     pub message: String,
 }
 
+/// A cancellation token that fires once a time limit passes.
+pub struct Deadline {
+    pub token: CancellationToken,
+    timer: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Deadline {
+    /// Start the timer; with no limit the token never fires.
+    pub fn new(limit: Option<Duration>) -> Self {
+        let token = CancellationToken::new();
+        let timer = limit.map(|limit| {
+            let token = token.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(limit).await;
+                token.cancel();
+            })
+        });
+        Self { token, timer }
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        if let Some(timer) = &self.timer {
+            timer.abort();
+        }
+    }
+}
+
+/// Exit code and message for an agent run that failed.
+///
+/// Exec runs are only cancelled by their deadline, so `Cancelled` means the
+/// time limit was reached.
+pub fn failure(err: &AgentProcessError, timeout: Option<Duration>) -> (i32, String) {
+    match err {
+        AgentProcessError::Cancelled => (
+            EXIT_BUDGET_EXCEEDED,
+            format!("timed out after {}s", timeout.unwrap_or_default().as_secs()),
+        ),
+        AgentProcessError::MaxIterations(_) => (EXIT_MAX_TURNS, err.to_string()),
+        AgentProcessError::Provider(_) => (EXIT_PROVIDER, err.to_string()),
+        _ => (EXIT_FAILURE, err.to_string()),
+    }
+}
+
+/// Name of the first tool whose call was denied in `messages`.
+pub fn denied_tool(messages: &[Message]) -> Option<String> {
+    collect_tool_events(messages)
+        .into_iter()
+        .find_map(|event| match event {
+            ToolEvent::Result { name, metadata, .. } if metadata["denied"] == true => Some(name),
+            _ => None,
+        })
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct ToolCallRecord {
     name: String,
     arguments: Value,
 }
 
-#[derive(Debug, Clone, Serialize)]
-struct UsageSummary {
-    input_tokens: usize,
-    output_tokens: usize,
+/// Build the exec prompt from the argument, piped stdin, and `--file` paths.
+///
+/// With no prompt, or `-`, stdin is the prompt. Otherwise anything piped on
+/// stdin is attached as context after the prompt.
+pub fn read_prompt(prompt: Option<&str>, files: &[String]) -> anyhow::Result<String> {
+    let stdin = std::io::stdin();
+    let piped = if stdin.is_terminal() {
+        None
+    } else {
+        let mut input = String::new();
+        stdin
+            .lock()
+            .read_to_string(&mut input)
+            .map_err(|e| anyhow::anyhow!("failed to read stdin: {e}"))?;
+        Some(input)
+    };
+
+    let (prompt, piped) = match (prompt, piped) {
+        (Some(prompt), piped) if prompt != "-" => (prompt.to_string(), piped),
+        (_, Some(input)) if !input.trim().is_empty() => (input, None),
+        _ => anyhow::bail!("no prompt given: pass one as an argument, with -p, or on stdin"),
+    };
+
+    let files = files
+        .iter()
+        .map(|path| {
+            std::fs::read_to_string(path)
+                .map(|content| (path.clone(), content))
+                .map_err(|e| anyhow::anyhow!("failed to read --file '{path}': {e}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(compose_prompt(&prompt, piped.as_deref(), &files))
+}
+
+/// `prompt` followed by each file and any piped input in tagged blocks.
+fn compose_prompt(prompt: &str, piped: Option<&str>, files: &[(String, String)]) -> String {
+    fn push_block(out: &mut String, open: &str, close: &str, content: &str) {
+        out.push_str(&format!("\n\n{open}\n{content}"));
+        if !content.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(close);
+    }
+
+    let mut out = prompt.trim_end().to_string();
+    for (path, content) in files {
+        push_block(
+            &mut out,
+            &format!("<file path=\"{path}\">"),
+            "</file>",
+            content,
+        );
+    }
+    if let Some(input) = piped.filter(|input| !input.trim().is_empty()) {
+        push_block(&mut out, "<stdin>", "</stdin>", input);
+    }
+    out
 }
 
 /// Execute a single prompt and print the result.
@@ -50,86 +250,326 @@ pub async fn run(
     options: ExecOptions,
 ) -> anyhow::Result<()> {
     let started = Instant::now();
-    let provider = create_provider(provider_name, model)?;
+    let output_schema = options
+        .output_schema
+        .as_deref()
+        .map(|path| OutputSchema::from_file(Path::new(path)))
+        .transpose()?;
+    let provider = create_provider(provider_name, model).await?;
     let agent_profile = AgentRegistry::resolve(agent_name)?;
     let provider_label = provider.name().to_string();
     let model_label = provider.current_model().to_string();
-    let sandbox_mode_label = sandbox_mode_label(runtime_security.sandbox_mode).to_string();
-    let approval_policy_label = approval_policy_label(runtime_security.approval_policy).to_string();
+    let sandbox_mode_label = runtime_security.sandbox_mode.as_str().to_string();
+    let approval_policy_label = runtime_security.approval_policy.as_str().to_string();
+    let dry_run = runtime_security.dry_run;
 
-    let (_, tools) = super::load_tool_registry(runtime_security.clone()).await?;
+    let (rot_config, tools) = super::load_tool_registry(runtime_security.clone(), false).await?;
 
+    let rlm_settings = options.rlm.apply(&rot_config.rlm);
+    let task_policy = rot_config.tasks.policy();
     let config = AgentConfig {
         agent_name: agent_profile.name.to_string(),
-        system_prompt: Some(agent_profile.system_prompt.to_string()),
+        system_prompt: Some(rot_core::project::with_instructions(
+            agent_profile.system_prompt.to_string(),
+            &std::env::current_dir()?,
+        )),
         max_tokens: Some(4096),
-        ..Default::default()
+        max_iterations: options.max_iterations,
+        task_policy: if rlm {
+            rlm_settings.task_policy(task_policy)
+        } else {
+            task_policy
+        },
+        verify: rot_config.verify.clone().enabled_if(options.verify),
+        ..AgentConfig::from_config(&rot_config)
     };
 
     let session_store = SessionStore::new();
     let cwd = std::env::current_dir()?;
-    let session = session_store.create(&cwd, &model_label, &provider_label).await?;
-    let agent = std::sync::Arc::new(
-        Agent::new(provider, tools, config, runtime_security.clone()).with_session_id(session.id),
-    );
+    let (mut session, mut messages) = match &options.resume {
+        None => (
+            session_store
+                .create(&cwd, &model_label, &provider_label)
+                .await?,
+            Vec::new(),
+        ),
+        Some(from) => {
+            let id = match from {
+                ResumeFrom::Id(id) => id.clone(),
+                ResumeFrom::Latest => session_store
+                    .latest(&cwd)
+                    .await?
+                    .map(|meta| meta.id)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no session to continue in {}", cwd.display())
+                    })?,
+            };
+            let session = session_store
+                .load(&cwd, &id)
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to resume session {id}: {e}"))?;
+            let messages = rot_core::transcript::from_session_entries(&session.entries);
+            (session, messages)
+        }
+    };
+    let persisted = messages.len();
+    let session_id = session.id.clone();
+    // A resumed session keeps working where its last `/cd` left it.
+    let working_dir = Some(session.working_dir())
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(|| cwd.clone());
+    let machine_output = options.machine_output();
+    let stream = options.stream;
+    if stream {
+        emit_line(&session_start_event(
+            &session_id,
+            &provider_label,
+            &model_label,
+            &sandbox_mode_label,
+            &approval_policy_label,
+            dry_run,
+        )?);
+    }
+
+    let usage_totals = Arc::new(Mutex::new(UsageSummary::default()));
+    let usage_sink = usage_totals.clone();
+    let usage_entries: Arc<Mutex<Vec<SessionEntry>>> = Arc::default();
+    let entry_sink = usage_entries.clone();
+    let usage_agent = agent_profile.name.to_string();
+    let mut agent = Agent::new(provider, tools, config, runtime_security.clone())
+        .with_session_id(session_id.clone())
+        .with_working_dir(working_dir)
+        .on_iteration(Box::new(move |progress| {
+            if !machine_output && progress.is_near_limit() {
+                eprintln!(
+                    "warning: iteration {}/{} ({} tokens so far)",
+                    progress.iteration,
+                    progress.max_iterations,
+                    progress.input_tokens + progress.output_tokens
+                );
+            }
+        }))
+        .on_usage(Box::new(move |report| {
+            if let Ok(mut totals) = usage_sink.lock() {
+                totals.record(report);
+            }
+            if let Ok(mut entries) = entry_sink.lock() {
+                entries.push(rot_core::usage::usage_entry(report, &usage_agent));
+            }
+            if stream {
+                emit_line(&usage_event(report));
+            }
+        }));
+    if stream {
+        agent = agent
+            .on_text(Box::new(|delta| {
+                emit_line(&serde_json::json!({"type": "text_delta", "delta": delta}));
+            }))
+            .on_tool(Box::new(|event| emit_line(&stream_tool_event(event))));
+    }
+    let agent = Arc::new(agent);
+    let usage_so_far = move || {
+        usage_totals
+            .lock()
+            .map(|totals| totals.clone())
+            .unwrap_or_default()
+    };
+    let hooks = Arc::new(Hooks::new(rot_config.hooks.clone()));
+    let hook_session = session_id.clone();
+    let hook_model = format!("{provider_label}/{model_label}");
+    let hook_pricing = rot_provider::pricing_for(&model_label);
+    let hook_payload = |event: HookEvent| {
+        let usage = usage_so_far();
+        let cost =
+            hook_pricing.map(|pricing| pricing.cost(usage.input_tokens, usage.output_tokens));
+        HookPayload::new(event, hook_session.clone(), hook_model.clone()).with_usage(
+            usage.input_tokens,
+            usage.output_tokens,
+            cost,
+        )
+    };
+    hooks.fire_in_background(hook_payload(HookEvent::RunStarted).with_summary(prompt));
 
     if rlm {
-        let ctx_path =
-            context_path.ok_or_else(|| anyhow::anyhow!("--context is required when using --rlm"))?;
-        let config = rot_rlm::RlmConfig::default();
-        let mut engine = rot_rlm::RlmEngine::new(config, agent.clone());
-        let final_text = engine.process(prompt, ctx_path).await?;
+        let resume_from = match options.resume {
+            Some(_) => {
+                if !prompt.is_empty() || context_path.is_some() {
+                    anyhow::bail!("a resumed RLM run keeps its prompt and context; pass neither");
+                }
+                let checkpoint = pending_rlm_checkpoint(&session.entries)?.ok_or_else(|| {
+                    anyhow::anyhow!("session {session_id} has no unfinished RLM run")
+                })?;
+                Some(checkpoint)
+            }
+            None => None,
+        };
+        let ctx_path = match (context_path, &rot_config.rlm.context) {
+            _ if resume_from.is_some() => String::new(),
+            (Some(path), _) => path.to_string(),
+            (None, Some(_)) => rot_config.rlm.context_path(&cwd).display().to_string(),
+            (None, None) => anyhow::bail!(
+                "--context is required when using --rlm without rlm.context in config"
+            ),
+        };
+        if resume_from.is_none() {
+            save_messages(&session_store, &mut session, &[Message::user(prompt)], &[]).await?;
+        }
+
+        // Checkpoints are saved as they arrive, so an interrupted run can be
+        // resumed from its last completed iteration.
+        let (checkpoint_tx, mut checkpoint_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut config = rot_rlm::RlmConfig::from_settings(&rlm_settings);
+        config.on_checkpoint = Some(Arc::new(move |checkpoint: &rot_rlm::RlmCheckpoint| {
+            let _ = checkpoint_tx.send(checkpoint.clone());
+        }));
+        if stream {
+            config.on_progress = Some(Arc::new(|progress: &rot_rlm::RlmProgress| {
+                emit_line(&rlm_progress_event(progress));
+            }));
+        }
+        let engine_agent = agent.clone();
+        let prompt = prompt.to_string();
+        let run = async move {
+            let mut engine = rot_rlm::RlmEngine::new(config, engine_agent);
+            match resume_from {
+                Some(checkpoint) => engine.resume(checkpoint).await,
+                None => engine.process(&prompt, &ctx_path).await,
+            }
+        };
+        let save_checkpoints = async {
+            while let Some(checkpoint) = checkpoint_rx.recv().await {
+                if let Err(e) = save_checkpoint(&session_store, &mut session, &checkpoint).await {
+                    tracing::warn!("Failed to save RLM checkpoint: {e}");
+                }
+            }
+        };
+        let (result, ()) = tokio::join!(run, save_checkpoints);
+        let answer: Vec<Message> = match &result {
+            Ok(text) => vec![Message::assistant(vec![ContentBlock::Text {
+                text: text.clone(),
+            }])],
+            Err(_) => Vec::new(),
+        };
+        let usage = take_entries(&usage_entries);
+        let summary = run_summary(&agent, &answer, &usage, agent_profile.name, started);
+        save_messages(
+            &session_store,
+            &mut session,
+            &answer,
+            &with_summary(usage, &summary),
+        )
+        .await?;
+        let final_text = match result {
+            Ok(text) => text,
+            Err(e) => {
+                hooks
+                    .fire(&hook_payload(HookEvent::RunFailed).with_summary(&e.to_string()))
+                    .await;
+                return Err(e);
+            }
+        };
+        hooks
+            .fire(&hook_payload(HookEvent::RunFinished).with_summary(&final_text))
+            .await;
         let elapsed_ms = started.elapsed().as_millis();
         let data = ExecOutputData {
-                status: "ok".to_string(),
-                final_text,
-                tool_calls: Vec::new(),
-                usage: UsageSummary {
-                    input_tokens: 0,
-                    output_tokens: 0,
-                },
-                elapsed_ms,
-                error: None,
-                provider: provider_label,
-                model: model_label,
-                sandbox_mode: sandbox_mode_label,
-                approval_policy: approval_policy_label,
-            };
-        maybe_validate_schema(options.output_schema.as_deref(), &data.final_text, &options, &data)?;
+            status: "ok".to_string(),
+            final_text,
+            tool_calls: Vec::new(),
+            usage: usage_so_far(),
+            summary,
+            elapsed_ms,
+            error: None,
+            session_id,
+            provider: provider_label,
+            model: model_label,
+            sandbox_mode: sandbox_mode_label,
+            approval_policy: approval_policy_label,
+            dry_run,
+        };
+        let validated = output_schema
+            .as_ref()
+            .map(|schema| schema.validate(&data.final_text));
+        maybe_validate_schema(validated, &options, &data)?;
         return emit_exec_output(&options, &data, &[]);
     }
 
-    let mut messages: Vec<Message> = Vec::new();
-    let response = match agent.process(&mut messages, prompt).await {
+    let deadline = Deadline::new(options.timeout);
+    let mut validated = None;
+    let result = match &output_schema {
+        Some(schema) => output_schema::process_with_schema(
+            &agent,
+            &mut messages,
+            prompt,
+            schema,
+            options.schema_retries,
+            deadline.token.clone(),
+        )
+        .await
+        .map(|(reply, value)| {
+            validated = Some(value);
+            reply
+        }),
+        None => {
+            agent
+                .process_cancellable(&mut messages, prompt, Vec::new(), deadline.token.clone())
+                .await
+        }
+    };
+    let usage = take_entries(&usage_entries);
+    let summary = run_summary(
+        &agent,
+        &messages[persisted..],
+        &usage,
+        agent_profile.name,
+        started,
+    );
+    let saved = save_messages(
+        &session_store,
+        &mut session,
+        &messages[persisted..],
+        &with_summary(usage, &summary),
+    )
+    .await;
+    let response = match result {
         Ok(resp) => resp,
         Err(err) => {
             let elapsed_ms = started.elapsed().as_millis();
+            let (code, message) = failure(&err, options.timeout);
+            hooks
+                .fire(&hook_payload(HookEvent::RunFailed).with_summary(&message))
+                .await;
             let data = ExecOutputData {
                 status: "error".to_string(),
                 final_text: String::new(),
                 tool_calls: Vec::new(),
-                usage: UsageSummary {
-                    input_tokens: 0,
-                    output_tokens: 0,
-                },
+                usage: usage_so_far(),
+                summary,
                 elapsed_ms,
-                error: Some(err.to_string()),
+                error: Some(message.clone()),
+                session_id,
                 provider: provider_label,
                 model: model_label,
                 sandbox_mode: sandbox_mode_label,
                 approval_policy: approval_policy_label,
+                dry_run,
             };
             emit_exec_output(&options, &data, &[])?;
             return Err(anyhow::Error::new(ExecExitError {
-                code: 1,
-                message: "exec failed".to_string(),
+                code,
+                message: format!("exec failed: {message}"),
             }));
         }
     };
 
+    saved?;
+
     let elapsed_ms = started.elapsed().as_millis();
     let final_text = extract_text_from_message(&response);
-    let tool_events = collect_tool_events(&messages);
+    hooks
+        .fire(&hook_payload(HookEvent::RunFinished).with_summary(&final_text))
+        .await;
+    let tool_events = collect_tool_events(&messages[persisted..]);
     let tool_calls = tool_events
         .iter()
         .filter_map(|event| {
@@ -141,27 +581,31 @@ pub async fn run(
         })
         .collect::<Vec<_>>();
 
-    let usage = UsageSummary {
-        input_tokens: 0,
-        output_tokens: 0,
-    };
-
     let data = ExecOutputData {
         status: "ok".to_string(),
         final_text,
         tool_calls,
-        usage,
+        usage: usage_so_far(),
+        summary,
         elapsed_ms,
         error: None,
+        session_id,
         provider: provider_label,
         model: model_label,
         sandbox_mode: sandbox_mode_label,
         approval_policy: approval_policy_label,
+        dry_run,
     };
 
-    maybe_validate_schema(options.output_schema.as_deref(), &data.final_text, &options, &data)?;
+    maybe_validate_schema(validated, &options, &data)?;
     emit_exec_output(&options, &data, &tool_events)?;
 
+    if let Some(name) = denied_tool(&messages[persisted..]) {
+        return Err(anyhow::Error::new(ExecExitError {
+            code: EXIT_TOOL_DENIED,
+            message: format!("a call to '{name}' was denied"),
+        }));
+    }
     Ok(())
 }
 
@@ -224,6 +668,7 @@ fn collect_tool_events(messages: &[Message]) -> Vec<ToolEvent> {
                     content,
                     is_error,
                     metadata,
+                    ..
                 } => {
                     let name = calls_by_id
                         .get(tool_call_id)
@@ -246,18 +691,182 @@ fn collect_tool_events(messages: &[Message]) -> Vec<ToolEvent> {
     events
 }
 
+/// `--stream` event for a tool starting or finishing.
+pub fn stream_tool_event(event: &rot_core::ToolEvent) -> Value {
+    match event {
+        rot_core::ToolEvent::Started {
+            call_id,
+            name,
+            arguments,
+        } => serde_json::json!({
+            "type": "tool_call",
+            "call_id": call_id,
+            "name": name,
+            "arguments": arguments,
+        }),
+        rot_core::ToolEvent::Finished { call_id, exchange } => serde_json::json!({
+            "type": "tool_result",
+            "call_id": call_id,
+            "name": exchange.name,
+            "is_error": exchange.is_error,
+            "output": exchange.output,
+            "metadata": exchange.metadata,
+        }),
+    }
+}
+
+/// `--stream` event for one provider usage report.
+pub fn usage_event(report: &rot_core::UsageReport) -> Value {
+    serde_json::json!({
+        "type": "usage",
+        "model": report.model,
+        "task_id": report.task_id,
+        "input_tokens": report.input_tokens,
+        "output_tokens": report.output_tokens,
+    })
+}
+
+/// `--stream` event for one step of an RLM run.
+pub fn rlm_progress_event(progress: &rot_rlm::RlmProgress) -> Value {
+    serde_json::json!({
+        "type": "rlm_progress",
+        "phase": progress.phase,
+        "step": progress.step,
+        "total": progress.total,
+        "branch": progress.branch.map(|(branch, _)| branch),
+        "branches": progress.branch.map(|(_, branches)| branches),
+        "input_tokens": progress.input_tokens,
+        "output_tokens": progress.output_tokens,
+        "goal": progress.goal,
+    })
+}
+
+/// Append `messages`, then the `usage` entries, to the session file.
+pub async fn save_messages(
+    store: &SessionStore,
+    session: &mut rot_session::Session,
+    messages: &[Message],
+    usage: &[SessionEntry],
+) -> anyhow::Result<()> {
+    let entries = rot_core::transcript::to_session_entries(messages)
+        .map_err(|e| anyhow::anyhow!("failed to save session: {e}"))?;
+    for entry in entries.into_iter().chain(usage.iter().cloned()) {
+        store.append(session, entry).await?;
+    }
+    Ok(())
+}
+
+/// Append an RLM checkpoint to the session file.
+async fn save_checkpoint(
+    store: &SessionStore,
+    session: &mut rot_session::Session,
+    checkpoint: &rot_rlm::RlmCheckpoint,
+) -> anyhow::Result<()> {
+    let entry = SessionEntry::RlmCheckpoint {
+        id: rot_core::MessageId::new().as_str().to_string(),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        state: serde_json::to_value(checkpoint)?,
+    };
+    store.append(session, entry).await?;
+    Ok(())
+}
+
+/// The last checkpoint of an RLM run in `entries` that has no saved answer.
+fn pending_rlm_checkpoint(
+    entries: &[SessionEntry],
+) -> anyhow::Result<Option<rot_rlm::RlmCheckpoint>> {
+    let mut pending = None;
+    for entry in entries {
+        match entry {
+            SessionEntry::RlmCheckpoint { state, .. } => pending = Some(state),
+            SessionEntry::Message { role, .. } if role == "assistant" => pending = None,
+            _ => {}
+        }
+    }
+    pending
+        .map(|state| {
+            serde_json::from_value(state.clone())
+                .map_err(|e| anyhow::anyhow!("invalid RLM checkpoint: {e}"))
+        })
+        .transpose()
+}
+
+/// Usage entries recorded so far, leaving the list empty.
+pub fn take_entries(entries: &Mutex<Vec<SessionEntry>>) -> Vec<SessionEntry> {
+    entries
+        .lock()
+        .map(|mut entries| std::mem::take(&mut *entries))
+        .unwrap_or_default()
+}
+
+/// Resource use of a run that added `messages` and recorded `usage`.
+fn run_summary(
+    agent: &Agent,
+    messages: &[Message],
+    usage: &[SessionEntry],
+    agent_name: &str,
+    started: Instant,
+) -> RunSummary {
+    RunSummary::new(
+        messages,
+        rot_core::usage::session_usage(usage, agent_name),
+        started.elapsed(),
+        agent.provider_time(),
+    )
+}
+
+/// `usage` followed by the run summary entry, for [`save_messages`].
+fn with_summary(mut usage: Vec<SessionEntry>, summary: &RunSummary) -> Vec<SessionEntry> {
+    usage.push(summary.to_entry());
+    usage
+}
+
+fn session_start_event(
+    session_id: &str,
+    provider: &str,
+    model: &str,
+    sandbox_mode: &str,
+    approval_policy: &str,
+    dry_run: bool,
+) -> anyhow::Result<Value> {
+    Ok(serde_json::json!({
+        "type": "session_start",
+        "session_id": session_id,
+        "model": model,
+        "provider": provider,
+        "sandbox_mode": sandbox_mode,
+        "approval_policy": approval_policy,
+        "dry_run": dry_run,
+        "cwd": std::env::current_dir()?.display().to_string(),
+    }))
+}
+
+/// Print one JSONL event and flush so readers see it immediately.
+fn emit_line(event: &Value) {
+    use std::io::Write;
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{event}");
+    let _ = stdout.flush();
+}
+
 #[derive(Debug, Clone)]
 struct ExecOutputData {
     status: String,
     final_text: String,
     tool_calls: Vec<ToolCallRecord>,
     usage: UsageSummary,
+    summary: RunSummary,
     elapsed_ms: u128,
     error: Option<String>,
+    session_id: String,
     provider: String,
     model: String,
     sandbox_mode: String,
     approval_policy: String,
+    dry_run: bool,
 }
 
 fn emit_exec_output(
@@ -265,21 +874,20 @@ fn emit_exec_output(
     data: &ExecOutputData,
     tool_events: &[ToolEvent],
 ) -> anyhow::Result<()> {
-    if options.json {
-        println!(
-            "{}",
-            serde_json::to_string(&serde_json::json!({
-                "type": "session_start",
-                "model": data.model,
-                "provider": data.provider,
-                "sandbox_mode": data.sandbox_mode,
-                "approval_policy": data.approval_policy,
-                "cwd": std::env::current_dir()?.display().to_string(),
-            }))?
-        );
-
-        for event in tool_events {
-            println!("{}", serde_json::to_string(&event.to_json())?);
+    if options.json || options.stream {
+        // Streaming runs already printed session start and tool events live.
+        if options.json {
+            emit_line(&session_start_event(
+                &data.session_id,
+                &data.provider,
+                &data.model,
+                &data.sandbox_mode,
+                &data.approval_policy,
+                data.dry_run,
+            )?);
+            for event in tool_events {
+                emit_line(&event.to_json());
+            }
         }
 
         println!(
@@ -287,8 +895,10 @@ fn emit_exec_output(
             serde_json::to_string(&serde_json::json!({
                 "type": "final",
                 "status": data.status,
+                "session_id": data.session_id,
                 "final_text": data.final_text,
                 "usage": data.usage,
+                "summary": data.summary,
                 "elapsed_ms": data.elapsed_ms,
                 "error": data.error,
             }))?
@@ -301,9 +911,12 @@ fn emit_exec_output(
             "{}",
             serde_json::to_string(&serde_json::json!({
                 "status": data.status,
+                "session_id": data.session_id,
                 "final_text": data.final_text,
                 "tool_calls": data.tool_calls,
+                "dry_run": data.dry_run,
                 "usage": data.usage,
+                "summary": data.summary,
                 "elapsed_ms": data.elapsed_ms,
                 "error": data.error,
             }))?
@@ -317,22 +930,18 @@ fn emit_exec_output(
     Ok(())
 }
 
+/// Fail with [`EXIT_SCHEMA`] when the reply did not pass `--output-schema`.
 fn maybe_validate_schema(
-    schema_path: Option<&str>,
-    final_text: &str,
+    validated: Option<Result<Value, OutputSchemaError>>,
     options: &ExecOptions,
     data: &ExecOutputData,
 ) -> anyhow::Result<()> {
-    let Some(path) = schema_path else {
-        return Ok(());
+    let validation_error = match validated {
+        None | Some(Ok(_)) => return Ok(()),
+        Some(Err(e)) => e.to_string(),
     };
 
-    let validation_error = match validate_output_schema(path, final_text) {
-        Ok(()) => return Ok(()),
-        Err(e) => e,
-    };
-
-    if options.json || options.final_json {
+    if options.machine_output() {
         let mut error_data = data.clone();
         error_data.status = "error".to_string();
         error_data.error = Some(validation_error.clone());
@@ -340,31 +949,11 @@ fn maybe_validate_schema(
     }
 
     Err(anyhow::Error::new(ExecExitError {
-        code: 2,
+        code: EXIT_SCHEMA,
         message: validation_error,
     }))
 }
 
-fn validate_output_schema(schema_path: &str, final_text: &str) -> Result<(), String> {
-    let schema_raw = std::fs::read_to_string(Path::new(schema_path))
-        .map_err(|e| format!("failed to read schema file '{schema_path}': {e}"))?;
-    let schema: Value = serde_json::from_str(&schema_raw)
-        .map_err(|e| format!("invalid schema JSON in '{schema_path}': {e}"))?;
-    let output_json: Value = serde_json::from_str(final_text)
-        .map_err(|e| format!("final response is not valid JSON: {e}"))?;
-
-    let validator = jsonschema::validator_for(&schema)
-        .map_err(|e| format!("invalid JSON schema: {e}"))?;
-
-    let mut errors = validator.iter_errors(&output_json).map(|e| e.to_string()).collect::<Vec<_>>();
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        errors.sort();
-        Err(format!("output schema validation failed: {}", errors.join("; ")))
-    }
-}
-
 fn extract_text_from_message(msg: &Message) -> String {
     msg.content
         .iter()
@@ -376,23 +965,18 @@ fn extract_text_from_message(msg: &Message) -> String {
         .join("")
 }
 
-fn sandbox_mode_label(mode: SandboxMode) -> &'static str {
-    match mode {
-        SandboxMode::ReadOnly => "read-only",
-        SandboxMode::WorkspaceWrite => "workspace-write",
-        SandboxMode::DangerFullAccess => "danger-full-access",
-    }
-}
-
-fn approval_policy_label(policy: ApprovalPolicy) -> &'static str {
-    match policy {
-        ApprovalPolicy::Untrusted => "untrusted",
-        ApprovalPolicy::OnRequest => "on-request",
-        ApprovalPolicy::Never => "never",
+/// Build the named provider, failing when its API key is not set.
+/// OpenRouter's model catalogue is fetched so any listed model can be used.
+pub async fn create_provider(
+    provider_name: &str,
+    model: Option<&str>,
+) -> anyhow::Result<Box<dyn Provider>> {
+    let config = rot_core::ConfigStore::new().load();
+    if let Some(custom) = config.providers.get(provider_name) {
+        return custom
+            .build(provider_name, model)
+            .map_err(|e| anyhow::anyhow!("{e}"));
     }
-}
-
-fn create_provider(provider_name: &str, model: Option<&str>) -> anyhow::Result<Box<dyn Provider>> {
     match provider_name {
         "anthropic" => {
             let api_key = std::env::var("ANTHROPIC_API_KEY").map_err(|_| {
@@ -434,16 +1018,37 @@ fn create_provider(provider_name: &str, model: Option<&str>) -> anyhow::Result<B
             }
             Ok(Box::new(provider))
         }
+        "openrouter" => {
+            let api_key = std::env::var("OPENROUTER_API_KEY").map_err(|_| {
+                anyhow::anyhow!(
+                    "OPENROUTER_API_KEY not set. Set it with:\n  \
+                     export OPENROUTER_API_KEY=your-key-here\n\n\
+                     Get your key from https://openrouter.ai/keys"
+                )
+            })?;
+            let mut provider = load_openrouter_provider(api_key, &config.openrouter_routing).await;
+            if let Some(m) = model {
+                provider.set_model(m).map_err(|e| anyhow::anyhow!("{e}"))?;
+            }
+            Ok(Box::new(provider))
+        }
         other => Err(anyhow::anyhow!(
-            "Unknown provider: {other}. Available: anthropic, zai, openai"
+            "Unknown provider: {other}. Available: anthropic, zai, openai, openrouter"
         )),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ToolEvent, collect_tool_events, validate_output_schema};
-    use rot_core::{ContentBlock, Message};
+    use super::{
+        collect_tool_events, compose_prompt, denied_tool, failure, pending_rlm_checkpoint,
+        rlm_progress_event, stream_tool_event, ToolEvent, EXIT_BUDGET_EXCEEDED, EXIT_FAILURE,
+        EXIT_MAX_TURNS,
+    };
+    use rot_core::message::ToolExchange;
+    use rot_core::{AgentProcessError, ContentBlock, Message};
+    use rot_session::SessionEntry;
+    use std::time::Duration;
 
     #[test]
     fn test_collect_tool_events_order() {
@@ -454,12 +1059,7 @@ mod tests {
                 name: "read".to_string(),
                 arguments: serde_json::json!({"path":"README.md"}),
             }]),
-            Message::tool_result_with_metadata(
-                "tc1",
-                "ok",
-                false,
-                serde_json::json!({"bytes":2}),
-            ),
+            Message::tool_result_with_metadata("tc1", "ok", false, serde_json::json!({"bytes":2})),
         ];
 
         let events = collect_tool_events(&messages);
@@ -469,24 +1069,124 @@ mod tests {
     }
 
     #[test]
-    fn test_output_schema_validation_pass_and_fail() {
-        let dir = tempfile::tempdir().unwrap();
-        let schema = dir.path().join("schema.json");
-        std::fs::write(
-            &schema,
-            r#"{
-                "type":"object",
-                "properties":{"name":{"type":"string"}},
-                "required":["name"]
-            }"#,
-        )
-        .unwrap();
+    fn test_rlm_progress_event_flattens_branch() {
+        let event = rlm_progress_event(&rot_rlm::RlmProgress {
+            phase: rot_rlm::RlmPhase::Iteration,
+            step: 2,
+            total: 30,
+            branch: Some((1, 3)),
+            input_tokens: 900,
+            output_tokens: 40,
+            goal: Some("Count the ERROR lines".to_string()),
+        });
+        assert_eq!(event["type"], "rlm_progress");
+        assert_eq!(event["phase"], "iteration");
+        assert_eq!(
+            (event["branch"].as_u64(), event["branches"].as_u64()),
+            (Some(1), Some(3))
+        );
+        assert_eq!(event["goal"], "Count the ERROR lines");
+    }
 
-        assert!(validate_output_schema(
-            schema.to_str().unwrap(),
-            r#"{"name":"rot"}"#
-        )
-        .is_ok());
-        assert!(validate_output_schema(schema.to_str().unwrap(), r#"{"age":1}"#).is_err());
+    #[test]
+    fn test_stream_tool_events_carry_call_id() {
+        let started = stream_tool_event(&rot_core::ToolEvent::Started {
+            call_id: "tc1".to_string(),
+            name: "read".to_string(),
+            arguments: serde_json::json!({"path": "README.md"}),
+        });
+        assert_eq!(started["type"], "tool_call");
+        assert_eq!(started["call_id"], "tc1");
+        assert_eq!(started["arguments"]["path"], "README.md");
+
+        let finished = stream_tool_event(&rot_core::ToolEvent::Finished {
+            call_id: "tc1".to_string(),
+            exchange: ToolExchange {
+                name: "read".to_string(),
+                arguments: serde_json::json!({"path": "README.md"}),
+                output: Some("ok".to_string()),
+                is_error: false,
+                metadata: serde_json::json!({"bytes": 2}),
+            },
+        });
+        assert_eq!(finished["type"], "tool_result");
+        assert_eq!(finished["call_id"], "tc1");
+        assert_eq!(finished["output"], "ok");
+        assert_eq!(finished["metadata"]["bytes"], 2);
+    }
+
+    #[test]
+    fn test_failure_exit_codes() {
+        let timeout = Some(Duration::from_secs(30));
+        assert_eq!(
+            failure(&AgentProcessError::Cancelled, timeout),
+            (EXIT_BUDGET_EXCEEDED, "timed out after 30s".to_string())
+        );
+        assert_eq!(
+            failure(&AgentProcessError::MaxIterations(5), None).0,
+            EXIT_MAX_TURNS
+        );
+        assert_eq!(
+            failure(&AgentProcessError::Compaction("x".to_string()), None).0,
+            EXIT_FAILURE
+        );
+
+        let messages = vec![
+            Message::assistant(vec![ContentBlock::ToolCall {
+                id: "c1".to_string(),
+                name: "bash".to_string(),
+                arguments: serde_json::json!({"command": "rm -rf /"}),
+            }]),
+            Message::tool_result_with_metadata(
+                "c1",
+                "denied",
+                true,
+                serde_json::json!({"denied": true}),
+            ),
+        ];
+        assert_eq!(denied_tool(&messages).as_deref(), Some("bash"));
+        assert_eq!(denied_tool(&messages[..1]), None);
+    }
+
+    #[test]
+    fn test_pending_rlm_checkpoint_is_cleared_by_an_answer() {
+        let checkpoint = |iteration: usize| SessionEntry::RlmCheckpoint {
+            id: format!("c{iteration}"),
+            timestamp: 0,
+            state: serde_json::to_value(rot_rlm::RlmCheckpoint {
+                prompt: "count TODOs".to_string(),
+                context_path: "src".to_string(),
+                iteration,
+                history: vec![Message::user("task")],
+                next_prompt: "next".to_string(),
+                repl: rot_rlm::ReplSnapshot::default(),
+            })
+            .unwrap(),
+        };
+        let answer = SessionEntry::Message {
+            id: "a".to_string(),
+            parent_id: None,
+            timestamp: 0,
+            role: "assistant".to_string(),
+            content: serde_json::json!([]),
+        };
+
+        let mut entries = vec![checkpoint(1), checkpoint(2)];
+        let pending = pending_rlm_checkpoint(&entries).unwrap().unwrap();
+        assert_eq!(pending.iteration, 2);
+        assert_eq!(pending.context_path, "src");
+
+        entries.push(answer);
+        assert!(pending_rlm_checkpoint(&entries).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_compose_prompt_attaches_files_then_stdin() {
+        let files = vec![("src/lib.rs".to_string(), "fn f() {}".to_string())];
+        assert_eq!(
+            compose_prompt("review this", Some("+added\n"), &files),
+            "review this\n\n<file path=\"src/lib.rs\">\nfn f() {}\n</file>\n\n<stdin>\n+added\n</stdin>"
+        );
+        assert_eq!(compose_prompt("hi\n", Some("  \n"), &[]), "hi");
     }
 }
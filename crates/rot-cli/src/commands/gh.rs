@@ -0,0 +1,512 @@
+//! `rot gh`: review or fix a GitHub pull request without a terminal, for
+//! GitHub Actions and other CI.
+//!
+//! `rot gh review` reviews the pull request's diff with the review agent
+//! under a read-only sandbox and posts the findings as a pull request
+//! review. `rot gh fix` reviews it the same way, lets the build agent fix the
+//! findings in the checked-out head branch, and commits the result; the
+//! commit is pushed only with `--push`.
+//!
+//! The token comes from `GITHUB_TOKEN` (or `GH_TOKEN`), the repository from
+//! `--repo` or `GITHUB_REPOSITORY`, and the pull request from `--pr` or the
+//! Actions event that started the run.
+
+use super::exec::{create_provider, ExecExitError};
+use super::review::{self, Review, Severity};
+use rot_core::{Agent, AgentConfig, AgentRegistry, Message, RuntimeSecurityConfig};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Which pull request to work on, and the agent limits.
+#[derive(Debug, Clone)]
+pub struct GhOptions {
+    pub pr: Option<u64>,
+    /// `owner/name`.
+    pub repo: Option<String>,
+    pub max_iterations: usize,
+}
+
+/// A pull request as the REST API describes it.
+#[derive(Debug, Clone, Deserialize)]
+struct PullRequest {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    body: Option<String>,
+    head: Branch,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Branch {
+    #[serde(rename = "ref")]
+    name: String,
+    sha: String,
+}
+
+/// A GitHub REST API client for one repository.
+struct GitHub {
+    api: String,
+    token: String,
+    repo: String,
+    client: reqwest::Client,
+}
+
+impl GitHub {
+    fn from_env(repo: Option<String>) -> anyhow::Result<Self> {
+        let token = ["GITHUB_TOKEN", "GH_TOKEN"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+            .ok_or_else(|| anyhow::anyhow!("set GITHUB_TOKEN or GH_TOKEN to use the GitHub API"))?;
+        let repo = repo
+            .or_else(|| std::env::var("GITHUB_REPOSITORY").ok())
+            .filter(|repo| repo.contains('/'))
+            .ok_or_else(|| anyhow::anyhow!("pass --repo OWNER/NAME or set GITHUB_REPOSITORY"))?;
+        let api = std::env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        Ok(Self {
+            api: api.trim_end_matches('/').to_string(),
+            token,
+            repo,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}/repos/{}/{path}", self.api, self.repo))
+            .bearer_auth(&self.token)
+            .header(reqwest::header::USER_AGENT, "rot")
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> anyhow::Result<reqwest::Response> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("GitHub request failed: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("GitHub API returned {status}: {}", body.trim());
+        }
+        Ok(response)
+    }
+
+    async fn pull(&self, number: u64) -> anyhow::Result<PullRequest> {
+        let response = self
+            .send(self.request(reqwest::Method::GET, &format!("pulls/{number}")))
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    async fn diff(&self, number: u64) -> anyhow::Result<String> {
+        let request = self
+            .request(reqwest::Method::GET, &format!("pulls/{number}"))
+            .header(reqwest::header::ACCEPT, "application/vnd.github.diff");
+        Ok(self.send(request).await?.text().await?)
+    }
+
+    async fn post_review(&self, number: u64, review: &Value) -> anyhow::Result<()> {
+        let request = self
+            .request(reqwest::Method::POST, &format!("pulls/{number}/reviews"))
+            .json(review);
+        self.send(request).await.map(drop)
+    }
+
+    async fn comment(&self, number: u64, body: &str) -> anyhow::Result<()> {
+        let request = self
+            .request(reqwest::Method::POST, &format!("issues/{number}/comments"))
+            .json(&json!({ "body": body }));
+        self.send(request).await.map(drop)
+    }
+}
+
+/// Review the pull request and post the findings, or print them with
+/// `print_only`.
+pub async fn review(
+    provider_name: &str,
+    model: Option<&str>,
+    runtime_security: RuntimeSecurityConfig,
+    options: GhOptions,
+    print_only: bool,
+    fail_on: Option<Severity>,
+) -> anyhow::Result<()> {
+    let github = GitHub::from_env(options.repo)?;
+    let number = pr_number(options.pr)?;
+    let pull = github.pull(number).await?;
+    let diff = github.diff(number).await?;
+    if diff.trim().is_empty() {
+        println!("No changes to review in {}#{number}.", github.repo);
+        return Ok(());
+    }
+
+    let (reply, review) = review::review_diff(
+        provider_name,
+        model,
+        runtime_security,
+        &diff,
+        options.max_iterations,
+    )
+    .await?;
+    let Some(review) = review else {
+        println!("{}", reply.trim());
+        return Err(review::invalid_reply());
+    };
+    review::print_review(&review);
+    if !print_only {
+        let request = review_request(&review, &commentable_lines(&diff), &pull.head.sha);
+        github.post_review(number, &request).await?;
+        eprintln!("Posted review to {}#{number}.", github.repo);
+    }
+    review::check_fail_on(&review, fail_on)
+}
+
+/// Review the pull request, fix the findings in the checked-out head branch,
+/// and commit the fix; with `push`, push it and say so on the pull request.
+pub async fn fix(
+    provider_name: &str,
+    model: Option<&str>,
+    runtime_security: RuntimeSecurityConfig,
+    options: GhOptions,
+    push: bool,
+) -> anyhow::Result<()> {
+    let github = GitHub::from_env(options.repo)?;
+    let number = pr_number(options.pr)?;
+    let pull = github.pull(number).await?;
+    let head = super::git(&["rev-parse", "HEAD"])?;
+    if head.trim() != pull.head.sha {
+        anyhow::bail!(
+            "HEAD is {}, not the head of {}#{number} ({}); check out '{}' first",
+            head.trim(),
+            github.repo,
+            pull.head.sha,
+            pull.head.name
+        );
+    }
+    if !super::git(&["status", "--porcelain"])?.trim().is_empty() {
+        anyhow::bail!("the working tree has uncommitted changes");
+    }
+
+    let diff = github.diff(number).await?;
+    let (_, review) = review::review_diff(
+        provider_name,
+        model,
+        runtime_security.clone(),
+        &diff,
+        options.max_iterations,
+    )
+    .await?;
+    let review = review.ok_or_else(review::invalid_reply)?;
+    if review.findings.is_empty() {
+        println!("No findings to fix in {}#{number}.", github.repo);
+        return Ok(());
+    }
+
+    let (rot_config, tools) = super::load_tool_registry(runtime_security.clone(), false).await?;
+    let profile = AgentRegistry::resolve(Some("build"))?;
+    let config = AgentConfig {
+        agent_name: profile.name.to_string(),
+        system_prompt: Some(rot_core::project::with_instructions(
+            profile.system_prompt.to_string(),
+            &std::env::current_dir()?,
+        )),
+        max_tokens: Some(4096),
+        max_iterations: options.max_iterations,
+        ..AgentConfig::from_config(&rot_config)
+    };
+    let provider = create_provider(provider_name, model).await?;
+    let agent = Arc::new(Agent::new(provider, tools, config, runtime_security));
+    let mut messages: Vec<Message> = Vec::new();
+    agent
+        .process(&mut messages, &fix_prompt(&pull, &review))
+        .await
+        .map_err(|e| {
+            anyhow::Error::new(ExecExitError {
+                code: 1,
+                message: format!("fix failed: {e}"),
+            })
+        })?;
+
+    if super::git(&["status", "--porcelain"])?.trim().is_empty() {
+        println!("The agent made no changes.");
+        return Ok(());
+    }
+    super::git(&["add", "-A"])?;
+    let message = commit_message(&review);
+    let mut commit = Vec::new();
+    if super::git(&["config", "user.email"]).map_or(true, |email| email.trim().is_empty()) {
+        // CI checkouts often have no identity configured.
+        commit.extend([
+            "-c",
+            "user.name=rot",
+            "-c",
+            "user.email=rot@users.noreply.github.com",
+        ]);
+    }
+    commit.extend(["commit", "--quiet", "-m", message.as_str()]);
+    super::git(&commit)?;
+    let sha = super::git(&["rev-parse", "--short", "HEAD"])?
+        .trim()
+        .to_string();
+
+    if push {
+        let target = format!("HEAD:refs/heads/{}", pull.head.name);
+        super::git(&["push", "--quiet", "origin", &target])?;
+        let body = format!("Pushed {sha} to fix:\n\n{}", finding_list(&review));
+        github.comment(number, &body).await?;
+        println!("Pushed {sha} to {}.", pull.head.name);
+    } else {
+        println!(
+            "Committed {sha}; pass --push to push it to {}.",
+            pull.head.name
+        );
+    }
+    Ok(())
+}
+
+/// The pull request to work on: `explicit`, else the one in the Actions
+/// event payload, else the one in `GITHUB_REF` (`refs/pull/<n>/merge`).
+fn pr_number(explicit: Option<u64>) -> anyhow::Result<u64> {
+    explicit
+        .or_else(|| {
+            let path = std::env::var("GITHUB_EVENT_PATH").ok()?;
+            pr_from_event(&std::fs::read_to_string(path).ok()?)
+        })
+        .or_else(|| pr_from_ref(&std::env::var("GITHUB_REF").ok()?))
+        .ok_or_else(|| anyhow::anyhow!("pass --pr N, or run from a pull_request event"))
+}
+
+/// The pull request an Actions event is about: `pull_request` events, and
+/// `issue_comment` events on pull requests.
+fn pr_from_event(event: &str) -> Option<u64> {
+    let event: Value = serde_json::from_str(event).ok()?;
+    event["pull_request"]["number"].as_u64().or_else(|| {
+        let issue = &event["issue"];
+        issue.get("pull_request").and(issue["number"].as_u64())
+    })
+}
+
+fn pr_from_ref(git_ref: &str) -> Option<u64> {
+    git_ref
+        .strip_prefix("refs/pull/")?
+        .split('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Lines of the new version of each file that `diff` shows, by path.
+/// GitHub only accepts inline review comments on these.
+fn commentable_lines(diff: &str) -> HashMap<String, HashSet<u64>> {
+    let mut lines: HashMap<String, HashSet<u64>> = HashMap::new();
+    let mut file: Option<String> = None;
+    let mut in_hunk = false;
+    let mut line = 0;
+    for text in diff.lines() {
+        if text.starts_with("diff --git ") {
+            file = None;
+            in_hunk = false;
+        } else if !in_hunk {
+            if let Some(path) = text.strip_prefix("+++ ") {
+                // `/dev/null` for deleted files, which take no comments.
+                file = path.strip_prefix("b/").map(str::to_string);
+            } else if let Some(start) = hunk_start(text) {
+                line = start;
+                in_hunk = true;
+            }
+        } else if let Some(start) = hunk_start(text) {
+            line = start;
+        } else if text.starts_with('+') || text.starts_with(' ') {
+            if let Some(file) = &file {
+                lines.entry(file.clone()).or_default().insert(line);
+            }
+            line += 1;
+        }
+    }
+    lines
+}
+
+/// First new-file line of a hunk header, `@@ -a,b +c,d @@`.
+fn hunk_start(text: &str) -> Option<u64> {
+    let range = text
+        .strip_prefix("@@ ")?
+        .split(' ')
+        .find_map(|part| part.strip_prefix('+'))?;
+    range.split(',').next()?.parse().ok()
+}
+
+/// Body of a "create review" request: findings on lines in the diff become
+/// inline comments, the rest are listed in the review body.
+fn review_request(
+    review: &Review,
+    lines: &HashMap<String, HashSet<u64>>,
+    commit_id: &str,
+) -> Value {
+    let (inline, other): (Vec<_>, Vec<_>) = review.findings.iter().partition(|finding| {
+        finding.line.is_some_and(|line| {
+            lines
+                .get(&finding.file)
+                .is_some_and(|lines| lines.contains(&line))
+        })
+    });
+    let comments: Vec<Value> = inline
+        .iter()
+        .map(|finding| {
+            let mut body = format!("**{}**: {}", finding.severity.as_str(), finding.issue);
+            if !finding.suggestion.is_empty() {
+                body.push_str(&format!("\n\n{}", finding.suggestion));
+            }
+            json!({"path": finding.file, "line": finding.line, "side": "RIGHT", "body": body})
+        })
+        .collect();
+
+    let mut body = String::from("### rot review\n");
+    if !review.summary.trim().is_empty() {
+        body.push_str(&format!("\n{}\n", review.summary.trim()));
+    }
+    if review.findings.is_empty() {
+        body.push_str("\nNo findings.\n");
+    } else if !other.is_empty() {
+        let other = Review {
+            summary: String::new(),
+            findings: other.into_iter().cloned().collect(),
+        };
+        body.push_str(&format!("\n{}", finding_list(&other)));
+    }
+    json!({
+        "commit_id": commit_id,
+        "event": "COMMENT",
+        "body": body,
+        "comments": comments,
+    })
+}
+
+/// Findings as a Markdown list.
+fn finding_list(review: &Review) -> String {
+    review
+        .findings
+        .iter()
+        .map(|finding| {
+            let location = match finding.line {
+                Some(line) => format!("{}:{line}", finding.file),
+                None => finding.file.clone(),
+            };
+            format!(
+                "- `{location}` **{}**: {}\n",
+                finding.severity.as_str(),
+                finding.issue
+            )
+        })
+        .collect()
+}
+
+fn fix_prompt(pull: &PullRequest, review: &Review) -> String {
+    let description = pull.body.as_deref().unwrap_or("").trim();
+    format!(
+        "Pull request #{} \"{}\" is checked out.\n{description}\n\n\
+         A review of it found these problems:\n\n{}\n\
+         Fix them with the smallest changes that solve them, matching the surrounding code. \
+         Skip a finding that turns out to be wrong. Run the tests if the project has them. \
+         Do not commit; the changes are committed for you.",
+        pull.number,
+        pull.title,
+        serde_json::to_string_pretty(&review.findings).unwrap_or_default(),
+    )
+}
+
+fn commit_message(review: &Review) -> String {
+    let count = review.findings.len();
+    let noun = if count == 1 { "finding" } else { "findings" };
+    format!("Fix {count} review {noun}\n\n{}", finding_list(review))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::review::Finding;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,3 +10,4 @@ fn main() {
+ let a = 1;
+-let b = 2;
++let b = 3;
++let c = 4;
+ }
+@@ -40,2 +41,2 @@
+--- removed line that looks like a header
++++ added line that looks like a header
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+";
+
+    fn finding(file: &str, line: Option<u64>, severity: Severity) -> Finding {
+        Finding {
+            file: file.to_string(),
+            line,
+            severity,
+            issue: format!("issue in {file}"),
+            suggestion: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_commentable_lines_follow_hunks() {
+        let lines = commentable_lines(DIFF);
+        let mut lib: Vec<u64> = lines["src/lib.rs"].iter().copied().collect();
+        lib.sort();
+        assert_eq!(lib, vec![10, 11, 12, 13, 41]);
+        assert_eq!(lines.len(), 1);
+    }
+
+    #[test]
+    fn test_pr_number_sources() {
+        assert_eq!(
+            pr_from_event(r#"{"pull_request": {"number": 12}}"#),
+            Some(12)
+        );
+        assert_eq!(
+            pr_from_event(r#"{"issue": {"number": 7, "pull_request": {"url": "u"}}}"#),
+            Some(7)
+        );
+        assert_eq!(pr_from_event(r#"{"issue": {"number": 7}}"#), None);
+        assert_eq!(pr_from_ref("refs/pull/31/merge"), Some(31));
+        assert_eq!(pr_from_ref("refs/heads/main"), None);
+        assert_eq!(pr_number(Some(5)).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_review_request_puts_off_diff_findings_in_body() {
+        let review = Review {
+            summary: "Two problems.".to_string(),
+            findings: vec![
+                finding("src/lib.rs", Some(12), Severity::High),
+                finding("src/lib.rs", Some(90), Severity::Low),
+                finding("README.md", None, Severity::Low),
+            ],
+        };
+        let request = review_request(&review, &commentable_lines(DIFF), "abc123");
+        assert_eq!(request["commit_id"], "abc123");
+        assert_eq!(request["event"], "COMMENT");
+        let comments = request["comments"].as_array().unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0]["line"], 12);
+        assert_eq!(comments[0]["side"], "RIGHT");
+        let body = request["body"].as_str().unwrap();
+        assert!(body.contains("Two problems."));
+        assert!(body.contains("- `src/lib.rs:90` **low**"));
+        assert!(body.contains("- `README.md` **low**"));
+        assert!(!body.contains(":12`"));
+    }
+}
@@ -0,0 +1,102 @@
+//! `rot init`: write starter project instructions and config.
+
+use rot_core::config::PROJECT_CONFIG_PATH;
+use rot_core::project::{ProjectSurvey, INSTRUCTIONS_FILE};
+use std::path::Path;
+
+/// Survey the working directory and write `ROT.md` and `.rot/config.toml`.
+///
+/// `ROT.md` is drafted by one tool-free model request over the survey;
+/// with `offline` set, or when the request fails, a template built from the
+/// survey alone is written instead. Existing files are kept unless `force`.
+pub async fn run(
+    provider_name: &str,
+    model: Option<&str>,
+    offline: bool,
+    force: bool,
+) -> anyhow::Result<()> {
+    let cwd = std::env::current_dir()?;
+    let instructions_path = cwd.join(INSTRUCTIONS_FILE);
+    let config_path = cwd.join(PROJECT_CONFIG_PATH);
+    if instructions_path.exists() && config_path.exists() && !force {
+        anyhow::bail!(
+            "{INSTRUCTIONS_FILE} and {PROJECT_CONFIG_PATH} already exist; pass --force to overwrite them"
+        );
+    }
+
+    let survey = ProjectSurvey::scan(&cwd);
+    if force || !instructions_path.exists() {
+        let instructions = if offline {
+            survey.starter_instructions()
+        } else {
+            match draft_instructions(&survey, provider_name, model).await {
+                Ok(text) => text,
+                Err(e) => {
+                    eprintln!("warning: model draft failed ({e}); writing a template instead");
+                    survey.starter_instructions()
+                }
+            }
+        };
+        write_file(&instructions_path, &instructions)?;
+    }
+    if force || !config_path.exists() {
+        write_file(&config_path, &survey.starter_config())?;
+    }
+    println!("Review and edit them, then commit both files.");
+    Ok(())
+}
+
+async fn draft_instructions(
+    survey: &ProjectSurvey,
+    provider_name: &str,
+    model: Option<&str>,
+) -> anyhow::Result<String> {
+    let reply = super::complete_once(
+        provider_name,
+        model,
+        "You write project instruction files for coding agents.",
+        &survey.init_prompt(),
+    )
+    .await?;
+    let text = strip_fence(&reply);
+    if text.is_empty() {
+        anyhow::bail!("the model returned no text");
+    }
+    Ok(format!("{text}\n"))
+}
+
+/// `text` without a code fence wrapped around the whole reply.
+pub fn strip_fence(text: &str) -> String {
+    let trimmed = text.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed.to_string();
+    };
+    match (rest.split_once('\n'), rest.strip_suffix("```")) {
+        (Some((_, body)), Some(_)) => body.trim_end_matches("```").trim().to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+        .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_fence;
+
+    #[test]
+    fn test_strip_fence() {
+        assert_eq!(strip_fence("```markdown\n# Repo\n```\n"), "# Repo");
+        assert_eq!(
+            strip_fence("# Repo\n\n```bash\nmake\n```"),
+            "# Repo\n\n```bash\nmake\n```"
+        );
+    }
+}
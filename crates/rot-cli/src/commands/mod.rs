@@ -1,36 +1,117 @@
 //! Chat and exec command implementations.
 
+pub mod agent;
+pub mod audit;
+pub mod batch;
 pub mod chat;
+pub mod commit;
+pub mod completions;
 pub mod exec;
+pub mod gh;
+pub mod init;
+pub mod review;
+pub mod serve;
 pub mod tools;
+pub mod usage;
 
-use rot_core::{ConfigStore, RuntimeSecurityConfig, SandboxMode};
-use rot_tools::ToolRegistry;
+use rot_core::mcp_json::{self, McpTrustStore};
+use rot_core::{Agent, AgentConfig, ConfigStore, Message, RuntimeSecurityConfig};
+use rot_tools::{McpServerConfig, ToolRegistry};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::Arc;
 
+/// Load the effective config and build its tool registry.
+///
+/// Servers from a project `.mcp.json` are included once trusted. With
+/// `interactive` set and a terminal on stdin, the user is asked about each
+/// untrusted server; otherwise untrusted servers are skipped.
 pub async fn load_tool_registry(
     runtime_security: RuntimeSecurityConfig,
+    interactive: bool,
 ) -> anyhow::Result<(rot_core::Config, ToolRegistry)> {
     let config_store = ConfigStore::new();
-    let config = config_store.load();
-
-    let mut tools = ToolRegistry::new();
-    rot_tools::register_all(&mut tools);
-    rot_tools::register_custom_tools(&mut tools, &config.custom_tools)
-        .map_err(|e| anyhow::anyhow!("Failed to load custom tools: {e}"))?;
-    rot_tools::register_mcp_tools(
-        &mut tools,
-        &config.mcp_servers,
-        &std::env::current_dir()?,
-        match runtime_security.sandbox_mode {
-            SandboxMode::ReadOnly => rot_tools::SandboxMode::ReadOnly,
-            SandboxMode::WorkspaceWrite => rot_tools::SandboxMode::WorkspaceWrite,
-            SandboxMode::DangerFullAccess => rot_tools::SandboxMode::DangerFullAccess,
-        },
-        runtime_security.sandbox_network_access
-            || runtime_security.sandbox_mode == SandboxMode::DangerFullAccess,
-    )
-    .await
-    .map_err(|e| anyhow::anyhow!("Failed to load MCP tools: {e}"))?;
+    let mut config = config_store.load();
+    let cwd = std::env::current_dir()?;
+
+    if let Some(source) = mcp_json::project_mcp_json(&cwd) {
+        let can_prompt = interactive && std::io::stdin().is_terminal();
+        let notes = mcp_json::add_project_mcp_servers(
+            &mut config,
+            &source,
+            &McpTrustStore::new(),
+            |server| can_prompt && prompt_mcp_trust(&source, server),
+        );
+        for note in notes {
+            eprintln!("warning: {note}");
+        }
+    }
+
+    let tools = rot_core::build_tool_registry(&config, &runtime_security, &cwd).await?;
 
     Ok((config, tools))
 }
+
+/// Send one tool-free request and return the reply text.
+pub async fn complete_once(
+    provider_name: &str,
+    model: Option<&str>,
+    system_prompt: &str,
+    prompt: &str,
+) -> anyhow::Result<String> {
+    let provider = exec::create_provider(provider_name, model).await?;
+    let config = AgentConfig {
+        system_prompt: Some(system_prompt.to_string()),
+        max_tokens: Some(2048),
+        max_iterations: 1,
+        ..Default::default()
+    };
+    let agent = Arc::new(Agent::new(
+        provider,
+        ToolRegistry::new(),
+        config,
+        RuntimeSecurityConfig::default(),
+    ));
+    let mut messages: Vec<Message> = Vec::new();
+    let response = agent.process(&mut messages, prompt).await?;
+    Ok(response.text())
+}
+
+/// Run `git` with `args` in the working directory and return its stdout.
+pub fn git(args: &[&str]) -> anyhow::Result<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run git: {e}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Ask a yes/no question on the terminal; anything but `y`/`yes` is no.
+fn confirm(question: &str) -> bool {
+    eprint!("{question} [y/N] ");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+fn prompt_mcp_trust(source: &Path, server: &McpServerConfig) -> bool {
+    eprint!(
+        "{} defines MCP server '{}':\n  {} {}\nTrust and start it? [y/N] ",
+        source.display(),
+        server.name,
+        server.command,
+        server.args.join(" ")
+    );
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
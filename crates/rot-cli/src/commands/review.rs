@@ -0,0 +1,266 @@
+//! `rot review`: review a git diff with the review agent.
+
+use super::exec::{create_provider, ExecExitError};
+use rot_core::{Agent, AgentConfig, AgentRegistry, Message, RuntimeSecurityConfig, SandboxMode};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Largest diff sent to the model, in bytes.
+const MAX_DIFF_BYTES: usize = 200 * 1024;
+
+/// How serious a finding is.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// One problem found in the diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u64>,
+    pub severity: Severity,
+    pub issue: String,
+    #[serde(default)]
+    pub suggestion: String,
+}
+
+/// The review agent's verdict on a diff.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Review {
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+/// Which changes to review and how to report them.
+#[derive(Debug, Clone)]
+pub struct ReviewOptions {
+    /// Review `base...HEAD` instead of uncommitted changes.
+    pub base: Option<String>,
+    /// Review staged changes only.
+    pub staged: bool,
+    pub json: bool,
+    /// Exit with code 3 when a finding is at least this severe.
+    pub fail_on: Option<Severity>,
+    pub max_iterations: usize,
+}
+
+/// Collect the diff, review it, and print the findings.
+pub async fn run(
+    provider_name: &str,
+    model: Option<&str>,
+    runtime_security: RuntimeSecurityConfig,
+    options: ReviewOptions,
+) -> anyhow::Result<()> {
+    let diff = collect_diff(options.base.as_deref(), options.staged)?;
+    if diff.trim().is_empty() {
+        if options.json {
+            println!("{}", serde_json::to_string(&Review::default())?);
+        } else {
+            println!("No changes to review.");
+        }
+        return Ok(());
+    }
+
+    let (reply, review) = review_diff(
+        provider_name,
+        model,
+        runtime_security,
+        &diff,
+        options.max_iterations,
+    )
+    .await?;
+    let Some(review) = review else {
+        if !options.json {
+            println!("{}", reply.trim());
+        }
+        return Err(invalid_reply());
+    };
+
+    if options.json {
+        println!("{}", serde_json::to_string(&review)?);
+    } else {
+        print_review(&review);
+    }
+    check_fail_on(&review, options.fail_on)
+}
+
+/// Review `diff` with the review agent under a read-only sandbox. Returns
+/// the reply and, when it holds findings JSON, the review with the most
+/// severe findings first.
+pub(crate) async fn review_diff(
+    provider_name: &str,
+    model: Option<&str>,
+    mut runtime_security: RuntimeSecurityConfig,
+    diff: &str,
+    max_iterations: usize,
+) -> anyhow::Result<(String, Option<Review>)> {
+    // Reviewing never needs to change the workspace.
+    runtime_security.sandbox_mode = SandboxMode::ReadOnly;
+    let (rot_config, tools) = super::load_tool_registry(runtime_security.clone(), false).await?;
+    let profile = AgentRegistry::resolve(Some("review"))?;
+    let cwd = std::env::current_dir()?;
+    let config = AgentConfig {
+        agent_name: profile.name.to_string(),
+        system_prompt: Some(rot_core::project::with_instructions(
+            profile.system_prompt.to_string(),
+            &cwd,
+        )),
+        max_tokens: Some(4096),
+        max_iterations,
+        // A review only reads; it has nothing to verify.
+        verify: Default::default(),
+        ..AgentConfig::from_config(&rot_config)
+    };
+    let provider = create_provider(provider_name, model).await?;
+    let agent = Arc::new(Agent::new(provider, tools, config, runtime_security));
+
+    let mut messages: Vec<Message> = Vec::new();
+    let response = agent
+        .process(&mut messages, &review_prompt(diff))
+        .await
+        .map_err(|e| {
+            anyhow::Error::new(ExecExitError {
+                code: 1,
+                message: format!("review failed: {e}"),
+            })
+        })?;
+    let reply = response.text();
+    let review = parse_review(&reply).map(|mut review| {
+        review.findings.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then(a.file.cmp(&b.file))
+                .then(a.line.cmp(&b.line))
+        });
+        review
+    });
+    Ok((reply, review))
+}
+
+/// Exit code 2: the reply had no findings JSON.
+pub(crate) fn invalid_reply() -> anyhow::Error {
+    anyhow::Error::new(ExecExitError {
+        code: 2,
+        message: "review reply was not valid findings JSON".to_string(),
+    })
+}
+
+/// Exit code 3 when a finding is at least as severe as `fail_on`.
+pub(crate) fn check_fail_on(review: &Review, fail_on: Option<Severity>) -> anyhow::Result<()> {
+    if let Some(threshold) = fail_on {
+        let failing = review
+            .findings
+            .iter()
+            .filter(|f| f.severity >= threshold)
+            .count();
+        if failing > 0 {
+            return Err(anyhow::Error::new(ExecExitError {
+                code: 3,
+                message: format!("{failing} finding(s) at or above {}", threshold.as_str()),
+            }));
+        }
+    }
+    Ok(())
+}
+
+fn collect_diff(base: Option<&str>, staged: bool) -> anyhow::Result<String> {
+    let range = base.map(|base| format!("{base}...HEAD"));
+    let target = match (&range, staged) {
+        (_, true) => "--staged",
+        (Some(range), false) => range.as_str(),
+        (None, false) => "HEAD",
+    };
+    super::git(&["diff", "--no-color", target])
+}
+
+fn review_prompt(diff: &str) -> String {
+    let mut end = diff.len().min(MAX_DIFF_BYTES);
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    let truncated = if end < diff.len() {
+        format!("\n(diff truncated at {end} of {} bytes)", diff.len())
+    } else {
+        String::new()
+    };
+    format!(
+        "Review this diff. Read surrounding code with your tools when the diff alone is not \
+         enough. Report only real problems: bugs, regressions, unhandled edge cases, security \
+         issues, and missing tests. Reply with JSON only, in this shape:\n\
+         {{\"summary\": \"one or two sentences\", \"findings\": [{{\"file\": \"path\", \
+         \"line\": 42, \"severity\": \"high|medium|low\", \"issue\": \"what is wrong\", \
+         \"suggestion\": \"how to fix it\"}}]}}\n\
+         Use the line number in the new version of the file. An empty findings list is fine.\n\n\
+         ```diff\n{}\n```{truncated}",
+        &diff[..end]
+    )
+}
+
+/// The review JSON in `reply`, allowing a code fence or prose around it.
+fn parse_review(reply: &str) -> Option<Review> {
+    let start = reply.find('{')?;
+    let end = reply.rfind('}')?;
+    serde_json::from_str(reply.get(start..=end)?).ok()
+}
+
+pub(crate) fn print_review(review: &Review) {
+    if !review.summary.is_empty() {
+        println!("{}\n", review.summary.trim());
+    }
+    if review.findings.is_empty() {
+        println!("No findings.");
+        return;
+    }
+    for finding in &review.findings {
+        let location = match finding.line {
+            Some(line) => format!("{}:{line}", finding.file),
+            None => finding.file.clone(),
+        };
+        println!(
+            "{location} [{}] {}",
+            finding.severity.as_str(),
+            finding.issue
+        );
+        if !finding.suggestion.is_empty() {
+            println!("  suggestion: {}", finding.suggestion);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_review_from_fenced_reply() {
+        let reply = "Here you go:\n```json\n{\"summary\": \"One bug.\", \"findings\": [\
+            {\"file\": \"src/lib.rs\", \"line\": 7, \"severity\": \"high\", \
+            \"issue\": \"off by one\", \"suggestion\": \"use <=\"}]}\n```";
+        let review = parse_review(reply).unwrap();
+        assert_eq!(review.summary, "One bug.");
+        assert_eq!(review.findings[0].line, Some(7));
+        assert_eq!(review.findings[0].severity, Severity::High);
+        assert!(Severity::High > Severity::Medium);
+
+        assert!(parse_review("no problems found").is_none());
+    }
+}
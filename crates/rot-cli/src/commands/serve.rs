@@ -0,0 +1,893 @@
+//! `rot serve`: a local HTTP API for driving the agent without the TUI.
+//!
+//! Routes (request and response bodies are JSON):
+//!
+//! - `GET /health`
+//! - `GET /metrics` — request, token, spend, tool latency, and sandbox
+//!   denial metrics in the Prometheus text format
+//! - `GET /sessions` — saved sessions for the working directory
+//! - `POST /sessions` — start a session: `{"agent": "plan"}`, or resume a
+//!   saved one with `{"resume": "<id>"}`; both fields are optional
+//! - `POST /sessions/{id}/messages` — send `{"text": "..."}`; the reply is
+//!   delivered as events. An optional `"cwd"` moves the session's tools to
+//!   that directory, relative to the current one, from this message on; it
+//!   must be inside the directory the server was started in
+//! - `GET /sessions/{id}/events` — server-sent events, one JSON object per
+//!   event: `text_delta`, `thinking_delta`, `tool_call`, `tool_result`,
+//!   `usage`, `approval_request`, `response`, `error`, `cancelled`
+//! - `POST /sessions/{id}/approvals/{request_id}` — answer an approval with
+//!   `{"decision": "allow" | "allow_always" | "deny" | "deny_always"}` and
+//!   optionally edited `"arguments"`
+//! - `POST /sessions/{id}/cancel` — cancel the running reply
+//!
+//! Every route but `/health` and `/metrics` needs `Authorization: Bearer
+//! <token>`, with the token generated at startup and written to
+//! `~/.rot/serve-token`. Requests whose `Host` is not the loopback address or the `--host` the server was
+//! started with are refused, so a web page cannot reach the API through DNS
+//! rebinding.
+
+use super::exec::{create_provider, stream_tool_event, take_entries, usage_event};
+use bytes::Bytes;
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Frame, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{HeaderMap, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use rot_core::output_schema::{self, OutputSchema};
+use rot_core::permission::ApprovalResponse;
+use rot_core::{
+    Agent, AgentConfig, AgentProcessError, AgentRegistry, CancellationToken, Message,
+    RuntimeSecurityConfig,
+};
+use rot_session::{Session, SessionEntry, SessionStore};
+use rot_tools::ToolRegistry;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, oneshot};
+
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+/// Events buffered per subscriber before slow readers start missing some.
+const EVENT_BUFFER: usize = 1024;
+
+/// Settings shared by every session the server starts.
+pub struct ServeOptions {
+    pub provider: String,
+    pub model: Option<String>,
+    pub agent: Option<String>,
+    pub runtime_security: RuntimeSecurityConfig,
+    pub max_iterations: usize,
+}
+
+struct ServeState {
+    options: ServeOptions,
+    /// Bearer token every route but `/health` requires.
+    token: String,
+    /// `Host` header values accepted besides the loopback names.
+    host: String,
+    /// Agent settings from config that every session starts with.
+    agent_config: AgentConfig,
+    tools: ToolRegistry,
+    store: SessionStore,
+    cwd: PathBuf,
+    sessions: Mutex<HashMap<String, Arc<LiveSession>>>,
+}
+
+/// A session the server has open.
+struct LiveSession {
+    agent: Arc<Agent>,
+    /// Held for the length of a turn, so only one runs at a time.
+    conversation: Arc<tokio::sync::Mutex<Conversation>>,
+    events: broadcast::Sender<Value>,
+    approvals: Arc<Mutex<HashMap<String, oneshot::Sender<ApprovalResponse>>>>,
+    cancel: Mutex<Option<CancellationToken>>,
+}
+
+struct Conversation {
+    session: Session,
+    messages: Vec<Message>,
+    /// Number of `messages` already written to the session file.
+    persisted: usize,
+    /// Token usage reported since the last save.
+    usage: Arc<Mutex<Vec<SessionEntry>>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CreateSessionRequest {
+    agent: Option<String>,
+    resume: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendMessageRequest {
+    text: String,
+    cwd: Option<String>,
+    /// JSON Schema the final reply must match.
+    output_schema: Option<Value>,
+    /// Times to ask again when the reply does not match `output_schema`.
+    schema_retries: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApprovalRequest {
+    decision: String,
+    arguments: Option<Value>,
+}
+
+/// A request matched to an API route.
+#[derive(Debug, PartialEq, Eq)]
+enum Route<'a> {
+    Health,
+    Metrics,
+    ListSessions,
+    CreateSession,
+    SendMessage(&'a str),
+    Events(&'a str),
+    Approve(&'a str, &'a str),
+    Cancel(&'a str),
+}
+
+impl<'a> Route<'a> {
+    fn parse(method: &Method, path: &'a str) -> Option<Self> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let route = match (method, segments.as_slice()) {
+            (&Method::GET, ["health"]) => Route::Health,
+            (&Method::GET, ["metrics"]) => Route::Metrics,
+            (&Method::GET, ["sessions"]) => Route::ListSessions,
+            (&Method::POST, ["sessions"]) => Route::CreateSession,
+            (&Method::POST, ["sessions", id, "messages"]) => Route::SendMessage(id),
+            (&Method::GET, ["sessions", id, "events"]) => Route::Events(id),
+            (&Method::POST, ["sessions", id, "approvals", request]) => Route::Approve(id, request),
+            (&Method::POST, ["sessions", id, "cancel"]) => Route::Cancel(id),
+            _ => return None,
+        };
+        Some(route)
+    }
+}
+
+/// Serve the API on `host:port` until the process is stopped.
+pub async fn run(host: &str, port: u16, options: ServeOptions) -> anyhow::Result<()> {
+    let (config, tools) =
+        super::load_tool_registry(options.runtime_security.clone(), false).await?;
+    let token = format!("{}{}", ulid::Ulid::new(), ulid::Ulid::new()).to_lowercase();
+    let token_path = token_path();
+    write_token(&token_path, &token)
+        .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", token_path.display()))?;
+    let state = Arc::new(ServeState {
+        options,
+        token,
+        host: host.to_string(),
+        agent_config: AgentConfig::from_config(&config),
+        tools,
+        store: SessionStore::new(),
+        cwd: std::env::current_dir()?,
+        sessions: Mutex::new(HashMap::new()),
+    });
+
+    let listener = TcpListener::bind((host, port)).await?;
+    eprintln!("rot serve listening on http://{}", listener.local_addr()?);
+    eprintln!("API token written to {}", token_path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle(state.clone(), req));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("serve connection ended: {e}");
+            }
+        });
+    }
+}
+
+/// `~/.rot/serve-token`.
+fn token_path() -> PathBuf {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".rot");
+    path.push("serve-token");
+    path
+}
+
+/// Write `token` readable only by the current user.
+fn write_token(path: &Path, token: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // An existing file keeps its mode when opened, so tighten it too.
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(token.as_bytes())
+}
+
+/// Refuse requests from outside the loopback interface's names or without
+/// the API token. `None` lets the request through.
+fn authorize(state: &ServeState, path: &str, headers: &HeaderMap) -> Option<Response<Body>> {
+    let host = headers
+        .get(hyper::header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(strip_port)
+        .unwrap_or_default();
+    if !["localhost", "127.0.0.1", "[::1]", state.host.as_str()].contains(&host) {
+        return Some(error(
+            StatusCode::FORBIDDEN,
+            format!("host '{host}' is not allowed"),
+        ));
+    }
+    // Scrapers can't follow a token that changes on every start; metrics
+    // carry counts, not conversation content.
+    if matches!(path.trim_matches('/'), "health" | "metrics") {
+        return None;
+    }
+    let token = headers
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !constant_time_eq(token.as_bytes(), state.token.as_bytes()) {
+        return Some(error(
+            StatusCode::UNAUTHORIZED,
+            "missing or wrong bearer token; see ~/.rot/serve-token".to_string(),
+        ));
+    }
+    None
+}
+
+/// `host` without a trailing `:port`.
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        Some((name, port)) if !name.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+            // A bare IPv6 address has colons of its own; only bracketed ones take a port.
+            if name.contains(':') && !name.ends_with(']') {
+                host
+            } else {
+                name
+            }
+        }
+        _ => host,
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn handle(
+    state: Arc<ServeState>,
+    req: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    if let Some(refusal) = authorize(&state, &path, req.headers()) {
+        return Ok(refusal);
+    }
+    let body = match req.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            return Ok(error(
+                StatusCode::BAD_REQUEST,
+                format!("failed to read body: {e}"),
+            ))
+        }
+    };
+    Ok(route(&state, &method, &path, body).await)
+}
+
+async fn route(
+    state: &Arc<ServeState>,
+    method: &Method,
+    path: &str,
+    body: Bytes,
+) -> Response<Body> {
+    let Some(route) = Route::parse(method, path) else {
+        return error(
+            StatusCode::NOT_FOUND,
+            format!("no route for {method} {path}"),
+        );
+    };
+    match route {
+        Route::Health => ok(json!({"status": "ok"})),
+        Route::Metrics => metrics(),
+        Route::ListSessions => match state.store.list_all(&state.cwd).await {
+            Ok(sessions) => ok(json!(sessions)),
+            Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        },
+        Route::CreateSession => {
+            let request = if body.is_empty() {
+                CreateSessionRequest::default()
+            } else {
+                match serde_json::from_slice(&body) {
+                    Ok(request) => request,
+                    Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+                }
+            };
+            match create_session(state, request).await {
+                Ok(id) => ok(json!({"id": id})),
+                Err((status, message)) => error(status, message),
+            }
+        }
+        Route::SendMessage(id) => {
+            let Some(live) = live_session(state, id) else {
+                return unknown_session(id);
+            };
+            let request: SendMessageRequest = match serde_json::from_slice(&body) {
+                Ok(request) => request,
+                Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            let cwd = match request.cwd {
+                Some(cwd) => match rot_core::agent::resolve_working_dir(
+                    &state.cwd,
+                    &live.agent.working_dir(),
+                    &cwd,
+                ) {
+                    Ok(dir) => Some(dir),
+                    Err(e) => return error(StatusCode::BAD_REQUEST, format!("invalid cwd: {e}")),
+                },
+                None => None,
+            };
+            let output_schema = match request.output_schema.map(OutputSchema::new).transpose() {
+                Ok(schema) => schema,
+                Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            let retries = request
+                .schema_retries
+                .unwrap_or(output_schema::DEFAULT_RETRIES);
+            send_message(
+                state,
+                live,
+                request.text,
+                cwd,
+                output_schema.map(|schema| (schema, retries)),
+            )
+        }
+        Route::Events(id) => match live_session(state, id) {
+            Some(live) => event_stream(live.events.subscribe()),
+            None => unknown_session(id),
+        },
+        Route::Approve(id, request_id) => {
+            let Some(live) = live_session(state, id) else {
+                return unknown_session(id);
+            };
+            let request: ApprovalRequest = match serde_json::from_slice(&body) {
+                Ok(request) => request,
+                Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            let Some(response) = parse_decision(&request.decision, request.arguments) else {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    format!("unknown decision '{}'", request.decision),
+                );
+            };
+            let pending = live.approvals.lock().unwrap().remove(request_id);
+            match pending {
+                Some(tx) => {
+                    let _ = tx.send(response);
+                    ok(json!({"status": "ok"}))
+                }
+                None => error(
+                    StatusCode::NOT_FOUND,
+                    format!("no pending approval '{request_id}'"),
+                ),
+            }
+        }
+        Route::Cancel(id) => match live_session(state, id) {
+            Some(live) => {
+                let cancelled = live
+                    .cancel
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .map(|token| token.cancel());
+                ok(json!({"cancelled": cancelled.is_some()}))
+            }
+            None => unknown_session(id),
+        },
+    }
+}
+
+/// Serve only `GET /metrics` on `host:port`, in the background, for
+/// `--metrics-port`. Fails if the address can't be bound.
+pub async fn serve_metrics(host: &str, port: u16) -> anyhow::Result<()> {
+    let listener = TcpListener::bind((host, port))
+        .await
+        .map_err(|e| anyhow::anyhow!("--metrics-port {port}: {e}"))?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let service = service_fn(|req: Request<Incoming>| async move {
+                    let response =
+                        if Route::parse(req.method(), req.uri().path()) == Some(Route::Metrics) {
+                            metrics()
+                        } else {
+                            error(
+                                StatusCode::NOT_FOUND,
+                                "only /metrics is served here".to_string(),
+                            )
+                        };
+                    Ok::<_, Infallible>(response)
+                });
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    tracing::debug!("metrics connection ended: {e}");
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn metrics() -> Response<Body> {
+    Response::builder()
+        .header("content-type", "text/plain; version=0.0.4")
+        .body(full(rot_core::metrics::global().render()))
+        .unwrap()
+}
+
+fn live_session(state: &ServeState, id: &str) -> Option<Arc<LiveSession>> {
+    state.sessions.lock().unwrap().get(id).cloned()
+}
+
+async fn create_session(
+    state: &Arc<ServeState>,
+    request: CreateSessionRequest,
+) -> Result<String, (StatusCode, String)> {
+    let agent_name = request.agent.as_deref().or(state.options.agent.as_deref());
+    let profile =
+        AgentRegistry::resolve(agent_name).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let provider = create_provider(&state.options.provider, state.options.model.as_deref())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let model = provider.current_model().to_string();
+
+    let (session, messages) = match request.resume {
+        Some(id) => {
+            let session = state
+                .store
+                .load(&state.cwd, &id)
+                .await
+                .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+            let messages = rot_core::transcript::from_session_entries(&session.entries);
+            (session, messages)
+        }
+        None => {
+            let session = state
+                .store
+                .create(&state.cwd, &model, &state.options.provider)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            (session, Vec::new())
+        }
+    };
+
+    let system_prompt = if profile.name == "default" {
+        AgentRegistry::default_chat_system_prompt().to_string()
+    } else {
+        profile.system_prompt.to_string()
+    };
+    let config = AgentConfig {
+        agent_name: profile.name.to_string(),
+        system_prompt: Some(rot_core::project::with_instructions(
+            system_prompt,
+            &state.cwd,
+        )),
+        max_iterations: state.options.max_iterations,
+        ..state.agent_config.clone()
+    };
+
+    // A resumed session continues in the directory it last moved to.
+    let working_dir = Some(session.working_dir())
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(|| state.cwd.clone());
+    let (events, _) = broadcast::channel(EVENT_BUFFER);
+    let approvals: Arc<Mutex<HashMap<String, oneshot::Sender<ApprovalResponse>>>> = Arc::default();
+    let usage: Arc<Mutex<Vec<SessionEntry>>> = Arc::default();
+    let agent = build_agent(
+        Agent::new(
+            provider,
+            state.tools.clone(),
+            config,
+            state.options.runtime_security.clone(),
+        )
+        .with_session_id(session.id.clone())
+        .with_working_dir(working_dir)
+        .with_host_allowlist(rot_core::network::HostAllowlist::new(&state.cwd)),
+        &events,
+        &approvals,
+        &usage,
+        profile.name,
+    );
+
+    let id = session.id.clone();
+    let persisted = messages.len();
+    let live = Arc::new(LiveSession {
+        agent,
+        conversation: Arc::new(tokio::sync::Mutex::new(Conversation {
+            session,
+            messages,
+            persisted,
+            usage,
+        })),
+        events,
+        approvals,
+        cancel: Mutex::new(None),
+    });
+    state.sessions.lock().unwrap().insert(id.clone(), live);
+    Ok(id)
+}
+
+/// Wire the agent's callbacks to the session's event channel, and collect
+/// its usage entries, attributed to `usage_agent`, for the next save.
+fn build_agent(
+    agent: Agent,
+    events: &broadcast::Sender<Value>,
+    approvals: &Arc<Mutex<HashMap<String, oneshot::Sender<ApprovalResponse>>>>,
+    usage: &Arc<Mutex<Vec<SessionEntry>>>,
+    usage_agent: &str,
+) -> Arc<Agent> {
+    static NEXT_APPROVAL: AtomicU64 = AtomicU64::new(1);
+
+    let text_tx = events.clone();
+    let thinking_tx = events.clone();
+    let tool_tx = events.clone();
+    let usage_tx = events.clone();
+    let approval_tx = events.clone();
+    let approvals = approvals.clone();
+    let usage = usage.clone();
+    let usage_agent = usage_agent.to_string();
+    Arc::new(
+        agent
+            .on_text(Box::new(move |delta| {
+                let _ = text_tx.send(json!({"type": "text_delta", "delta": delta}));
+            }))
+            .on_thinking(Box::new(move |delta| {
+                let _ = thinking_tx.send(json!({"type": "thinking_delta", "delta": delta}));
+            }))
+            .on_tool(Box::new(move |event| {
+                let _ = tool_tx.send(stream_tool_event(event));
+            }))
+            .on_usage(Box::new(move |report| {
+                usage
+                    .lock()
+                    .unwrap()
+                    .push(rot_core::usage::usage_entry(report, &usage_agent));
+                let _ = usage_tx.send(usage_event(report));
+            }))
+            .on_approval(Box::new(move |tool, arguments| {
+                let events = approval_tx.clone();
+                let approvals = approvals.clone();
+                let tool = tool.to_string();
+                let arguments = arguments.clone();
+                Box::pin(async move {
+                    let request_id =
+                        format!("approval-{}", NEXT_APPROVAL.fetch_add(1, Ordering::Relaxed));
+                    let (tx, rx) = oneshot::channel();
+                    approvals.lock().unwrap().insert(request_id.clone(), tx);
+                    let _ = events.send(json!({
+                        "type": "approval_request",
+                        "request_id": request_id,
+                        "tool": tool,
+                        "arguments": arguments,
+                    }));
+                    rx.await.unwrap_or(ApprovalResponse::DenyOnce)
+                })
+            })),
+    )
+}
+
+/// Start a turn in the background, in `cwd` when given, holding the reply
+/// to `schema` with up to the given retries. Fails with 409 while another
+/// turn is running.
+fn send_message(
+    state: &Arc<ServeState>,
+    live: Arc<LiveSession>,
+    text: String,
+    cwd: Option<PathBuf>,
+    schema: Option<(OutputSchema, usize)>,
+) -> Response<Body> {
+    let Ok(mut conversation) = live.conversation.clone().try_lock_owned() else {
+        return error(
+            StatusCode::CONFLICT,
+            "a reply is already running".to_string(),
+        );
+    };
+    if let Some(dir) = &cwd {
+        live.agent.set_working_dir(dir.clone());
+    }
+    let cancel = CancellationToken::new();
+    *live.cancel.lock().unwrap() = Some(cancel.clone());
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        let conversation = &mut *conversation;
+        if let Some(dir) = cwd {
+            let entry = SessionEntry::CwdChange {
+                id: rot_core::MessageId::new().as_str().to_string(),
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                cwd: dir.display().to_string(),
+            };
+            if let Err(e) = state.store.append(&mut conversation.session, entry).await {
+                let _ = live.events.send(
+                    json!({"type": "error", "message": format!("Failed to save session: {e}")}),
+                );
+            }
+        }
+        let result = match &schema {
+            Some((schema, retries)) => output_schema::process_with_schema(
+                &live.agent,
+                &mut conversation.messages,
+                &text,
+                schema,
+                *retries,
+                cancel,
+            )
+            .await
+            .map(|(response, validated)| (response, Some(validated))),
+            None => live
+                .agent
+                .process_cancellable(&mut conversation.messages, &text, Vec::new(), cancel)
+                .await
+                .map(|response| (response, None)),
+        };
+        live.cancel.lock().unwrap().take();
+        // Approvals left unanswered by a cancelled turn can no longer be used.
+        live.approvals.lock().unwrap().clear();
+
+        let event = match result {
+            Ok((response, None)) => json!({"type": "response", "text": response.text()}),
+            Ok((response, Some(Ok(output)))) => {
+                json!({"type": "response", "text": response.text(), "output": output})
+            }
+            Ok((response, Some(Err(e)))) => {
+                json!({"type": "error", "message": e.to_string(), "text": response.text()})
+            }
+            Err(AgentProcessError::Cancelled) => json!({"type": "cancelled"}),
+            Err(e) => json!({"type": "error", "message": e.to_string()}),
+        };
+        if let Err(e) = persist(&state.store, conversation).await {
+            let _ = live.events.send(json!({"type": "error", "message": e}));
+        }
+        let _ = live.events.send(event);
+    });
+
+    Response::builder()
+        .status(StatusCode::ACCEPTED)
+        .header("content-type", "application/json")
+        .body(full(json!({"status": "accepted"}).to_string()))
+        .unwrap()
+}
+
+/// Append messages added since the last call to the session file.
+async fn persist(store: &SessionStore, conversation: &mut Conversation) -> Result<(), String> {
+    let new_messages =
+        &conversation.messages[conversation.persisted.min(conversation.messages.len())..];
+    let entries = rot_core::transcript::to_session_entries(new_messages)
+        .map_err(|e| format!("Failed to save conversation: {e}"))?;
+    let usage = take_entries(&conversation.usage);
+    for entry in entries.into_iter().chain(usage) {
+        store
+            .append(&mut conversation.session, entry)
+            .await
+            .map_err(|e| format!("Failed to save conversation: {e}"))?;
+    }
+    conversation.persisted = conversation.messages.len();
+    Ok(())
+}
+
+fn parse_decision(decision: &str, arguments: Option<Value>) -> Option<ApprovalResponse> {
+    let response = match (decision, arguments) {
+        ("allow", Some(arguments)) => ApprovalResponse::AllowEdited(arguments),
+        ("allow", None) => ApprovalResponse::AllowOnce,
+        ("allow_always", _) => ApprovalResponse::AllowAlways,
+        ("deny", _) => ApprovalResponse::DenyOnce,
+        ("deny_always", _) => ApprovalResponse::DenyAlways,
+        _ => return None,
+    };
+    Some(response)
+}
+
+/// One server-sent event carrying `event` as JSON.
+fn sse_frame(event: &Value) -> Bytes {
+    Bytes::from(format!("data: {event}\n\n"))
+}
+
+fn event_stream(events: broadcast::Receiver<Value>) -> Response<Body> {
+    let stream = futures::stream::unfold(events, |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => return Some((Ok(Frame::data(sse_frame(&event))), events)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Response::builder()
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .body(UnsyncBoxBody::new(StreamBody::new(stream)))
+        .unwrap()
+}
+
+fn full(body: String) -> Body {
+    UnsyncBoxBody::new(Full::new(Bytes::from(body)))
+}
+
+fn ok(value: Value) -> Response<Body> {
+    Response::builder()
+        .header("content-type", "application/json")
+        .body(full(value.to_string()))
+        .unwrap()
+}
+
+fn error(status: StatusCode, message: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(full(json!({"error": message}).to_string()))
+        .unwrap()
+}
+
+fn unknown_session(id: &str) -> Response<Body> {
+    error(StatusCode::NOT_FOUND, format!("no open session '{id}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(dir: &std::path::Path) -> Arc<ServeState> {
+        Arc::new(ServeState {
+            options: ServeOptions {
+                provider: "openai".to_string(),
+                model: None,
+                agent: None,
+                runtime_security: RuntimeSecurityConfig::default(),
+                max_iterations: 10,
+            },
+            token: "secret".to_string(),
+            host: "127.0.0.1".to_string(),
+            agent_config: AgentConfig::default(),
+            tools: ToolRegistry::new(),
+            store: SessionStore::with_dir(dir),
+            cwd: dir.to_path_buf(),
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn body_json(response: Response<Body>) -> Value {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_route_parse() {
+        assert_eq!(Route::parse(&Method::GET, "/health"), Some(Route::Health));
+        assert_eq!(Route::parse(&Method::GET, "/metrics"), Some(Route::Metrics));
+        assert_eq!(
+            Route::parse(&Method::POST, "/sessions/s1/approvals/approval-3"),
+            Some(Route::Approve("s1", "approval-3"))
+        );
+        assert_eq!(
+            Route::parse(&Method::GET, "/sessions/s1/events/"),
+            Some(Route::Events("s1"))
+        );
+        assert_eq!(Route::parse(&Method::GET, "/sessions/s1/messages"), None);
+
+        assert!(matches!(
+            parse_decision("allow", Some(json!({"path": "a"}))),
+            Some(ApprovalResponse::AllowEdited(_))
+        ));
+        assert!(parse_decision("maybe", None).is_none());
+        assert_eq!(
+            sse_frame(&json!({"type": "cancelled"})),
+            "data: {\"type\":\"cancelled\"}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_requests_need_a_local_host_and_the_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, value.parse().unwrap());
+            }
+            headers
+        };
+        let status = |path: &str, headers: HeaderMap| {
+            authorize(&state, path, &headers).map(|response| response.status())
+        };
+
+        let rebound = headers(&[
+            ("host", "attacker.example:7430"),
+            ("authorization", "Bearer secret"),
+        ]);
+        assert_eq!(
+            status("/sessions", rebound.clone()),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(status("/health", rebound), Some(StatusCode::FORBIDDEN));
+        assert_eq!(
+            status("/sessions", headers(&[])),
+            Some(StatusCode::FORBIDDEN)
+        );
+
+        assert_eq!(
+            status("/health", headers(&[("host", "localhost:7430")])),
+            None
+        );
+        assert_eq!(
+            status("/metrics", headers(&[("host", "localhost:7430")])),
+            None
+        );
+        assert_eq!(
+            status("/metrics", headers(&[("host", "attacker.example:7430")])),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            status("/sessions", headers(&[("host", "localhost:7430")])),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(
+                "/sessions",
+                headers(&[
+                    ("host", "127.0.0.1:7430"),
+                    ("authorization", "Bearer wrong")
+                ])
+            ),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(
+                "/sessions/s1/approvals/a1",
+                headers(&[("host", "[::1]:7430"), ("authorization", "Bearer secret")])
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_routes_report_missing_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+
+        let response = route(&state, &Method::GET, "/sessions", Bytes::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await, json!([]));
+
+        let response = route(
+            &state,
+            &Method::POST,
+            "/sessions/nope/messages",
+            Bytes::from(r#"{"text":"hi"}"#),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_json(response).await["error"], "no open session 'nope'");
+    }
+}
@@ -1,10 +1,14 @@
+use rot_core::permission::{PermissionAction, PermissionSystem};
 use rot_core::RuntimeSecurityConfig;
+use rot_tools::{CommandVerdict, ToolContext, ToolResult};
+use std::io::IsTerminal;
+use std::time::Duration;
 
 pub async fn run(
     name: Option<&str>,
     runtime_security: RuntimeSecurityConfig,
 ) -> anyhow::Result<()> {
-    let (_, tools) = super::load_tool_registry(runtime_security).await?;
+    let (_, tools) = super::load_tool_registry(runtime_security, true).await?;
 
     match name {
         Some(name) => print_tool_detail(&tools, name)?,
@@ -45,12 +49,107 @@ fn print_tool_detail(tools: &rot_tools::ToolRegistry, name: &str) -> anyhow::Res
     Ok(())
 }
 
+/// Run one tool with `args` (a JSON object) and print its result.
+///
+/// Tools that need approval under the permission rules or the active policy
+/// ask on the terminal and are refused when stdin is not one. Fails when the
+/// tool reports an error.
+pub async fn run_tool(
+    name: &str,
+    args: &str,
+    json: bool,
+    runtime_security: RuntimeSecurityConfig,
+) -> anyhow::Result<()> {
+    let args = parse_args(args)?;
+    let (_, tools) = super::load_tool_registry(runtime_security.clone(), true).await?;
+    let tool = tools
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown tool: {name}"))?;
+
+    let permissions = PermissionSystem::new(runtime_security.approval_policy)
+        .with_rules(&runtime_security.permissions);
+    let mut rule = permissions.rule_action(name, &args);
+    match tool.command_verdict(&args) {
+        CommandVerdict::Denied(pattern) => {
+            anyhow::bail!("'{name}' is blocked: the command matches the denied pattern '{pattern}'")
+        }
+        CommandVerdict::Allowed => rule = rule.max(Some(PermissionAction::Allow)),
+        CommandVerdict::Unlisted => {}
+    }
+    if rule == Some(PermissionAction::Deny) {
+        anyhow::bail!("'{name}' is denied by a permission rule");
+    }
+    let needs_approval = match rule {
+        Some(action) => action == PermissionAction::Ask,
+        None => permissions.requires_approval(name),
+    };
+    if needs_approval {
+        if !std::io::stdin().is_terminal() {
+            if rule.is_some() {
+                anyhow::bail!("'{name}' needs approval under a permission rule");
+            }
+            let policy = runtime_security.approval_policy.as_str();
+            anyhow::bail!(
+                "'{name}' needs approval under the {policy} policy; pass --ask-for-approval never to run it without asking"
+            );
+        }
+        if !super::confirm(&format!("Run '{name}' with {args}?")) {
+            anyhow::bail!("Denied running '{name}'");
+        }
+    }
+
+    let ctx = ToolContext {
+        working_dir: std::env::current_dir()?,
+        timeout: tools.timeout_for(name).unwrap_or(Duration::from_secs(
+            rot_core::config::DEFAULT_TOOL_TIMEOUT_SECS,
+        )),
+        sandbox_mode: runtime_security.tool_sandbox_mode(),
+        network_access: runtime_security.allows_network(),
+        dry_run: runtime_security.dry_run,
+        ..Default::default()
+    };
+    let result = tool
+        .execute(args, &ctx)
+        .await
+        .unwrap_or_else(|e| ToolResult::error(format!("Tool error: {e}")));
+
+    if json {
+        println!("{}", serde_json::to_string(&result_json(name, &result))?);
+    } else if !result.output.is_empty() {
+        println!("{}", result.output.trim_end_matches('\n'));
+    }
+    if result.is_error {
+        anyhow::bail!("'{name}' reported an error");
+    }
+    Ok(())
+}
+
+fn parse_args(args: &str) -> anyhow::Result<serde_json::Value> {
+    let value: serde_json::Value =
+        serde_json::from_str(args).map_err(|e| anyhow::anyhow!("--args is not valid JSON: {e}"))?;
+    if !value.is_object() {
+        anyhow::bail!("--args must be a JSON object");
+    }
+    Ok(value)
+}
+
+fn result_json(name: &str, result: &ToolResult) -> serde_json::Value {
+    serde_json::json!({
+        "name": name,
+        "is_error": result.is_error,
+        "output": result.output,
+        "metadata": result.metadata,
+    })
+}
+
 fn tool_kind(name: &str) -> &'static str {
     if name.starts_with("mcp__") {
         "mcp"
     } else if matches!(
         name,
         "read" | "write" | "edit" | "bash" | "glob" | "grep" | "task" | "webfetch"
+            | "resolve_conflicts"
+            | "read_more"
     ) {
         "builtin"
     } else {
@@ -60,7 +159,7 @@ fn tool_kind(name: &str) -> &'static str {
 
 #[cfg(test)]
 mod tests {
-    use super::tool_kind;
+    use super::{parse_args, tool_kind};
 
     #[test]
     fn test_tool_kind_builtin() {
@@ -76,4 +175,11 @@ mod tests {
     fn test_tool_kind_custom() {
         assert_eq!(tool_kind("echo_args"), "custom");
     }
+
+    #[test]
+    fn test_parse_args_requires_object() {
+        assert_eq!(parse_args(r#"{"path":"a"}"#).unwrap()["path"], "a");
+        assert!(parse_args("[1]").is_err());
+        assert!(parse_args("{path").is_err());
+    }
 }
@@ -0,0 +1,190 @@
+//! `rot usage`: token usage and spend across every saved session.
+
+use rot_core::usage::{self, UsageGrouping, UsageTotals};
+use rot_session::SessionStore;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// What `rot usage` groups requests by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UsageBy {
+    Day,
+    Model,
+    Project,
+}
+
+impl From<UsageBy> for UsageGrouping {
+    fn from(value: UsageBy) -> Self {
+        match value {
+            UsageBy::Day => UsageGrouping::Day,
+            UsageBy::Model => UsageGrouping::Model,
+            UsageBy::Project => UsageGrouping::Project,
+        }
+    }
+}
+
+/// Print usage recorded over the last `days` UTC days (all time when 0),
+/// as a table or as JSON.
+pub async fn run(by: UsageBy, days: u64, json: bool) -> anyhow::Result<()> {
+    let store = SessionStore::new();
+    let mut sessions = Vec::new();
+    for meta in store.list_everywhere().await? {
+        // Sessions that can no longer be read are left out, as in `session list`.
+        if let Ok(session) = store.load(Path::new(&meta.cwd), &meta.id).await {
+            sessions.push((meta.cwd, session.entries));
+        }
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let since = since(now, days);
+    let groups = usage::group_usage(
+        sessions
+            .iter()
+            .map(|(cwd, entries)| (cwd.as_str(), entries.as_slice())),
+        by.into(),
+        since.unwrap_or(0),
+    );
+    let mut rows: Vec<(String, UsageTotals)> = groups.into_iter().collect();
+    if by != UsageBy::Day {
+        rows.sort_by(|(_, a), (_, b)| {
+            b.cost
+                .unwrap_or(0.0)
+                .total_cmp(&a.cost.unwrap_or(0.0))
+                .then_with(|| b.input_tokens.cmp(&a.input_tokens))
+        });
+    }
+    let mut total = UsageTotals::default();
+    for (_, totals) in &rows {
+        total.merge(totals);
+    }
+
+    if json {
+        let report = json!({
+            "by": label(by),
+            "since": since.map(usage::utc_date),
+            "groups": rows
+                .iter()
+                .map(|(key, totals)| {
+                    let mut value = totals_json(totals);
+                    value["key"] = json!(key);
+                    value
+                })
+                .collect::<Vec<_>>(),
+            "total": totals_json(&total),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if rows.is_empty() {
+        println!("No token usage recorded.");
+    } else {
+        print!("{}", format_table(by, &rows, &total));
+    }
+    Ok(())
+}
+
+/// Start of the window covering the last `days` UTC days, today included.
+fn since(now: u64, days: u64) -> Option<u64> {
+    (days > 0).then(|| (now / 86_400).saturating_sub(days - 1) * 86_400)
+}
+
+fn label(by: UsageBy) -> &'static str {
+    match by {
+        UsageBy::Day => "day",
+        UsageBy::Model => "model",
+        UsageBy::Project => "project",
+    }
+}
+
+fn totals_json(totals: &UsageTotals) -> Value {
+    json!({
+        "requests": totals.requests,
+        "input_tokens": totals.input_tokens,
+        "output_tokens": totals.output_tokens,
+        "cost": totals.cost,
+    })
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map_or_else(|| "-".to_string(), |cost| format!("${cost:.4}"))
+}
+
+/// One row per group and a total, with columns aligned.
+fn format_table(by: UsageBy, rows: &[(String, UsageTotals)], total: &UsageTotals) -> String {
+    let header = label(by).to_uppercase();
+    let width = rows
+        .iter()
+        .map(|(key, _)| key.len())
+        .chain([header.len(), "TOTAL".len()])
+        .max()
+        .unwrap_or(0);
+    let line = |key: &str, requests: String, input: String, output: String, cost: String| {
+        format!("{key:width$}  {requests:>8}  {input:>12}  {output:>12}  {cost:>10}\n")
+    };
+    let totals_line = |key: &str, totals: &UsageTotals| {
+        line(
+            key,
+            totals.requests.to_string(),
+            totals.input_tokens.to_string(),
+            totals.output_tokens.to_string(),
+            format_cost(totals.cost),
+        )
+    };
+
+    let mut table = line(
+        &header,
+        "REQUESTS".to_string(),
+        "INPUT".to_string(),
+        "OUTPUT".to_string(),
+        "COST".to_string(),
+    );
+    for (key, totals) in rows {
+        table.push_str(&totals_line(key, totals));
+    }
+    table.push_str(&totals_line("TOTAL", total));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_since_counts_today() {
+        let now = 1_792_108_800 + 3_600;
+        assert_eq!(since(now, 0), None);
+        assert_eq!(since(now, 1), Some(1_792_108_800));
+        assert_eq!(
+            since(now, 7).map(usage::utc_date).as_deref(),
+            Some("2026-10-10")
+        );
+    }
+
+    #[test]
+    fn test_format_table_aligns_columns() {
+        let priced = UsageTotals {
+            requests: 2,
+            input_tokens: 1_000,
+            output_tokens: 50,
+            cost: Some(0.25),
+        };
+        let unpriced = UsageTotals {
+            requests: 1,
+            ..Default::default()
+        };
+        let rows = vec![
+            ("claude-sonnet-4".to_string(), priced.clone()),
+            ("local".to_string(), unpriced),
+        ];
+        let mut total = priced;
+        total.requests += 1;
+        let table = format_table(UsageBy::Model, &rows, &total);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("MODEL "));
+        assert!(lines[2].ends_with('-'));
+        assert!(lines[3].starts_with("TOTAL ") && lines[3].ends_with("$0.2500"));
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+    }
+}
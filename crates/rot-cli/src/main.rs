@@ -4,54 +4,185 @@
 
 mod cli;
 mod commands;
+mod telemetry;
 
 use clap::Parser;
-use cli::{Cli, Commands, SessionAction};
+use cli::{AgentAction, Cli, Commands, GhAction, KeysAction, SessionAction, ToolsAction};
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    // Everything after this point reads the config path from the env and the
+    // workspace from the process working directory, so set both first.
+    if let Some(path) = &cli.config {
+        let path = std::fs::canonicalize(path)
+            .map_err(|e| anyhow::anyhow!("--config {}: {e}", path.display()))?;
+        std::env::set_var(rot_core::config::CONFIG_ENV, path);
+    }
+    if let Some(dir) = &cli.cwd {
+        std::env::set_current_dir(dir)
+            .map_err(|e| anyhow::anyhow!("--cwd {}: {e}", dir.display()))?;
+    }
+    if let Some(profile) = &cli.profile {
+        // Later ConfigStore instances (TUI, tool loading) pick the profile up from the env.
+        std::env::set_var(rot_core::config::PROFILE_ENV, profile);
+    }
     let config_store = rot_core::ConfigStore::new();
+    if let Some(profile) = config_store.profile() {
+        let available = config_store.profile_names();
+        if !available.iter().any(|name| name == profile) {
+            anyhow::bail!(
+                "Unknown profile '{profile}'. Available: {}",
+                if available.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    available.join(", ")
+                }
+            );
+        }
+    }
+    // The TUI shows config warnings in the chat instead; completion output
+    // stays clean.
+    if !matches!(
+        cli.command,
+        None | Some(Commands::Chat) | Some(Commands::Complete { .. })
+    ) {
+        for warning in config_store.warnings() {
+            eprintln!("warning: {warning}");
+        }
+    }
     config_store.hydrate_env();
     let config = config_store.load();
+    let (provider, model) = cli.resolve_model(&config);
 
-    // Initialize logging
-    if cli.verbose {
-        tracing_subscriber::fmt()
-            .with_env_filter("rot=debug")
-            .init();
+    let _telemetry = telemetry::init(cli.verbose);
+    if let Some(port) = cli.metrics_port {
+        commands::serve::serve_metrics("127.0.0.1", port).await?;
     }
 
     match cli.command {
         None | Some(Commands::Chat) => {
             let security = cli.resolve_runtime_security(&config);
             commands::chat::run(
-                cli.model.as_deref(),
-                &cli.provider,
+                model.as_deref(),
+                &provider,
                 cli.agent.as_deref(),
                 security,
+                cli.resolve_max_iterations(&config),
+                None,
             )
             .await?;
         }
         Some(Commands::Exec {
             ref prompt,
+            ref prompt_flag,
+            ref files,
             rlm,
             ref context,
+            rlm_max_iterations,
+            rlm_max_stalled,
+            rlm_iteration_tokens,
+            rlm_branches,
+            ref rlm_system_prompt,
+            no_cache,
             json,
             final_json,
+            stream,
             ref output_schema,
+            schema_retries,
+            timeout,
+            verify,
+            continue_last,
+            ref resume,
+            ref batch,
+            parallel,
+            ref out,
+            max_total_tokens,
         }) => {
             let security = cli.resolve_runtime_security_for_exec(&config)?;
+            // clap drops `requires` once a conflicting prompt is given.
+            if batch.is_none()
+                && (parallel.is_some() || out.is_some() || max_total_tokens.is_some())
+            {
+                anyhow::bail!("--parallel, --out, and --max-total-tokens require --batch");
+            }
+            if let Some(batch) = batch {
+                let options = commands::batch::BatchOptions {
+                    input: batch.clone(),
+                    output: out.clone(),
+                    parallel: parallel.unwrap_or(4) as usize,
+                    max_total_tokens,
+                    max_iterations: cli.resolve_max_iterations(&config),
+                    timeout: timeout.map(Duration::from_secs),
+                    verify,
+                };
+                if let Err(err) = commands::batch::run(
+                    &provider,
+                    model.as_deref(),
+                    cli.agent.as_deref(),
+                    security,
+                    options,
+                )
+                .await
+                {
+                    if let Some(exit_err) = err.downcast_ref::<commands::exec::ExecExitError>() {
+                        eprintln!("{}", exit_err.message);
+                        telemetry::shutdown();
+                        std::process::exit(exit_err.code);
+                    }
+                    return Err(err);
+                }
+                return Ok(());
+            }
             let options = commands::exec::ExecOptions {
                 json,
                 final_json,
+                stream,
                 output_schema: output_schema.clone(),
+                schema_retries: schema_retries
+                    .map_or(rot_core::output_schema::DEFAULT_RETRIES, |n| n as usize),
+                max_iterations: cli.resolve_max_iterations(&config),
+                resume: match (continue_last, resume) {
+                    (true, _) => Some(commands::exec::ResumeFrom::Latest),
+                    (false, Some(id)) => Some(commands::exec::ResumeFrom::Id(id.clone())),
+                    (false, None) => None,
+                },
+                timeout: timeout.map(Duration::from_secs),
+                verify,
+                rlm: commands::exec::RlmOverrides {
+                    max_iterations: rlm_max_iterations.map(|n| n as usize),
+                    max_stalled_iterations: rlm_max_stalled.map(|n| n as usize),
+                    iteration_output_tokens: rlm_iteration_tokens.map(|n| n as usize),
+                    system_prompt: rlm_system_prompt
+                        .as_deref()
+                        .map(|path| {
+                            std::fs::read_to_string(path)
+                                .map_err(|e| anyhow::anyhow!("--rlm-system-prompt {path}: {e}"))
+                        })
+                        .transpose()?,
+                    branches: rlm_branches.map(|n| n as usize),
+                    no_cache,
+                },
+            };
+            let machine_output = options.machine_output();
+            let prompt = prompt_flag.as_deref().or(prompt.as_deref());
+            // A resumed RLM run keeps the prompt it was started with.
+            let prompt = if rlm && options.resume.is_some() && prompt.is_none() && files.is_empty()
+            {
+                String::new()
+            } else {
+                commands::exec::read_prompt(prompt, files)?
+            };
+            let (provider, model) = if rlm {
+                cli.resolve_rlm_model(&config)
+            } else {
+                (provider, model)
             };
-            let machine_output = options.json || options.final_json;
             if let Err(err) = commands::exec::run(
-                prompt,
-                cli.model.as_deref(),
-                &cli.provider,
+                &prompt,
+                model.as_deref(),
+                &provider,
                 cli.agent.as_deref(),
                 rlm,
                 context.as_deref(),
@@ -64,13 +195,108 @@ async fn main() -> anyhow::Result<()> {
                     if !machine_output {
                         eprintln!("{}", exit_err.message);
                     }
+                    telemetry::shutdown();
+                    std::process::exit(exit_err.code);
+                }
+                return Err(err);
+            }
+        }
+        Some(Commands::Init { offline, force }) => {
+            commands::init::run(&provider, model.as_deref(), offline, force).await?;
+        }
+        Some(Commands::Commit { yes, split }) => {
+            commands::commit::run(&provider, model.as_deref(), split, yes).await?;
+        }
+        Some(Commands::Review {
+            ref base,
+            staged,
+            json,
+            fail_on,
+        }) => {
+            let options = commands::review::ReviewOptions {
+                base: base.clone(),
+                staged,
+                json,
+                fail_on,
+                max_iterations: cli.resolve_max_iterations(&config),
+            };
+            let security = cli.resolve_runtime_security_for_exec(&config)?;
+            if let Err(err) =
+                commands::review::run(&provider, model.as_deref(), security, options).await
+            {
+                if let Some(exit_err) = err.downcast_ref::<commands::exec::ExecExitError>() {
+                    eprintln!("{}", exit_err.message);
+                    telemetry::shutdown();
+                    std::process::exit(exit_err.code);
+                }
+                return Err(err);
+            }
+        }
+        Some(Commands::Gh { ref action }) => {
+            let security = cli.resolve_runtime_security_for_exec(&config)?;
+            let max_iterations = cli.resolve_max_iterations(&config);
+            let result = match action {
+                GhAction::Review {
+                    pr,
+                    repo,
+                    no_post,
+                    fail_on,
+                } => {
+                    let options = commands::gh::GhOptions {
+                        pr: *pr,
+                        repo: repo.clone(),
+                        max_iterations,
+                    };
+                    commands::gh::review(
+                        &provider,
+                        model.as_deref(),
+                        security,
+                        options,
+                        *no_post,
+                        *fail_on,
+                    )
+                    .await
+                }
+                GhAction::Fix { pr, repo, push } => {
+                    let options = commands::gh::GhOptions {
+                        pr: *pr,
+                        repo: repo.clone(),
+                        max_iterations,
+                    };
+                    commands::gh::fix(&provider, model.as_deref(), security, options, *push).await
+                }
+            };
+            if let Err(err) = result {
+                if let Some(exit_err) = err.downcast_ref::<commands::exec::ExecExitError>() {
+                    eprintln!("{}", exit_err.message);
+                    telemetry::shutdown();
                     std::process::exit(exit_err.code);
                 }
                 return Err(err);
             }
         }
-        Some(Commands::Session { action }) => match action {
-            SessionAction::List { limit } => {
+        Some(Commands::Serve { ref host, port }) => {
+            let options = commands::serve::ServeOptions {
+                provider,
+                model,
+                agent: cli.agent.clone(),
+                runtime_security: cli.resolve_runtime_security(&config),
+                max_iterations: cli.resolve_max_iterations(&config),
+            };
+            commands::serve::run(host, port, options).await?;
+        }
+        Some(Commands::Agent { action }) => match action {
+            AgentAction::List { json } => commands::agent::list(json)?,
+            AgentAction::Show { ref name, json } => commands::agent::show(name, json)?,
+            AgentAction::Create {
+                ref name,
+                ref from,
+                force,
+            } => commands::agent::create(name, from.as_deref(), force)?,
+            AgentAction::Edit { ref name } => commands::agent::edit(name)?,
+        },
+        Some(Commands::Session { ref action }) => match *action {
+            SessionAction::List { limit, json } => {
                 let store = rot_session::SessionStore::new();
                 let cwd = std::env::current_dir()?;
                 let sessions = store
@@ -78,7 +304,13 @@ async fn main() -> anyhow::Result<()> {
                     .await
                     .map_err(|e| anyhow::anyhow!("{e}"))?;
 
-                if sessions.is_empty() {
+                if json {
+                    let mut listed = Vec::with_capacity(sessions.len());
+                    for meta in &sessions {
+                        listed.push(session_json(&store, &cwd, meta).await?);
+                    }
+                    println!("{}", serde_json::to_string_pretty(&listed)?);
+                } else if sessions.is_empty() {
                     println!("No sessions found.");
                 } else {
                     for s in &sessions {
@@ -89,20 +321,99 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
             }
-            SessionAction::Tree { id } => {
+            SessionAction::Tree { ref id, json } => {
                 let store = rot_session::SessionStore::new();
                 let cwd = std::env::current_dir()?;
                 let tree = store
                     .tree(&cwd, id.as_deref())
                     .await
                     .map_err(|e| anyhow::anyhow!("{e}"))?;
-                print_session_tree(&tree.root, &tree.focus_id, "", true, true);
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&tree)?);
+                } else {
+                    print_session_tree(&tree.root, &tree.focus_id, "", true, true);
+                }
+            }
+            SessionAction::Resume { ref id } => {
+                let security = cli.resolve_runtime_security(&config);
+                commands::chat::run(
+                    model.as_deref(),
+                    &provider,
+                    cli.agent.as_deref(),
+                    security,
+                    cli.resolve_max_iterations(&config),
+                    Some(id),
+                )
+                .await?;
+            }
+        },
+        Some(Commands::Usage { by, days, json }) => {
+            commands::usage::run(by, days, json).await?;
+        }
+        Some(Commands::Audit {
+            session,
+            tool,
+            denied,
+            limit,
+            json,
+        }) => {
+            let filter = commands::audit::AuditFilter {
+                session,
+                tool,
+                denied,
+            };
+            commands::audit::run(&config.audit.path(), &filter, limit, json)?;
+        }
+        Some(Commands::Completions { shell }) => commands::completions::completions(shell),
+        Some(Commands::Man { dir }) => commands::completions::man(dir.as_deref())?,
+        Some(Commands::Complete { values }) => commands::completions::complete(values).await,
+        Some(Commands::Keys { action }) => match action {
+            KeysAction::Set { provider, key } => {
+                let key = match key {
+                    Some(key) => key,
+                    None => {
+                        let mut buf = String::new();
+                        std::io::stdin().read_line(&mut buf)?;
+                        buf
+                    }
+                };
+                let key = key.trim();
+                if key.is_empty() {
+                    anyhow::bail!("API key is empty");
+                }
+                match config_store.save_api_key(&provider, key)? {
+                    rot_core::credentials::KeyLocation::Keyring => {
+                        println!("Saved {provider} API key to the system keyring.");
+                    }
+                    rot_core::credentials::KeyLocation::ConfigFile => {
+                        println!(
+                            "No system keyring available; saved {provider} API key to ~/.rot/config.json."
+                        );
+                    }
+                }
             }
-            SessionAction::Resume { id } => {
-                eprintln!("Session resume not yet implemented: {id}");
+            KeysAction::Migrate => {
+                let migrated = config_store.migrate_api_keys()?;
+                if migrated.is_empty() {
+                    println!("No plaintext API keys to migrate.");
+                } else {
+                    println!("Moved to system keyring: {}", migrated.join(", "));
+                }
             }
         },
-        Some(Commands::Tools { ref name }) => {
+        Some(Commands::Tools {
+            action:
+                Some(ToolsAction::Run {
+                    ref name,
+                    ref args,
+                    json,
+                }),
+            ..
+        }) => {
+            let security = cli.resolve_runtime_security(&config);
+            commands::tools::run_tool(name, args, json, security).await?;
+        }
+        Some(Commands::Tools { ref name, .. }) => {
             let security = cli.resolve_runtime_security(&config);
             commands::tools::run(name.as_deref(), security).await?;
         }
@@ -111,6 +422,30 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// A session's metadata with its recorded token usage and cost, for `--json`.
+async fn session_json(
+    store: &rot_session::SessionStore,
+    cwd: &std::path::Path,
+    meta: &rot_session::SessionMeta,
+) -> anyhow::Result<serde_json::Value> {
+    let session = store
+        .load(cwd, &meta.id)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    let usage = rot_core::usage::session_usage(
+        &session.entries,
+        meta.agent.as_deref().unwrap_or("default"),
+    );
+    let mut value = serde_json::to_value(meta)?;
+    value["usage"] = serde_json::json!({
+        "requests": usage.total.requests,
+        "input_tokens": usage.total.input_tokens,
+        "output_tokens": usage.total.output_tokens,
+    });
+    value["cost"] = serde_json::json!(usage.total.cost);
+    Ok(value)
+}
+
 fn print_session_tree(
     node: &rot_session::SessionTreeNode,
     focus_id: &str,
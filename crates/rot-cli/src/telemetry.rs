@@ -0,0 +1,102 @@
+//! Logging setup and, with the `otel` feature, span export over OTLP.
+//!
+//! The agent, providers, tools, and MCP servers report their work as
+//! `tracing` spans (`agent.run`, `agent.iteration`, `provider.request`,
+//! `tool.execute`, `mcp.call`). With `--verbose` they are logged;
+//! in an `otel` build with `OTEL_EXPORTER_OTLP_ENDPOINT` (or
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) set they are also sent to that
+//! collector over OTLP/HTTP.
+
+use tracing_subscriber::prelude::*;
+
+/// Flushes exported spans when dropped at the end of `main`.
+pub struct TelemetryGuard;
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        shutdown();
+    }
+}
+
+/// Install the global subscriber. Hold the guard until the process exits.
+pub fn init(verbose: bool) -> TelemetryGuard {
+    let log = verbose.then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_filter(tracing_subscriber::EnvFilter::new("rot=debug"))
+    });
+    let registry = tracing_subscriber::registry().with(log);
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otel::layer());
+    // Another subscriber may already be installed, as in tests.
+    let _ = registry.try_init();
+    TelemetryGuard
+}
+
+/// Send the spans still buffered; call before `std::process::exit`.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    otel::shutdown();
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use std::sync::OnceLock;
+    use tracing_subscriber::filter::{LevelFilter, Targets};
+    use tracing_subscriber::Layer;
+
+    static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+    /// The OTLP layer, when an endpoint is configured.
+    pub fn layer<S>() -> Option<impl Layer<S>>
+    where
+        S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    {
+        let configured = [
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+        ]
+        .iter()
+        .any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()));
+        if !configured {
+            return None;
+        }
+        // The exporter reads the endpoint, headers, and timeout from the
+        // standard `OTEL_EXPORTER_OTLP_*` variables.
+        let exporter = match opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()
+        {
+            Ok(exporter) => exporter,
+            Err(e) => {
+                eprintln!("warning: OpenTelemetry export disabled: {e}");
+                return None;
+            }
+        };
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                opentelemetry_sdk::Resource::builder()
+                    .with_service_name("rot")
+                    .build(),
+            )
+            .build();
+        let tracer = provider.tracer("rot");
+        let _ = PROVIDER.set(provider);
+        // Only rot's own spans; HTTP client internals would drown them out.
+        let filter = Targets::new().with_target("rot", LevelFilter::INFO);
+        Some(
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(filter),
+        )
+    }
+
+    pub fn shutdown() {
+        if let Some(provider) = PROVIDER.get() {
+            // Shutting down twice only reports that it already happened.
+            let _ = provider.shutdown();
+        }
+    }
+}
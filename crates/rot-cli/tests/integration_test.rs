@@ -28,6 +28,7 @@ impl Provider for MockProvider {
             max_output_tokens: 4096,
             supports_thinking: false,
             supports_tools: true,
+            supports_images: false,
         }]
     }
 
@@ -162,6 +163,11 @@ async fn test_tool_registry_has_all_builtin_tools() {
     assert!(names.contains(&"grep"), "Missing grep tool");
     assert!(names.contains(&"task"), "Missing task tool");
     assert!(names.contains(&"webfetch"), "Missing webfetch tool");
+    assert!(
+        names.contains(&"resolve_conflicts"),
+        "Missing resolve_conflicts tool"
+    );
+    assert!(names.contains(&"read_more"), "Missing read_more tool");
 }
 
 #[tokio::test]
@@ -171,16 +177,392 @@ async fn test_rlm_context_manager() {
     let dir = tempfile::tempdir().unwrap();
     let mut ctx = ContextManager::with_dir(dir.path());
 
-    let var = ctx
-        .store("some large content here")
-        .await
-        .unwrap();
+    let var = ctx.store("some large content here").await.unwrap();
     assert!(!var.is_empty());
 
     let loaded = ctx.load(&var).await.unwrap();
     assert_eq!(loaded, "some large content here");
 }
 
+/// Replies with the queued texts in order.
+struct ScriptedProvider(std::sync::Mutex<std::collections::VecDeque<String>>);
+
+#[async_trait]
+impl Provider for ScriptedProvider {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn models(&self) -> Vec<ModelInfo> {
+        MockProvider.models()
+    }
+
+    fn current_model(&self) -> &str {
+        "mock-model"
+    }
+
+    fn set_model(&mut self, _model: &str) -> Result<(), ProviderError> {
+        Ok(())
+    }
+
+    async fn stream(
+        &self,
+        _request: Request,
+    ) -> Result<BoxStream<'_, Result<StreamEvent, ProviderError>>, ProviderError> {
+        let delta = self.0.lock().unwrap().pop_front().unwrap_or_default();
+        let events = vec![
+            Ok(StreamEvent::TextDelta { delta }),
+            Ok(StreamEvent::Usage {
+                input: 100,
+                output: 10,
+            }),
+            Ok(StreamEvent::Done {
+                reason: StopReason::EndTurn,
+            }),
+        ];
+        Ok(stream::iter(events).boxed())
+    }
+
+    async fn complete(&self, _request: Request) -> Result<Response, ProviderError> {
+        unimplemented!("the agent streams")
+    }
+}
+
+/// Security for RLM tests: the REPL runs unsandboxed and unprompted.
+fn unsandboxed() -> RuntimeSecurityConfig {
+    RuntimeSecurityConfig {
+        approval_policy: rot_core::ApprovalPolicy::Never,
+        sandbox_mode: rot_core::SandboxMode::DangerFullAccess,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_rlm_repl_code_needs_approval() {
+    use rot_core::permission::ApprovalResponse;
+    use rot_rlm::{RlmCheckpoint, RlmConfig, RlmEngine};
+    use std::sync::{Arc, Mutex};
+
+    let security = RuntimeSecurityConfig {
+        approval_policy: rot_core::ApprovalPolicy::OnRequest,
+        ..unsandboxed()
+    };
+    let context = tempfile::NamedTempFile::new().unwrap();
+    let context = context.path().to_str().unwrap();
+    let reply = "```repl\nFINAL \"unapproved\"\n```";
+
+    // Nobody to ask: the block is refused and the model is told why.
+    let provider = ScriptedProvider(Mutex::new([reply.to_string()].into()));
+    let agent = Arc::new(Agent::new(
+        Box::new(provider),
+        ToolRegistry::new(),
+        AgentConfig::default(),
+        security.clone(),
+    ));
+    let saved: Arc<Mutex<Vec<RlmCheckpoint>>> = Arc::default();
+    let sink = saved.clone();
+    let config = RlmConfig {
+        max_iterations: 1,
+        on_checkpoint: Some(Arc::new(move |checkpoint: &RlmCheckpoint| {
+            sink.lock().unwrap().push(checkpoint.clone());
+        })),
+        ..Default::default()
+    };
+    assert!(RlmEngine::new(config, agent)
+        .process("go", context)
+        .await
+        .is_err());
+    let next_prompt = saved.lock().unwrap()[0].next_prompt.clone();
+    assert!(next_prompt.contains("not run: Cannot execute 'bash'"));
+
+    // The user may edit the code before it runs.
+    let provider = ScriptedProvider(Mutex::new([reply.to_string()].into()));
+    let agent = Agent::new(
+        Box::new(provider),
+        ToolRegistry::new(),
+        AgentConfig::default(),
+        security,
+    )
+    .on_approval(Box::new(|tool, args| {
+        assert_eq!(tool, "bash");
+        let edited = args["command"]
+            .as_str()
+            .unwrap()
+            .replace("unapproved", "edited");
+        Box::pin(
+            async move { ApprovalResponse::AllowEdited(serde_json::json!({ "command": edited })) },
+        )
+    }));
+    let answer = RlmEngine::new(RlmConfig::default(), Arc::new(agent))
+        .process("go", context)
+        .await
+        .unwrap();
+    assert_eq!(answer, "edited");
+}
+
+#[tokio::test]
+async fn test_rlm_resumes_from_checkpoint() {
+    use rot_rlm::{RlmCheckpoint, RlmConfig, RlmEngine};
+    use std::sync::{Arc, Mutex};
+
+    let replies = [
+        "```repl\nCOUNT=$(wc -l < \"$CONTEXT_FILE\")\n```",
+        "```repl\nFINAL \"lines: $COUNT\"\n```",
+    ];
+    let provider = ScriptedProvider(Mutex::new(replies.iter().map(|r| r.to_string()).collect()));
+    let agent = Arc::new(Agent::new(
+        Box::new(provider),
+        ToolRegistry::new(),
+        AgentConfig::default(),
+        unsandboxed(),
+    ));
+    let mut context = tempfile::NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut context, b"one\ntwo\n").unwrap();
+
+    // Stop after the first iteration, as an interrupted run would.
+    let saved: Arc<Mutex<Vec<RlmCheckpoint>>> = Arc::default();
+    let sink = saved.clone();
+    let config = RlmConfig {
+        max_iterations: 1,
+        on_checkpoint: Some(Arc::new(move |checkpoint: &RlmCheckpoint| {
+            sink.lock().unwrap().push(checkpoint.clone());
+        })),
+        ..Default::default()
+    };
+    let mut engine = RlmEngine::new(config, agent.clone());
+    assert!(engine
+        .process("count lines", context.path().to_str().unwrap())
+        .await
+        .is_err());
+    drop(engine);
+
+    let checkpoint = saved.lock().unwrap().pop().unwrap();
+    assert_eq!(checkpoint.iteration, 1);
+    let config = RlmConfig {
+        max_iterations: 2,
+        ..Default::default()
+    };
+    let answer = RlmEngine::new(config, agent)
+        .resume(checkpoint)
+        .await
+        .unwrap();
+    assert_eq!(answer, "lines: 2");
+}
+
+#[tokio::test]
+async fn test_rlm_stops_when_stalled_and_uses_custom_prompt() {
+    use rot_rlm::{RlmCheckpoint, RlmConfig, RlmEngine};
+    use std::sync::{Arc, Mutex};
+
+    let replies = ["Let me think.", "Still thinking."];
+    let provider = ScriptedProvider(Mutex::new(replies.iter().map(|r| r.to_string()).collect()));
+    let agent = Arc::new(Agent::new(
+        Box::new(provider),
+        ToolRegistry::new(),
+        AgentConfig::default(),
+        unsandboxed(),
+    ));
+    let context = tempfile::NamedTempFile::new().unwrap();
+    let saved: Arc<Mutex<Vec<RlmCheckpoint>>> = Arc::default();
+    let sink = saved.clone();
+    let config = RlmConfig {
+        max_stalled_iterations: Some(2),
+        system_prompt: Some("Answer in one word.".to_string()),
+        on_checkpoint: Some(Arc::new(move |checkpoint: &RlmCheckpoint| {
+            sink.lock().unwrap().push(checkpoint.clone());
+        })),
+        ..Default::default()
+    };
+    let err = RlmEngine::new(config, agent)
+        .process("summarize", context.path().to_str().unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "RLM stopped after 2 iterations without new REPL output"
+    );
+
+    let saved = saved.lock().unwrap();
+    assert_eq!(saved.len(), 1);
+    let instructions = saved[0].history[0].text();
+    assert!(instructions.contains("Answer in one word."));
+    assert!(!instructions.contains("REPL environment provides"));
+}
+
+#[tokio::test]
+async fn test_rlm_branches_merge_answers() {
+    use rot_rlm::{RlmConfig, RlmEngine, RlmPhase, RlmProgress};
+    use std::sync::{Arc, Mutex};
+
+    let scripted_agent = |replies: &[&str], task_policy: rot_core::TaskExecutionPolicy| {
+        let provider =
+            ScriptedProvider(Mutex::new(replies.iter().map(|r| r.to_string()).collect()));
+        let config = AgentConfig {
+            task_policy,
+            ..Default::default()
+        };
+        Arc::new(Agent::new(
+            Box::new(provider),
+            ToolRegistry::new(),
+            config,
+            unsandboxed(),
+        ))
+    };
+    let context = tempfile::NamedTempFile::new().unwrap();
+    let context = context.path().to_str().unwrap();
+
+    // One task slot at a time: the branches take turns, then their answers are merged.
+    let agent = scripted_agent(
+        &[
+            "```repl\nFINAL \"a\"\n```",
+            "```repl\nFINAL \"b\"\n```",
+            "a and b",
+        ],
+        Default::default(),
+    );
+    let progress: Arc<Mutex<Vec<RlmProgress>>> = Arc::default();
+    let sink = progress.clone();
+    let config = RlmConfig {
+        branches: 2,
+        on_progress: Some(Arc::new(move |event: &RlmProgress| {
+            sink.lock().unwrap().push(event.clone())
+        })),
+        ..Default::default()
+    };
+    let answer = RlmEngine::new(config, agent)
+        .process("explore", context)
+        .await
+        .unwrap();
+    assert_eq!(answer, "a and b");
+    let progress = progress.lock().unwrap().clone();
+    assert_eq!(progress[0].to_string(), "RLM BRANCH 1/2 RLM ITERATION 1/30");
+    let select = &progress[progress.len() - 2];
+    assert_eq!(select.to_string(), "RLM SELECT 2 answers");
+    // Tokens count every branch, not just the one reporting.
+    assert_eq!((select.input_tokens, select.output_tokens), (200, 20));
+    let done = progress.last().unwrap();
+    assert_eq!((done.phase, done.branch), (RlmPhase::Done, None));
+    assert_eq!(done.total_tokens(), 330);
+
+    // Without task budget the prompt runs as a single branch.
+    let agent = scripted_agent(
+        &["```repl\nFINAL \"solo\"\n```"],
+        rot_core::TaskExecutionPolicy {
+            max_total_tasks: 0,
+            ..Default::default()
+        },
+    );
+    let config = RlmConfig {
+        branches: 3,
+        ..Default::default()
+    };
+    let answer = RlmEngine::new(config, agent)
+        .process("explore", context)
+        .await
+        .unwrap();
+    assert_eq!(answer, "solo");
+}
+
+#[tokio::test]
+async fn test_rlm_reuses_cached_answer_until_context_changes() {
+    use rot_rlm::{RlmCache, RlmConfig, RlmEngine, RlmPhase, RlmProgress};
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempfile::tempdir().unwrap();
+    let context = dir.path().join("notes.txt");
+    std::fs::write(&context, "one\ntwo\n").unwrap();
+    let context = context.to_str().unwrap();
+
+    // One scripted reply: only the first run reaches the model.
+    let provider = ScriptedProvider(Mutex::new(["```repl\nFINAL \"2\"\n```".to_string()].into()));
+    let agent = Arc::new(Agent::new(
+        Box::new(provider),
+        ToolRegistry::new(),
+        AgentConfig::default(),
+        unsandboxed(),
+    ));
+    let phases: Arc<Mutex<Vec<RlmPhase>>> = Arc::default();
+    let sink = phases.clone();
+    let config = RlmConfig {
+        max_iterations: 1,
+        cache: Some(RlmCache::with_dir(dir.path().join("cache"))),
+        on_progress: Some(Arc::new(move |event: &RlmProgress| {
+            sink.lock().unwrap().push(event.phase)
+        })),
+        ..Default::default()
+    };
+    let mut engine = RlmEngine::new(config.clone(), agent.clone());
+    assert_eq!(engine.process("count lines", context).await.unwrap(), "2");
+    assert_eq!(engine.process("count lines", context).await.unwrap(), "2");
+    assert_eq!(phases.lock().unwrap().last(), Some(&RlmPhase::Cached));
+
+    // A changed context misses the cache, and the model has nothing left to say.
+    std::fs::write(context, "one\ntwo\nthree\n").unwrap();
+    assert!(engine.process("count lines", context).await.is_err());
+
+    let uncached = RlmConfig {
+        cache: None,
+        ..config
+    };
+    std::fs::write(context, "one\ntwo\n").unwrap();
+    assert!(RlmEngine::new(uncached, agent)
+        .process("count lines", context)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_rlm_map_reduces_context_larger_than_window() {
+    use rot_rlm::{RlmConfig, RlmEngine, RlmPhase, RlmProgress};
+    use std::sync::{Arc, Mutex};
+
+    // The mock model's 8192-token window fits about 32K characters, so a
+    // 40K-character context is read as three 16K chunks.
+    let dir = tempfile::tempdir().unwrap();
+    let line = "a".repeat(79) + "\n";
+    std::fs::write(dir.path().join("notes.txt"), line.repeat(500)).unwrap();
+
+    let replies = [
+        "TODO at notes.txt:3",
+        "NOTHING RELEVANT",
+        "TODO at notes.txt:480",
+        "Two TODOs.",
+    ];
+    let provider = ScriptedProvider(Mutex::new(replies.iter().map(|r| r.to_string()).collect()));
+    let agent = Arc::new(Agent::new(
+        Box::new(provider),
+        ToolRegistry::new(),
+        AgentConfig::default(),
+        unsandboxed(),
+    ));
+    let progress: Arc<Mutex<Vec<RlmProgress>>> = Arc::default();
+    let sink = progress.clone();
+    let config = RlmConfig {
+        on_progress: Some(Arc::new(move |event: &RlmProgress| {
+            sink.lock().unwrap().push(event.clone())
+        })),
+        ..Default::default()
+    };
+    let answer = RlmEngine::new(config, agent)
+        .process("find TODOs", dir.path().to_str().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(answer, "Two TODOs.");
+
+    let progress = progress.lock().unwrap();
+    let lines: Vec<String> = progress.iter().map(ToString::to_string).collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], "RLM CHUNK 1/3 (notes.txt:1 .. notes.txt:202)");
+    assert!(lines[2].starts_with("RLM CHUNK 3/3"));
+    assert_eq!(lines[3], "RLM REDUCE final");
+    assert_eq!(progress[3].input_tokens, 300);
+    assert_eq!(progress[4].phase, RlmPhase::Done);
+    assert_eq!(
+        (progress[4].input_tokens, progress[4].output_tokens),
+        (400, 40)
+    );
+}
+
 #[test]
 fn test_provider_anthropic_creation() {
     let provider = AnthropicProvider::new("test-key".to_string());
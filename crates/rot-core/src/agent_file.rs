@@ -0,0 +1,185 @@
+//! Project-defined agents in `.rot/agents/*.toml`.
+//!
+//! Each file defines one agent named after the file stem:
+//!
+//! ```toml
+//! description = "Writes and fixes database migrations."
+//! mode = "primary"
+//! model = "sonnet"
+//! tools = ["read", "grep", "edit", "bash"]
+//! system_prompt = """
+//! You are rot in migrations mode. ...
+//! """
+//! ```
+
+use crate::agent_profile::{AgentMode, AgentProfile};
+use crate::agent_registry::AgentRegistry;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory holding agent files, relative to a workspace directory.
+pub const AGENTS_DIR: &str = ".rot/agents";
+
+/// Errors reading or writing agent files.
+#[derive(Debug, thiserror::Error)]
+pub enum AgentFileError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid agent file {path}: {message}")]
+    Invalid { path: PathBuf, message: String },
+
+    #[error("invalid agent name '{0}': use letters, digits, '-' and '_'")]
+    InvalidName(String),
+}
+
+fn default_mode() -> AgentMode {
+    AgentMode::Primary
+}
+
+/// An agent defined in a project's `.rot/agents` directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentFile {
+    /// File stem; not stored in the file.
+    #[serde(skip)]
+    pub name: String,
+    /// File the agent was read from; not stored in the file.
+    #[serde(skip)]
+    pub path: PathBuf,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_mode")]
+    pub mode: AgentMode,
+    /// Model or alias to use instead of the session model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Tools the agent may call; all tools when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    pub system_prompt: String,
+}
+
+impl AgentFile {
+    /// Parse the agent file at `path`.
+    pub fn read(path: &Path) -> Result<Self, AgentFileError> {
+        let content = fs::read_to_string(path).map_err(|source| AgentFileError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut agent: Self = toml::from_str(&content).map_err(|e| AgentFileError::Invalid {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        agent.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        agent.path = path.to_path_buf();
+        Ok(agent)
+    }
+
+    /// Starter file contents for a new agent, copying `base`'s prompt.
+    pub fn scaffold(name: &str, base: &AgentProfile) -> String {
+        let mode = match base.mode {
+            AgentMode::Primary => "primary",
+            AgentMode::Subagent => "subagent",
+        };
+        format!(
+            "# Agent '{name}'; see `rot agent show {name}`.\n\
+             description = {description:?}\n\
+             # \"primary\" for direct use, \"subagent\" for delegated tasks.\n\
+             mode = \"{mode}\"\n\
+             # Model or alias to use instead of the session model.\n\
+             # model = \"sonnet\"\n\
+             # Tools the agent may call; all tools when unset.\n\
+             # tools = [\"read\", \"grep\", \"glob\", \"edit\", \"bash\"]\n\
+             system_prompt = \"\"\"\n{prompt}\n\"\"\"\n",
+            description = base.description,
+            prompt = base.system_prompt,
+        )
+    }
+}
+
+/// Check that `name` is usable as an agent file stem.
+pub fn validate_name(name: &str) -> Result<(), AgentFileError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(AgentFileError::InvalidName(name.to_string()))
+    }
+}
+
+/// The nearest `.rot/agents` directory at or above `cwd`.
+pub fn find_agents_dir(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(AGENTS_DIR))
+        .find(|dir| dir.is_dir())
+}
+
+/// Agents defined for the project containing `cwd`, sorted by name.
+pub fn load_project_agents(cwd: &Path) -> Result<Vec<AgentFile>, AgentFileError> {
+    let Some(dir) = find_agents_dir(cwd) else {
+        return Ok(Vec::new());
+    };
+    let entries = fs::read_dir(&dir).map_err(|source| AgentFileError::Read {
+        path: dir.clone(),
+        source,
+    })?;
+    let mut agents = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .map(|path| AgentFile::read(&path))
+        .collect::<Result<Vec<_>, _>>()?;
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(agents)
+}
+
+/// Whether `name` is taken by a built-in agent.
+pub fn is_builtin(name: &str) -> bool {
+    AgentRegistry::get(name).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let agents_dir = dir.path().join(AGENTS_DIR);
+        fs::create_dir_all(&agents_dir).unwrap();
+        let base = AgentRegistry::get("review").unwrap();
+        fs::write(
+            agents_dir.join("strict-review.toml"),
+            AgentFile::scaffold("strict-review", &base),
+        )
+        .unwrap();
+        fs::write(agents_dir.join("notes.md"), "ignored").unwrap();
+
+        let nested = dir.path().join("src");
+        fs::create_dir_all(&nested).unwrap();
+        let agents = load_project_agents(&nested).unwrap();
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].name, "strict-review");
+        assert_eq!(agents[0].mode, AgentMode::Subagent);
+        assert_eq!(agents[0].description, base.description);
+        assert_eq!(agents[0].system_prompt.trim(), base.system_prompt);
+        assert_eq!(agents[0].tools, None);
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("db_migrations-2").is_ok());
+        assert!(validate_name("../escape").is_err());
+        assert!(validate_name("").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Append-only audit log of tool calls, kept apart from sessions.
+//!
+//! Each tool call the model makes is one JSON line in the log: when, in which
+//! session and agent, the tool and a hash of its arguments, the sandbox mode,
+//! how the call was approved, how it ended, and the files it named. Sessions
+//! can be deleted or compacted; the log is only ever appended to. `rot audit`
+//! reads it back.
+
+use crate::security::SandboxMode;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Log used when `audit.path` is unset: `~/.local/share/rot/audit.jsonl`.
+pub fn default_audit_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rot")
+        .join("audit.jsonl")
+}
+
+/// Errors reading or writing the audit log.
+#[derive(Debug, thiserror::Error)]
+pub enum AuditError {
+    #[error("failed to access audit log {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid audit record at {path}:{line}: {message}")]
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+/// How a tool call got past, or was stopped by, the approval policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    /// The policy allows the tool without asking.
+    Auto,
+    /// The user allowed this call.
+    Approved,
+    /// The user allowed the tool for the rest of the session.
+    ApprovedAlways,
+    /// The user allowed the call with edited arguments.
+    Edited,
+    /// The user refused this call.
+    Denied,
+    /// The user refused the tool for the rest of the session.
+    DeniedAlways,
+    /// Refused without asking: denied earlier in the session, or no one
+    /// was there to ask.
+    Blocked,
+}
+
+impl ApprovalDecision {
+    /// The decision as written in the log.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Approved => "approved",
+            Self::ApprovedAlways => "approved_always",
+            Self::Edited => "edited",
+            Self::Denied => "denied",
+            Self::DeniedAlways => "denied_always",
+            Self::Blocked => "blocked",
+        }
+    }
+
+    /// Whether the call was allowed to run.
+    pub fn allowed(self) -> bool {
+        matches!(
+            self,
+            Self::Auto | Self::Approved | Self::ApprovedAlways | Self::Edited
+        )
+    }
+}
+
+/// One tool call in the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub session_id: String,
+    pub agent: String,
+    pub tool: String,
+    pub call_id: String,
+    /// BLAKE3 hash of the arguments the tool ran with, as compact JSON.
+    pub args_hash: String,
+    pub sandbox_mode: SandboxMode,
+    pub dry_run: bool,
+    pub approval: ApprovalDecision,
+    /// Whether the tool ran; refused calls don't.
+    pub executed: bool,
+    pub is_error: bool,
+    /// Exit status, for tools that report one such as `bash`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Files named in the arguments (`path`, `file_path`, `paths`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+
+/// How a tool call that ran ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolOutcome {
+    pub is_error: bool,
+    pub exit_code: Option<i64>,
+    pub duration_ms: Option<u64>,
+}
+
+impl ToolOutcome {
+    /// Read the outcome from a tool result and its metadata.
+    pub fn from_result(is_error: bool, metadata: &serde_json::Value) -> Self {
+        Self {
+            is_error,
+            exit_code: metadata
+                .get("exit_code")
+                .and_then(serde_json::Value::as_i64),
+            duration_ms: metadata
+                .get("duration_ms")
+                .and_then(serde_json::Value::as_u64),
+        }
+    }
+}
+
+/// Hash of a tool call's arguments. Object keys are sorted, so equal
+/// arguments hash the same however they were written.
+pub fn args_hash(args: &serde_json::Value) -> String {
+    blake3::hash(args.to_string().as_bytes())
+        .to_hex()
+        .to_string()
+}
+
+/// Files a tool call names in its arguments.
+pub fn touched_files(args: &serde_json::Value) -> Vec<String> {
+    let mut files: Vec<String> = ["path", "file_path"]
+        .iter()
+        .filter_map(|key| args.get(key).and_then(|value| value.as_str()))
+        .map(str::to_string)
+        .collect();
+    if let Some(paths) = args.get("paths").and_then(|value| value.as_array()) {
+        files.extend(
+            paths
+                .iter()
+                .filter_map(|path| path.as_str())
+                .map(str::to_string),
+        );
+    }
+    files
+}
+
+/// An audit log file.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add `record` to the end of the log, creating the file if needed.
+    pub fn append(&self, record: &AuditRecord) -> Result<(), AuditError> {
+        let io_error = |source| AuditError::Io {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let mut line = serde_json::to_string(record).map_err(|e| io_error(e.into()))?;
+        line.push('\n');
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error)?;
+        // One write per record, so concurrent rot processes don't interleave lines.
+        file.write_all(line.as_bytes()).map_err(io_error)
+    }
+
+    /// Every record in the log, oldest first; none when there is no log yet.
+    pub fn read(&self) -> Result<Vec<AuditRecord>, AuditError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => {
+                return Err(AuditError::Io {
+                    path: self.path.clone(),
+                    source,
+                })
+            }
+        };
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| AuditError::Invalid {
+                    path: self.path.clone(),
+                    line: index + 1,
+                    message: e.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tool: &str, args: &serde_json::Value) -> AuditRecord {
+        AuditRecord {
+            timestamp: 1_792_108_800,
+            session_id: "s1".to_string(),
+            agent: "build".to_string(),
+            tool: tool.to_string(),
+            call_id: "tc1".to_string(),
+            args_hash: args_hash(args),
+            sandbox_mode: SandboxMode::WorkspaceWrite,
+            dry_run: false,
+            approval: ApprovalDecision::Auto,
+            executed: true,
+            is_error: false,
+            exit_code: None,
+            duration_ms: Some(3),
+            files: touched_files(args),
+        }
+    }
+
+    #[test]
+    fn test_audit_log_appends_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(dir.path().join("nested").join("audit.jsonl"));
+        assert!(log.read().unwrap().is_empty());
+
+        let edit = record(
+            "edit",
+            &serde_json::json!({"path": "src/lib.rs", "old_string": "a"}),
+        );
+        let bash = AuditRecord {
+            approval: ApprovalDecision::Denied,
+            executed: false,
+            ..record("bash", &serde_json::json!({"command": "rm -rf target"}))
+        };
+        log.append(&edit).unwrap();
+        log.append(&bash).unwrap();
+        assert_eq!(log.read().unwrap(), vec![edit.clone(), bash]);
+        assert_eq!(edit.files, ["src/lib.rs"]);
+
+        let raw = std::fs::read_to_string(log.path()).unwrap();
+        assert!(!raw.contains("old_string"), "arguments are only hashed");
+    }
+
+    #[test]
+    fn test_args_hash_ignores_key_order() {
+        let a: serde_json::Value = serde_json::from_str(r#"{"path":"a","limit":1}"#).unwrap();
+        let b: serde_json::Value = serde_json::from_str(r#"{"limit":1,"path":"a"}"#).unwrap();
+        assert_eq!(args_hash(&a), args_hash(&b));
+        assert_ne!(
+            args_hash(&a),
+            args_hash(&serde_json::json!({"path": "b", "limit": 1}))
+        );
+    }
+}
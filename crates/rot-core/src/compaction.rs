@@ -0,0 +1,118 @@
+//! Summarizing older turns to free context window space.
+//!
+//! [`Agent::compact`](crate::Agent::compact) asks the model for a summary of
+//! everything before the most recent turns, then replaces those messages
+//! with a single [`summary_message`].
+
+use crate::message::{ContentBlock, Message, Role};
+use rot_provider::ToolResultPart;
+
+/// Instruction sent after the transcript being summarized.
+pub const SUMMARY_PROMPT: &str = "Summarize the conversation so far so it can replace the \
+full transcript. Keep the user's goals and constraints, decisions made, files read or changed \
+(with paths), commands run and their outcomes, and any open questions or next steps. Be \
+concise; reply with the summary only.";
+
+/// Opening line of the message that stands in for compacted turns.
+const SUMMARY_HEADER: &str = "Summary of the earlier conversation:";
+
+/// Outcome of a compaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compaction {
+    pub summary: String,
+    /// Messages replaced by the summary.
+    pub removed_messages: usize,
+    /// Id of the first message kept as is, if any were kept.
+    pub first_kept_id: Option<String>,
+    /// Estimated conversation size before and after, in tokens.
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+impl Compaction {
+    /// Estimated tokens freed.
+    pub fn reclaimed(&self) -> usize {
+        self.tokens_before.saturating_sub(self.tokens_after)
+    }
+}
+
+/// The user message that replaces compacted turns.
+pub fn summary_message(summary: &str) -> Message {
+    Message::user(format!("{SUMMARY_HEADER}\n\n{}", summary.trim()))
+}
+
+/// Index of the first message to keep so the last `keep_turns` user turns
+/// stay intact, or `None` when there is nothing older to compact.
+pub fn split_point(messages: &[Message], keep_turns: usize) -> Option<usize> {
+    let prompts = crate::message::user_prompt_indices(messages);
+    let split = if keep_turns == 0 {
+        messages.len()
+    } else {
+        *prompts.get(prompts.len().checked_sub(keep_turns)?)?
+    };
+    (split > 0).then_some(split)
+}
+
+/// Rough token count of `messages`, at about four characters per token.
+pub fn estimate_tokens(messages: &[Message]) -> usize {
+    let chars: usize = messages
+        .iter()
+        .flat_map(|message| &message.content)
+        .map(|block| match block {
+            ContentBlock::Text { text } => text.len(),
+            ContentBlock::Thinking { thinking, .. } => thinking.len(),
+            ContentBlock::ToolCall {
+                name, arguments, ..
+            } => name.len() + arguments.to_string().len(),
+            ContentBlock::ToolResult { content, parts, .. } => {
+                content.len()
+                    + parts
+                        .iter()
+                        .map(|part| match part {
+                            ToolResultPart::Json { .. } => part.to_text().len(),
+                            ToolResultPart::Image { .. } => 4 * 1_000,
+                        })
+                        .sum::<usize>()
+            }
+            // Providers bill images by size, not by their base64 text.
+            ContentBlock::Image { .. } => 4 * 1_000,
+        })
+        .sum();
+    chars / 4
+}
+
+/// Whether `message` is a summary written by a previous compaction.
+pub fn is_summary_message(message: &Message) -> bool {
+    message.role == Role::User && message.text().starts_with(SUMMARY_HEADER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(prompt: &str) -> Vec<Message> {
+        vec![
+            Message::user(prompt),
+            Message::assistant(vec![ContentBlock::Text {
+                text: format!("reply to {prompt}"),
+            }]),
+        ]
+    }
+
+    #[test]
+    fn test_split_point_keeps_recent_turns() {
+        let messages: Vec<Message> = ["a", "b", "c"].into_iter().flat_map(turn).collect();
+        assert_eq!(split_point(&messages, 2), Some(2));
+        assert_eq!(split_point(&messages, 0), Some(6));
+        assert_eq!(split_point(&messages, 3), None);
+        assert_eq!(split_point(&messages, 4), None);
+    }
+
+    #[test]
+    fn test_summary_message_round_trips() {
+        let message = summary_message("  user is fixing the parser\n");
+        assert!(is_summary_message(&message));
+        assert!(message.text().ends_with("user is fixing the parser"));
+        assert!(!is_summary_message(&Message::user("hello")));
+    }
+}
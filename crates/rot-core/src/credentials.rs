@@ -0,0 +1,124 @@
+//! API key storage in the operating system keyring.
+//!
+//! Keys saved through [`crate::ConfigStore::save_api_key`] go to the macOS
+//! Keychain, Secret Service, or Windows Credential Manager when one is
+//! available, and fall back to plaintext `api_keys` in the config file
+//! otherwise.
+
+use std::sync::Arc;
+
+/// Keyring service name under which provider keys are stored.
+pub const KEYRING_SERVICE: &str = "rot";
+
+/// Error from a secret store backend.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct SecretStoreError(pub String);
+
+/// Backend that holds provider API keys outside the config file.
+pub trait SecretStore: Send + Sync {
+    /// Read the key for `provider`, or `None` if no entry exists.
+    fn get(&self, provider: &str) -> Result<Option<String>, SecretStoreError>;
+    /// Store or replace the key for `provider`.
+    fn set(&self, provider: &str, key: &str) -> Result<(), SecretStoreError>;
+    /// Remove the key for `provider`. Missing entries are not an error.
+    fn delete(&self, provider: &str) -> Result<(), SecretStoreError>;
+}
+
+/// The platform keyring.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsKeyring;
+
+impl OsKeyring {
+    fn entry(provider: &str) -> Result<keyring::Entry, SecretStoreError> {
+        keyring::Entry::new(KEYRING_SERVICE, provider).map_err(|e| SecretStoreError(e.to_string()))
+    }
+}
+
+impl SecretStore for OsKeyring {
+    fn get(&self, provider: &str) -> Result<Option<String>, SecretStoreError> {
+        match Self::entry(provider)?.get_password() {
+            Ok(key) => Ok(Some(key)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(SecretStoreError(e.to_string())),
+        }
+    }
+
+    fn set(&self, provider: &str, key: &str) -> Result<(), SecretStoreError> {
+        Self::entry(provider)?
+            .set_password(key)
+            .map_err(|e| SecretStoreError(e.to_string()))
+    }
+
+    fn delete(&self, provider: &str) -> Result<(), SecretStoreError> {
+        match Self::entry(provider)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(SecretStoreError(e.to_string())),
+        }
+    }
+}
+
+/// Where a saved API key ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyLocation {
+    /// Stored in the OS keyring.
+    Keyring,
+    /// Stored in plaintext in `~/.rot/config.json` because no keyring was usable.
+    ConfigFile,
+}
+
+pub(crate) fn default_store() -> Arc<dyn SecretStore> {
+    Arc::new(OsKeyring)
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::{SecretStore, SecretStoreError};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// In-memory store; `available: false` simulates a machine without a keyring.
+    pub struct MemoryStore {
+        pub available: bool,
+        pub keys: Mutex<HashMap<String, String>>,
+    }
+
+    impl MemoryStore {
+        pub fn new(available: bool) -> Self {
+            Self {
+                available,
+                keys: Mutex::new(HashMap::new()),
+            }
+        }
+
+        fn check(&self) -> Result<(), SecretStoreError> {
+            if self.available {
+                Ok(())
+            } else {
+                Err(SecretStoreError("no keyring".to_string()))
+            }
+        }
+    }
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, provider: &str) -> Result<Option<String>, SecretStoreError> {
+            self.check()?;
+            Ok(self.keys.lock().unwrap().get(provider).cloned())
+        }
+
+        fn set(&self, provider: &str, key: &str) -> Result<(), SecretStoreError> {
+            self.check()?;
+            self.keys
+                .lock()
+                .unwrap()
+                .insert(provider.to_string(), key.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, provider: &str) -> Result<(), SecretStoreError> {
+            self.check()?;
+            self.keys.lock().unwrap().remove(provider);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Providers declared in config rather than compiled in.
+//!
+//! A `[providers.<name>]` entry points one of the built-in wire protocols at
+//! a different endpoint, such as a self-hosted vLLM or TGI gateway.
+
+use rot_provider::{
+    AnthropicProvider, ModelInfo, OpenAiCompatConfig, OpenAiCompatProvider, Provider, ProviderError,
+};
+use serde::{Deserialize, Serialize};
+
+/// Providers compiled into `rot`. A custom provider cannot take one of these
+/// names, since it would receive that provider's API key.
+pub const BUILTIN_PROVIDERS: &[&str] = &["anthropic", "zai", "openai", "openrouter"];
+
+/// Wire protocol spoken by a custom provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProviderKind {
+    /// OpenAI chat completions API.
+    OpenaiCompat,
+    /// Anthropic Messages API.
+    Anthropic,
+}
+
+/// A model offered by a custom provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomModelConfig {
+    /// Model identifier sent to the endpoint.
+    pub id: String,
+    /// Display name; defaults to `id`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Context window in tokens.
+    #[serde(default = "default_context_window")]
+    pub context_window: usize,
+    /// Maximum output tokens.
+    #[serde(default = "default_max_output_tokens")]
+    pub max_output_tokens: usize,
+    /// Whether the model accepts tool definitions.
+    #[serde(default = "default_true")]
+    pub supports_tools: bool,
+    /// Whether the model supports extended thinking.
+    #[serde(default)]
+    pub supports_thinking: bool,
+    /// Whether the model accepts image input.
+    #[serde(default)]
+    pub supports_images: bool,
+}
+
+fn default_context_window() -> usize {
+    128_000
+}
+
+fn default_max_output_tokens() -> usize {
+    4_096
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A provider declared under `providers` in config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomProviderConfig {
+    /// Wire protocol.
+    pub kind: ProviderKind,
+    /// API base URL, e.g. `http://localhost:8000/v1`.
+    pub base_url: String,
+    /// Environment variable holding the API key. Defaults to `<NAME>_API_KEY`.
+    /// A missing key is sent as empty, which most self-hosted gateways accept.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// Models served by the endpoint. The first is the default.
+    #[serde(default)]
+    pub models: Vec<CustomModelConfig>,
+}
+
+impl CustomProviderConfig {
+    /// Environment variable the API key is read from.
+    pub fn api_key_env(&self, name: &str) -> String {
+        self.api_key_env
+            .clone()
+            .unwrap_or_else(|| format!("{}_API_KEY", name.to_uppercase().replace('-', "_")))
+    }
+
+    /// Model metadata in provider form.
+    pub fn model_infos(&self) -> Vec<ModelInfo> {
+        self.models
+            .iter()
+            .map(|m| ModelInfo {
+                id: m.id.clone(),
+                name: m.name.clone().unwrap_or_else(|| m.id.clone()),
+                context_window: m.context_window,
+                max_output_tokens: m.max_output_tokens,
+                supports_thinking: m.supports_thinking,
+                supports_tools: m.supports_tools,
+                supports_images: m.supports_images,
+            })
+            .collect()
+    }
+
+    /// Create the provider registered as `name`, optionally selecting `model`.
+    pub fn build(
+        &self,
+        name: &str,
+        model: Option<&str>,
+    ) -> Result<Box<dyn Provider>, ProviderError> {
+        let models = self.model_infos();
+        let Some(default_model) = models.first().map(|m| m.id.clone()) else {
+            return Err(ProviderError::InvalidModel(format!(
+                "provider '{name}' declares no models"
+            )));
+        };
+        let api_key = std::env::var(self.api_key_env(name)).unwrap_or_default();
+
+        let mut provider: Box<dyn Provider> = match self.kind {
+            ProviderKind::OpenaiCompat => Box::new(OpenAiCompatProvider::new(OpenAiCompatConfig {
+                base_url: self.base_url.trim_end_matches('/').to_string(),
+                api_key,
+                provider_name: name.to_string(),
+                default_model,
+                models,
+            })),
+            ProviderKind::Anthropic => Box::new(
+                AnthropicProvider::new(api_key)
+                    .with_base_url(self.base_url.trim_end_matches('/'))
+                    .with_name(name)
+                    .with_models(models),
+            ),
+        };
+        if let Some(model) = model {
+            provider.set_model(model)?;
+        }
+        Ok(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_provider_from_toml() {
+        let config: crate::Config = serde_json::to_value(
+            toml::from_str::<toml::Value>(
+                r#"
+                [providers.vllm]
+                kind = "openai-compat"
+                base_url = "http://localhost:8000/v1/"
+                models = [
+                    { id = "qwen2.5-coder", context_window = 32768 },
+                    { id = "llama-3.1-70b" },
+                ]
+                "#,
+            )
+            .unwrap(),
+        )
+        .and_then(serde_json::from_value)
+        .unwrap();
+
+        let vllm = &config.providers["vllm"];
+        assert_eq!(vllm.kind, ProviderKind::OpenaiCompat);
+        assert_eq!(vllm.api_key_env("vllm"), "VLLM_API_KEY");
+
+        let provider = vllm.build("vllm", Some("llama-3.1-70b")).unwrap();
+        assert_eq!(provider.name(), "vllm");
+        assert_eq!(provider.current_model(), "llama-3.1-70b");
+        assert_eq!(provider.models()[0].context_window, 32_768);
+        assert!(vllm.build("vllm", Some("gpt-4o")).is_err());
+    }
+
+    #[test]
+    fn test_custom_provider_requires_models() {
+        let config = CustomProviderConfig {
+            kind: ProviderKind::Anthropic,
+            base_url: "https://gateway.internal".to_string(),
+            api_key_env: Some("GATEWAY_KEY".to_string()),
+            models: Vec::new(),
+        };
+        assert!(config.build("gateway", None).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+//! Files changed outside the agent since it last read or wrote them.
+//!
+//! With `watch_files` on, the agent stamps each file it reads, writes, or
+//! edits with its size and modification time, and a
+//! [`notify::RecommendedWatcher`] on the file's directory marks it whenever
+//! the file system reports an event for it. Changes are collected before
+//! every batch of tool calls, and afterwards only the files the batch named
+//! are stamped again, so changes made by its own tools don't count while an
+//! edit made elsewhere during the batch still does. Tools that may write
+//! anywhere, such as `bash` and subagents, renew the stamp of every marked
+//! file. Before the next provider request, any marked file whose stamp no
+//! longer matches was changed by someone else, such as the user in their
+//! editor; the agent is told so it reads the file again instead of
+//! overwriting the edit from a stale copy.
+//!
+//! Events only say which files to look at: a file is reported once its stamp
+//! differs, so events for the agent's own writes that arrive late are
+//! ignored. Directories are watched rather than files so editors that save
+//! by replacing the file are still seen.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Files tracked at most; later ones are not watched.
+const MAX_FILES: usize = 500;
+/// Files named in one note; the rest are counted.
+const MAX_NOTED: usize = 20;
+
+/// Size and modification time of a file, or `None` once it is gone.
+type Stamp = Option<(u64, Option<SystemTime>)>;
+
+async fn stamp(path: &Path) -> Stamp {
+    tokio::fs::metadata(path)
+        .await
+        .ok()
+        .map(|metadata| (metadata.len(), metadata.modified().ok()))
+}
+
+/// How a watched file changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub deleted: bool,
+}
+
+/// A watched file, keyed by the path events report for it.
+struct Watched {
+    /// The path the agent used.
+    path: PathBuf,
+    /// The stamp the agent last saw.
+    seen: Stamp,
+}
+
+#[derive(Default)]
+struct State {
+    files: HashMap<PathBuf, Watched>,
+    /// Directories already handed to the watcher.
+    dirs: HashSet<PathBuf>,
+    /// Files with events since they were last stamped.
+    marked: HashSet<PathBuf>,
+    /// Changes collected but not yet reported.
+    pending: Vec<FileChange>,
+}
+
+/// Files the agent has seen, with the stamp it last saw them at.
+pub struct FileWatch {
+    /// Shared with the watcher's event handler.
+    state: Arc<Mutex<State>>,
+    watcher: Mutex<RecommendedWatcher>,
+}
+
+impl FileWatch {
+    /// Start a watcher with nothing watched yet.
+    pub fn new() -> notify::Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let events = Arc::clone(&state);
+        let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if event.kind.is_access() {
+                return;
+            }
+            let mut state = events.lock().unwrap();
+            for path in event.paths {
+                if state.files.contains_key(&path) {
+                    state.marked.insert(path);
+                }
+            }
+        })?;
+        Ok(Self {
+            state,
+            watcher: Mutex::new(watcher),
+        })
+    }
+
+    /// Start watching `path`, or take its current state as seen.
+    pub async fn track(&self, path: PathBuf) {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        // Events name files under the directory's canonical path.
+        let Ok(dir) = tokio::fs::canonicalize(parent).await else {
+            return;
+        };
+        let key = dir.join(name);
+        let seen = stamp(&path).await;
+        let new_dir = {
+            let mut state = self.state.lock().unwrap();
+            if state.files.len() >= MAX_FILES && !state.files.contains_key(&key) {
+                return;
+            }
+            state.files.insert(key, Watched { path, seen });
+            state.dirs.insert(dir.clone())
+        };
+        // Not under the state lock: the event handler takes it, and the
+        // watcher may wait on the handler.
+        if new_dir {
+            if let Err(e) = self
+                .watcher
+                .lock()
+                .unwrap()
+                .watch(&dir, RecursiveMode::NonRecursive)
+            {
+                tracing::debug!(dir = %dir.display(), error = %e, "cannot watch directory");
+            }
+        }
+    }
+
+    /// Take the current state of every file with events as seen, after a
+    /// tool that may have written anywhere ran.
+    pub async fn refresh(&self) {
+        for (key, current) in self.stamp_marked().await {
+            if let Some(watched) = self.state.lock().unwrap().files.get_mut(&key) {
+                watched.seen = current;
+            }
+        }
+    }
+
+    /// Set aside changes made so far, before the agent's own tools run and
+    /// their stamps are renewed.
+    pub async fn collect(&self) {
+        let stamped = self.stamp_marked().await;
+        let mut state = self.state.lock().unwrap();
+        let State { files, pending, .. } = &mut *state;
+        for (key, current) in stamped {
+            let Some(watched) = files.get_mut(&key) else {
+                continue;
+            };
+            if current == watched.seen {
+                continue;
+            }
+            let deleted = current.is_none();
+            watched.seen = current;
+            match pending
+                .iter_mut()
+                .find(|change| change.path == watched.path)
+            {
+                Some(change) => change.deleted = deleted,
+                None => pending.push(FileChange {
+                    path: watched.path.clone(),
+                    deleted,
+                }),
+            }
+        }
+    }
+
+    /// Files changed since they were last seen, sorted by path. Each change
+    /// is reported once.
+    pub async fn changes(&self) -> Vec<FileChange> {
+        self.collect().await;
+        let mut changes = std::mem::take(&mut self.state.lock().unwrap().pending);
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+
+    /// Clear the marks and stamp the marked files. Events arriving meanwhile
+    /// mark them again for next time.
+    async fn stamp_marked(&self) -> Vec<(PathBuf, Stamp)> {
+        let marked: Vec<(PathBuf, PathBuf)> = {
+            let mut state = self.state.lock().unwrap();
+            let marked = std::mem::take(&mut state.marked);
+            marked
+                .into_iter()
+                .filter_map(|key| {
+                    let path = state.files.get(&key)?.path.clone();
+                    Some((key, path))
+                })
+                .collect()
+        };
+        let mut stamped = Vec::with_capacity(marked.len());
+        for (key, path) in marked {
+            let current = stamp(&path).await;
+            stamped.push((key, current));
+        }
+        stamped
+    }
+}
+
+/// The note telling the model about `changes`, with paths relative to
+/// `working_dir` where possible.
+pub fn change_note(changes: &[FileChange], working_dir: &Path) -> String {
+    let mut listed: Vec<String> = changes
+        .iter()
+        .take(MAX_NOTED)
+        .map(|change| {
+            let path = change
+                .path
+                .strip_prefix(working_dir)
+                .unwrap_or(&change.path);
+            match change.deleted {
+                true => format!("- {} (deleted)", path.display()),
+                false => format!("- {}", path.display()),
+            }
+        })
+        .collect();
+    if changes.len() > MAX_NOTED {
+        listed.push(format!("- ... and {} more", changes.len() - MAX_NOTED));
+    }
+    format!(
+        "These files changed outside the agent since you last read or wrote them. \
+         Read them again before editing them, and keep the changes made there:\n{}",
+        listed.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Wait for the watcher to report an event for `path`.
+    async fn wait_marked(watch: &FileWatch, path: &Path) {
+        let dir = tokio::fs::canonicalize(path.parent().unwrap())
+            .await
+            .unwrap();
+        let key = dir.join(path.file_name().unwrap());
+        for _ in 0..500 {
+            if watch.state.lock().unwrap().marked.contains(&key) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("no event for {}", path.display());
+    }
+
+    #[tokio::test]
+    async fn test_changes_since_last_seen_are_reported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (a, b) = (root.join("a.rs"), root.join("b.rs"));
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+        let watch = FileWatch::new().unwrap();
+        watch.track(a.clone()).await;
+        watch.track(b.clone()).await;
+        assert!(watch.changes().await.is_empty());
+
+        // The agent's own writes are absorbed by a refresh.
+        std::fs::write(&a, "agent edit").unwrap();
+        wait_marked(&watch, &a).await;
+        watch.refresh().await;
+        assert!(watch.changes().await.is_empty());
+
+        std::fs::write(&a, "user edit!").unwrap();
+        std::fs::remove_file(&b).unwrap();
+        wait_marked(&watch, &a).await;
+        wait_marked(&watch, &b).await;
+        let changes = watch.changes().await;
+        assert_eq!(
+            changes,
+            [
+                FileChange {
+                    path: a.clone(),
+                    deleted: false
+                },
+                FileChange {
+                    path: b.clone(),
+                    deleted: true
+                },
+            ]
+        );
+        assert!(watch.changes().await.is_empty());
+        assert!(change_note(&changes, root).ends_with(":\n- a.rs\n- b.rs (deleted)"));
+    }
+
+    #[tokio::test]
+    async fn test_edits_around_a_batch_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (a, b) = (root.join("a.rs"), root.join("b.rs"));
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+        let watch = FileWatch::new().unwrap();
+        watch.track(a.clone()).await;
+        watch.track(b.clone()).await;
+
+        // The user edits a.rs before the batch and b.rs while it runs; the
+        // batch itself writes a.rs, which is stamped again afterwards.
+        std::fs::write(&a, "user edit").unwrap();
+        wait_marked(&watch, &a).await;
+        watch.collect().await;
+        std::fs::write(&b, "user edit").unwrap();
+        std::fs::write(&a, "agent edit!").unwrap();
+        watch.track(a.clone()).await;
+        wait_marked(&watch, &b).await;
+
+        let paths: Vec<_> = watch
+            .changes()
+            .await
+            .into_iter()
+            .map(|change| change.path)
+            .collect();
+        assert_eq!(paths, [a, b]);
+        assert!(watch.changes().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replaced_file_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.rs");
+        std::fs::write(&a, "a").unwrap();
+        let watch = FileWatch::new().unwrap();
+        watch.track(a.clone()).await;
+
+        // Editors often save by renaming a new file over the old one.
+        std::fs::write(dir.path().join(".a.rs.swp"), "saved by editor").unwrap();
+        std::fs::rename(dir.path().join(".a.rs.swp"), &a).unwrap();
+        wait_marked(&watch, &a).await;
+        let paths: Vec<_> = watch
+            .changes()
+            .await
+            .into_iter()
+            .map(|change| change.path)
+            .collect();
+        assert_eq!(paths, [a]);
+    }
+}
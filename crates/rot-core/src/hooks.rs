@@ -0,0 +1,268 @@
+//! Webhooks and command hooks told when a run starts, finishes, fails, or
+//! waits for approval, so unattended runs can report to chat or a phone.
+//!
+//! Every hook gets the same JSON [`HookPayload`]: a webhook as the body of a
+//! `POST`, a command on stdin. Delivery failures are logged and otherwise
+//! ignored; a broken hook never stops a run.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// How long one hook may take before it is abandoned.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Characters of the prompt, answer, or error kept in a payload's summary.
+const SUMMARY_CHARS: usize = 500;
+
+/// Run lifecycle events hooks can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    RunStarted,
+    RunFinished,
+    RunFailed,
+    ApprovalNeeded,
+}
+
+/// One webhook or command hook, from the `hooks` config list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// POST the payload to this URL as JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Extra request headers for `url`, such as `Authorization`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Run this shell command with the payload on stdin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Events to send; every event when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<HookEvent>,
+}
+
+impl HookConfig {
+    fn wants(&self, event: HookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// What a hook is told about a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookPayload {
+    pub event: HookEvent,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub session_id: String,
+    /// Working directory of the run.
+    pub cwd: String,
+    /// `provider/model` answering the run.
+    pub model: String,
+    /// Tokens used so far by the run, or by the session in the TUI.
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    /// Spend in USD for those tokens; `None` for unpriced models.
+    pub cost: Option<f64>,
+    /// The prompt on start, the answer on finish, the error on failure, or
+    /// the call waiting for approval; cut to 500 characters.
+    pub summary: String,
+    /// Tool waiting for approval.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+}
+
+impl HookPayload {
+    /// A payload for `event` in `session_id`, stamped with the current time
+    /// and directory.
+    pub fn new(event: HookEvent, session_id: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            event,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            session_id: session_id.into(),
+            cwd: std::env::current_dir()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            model: model.into(),
+            input_tokens: 0,
+            output_tokens: 0,
+            cost: None,
+            summary: String::new(),
+            tool: None,
+        }
+    }
+
+    /// Set the token counts and their cost.
+    pub fn with_usage(
+        mut self,
+        input_tokens: usize,
+        output_tokens: usize,
+        cost: Option<f64>,
+    ) -> Self {
+        self.input_tokens = input_tokens;
+        self.output_tokens = output_tokens;
+        self.cost = cost;
+        self
+    }
+
+    /// Set the summary, cut to its first 500 characters.
+    pub fn with_summary(mut self, summary: &str) -> Self {
+        let summary = summary.trim();
+        self.summary = match summary.char_indices().nth(SUMMARY_CHARS) {
+            Some((end, _)) => format!("{}…", &summary[..end]),
+            None => summary.to_string(),
+        };
+        self
+    }
+
+    /// Name the tool waiting for approval.
+    pub fn with_tool(mut self, tool: impl Into<String>) -> Self {
+        self.tool = Some(tool.into());
+        self
+    }
+}
+
+/// The configured hooks, ready to fire.
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    hooks: Vec<HookConfig>,
+    client: reqwest::Client,
+}
+
+impl Hooks {
+    pub fn new(hooks: Vec<HookConfig>) -> Self {
+        Self {
+            hooks,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Whether any hook subscribes to `event`.
+    pub fn wants(&self, event: HookEvent) -> bool {
+        self.hooks.iter().any(|hook| hook.wants(event))
+    }
+
+    /// Deliver `payload` to every hook subscribed to its event, all at once.
+    /// Returns when each has been delivered, failed, or timed out.
+    pub async fn fire(&self, payload: &HookPayload) {
+        let deliveries = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.wants(payload.event))
+            .map(|hook| async move {
+                match tokio::time::timeout(HOOK_TIMEOUT, self.deliver(hook, payload)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => tracing::warn!("Hook for {:?} failed: {e}", payload.event),
+                    Err(_) => tracing::warn!("Hook for {:?} timed out", payload.event),
+                }
+            });
+        futures::future::join_all(deliveries).await;
+    }
+
+    /// [`Hooks::fire`] without waiting, for callers that must not block.
+    pub fn fire_in_background(self: &Arc<Self>, payload: HookPayload) {
+        if !self.wants(payload.event) {
+            return;
+        }
+        let hooks = Arc::clone(self);
+        tokio::spawn(async move { hooks.fire(&payload).await });
+    }
+
+    async fn deliver(&self, hook: &HookConfig, payload: &HookPayload) -> Result<(), String> {
+        if let Some(url) = &hook.url {
+            let mut request = self.client.post(url).json(payload);
+            for (name, value) in &hook.headers {
+                request = request.header(name, value);
+            }
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("{url} returned {}", response.status()));
+            }
+        }
+        if let Some(command) = &hook.command {
+            run_command(command, payload).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Run `command` in the shell with `payload` on stdin and the event name in
+/// `ROT_HOOK_EVENT`.
+async fn run_command(command: &str, payload: &HookPayload) -> Result<(), String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let event = serde_json::to_value(payload.event).map_err(|e| e.to_string())?;
+    let mut child = tokio::process::Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .env("ROT_HOOK_EVENT", event.as_str().unwrap_or_default())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to run `{command}`: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let json = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+        // A command that ignores its input may exit before reading it.
+        let _ = stdin.write_all(&json).await;
+    }
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{command}` exited with {status}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_command_hook_receives_subscribed_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("events.jsonl");
+        let hooks = Hooks::new(vec![HookConfig {
+            command: Some(format!(
+                "{{ cat; echo; }} >> '{}' && echo \"$ROT_HOOK_EVENT\" >> '{}'",
+                out.display(),
+                dir.path().join("names").display()
+            )),
+            events: vec![HookEvent::RunFinished, HookEvent::RunFailed],
+            ..Default::default()
+        }]);
+        assert!(hooks.wants(HookEvent::RunFailed));
+        assert!(!hooks.wants(HookEvent::RunStarted));
+
+        hooks
+            .fire(
+                &HookPayload::new(HookEvent::RunStarted, "s1", "openai/gpt-4o").with_summary("go"),
+            )
+            .await;
+        let finished = HookPayload::new(HookEvent::RunFinished, "s1", "openai/gpt-4o")
+            .with_usage(1_000, 50, Some(0.01))
+            .with_summary(&"x".repeat(600));
+        hooks.fire(&finished).await;
+
+        let written = std::fs::read_to_string(&out).unwrap();
+        let payloads: Vec<HookPayload> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(payloads, vec![finished]);
+        assert_eq!(payloads[0].summary.chars().count(), 501);
+        let names = std::fs::read_to_string(dir.path().join("names")).unwrap();
+        assert_eq!(names.trim(), "run_finished");
+    }
+}
@@ -1,19 +1,52 @@
 //! rot-core: Core agent logic, message types, and permissions.
 
 pub mod agent;
+pub mod agent_file;
 pub mod agent_profile;
 pub mod agent_registry;
+pub mod audit;
+pub mod compaction;
+pub mod config;
+pub mod credentials;
+pub mod custom_provider;
 mod error;
+pub mod file_watch;
+pub mod hooks;
+pub mod mcp_json;
 pub mod message;
+pub mod metrics;
+pub mod middleware;
+pub mod network;
+pub mod output_schema;
 pub mod permission;
-pub mod config;
+pub mod project;
+pub mod redaction;
+pub mod reload;
+pub mod run_summary;
+pub mod schema;
 pub mod security;
+pub mod tool_budget;
+pub mod transcript;
+pub mod usage;
+pub mod verify;
 
 
-pub use agent::{Agent, AgentConfig, AgentProcessError, TaskExecutionPolicy};
+pub use agent::{
+    Agent, AgentConfig, AgentProcessError, IterationProgress, TaskBudgetTicket, TaskCallback,
+    TaskDepthPolicy, TaskEvent, TaskExecutionPolicy, TextCallback, ThinkingCallback, ToolCallback,
+    ToolEvent, UsageCallback, UsageReport, DEFAULT_MAX_ITERATIONS,
+};
 pub use agent_profile::{AgentMode, AgentProfile};
 pub use agent_registry::{AgentRegistry, UnknownAgentError};
+pub use config::{resolve_model_alias, Config, ConfigStore, ModelRef};
+pub use custom_provider::{
+    CustomModelConfig, CustomProviderConfig, ProviderKind, BUILTIN_PROVIDERS,
+};
 pub use error::RotError;
 pub use message::{ContentBlock, Message, MessageId, Role};
-pub use config::{Config, ConfigStore};
+pub use middleware::{MiddlewareContext, MiddlewareError, ProviderMiddleware};
+pub use redaction::{RedactionConfig, RedactionRule, Redactor};
+pub use reload::{build_tool_registry, ConfigDiff, ConfigWatcher};
 pub use security::{ApprovalPolicy, RuntimeSecurityConfig, SandboxMode};
+/// Token for stopping a running turn; see [`Agent::process_cancellable`].
+pub use tokio_util::sync::CancellationToken;
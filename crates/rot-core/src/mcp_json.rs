@@ -0,0 +1,276 @@
+//! Project `.mcp.json` files and per-server trust.
+//!
+//! Many editors and agents share MCP setups through a `.mcp.json` file in the
+//! repository root. Its servers are added to `config.mcp_servers`, but each one
+//! must be trusted before it is started, because the file comes from the
+//! repository rather than the user.
+
+use crate::config::Config;
+use rot_tools::McpServerConfig;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of a project MCP definition file.
+pub const MCP_JSON_FILE: &str = ".mcp.json";
+
+#[derive(Debug, Deserialize)]
+struct McpJson {
+    #[serde(rename = "mcpServers", default)]
+    mcp_servers: BTreeMap<String, McpJsonServer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct McpJsonServer {
+    #[serde(rename = "type", default)]
+    transport: Option<String>,
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    cwd: Option<String>,
+}
+
+/// Find the nearest `.mcp.json` at or above `start`, skipping the home directory.
+pub fn find_mcp_json(start: &Path, home: Option<&Path>) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .filter(|dir| Some(*dir) != home)
+        .map(|dir| dir.join(MCP_JSON_FILE))
+        .find(|candidate| candidate.is_file())
+}
+
+/// The `.mcp.json` that applies to a session started in `cwd`.
+pub fn project_mcp_json(cwd: &Path) -> Option<PathBuf> {
+    find_mcp_json(cwd, dirs::home_dir().as_deref())
+}
+
+/// Parse the stdio servers in a `.mcp.json` file.
+///
+/// Relative or missing `cwd` values resolve against the file's directory.
+/// Servers using other transports are skipped with a warning, because `rot`
+/// only speaks stdio. `${VAR}` references are kept so trust decisions do not
+/// depend on the environment; see [`add_project_mcp_servers`].
+pub fn read_mcp_json(path: &Path) -> Result<Vec<McpServerConfig>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: McpJson = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let root = path.parent().unwrap_or(Path::new("."));
+
+    let mut servers = Vec::new();
+    for (name, server) in file.mcp_servers {
+        let transport = server.transport.as_deref().unwrap_or("stdio");
+        let Some(command) = server.command.filter(|_| transport == "stdio") else {
+            tracing::warn!(
+                "Skipping MCP server '{name}' in {}: only stdio servers are supported",
+                path.display()
+            );
+            continue;
+        };
+        let cwd = match server.cwd {
+            Some(cwd) if Path::new(&cwd).is_absolute() => cwd,
+            Some(cwd) => root.join(cwd).display().to_string(),
+            None => root.display().to_string(),
+        };
+        let mut config: McpServerConfig = serde_json::from_value(serde_json::json!({
+            "name": name,
+            "command": command,
+        }))
+        .map_err(|e| e.to_string())?;
+        config.args = server.args;
+        config.env = server.env;
+        config.cwd = Some(cwd);
+        servers.push(config);
+    }
+    Ok(servers)
+}
+
+/// Remembers which project MCP servers the user has approved.
+///
+/// Entries are keyed by the `.mcp.json` path and server name and store the
+/// full definition, so an edited server asks for trust again.
+#[derive(Debug, Clone)]
+pub struct McpTrustStore {
+    path: PathBuf,
+}
+
+impl Default for McpTrustStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type TrustFile = BTreeMap<String, BTreeMap<String, McpServerConfig>>;
+
+impl McpTrustStore {
+    /// Trust store at `~/.rot/trusted_mcp.json`.
+    pub fn new() -> Self {
+        let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push(".rot");
+        path.push("trusted_mcp.json");
+        Self { path }
+    }
+
+    /// Trust store at an explicit path.
+    pub fn with_path(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Whether `server` from `source` was approved with this exact definition.
+    pub fn is_trusted(&self, source: &Path, server: &McpServerConfig) -> bool {
+        self.read()
+            .get(&source_key(source))
+            .and_then(|servers| servers.get(&server.name))
+            .is_some_and(|trusted| trusted == server)
+    }
+
+    /// Record `server` from `source` as approved.
+    pub fn trust(&self, source: &Path, server: &McpServerConfig) -> std::io::Result<()> {
+        let mut file = self.read();
+        file.entry(source_key(source))
+            .or_default()
+            .insert(server.name.clone(), server.clone());
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&file)?)
+    }
+
+    fn read(&self) -> TrustFile {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn source_key(source: &Path) -> String {
+    source
+        .canonicalize()
+        .unwrap_or_else(|_| source.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Add servers from a `.mcp.json` file to `config.mcp_servers`.
+///
+/// Servers already named in the rot config are left to the rot definition.
+/// Untrusted servers are passed to `approve`. Approved servers are recorded
+/// in `trust` and added. Returns a note for each server that was not added.
+pub fn add_project_mcp_servers(
+    config: &mut Config,
+    source: &Path,
+    trust: &McpTrustStore,
+    mut approve: impl FnMut(&McpServerConfig) -> bool,
+) -> Vec<String> {
+    let servers = match read_mcp_json(source) {
+        Ok(servers) => servers,
+        Err(e) => return vec![format!("{}: {e}", source.display())],
+    };
+
+    let mut notes = Vec::new();
+    for server in servers {
+        if config.mcp_servers.iter().any(|s| s.name == server.name) {
+            notes.push(format!(
+                "MCP server '{}' from {} is overridden by the rot config",
+                server.name,
+                source.display()
+            ));
+            continue;
+        }
+        if !trust.is_trusted(source, &server) {
+            if !approve(&server) {
+                notes.push(format!(
+                    "MCP server '{}' from {} is not trusted; skipped",
+                    server.name,
+                    source.display()
+                ));
+                continue;
+            }
+            if let Err(e) = trust.trust(source, &server) {
+                tracing::warn!("Cannot record trust for MCP server '{}': {e}", server.name);
+            }
+        }
+        config
+            .mcp_servers
+            .push(crate::config::interpolate_mcp_server(server));
+    }
+    notes
+}
+
+/// Add already-trusted servers from the `.mcp.json` nearest to `cwd`.
+///
+/// Used where the user cannot be asked, such as config reloads.
+pub fn add_trusted_project_mcp_servers(config: &mut Config, cwd: &Path) -> Vec<String> {
+    match project_mcp_json(cwd) {
+        Some(source) => add_project_mcp_servers(config, &source, &McpTrustStore::new(), |_| false),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_mcp_json(dir: &Path) -> PathBuf {
+        let path = dir.join(MCP_JSON_FILE);
+        fs::write(
+            &path,
+            r#"{
+                "mcpServers": {
+                    "github": {
+                        "command": "npx",
+                        "args": ["-y", "@modelcontextprotocol/server-github"],
+                        "env": {"GITHUB_TOKEN": "${ROT_TEST_UNSET_TOKEN:-none}"}
+                    },
+                    "remote": {"type": "http", "url": "https://example.com/mcp"}
+                }
+            }"#,
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_mcp_json_keeps_stdio_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_mcp_json(dir.path());
+
+        let servers = read_mcp_json(&path).unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].name, "github");
+        assert_eq!(servers[0].args[1], "@modelcontextprotocol/server-github");
+        assert_eq!(
+            servers[0].cwd.as_deref(),
+            Some(dir.path().display().to_string().as_str())
+        );
+        assert_eq!(find_mcp_json(&dir.path().join("sub"), None), Some(path));
+    }
+
+    #[test]
+    fn test_servers_require_trust_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_mcp_json(dir.path());
+        let trust = McpTrustStore::with_path(dir.path().join("trusted.json"));
+
+        let mut config = Config::default();
+        let notes = add_project_mcp_servers(&mut config, &path, &trust, |_| false);
+        assert!(config.mcp_servers.is_empty());
+        assert!(notes[0].contains("not trusted"));
+
+        let mut asked = 0;
+        add_project_mcp_servers(&mut config, &path, &trust, |_| {
+            asked += 1;
+            true
+        });
+        assert_eq!(asked, 1);
+        assert_eq!(config.mcp_servers[0].env["GITHUB_TOKEN"], "none");
+
+        let mut config = Config::default();
+        add_project_mcp_servers(&mut config, &path, &trust, |_| panic!("already trusted"));
+        assert_eq!(config.mcp_servers.len(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 //! Core message types used throughout the rot system.
 
+use rot_provider::{ToolResultPart, Usage};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -96,6 +97,9 @@ pub enum ContentBlock {
         is_error: bool,
         #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
         metadata: serde_json::Value,
+        /// Structured blocks sent after `content`, such as images.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        parts: Vec<ToolResultPart>,
     },
 
     /// Model thinking/reasoning (extended thinking).
@@ -121,6 +125,9 @@ pub struct Message {
     /// Parent message ID (for branching conversations).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_id: Option<MessageId>,
+    /// Tokens used by the provider request that produced this assistant message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 impl Message {
@@ -142,6 +149,7 @@ impl Message {
             }],
             timestamp: Self::now_timestamp(),
             parent_id: None,
+            usage: None,
         }
     }
 
@@ -153,6 +161,7 @@ impl Message {
             content,
             timestamp: Self::now_timestamp(),
             parent_id: None,
+            usage: None,
         }
     }
 
@@ -166,6 +175,7 @@ impl Message {
             }],
             timestamp: Self::now_timestamp(),
             parent_id: None,
+            usage: None,
         }
     }
 
@@ -198,12 +208,25 @@ impl Message {
                 content: content.into(),
                 is_error,
                 metadata,
+                parts: Vec::new(),
             }],
             timestamp: Self::now_timestamp(),
             parent_id: None,
+            usage: None,
         }
     }
 
+    /// Attach structured blocks to this message's tool result.
+    pub fn with_result_parts(mut self, result_parts: Vec<ToolResultPart>) -> Self {
+        for block in &mut self.content {
+            if let ContentBlock::ToolResult { parts, .. } = block {
+                *parts = result_parts;
+                break;
+            }
+        }
+        self
+    }
+
     /// Set the parent message ID.
     pub fn with_parent(mut self, parent_id: MessageId) -> Self {
         self.parent_id = Some(parent_id);
@@ -223,10 +246,119 @@ impl Message {
     }
 }
 
+/// Positions of user-authored messages in a conversation (tool results excluded).
+pub fn user_prompt_indices(messages: &[Message]) -> Vec<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| message.role == Role::User)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// A tool call paired with its result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolExchange {
+    pub name: String,
+    pub arguments: serde_json::Value,
+    /// Tool output, or `None` if the call has no result yet.
+    pub output: Option<String>,
+    pub is_error: bool,
+    pub metadata: serde_json::Value,
+}
+
+/// Tool calls in `messages`, in order, each with its matching result.
+pub fn tool_exchanges(messages: &[Message]) -> Vec<ToolExchange> {
+    let mut exchanges: Vec<(String, ToolExchange)> = Vec::new();
+    for block in messages.iter().flat_map(|m| &m.content) {
+        match block {
+            ContentBlock::ToolCall {
+                id,
+                name,
+                arguments,
+            } => exchanges.push((
+                id.clone(),
+                ToolExchange {
+                    name: name.clone(),
+                    arguments: arguments.clone(),
+                    output: None,
+                    is_error: false,
+                    metadata: serde_json::Value::Null,
+                },
+            )),
+            ContentBlock::ToolResult {
+                tool_call_id,
+                content,
+                is_error,
+                metadata,
+                ..
+            } => {
+                if let Some((_, exchange)) = exchanges.iter_mut().find(|(id, _)| id == tool_call_id)
+                {
+                    exchange.output = Some(content.clone());
+                    exchange.is_error = *is_error;
+                    exchange.metadata = metadata.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+    exchanges
+        .into_iter()
+        .map(|(_, exchange)| exchange)
+        .collect()
+}
+
+/// Rewind a conversation to just before its `nth` user prompt (0-based).
+///
+/// The prompt and everything after it are dropped; the removed prompt is
+/// returned so callers can prefill an editor with it. Returns `None` and
+/// leaves `messages` untouched when there is no such prompt.
+pub fn rewind_to_user_prompt(messages: &mut Vec<Message>, nth: usize) -> Option<Message> {
+    let idx = *user_prompt_indices(messages).get(nth)?;
+    let mut removed = messages.split_off(idx);
+    Some(removed.swap_remove(0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tool_exchanges_pair_calls_with_results() {
+        let call = Message {
+            id: MessageId::new(),
+            role: Role::Assistant,
+            content: vec![
+                ContentBlock::ToolCall {
+                    id: "a".to_string(),
+                    name: "edit".to_string(),
+                    arguments: serde_json::json!({"path": "f.txt"}),
+                },
+                ContentBlock::ToolCall {
+                    id: "b".to_string(),
+                    name: "bash".to_string(),
+                    arguments: serde_json::json!({}),
+                },
+            ],
+            timestamp: 0,
+            parent_id: None,
+            usage: None,
+        };
+        let result = Message::tool_result_with_metadata(
+            "a",
+            "Replaced 1 occurrence(s)",
+            false,
+            serde_json::json!({"diff": "+x"}),
+        );
+
+        let exchanges = tool_exchanges(&[Message::user("go"), call, result]);
+        assert_eq!(exchanges.len(), 2);
+        assert_eq!(exchanges[0].name, "edit");
+        assert_eq!(exchanges[0].metadata["diff"], "+x");
+        assert_eq!(exchanges[1].output, None);
+    }
+
     #[test]
     fn test_message_id_unique() {
         let id1 = MessageId::new();
@@ -275,11 +407,13 @@ mod tests {
                 content,
                 is_error,
                 metadata,
+                parts,
             } => {
                 assert_eq!(tool_call_id, "call-1");
                 assert_eq!(content, "file contents here");
                 assert!(!is_error);
                 assert!(metadata.is_null());
+                assert!(parts.is_empty());
             }
             _ => panic!("Expected ToolResult content block"),
         }
@@ -357,7 +491,32 @@ mod tests {
             ],
             timestamp: 0,
             parent_id: None,
+            usage: None,
         };
         assert_eq!(msg.text(), "Hello world");
     }
+
+    #[test]
+    fn test_rewind_to_user_prompt() {
+        let mut messages = vec![
+            Message::user("first"),
+            Message::assistant(vec![ContentBlock::Text {
+                text: "one".to_string(),
+            }]),
+            Message::tool_result("tc1", "ok", false),
+            Message::user("second"),
+            Message::assistant(vec![ContentBlock::Text {
+                text: "two".to_string(),
+            }]),
+        ];
+
+        assert_eq!(user_prompt_indices(&messages), vec![0, 3]);
+        assert!(rewind_to_user_prompt(&mut messages, 2).is_none());
+        assert_eq!(messages.len(), 5);
+
+        let removed = rewind_to_user_prompt(&mut messages, 1).unwrap();
+        assert_eq!(removed.text(), "second");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages.last().unwrap().role, Role::Tool);
+    }
 }
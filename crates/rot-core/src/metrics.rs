@@ -0,0 +1,321 @@
+//! Process-wide counters and histograms, rendered in the Prometheus text
+//! format for `rot serve`'s `/metrics` route and `--metrics-port`.
+//!
+//! The agent records every provider request, the tokens and spend it
+//! reports, each tool call's latency, and tool calls the sandbox refused.
+//! Recording is cheap and always on; nothing is exported unless asked for.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const BUCKETS: [f64; 12] = [
+    0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// The metrics the agent records into.
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+/// Latency observations, bucketed.
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations at or below each of [`BUCKETS`].
+    counts: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (count, bound) in self.counts.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// By provider, model, and `ok` or `error`.
+    requests: BTreeMap<[String; 3], Histogram>,
+    /// By model and `input` or `output`.
+    tokens: BTreeMap<[String; 2], u64>,
+    /// USD by model, for priced models.
+    cost: BTreeMap<String, f64>,
+    /// By tool and `ok` or `error`.
+    tools: BTreeMap<[String; 2], Histogram>,
+    /// By tool.
+    sandbox_denials: BTreeMap<String, u64>,
+}
+
+/// Counters and histograms for one process.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Inner>,
+}
+
+impl Metrics {
+    /// A provider request that streamed to the end (`ok`) or failed.
+    pub fn record_request(&self, provider: &str, model: &str, ok: bool, elapsed: Duration) {
+        let key = [
+            provider.to_string(),
+            model.to_string(),
+            status(!ok).to_string(),
+        ];
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .requests
+            .entry(key)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Tokens a provider reported for `model`, and what they cost.
+    pub fn record_usage(&self, model: &str, input_tokens: usize, output_tokens: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        for (direction, tokens) in [("input", input_tokens), ("output", output_tokens)] {
+            *inner
+                .tokens
+                .entry([model.to_string(), direction.to_string()])
+                .or_default() += tokens as u64;
+        }
+        if let Some(pricing) = rot_provider::pricing_for(model) {
+            *inner.cost.entry(model.to_string()).or_default() +=
+                pricing.cost(input_tokens, output_tokens);
+        }
+    }
+
+    /// A tool call that ran, however it ended.
+    pub fn record_tool(&self, tool: &str, is_error: bool, elapsed: Duration) {
+        let key = [tool.to_string(), status(is_error).to_string()];
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .tools
+            .entry(key)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// A tool call the sandbox refused.
+    pub fn record_sandbox_denial(&self, tool: &str) {
+        *self
+            .inner
+            .lock()
+            .unwrap()
+            .sandbox_denials
+            .entry(tool.to_string())
+            .or_default() += 1;
+    }
+
+    /// Everything recorded so far, in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "rot_provider_requests_total",
+            "counter",
+            "Provider requests.",
+        );
+        for (labels, histogram) in &inner.requests {
+            let labels = label_set(&["provider", "model", "status"], labels);
+            sample(
+                &mut out,
+                "rot_provider_requests_total",
+                &labels,
+                histogram.count,
+            );
+        }
+        histograms(
+            &mut out,
+            "rot_provider_request_duration_seconds",
+            "Provider request time, to the end of the stream.",
+            &["provider", "model", "status"],
+            &inner.requests,
+        );
+
+        header(
+            &mut out,
+            "rot_tokens_total",
+            "counter",
+            "Tokens reported by providers.",
+        );
+        for (labels, tokens) in &inner.tokens {
+            sample(
+                &mut out,
+                "rot_tokens_total",
+                &label_set(&["model", "direction"], labels),
+                tokens,
+            );
+        }
+
+        header(
+            &mut out,
+            "rot_cost_usd_total",
+            "counter",
+            "Estimated spend in USD, for priced models.",
+        );
+        for (model, cost) in &inner.cost {
+            sample(
+                &mut out,
+                "rot_cost_usd_total",
+                &label_set(&["model"], &[model]),
+                cost,
+            );
+        }
+
+        histograms(
+            &mut out,
+            "rot_tool_duration_seconds",
+            "Tool call time.",
+            &["tool", "status"],
+            &inner.tools,
+        );
+
+        header(
+            &mut out,
+            "rot_sandbox_denials_total",
+            "counter",
+            "Tool calls refused by the sandbox.",
+        );
+        for (tool, count) in &inner.sandbox_denials {
+            sample(
+                &mut out,
+                "rot_sandbox_denials_total",
+                &label_set(&["tool"], &[tool]),
+                count,
+            );
+        }
+        out
+    }
+}
+
+fn status(is_error: bool) -> &'static str {
+    if is_error {
+        "error"
+    } else {
+        "ok"
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "{name}{{{labels}}} {value}");
+}
+
+fn histograms<const N: usize>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    names: &[&str],
+    series: &BTreeMap<[String; N], Histogram>,
+) {
+    header(out, name, "histogram", help);
+    for (values, histogram) in series {
+        let labels = label_set(names, values);
+        for (bound, count) in BUCKETS.iter().zip(histogram.counts) {
+            sample(
+                out,
+                &format!("{name}_bucket"),
+                &format!("{labels},le=\"{bound}\""),
+                count,
+            );
+        }
+        let bucket = format!("{name}_bucket");
+        sample(
+            out,
+            &bucket,
+            &format!("{labels},le=\"+Inf\""),
+            histogram.count,
+        );
+        sample(out, &format!("{name}_sum"), &labels, histogram.sum);
+        sample(out, &format!("{name}_count"), &labels, histogram.count);
+    }
+}
+
+/// `name="value",...` with the values escaped.
+fn label_set<S: AsRef<str>>(names: &[&str], values: &[S]) -> String {
+    names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| {
+            let value = value
+                .as_ref()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{name}=\"{value}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counts_and_buckets() {
+        let metrics = Metrics::default();
+        metrics.record_request("openai", "gpt-4o", true, Duration::from_millis(300));
+        metrics.record_request("openai", "gpt-4o", false, Duration::from_secs(2));
+        metrics.record_usage("gpt-4o", 1_000_000, 0);
+        metrics.record_usage("my-local-model", 10, 5);
+        metrics.record_tool("bash", false, Duration::from_millis(40));
+        metrics.record_tool("bash", false, Duration::from_millis(700));
+        metrics.record_sandbox_denial("write");
+        metrics.record_sandbox_denial("write");
+
+        let text = metrics.render();
+        let has = |line: &str| text.lines().any(|l| l == line);
+        assert!(has(
+            r#"rot_provider_requests_total{provider="openai",model="gpt-4o",status="ok"} 1"#
+        ));
+        assert!(has(
+            r#"rot_provider_requests_total{provider="openai",model="gpt-4o",status="error"} 1"#
+        ));
+        assert!(has(
+            r#"rot_tokens_total{model="gpt-4o",direction="input"} 1000000"#
+        ));
+        assert!(has(
+            r#"rot_tokens_total{model="my-local-model",direction="output"} 5"#
+        ));
+        assert!(has(r#"rot_cost_usd_total{model="gpt-4o"} 2.5"#));
+        assert!(!text.contains(r#"rot_cost_usd_total{model="my-local-model"}"#));
+        assert!(has(
+            r#"rot_tool_duration_seconds_bucket{tool="bash",status="ok",le="0.05"} 1"#
+        ));
+        assert!(has(
+            r#"rot_tool_duration_seconds_bucket{tool="bash",status="ok",le="1"} 2"#
+        ));
+        assert!(has(
+            r#"rot_tool_duration_seconds_bucket{tool="bash",status="ok",le="+Inf"} 2"#
+        ));
+        assert!(has(
+            r#"rot_tool_duration_seconds_count{tool="bash",status="ok"} 2"#
+        ));
+        assert!(has(r#"rot_sandbox_denials_total{tool="write"} 2"#));
+        assert!(has("# TYPE rot_tool_duration_seconds histogram"));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(
+            label_set(&["model", "tool"], &["a\"b", "c\\d\ne"]),
+            r#"model="a\"b",tool="c\\d\ne""#
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Provider middleware hooks around the agent loop.
+//!
+//! Middleware sees every provider request before it is sent and every
+//! streamed event before the agent consumes it. This is the extension point
+//! for features such as memory injection, token-budget trimming, and output
+//! filters that would otherwise need changes to the core loop.
+
+use rot_provider::{Request, StreamEvent};
+
+/// Per-request information available to middleware.
+#[derive(Debug, Clone, Copy)]
+pub struct MiddlewareContext<'a> {
+    /// Session the request belongs to.
+    pub session_id: &'a str,
+    /// Active agent profile name.
+    pub agent_name: &'a str,
+    /// 1-based loop iteration within the current turn.
+    pub iteration: usize,
+    /// Delegated task depth (0 for the root agent).
+    pub task_depth: usize,
+}
+
+/// Error returned by middleware to abort the current turn.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{0}")]
+pub struct MiddlewareError(pub String);
+
+/// A hook layered around provider calls.
+///
+/// Middleware runs in registration order for both requests and events.
+#[async_trait::async_trait]
+pub trait ProviderMiddleware: Send + Sync {
+    /// Name used in error messages.
+    fn name(&self) -> &str;
+
+    /// Transform a request before it is sent to the provider.
+    async fn on_request(
+        &self,
+        request: Request,
+        _ctx: &MiddlewareContext<'_>,
+    ) -> Result<Request, MiddlewareError> {
+        Ok(request)
+    }
+
+    /// Transform or observe a streamed event. Returning `None` drops it.
+    ///
+    /// Dropping `Done` or tool-call events changes how the agent interprets
+    /// the response, so filters should normally only rewrite text deltas.
+    fn on_event(&self, event: StreamEvent, _ctx: &MiddlewareContext<'_>) -> Option<StreamEvent> {
+        Some(event)
+    }
+}
@@ -0,0 +1,97 @@
+//! Hosts tools may reach while sandbox network access is off.
+//!
+//! Tools that report where they connect, such as `webfetch`, ask before
+//! reaching a host for the first time. Hosts allowed "always" are kept per
+//! project in `~/.rot/allowed_hosts.json`, next to the trusted MCP servers.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+type AllowFile = BTreeMap<String, BTreeSet<String>>;
+
+/// The hosts allowed for one project.
+#[derive(Debug, Clone)]
+pub struct HostAllowlist {
+    path: PathBuf,
+    project: String,
+}
+
+impl HostAllowlist {
+    /// Allowlist for `project` at `~/.rot/allowed_hosts.json`.
+    pub fn new(project: &Path) -> Self {
+        let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push(".rot");
+        path.push("allowed_hosts.json");
+        Self::with_path(path, project)
+    }
+
+    /// Allowlist for `project` at an explicit path.
+    pub fn with_path(path: PathBuf, project: &Path) -> Self {
+        let project = project
+            .canonicalize()
+            .unwrap_or_else(|_| project.to_path_buf())
+            .display()
+            .to_string();
+        Self { path, project }
+    }
+
+    /// Whether `host` was allowed for this project.
+    pub fn contains(&self, host: &str) -> bool {
+        self.hosts()
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    /// Hosts allowed for this project, sorted.
+    pub fn hosts(&self) -> Vec<String> {
+        self.read()
+            .remove(&self.project)
+            .map(|hosts| hosts.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Allow `host` for this project from now on.
+    pub fn allow(&self, host: &str) -> std::io::Result<()> {
+        let mut file = self.read();
+        file.entry(self.project.clone())
+            .or_default()
+            .insert(host.to_ascii_lowercase());
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&file)?)
+    }
+
+    fn read(&self) -> AllowFile {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hosts_are_kept_per_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowed_hosts.json");
+        let project = dir.path().join("app");
+        let other = dir.path().join("other");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        let allowlist = HostAllowlist::with_path(path.clone(), &project);
+        assert!(!allowlist.contains("docs.rs"));
+        allowlist.allow("Docs.rs").unwrap();
+        allowlist.allow("crates.io").unwrap();
+
+        let reloaded = HostAllowlist::with_path(path.clone(), &project);
+        assert!(reloaded.contains("docs.rs"));
+        assert_eq!(reloaded.hosts(), ["crates.io", "docs.rs"]);
+        assert!(!HostAllowlist::with_path(path, &other).contains("docs.rs"));
+    }
+}
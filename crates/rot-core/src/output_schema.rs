@@ -0,0 +1,269 @@
+//! JSON Schema validation of final replies, with retries.
+//!
+//! `rot exec --output-schema` and `rot serve` requests with an
+//! `output_schema` both need a reply that parses as JSON and matches a
+//! schema. [`process_with_schema`] runs the agent, checks its reply, and on a
+//! mismatch sends the validation errors back as a new prompt, up to the
+//! given number of retries.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::agent::{Agent, AgentProcessError};
+use crate::message::Message;
+
+/// Times the model is asked again after an invalid reply, by default.
+pub const DEFAULT_RETRIES: usize = 2;
+
+/// Errors loading a schema or validating a reply against it.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OutputSchemaError {
+    #[error("failed to read schema file '{path}': {message}")]
+    Read { path: String, message: String },
+
+    #[error("invalid schema JSON in '{path}': {message}")]
+    SchemaJson { path: String, message: String },
+
+    #[error("invalid JSON schema: {0}")]
+    InvalidSchema(String),
+
+    #[error("final response is not valid JSON: {0}")]
+    NotJson(String),
+
+    #[error("output schema validation failed: {}", .0.join("; "))]
+    Mismatch(Vec<String>),
+}
+
+/// A compiled JSON Schema for final replies.
+#[derive(Debug)]
+pub struct OutputSchema {
+    schema: Value,
+    validator: jsonschema::Validator,
+}
+
+impl OutputSchema {
+    /// Compile `schema`.
+    pub fn new(schema: Value) -> Result<Self, OutputSchemaError> {
+        let validator = jsonschema::validator_for(&schema)
+            .map_err(|e| OutputSchemaError::InvalidSchema(e.to_string()))?;
+        Ok(Self { schema, validator })
+    }
+
+    /// Read and compile the schema in the file at `path`.
+    pub fn from_file(path: &Path) -> Result<Self, OutputSchemaError> {
+        let raw = std::fs::read_to_string(path).map_err(|e| OutputSchemaError::Read {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        let schema = serde_json::from_str(&raw).map_err(|e| OutputSchemaError::SchemaJson {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Self::new(schema)
+    }
+
+    /// The schema as given.
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// Parse `text` and check it against the schema, returning the value.
+    pub fn validate(&self, text: &str) -> Result<Value, OutputSchemaError> {
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| OutputSchemaError::NotJson(e.to_string()))?;
+        let mut errors: Vec<String> = self
+            .validator
+            .iter_errors(&value)
+            .map(|e| e.to_string())
+            .collect();
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            errors.sort();
+            Err(OutputSchemaError::Mismatch(errors))
+        }
+    }
+
+    /// The prompt asking the model to fix a reply that failed with `error`.
+    pub fn retry_prompt(&self, error: &OutputSchemaError) -> String {
+        format!(
+            "Your reply did not match the required output: {error}\n\n\
+             Reply again with only a JSON value, no prose or code fences, that matches this JSON Schema:\n{}",
+            serde_json::to_string_pretty(&self.schema).unwrap_or_default()
+        )
+    }
+}
+
+/// Run `prompt` and validate the reply against `schema`, asking again with
+/// the validation errors up to `retries` times.
+///
+/// Returns the last reply along with its parsed value, or the validation
+/// error that remained once the retries ran out.
+pub async fn process_with_schema(
+    agent: &Arc<Agent>,
+    messages: &mut Vec<Message>,
+    prompt: &str,
+    schema: &OutputSchema,
+    retries: usize,
+    cancel: CancellationToken,
+) -> Result<(Message, Result<Value, OutputSchemaError>), AgentProcessError> {
+    let mut reply = agent
+        .process_cancellable(messages, prompt, Vec::new(), cancel.clone())
+        .await?;
+    for attempt in 1..=retries {
+        let error = match schema.validate(&reply.text()) {
+            Ok(value) => return Ok((reply, Ok(value))),
+            Err(error) => error,
+        };
+        tracing::info!(attempt, %error, "reply failed output schema validation");
+        reply = agent
+            .process_cancellable(
+                messages,
+                &schema.retry_prompt(&error),
+                Vec::new(),
+                cancel.clone(),
+            )
+            .await?;
+    }
+    let validated = schema.validate(&reply.text());
+    Ok((reply, validated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentConfig;
+    use crate::security::RuntimeSecurityConfig;
+    use futures::stream::{self, BoxStream, StreamExt};
+    use rot_provider::{Provider, ProviderError, Request, StopReason, StreamEvent};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Replies in prose first, then with the JSON asked for.
+    struct ProseThenJsonProvider {
+        step: Mutex<usize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for ProseThenJsonProvider {
+        fn name(&self) -> &str {
+            "dummy"
+        }
+
+        fn models(&self) -> Vec<rot_provider::ModelInfo> {
+            vec![]
+        }
+
+        fn current_model(&self) -> &str {
+            "dummy"
+        }
+
+        fn set_model(&mut self, _: &str) -> Result<(), ProviderError> {
+            Ok(())
+        }
+
+        async fn stream(
+            &self,
+            _: Request,
+        ) -> Result<BoxStream<'_, Result<StreamEvent, ProviderError>>, ProviderError> {
+            let mut step = self.step.lock().unwrap();
+            *step += 1;
+            let delta = if *step == 1 {
+                "Sure! The name is rot."
+            } else {
+                r#"{"name":"rot"}"#
+            };
+            let events = vec![
+                Ok(StreamEvent::TextDelta {
+                    delta: delta.to_string(),
+                }),
+                Ok(StreamEvent::Done {
+                    reason: StopReason::EndTurn,
+                }),
+            ];
+            Ok(stream::iter(events).boxed())
+        }
+
+        async fn complete(&self, _: Request) -> Result<rot_provider::Response, ProviderError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reply_is_retried_with_the_errors() {
+        let agent = Arc::new(Agent::new(
+            Box::new(ProseThenJsonProvider {
+                step: Mutex::new(0),
+            }),
+            rot_tools::ToolRegistry::new(),
+            AgentConfig::default(),
+            RuntimeSecurityConfig::default(),
+        ));
+        let schema = OutputSchema::new(json!({"type": "object", "required": ["name"]})).unwrap();
+        let mut messages = Vec::new();
+
+        let (reply, value) = process_with_schema(
+            &agent,
+            &mut messages,
+            "name?",
+            &schema,
+            1,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply.text(), r#"{"name":"rot"}"#);
+        assert_eq!(value.unwrap(), json!({"name": "rot"}));
+        assert!(messages[2].text().starts_with(
+            "Your reply did not match the required output: final response is not valid JSON"
+        ));
+
+        let (_, value) = process_with_schema(
+            &agent,
+            &mut messages,
+            "again?",
+            &schema,
+            0,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+        assert!(value.is_ok());
+    }
+
+    #[test]
+    fn test_output_schema_validation_pass_and_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema.json");
+        std::fs::write(
+            &path,
+            r#"{"type":"object","properties":{"name":{"type":"string"}},"required":["name"]}"#,
+        )
+        .unwrap();
+        let schema = OutputSchema::from_file(&path).unwrap();
+
+        assert_eq!(
+            schema.validate(r#"{"name":"rot"}"#).unwrap(),
+            json!({"name": "rot"})
+        );
+        let mismatch = schema.validate(r#"{"age":1}"#).unwrap_err();
+        assert!(matches!(mismatch, OutputSchemaError::Mismatch(_)));
+        assert!(schema.retry_prompt(&mismatch).contains("\"required\""));
+        assert!(matches!(
+            schema.validate("Sure! Here it is"),
+            Err(OutputSchemaError::NotJson(_))
+        ));
+
+        assert!(matches!(
+            OutputSchema::new(json!({"type": 12})),
+            Err(OutputSchemaError::InvalidSchema(_))
+        ));
+        assert!(matches!(
+            OutputSchema::from_file(&dir.path().join("missing.json")),
+            Err(OutputSchemaError::Read { .. })
+        ));
+    }
+}
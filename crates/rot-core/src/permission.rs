@@ -1,12 +1,21 @@
-//! Tool approval policy and per-session approval state.
+//! Tool approval policy, configured permission rules, and per-session
+//! approval state.
 
 use crate::security::ApprovalPolicy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Tool name on approval requests to reach a new host while sandbox network
+/// access is off. The arguments are `{"host": ..., "tool": ...}`.
+pub const NETWORK_APPROVAL: &str = "network";
 
 /// Response from the interactive permission prompt.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ApprovalResponse {
     /// Allow the tool execution this one time.
     AllowOnce,
+    /// Allow the tool execution this one time, with arguments edited by the user.
+    AllowEdited(serde_json::Value),
     /// Allow this tool execution automatically for the rest of the session.
     AllowAlways,
     /// Deny the tool execution this one time.
@@ -15,12 +24,43 @@ pub enum ApprovalResponse {
     DenyAlways,
 }
 
+/// Arguments a rule's pattern is matched against, the first one present.
+const SUBJECT_ARGUMENTS: &[&str] = &["command", "path", "url", "pattern", "query", "agent"];
+
+/// What a permission rule does with the calls it matches, from the most
+/// to the least permissive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionAction {
+    /// Run without asking.
+    Allow,
+    /// Ask every time, whatever the approval policy.
+    Ask,
+    /// Never run.
+    Deny,
+}
+
+/// A rule from the `permissions` config list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionRule {
+    /// Tool the rule covers; `*` covers every tool.
+    pub tool: String,
+    /// Regex searched for in the call's main argument: the bash command, a
+    /// path, or a URL. Unset matches every call to the tool.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    pub action: PermissionAction,
+}
+
 /// The permission system manages auto-approval and denial of tool execution.
 #[derive(Clone, Debug)]
 pub struct PermissionSystem {
     policy: ApprovalPolicy,
+    rules: Vec<(PermissionRule, Option<Regex>)>,
     session_allowed: Vec<String>,
     session_denied: Vec<String>,
+    session_allowed_hosts: Vec<String>,
+    session_denied_hosts: Vec<String>,
 }
 
 impl Default for PermissionSystem {
@@ -34,11 +74,61 @@ impl PermissionSystem {
     pub fn new(policy: ApprovalPolicy) -> Self {
         Self {
             policy,
+            rules: Vec::new(),
             session_allowed: Vec::new(),
             session_denied: Vec::new(),
+            session_allowed_hosts: Vec::new(),
+            session_denied_hosts: Vec::new(),
         }
     }
 
+    /// Apply configured `rules`. When several match a call, the strictest
+    /// decides it. An `allow` rule with an invalid pattern is skipped; an
+    /// `ask` or `deny` rule with one covers every call to its tool, so a typo
+    /// never loosens a restriction.
+    pub fn with_rules(mut self, rules: &[PermissionRule]) -> Self {
+        self.rules = rules
+            .iter()
+            .filter_map(|rule| match rule.pattern.as_deref().map(Regex::new).transpose() {
+                Ok(regex) => Some((rule.clone(), regex)),
+                Err(e) if rule.action == PermissionAction::Allow => {
+                    tracing::warn!("Skipping permission rule for '{}': {e}", rule.tool);
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Permission rule for '{}' has an invalid pattern; applying it to every call: {e}",
+                        rule.tool
+                    );
+                    Some((rule.clone(), None))
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// The strictest action of the rules matching a call to `tool_name` with `args`.
+    pub fn rule_action(
+        &self,
+        tool_name: &str,
+        args: &serde_json::Value,
+    ) -> Option<PermissionAction> {
+        let subject = SUBJECT_ARGUMENTS
+            .iter()
+            .find_map(|key| args.get(*key).and_then(|v| v.as_str()));
+        self.rules
+            .iter()
+            .filter(|(rule, regex)| {
+                (rule.tool == "*" || rule.tool == tool_name)
+                    && match regex {
+                        Some(regex) => subject.is_some_and(|subject| regex.is_match(subject)),
+                        None => true,
+                    }
+            })
+            .map(|(rule, _)| rule.action)
+            .max()
+    }
+
     /// Returns the current approval policy.
     pub fn policy(&self) -> ApprovalPolicy {
         self.policy
@@ -73,7 +163,36 @@ impl PermissionSystem {
                     self.session_denied.push(tool_name.to_string());
                 }
             }
-            ApprovalResponse::AllowOnce | ApprovalResponse::DenyOnce => {}
+            ApprovalResponse::AllowOnce
+            | ApprovalResponse::AllowEdited(_)
+            | ApprovalResponse::DenyOnce => {}
+        }
+    }
+
+    /// The session's standing answer for `host`: allowed, denied, or not asked yet.
+    pub fn host_decision(&self, host: &str) -> Option<bool> {
+        let host = host.to_ascii_lowercase();
+        if self.session_denied_hosts.contains(&host) {
+            Some(false)
+        } else if self.session_allowed_hosts.contains(&host) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Remember an "always" or "deny" answer about `host` for the session.
+    pub fn handle_host_response(&mut self, host: &str, response: &ApprovalResponse) {
+        let host = host.to_ascii_lowercase();
+        let list = match response {
+            ApprovalResponse::AllowAlways => &mut self.session_allowed_hosts,
+            ApprovalResponse::DenyAlways => &mut self.session_denied_hosts,
+            ApprovalResponse::AllowOnce
+            | ApprovalResponse::AllowEdited(_)
+            | ApprovalResponse::DenyOnce => return,
+        };
+        if !list.contains(&host) {
+            list.push(host);
         }
     }
 }
@@ -82,28 +201,86 @@ impl PermissionSystem {
 pub fn is_auto_allowed_by_policy(policy: ApprovalPolicy, tool_name: &str) -> bool {
     match policy {
         ApprovalPolicy::Never => true,
-        ApprovalPolicy::Untrusted => matches!(tool_name, "read" | "grep" | "glob"),
+        ApprovalPolicy::Untrusted => matches!(tool_name, "read" | "grep" | "glob" | "read_more"),
         ApprovalPolicy::OnRequest => {
-            matches!(tool_name, "read" | "grep" | "glob" | "write" | "edit")
+            matches!(
+                tool_name,
+                "read" | "grep" | "glob" | "read_more" | "write" | "edit" | "resolve_conflicts"
+            )
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::is_auto_allowed_by_policy;
-    use crate::security::ApprovalPolicy;
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_strictest_matching_rule_decides() {
+        let rules: Vec<PermissionRule> = serde_json::from_value(json!([
+            {"tool": "bash", "action": "allow"},
+            {"tool": "bash", "pattern": "^git (push|reset)", "action": "ask"},
+            {"tool": "*", "pattern": "rm -rf /", "action": "deny"},
+            {"tool": "webfetch", "action": "deny"},
+            {"tool": "read", "pattern": "(", "action": "deny"},
+            {"tool": "glob", "pattern": "(", "action": "allow"}
+        ]))
+        .unwrap();
+        let system = PermissionSystem::new(ApprovalPolicy::Untrusted).with_rules(&rules);
+
+        let bash = |command: &str| system.rule_action("bash", &json!({"command": command}));
+        assert_eq!(bash("git push origin main"), Some(PermissionAction::Ask));
+        assert_eq!(bash("git status"), Some(PermissionAction::Allow));
+        assert_eq!(bash("sudo rm -rf /"), Some(PermissionAction::Deny));
+        assert_eq!(
+            system.rule_action("webfetch", &json!({"url": "https://example.com"})),
+            Some(PermissionAction::Deny)
+        );
+        // A deny rule with an invalid pattern covers every call; an allow rule is skipped.
+        assert_eq!(
+            system.rule_action("read", &json!({"path": "a.rs"})),
+            Some(PermissionAction::Deny)
+        );
+        assert_eq!(
+            system.rule_action("glob", &json!({"pattern": "*.rs"})),
+            None
+        );
+    }
 
     #[test]
     fn test_untrusted_policy_matrix() {
         assert!(is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "read"));
         assert!(is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "grep"));
         assert!(is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "glob"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "write"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "edit"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "bash"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "task"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::Untrusted, "webfetch"));
+        assert!(is_auto_allowed_by_policy(
+            ApprovalPolicy::Untrusted,
+            "read_more"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::Untrusted,
+            "write"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::Untrusted,
+            "edit"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::Untrusted,
+            "resolve_conflicts"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::Untrusted,
+            "bash"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::Untrusted,
+            "task"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::Untrusted,
+            "webfetch"
+        ));
     }
 
     #[test]
@@ -113,9 +290,22 @@ mod tests {
         assert!(is_auto_allowed_by_policy(ApprovalPolicy::OnRequest, "glob"));
         assert!(is_auto_allowed_by_policy(ApprovalPolicy::OnRequest, "write"));
         assert!(is_auto_allowed_by_policy(ApprovalPolicy::OnRequest, "edit"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::OnRequest, "bash"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::OnRequest, "task"));
-        assert!(!is_auto_allowed_by_policy(ApprovalPolicy::OnRequest, "webfetch"));
+        assert!(is_auto_allowed_by_policy(
+            ApprovalPolicy::OnRequest,
+            "resolve_conflicts"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::OnRequest,
+            "bash"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::OnRequest,
+            "task"
+        ));
+        assert!(!is_auto_allowed_by_policy(
+            ApprovalPolicy::OnRequest,
+            "webfetch"
+        ));
     }
 
     #[test]
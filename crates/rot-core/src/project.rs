@@ -0,0 +1,305 @@
+//! Project instructions (`ROT.md`) and the repository survey used by `rot init`.
+//!
+//! `ROT.md` holds notes for the agent about one repository: how to build and
+//! test it, its layout, and conventions to follow. The nearest one at or
+//! above the working directory is appended to the system prompt.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Project instructions file, relative to a workspace directory.
+pub const INSTRUCTIONS_FILE: &str = "ROT.md";
+
+/// Largest instructions file read into the system prompt, in bytes.
+const MAX_INSTRUCTIONS_BYTES: usize = 32 * 1024;
+
+/// Top-level entries listed in a survey.
+const MAX_LAYOUT_ENTRIES: usize = 40;
+
+/// Path of the nearest `ROT.md` at or above `cwd`.
+pub fn find_instructions(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(INSTRUCTIONS_FILE))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Contents of the nearest `ROT.md`, truncated to a size that fits a prompt.
+pub fn load_instructions(cwd: &Path) -> Option<String> {
+    let content = fs::read_to_string(find_instructions(cwd)?).ok()?;
+    let content = content.trim();
+    if content.is_empty() {
+        return None;
+    }
+    let mut end = content.len().min(MAX_INSTRUCTIONS_BYTES);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(content[..end].to_string())
+}
+
+/// `system_prompt` followed by the project instructions for `cwd`, if any.
+pub fn with_instructions(system_prompt: String, cwd: &Path) -> String {
+    match load_instructions(cwd) {
+        Some(instructions) => {
+            format!("{system_prompt}\n\n## Project instructions ({INSTRUCTIONS_FILE})\n\n{instructions}")
+        }
+        None => system_prompt,
+    }
+}
+
+/// A toolchain recognized by a marker file in the repository root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toolchain {
+    pub language: &'static str,
+    /// File that identified the toolchain, such as `Cargo.toml`.
+    pub marker: &'static str,
+    pub build: Option<&'static str>,
+    pub test: Option<&'static str>,
+}
+
+/// Marker files, checked in order.
+const TOOLCHAINS: &[Toolchain] = &[
+    Toolchain {
+        language: "Rust",
+        marker: "Cargo.toml",
+        build: Some("cargo build"),
+        test: Some("cargo test"),
+    },
+    Toolchain {
+        language: "JavaScript/TypeScript",
+        marker: "package.json",
+        build: Some("npm run build"),
+        test: Some("npm test"),
+    },
+    Toolchain {
+        language: "Python",
+        marker: "pyproject.toml",
+        build: None,
+        test: Some("pytest"),
+    },
+    Toolchain {
+        language: "Python",
+        marker: "setup.py",
+        build: None,
+        test: Some("pytest"),
+    },
+    Toolchain {
+        language: "Go",
+        marker: "go.mod",
+        build: Some("go build ./..."),
+        test: Some("go test ./..."),
+    },
+    Toolchain {
+        language: "Java",
+        marker: "pom.xml",
+        build: Some("mvn package"),
+        test: Some("mvn test"),
+    },
+    Toolchain {
+        language: "Java/Kotlin",
+        marker: "build.gradle",
+        build: Some("./gradlew build"),
+        test: Some("./gradlew test"),
+    },
+    Toolchain {
+        language: "Java/Kotlin",
+        marker: "build.gradle.kts",
+        build: Some("./gradlew build"),
+        test: Some("./gradlew test"),
+    },
+    Toolchain {
+        language: "Ruby",
+        marker: "Gemfile",
+        build: None,
+        test: Some("bundle exec rake test"),
+    },
+    Toolchain {
+        language: "C/C++",
+        marker: "CMakeLists.txt",
+        build: Some("cmake --build build"),
+        test: Some("ctest --test-dir build"),
+    },
+    Toolchain {
+        language: "Make",
+        marker: "Makefile",
+        build: Some("make"),
+        test: Some("make test"),
+    },
+];
+
+/// What `rot init` learned about a repository without asking a model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectSurvey {
+    /// Directory name of the repository.
+    pub name: String,
+    pub toolchains: Vec<Toolchain>,
+    /// Top-level files and directories, directories with a trailing `/`.
+    pub layout: Vec<String>,
+    /// Opening of the README, if there is one.
+    pub readme: Option<String>,
+}
+
+impl ProjectSurvey {
+    /// Survey the repository rooted at `root`.
+    pub fn scan(root: &Path) -> Self {
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "project".to_string());
+        let toolchains = TOOLCHAINS
+            .iter()
+            .filter(|toolchain| root.join(toolchain.marker).is_file())
+            .cloned()
+            .collect();
+
+        let mut layout: Vec<String> = fs::read_dir(root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.starts_with('.') || matches!(name.as_str(), "target" | "node_modules") {
+                    return None;
+                }
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                Some(if is_dir { format!("{name}/") } else { name })
+            })
+            .collect();
+        layout.sort();
+        layout.truncate(MAX_LAYOUT_ENTRIES);
+
+        let readme = ["README.md", "README", "readme.md"]
+            .iter()
+            .find_map(|file| fs::read_to_string(root.join(file)).ok())
+            .map(|text| text.lines().take(30).collect::<Vec<_>>().join("\n"));
+
+        Self {
+            name,
+            toolchains,
+            layout,
+            readme,
+        }
+    }
+
+    fn commands(&self, pick: fn(&Toolchain) -> Option<&'static str>) -> Vec<&'static str> {
+        let mut commands: Vec<&'static str> = self.toolchains.iter().filter_map(pick).collect();
+        commands.dedup();
+        commands
+    }
+
+    /// The survey as plain text, for the model pass.
+    pub fn summary(&self) -> String {
+        let mut out = format!("Repository: {}\n", self.name);
+        for toolchain in &self.toolchains {
+            out.push_str(&format!(
+                "Found {} ({})\n",
+                toolchain.marker, toolchain.language
+            ));
+        }
+        out.push_str(&format!("Top-level entries: {}\n", self.layout.join(", ")));
+        if let Some(readme) = &self.readme {
+            out.push_str(&format!("\nREADME (opening):\n{readme}\n"));
+        }
+        out
+    }
+
+    /// Prompt asking a model to write `ROT.md` from the survey.
+    pub fn init_prompt(&self) -> String {
+        format!(
+            "Write a concise {INSTRUCTIONS_FILE} for this repository. It is read by a coding \
+             agent before every task. Use Markdown with these sections: Overview (one or two \
+             sentences), Build and test (exact commands in code blocks), Layout (the main \
+             directories and what they hold), and Conventions (only what the survey supports). \
+             Do not invent commands or directories. Reply with the file contents only.\n\n{}",
+            self.summary()
+        )
+    }
+
+    /// A starter `ROT.md` built from the survey alone.
+    pub fn starter_instructions(&self) -> String {
+        let mut out = format!("# {}\n\n", self.name);
+        let mut languages: Vec<&str> = self.toolchains.iter().map(|t| t.language).collect();
+        languages.dedup();
+        if !languages.is_empty() {
+            out.push_str(&format!("Languages: {}.\n\n", languages.join(", ")));
+        }
+
+        out.push_str("## Build and test\n\n");
+        let build = self.commands(|t| t.build);
+        let test = self.commands(|t| t.test);
+        if build.is_empty() && test.is_empty() {
+            out.push_str("<!-- Add the commands used to build and test this project. -->\n\n");
+        } else {
+            out.push_str("```bash\n");
+            for command in build.iter().chain(&test) {
+                out.push_str(command);
+                out.push('\n');
+            }
+            out.push_str("```\n\n");
+        }
+
+        out.push_str("## Layout\n\n");
+        for entry in &self.layout {
+            out.push_str(&format!("- `{entry}`\n"));
+        }
+        out.push_str(
+            "\n## Conventions\n\n<!-- Add coding conventions the agent should follow. -->\n",
+        );
+        out
+    }
+
+    /// A starter `.rot/config.toml` with commented project settings.
+    pub fn starter_config(&self) -> String {
+        let mut out = String::from(
+            "# Project settings for rot, merged over ~/.rot/config.json.\n\
+             # Commit this file to share them with everyone working in the repository.\n\n\
+             # Tighten the sandbox for this repository:\n\
+             # sandbox_mode = \"read-only\"\n\
+             # approval_policy = \"untrusted\"\n",
+        );
+        if !self.commands(|t| t.test).is_empty() {
+            out.push_str(
+                "\n[tool_timeouts]\n# Builds and test runs can take a while.\nbash = 600\n",
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_survey_detects_toolchains_and_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(dir.path().join("README.md"), "# Demo\n\nA demo crate.\n").unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+
+        let survey = ProjectSurvey::scan(dir.path());
+        assert_eq!(survey.toolchains.len(), 1);
+        assert_eq!(survey.layout, vec!["Cargo.toml", "README.md", "src/"]);
+
+        let starter = survey.starter_instructions();
+        assert!(starter.contains("```bash\ncargo build\ncargo test\n```"));
+        assert!(starter.contains("- `src/`"));
+        assert!(survey.starter_config().contains("bash = 600"));
+        assert!(survey.init_prompt().contains("A demo crate."));
+    }
+
+    #[test]
+    fn test_instructions_found_above_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("crates").join("core");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(with_instructions("base".to_string(), &nested), "base");
+
+        fs::write(dir.path().join(INSTRUCTIONS_FILE), "Run `make check`.\n").unwrap();
+        assert_eq!(
+            with_instructions("base".to_string(), &nested),
+            "base\n\n## Project instructions (ROT.md)\n\nRun `make check`."
+        );
+    }
+}
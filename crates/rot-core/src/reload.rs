@@ -0,0 +1,339 @@
+//! Config reload support for long-running sessions.
+//!
+//! [`ConfigWatcher`] notices edits to the config files, [`ConfigDiff`]
+//! describes what changed, and [`build_tool_registry`] rebuilds the tool set
+//! so a session can pick up new MCP servers and custom tools without a
+//! restart.
+
+use crate::config::{Config, ConfigStore};
+use crate::security::RuntimeSecurityConfig;
+use crate::RotError;
+use rot_tools::ToolRegistry;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Build the tool registry described by `config`: built-in tools, timeouts,
+/// custom tools, and MCP servers started under `runtime_security`.
+pub async fn build_tool_registry(
+    config: &Config,
+    runtime_security: &RuntimeSecurityConfig,
+    cwd: &Path,
+) -> Result<ToolRegistry, RotError> {
+    let mut tools = ToolRegistry::new();
+    rot_tools::register_all(&mut tools);
+    tools.set_default_timeout(Duration::from_secs(config.tool_timeout_secs.max(1)));
+    for (name, secs) in &config.tool_timeouts {
+        tools.set_timeout(name.clone(), Duration::from_secs((*secs).max(1)));
+    }
+    tools.set_output_policy(config.tool_output);
+    tools.set_command_policy(config.bash_commands.clone());
+    rot_tools::register_custom_tools(&mut tools, &config.custom_tools)
+        .map_err(|e| RotError::Other(format!("Failed to load custom tools: {e}")))?;
+    rot_tools::register_mcp_tools(
+        &mut tools,
+        &config.mcp_servers,
+        cwd,
+        runtime_security.tool_sandbox_mode(),
+        runtime_security.allows_network(),
+    )
+    .await
+    .map_err(|e| RotError::Other(format!("Failed to load MCP tools: {e}")))?;
+
+    Ok(tools)
+}
+
+/// Polls the global and project config files for modifications.
+///
+/// The project file is the one found when the store was created; a
+/// `.rot/config.toml` added later is not picked up until restart.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl ConfigWatcher {
+    /// Start watching the files backing `store`.
+    pub fn new(store: &ConfigStore) -> Self {
+        let files = std::iter::once(store.path())
+            .chain(store.project_path())
+            .map(|path| (path.to_path_buf(), modified(path)))
+            .collect();
+        Self { files }
+    }
+
+    /// Also watch `path`, such as a project `.mcp.json`.
+    pub fn watch(mut self, path: PathBuf) -> Self {
+        let stamp = modified(&path);
+        self.files.push((path, stamp));
+        self
+    }
+
+    /// Whether any watched file was created, removed, or modified since the
+    /// last call.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        for (path, stamp) in &mut self.files {
+            let current = modified(path);
+            if current != *stamp {
+                *stamp = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Differences between two loaded configs that matter to a running session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    /// Custom tools, MCP servers, tool timeouts, or bash command lists changed.
+    pub tools: bool,
+    /// `approval_policy`, `sandbox_mode`, `sandbox_network_access`, or
+    /// `permissions` changed.
+    pub security: bool,
+    /// Human-readable description of each change.
+    pub summary: Vec<String>,
+}
+
+impl ConfigDiff {
+    /// Compare the config a session started with against a freshly loaded one.
+    pub fn between(old: &Config, new: &Config) -> Self {
+        let mut diff = Self::default();
+
+        diff_named(
+            &mut diff,
+            "MCP server",
+            &old.mcp_servers,
+            &new.mcp_servers,
+            |s| &s.name,
+        );
+        diff_named(
+            &mut diff,
+            "custom tool",
+            &old.custom_tools,
+            &new.custom_tools,
+            |t| &t.name,
+        );
+
+        if old.tool_timeout_secs != new.tool_timeout_secs || old.tool_timeouts != new.tool_timeouts
+        {
+            diff.tools = true;
+            diff.summary.push("tool timeouts updated".to_string());
+        }
+        if old.bash_commands != new.bash_commands {
+            diff.tools = true;
+            diff.summary.push("bash command lists updated".to_string());
+        }
+
+        if old.approval_policy != new.approval_policy {
+            diff.security = true;
+            diff.summary.push(format!(
+                "approval_policy: {:?} → {:?}",
+                old.approval_policy, new.approval_policy
+            ));
+        }
+        if old.sandbox_mode != new.sandbox_mode {
+            diff.security = true;
+            diff.summary.push(format!(
+                "sandbox_mode: {:?} → {:?}",
+                old.sandbox_mode, new.sandbox_mode
+            ));
+        }
+        if old.sandbox_network_access != new.sandbox_network_access {
+            diff.security = true;
+            diff.summary.push(format!(
+                "sandbox_network_access: {} → {}",
+                old.sandbox_network_access, new.sandbox_network_access
+            ));
+        }
+        if old.permissions != new.permissions {
+            diff.security = true;
+            diff.summary.push("permission rules updated".to_string());
+        }
+
+        if old.max_iterations != new.max_iterations {
+            diff.summary.push(format!(
+                "max_iterations: {} → {}",
+                old.max_iterations, new.max_iterations
+            ));
+        }
+        if old.redaction != new.redaction {
+            diff.summary.push("redaction rules updated".to_string());
+        }
+        if old.aliases != new.aliases {
+            diff.summary.push("model aliases updated".to_string());
+        }
+        if old.rlm != new.rlm {
+            diff.summary.push("RLM settings updated".to_string());
+        }
+        if old.notifications != new.notifications {
+            diff.summary
+                .push("notification settings updated".to_string());
+        }
+        if old.hooks != new.hooks {
+            diff.summary.push("hooks updated".to_string());
+        }
+        if old.providers != new.providers {
+            diff.summary.push("custom providers updated".to_string());
+        }
+
+        diff
+    }
+
+    /// Whether nothing relevant changed.
+    pub fn is_empty(&self) -> bool {
+        self.summary.is_empty()
+    }
+
+    /// Apply changed security settings from `new` on top of `current`.
+    ///
+    /// Only settings that changed between the two configs are applied, so CLI
+    /// overrides for untouched settings survive a reload.
+    pub fn apply_security(
+        old: &Config,
+        new: &Config,
+        current: &RuntimeSecurityConfig,
+    ) -> RuntimeSecurityConfig {
+        let mut next = current.clone();
+        if old.approval_policy != new.approval_policy {
+            next.approval_policy = new.approval_policy;
+        }
+        if old.sandbox_mode != new.sandbox_mode {
+            next.sandbox_mode = new.sandbox_mode;
+        }
+        if old.sandbox_network_access != new.sandbox_network_access {
+            next.sandbox_network_access = new.sandbox_network_access;
+        }
+        if old.permissions != new.permissions {
+            next.permissions = new.permissions.clone();
+        }
+        next
+    }
+}
+
+fn diff_named<T: PartialEq>(
+    diff: &mut ConfigDiff,
+    label: &str,
+    before: &[T],
+    after: &[T],
+    name: fn(&T) -> &str,
+) {
+    let find = |entries: &[T], entry: &T| entries.iter().position(|e| name(e) == name(entry));
+    let added: Vec<&str> = after
+        .iter()
+        .filter(|e| find(before, e).is_none())
+        .map(name)
+        .collect();
+    let removed: Vec<&str> = before
+        .iter()
+        .filter(|e| find(after, e).is_none())
+        .map(name)
+        .collect();
+    let updated: Vec<&str> = after
+        .iter()
+        .filter(|e| find(before, e).is_some_and(|i| before[i] != **e))
+        .map(name)
+        .collect();
+
+    for (verb, names) in [("added", added), ("removed", removed), ("updated", updated)] {
+        if !names.is_empty() {
+            diff.tools = true;
+            diff.summary
+                .push(format!("{label} {verb}: {}", names.join(", ")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{ApprovalPolicy, SandboxMode};
+
+    fn config(json: &str) -> Config {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_diff_reports_tool_and_security_changes() {
+        let old = config(
+            r#"{"mcp_servers": [{"name": "fs", "command": "a"}, {"name": "gone", "command": "b"}]}"#,
+        );
+        let new = config(
+            r#"{
+                "approval_policy": "untrusted",
+                "mcp_servers": [{"name": "fs", "command": "a2"}, {"name": "gh", "command": "c"}],
+                "custom_tools": [{"name": "lint", "description": "d", "command": "make lint"}]
+            }"#,
+        );
+
+        let diff = ConfigDiff::between(&old, &new);
+        assert!(diff.tools);
+        assert!(diff.security);
+        assert!(diff.summary.contains(&"MCP server added: gh".to_string()));
+        assert!(diff
+            .summary
+            .contains(&"MCP server removed: gone".to_string()));
+        assert!(diff.summary.contains(&"MCP server updated: fs".to_string()));
+        assert!(diff
+            .summary
+            .contains(&"custom tool added: lint".to_string()));
+
+        assert!(ConfigDiff::between(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_apply_security_keeps_untouched_overrides() {
+        let old = config(r#"{"approval_policy": "on-request"}"#);
+        let new = config(r#"{"approval_policy": "untrusted"}"#);
+        let current = RuntimeSecurityConfig {
+            approval_policy: ApprovalPolicy::Never,
+            sandbox_mode: SandboxMode::DangerFullAccess,
+            ..Default::default()
+        };
+
+        let next = ConfigDiff::apply_security(&old, &new, &current);
+        assert_eq!(next.approval_policy, ApprovalPolicy::Untrusted);
+        assert_eq!(next.sandbox_mode, SandboxMode::DangerFullAccess);
+    }
+
+    #[test]
+    fn test_permission_changes_are_security_changes() {
+        let old = config("{}");
+        let new = config(r#"{"permissions": [{"tool": "bash", "action": "deny"}]}"#);
+
+        let diff = ConfigDiff::between(&old, &new);
+        assert!(diff.security);
+        assert!(!diff.tools);
+        assert_eq!(diff.summary, vec!["permission rules updated".to_string()]);
+
+        let next = ConfigDiff::apply_security(&old, &new, &RuntimeSecurityConfig::default());
+        assert_eq!(next.permissions, new.permissions);
+    }
+
+    #[test]
+    fn test_bash_command_changes_rebuild_tools() {
+        let old = config(r#"{"bash_commands": {"allow": ["git status"]}}"#);
+        let new = config(r#"{"bash_commands": {"allow": ["git status"], "deny": ["rm -rf"]}}"#);
+
+        let diff = ConfigDiff::between(&old, &new);
+        assert!(diff.tools);
+        assert_eq!(diff.summary, vec!["bash command lists updated".to_string()]);
+    }
+
+    #[test]
+    fn test_watcher_detects_modification() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let store = ConfigStore::with_paths(path.clone(), None);
+        let mut watcher = ConfigWatcher::new(&store);
+        assert!(!watcher.poll());
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+    }
+}
@@ -0,0 +1,163 @@
+//! What one run cost, for `rot exec --json` and the session file.
+//!
+//! A [`RunSummary`] is built once a run ends from the messages it added and
+//! the provider requests it made: wall time, time spent on provider requests
+//! and in tools, tokens and spend by model and agent, the tools called, the
+//! files written or edited (by the run or its subagents), and the subagents
+//! it delegated to.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use rot_session::SessionEntry;
+use serde::Serialize;
+
+use crate::message::{tool_exchanges, Message, MessageId};
+use crate::usage::UsageLedger;
+
+/// Resource use of one run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunSummary {
+    pub wall_ms: u64,
+    /// Time waiting on provider requests, summed; parallel subagent requests
+    /// each count in full.
+    pub provider_ms: u64,
+    /// Time the top-level agent's tool calls ran, delegated tasks included.
+    pub tool_ms: u64,
+    /// Requests, tokens, and spend overall, by model, and by agent.
+    pub usage: UsageLedger,
+    /// Tool calls by tool name.
+    pub tools: BTreeMap<String, usize>,
+    pub tool_errors: usize,
+    /// Files written or edited, including by subagents, in first-touch order.
+    pub files_modified: Vec<String>,
+    pub subagents: usize,
+}
+
+impl RunSummary {
+    /// Summarize a run that added `messages` and made the requests in `usage`.
+    pub fn new(
+        messages: &[Message],
+        usage: UsageLedger,
+        wall: Duration,
+        provider: Duration,
+    ) -> Self {
+        let mut summary = Self {
+            wall_ms: wall.as_millis() as u64,
+            provider_ms: provider.as_millis() as u64,
+            usage,
+            ..Self::default()
+        };
+        for exchange in tool_exchanges(messages) {
+            *summary.tools.entry(exchange.name.clone()).or_default() += 1;
+            summary.tool_errors += usize::from(exchange.is_error);
+            summary.tool_ms += exchange.metadata["duration_ms"].as_u64().unwrap_or(0);
+            if exchange.is_error || exchange.output.is_none() {
+                continue;
+            }
+            let touched = match exchange.name.as_str() {
+                "write" | "edit" => crate::audit::touched_files(&exchange.arguments),
+                "resolve_conflicts" if exchange.arguments["action"] == "resolve" => {
+                    crate::audit::touched_files(&exchange.arguments)
+                }
+                "task" => {
+                    summary.subagents += 1;
+                    exchange.metadata["report"]["files_changed"]
+                        .as_array()
+                        .map(|files| {
+                            files
+                                .iter()
+                                .filter_map(|f| f.as_str())
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                }
+                _ => Vec::new(),
+            };
+            for file in touched {
+                if !summary.files_modified.contains(&file) {
+                    summary.files_modified.push(file);
+                }
+            }
+        }
+        summary
+    }
+
+    /// The session entry recording this summary.
+    pub fn to_entry(&self) -> SessionEntry {
+        SessionEntry::RunSummary {
+            id: MessageId::new().as_str().to_string(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            summary: serde_json::to_value(self).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::ContentBlock;
+
+    #[test]
+    fn test_run_summary_counts_tools_files_and_subagents() {
+        let call = |id: &str, name: &str, arguments: serde_json::Value| {
+            Message::assistant(vec![ContentBlock::ToolCall {
+                id: id.to_string(),
+                name: name.to_string(),
+                arguments,
+            }])
+        };
+        let messages = vec![
+            call("1", "edit", serde_json::json!({"path": "src/lib.rs"})),
+            Message::tool_result_with_metadata(
+                "1",
+                "edited",
+                false,
+                serde_json::json!({"duration_ms": 5}),
+            ),
+            call("2", "bash", serde_json::json!({"command": "cargo test"})),
+            Message::tool_result_with_metadata(
+                "2",
+                "failed",
+                true,
+                serde_json::json!({"duration_ms": 900}),
+            ),
+            call("3", "task", serde_json::json!({"prompt": "fix docs"})),
+            Message::tool_result_with_metadata(
+                "3",
+                "done",
+                false,
+                serde_json::json!({
+                    "duration_ms": 2000,
+                    "report": {"files_changed": ["README.md", "src/lib.rs"]}
+                }),
+            ),
+        ];
+        let mut usage = UsageLedger::default();
+        usage.record("gpt-4o", "build", 1000, 200);
+
+        let summary = RunSummary::new(
+            &messages,
+            usage,
+            Duration::from_secs(4),
+            Duration::from_secs(1),
+        );
+        assert_eq!(summary.wall_ms, 4000);
+        assert_eq!(summary.provider_ms, 1000);
+        assert_eq!(summary.tool_ms, 2905);
+        assert_eq!(summary.tools["bash"], 1);
+        assert_eq!(summary.tool_errors, 1);
+        assert_eq!(summary.files_modified, ["src/lib.rs", "README.md"]);
+        assert_eq!(summary.subagents, 1);
+
+        let SessionEntry::RunSummary { summary: value, .. } = summary.to_entry() else {
+            panic!("expected a run summary entry");
+        };
+        assert_eq!(value["usage"]["total"]["input_tokens"], 1000);
+        assert_eq!(value["usage"]["by_agent"]["build"]["output_tokens"], 200);
+    }
+}
@@ -0,0 +1,220 @@
+//! Config schema versioning, migrations, and unknown-key checks.
+
+use crate::config::Config;
+use serde_json::Value;
+
+/// Current config schema version, written to the `version` key on save.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// One upgrade step; entry `i` migrates a value from version `i` to `i + 1`
+/// and returns a description of anything it rewrote.
+type Migration = fn(&mut serde_json::Map<String, Value>) -> Vec<String>;
+
+const MIGRATIONS: &[Migration] = &[
+    // 0 → 1: unversioned configs predate the `version` key; the layout is unchanged.
+    |_| Vec::new(),
+];
+
+/// Schema version recorded in a raw config value. Unversioned configs are version 0.
+pub fn version_of(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// Upgrade a raw config value to [`CONFIG_VERSION`] in place.
+///
+/// Returns the notes produced by each migration that ran. A config written by
+/// a newer `rot` is left untouched.
+pub fn migrate(value: &mut Value) -> Vec<String> {
+    let from = version_of(value);
+    if from >= CONFIG_VERSION {
+        return Vec::new();
+    }
+    let Value::Object(map) = value else {
+        return Vec::new();
+    };
+
+    let mut notes = Vec::new();
+    for (version, step) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        for note in step(map) {
+            notes.push(format!("v{version}→v{}: {note}", version + 1));
+        }
+    }
+    map.insert("version".to_string(), Value::from(CONFIG_VERSION));
+    notes
+}
+
+/// Warnings for keys that `rot` does not recognise in a raw config value.
+///
+/// `source` names the file or section in each message. Top-level keys,
+/// `redaction`, and entries of `mcp_servers`, `custom_tools`, and
+/// `permissions` are checked.
+pub fn unknown_keys(value: &Value, source: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let Value::Object(map) = value else {
+        return warnings;
+    };
+
+    let top = known_keys(&serde_json::to_value(Config::default()).unwrap_or_default());
+    check_keys(map, &top, source, &mut warnings);
+
+    if let Some(Value::Object(redaction)) = map.get("redaction") {
+        let known = known_keys(
+            &serde_json::to_value(crate::RedactionConfig::default()).unwrap_or_default(),
+        );
+        check_keys(
+            redaction,
+            &known,
+            &format!("{source} redaction"),
+            &mut warnings,
+        );
+    }
+
+    for (section, known) in [
+        (
+            "mcp_servers",
+            sample_keys::<rot_tools::McpServerConfig>(
+                serde_json::json!({"name": "", "command": ""}),
+            ),
+        ),
+        (
+            "custom_tools",
+            sample_keys::<rot_tools::CustomToolConfig>(
+                serde_json::json!({"name": "", "description": "", "command": ""}),
+            ),
+        ),
+        (
+            "permissions",
+            sample_keys::<crate::permission::PermissionRule>(
+                serde_json::json!({"tool": "", "action": "ask"}),
+            ),
+        ),
+    ] {
+        for entry in map
+            .get(section)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if let Value::Object(entry_map) = entry {
+                let name = entry_map
+                    .get("name")
+                    .or_else(|| entry_map.get("tool"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("?");
+                check_keys(
+                    entry_map,
+                    &known,
+                    &format!("{source} {section} '{name}'"),
+                    &mut warnings,
+                );
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Field names of `T`, found by round-tripping a minimal instance.
+fn sample_keys<T: serde::de::DeserializeOwned + serde::Serialize>(sample: Value) -> Vec<String> {
+    serde_json::from_value::<T>(sample)
+        .ok()
+        .and_then(|v| serde_json::to_value(v).ok())
+        .map(|v| known_keys(&v))
+        .unwrap_or_default()
+}
+
+fn known_keys(value: &Value) -> Vec<String> {
+    value
+        .as_object()
+        .map(|map| map.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn check_keys(
+    map: &serde_json::Map<String, Value>,
+    known: &[String],
+    source: &str,
+    warnings: &mut Vec<String>,
+) {
+    for key in map.keys() {
+        if known.iter().any(|k| k == key) {
+            continue;
+        }
+        match suggest(key, known) {
+            Some(s) => warnings.push(format!(
+                "{source}: unknown key '{key}' (did you mean '{s}'?)"
+            )),
+            None => warnings.push(format!("{source}: unknown key '{key}'")),
+        }
+    }
+}
+
+/// Closest known key within a small edit distance.
+fn suggest<'a>(key: &str, known: &'a [String]) -> Option<&'a str> {
+    let limit = (key.len() / 3).max(2);
+    known
+        .iter()
+        .map(|k| (edit_distance(key, k), k))
+        .filter(|(d, _)| *d <= limit)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k.as_str())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            row[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_stamps_unversioned_config() {
+        let mut value = serde_json::json!({"model": "glm-5"});
+        assert_eq!(version_of(&value), 0);
+        migrate(&mut value);
+        assert_eq!(version_of(&value), CONFIG_VERSION);
+        assert_eq!(value["model"], "glm-5");
+
+        let mut newer = serde_json::json!({"version": CONFIG_VERSION + 1});
+        assert!(migrate(&mut newer).is_empty());
+        assert_eq!(version_of(&newer), CONFIG_VERSION + 1);
+    }
+
+    #[test]
+    fn test_unknown_keys_suggest_close_matches() {
+        let value = serde_json::json!({
+            "modle": "gpt-4o",
+            "sandbox_mode": "read-only",
+            "totally_unrelated": true,
+            "redaction": {"enabeld": false},
+            "mcp_servers": [{"name": "fs", "command": "npx", "arg": ["-y"]}]
+        });
+        let warnings = unknown_keys(&value, "config.json");
+
+        assert!(warnings
+            .contains(&"config.json: unknown key 'modle' (did you mean 'model'?)".to_string()));
+        assert!(warnings.contains(&"config.json: unknown key 'totally_unrelated'".to_string()));
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("redaction") && w.contains("'enabled'")));
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("mcp_servers 'fs'") && w.contains("'args'")));
+        assert_eq!(warnings.len(), 4);
+    }
+}
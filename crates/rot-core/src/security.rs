@@ -1,5 +1,6 @@
 //! Runtime security configuration and policy types.
 
+use crate::permission::PermissionRule;
 use serde::{Deserialize, Serialize};
 
 /// Approval behavior for tool execution.
@@ -15,6 +16,24 @@ pub enum ApprovalPolicy {
     Never,
 }
 
+impl ApprovalPolicy {
+    /// Parse a policy written as in config: `untrusted`, `on-request`, or `never`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Untrusted, Self::OnRequest, Self::Never]
+            .into_iter()
+            .find(|policy| policy.as_str() == name)
+    }
+
+    /// The policy as written in config and on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Untrusted => "untrusted",
+            Self::OnRequest => "on-request",
+            Self::Never => "never",
+        }
+    }
+}
+
 /// Filesystem sandbox mode for tool execution.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
@@ -28,6 +47,25 @@ pub enum SandboxMode {
     DangerFullAccess,
 }
 
+impl SandboxMode {
+    /// Parse a mode written as in config: `read-only`, `workspace-write`, or
+    /// `danger-full-access`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::ReadOnly, Self::WorkspaceWrite, Self::DangerFullAccess]
+            .into_iter()
+            .find(|mode| mode.as_str() == name)
+    }
+
+    /// The mode as written in config and on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read-only",
+            Self::WorkspaceWrite => "workspace-write",
+            Self::DangerFullAccess => "danger-full-access",
+        }
+    }
+}
+
 /// Effective runtime security options after CLI/config resolution.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct RuntimeSecurityConfig {
@@ -37,4 +75,29 @@ pub struct RuntimeSecurityConfig {
     pub sandbox_mode: SandboxMode,
     /// Whether outbound network access is permitted.
     pub sandbox_network_access: bool,
+    /// Simulate mutating tools (write, edit, bash, custom and MCP tools) instead of running them.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Append-only log that records every tool call; see [`crate::audit`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<std::path::PathBuf>,
+    /// Configured per-tool rules, checked before the approval policy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<PermissionRule>,
+}
+
+impl RuntimeSecurityConfig {
+    /// The sandbox mode in the form tools take it.
+    pub fn tool_sandbox_mode(&self) -> rot_tools::SandboxMode {
+        match self.sandbox_mode {
+            SandboxMode::ReadOnly => rot_tools::SandboxMode::ReadOnly,
+            SandboxMode::WorkspaceWrite => rot_tools::SandboxMode::WorkspaceWrite,
+            SandboxMode::DangerFullAccess => rot_tools::SandboxMode::DangerFullAccess,
+        }
+    }
+
+    /// Whether tools may reach the network: allowed explicitly or unsandboxed.
+    pub fn allows_network(&self) -> bool {
+        self.sandbox_network_access || self.sandbox_mode == SandboxMode::DangerFullAccess
+    }
 }
@@ -0,0 +1,339 @@
+//! Keeping the fixed part of every request under a token budget.
+//!
+//! Each request repeats the system prompt, project instructions included, and
+//! the schema of every registered tool. A few MCP servers can add dozens of
+//! tools with long descriptions. When the total passes
+//! [`ToolBudgetConfig::max_tokens`], MCP tool descriptions are shortened
+//! first. If that is not enough, MCP tools the conversation has not used are
+//! left out and offered through the [`LIST_MORE_TOOLS`] meta-tool, which
+//! lists them and loads the ones the model asks for.
+
+use crate::message::{ContentBlock, Message};
+use rot_provider::ToolDefinition;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Name of the meta-tool that lists and loads deferred tools.
+pub const LIST_MORE_TOOLS: &str = "list_more_tools";
+
+/// Prefix of tools exported from MCP servers.
+const MCP_PREFIX: &str = "mcp__";
+
+/// Limits on the system prompt and tool schemas sent with each request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolBudgetConfig {
+    /// Estimated tokens the system prompt and tool schemas may take; `0`
+    /// sends everything as is.
+    pub max_tokens: usize,
+    /// Characters kept of an MCP tool description once over budget.
+    pub mcp_description_chars: usize,
+}
+
+impl Default for ToolBudgetConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: 12_000,
+            mcp_description_chars: 200,
+        }
+    }
+}
+
+/// The tools for one request, after budgeting.
+#[derive(Debug, Clone, Default)]
+pub struct BudgetedTools {
+    /// Definitions to send, including [`LIST_MORE_TOOLS`] when anything was deferred.
+    pub tools: Vec<ToolDefinition>,
+    /// Tools left out, in full.
+    pub deferred: Vec<ToolDefinition>,
+    /// Estimated tokens of the system prompt and `tools`.
+    pub tokens: usize,
+}
+
+/// Rough token count of a system prompt and tool definitions, at about four
+/// characters per token.
+pub fn estimate_tokens(system: Option<&str>, tools: &[ToolDefinition]) -> usize {
+    let chars: usize =
+        system.map_or(0, str::len) + tools.iter().map(definition_chars).sum::<usize>();
+    chars / 4
+}
+
+fn definition_chars(tool: &ToolDefinition) -> usize {
+    tool.name.len() + tool.description.len() + tool.parameters.to_string().len()
+}
+
+/// Fit `tools` and `system` into `config.max_tokens`. Tools called or loaded
+/// earlier in `messages` are never deferred; the rest go largest first.
+pub fn apply(
+    config: &ToolBudgetConfig,
+    system: Option<&str>,
+    mut tools: Vec<ToolDefinition>,
+    messages: &[Message],
+) -> BudgetedTools {
+    let mut tokens = estimate_tokens(system, &tools);
+    if config.max_tokens == 0 || tokens <= config.max_tokens {
+        return BudgetedTools {
+            tools,
+            deferred: Vec::new(),
+            tokens,
+        };
+    }
+
+    for tool in tools.iter_mut().filter(|tool| is_mcp(&tool.name)) {
+        tool.description = shorten(&tool.description, config.mcp_description_chars);
+    }
+    tokens = estimate_tokens(system, &tools);
+    if tokens <= config.max_tokens {
+        return BudgetedTools {
+            tools,
+            deferred: Vec::new(),
+            tokens,
+        };
+    }
+
+    let used = used_tools(messages);
+    let mut candidates: Vec<usize> = (0..tools.len())
+        .filter(|&i| is_mcp(&tools[i].name) && !used.contains(&tools[i].name))
+        .collect();
+    candidates.sort_by_key(|&i| std::cmp::Reverse(definition_chars(&tools[i])));
+
+    let mut over = tokens.saturating_sub(config.max_tokens) * 4 + definition_chars(&meta_tool(0));
+    let mut deferred_indices = HashSet::new();
+    for i in candidates {
+        if over == 0 {
+            break;
+        }
+        over = over.saturating_sub(definition_chars(&tools[i]));
+        deferred_indices.insert(i);
+    }
+    if deferred_indices.is_empty() {
+        return BudgetedTools {
+            tools,
+            deferred: Vec::new(),
+            tokens,
+        };
+    }
+
+    let mut kept = Vec::new();
+    let mut deferred = Vec::new();
+    for (i, tool) in tools.into_iter().enumerate() {
+        if deferred_indices.contains(&i) {
+            deferred.push(tool);
+        } else {
+            kept.push(tool);
+        }
+    }
+    kept.push(meta_tool(deferred.len()));
+    BudgetedTools {
+        tokens: estimate_tokens(system, &kept),
+        tools: kept,
+        deferred,
+    }
+}
+
+/// Answer a [`LIST_MORE_TOOLS`] call: the deferred tools with a one-line
+/// summary, or the full definitions of the ones named in `names`.
+pub fn list_more_tools(args: &serde_json::Value, deferred: &[ToolDefinition]) -> String {
+    let names: Vec<&str> = args
+        .get("names")
+        .and_then(|names| names.as_array())
+        .map(|names| names.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_default();
+    if deferred.is_empty() {
+        return "No tools are deferred; every tool is already available.".to_string();
+    }
+    if names.is_empty() {
+        let mut out = String::from("Deferred tools; call again with `names` to load some:\n");
+        for tool in deferred {
+            let summary = tool.description.lines().next().unwrap_or_default();
+            out.push_str(&format!("- {}: {}\n", tool.name, shorten(summary, 120)));
+        }
+        return out;
+    }
+
+    let mut out = String::from("Loaded; these tools can be called from now on:\n");
+    for name in names {
+        match deferred.iter().find(|tool| tool.name == name) {
+            Some(tool) => out.push_str(&format!(
+                "\n## {}\n{}\nParameters: {}\n",
+                tool.name, tool.description, tool.parameters
+            )),
+            None => out.push_str(&format!("\n## {name}\nNot a deferred tool.\n")),
+        }
+    }
+    out
+}
+
+fn meta_tool(deferred: usize) -> ToolDefinition {
+    ToolDefinition {
+        name: LIST_MORE_TOOLS.to_string(),
+        description: format!(
+            "{deferred} more tools are available but left out to save space. Call with no \
+             arguments to list them, or with `names` to get their parameters and use them."
+        ),
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "names": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Tools to load"
+                }
+            }
+        }),
+    }
+}
+
+/// Tools the conversation has called, or loaded through [`LIST_MORE_TOOLS`].
+fn used_tools(messages: &[Message]) -> HashSet<String> {
+    let mut used = HashSet::new();
+    for block in messages.iter().flat_map(|message| &message.content) {
+        let ContentBlock::ToolCall {
+            name, arguments, ..
+        } = block
+        else {
+            continue;
+        };
+        if name != LIST_MORE_TOOLS {
+            used.insert(name.clone());
+            continue;
+        }
+        let loaded = arguments.get("names").and_then(|names| names.as_array());
+        for name in loaded
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str())
+        {
+            used.insert(name.to_string());
+        }
+    }
+    used
+}
+
+fn is_mcp(name: &str) -> bool {
+    name.starts_with(MCP_PREFIX)
+}
+
+/// The first paragraph of `text`, cut to `max_chars`.
+fn shorten(text: &str, max_chars: usize) -> String {
+    let paragraph = text.split("\n\n").next().unwrap_or_default().trim();
+    if paragraph.chars().count() <= max_chars {
+        return paragraph.to_string();
+    }
+    let cut: String = paragraph
+        .chars()
+        .take(max_chars.saturating_sub(1))
+        .collect();
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str, description: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters: serde_json::json!({ "type": "object" }),
+        }
+    }
+
+    fn tools() -> Vec<ToolDefinition> {
+        vec![
+            tool("read", &"Read a file. ".repeat(20)),
+            tool(
+                "mcp__gh__issues",
+                &format!("List issues.\n\n{}", "Details. ".repeat(200)),
+            ),
+            tool("mcp__gh__pulls", &"List pull requests. ".repeat(100)),
+            tool("mcp__db__query", &"Run a query. ".repeat(150)),
+        ]
+    }
+
+    #[test]
+    fn test_under_budget_is_unchanged() {
+        let config = ToolBudgetConfig {
+            max_tokens: 100_000,
+            ..Default::default()
+        };
+        let budgeted = apply(&config, Some("system"), tools(), &[]);
+        assert_eq!(budgeted.tools.len(), 4);
+        assert!(budgeted.deferred.is_empty());
+        assert_eq!(budgeted.tools[1].description, tools()[1].description);
+
+        let off = ToolBudgetConfig {
+            max_tokens: 0,
+            ..Default::default()
+        };
+        assert!(apply(&off, None, tools(), &[]).deferred.is_empty());
+    }
+
+    #[test]
+    fn test_trims_mcp_descriptions_first() {
+        let config = ToolBudgetConfig {
+            max_tokens: 400,
+            mcp_description_chars: 50,
+        };
+        let budgeted = apply(&config, None, tools(), &[]);
+        assert!(budgeted.deferred.is_empty());
+        assert!(budgeted.tokens <= 400);
+        assert_eq!(budgeted.tools[0].description, tools()[0].description);
+        assert_eq!(budgeted.tools[1].description, "List issues.");
+        assert_eq!(budgeted.tools[2].description.chars().count(), 50);
+        assert!(budgeted.tools[2].description.ends_with('…'));
+    }
+
+    #[test]
+    fn test_defers_unused_mcp_tools() {
+        let config = ToolBudgetConfig {
+            max_tokens: 150,
+            mcp_description_chars: 200,
+        };
+        let used = vec![Message::assistant(vec![ContentBlock::ToolCall {
+            id: "call_1".to_string(),
+            name: "mcp__gh__pulls".to_string(),
+            arguments: serde_json::json!({}),
+        }])];
+        let budgeted = apply(&config, Some("system"), tools(), &used);
+
+        let sent: Vec<&str> = budgeted
+            .tools
+            .iter()
+            .map(|tool| tool.name.as_str())
+            .collect();
+        assert!(sent.contains(&"read"));
+        assert!(sent.contains(&"mcp__gh__pulls"));
+        assert_eq!(sent.last(), Some(&LIST_MORE_TOOLS));
+        assert!(!budgeted.deferred.is_empty());
+        assert!(budgeted
+            .deferred
+            .iter()
+            .all(|tool| tool.name != "mcp__gh__pulls"));
+        assert!(budgeted
+            .deferred
+            .iter()
+            .all(|tool| !sent.contains(&tool.name.as_str())));
+
+        let listing = list_more_tools(&serde_json::json!({}), &budgeted.deferred);
+        assert!(listing.contains(&format!("- {}:", budgeted.deferred[0].name)));
+        let name = budgeted.deferred[0].name.clone();
+        let loaded = list_more_tools(
+            &serde_json::json!({ "names": [name, "nope"] }),
+            &budgeted.deferred,
+        );
+        assert!(loaded.contains(&format!("## {name}\n")));
+        assert!(loaded.contains("Parameters: {\"type\":\"object\"}"));
+        assert!(loaded.contains("## nope\nNot a deferred tool."));
+
+        // Once loaded, the tool is sent again.
+        let mut history = used;
+        history.push(Message::assistant(vec![ContentBlock::ToolCall {
+            id: "call_2".to_string(),
+            name: LIST_MORE_TOOLS.to_string(),
+            arguments: serde_json::json!({ "names": [name] }),
+        }]));
+        let budgeted = apply(&config, Some("system"), tools(), &history);
+        assert!(budgeted.tools.iter().any(|tool| tool.name == name));
+    }
+}
@@ -0,0 +1,184 @@
+//! Conversion between conversation messages and session transcript entries.
+
+use crate::message::{ContentBlock, Message, MessageId, Role};
+use rot_session::SessionEntry;
+
+/// Session entries recording `messages`, with a `ToolCall` or `ToolResult`
+/// entry after each message for every tool block it contains.
+pub fn to_session_entries(messages: &[Message]) -> Result<Vec<SessionEntry>, serde_json::Error> {
+    let mut entries = Vec::new();
+
+    for message in messages {
+        entries.push(SessionEntry::Message {
+            id: message.id.to_string(),
+            parent_id: message.parent_id.as_ref().map(ToString::to_string),
+            timestamp: message.timestamp,
+            role: message.role.to_string(),
+            content: serde_json::to_value(&message.content)?,
+        });
+
+        for (idx, block) in message.content.iter().enumerate() {
+            match block {
+                ContentBlock::ToolCall {
+                    id,
+                    name,
+                    arguments,
+                } => entries.push(SessionEntry::ToolCall {
+                    id: id.clone(),
+                    parent_id: message.id.to_string(),
+                    timestamp: message.timestamp,
+                    name: name.clone(),
+                    arguments: arguments.clone(),
+                }),
+                ContentBlock::ToolResult {
+                    tool_call_id,
+                    content,
+                    is_error,
+                    ..
+                } => entries.push(SessionEntry::ToolResult {
+                    id: format!("{}:tool_result:{idx}", message.id),
+                    call_id: tool_call_id.clone(),
+                    timestamp: message.timestamp,
+                    output: content.clone(),
+                    is_error: *is_error,
+                }),
+                ContentBlock::Text { .. }
+                | ContentBlock::Image { .. }
+                | ContentBlock::Thinking { .. } => {}
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Rebuild the conversation recorded in `entries`.
+///
+/// A `Branch` entry rewinds to the message it branches from, so the result
+/// is the branch that was active last. A `Compaction` entry replaces the
+/// messages before its first kept message with the summary. Messages that no longer parse are
+/// skipped.
+pub fn from_session_entries(entries: &[SessionEntry]) -> Vec<Message> {
+    let mut messages: Vec<Message> = Vec::new();
+
+    for entry in entries {
+        match entry {
+            SessionEntry::Message {
+                id,
+                parent_id,
+                timestamp,
+                role,
+                content,
+            } => {
+                let role = serde_json::from_value::<Role>(serde_json::Value::String(role.clone()));
+                let content = serde_json::from_value::<Vec<ContentBlock>>(content.clone());
+                let (Ok(role), Ok(content)) = (role, content) else {
+                    tracing::warn!("Skipping unreadable session message {id}");
+                    continue;
+                };
+                messages.push(Message {
+                    id: MessageId::from_string(id.clone()),
+                    role,
+                    content,
+                    timestamp: *timestamp,
+                    parent_id: parent_id.clone().map(MessageId::from_string),
+                    usage: None,
+                });
+            }
+            SessionEntry::Compaction {
+                summary,
+                first_kept_id,
+                ..
+            } => {
+                let first_kept = messages
+                    .iter()
+                    .position(|message| message.id.as_str() == first_kept_id)
+                    .unwrap_or(messages.len());
+                messages.drain(..first_kept);
+                messages.insert(0, crate::compaction::summary_message(summary));
+            }
+            SessionEntry::Branch { from_id, .. } => {
+                let keep = messages
+                    .iter()
+                    .position(|message| message.id.as_str() == from_id)
+                    .map(|idx| idx + 1)
+                    .unwrap_or(0);
+                messages.truncate(keep);
+            }
+            _ => {}
+        }
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_session_entries_emits_tool_entries() {
+        let message = Message::assistant(vec![
+            ContentBlock::Text {
+                text: "hello".to_string(),
+            },
+            ContentBlock::ToolCall {
+                id: "call-1".to_string(),
+                name: "read".to_string(),
+                arguments: serde_json::json!({"path":"src/main.rs"}),
+            },
+        ]);
+        let tool_result = Message::tool_result("call-1", "ok", false);
+
+        let entries = to_session_entries(&[message, tool_result]).unwrap();
+        assert!(entries
+            .iter()
+            .any(|entry| matches!(entry, SessionEntry::Message { .. })));
+        assert!(entries
+            .iter()
+            .any(|entry| matches!(entry, SessionEntry::ToolCall { name, .. } if name == "read")));
+        assert!(entries.iter().any(
+            |entry| matches!(entry, SessionEntry::ToolResult { call_id, .. } if call_id == "call-1")
+        ));
+    }
+
+    #[test]
+    fn test_from_session_entries_follows_branches() {
+        let first = Message::user("first");
+        let reply = Message::assistant(vec![ContentBlock::Text {
+            text: "reply".to_string(),
+        }]);
+        let abandoned = Message::user("abandoned");
+        let mut entries = to_session_entries(&[first.clone(), reply.clone(), abandoned]).unwrap();
+        entries.push(SessionEntry::Branch {
+            id: "branch-1".to_string(),
+            from_id: reply.id.to_string(),
+            timestamp: 0,
+            label: None,
+        });
+        entries.extend(to_session_entries(&[Message::user("edited")]).unwrap());
+
+        let messages = from_session_entries(&entries);
+        let texts: Vec<String> = messages.iter().map(Message::text).collect();
+        assert_eq!(texts, vec!["first", "reply", "edited"]);
+        assert_eq!(messages[0], first);
+    }
+
+    #[test]
+    fn test_from_session_entries_applies_compaction() {
+        let old = Message::user("old question");
+        let kept = Message::user("recent question");
+        let mut entries = to_session_entries(&[old, kept.clone()]).unwrap();
+        entries.push(SessionEntry::Compaction {
+            id: "compaction-1".to_string(),
+            timestamp: 0,
+            summary: "asked an old question".to_string(),
+            first_kept_id: kept.id.to_string(),
+        });
+
+        let messages = from_session_entries(&entries);
+        assert_eq!(messages.len(), 2);
+        assert!(crate::compaction::is_summary_message(&messages[0]));
+        assert_eq!(messages[1], kept);
+    }
+}
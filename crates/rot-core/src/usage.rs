@@ -0,0 +1,295 @@
+//! Token and spend totals, grouped by model and by agent.
+//!
+//! The TUI keeps a [`UsageLedger`] for the running session; saved sessions
+//! rebuild theirs from [`SessionEntry::Usage`] entries with [`session_usage`],
+//! and [`group_usage`] adds them up across sessions for `rot usage`.
+
+use std::collections::BTreeMap;
+
+use rot_provider::pricing_for;
+use rot_session::SessionEntry;
+use serde::Serialize;
+
+use crate::agent::UsageReport;
+use crate::message::MessageId;
+
+/// Tokens and spend for one group of requests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub requests: usize,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    /// Spend in USD; `None` while every request was made with an unpriced model.
+    pub cost: Option<f64>,
+}
+
+impl UsageTotals {
+    fn add(&mut self, model: &str, input: usize, output: usize) {
+        self.requests += 1;
+        self.input_tokens += input;
+        self.output_tokens += output;
+        if let Some(pricing) = pricing_for(model) {
+            *self.cost.get_or_insert(0.0) += pricing.cost(input, output);
+        }
+    }
+
+    /// Fold `other` into these totals.
+    pub fn merge(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        if let Some(cost) = other.cost {
+            *self.cost.get_or_insert(0.0) += cost;
+        }
+    }
+}
+
+/// Tokens used across one run, by the agent and its delegated tasks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UsageSummary {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+}
+
+impl UsageSummary {
+    /// Add one provider request.
+    pub fn record(&mut self, report: &UsageReport) {
+        self.input_tokens += report.input_tokens;
+        self.output_tokens += report.output_tokens;
+    }
+}
+
+/// Usage totals overall, per model, and per agent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageLedger {
+    pub total: UsageTotals,
+    pub by_model: BTreeMap<String, UsageTotals>,
+    pub by_agent: BTreeMap<String, UsageTotals>,
+}
+
+impl UsageLedger {
+    /// Record one provider request.
+    pub fn record(&mut self, model: &str, agent: &str, input: usize, output: usize) {
+        self.total.add(model, input, output);
+        self.by_model
+            .entry(model.to_string())
+            .or_default()
+            .add(model, input, output);
+        self.by_agent
+            .entry(agent.to_string())
+            .or_default()
+            .add(model, input, output);
+    }
+
+    /// Fold `other` into this ledger.
+    pub fn merge(&mut self, other: &UsageLedger) {
+        self.total.merge(&other.total);
+        for (model, totals) in &other.by_model {
+            self.by_model
+                .entry(model.clone())
+                .or_default()
+                .merge(totals);
+        }
+        for (agent, totals) in &other.by_agent {
+            self.by_agent
+                .entry(agent.clone())
+                .or_default()
+                .merge(totals);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total.requests == 0
+    }
+}
+
+/// Rebuild the usage recorded in a saved session. Requests saved without an
+/// agent are counted under `default_agent`.
+pub fn session_usage(entries: &[SessionEntry], default_agent: &str) -> UsageLedger {
+    let mut ledger = UsageLedger::default();
+    for entry in entries {
+        if let SessionEntry::Usage {
+            model,
+            input_tokens,
+            output_tokens,
+            agent,
+            ..
+        } = entry
+        {
+            let agent = agent.as_deref().unwrap_or(default_agent);
+            ledger.record(model, agent, *input_tokens, *output_tokens);
+        }
+    }
+    ledger
+}
+
+/// The session entry recording one provider request made by `agent`.
+pub fn usage_entry(report: &UsageReport, agent: &str) -> SessionEntry {
+    SessionEntry::Usage {
+        id: MessageId::new().as_str().to_string(),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        model: report.model.clone(),
+        input_tokens: report.input_tokens,
+        output_tokens: report.output_tokens,
+        agent: Some(agent.to_string()),
+    }
+}
+
+/// How [`group_usage`] keys its totals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageGrouping {
+    /// UTC date of the request, as `YYYY-MM-DD`.
+    Day,
+    Model,
+    /// Working directory of the session.
+    Project,
+}
+
+/// Add up the requests recorded in saved sessions at or after `since` (Unix
+/// seconds). Each session is given as its project directory and entries.
+pub fn group_usage<'a>(
+    sessions: impl IntoIterator<Item = (&'a str, &'a [SessionEntry])>,
+    by: UsageGrouping,
+    since: u64,
+) -> BTreeMap<String, UsageTotals> {
+    let mut groups: BTreeMap<String, UsageTotals> = BTreeMap::new();
+    for (project, entries) in sessions {
+        for entry in entries {
+            let SessionEntry::Usage {
+                timestamp,
+                model,
+                input_tokens,
+                output_tokens,
+                ..
+            } = entry
+            else {
+                continue;
+            };
+            if *timestamp < since {
+                continue;
+            }
+            let key = match by {
+                UsageGrouping::Day => utc_date(*timestamp),
+                UsageGrouping::Model => model.clone(),
+                UsageGrouping::Project => project.to_string(),
+            };
+            groups
+                .entry(key)
+                .or_default()
+                .add(model, *input_tokens, *output_tokens);
+        }
+    }
+    groups
+}
+
+/// The UTC calendar date of a Unix timestamp, as `YYYY-MM-DD`.
+pub fn utc_date(timestamp: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm, shifted so years start in March.
+    let days = timestamp / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(model: &str, input: usize, output: usize, agent: Option<&str>) -> SessionEntry {
+        usage_at(0, model, input, output, agent)
+    }
+
+    fn usage_at(
+        timestamp: u64,
+        model: &str,
+        input: usize,
+        output: usize,
+        agent: Option<&str>,
+    ) -> SessionEntry {
+        SessionEntry::Usage {
+            id: ulid::Ulid::new().to_string(),
+            timestamp,
+            model: model.to_string(),
+            input_tokens: input,
+            output_tokens: output,
+            agent: agent.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_session_usage_groups_by_model_and_agent() {
+        let entries = vec![
+            usage("claude-sonnet-4-20250514", 1_000_000, 0, None),
+            usage("claude-sonnet-4-20250514", 0, 100_000, Some("explore")),
+            usage("my-local-model", 500, 20, Some("explore")),
+        ];
+
+        let ledger = session_usage(&entries, "build");
+        assert_eq!(ledger.total.requests, 3);
+        assert_eq!(ledger.total.input_tokens, 1_000_500);
+        assert!((ledger.total.cost.unwrap() - 4.5).abs() < 1e-9);
+        assert!(ledger.by_model["my-local-model"].cost.is_none());
+        assert_eq!(ledger.by_agent["build"].input_tokens, 1_000_000);
+        assert_eq!(ledger.by_agent["explore"].requests, 2);
+
+        let mut project = UsageLedger::default();
+        project.merge(&ledger);
+        project.merge(&ledger);
+        assert_eq!(project.by_agent["explore"].output_tokens, 200_040);
+        assert!((project.total.cost.unwrap() - 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_group_usage_by_day_model_and_project() {
+        // 2026-10-15T23:59:59Z and 2026-10-16T00:00:00Z.
+        let (late, midnight) = (1_792_108_799, 1_792_108_800);
+        let a = vec![
+            usage_at(10, "claude-sonnet-4-20250514", 1_000_000, 0, None),
+            usage_at(late, "claude-sonnet-4-20250514", 1_000_000, 0, None),
+        ];
+        let b = vec![usage_at(
+            midnight,
+            "my-local-model",
+            500,
+            20,
+            Some("explore"),
+        )];
+        let sessions = || [("/work/a", a.as_slice()), ("/work/b", b.as_slice())];
+
+        let by_day = group_usage(sessions(), UsageGrouping::Day, 100);
+        assert_eq!(
+            by_day.keys().collect::<Vec<_>>(),
+            ["2026-10-15", "2026-10-16"]
+        );
+        assert!((by_day["2026-10-15"].cost.unwrap() - 3.0).abs() < 1e-9);
+        assert!(by_day["2026-10-16"].cost.is_none());
+
+        let by_model = group_usage(sessions(), UsageGrouping::Model, 0);
+        assert_eq!(by_model["claude-sonnet-4-20250514"].requests, 2);
+
+        let by_project = group_usage(sessions(), UsageGrouping::Project, 0);
+        assert_eq!(by_project["/work/a"].input_tokens, 2_000_000);
+        assert_eq!(by_project["/work/b"].output_tokens, 20);
+    }
+
+    #[test]
+    fn test_utc_date() {
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(951_782_400), "2000-02-29");
+        assert_eq!(utc_date(1_792_108_800), "2026-10-16");
+    }
+}
@@ -0,0 +1,172 @@
+//! Checks run before the agent may finish a turn that changed files.
+//!
+//! With verification on, a final answer is held back until every check
+//! command (build, tests, lints) exits zero. Failures go back to the model
+//! as a user message and the loop continues, up to `max_attempts` times;
+//! if the checks still fail after that, the answer is returned with a note
+//! listing what is broken.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::message::ToolExchange;
+
+/// Characters of output kept per failed check in the message to the model.
+const MAX_OUTPUT_CHARS: usize = 4_000;
+
+/// Tools whose use means a turn may have changed the workspace.
+const MUTATING_TOOLS: &[&str] = &["write", "edit", "bash", "resolve_conflicts", "task"];
+
+/// Self-verification before a turn ends.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifyConfig {
+    /// Run the checks before finishing; also set by `--verify`.
+    pub enabled: bool,
+    /// Shell commands that must all succeed. Empty picks defaults from the
+    /// project files in the working directory.
+    pub checks: Vec<String>,
+    /// Times the model is sent back to fix failing checks.
+    pub max_attempts: usize,
+}
+
+impl Default for VerifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            checks: Vec::new(),
+            max_attempts: 2,
+        }
+    }
+}
+
+impl VerifyConfig {
+    /// This config, turned on when `flag` (`--verify`) is set.
+    pub fn enabled_if(self, flag: bool) -> Self {
+        Self {
+            enabled: self.enabled || flag,
+            ..self
+        }
+    }
+
+    /// The configured checks, or the usual build, test, and lint commands
+    /// for the project in `dir`.
+    pub fn checks_for(&self, dir: &Path) -> Vec<String> {
+        if !self.checks.is_empty() {
+            return self.checks.clone();
+        }
+        let defaults: &[&str] = if dir.join("Cargo.toml").is_file() {
+            &["cargo build", "cargo test", "cargo clippy -- -D warnings"]
+        } else if dir.join("go.mod").is_file() {
+            &["go build ./...", "go test ./...", "go vet ./..."]
+        } else if dir.join("package.json").is_file() {
+            &["npm test"]
+        } else {
+            &[]
+        };
+        defaults.iter().map(|check| check.to_string()).collect()
+    }
+}
+
+/// Whether a turn that made these tool calls needs verifying.
+pub fn needs_verification(exchanges: &[ToolExchange]) -> bool {
+    exchanges
+        .iter()
+        .any(|exchange| !exchange.is_error && MUTATING_TOOLS.contains(&exchange.name.as_str()))
+}
+
+/// A check that did not pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckFailure {
+    pub command: String,
+    pub output: String,
+}
+
+/// The message sending the model back to fix failing checks.
+pub fn failure_prompt(failures: &[CheckFailure], attempt: usize, max_attempts: usize) -> String {
+    let mut prompt = format!(
+        "Verification failed (attempt {attempt} of {max_attempts}). Fix these problems, then finish again.\n"
+    );
+    for failure in failures {
+        prompt.push_str(&format!(
+            "\n$ {}\n{}\n",
+            failure.command,
+            tail(&failure.output)
+        ));
+    }
+    prompt
+}
+
+/// The note added to an answer whose checks still fail.
+pub fn unverified_note(failures: &[CheckFailure]) -> String {
+    let commands: Vec<&str> = failures
+        .iter()
+        .map(|failure| failure.command.as_str())
+        .collect();
+    format!(
+        "\n\nVerification still failing after the retry budget: {}",
+        commands.join(", ")
+    )
+}
+
+/// The end of `output`, where build and test failures are summarized.
+fn tail(output: &str) -> &str {
+    let output = output.trim_end();
+    match output.char_indices().rev().nth(MAX_OUTPUT_CHARS - 1) {
+        Some((start, _)) => &output[start..],
+        None => output,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(name: &str, is_error: bool) -> ToolExchange {
+        ToolExchange {
+            name: name.to_string(),
+            arguments: serde_json::Value::Null,
+            output: Some(String::new()),
+            is_error,
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_checks_default_by_project_and_skip_read_only_turns() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = VerifyConfig::default();
+        assert!(config.checks_for(dir.path()).is_empty());
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        assert_eq!(config.checks_for(dir.path())[1], "cargo test");
+        let custom = VerifyConfig {
+            checks: vec!["make check".to_string()],
+            ..VerifyConfig::default()
+        };
+        assert_eq!(custom.checks_for(dir.path()), ["make check"]);
+
+        assert!(!needs_verification(&[
+            exchange("read", false),
+            exchange("grep", false)
+        ]));
+        assert!(!needs_verification(&[exchange("edit", true)]));
+        assert!(needs_verification(&[
+            exchange("read", false),
+            exchange("edit", false)
+        ]));
+
+        let long = "x".repeat(MAX_OUTPUT_CHARS + 10);
+        let prompt = failure_prompt(
+            &[CheckFailure {
+                command: "cargo test".to_string(),
+                output: long,
+            }],
+            1,
+            2,
+        );
+        assert!(prompt.starts_with("Verification failed (attempt 1 of 2)."));
+        assert!(prompt.contains(&format!("$ cargo test\n{}\n", "x".repeat(MAX_OUTPUT_CHARS))));
+        assert!(!prompt.contains(&"x".repeat(MAX_OUTPUT_CHARS + 1)));
+    }
+}
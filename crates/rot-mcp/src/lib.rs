@@ -1,5 +1,7 @@
 //! MCP stdio client for loading and calling external tools.
 
+pub mod stderr;
+
 use rot_sandbox::{spawn_command, SandboxError, SandboxPolicy};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -9,6 +11,9 @@ use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout};
 use tokio::sync::Mutex;
+use tracing::Instrument;
+
+pub use stderr::StderrLog;
 
 const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
 
@@ -46,6 +51,17 @@ pub struct McpToolCallResult {
 #[derive(Debug, Clone)]
 pub struct McpClient {
     inner: Arc<Mutex<Connection>>,
+    stderr: Arc<std::sync::Mutex<StderrLog>>,
+}
+
+/// Whether an MCP server process is still up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum McpServerState {
+    Running,
+    /// A request is in flight, so the process was not checked.
+    Busy,
+    /// The process exited, with its exit code when it has one.
+    Exited(Option<i32>),
 }
 
 #[derive(Debug)]
@@ -105,8 +121,10 @@ impl McpClient {
             server: config.name.clone(),
             message: "child stdout was not piped".to_string(),
         })?;
+        let stderr_log = Arc::new(std::sync::Mutex::new(StderrLog::default()));
         if let Some(stderr) = child.stderr.take() {
             let server_name = config.name.clone();
+            let log = Arc::clone(&stderr_log);
             tokio::spawn(async move {
                 let mut stderr = BufReader::new(stderr);
                 let mut line = String::new();
@@ -115,7 +133,9 @@ impl McpClient {
                     match stderr.read_line(&mut line).await {
                         Ok(0) => break,
                         Ok(_) => {
-                            tracing::debug!(server = %server_name, stderr = line.trim_end(), "mcp stderr");
+                            if log.lock().unwrap().push(&line) {
+                                tracing::debug!(server = %server_name, stderr = line.trim_end(), "mcp stderr");
+                            }
                         }
                         Err(err) => {
                             tracing::debug!(server = %server_name, error = %err, "failed to read mcp stderr");
@@ -135,6 +155,7 @@ impl McpClient {
                 server_name: config.name.clone(),
                 tool_timeout: config.tool_timeout,
             })),
+            stderr: stderr_log,
         };
 
         client.initialize(config.startup_timeout).await?;
@@ -142,16 +163,49 @@ impl McpClient {
         Ok((client, tools))
     }
 
+    /// Stderr lines received so far, to pass to [`Self::stderr_since`].
+    pub fn stderr_mark(&self) -> u64 {
+        self.stderr.lock().unwrap().mark()
+    }
+
+    /// The last `limit` stderr lines the server wrote after `mark`.
+    pub fn stderr_since(&self, mark: u64, limit: usize) -> Vec<String> {
+        self.stderr.lock().unwrap().since(mark, limit)
+    }
+
+    /// The last `limit` stderr lines the server wrote.
+    pub fn recent_stderr(&self, limit: usize) -> Vec<String> {
+        self.stderr.lock().unwrap().recent(limit)
+    }
+
+    /// Whether the server process is still running.
+    pub fn state(&self) -> McpServerState {
+        let Ok(mut conn) = self.inner.try_lock() else {
+            return McpServerState::Busy;
+        };
+        match conn.child.try_wait() {
+            Ok(None) => McpServerState::Running,
+            Ok(Some(status)) => McpServerState::Exited(status.code()),
+            Err(_) => McpServerState::Exited(None),
+        }
+    }
+
     /// Call one tool on the connected MCP server.
     pub async fn call_tool(
         &self,
         name: &str,
         arguments: Value,
     ) -> Result<McpToolCallResult, McpError> {
-        let timeout = {
+        let (timeout, server) = {
             let conn = self.inner.lock().await;
-            conn.tool_timeout
+            (conn.tool_timeout, conn.server_name.clone())
         };
+        let span = tracing::info_span!(
+            "mcp.call",
+            server = %server,
+            tool = name,
+            is_error = tracing::field::Empty,
+        );
         let result = self
             .request_with_timeout(
                 "tools/call",
@@ -161,6 +215,7 @@ impl McpClient {
                 }),
                 timeout,
             )
+            .instrument(span.clone())
             .await?;
 
         let content = result
@@ -173,6 +228,7 @@ impl McpClient {
             .get("isError")
             .and_then(Value::as_bool)
             .unwrap_or(false);
+        span.record("is_error", is_error);
 
         Ok(McpToolCallResult {
             text: render_content_text(&content, structured_content.as_ref()),
@@ -418,13 +474,14 @@ async fn read_message(
 }
 
 fn parse_tool_info(server_name: &str, value: &Value) -> Result<McpToolInfo, McpError> {
-    let name = value
-        .get("name")
-        .and_then(Value::as_str)
-        .ok_or_else(|| McpError::InvalidResponse {
-            server: server_name.to_string(),
-            message: "tool entry missing name".to_string(),
-        })?;
+    let name =
+        value
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| McpError::InvalidResponse {
+                server: server_name.to_string(),
+                message: "tool entry missing name".to_string(),
+            })?;
     let input_schema = value
         .get("inputSchema")
         .cloned()
@@ -460,6 +517,13 @@ fn render_content_text(content: &[Value], structured_content: Option<&Value>) ->
                     parts.push("[resource content omitted]".to_string());
                 }
             }
+            Some("image") => {
+                let mime_type = item
+                    .get("mimeType")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown");
+                parts.push(format!("[image: {mime_type}]"));
+            }
             Some(kind) => parts.push(format!("[{kind} content omitted]")),
             None => {}
         }
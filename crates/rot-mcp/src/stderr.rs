@@ -0,0 +1,168 @@
+//! Recent stderr of an MCP server, kept for error messages and `/mcp status`.
+//!
+//! Servers can be chatty, so the log is bounded three ways: it holds the
+//! last [`MAX_LINES`] lines, a line repeated back to back is counted rather
+//! than stored again, and past [`MAX_LINES_PER_SEC`] lines in one second the
+//! rest of that second is dropped and noted by a single count.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Lines kept per server.
+pub const MAX_LINES: usize = 100;
+/// Lines stored per second before the rest are dropped.
+pub const MAX_LINES_PER_SEC: usize = 20;
+/// Longer lines are cut to this many characters.
+const MAX_LINE_CHARS: usize = 500;
+
+#[derive(Debug, Clone)]
+struct StderrLine {
+    /// Sequence number of the last time this line was logged.
+    seq: u64,
+    text: String,
+    repeats: usize,
+}
+
+/// Bounded, deduplicated log of an MCP server's stderr.
+#[derive(Debug)]
+pub struct StderrLog {
+    lines: VecDeque<StderrLine>,
+    /// Lines received so far, kept or not.
+    seq: u64,
+    window_start: Instant,
+    window_lines: usize,
+    dropped: usize,
+}
+
+impl Default for StderrLog {
+    fn default() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            seq: 0,
+            window_start: Instant::now(),
+            window_lines: 0,
+            dropped: 0,
+        }
+    }
+}
+
+impl StderrLog {
+    /// Record one line. Returns whether it was stored as a new line.
+    pub fn push(&mut self, line: &str) -> bool {
+        self.push_at(line, Instant::now())
+    }
+
+    fn push_at(&mut self, line: &str, now: Instant) -> bool {
+        let line = line.trim_end();
+        if line.is_empty() {
+            return false;
+        }
+        self.seq += 1;
+        if let Some(last) = self
+            .lines
+            .back_mut()
+            .filter(|last| last.text == truncate(line))
+        {
+            last.repeats += 1;
+            last.seq = self.seq;
+            return false;
+        }
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.flush_dropped();
+            self.window_start = now;
+            self.window_lines = 0;
+        }
+        if self.window_lines >= MAX_LINES_PER_SEC {
+            self.dropped += 1;
+            return false;
+        }
+        self.window_lines += 1;
+        self.store(truncate(line));
+        true
+    }
+
+    fn flush_dropped(&mut self) {
+        if self.dropped > 0 {
+            let note = format!("... {} line(s) dropped", std::mem::take(&mut self.dropped));
+            self.store(note);
+        }
+    }
+
+    fn store(&mut self, text: String) {
+        self.lines.push_back(StderrLine {
+            seq: self.seq,
+            text,
+            repeats: 1,
+        });
+        while self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Number of lines received so far; pass it to [`Self::since`] later to
+    /// get only what came after.
+    pub fn mark(&self) -> u64 {
+        self.seq
+    }
+
+    /// The last `limit` lines logged after `mark`, oldest first.
+    pub fn since(&self, mark: u64, limit: usize) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .lines
+            .iter()
+            .filter(|line| line.seq > mark)
+            .map(|line| match line.repeats {
+                1 => line.text.clone(),
+                n => format!("{} (x{n})", line.text),
+            })
+            .collect();
+        if self.dropped > 0 {
+            lines.push(format!("... {} line(s) dropped", self.dropped));
+        }
+        let skip = lines.len().saturating_sub(limit);
+        lines.split_off(skip)
+    }
+
+    /// The last `limit` lines, oldest first.
+    pub fn recent(&self, limit: usize) -> Vec<String> {
+        self.since(0, limit)
+    }
+}
+
+fn truncate(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stderr_log_dedupes_rate_limits_and_marks() {
+        let start = Instant::now();
+        let mut log = StderrLog {
+            window_start: start,
+            ..StderrLog::default()
+        };
+
+        assert!(log.push_at("starting", start));
+        assert!(log.push_at("retrying", start));
+        assert!(!log.push_at("retrying", start));
+        assert_eq!(log.recent(10), ["starting", "retrying (x2)"]);
+
+        let mark = log.mark();
+        for n in 0..30 {
+            log.push_at(&format!("noise {n}"), start);
+        }
+        let since = log.since(mark, 100);
+        assert_eq!(since.len(), MAX_LINES_PER_SEC - 2 + 1);
+        assert_eq!(since.last().unwrap(), "... 12 line(s) dropped");
+
+        log.push_at("next second", start + Duration::from_secs(1));
+        assert_eq!(log.recent(2), ["... 12 line(s) dropped", "next second"]);
+        assert_eq!(log.since(log.mark(), 10), Vec::<String>::new());
+    }
+}
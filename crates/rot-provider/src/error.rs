@@ -1,5 +1,7 @@
 //! Error types for the rot-provider crate.
 
+use std::time::Duration;
+
 /// Errors that can occur in LLM provider operations.
 #[derive(Debug, thiserror::Error)]
 pub enum ProviderError {
@@ -11,6 +13,15 @@ pub enum ProviderError {
     #[error("API error: {0}")]
     ApiError(String),
 
+    /// API answered with a non-success HTTP status
+    #[error("API error: HTTP {status}: {body}")]
+    Status {
+        status: reqwest::StatusCode,
+        body: String,
+        /// Delay the server asked for in `Retry-After`.
+        retry_after: Option<Duration>,
+    },
+
     /// Invalid model specified
     #[error("Invalid model: {0}")]
     InvalidModel(String),
@@ -27,3 +38,76 @@ pub enum ProviderError {
     #[error("Authentication error: {0}")]
     Auth(String),
 }
+
+impl ProviderError {
+    /// The error for a non-success `response`, with its body and any
+    /// `Retry-After` delay.
+    pub(crate) async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status();
+        let retry_after = retry_after(response.headers());
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "unknown error".to_string());
+        Self::Status {
+            status,
+            body,
+            retry_after,
+        }
+    }
+
+    /// Whether the request may succeed if sent again: rate limits, server
+    /// errors and overload, timeouts, and failed connections.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Status { status, .. } => {
+                status.as_u16() == 408 || status.as_u16() == 429 || status.is_server_error()
+            }
+            Self::Http(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
+
+    /// Delay the server asked for before retrying.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Status { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// The delay in `retry-after-ms` or `retry-after` (in seconds).
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<f64>().ok();
+    if let Some(ms) = header("retry-after-ms") {
+        return (ms >= 0.0).then(|| Duration::from_secs_f64(ms / 1000.0));
+    }
+    header("retry-after")
+        .filter(|secs| *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    #[test]
+    fn test_retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("retry-after", HeaderValue::from_static("2"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+        headers.insert("retry-after-ms", HeaderValue::from_static("1500"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(1500)));
+
+        // HTTP dates are not used; the backoff applies instead.
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+}
@@ -1,17 +1,24 @@
 //! rot-provider: LLM provider abstraction and implementations.
 
 mod error;
+pub mod pricing;
 pub mod providers;
+pub mod retry;
 pub mod traits;
 pub mod types;
 
 pub use error::ProviderError;
+pub use pricing::{pricing_for, register_pricing, ModelPricing};
 pub use providers::anthropic::AnthropicProvider;
 pub use providers::openai::new_openai_provider;
 pub use providers::openai_compat::{OpenAiCompatConfig, OpenAiCompatProvider};
+pub use providers::openrouter::{
+    load_openrouter_provider, new_openrouter_provider, OpenRouterRouting,
+};
 pub use providers::zai::new_zai_provider;
+pub use retry::{RetryPolicy, RetryProvider};
 pub use traits::Provider;
 pub use types::{
     ModelInfo, ProviderContent, ProviderMessage, Request, Response, StopReason, StreamEvent,
-    ThinkingConfig, ToolDefinition, Usage,
+    ThinkingConfig, ToolDefinition, ToolResultPart, Usage,
 };
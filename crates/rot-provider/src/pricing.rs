@@ -0,0 +1,104 @@
+//! Published per-token prices for known models.
+//!
+//! Prices listed by a provider's live model catalogue, such as OpenRouter's,
+//! are registered at runtime and take precedence over the built-in table.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// USD prices per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPricing {
+    /// Cost in USD of `input` prompt tokens and `output` completion tokens.
+    pub fn cost(&self, input: usize, output: usize) -> f64 {
+        (input as f64 * self.input_per_mtok + output as f64 * self.output_per_mtok) / 1_000_000.0
+    }
+}
+
+/// Prices by model id prefix. More specific prefixes come first.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("o3-mini", 1.1, 4.4),
+    ("o4-mini", 1.1, 4.4),
+    ("glm-4.5-air", 0.2, 1.1),
+    ("glm-4.5", 0.6, 2.2),
+    ("glm-4.6", 0.6, 2.2),
+    ("glm-4.7", 0.6, 2.2),
+];
+
+fn listed() -> &'static RwLock<HashMap<String, ModelPricing>> {
+    static LISTED: OnceLock<RwLock<HashMap<String, ModelPricing>>> = OnceLock::new();
+    LISTED.get_or_init(Default::default)
+}
+
+/// Record the price a provider lists for `model`.
+pub fn register_pricing(model: &str, pricing: ModelPricing) {
+    listed().write().unwrap().insert(model.to_string(), pricing);
+}
+
+/// Pricing for `model`, or `None` when its price is unknown.
+///
+/// Registered prices come first. Otherwise the built-in table is matched by
+/// id prefix, also without a `vendor/` part as gateways name models.
+pub fn pricing_for(model: &str) -> Option<ModelPricing> {
+    if let Some(pricing) = listed().read().unwrap().get(model) {
+        return Some(*pricing);
+    }
+    let bare = model.rsplit('/').next().unwrap_or(model);
+    PRICES
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix) || bare.starts_with(prefix))
+        .map(|&(_, input_per_mtok, output_per_mtok)| ModelPricing {
+            input_per_mtok,
+            output_per_mtok,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pricing_prefers_specific_prefix() {
+        let mini = pricing_for("gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(mini.input_per_mtok, 0.15);
+        let sonnet = pricing_for("claude-sonnet-4-20250514").unwrap();
+        assert!((sonnet.cost(1_000_000, 100_000) - 4.5).abs() < 1e-9);
+        assert!(pricing_for("my-local-model").is_none());
+    }
+
+    #[test]
+    fn test_registered_and_vendor_prefixed_pricing() {
+        let routed = pricing_for("openai/gpt-4o-mini").unwrap();
+        assert_eq!(routed.input_per_mtok, 0.15);
+
+        assert!(pricing_for("example/listed-model").is_none());
+        register_pricing(
+            "example/listed-model",
+            ModelPricing {
+                input_per_mtok: 1.0,
+                output_per_mtok: 2.0,
+            },
+        );
+        assert_eq!(
+            pricing_for("example/listed-model").unwrap().output_per_mtok,
+            2.0
+        );
+    }
+}
@@ -6,7 +6,7 @@ use crate::error::ProviderError;
 use crate::traits::Provider;
 use crate::types::{
     ModelInfo, ProviderContent, ProviderMessage, Request, Response, StopReason, StreamEvent,
-    ToolDefinition, Usage,
+    ToolDefinition, ToolResultPart, Usage,
 };
 use async_trait::async_trait;
 use futures::stream::{self, BoxStream, StreamExt};
@@ -25,6 +25,8 @@ pub struct AnthropicProvider {
     api_key: String,
     model: String,
     base_url: String,
+    name: String,
+    models: Option<Vec<ModelInfo>>,
 }
 
 impl AnthropicProvider {
@@ -35,6 +37,8 @@ impl AnthropicProvider {
             api_key: api_key.into(),
             model: DEFAULT_MODEL.to_string(),
             base_url: API_BASE.to_string(),
+            name: "anthropic".to_string(),
+            models: None,
         }
     }
 
@@ -44,6 +48,21 @@ impl AnthropicProvider {
         self
     }
 
+    /// Report a different provider name, for gateways that speak the Anthropic API.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Replace the built-in model list. The first model becomes the default.
+    pub fn with_models(mut self, models: Vec<ModelInfo>) -> Self {
+        if let Some(first) = models.first() {
+            self.model = first.id.clone();
+        }
+        self.models = Some(models);
+        self
+    }
+
     /// Convert a generic Request into the Anthropic API request body.
     fn build_request_body(&self, request: &Request) -> Value {
         let messages: Vec<Value> = request
@@ -132,12 +151,38 @@ impl AnthropicProvider {
                 tool_call_id,
                 content,
                 is_error,
-            } => Some(json!({
-                "type": "tool_result",
-                "tool_use_id": tool_call_id,
-                "content": content,
-                "is_error": is_error,
-            })),
+                parts,
+            } => {
+                let content = if parts.is_empty() {
+                    json!(content)
+                } else {
+                    // Tool results take text and image blocks; JSON goes as text.
+                    let mut blocks = Vec::with_capacity(parts.len() + 1);
+                    if !content.is_empty() {
+                        blocks.push(json!({"type": "text", "text": content}));
+                    }
+                    blocks.extend(parts.iter().map(|part| match part {
+                        ToolResultPart::Image { data, mime_type } => json!({
+                            "type": "image",
+                            "source": {
+                                "type": "base64",
+                                "media_type": mime_type,
+                                "data": data,
+                            },
+                        }),
+                        ToolResultPart::Json { .. } => {
+                            json!({"type": "text", "text": part.to_text()})
+                        }
+                    }));
+                    Value::Array(blocks)
+                };
+                Some(json!({
+                    "type": "tool_result",
+                    "tool_use_id": tool_call_id,
+                    "content": content,
+                    "is_error": is_error,
+                }))
+            }
             ProviderContent::Image { data, mime_type } => Some(json!({
                 "type": "image",
                 "source": {
@@ -146,6 +191,18 @@ impl AnthropicProvider {
                     "data": data,
                 },
             })),
+            // The API rejects thinking blocks that lack the signature it issued.
+            ProviderContent::Thinking {
+                thinking,
+                signature: Some(signature),
+            } => Some(json!({
+                "type": "thinking",
+                "thinking": thinking,
+                "signature": signature,
+            })),
+            ProviderContent::Thinking {
+                signature: None, ..
+            } => None,
         }
     }
 
@@ -174,6 +231,11 @@ impl AnthropicProvider {
                         delta: thinking.clone(),
                     }]
                 }
+                Delta::Signature { signature } => {
+                    vec![StreamEvent::ThinkingSignature {
+                        signature: signature.clone(),
+                    }]
+                }
                 Delta::InputJson { partial_json } => {
                     // Tool argument streaming — need to track which tool call this belongs to
                     // For now, we emit a generic delta. The caller tracks tool call state.
@@ -251,10 +313,13 @@ impl AnthropicProvider {
 #[async_trait]
 impl Provider for AnthropicProvider {
     fn name(&self) -> &str {
-        "anthropic"
+        &self.name
     }
 
     fn models(&self) -> Vec<ModelInfo> {
+        if let Some(models) = &self.models {
+            return models.clone();
+        }
         vec![
             ModelInfo {
                 id: "claude-sonnet-4-20250514".to_string(),
@@ -263,6 +328,7 @@ impl Provider for AnthropicProvider {
                 max_output_tokens: 16_384,
                 supports_thinking: true,
                 supports_tools: true,
+                supports_images: true,
             },
             ModelInfo {
                 id: "claude-3-5-sonnet-20241022".to_string(),
@@ -271,6 +337,7 @@ impl Provider for AnthropicProvider {
                 max_output_tokens: 8_192,
                 supports_thinking: false,
                 supports_tools: true,
+                supports_images: true,
             },
         ]
     }
@@ -288,6 +355,24 @@ impl Provider for AnthropicProvider {
         }
     }
 
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let response = self
+            .client
+            .get(format!("{}/v1/models?limit=1000", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .send()
+            .await
+            .map_err(ProviderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response(response).await);
+        }
+
+        let list: ModelList = response.json().await.map_err(ProviderError::Http)?;
+        Ok(listed_models(list, &self.models()))
+    }
+
     async fn stream(
         &self,
         request: Request,
@@ -306,12 +391,7 @@ impl Provider for AnthropicProvider {
             .map_err(ProviderError::Http)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::ApiError(format!(
-                "HTTP {}: {}",
-                status, error_body
-            )));
+            return Err(ProviderError::from_response(response).await);
         }
 
         // Parse SSE stream from response bytes
@@ -474,6 +554,9 @@ enum Delta {
     #[serde(rename = "thinking_delta")]
     Thinking { thinking: String },
 
+    #[serde(rename = "signature_delta")]
+    Signature { signature: String },
+
     #[serde(rename = "input_json_delta")]
     InputJson { partial_json: String },
 }
@@ -500,6 +583,41 @@ struct ErrorData {
     message: String,
 }
 
+/// Response of `GET /v1/models`.
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ListedModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListedModel {
+    id: String,
+    #[serde(default)]
+    display_name: Option<String>,
+}
+
+/// Context window assumed for listed models without built-in metadata.
+const LISTED_CONTEXT_WINDOW: usize = 200_000;
+
+/// Model metadata for a listing, newest first as the API returns it.
+fn listed_models(list: ModelList, known: &[ModelInfo]) -> Vec<ModelInfo> {
+    list.data
+        .into_iter()
+        .map(|listed| match known.iter().find(|m| m.id == listed.id) {
+            Some(model) => model.clone(),
+            None => ModelInfo {
+                name: listed.display_name.unwrap_or_else(|| listed.id.clone()),
+                id: listed.id,
+                context_window: LISTED_CONTEXT_WINDOW,
+                max_output_tokens: DEFAULT_MAX_TOKENS,
+                supports_thinking: false,
+                supports_tools: true,
+                supports_images: true,
+            },
+        })
+        .collect()
+}
+
 // ──────────────────────────────────────────────────────────
 // Tests
 // ──────────────────────────────────────────────────────────
@@ -529,6 +647,24 @@ mod tests {
         assert_eq!(provider.current_model(), "claude-3-5-sonnet-20241022");
     }
 
+    #[test]
+    fn test_custom_name_and_models() {
+        let mut provider = AnthropicProvider::new("test-key")
+            .with_name("gateway")
+            .with_models(vec![ModelInfo {
+                id: "claude-internal".to_string(),
+                name: "Internal".to_string(),
+                context_window: 100_000,
+                max_output_tokens: 4_096,
+                supports_thinking: false,
+                supports_tools: true,
+                supports_images: true,
+            }]);
+        assert_eq!(provider.name(), "gateway");
+        assert_eq!(provider.current_model(), "claude-internal");
+        assert!(provider.set_model(DEFAULT_MODEL).is_err());
+    }
+
     #[test]
     fn test_set_invalid_model() {
         let mut provider = AnthropicProvider::new("test-key");
@@ -543,6 +679,27 @@ mod tests {
         assert!(models.iter().any(|m| m.id == "claude-sonnet-4-20250514"));
     }
 
+    #[test]
+    fn test_listed_models_keep_known_metadata() {
+        let list: ModelList = serde_json::from_value(json!({
+            "data": [
+                {"id": "claude-opus-5", "display_name": "Claude Opus 5", "type": "model"},
+                {"id": "claude-sonnet-4-20250514", "display_name": "Claude Sonnet 4"}
+            ],
+            "has_more": false
+        }))
+        .unwrap();
+        let provider = AnthropicProvider::new("test-key");
+
+        let models = listed_models(list, &provider.models());
+        assert_eq!(models[0].name, "Claude Opus 5");
+        assert_eq!(models[0].context_window, LISTED_CONTEXT_WINDOW);
+        assert!(models[1].supports_thinking);
+
+        let mut provider = provider.with_models(models);
+        assert!(provider.set_model("claude-opus-5").is_ok());
+    }
+
     #[test]
     fn test_build_request_body() {
         let provider = AnthropicProvider::new("test-key");
@@ -586,6 +743,38 @@ mod tests {
         assert_eq!(body["tools"][0]["name"], "read");
     }
 
+    #[test]
+    fn test_tool_result_parts_become_content_blocks() {
+        let provider = AnthropicProvider::new("test-key");
+        let result = |parts| ProviderContent::ToolResult {
+            tool_call_id: "toolu_1".to_string(),
+            content: "Captured".to_string(),
+            is_error: false,
+            parts,
+        };
+
+        let plain = provider.convert_content(&result(vec![])).unwrap();
+        assert_eq!(plain["content"], "Captured");
+
+        let rich = provider
+            .convert_content(&result(vec![
+                ToolResultPart::Json {
+                    value: json!({"width": 800}),
+                },
+                ToolResultPart::Image {
+                    data: "aGVsbG8=".to_string(),
+                    mime_type: "image/png".to_string(),
+                },
+            ]))
+            .unwrap();
+        let blocks = rich["content"].as_array().unwrap();
+        assert_eq!(blocks[0], json!({"type": "text", "text": "Captured"}));
+        assert_eq!(blocks[1]["text"], "{\n  \"width\": 800\n}");
+        assert_eq!(blocks[2]["type"], "image");
+        assert_eq!(blocks[2]["source"]["media_type"], "image/png");
+        assert_eq!(blocks[2]["source"]["data"], "aGVsbG8=");
+    }
+
     #[test]
     fn test_parse_text_delta() {
         let provider = AnthropicProvider::new("test-key");
@@ -683,4 +872,53 @@ mod tests {
             _ => panic!("Wrong event type"),
         }
     }
+
+    #[test]
+    fn test_parse_signature_delta() {
+        let provider = AnthropicProvider::new("test-key");
+        let raw = r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig-abc"}}"#;
+        let event: AnthropicEvent = serde_json::from_str(raw).unwrap();
+
+        let events = provider.parse_sse_event(&event);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            StreamEvent::ThinkingSignature { signature } => assert_eq!(signature, "sig-abc"),
+            _ => panic!("Expected ThinkingSignature"),
+        }
+    }
+
+    #[test]
+    fn test_signed_thinking_round_trips_into_request() {
+        let provider = AnthropicProvider::new("test-key");
+        let request = Request {
+            messages: vec![ProviderMessage {
+                role: "assistant".to_string(),
+                content: vec![
+                    ProviderContent::Thinking {
+                        thinking: "let me check".to_string(),
+                        signature: Some("sig-abc".to_string()),
+                    },
+                    ProviderContent::Thinking {
+                        thinking: "unsigned".to_string(),
+                        signature: None,
+                    },
+                    ProviderContent::Text {
+                        text: "Done".to_string(),
+                    },
+                ],
+            }],
+            tools: vec![],
+            system: None,
+            max_tokens: None,
+            thinking: None,
+        };
+
+        let body = provider.build_request_body(&request);
+        let content = body["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 2);
+        assert_eq!(content[0]["type"], "thinking");
+        assert_eq!(content[0]["thinking"], "let me check");
+        assert_eq!(content[0]["signature"], "sig-abc");
+        assert_eq!(content[1]["type"], "text");
+    }
 }
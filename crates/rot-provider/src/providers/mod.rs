@@ -3,4 +3,5 @@
 pub mod anthropic;
 pub mod openai;
 pub mod openai_compat;
+pub mod openrouter;
 pub mod zai;
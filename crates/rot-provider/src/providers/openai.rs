@@ -22,6 +22,7 @@ pub fn new_openai_provider(api_key: String) -> OpenAiCompatProvider {
                 max_output_tokens: 16_384,
                 supports_thinking: false,
                 supports_tools: true,
+                supports_images: true,
             },
             ModelInfo {
                 id: "gpt-4o-mini".to_string(),
@@ -30,6 +31,7 @@ pub fn new_openai_provider(api_key: String) -> OpenAiCompatProvider {
                 max_output_tokens: 16_384,
                 supports_thinking: false,
                 supports_tools: true,
+                supports_images: true,
             },
         ],
     };
@@ -4,10 +4,10 @@
 //! Ollama, OpenRouter, and many other providers.
 
 use crate::error::ProviderError;
+use crate::pricing::{register_pricing, ModelPricing};
 use crate::traits::Provider;
 use crate::types::{
-    ModelInfo, ProviderContent, Request, Response, StopReason, StreamEvent,
-    Usage,
+    ModelInfo, ProviderContent, Request, Response, StopReason, StreamEvent, ToolResultPart, Usage,
 };
 use async_trait::async_trait;
 use futures::stream::{self, BoxStream, StreamExt};
@@ -30,6 +30,8 @@ pub struct OpenAiCompatProvider {
     config: OpenAiCompatConfig,
     model: String,
     client: Client,
+    /// Fields added to every request body.
+    extra_body: serde_json::Map<String, Value>,
 }
 
 impl OpenAiCompatProvider {
@@ -40,9 +42,24 @@ impl OpenAiCompatProvider {
             config,
             model,
             client: Client::new(),
+            extra_body: serde_json::Map::new(),
         }
     }
 
+    /// Send `value` as the top-level `name` field of every request, for
+    /// options a gateway adds to the protocol.
+    pub fn with_body_field(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.extra_body.insert(name.into(), value);
+        self
+    }
+
+    /// Replace the configured model list, e.g. with one from
+    /// [`fetch_models`](Provider::fetch_models).
+    pub fn with_models(mut self, models: Vec<ModelInfo>) -> Self {
+        self.config.models = models;
+        self
+    }
+
     /// Build the JSON request body.
     fn build_request_body(&self, request: Request) -> Value {
         let messages = self.convert_messages(&request);
@@ -51,6 +68,8 @@ impl OpenAiCompatProvider {
             "model": self.model,
             "messages": messages,
             "stream": true,
+            // Ask for a final chunk carrying token usage.
+            "stream_options": { "include_usage": true },
         });
 
         if let Some(max_tokens) = request.max_tokens {
@@ -70,6 +89,10 @@ impl OpenAiCompatProvider {
             }).collect::<Vec<_>>());
         }
 
+        for (name, value) in &self.extra_body {
+            body[name] = value.clone();
+        }
+
         body
     }
 
@@ -91,20 +114,47 @@ impl OpenAiCompatProvider {
             });
 
             if has_tool_results {
+                // Tool messages take only text, so images follow in a user message.
+                let mut images = Vec::new();
                 for block in &msg.content {
                     if let ProviderContent::ToolResult {
                         tool_call_id,
                         content,
+                        parts,
                         ..
                     } = block
                     {
+                        let mut text = content.clone();
+                        for part in parts {
+                            match part {
+                                ToolResultPart::Json { .. } => {
+                                    if !text.is_empty() {
+                                        text.push_str("\n\n");
+                                    }
+                                    text.push_str(&part.to_text());
+                                }
+                                ToolResultPart::Image { data, mime_type } => {
+                                    images.push(json!({
+                                        "type": "text",
+                                        "text": format!("Image from tool call {tool_call_id}:"),
+                                    }));
+                                    images.push(json!({
+                                        "type": "image_url",
+                                        "image_url": {"url": format!("data:{mime_type};base64,{data}")},
+                                    }));
+                                }
+                            }
+                        }
                         messages.push(json!({
                             "role": "tool",
                             "tool_call_id": tool_call_id,
-                            "content": content,
+                            "content": text,
                         }));
                     }
                 }
+                if !images.is_empty() {
+                    messages.push(json!({"role": "user", "content": images}));
+                }
                 continue;
             }
 
@@ -162,6 +212,32 @@ impl OpenAiCompatProvider {
                 continue;
             }
 
+            // Messages with images use the content-parts form.
+            let has_images = msg
+                .content
+                .iter()
+                .any(|c| matches!(c, ProviderContent::Image { .. }));
+
+            if has_images {
+                let parts: Vec<Value> = msg
+                    .content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ProviderContent::Text { text } => Some(json!({
+                            "type": "text",
+                            "text": text,
+                        })),
+                        ProviderContent::Image { data, mime_type } => Some(json!({
+                            "type": "image_url",
+                            "image_url": {"url": format!("data:{mime_type};base64,{data}")},
+                        })),
+                        _ => None,
+                    })
+                    .collect();
+                messages.push(json!({"role": role, "content": parts}));
+                continue;
+            }
+
             // Regular message
             let content: String = msg
                 .content
@@ -197,7 +273,28 @@ impl OpenAiCompatProvider {
 
         let mut events = Vec::new();
 
+        if let Some(usage) = &chunk.usage {
+            events.push(StreamEvent::Usage {
+                input: usage.prompt_tokens,
+                output: usage.completion_tokens,
+            });
+        }
+
         for choice in &chunk.choices {
+            // Reasoning delta
+            let reasoning = choice
+                .delta
+                .reasoning_content
+                .as_ref()
+                .or(choice.delta.reasoning.as_ref());
+            if let Some(reasoning) = reasoning {
+                if !reasoning.is_empty() {
+                    events.push(StreamEvent::ThinkingDelta {
+                        delta: reasoning.clone(),
+                    });
+                }
+            }
+
             // Text delta
             if let Some(ref content) = choice.delta.content {
                 if !content.is_empty() {
@@ -271,6 +368,23 @@ impl Provider for OpenAiCompatProvider {
         }
     }
 
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let response = self
+            .client
+            .get(format!("{}/models", self.config.base_url))
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .send()
+            .await
+            .map_err(ProviderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response(response).await);
+        }
+
+        let list: ModelList = response.json().await.map_err(ProviderError::Http)?;
+        Ok(listed_models(list, &self.config.models))
+    }
+
     async fn stream(
         &self,
         request: Request,
@@ -289,12 +403,7 @@ impl Provider for OpenAiCompatProvider {
             .map_err(ProviderError::Http)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "unknown error".to_string());
-            return Err(ProviderError::ApiError(format!("HTTP {status}: {body}")));
+            return Err(ProviderError::from_response(response).await);
         }
 
         let byte_stream = response.bytes_stream();
@@ -338,12 +447,7 @@ impl Provider for OpenAiCompatProvider {
             .map_err(ProviderError::Http)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body: String = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "unknown error".to_string());
-            return Err(ProviderError::ApiError(format!("HTTP {status}: {body}")));
+            return Err(ProviderError::from_response(response).await);
         }
 
         let resp: OpenAiResponse = response
@@ -385,6 +489,9 @@ impl Provider for OpenAiCompatProvider {
 struct OpenAiChunk {
     #[serde(default)]
     choices: Vec<OpenAiChunkChoice>,
+    /// Sent in the last chunk when `stream_options.include_usage` is set.
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -400,6 +507,12 @@ struct OpenAiChunkChoice {
 struct OpenAiDelta {
     #[serde(default)]
     content: Option<String>,
+    /// Reasoning text from models that think aloud (z.ai GLM, DeepSeek).
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    /// The same, as OpenRouter names it.
+    #[serde(default)]
+    reasoning: Option<String>,
     #[serde(default)]
     tool_calls: Option<Vec<OpenAiToolCallDelta>>,
 }
@@ -444,6 +557,134 @@ struct OpenAiMessage {
     content: Option<String>,
 }
 
+/// Response of `GET /models`.
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ListedModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListedModel {
+    id: String,
+    // The rest is reported by some gateways, such as OpenRouter.
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default, alias = "context_window")]
+    context_length: Option<usize>,
+    #[serde(default)]
+    pricing: Option<ListedPricing>,
+    #[serde(default)]
+    top_provider: Option<TopProvider>,
+    #[serde(default)]
+    architecture: Option<Architecture>,
+    /// Request parameters the model accepts, such as `tools` and `reasoning`.
+    #[serde(default)]
+    supported_parameters: Option<Vec<String>>,
+}
+
+/// USD per token, as decimal strings; negative when the price varies.
+#[derive(Debug, Deserialize)]
+struct ListedPricing {
+    prompt: String,
+    completion: String,
+}
+
+impl ListedPricing {
+    fn per_mtok(&self) -> Option<ModelPricing> {
+        let input: f64 = self.prompt.parse().ok()?;
+        let output: f64 = self.completion.parse().ok()?;
+        (input >= 0.0 && output >= 0.0).then_some(ModelPricing {
+            input_per_mtok: input * 1_000_000.0,
+            output_per_mtok: output * 1_000_000.0,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TopProvider {
+    #[serde(default)]
+    max_completion_tokens: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Architecture {
+    #[serde(default)]
+    input_modalities: Vec<String>,
+}
+
+/// Id fragments of listed models that cannot chat: embeddings, speech, images.
+const NON_CHAT_MARKERS: &[&str] = &[
+    "embedding",
+    "whisper",
+    "tts",
+    "dall-e",
+    "moderation",
+    "transcribe",
+    "image",
+    "audio",
+    "realtime",
+    "davinci",
+    "babbage",
+];
+
+/// Context window assumed for listed models without metadata.
+const LISTED_CONTEXT_WINDOW: usize = 128_000;
+
+/// Output limit assumed for listed models without metadata.
+const LISTED_MAX_OUTPUT_TOKENS: usize = 4_096;
+
+/// Chat models of a listing: configured ones first, in their order, then the
+/// rest by id. Listed prices are registered for cost tracking.
+fn listed_models(list: ModelList, known: &[ModelInfo]) -> Vec<ModelInfo> {
+    let mut models: Vec<ModelInfo> = list
+        .data
+        .into_iter()
+        .filter(|listed| {
+            !NON_CHAT_MARKERS
+                .iter()
+                .any(|marker| listed.id.contains(marker))
+        })
+        .map(|listed| {
+            if let Some(pricing) = listed.pricing.as_ref().and_then(ListedPricing::per_mtok) {
+                register_pricing(&listed.id, pricing);
+            }
+            if let Some(model) = known.iter().find(|m| m.id == listed.id) {
+                return model.clone();
+            }
+            let supports = |parameter: &str| {
+                listed
+                    .supported_parameters
+                    .as_ref()
+                    .map(|parameters| parameters.iter().any(|p| p == parameter))
+            };
+            ModelInfo {
+                name: listed.name.clone().unwrap_or_else(|| listed.id.clone()),
+                context_window: listed.context_length.unwrap_or(LISTED_CONTEXT_WINDOW),
+                max_output_tokens: listed
+                    .top_provider
+                    .as_ref()
+                    .and_then(|top| top.max_completion_tokens)
+                    .unwrap_or(LISTED_MAX_OUTPUT_TOKENS),
+                supports_thinking: supports("reasoning").unwrap_or(false),
+                supports_tools: supports("tools").unwrap_or(true),
+                supports_images: listed
+                    .architecture
+                    .as_ref()
+                    .is_some_and(|arch| arch.input_modalities.iter().any(|m| m == "image")),
+                id: listed.id,
+            }
+        })
+        .collect();
+    models.sort_by_cached_key(|model| {
+        let rank = known
+            .iter()
+            .position(|m| m.id == model.id)
+            .unwrap_or(usize::MAX);
+        (rank, model.id.clone())
+    });
+    models
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct OpenAiUsage {
@@ -472,6 +713,7 @@ mod tests {
                     max_output_tokens: 4096,
                     supports_thinking: false,
                     supports_tools: true,
+                    supports_images: false,
                 },
                 ModelInfo {
                     id: "test-model-2".to_string(),
@@ -480,6 +722,7 @@ mod tests {
                     max_output_tokens: 8192,
                     supports_thinking: false,
                     supports_tools: true,
+                    supports_images: false,
                 },
             ],
         }
@@ -536,6 +779,80 @@ mod tests {
         assert_eq!(msgs[1]["content"], "Hello");
     }
 
+    #[test]
+    fn test_build_request_with_image() {
+        let p = OpenAiCompatProvider::new(test_config());
+        let request = Request {
+            messages: vec![ProviderMessage {
+                role: "user".to_string(),
+                content: vec![
+                    ProviderContent::Text {
+                        text: "What is this?".to_string(),
+                    },
+                    ProviderContent::Image {
+                        data: "aGVsbG8=".to_string(),
+                        mime_type: "image/png".to_string(),
+                    },
+                ],
+            }],
+            tools: vec![],
+            system: None,
+            max_tokens: None,
+            thinking: None,
+        };
+
+        let body = p.build_request_body(request);
+        let parts = body["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(parts[0]["text"], "What is this?");
+        assert_eq!(
+            parts[1]["image_url"]["url"],
+            "data:image/png;base64,aGVsbG8="
+        );
+    }
+
+    #[test]
+    fn test_tool_result_parts_become_text_and_a_user_image() {
+        let p = OpenAiCompatProvider::new(test_config());
+        let request = Request {
+            messages: vec![ProviderMessage {
+                role: "tool".to_string(),
+                content: vec![ProviderContent::ToolResult {
+                    tool_call_id: "call_1".to_string(),
+                    content: "Captured".to_string(),
+                    is_error: false,
+                    parts: vec![
+                        ToolResultPart::Json {
+                            value: serde_json::json!({"width": 800}),
+                        },
+                        ToolResultPart::Image {
+                            data: "aGVsbG8=".to_string(),
+                            mime_type: "image/png".to_string(),
+                        },
+                    ],
+                }],
+            }],
+            tools: vec![],
+            system: None,
+            max_tokens: None,
+            thinking: None,
+        };
+
+        let body = p.build_request_body(request);
+        let msgs = body["messages"].as_array().unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0]["role"], "tool");
+        assert_eq!(msgs[0]["content"], "Captured\n\n{\n  \"width\": 800\n}");
+        assert_eq!(msgs[1]["role"], "user");
+        assert_eq!(
+            msgs[1]["content"][0]["text"],
+            "Image from tool call call_1:"
+        );
+        assert_eq!(
+            msgs[1]["content"][1]["image_url"]["url"],
+            "data:image/png;base64,aGVsbG8="
+        );
+    }
+
     #[test]
     fn test_parse_text_delta() {
         let data = r#"{"choices":[{"delta":{"content":"Hello"},"index":0}]}"#;
@@ -554,6 +871,19 @@ mod tests {
         assert!(events.iter().any(|e| matches!(e, StreamEvent::ToolCallStart { name, .. } if name == "read")));
     }
 
+    #[test]
+    fn test_parse_usage_chunk() {
+        let data = r#"{"choices":[],"usage":{"prompt_tokens":1200,"completion_tokens":80}}"#;
+        let events = OpenAiCompatProvider::parse_sse_event(data);
+        assert!(matches!(
+            events[..],
+            [StreamEvent::Usage {
+                input: 1200,
+                output: 80
+            }]
+        ));
+    }
+
     #[test]
     fn test_parse_done() {
         let events = OpenAiCompatProvider::parse_sse_event("[DONE]");
@@ -561,6 +891,21 @@ mod tests {
         assert!(matches!(events[0], StreamEvent::Done { reason: StopReason::EndTurn }));
     }
 
+    #[test]
+    fn test_parse_reasoning_delta() {
+        let data = r#"{"choices":[{"delta":{"reasoning_content":"Let me check"},"index":0}]}"#;
+        let events = OpenAiCompatProvider::parse_sse_event(data);
+        assert!(matches!(
+            &events[0],
+            StreamEvent::ThinkingDelta { delta } if delta == "Let me check"
+        ));
+
+        let data = r#"{"choices":[{"delta":{"reasoning":"hmm","content":""},"index":0}]}"#;
+        let events = OpenAiCompatProvider::parse_sse_event(data);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], StreamEvent::ThinkingDelta { delta } if delta == "hmm"));
+    }
+
     #[test]
     fn test_parse_finish_reason_tool_calls() {
         let data = r#"{"choices":[{"delta":{},"finish_reason":"tool_calls","index":0}]}"#;
@@ -588,4 +933,73 @@ mod tests {
         assert_eq!(tools.len(), 1);
         assert_eq!(tools[0]["function"]["name"], "read");
     }
+
+    #[test]
+    fn test_listed_models_skip_non_chat_and_rank_configured_first() {
+        let list: ModelList = serde_json::from_value(json!({
+            "object": "list",
+            "data": [
+                {"id": "zeta-chat", "context_length": 64000},
+                {"id": "text-embedding-3-small"},
+                {"id": "test-model-2"},
+                {"id": "alpha-chat"}
+            ]
+        }))
+        .unwrap();
+
+        let models = listed_models(list, &test_config().models);
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["test-model-2", "alpha-chat", "zeta-chat"]);
+        assert_eq!(models[0].context_window, 32000);
+        assert_eq!(models[2].context_window, 64000);
+
+        let mut provider = OpenAiCompatProvider::new(test_config()).with_models(models);
+        assert!(provider.set_model("zeta-chat").is_ok());
+    }
+
+    #[test]
+    fn test_listed_models_read_gateway_metadata_and_prices() {
+        let list: ModelList = serde_json::from_value(json!({
+            "data": [{
+                "id": "vendor/listed-chat",
+                "name": "Vendor: Listed Chat",
+                "context_length": 200000,
+                "pricing": {"prompt": "0.000003", "completion": "0.000015"},
+                "top_provider": {"max_completion_tokens": 64000},
+                "architecture": {"input_modalities": ["text", "image"]},
+                "supported_parameters": ["max_tokens", "reasoning", "tools"]
+            }, {
+                "id": "vendor/auto",
+                "pricing": {"prompt": "-1", "completion": "-1"},
+                "supported_parameters": ["max_tokens"]
+            }]
+        }))
+        .unwrap();
+
+        let models = listed_models(list, &[]);
+        assert_eq!(models[1].name, "Vendor: Listed Chat");
+        assert_eq!(models[1].context_window, 200_000);
+        assert_eq!(models[1].max_output_tokens, 64_000);
+        assert!(
+            models[1].supports_thinking && models[1].supports_tools && models[1].supports_images
+        );
+        assert!(!models[0].supports_tools);
+        let pricing = crate::pricing_for("vendor/listed-chat").unwrap();
+        assert!((pricing.cost(1_000_000, 0) - 3.0).abs() < 1e-9);
+        assert!(crate::pricing_for("vendor/auto").is_none());
+    }
+
+    #[test]
+    fn test_body_fields_are_added_to_requests() {
+        let p = OpenAiCompatProvider::new(test_config())
+            .with_body_field("provider", json!({"order": ["a"]}));
+        let request = Request {
+            messages: vec![],
+            tools: vec![],
+            system: None,
+            max_tokens: None,
+            thinking: None,
+        };
+        assert_eq!(p.build_request_body(request)["provider"]["order"][0], "a");
+    }
 }
@@ -0,0 +1,154 @@
+//! OpenRouter provider — hundreds of models from many vendors behind one key.
+//!
+//! Uses the OpenAI-compatible API at `https://openrouter.ai/api/v1`. The
+//! model catalogue changes often, so [`load_openrouter_provider`] fetches
+//! `/models` for real context windows, capabilities, and prices, falling
+//! back to a short built-in list. Provider routing preferences are sent with
+//! every request.
+
+use crate::error::ProviderError;
+use crate::providers::openai_compat::{OpenAiCompatConfig, OpenAiCompatProvider};
+use crate::traits::Provider;
+use crate::types::ModelInfo;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api/v1";
+
+/// How long startup waits for the model catalogue before using the built-in list.
+const CATALOGUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Which upstream providers OpenRouter may route requests to, sent as the
+/// request's `provider` object. Unset fields keep OpenRouter's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenRouterRouting {
+    /// Providers to try first, in order (e.g. `["anthropic", "amazon-bedrock"]`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    /// Providers allowed at all; empty allows every provider.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    /// Providers never used.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Whether to fall back to providers outside `order` when those fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_fallbacks: Option<bool>,
+    /// Only use providers that support every parameter in the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_parameters: Option<bool>,
+    /// `allow` or `deny` providers that may store prompts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_collection: Option<String>,
+    /// Rank providers by `price`, `throughput`, or `latency`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+impl OpenRouterRouting {
+    /// Whether no preference is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Create a new OpenRouter provider with the built-in model list.
+///
+/// Set the `OPENROUTER_API_KEY` environment variable.
+pub fn new_openrouter_provider(
+    api_key: String,
+    routing: &OpenRouterRouting,
+) -> OpenAiCompatProvider {
+    let config = OpenAiCompatConfig {
+        base_url: OPENROUTER_BASE_URL.to_string(),
+        api_key,
+        provider_name: "openrouter".to_string(),
+        default_model: "anthropic/claude-sonnet-4".to_string(),
+        models: vec![
+            ModelInfo {
+                id: "anthropic/claude-sonnet-4".to_string(),
+                name: "Anthropic: Claude Sonnet 4".to_string(),
+                context_window: 200_000,
+                max_output_tokens: 64_000,
+                supports_thinking: true,
+                supports_tools: true,
+                supports_images: true,
+            },
+            ModelInfo {
+                id: "openai/gpt-4o".to_string(),
+                name: "OpenAI: GPT-4o".to_string(),
+                context_window: 128_000,
+                max_output_tokens: 16_384,
+                supports_thinking: false,
+                supports_tools: true,
+                supports_images: true,
+            },
+        ],
+    };
+
+    let provider = OpenAiCompatProvider::new(config);
+    if routing.is_empty() {
+        provider
+    } else {
+        provider.with_body_field(
+            "provider",
+            serde_json::to_value(routing).expect("routing serialization should not fail"),
+        )
+    }
+}
+
+/// Create an OpenRouter provider with its live model catalogue, fetched
+/// once per process. Listed prices are registered for cost tracking. When
+/// the catalogue cannot be loaded within [`CATALOGUE_TIMEOUT`], the built-in
+/// list is kept.
+pub async fn load_openrouter_provider(
+    api_key: String,
+    routing: &OpenRouterRouting,
+) -> OpenAiCompatProvider {
+    static CATALOGUE: OnceCell<Vec<ModelInfo>> = OnceCell::const_new();
+    let provider = new_openrouter_provider(api_key, routing);
+    let fetch = || async {
+        tokio::time::timeout(CATALOGUE_TIMEOUT, provider.fetch_models())
+            .await
+            .map_err(|_| {
+                ProviderError::ApiError(format!("timed out after {}s", CATALOGUE_TIMEOUT.as_secs()))
+            })?
+    };
+    match CATALOGUE.get_or_try_init(fetch).await {
+        Ok(models) if !models.is_empty() => provider.with_models(models.clone()),
+        Ok(_) => provider,
+        Err(e) => {
+            tracing::warn!("Failed to load the OpenRouter model catalogue: {e}");
+            provider
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openrouter_provider_defaults() {
+        let mut p = new_openrouter_provider("test-key".to_string(), &OpenRouterRouting::default());
+        assert_eq!(p.name(), "openrouter");
+        assert_eq!(p.current_model(), "anthropic/claude-sonnet-4");
+        assert!(p.set_model("openai/gpt-4o").is_ok());
+    }
+
+    #[test]
+    fn test_routing_serializes_only_set_preferences() {
+        let routing = OpenRouterRouting {
+            order: vec!["anthropic".to_string()],
+            allow_fallbacks: Some(false),
+            ..OpenRouterRouting::default()
+        };
+        assert!(!routing.is_empty());
+        assert_eq!(
+            serde_json::to_value(&routing).unwrap(),
+            serde_json::json!({"order": ["anthropic"], "allow_fallbacks": false})
+        );
+    }
+}
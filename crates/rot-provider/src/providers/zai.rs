@@ -24,6 +24,7 @@ pub fn new_zai_provider(api_key: String) -> OpenAiCompatProvider {
                 max_output_tokens: 16_384,
                 supports_thinking: false,
                 supports_tools: true,
+                supports_images: false,
             },
             ModelInfo {
                 id: "glm-4.7".to_string(),
@@ -32,6 +33,7 @@ pub fn new_zai_provider(api_key: String) -> OpenAiCompatProvider {
                 max_output_tokens: 8_192,
                 supports_thinking: false,
                 supports_tools: true,
+                supports_images: false,
             },
         ],
     };
@@ -0,0 +1,386 @@
+//! Retrying provider requests that fail for transient reasons.
+//!
+//! [`RetryProvider`] wraps any [`Provider`] and sends a request again when it
+//! fails with a rate limit, a server error, or a dropped connection, waiting
+//! longer each time or as long as the server's `Retry-After` asks. A stream is
+//! only retried before its first event; once output has arrived, an error is
+//! passed on. Each wait is announced with a [`StreamEvent::Retrying`] event.
+
+use crate::error::ProviderError;
+use crate::traits::Provider;
+use crate::types::{ModelInfo, Request, Response, StreamEvent};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often and how patiently failed requests are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after it.
+    pub initial_backoff_ms: u64,
+    /// Longest wait between attempts, unless the server asks for more.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait before retry number `retry` (from 0) after `error`,
+    /// or `None` when the request should not be retried.
+    pub fn delay(&self, retry: u32, error: &ProviderError) -> Option<Duration> {
+        if retry >= self.max_retries || !error.is_retryable() {
+            return None;
+        }
+        Some(error.retry_after().unwrap_or_else(|| {
+            let backoff = self
+                .initial_backoff_ms
+                .saturating_mul(1u64.checked_shl(retry).unwrap_or(u64::MAX));
+            Duration::from_millis(backoff.min(self.max_backoff_ms))
+        }))
+    }
+}
+
+/// A [`Provider`] that retries transient failures of another one.
+pub struct RetryProvider {
+    inner: Box<dyn Provider>,
+    policy: RetryPolicy,
+}
+
+impl RetryProvider {
+    pub fn new(inner: Box<dyn Provider>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    fn retrying(&self, retry: u32, delay: Duration, error: &ProviderError) -> StreamEvent {
+        tracing::warn!(
+            "{} request failed, retrying in {}ms (retry {} of {}): {error}",
+            self.inner.name(),
+            delay.as_millis(),
+            retry + 1,
+            self.policy.max_retries
+        );
+        StreamEvent::Retrying {
+            attempt: retry + 1,
+            max_retries: self.policy.max_retries,
+            delay_ms: delay.as_millis() as u64,
+            error: error.to_string(),
+        }
+    }
+
+    /// Events of `request` from `state` on, sending it again after
+    /// retryable failures that come before any output.
+    fn retry_stream<'a>(
+        &'a self,
+        request: Request,
+        state: StreamState<'a>,
+    ) -> BoxStream<'a, Result<StreamEvent, ProviderError>> {
+        stream::unfold(state, move |mut state| {
+            let request = request.clone();
+            async move {
+                loop {
+                    // A failed attempt: announce the retry, or end with the error.
+                    let (retry, error) = match state {
+                        StreamState::Finished => return None,
+                        StreamState::Wait { retry, delay } => {
+                            tokio::time::sleep(delay).await;
+                            state = StreamState::Connect { retry };
+                            continue;
+                        }
+                        StreamState::Connect { retry } => {
+                            match self.inner.stream(request.clone()).await {
+                                Ok(events) => {
+                                    state = StreamState::Streaming {
+                                        retry,
+                                        started: false,
+                                        events,
+                                    };
+                                    continue;
+                                }
+                                Err(e) => (retry, e),
+                            }
+                        }
+                        StreamState::Streaming {
+                            retry,
+                            started,
+                            mut events,
+                        } => match events.next().await {
+                            Some(Err(e)) if !started => (retry, e),
+                            Some(item) => {
+                                let state = StreamState::Streaming {
+                                    retry,
+                                    started: true,
+                                    events,
+                                };
+                                return Some((item, state));
+                            }
+                            None => return None,
+                        },
+                    };
+                    return Some(match self.policy.delay(retry, &error) {
+                        Some(delay) => (
+                            Ok(self.retrying(retry, delay, &error)),
+                            StreamState::Wait {
+                                retry: retry + 1,
+                                delay,
+                            },
+                        ),
+                        None => (Err(error), StreamState::Finished),
+                    });
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+enum StreamState<'a> {
+    /// Send the request; `retry` retries have been made.
+    Connect {
+        retry: u32,
+    },
+    /// Wait before sending it again.
+    Wait {
+        retry: u32,
+        delay: Duration,
+    },
+    /// Pass on events; `started` once the first one has.
+    Streaming {
+        retry: u32,
+        started: bool,
+        events: BoxStream<'a, Result<StreamEvent, ProviderError>>,
+    },
+    Finished,
+}
+
+#[async_trait]
+impl Provider for RetryProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn models(&self) -> Vec<ModelInfo> {
+        self.inner.models()
+    }
+
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        self.inner.fetch_models().await
+    }
+
+    fn current_model(&self) -> &str {
+        self.inner.current_model()
+    }
+
+    fn set_model(&mut self, model: &str) -> Result<(), ProviderError> {
+        self.inner.set_model(model)
+    }
+
+    async fn stream(
+        &self,
+        request: Request,
+    ) -> Result<BoxStream<'_, Result<StreamEvent, ProviderError>>, ProviderError> {
+        // The first attempt is made here so errors that are not retried
+        // surface the same way as without this wrapper.
+        let first = match self.inner.stream(request.clone()).await {
+            Ok(events) => StreamState::Streaming {
+                retry: 0,
+                started: false,
+                events,
+            },
+            Err(e) => match self.policy.delay(0, &e) {
+                Some(delay) => {
+                    let event = self.retrying(0, delay, &e);
+                    let rest = self.retry_stream(request, StreamState::Wait { retry: 1, delay });
+                    return Ok(stream::once(async move { Ok(event) }).chain(rest).boxed());
+                }
+                None => return Err(e),
+            },
+        };
+        Ok(self.retry_stream(request, first))
+    }
+
+    async fn complete(&self, request: Request) -> Result<Response, ProviderError> {
+        let mut retry = 0;
+        loop {
+            match self.inner.complete(request.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    let Some(delay) = self.policy.delay(retry, &e) else {
+                        return Err(e);
+                    };
+                    self.retrying(retry, delay, &e);
+                    tokio::time::sleep(delay).await;
+                    retry += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{StopReason, Usage};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// Fails with `status` for the first `failures` calls.
+    struct FlakyProvider {
+        calls: Arc<AtomicU32>,
+        failures: u32,
+        status: u16,
+    }
+
+    impl FlakyProvider {
+        fn fail(&self) -> Option<ProviderError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            (call < self.failures).then(|| ProviderError::Status {
+                status: reqwest::StatusCode::from_u16(self.status).unwrap(),
+                body: "busy".to_string(),
+                retry_after: Some(Duration::from_millis(1)),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Provider for FlakyProvider {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+        fn models(&self) -> Vec<ModelInfo> {
+            Vec::new()
+        }
+        fn current_model(&self) -> &str {
+            "flaky-1"
+        }
+        fn set_model(&mut self, _: &str) -> Result<(), ProviderError> {
+            Ok(())
+        }
+        async fn stream(
+            &self,
+            _: Request,
+        ) -> Result<BoxStream<'_, Result<StreamEvent, ProviderError>>, ProviderError> {
+            match self.fail() {
+                Some(e) => Err(e),
+                None => Ok(stream::iter(vec![
+                    Ok(StreamEvent::TextDelta {
+                        delta: "hi".to_string(),
+                    }),
+                    Ok(StreamEvent::Done {
+                        reason: StopReason::EndTurn,
+                    }),
+                ])
+                .boxed()),
+            }
+        }
+        async fn complete(&self, _: Request) -> Result<Response, ProviderError> {
+            match self.fail() {
+                Some(e) => Err(e),
+                None => Ok(Response {
+                    content: Vec::new(),
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                }),
+            }
+        }
+    }
+
+    fn flaky(failures: u32, status: u16) -> (RetryProvider, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let inner = FlakyProvider {
+            calls: calls.clone(),
+            failures,
+            status,
+        };
+        (
+            RetryProvider::new(Box::new(inner), RetryPolicy::default()),
+            calls,
+        )
+    }
+
+    fn request() -> Request {
+        Request {
+            messages: Vec::new(),
+            tools: Vec::new(),
+            system: None,
+            max_tokens: None,
+            thinking: None,
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_limit() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 5000,
+        };
+        let error = ProviderError::Status {
+            status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+            body: String::new(),
+            retry_after: None,
+        };
+        let delays: Vec<_> = (0..4).map(|n| policy.delay(n, &error).unwrap()).collect();
+        assert_eq!(delays, [1000, 2000, 4000, 5000].map(Duration::from_millis));
+        assert_eq!(policy.delay(10, &error), None);
+
+        let bad_request = ProviderError::Status {
+            status: reqwest::StatusCode::BAD_REQUEST,
+            body: String::new(),
+            retry_after: None,
+        };
+        assert_eq!(policy.delay(0, &bad_request), None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_retries_and_announces_each_wait() {
+        let (provider, calls) = flaky(2, 529);
+        let events: Vec<_> = provider.stream(request()).await.unwrap().collect().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(matches!(
+            events[0],
+            Ok(StreamEvent::Retrying {
+                attempt: 1,
+                max_retries: 3,
+                delay_ms: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            events[1],
+            Ok(StreamEvent::Retrying { attempt: 2, .. })
+        ));
+        assert!(matches!(events[2], Ok(StreamEvent::TextDelta { .. })));
+        assert_eq!(events.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries_and_on_client_errors() {
+        let (provider, calls) = flaky(10, 500);
+        let events: Vec<_> = provider.stream(request()).await.unwrap().collect().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert!(matches!(
+            events.last(),
+            Some(Err(ProviderError::Status { .. }))
+        ));
+
+        let (provider, calls) = flaky(1, 401);
+        assert!(provider.stream(request()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (provider, calls) = flaky(3, 429);
+        assert!(provider.complete(request()).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
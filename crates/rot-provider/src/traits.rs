@@ -17,6 +17,15 @@ pub trait Provider: Send + Sync {
     /// List of models supported by this provider.
     fn models(&self) -> Vec<ModelInfo>;
 
+    /// Fetch the models the provider currently offers from its API.
+    ///
+    /// Listed models that are also in [`models`](Provider::models) keep that
+    /// metadata; others get conservative defaults. Providers without a
+    /// listing endpoint return their built-in list.
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        Ok(self.models())
+    }
+
     /// Currently selected model identifier.
     fn current_model(&self) -> &str;
 
@@ -33,11 +33,48 @@ pub enum ProviderContent {
         tool_call_id: String,
         content: String,
         is_error: bool,
+        /// Structured blocks sent after `content`, such as a screenshot.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        parts: Vec<ToolResultPart>,
     },
 
     /// Image.
     #[serde(rename = "image")]
     Image { data: String, mime_type: String },
+
+    /// Model reasoning from a previous turn, echoed back verbatim.
+    #[serde(rename = "thinking")]
+    Thinking {
+        thinking: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<String>,
+    },
+}
+
+/// A structured block of a tool result, beyond its text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ToolResultPart {
+    /// Structured data, such as an MCP tool's `structuredContent`.
+    #[serde(rename = "json")]
+    Json { value: serde_json::Value },
+
+    /// Base64-encoded image.
+    #[serde(rename = "image")]
+    Image { data: String, mime_type: String },
+}
+
+impl ToolResultPart {
+    /// The part as text, for APIs that take only text tool results. Images
+    /// render as a placeholder.
+    pub fn to_text(&self) -> String {
+        match self {
+            Self::Json { value } => {
+                serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+            }
+            Self::Image { mime_type, .. } => format!("[image: {mime_type}]"),
+        }
+    }
 }
 
 /// Tool definition for the provider API.
@@ -87,7 +124,7 @@ pub struct Response {
 }
 
 /// Token usage information.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Usage {
     /// Input tokens consumed.
     pub input_tokens: usize,
@@ -116,6 +153,8 @@ pub enum StreamEvent {
     TextDelta { delta: String },
     /// A chunk of thinking/reasoning output.
     ThinkingDelta { delta: String },
+    /// Signature sealing the current thinking block.
+    ThinkingSignature { signature: String },
     /// Start of a tool call.
     ToolCallStart { id: String, name: String },
     /// Incremental arguments for a tool call.
@@ -128,6 +167,14 @@ pub enum StreamEvent {
     Done { reason: StopReason },
     /// Stream error.
     Error(String),
+    /// The request failed and will be sent again after `delay_ms`.
+    Retrying {
+        /// Which retry this is, from 1.
+        attempt: u32,
+        max_retries: u32,
+        delay_ms: u64,
+        error: String,
+    },
 }
 
 /// Information about a model supported by a provider.
@@ -145,4 +192,7 @@ pub struct ModelInfo {
     pub supports_thinking: bool,
     /// Whether the model supports tool use.
     pub supports_tools: bool,
+    /// Whether the model accepts image content.
+    #[serde(default)]
+    pub supports_images: bool,
 }
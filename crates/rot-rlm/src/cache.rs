@@ -0,0 +1,140 @@
+//! Answers of finished RLM runs, stored under the data directory so the
+//! same question about an unchanged context is answered without the model.
+
+use crate::context::ContextFile;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// One cached answer, stored as `<key>.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedAnswer {
+    prompt: String,
+    model: String,
+    answer: String,
+    /// Seconds since the Unix epoch.
+    created_at: u64,
+}
+
+/// RLM answers keyed by prompt, context content, and model.
+#[derive(Debug, Clone)]
+pub struct RlmCache {
+    dir: PathBuf,
+}
+
+impl Default for RlmCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RlmCache {
+    /// Cache in `~/.local/share/rot/rlm-cache/` by default.
+    pub fn new() -> Self {
+        let dir = dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rot")
+            .join("rlm-cache");
+        Self { dir }
+    }
+
+    /// Create with a custom directory (for testing).
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Key for `prompt` asked of `model` about `files`. Any change to a
+    /// file's path or content gives a new key.
+    pub fn key(prompt: &str, files: &[ContextFile], model: &str) -> String {
+        let mut hasher = blake3::Hasher::new();
+        // Length prefixes keep neighbouring fields from running together.
+        let mut field = |bytes: &[u8]| {
+            hasher.update(&(bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        };
+        field(prompt.as_bytes());
+        field(model.as_bytes());
+        for file in files {
+            field(file.path.as_bytes());
+            field(file.content.as_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// The answer stored under `key`, if any. Unreadable entries count as
+    /// misses.
+    pub async fn get(&self, key: &str) -> Option<String> {
+        let content = tokio::fs::read_to_string(self.path(key)).await.ok()?;
+        match serde_json::from_str::<CachedAnswer>(&content) {
+            Ok(cached) => Some(cached.answer),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable RLM cache entry {key}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Store `answer` under `key`, replacing any earlier answer.
+    pub async fn put(
+        &self,
+        key: &str,
+        prompt: &str,
+        model: &str,
+        answer: &str,
+    ) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let cached = CachedAnswer {
+            prompt: prompt.to_string(),
+            model: model.to_string(),
+            answer: answer.to_string(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        let json = serde_json::to_string_pretty(&cached)?;
+        // Written aside and renamed, so a reader never sees half an entry.
+        let partial = self.dir.join(format!("{key}.json.partial"));
+        tokio::fs::write(&partial, json).await?;
+        tokio::fs::rename(&partial, self.path(key)).await
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> ContextFile {
+        ContextFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_round_trips_by_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = RlmCache::with_dir(dir.path().join("cache"));
+        let files = [file("a.txt", "alpha"), file("b.txt", "beta")];
+        let key = RlmCache::key("count lines", &files, "openai/gpt-4o");
+        assert_eq!(cache.get(&key).await, None);
+
+        cache
+            .put(&key, "count lines", "openai/gpt-4o", "2")
+            .await
+            .unwrap();
+        assert_eq!(cache.get(&key).await.as_deref(), Some("2"));
+
+        let edited = [file("a.txt", "alpha"), file("b.txt", "beta!")];
+        assert_ne!(RlmCache::key("count lines", &edited, "openai/gpt-4o"), key);
+        assert_ne!(
+            RlmCache::key("count lines", &files, "openai/gpt-4o-mini"),
+            key
+        );
+        let moved = [file("a.txtb", ".txt"), file("", "beta")];
+        assert_ne!(RlmCache::key("count lines", &moved, "openai/gpt-4o"), key);
+    }
+}
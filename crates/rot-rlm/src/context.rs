@@ -1,8 +1,11 @@
 //! RLM (Recursive Language Model) context management.
 //!
 //! The ContextManager handles storing large content in temporary files
-//! and building metadata summaries for the LLM to reference.
+//! and building metadata summaries for the LLM to reference. Contexts too
+//! large for the model window are read with [`read_context`] and split with
+//! [`chunk_context`] for the engine's map/reduce passes.
 
+use ignore::WalkBuilder;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
@@ -93,6 +96,265 @@ impl ContextManager {
     }
 }
 
+/// One text file of a context, with its path relative to the context root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// A piece of a context small enough for one model request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextChunk {
+    /// Position of the chunk, from 0.
+    pub index: usize,
+    /// Where the chunk starts and ends, e.g. `src/a.rs:1 .. src/b.rs:40`.
+    pub span: String,
+    /// `<file path="..." lines="a-b">` blocks for the text it holds.
+    pub content: String,
+}
+
+/// Whether a context path is a glob pattern such as `src/**/*.rs`.
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Read the text files making up a context: the file itself, every file
+/// under a directory that `.gitignore` doesn't exclude, or every file
+/// matching a glob. Binary and non-UTF-8 files are skipped.
+pub fn read_context(path: &Path) -> std::io::Result<Vec<ContextFile>> {
+    let pattern = path.to_string_lossy();
+    if is_glob(&pattern) {
+        return read_glob(&pattern);
+    }
+    if path.is_file() {
+        let content = std::fs::read_to_string(path)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        return Ok(vec![ContextFile {
+            path: name,
+            content,
+        }]);
+    }
+    if !path.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("context path not found: {}", path.display()),
+        ));
+    }
+
+    let mut files = Vec::new();
+    let walker = WalkBuilder::new(path)
+        .add_custom_ignore_filename(rot_tools::rotignore::ROTIGNORE_FILE)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    for entry in walker {
+        let Ok(entry) = entry else { continue };
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        if content.contains('\0') {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+        files.push(ContextFile {
+            path: relative.to_string_lossy().into_owned(),
+            content,
+        });
+    }
+    Ok(files)
+}
+
+/// Check that `path` names a context without reading it: an existing file
+/// or directory, or a glob matching at least one file.
+pub fn check_context(path: &Path) -> std::io::Result<()> {
+    let pattern = path.to_string_lossy();
+    let found = if is_glob(&pattern) {
+        glob::glob(&pattern)
+            .map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("invalid context glob {pattern}: {e}"),
+                )
+            })?
+            .flatten()
+            .any(|path| path.is_file())
+    } else {
+        path.exists()
+    };
+    if found {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("nothing matches {pattern}"),
+        ))
+    }
+}
+
+/// Files matching `pattern`, named relative to the directory before its
+/// first wildcard.
+fn read_glob(pattern: &str) -> std::io::Result<Vec<ContextFile>> {
+    let paths = glob::glob(pattern).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid context glob {pattern}: {e}"),
+        )
+    })?;
+    let base: PathBuf = Path::new(pattern)
+        .components()
+        .take_while(|component| !is_glob(&component.as_os_str().to_string_lossy()))
+        .collect();
+
+    let mut files = Vec::new();
+    for path in paths.flatten().filter(|path| path.is_file()) {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if content.contains('\0') {
+            continue;
+        }
+        let relative = path.strip_prefix(&base).unwrap_or(&path);
+        files.push(ContextFile {
+            path: relative.to_string_lossy().into_owned(),
+            content,
+        });
+    }
+    if files.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no text files match {pattern}"),
+        ));
+    }
+    Ok(files)
+}
+
+/// `files` as one text of `<file path="...">` blocks, for a context that
+/// has to be a single file.
+pub fn bundle_context(files: &[ContextFile]) -> String {
+    let mut bundle = String::new();
+    for file in files {
+        bundle.push_str(&format!("<file path=\"{}\">\n{}", file.path, file.content));
+        if !file.content.ends_with('\n') {
+            bundle.push('\n');
+        }
+        bundle.push_str("</file>\n");
+    }
+    bundle
+}
+
+/// Split `files` into chunks of at most about `max_chars` characters,
+/// breaking at line boundaries. A file too large for one chunk continues
+/// in the next, and a single overlong line is cut where it hits the limit.
+pub fn chunk_context(files: &[ContextFile], max_chars: usize) -> Vec<ContextChunk> {
+    // Room for the `<file>` header and footer around each piece.
+    let text_chars = max_chars.saturating_sub(200).max(1);
+    let mut chunker = Chunker {
+        max_chars,
+        chunks: Vec::new(),
+        content: String::new(),
+        start: None,
+        end: String::new(),
+    };
+
+    for file in files {
+        let mut piece = String::new();
+        let mut first_line = 1;
+        // Lines completed so far.
+        let mut line_no = 0;
+        for line in split_long_lines(&file.content, text_chars) {
+            if !piece.is_empty() && piece.len() + line.len() > text_chars {
+                let last_line = if piece.ends_with('\n') {
+                    line_no
+                } else {
+                    line_no + 1
+                };
+                chunker.push(&file.path, first_line, last_line, &piece);
+                piece.clear();
+                first_line = line_no + 1;
+            }
+            piece.push_str(line);
+            if line.ends_with('\n') {
+                line_no += 1;
+            }
+        }
+        if !piece.is_empty() {
+            let last_line = if piece.ends_with('\n') {
+                line_no
+            } else {
+                line_no + 1
+            };
+            chunker.push(&file.path, first_line, last_line, &piece);
+        }
+    }
+    chunker.flush();
+    chunker.chunks
+}
+
+/// Total characters across `files`.
+pub fn context_len(files: &[ContextFile]) -> usize {
+    files.iter().map(|file| file.content.len()).sum()
+}
+
+/// Packs `<file>` blocks into chunks of at most `max_chars`.
+struct Chunker {
+    max_chars: usize,
+    chunks: Vec<ContextChunk>,
+    content: String,
+    start: Option<String>,
+    end: String,
+}
+
+impl Chunker {
+    fn push(&mut self, path: &str, first: usize, last: usize, text: &str) {
+        let newline = if text.ends_with('\n') { "" } else { "\n" };
+        let block =
+            format!("<file path=\"{path}\" lines=\"{first}-{last}\">\n{text}{newline}</file>\n");
+        if !self.content.is_empty() && self.content.len() + block.len() > self.max_chars {
+            self.flush();
+        }
+        self.start.get_or_insert_with(|| format!("{path}:{first}"));
+        self.end = format!("{path}:{last}");
+        self.content.push_str(&block);
+    }
+
+    fn flush(&mut self) {
+        if self.content.is_empty() {
+            return;
+        }
+        self.chunks.push(ContextChunk {
+            index: self.chunks.len(),
+            span: format!("{} .. {}", self.start.take().unwrap_or_default(), self.end),
+            content: std::mem::take(&mut self.content),
+        });
+    }
+}
+
+/// Lines of `text` with their newlines, with lines longer than `max` cut
+/// into `max`-sized pieces at character boundaries.
+fn split_long_lines(text: &str, max: usize) -> impl Iterator<Item = &str> {
+    text.split_inclusive('\n').flat_map(move |line| {
+        let mut rest = line;
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let mut cut = rest.len().min(max);
+            while !rest.is_char_boundary(cut) {
+                cut += 1;
+            }
+            let (head, tail) = rest.split_at(cut);
+            rest = tail;
+            Some(head)
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,6 +399,86 @@ mod tests {
         assert!(cm.load("nonexistent").await.is_err());
     }
 
+    #[test]
+    fn test_read_context_skips_ignored_and_binary_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        std::fs::write(dir.path().join("target/out.txt"), "built").unwrap();
+        std::fs::write(dir.path().join("logo.png"), [0x89, b'P', 0, 0]).unwrap();
+
+        let files = read_context(dir.path()).unwrap();
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["src/lib.rs"]);
+        assert_eq!(context_len(&files), 14);
+
+        let single = read_context(&dir.path().join("src/lib.rs")).unwrap();
+        assert_eq!(single[0].path, "lib.rs");
+        assert!(read_context(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_read_context_expands_globs() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/bin")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn a() {}").unwrap();
+        std::fs::write(dir.path().join("src/bin/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(dir.path().join("src/notes.md"), "# notes\n").unwrap();
+
+        let pattern = dir.path().join("src/**/*.rs");
+        let files = read_context(&pattern).unwrap();
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["bin/main.rs", "lib.rs"]);
+        assert_eq!(
+            bundle_context(&files),
+            "<file path=\"bin/main.rs\">\nfn main() {}\n</file>\n\
+             <file path=\"lib.rs\">\npub fn a() {}\n</file>\n"
+        );
+        assert!(read_context(&dir.path().join("src/*.py")).is_err());
+        assert!(check_context(&pattern).is_ok());
+        assert!(check_context(&dir.path().join("src/*.py")).is_err());
+    }
+
+    #[test]
+    fn test_chunk_context_splits_at_line_boundaries() {
+        let line = "x".repeat(99) + "\n";
+        let files = vec![
+            ContextFile {
+                path: "a.txt".to_string(),
+                content: line.repeat(10),
+            },
+            ContextFile {
+                path: "b.txt".to_string(),
+                content: "short\n".to_string(),
+            },
+        ];
+        let chunks = chunk_context(&files, 700);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| chunk.content.len() <= 700));
+        assert!(chunks[0]
+            .content
+            .starts_with("<file path=\"a.txt\" lines=\"1-5\">\n"));
+        assert_eq!(chunks[0].span, "a.txt:1 .. a.txt:5");
+        assert_eq!(chunks[1].span, "a.txt:6 .. b.txt:1");
+        assert!(chunks[1]
+            .content
+            .contains("<file path=\"b.txt\" lines=\"1-1\">\nshort\n</file>\n"));
+
+        // A line longer than a chunk is cut rather than dropped.
+        let long = vec![ContextFile {
+            path: "min.js".to_string(),
+            content: "é".repeat(400),
+        }];
+        let chunks = chunk_context(&long, 500);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.content.contains("lines=\"1-1\"")));
+    }
+
     #[tokio::test]
     async fn test_multiple_vars() {
         let dir = TempDir::new().unwrap();
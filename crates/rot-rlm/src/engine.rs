@@ -1,14 +1,143 @@
-use crate::repl::ReplEnv;
-use crate::prompts::RLM_SYSTEM_PROMPT;
-use rot_core::{Agent, Message};
-use std::sync::Arc;
+use crate::cache::RlmCache;
+use crate::context::{chunk_context, context_len, read_context, ContextFile};
+use crate::prompts::{
+    branch_prompt, map_prompt, reduce_prompt, select_prompt, NOTHING_RELEVANT, RLM_SYSTEM_PROMPT,
+};
+use crate::repl::{ReplEnv, ReplSnapshot};
 use regex::Regex;
+use rot_core::audit::{ApprovalDecision, ToolOutcome};
+use rot_core::{Agent, Message, SandboxMode};
+use rot_sandbox::SandboxPolicy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Window assumed when the provider doesn't list the current model.
+const DEFAULT_CONTEXT_WINDOW: usize = 128_000;
+
+/// Rough characters per token, as in `rot_core::compaction`.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Callback for the engine's state after each iteration.
+pub type CheckpointCallback = Arc<dyn Fn(&RlmCheckpoint) + Send + Sync>;
+
+/// Callback for each [`RlmProgress`] event.
+pub type ProgressCallback = Arc<dyn Fn(&RlmProgress) + Send + Sync>;
+
+/// Longest sub-goal carried in a progress event, in characters.
+const GOAL_CHARS: usize = 120;
+
+/// Stage of an RLM run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RlmPhase {
+    /// A REPL iteration is starting.
+    Iteration,
+    /// Notes are being taken on one chunk of a large context.
+    Chunk,
+    /// Chunk notes are being merged.
+    Reduce,
+    /// Answers from several branches are being merged.
+    Select,
+    /// A branch ended with an error; the other branches carry on.
+    BranchFailed,
+    /// The run produced its answer.
+    Done,
+    /// The answer of an earlier run was reused.
+    Cached,
+}
+
+/// Progress of an RLM run, reported to [`RlmConfig::on_progress`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RlmProgress {
+    pub phase: RlmPhase,
+    /// 1-based step within the phase; 0 for the final reduce and for
+    /// phases without steps.
+    pub step: usize,
+    /// Steps in the phase: the iteration budget, chunks, reduce groups, or
+    /// answers being selected from.
+    pub total: usize,
+    /// 1-based branch and branch count, for events from a branch.
+    pub branch: Option<(usize, usize)>,
+    /// Tokens used by the whole run so far, across all branches.
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    /// What the step is working on: the plan from the model's last reply,
+    /// the chunk's span, or a failed branch's error.
+    pub goal: Option<String>,
+}
+
+impl RlmProgress {
+    pub fn total_tokens(&self) -> usize {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+impl std::fmt::Display for RlmProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((branch, branches)) = self.branch {
+            write!(f, "RLM BRANCH {branch}/{branches} ")?;
+        }
+        match self.phase {
+            RlmPhase::Iteration => write!(f, "RLM ITERATION {}/{}", self.step, self.total),
+            RlmPhase::Chunk => {
+                write!(f, "RLM CHUNK {}/{}", self.step, self.total)?;
+                match &self.goal {
+                    Some(span) => write!(f, " ({span})"),
+                    None => Ok(()),
+                }
+            }
+            RlmPhase::Reduce if self.step == 0 => write!(f, "RLM REDUCE final"),
+            RlmPhase::Reduce => write!(f, "RLM REDUCE {}/{}", self.step, self.total),
+            RlmPhase::Select => write!(f, "RLM SELECT {} answers", self.total),
+            RlmPhase::BranchFailed => {
+                write!(
+                    f,
+                    "failed: {}",
+                    self.goal.as_deref().unwrap_or("unknown error")
+                )
+            }
+            RlmPhase::Done => write!(f, "RLM DONE ({} tokens)", self.total_tokens()),
+            RlmPhase::Cached => write!(f, "RLM CACHED"),
+        }
+    }
+}
 
 /// RLM configuration
+#[derive(Clone)]
 pub struct RlmConfig {
     pub max_iterations: usize,
     pub max_timeout: Option<std::time::Duration>,
-    pub on_progress: Option<Arc<dyn Fn(String) + Send + Sync>>,
+    pub on_progress: Option<ProgressCallback>,
+    /// Called after every iteration with enough state to resume the run.
+    pub on_checkpoint: Option<CheckpointCallback>,
+    /// Stop once this many iterations in a row write no code or repeat the
+    /// previous iteration's REPL output.
+    pub max_stalled_iterations: Option<usize>,
+    /// Tokens of REPL output shown to the model per iteration; longer
+    /// output keeps its start and end.
+    pub iteration_output_tokens: usize,
+    /// Instructions used in place of [`RLM_SYSTEM_PROMPT`].
+    pub system_prompt: Option<String>,
+    /// Independent explorations of the task; above one, each runs in its own
+    /// REPL under an agent task slot and their answers are merged.
+    pub branches: usize,
+    /// Where answers are looked up before a run and stored after it.
+    pub cache: Option<RlmCache>,
+}
+
+/// Engine state between iterations; [`RlmEngine::resume`] continues from it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RlmCheckpoint {
+    pub prompt: String,
+    pub context_path: String,
+    /// Iterations already completed.
+    pub iteration: usize,
+    /// Conversation with the model so far.
+    pub history: Vec<Message>,
+    /// Prompt sent to the model in the next iteration.
+    pub next_prompt: String,
+    pub repl: ReplSnapshot,
 }
 
 impl Default for RlmConfig {
@@ -17,6 +146,30 @@ impl Default for RlmConfig {
             max_iterations: 30,
             max_timeout: Some(std::time::Duration::from_secs(300)),
             on_progress: None,
+            on_checkpoint: None,
+            max_stalled_iterations: None,
+            iteration_output_tokens: 2_500,
+            system_prompt: None,
+            branches: 1,
+            cache: None,
+        }
+    }
+}
+
+impl RlmConfig {
+    /// Engine limits from the `rlm` section of the config.
+    pub fn from_settings(settings: &rot_core::config::RlmSettings) -> Self {
+        Self {
+            max_iterations: settings.max_iterations.max(1),
+            max_timeout: settings.timeout(),
+            on_progress: None,
+            on_checkpoint: None,
+            max_stalled_iterations: (settings.max_stalled_iterations > 0)
+                .then_some(settings.max_stalled_iterations),
+            iteration_output_tokens: settings.iteration_output_tokens.max(1),
+            system_prompt: settings.system_prompt.clone(),
+            branches: settings.branches.max(1),
+            cache: settings.cache.then(RlmCache::new),
         }
     }
 }
@@ -25,14 +178,33 @@ pub struct RlmEngine {
     config: RlmConfig,
     agent: Arc<Agent>,
     repl: ReplEnv,
+    /// Branch and branch count when this engine explores one branch.
+    branch: Option<(usize, usize)>,
+    /// Agent token totals when the run started.
+    tokens_at_start: (usize, usize),
 }
 
 impl RlmEngine {
+    /// An engine whose REPL runs in the agent's working directory under its
+    /// sandbox, with each code block approved as a `bash` call under its approval policy.
     pub fn new(config: RlmConfig, agent: Arc<Agent>) -> Self {
+        let security = agent.runtime_security();
+        let repl = ReplEnv::with_sandbox(SandboxPolicy {
+            mode: match security.sandbox_mode {
+                SandboxMode::ReadOnly => rot_sandbox::SandboxMode::ReadOnly,
+                SandboxMode::WorkspaceWrite => rot_sandbox::SandboxMode::WorkspaceWrite,
+                SandboxMode::DangerFullAccess => rot_sandbox::SandboxMode::DangerFullAccess,
+            },
+            network_access: security.allows_network(),
+        })
+        .with_working_dir(agent.working_dir());
+        let tokens_at_start = agent.tokens_used();
         Self {
             config,
             agent,
-            repl: ReplEnv::new(),
+            repl,
+            branch: None,
+            tokens_at_start,
         }
     }
 
@@ -58,22 +230,134 @@ impl RlmEngine {
         Ok(result)
     }
 
-    /// Process a prompt using RLM with dynamic context
+    /// Process a prompt using RLM with dynamic context.
+    ///
+    /// A context larger than the model window is answered by map/reduce
+    /// instead: one request per chunk collects notes for the task, and the
+    /// notes are combined into the answer. Those runs report each chunk to
+    /// `on_progress` but leave no checkpoints, as do runs with several
+    /// branches.
+    ///
+    /// With a cache set, an earlier answer to the same prompt from the same
+    /// model about the same context content is returned without a run.
     pub async fn process(&mut self, prompt: &str, context_path: &str) -> anyhow::Result<String> {
-        let start = std::time::Instant::now();
+        self.tokens_at_start = self.agent.tokens_used();
+        // An unreadable context is left for the REPL to report.
+        let files = read_context_async(context_path).await;
+        let key = self.cache_key(prompt, files.as_deref());
+        if let (Some(cache), Some(key)) = (&self.config.cache, &key) {
+            if let Some(answer) = cache.get(key).await {
+                self.report(RlmPhase::Cached, 0, 0, None);
+                return Ok(answer);
+            }
+        }
+        let answer = self.answer(prompt, context_path, files).await?;
+        self.finish(prompt, key, &answer).await;
+        Ok(answer)
+    }
 
+    async fn answer(
+        &mut self,
+        prompt: &str,
+        context_path: &str,
+        files: Option<Vec<ContextFile>>,
+    ) -> anyhow::Result<String> {
+        let window_chars = self
+            .agent
+            .context_window()
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+            * CHARS_PER_TOKEN;
+        if let Some(files) = files {
+            if context_len(&files) > window_chars {
+                // Half the window per request leaves room for the prompt and reply.
+                return self.map_reduce(prompt, &files, window_chars / 2).await;
+            }
+        }
+        if self.config.branches > 1 {
+            if let Some(answer) = self.explore(prompt, context_path).await? {
+                return Ok(answer);
+            }
+        }
+        self.explore_one(prompt, context_path).await
+    }
+
+    /// Work on `prompt` in this engine's REPL until the model calls FINAL.
+    async fn explore_one(&mut self, prompt: &str, context_path: &str) -> anyhow::Result<String> {
         // 1. Initialize REPL with context
         self.repl.init(context_path).await?;
-        
+
+        let metadata = self.build_metadata(prompt);
+        let state = RlmCheckpoint {
+            prompt: prompt.to_string(),
+            context_path: context_path.to_string(),
+            iteration: 0,
+            history: vec![Message::user(format!(
+                "SYSTEM INSTRUCTIONS FOR THIS TASK:\n{}\n\n{}",
+                self.config
+                    .system_prompt
+                    .as_deref()
+                    .unwrap_or(RLM_SYSTEM_PROMPT),
+                metadata
+            ))],
+            next_prompt: metadata,
+            repl: ReplSnapshot::default(),
+        };
+        self.run(state).await
+    }
+
+    /// Continue an interrupted run from its last checkpoint, rebuilding the
+    /// REPL's variables, functions, and directory first. The iteration budget
+    /// counts the iterations already done; the timeout and the stall count
+    /// start over.
+    pub async fn resume(&mut self, checkpoint: RlmCheckpoint) -> anyhow::Result<String> {
+        self.tokens_at_start = self.agent.tokens_used();
+        let prompt = checkpoint.prompt.clone();
+        let files = read_context_async(&checkpoint.context_path).await;
+        let key = self.cache_key(&prompt, files.as_deref());
+        self.repl.init(&checkpoint.context_path).await?;
+        self.repl.restore(&checkpoint.repl).await?;
+        let answer = self.run(checkpoint).await?;
+        self.finish(&prompt, key, &answer).await;
+        Ok(answer)
+    }
+
+    /// Cache key for `prompt` about `files`, when caching is on and the
+    /// context could be read.
+    fn cache_key(&self, prompt: &str, files: Option<&[ContextFile]>) -> Option<String> {
+        self.config.cache.as_ref()?;
+        Some(RlmCache::key(prompt, files?, &self.agent.model_label()))
+    }
+
+    /// Store a finished run's answer and report it done.
+    async fn finish(&self, prompt: &str, key: Option<String>, answer: &str) {
+        if let (Some(cache), Some(key)) = (&self.config.cache, key) {
+            if let Err(e) = cache
+                .put(&key, prompt, &self.agent.model_label(), answer)
+                .await
+            {
+                tracing::warn!("Failed to cache RLM answer: {e}");
+            }
+        }
+        self.report(RlmPhase::Done, 0, 0, None);
+    }
+
+    async fn run(&mut self, state: RlmCheckpoint) -> anyhow::Result<String> {
+        let start = std::time::Instant::now();
+
         let repl_block_re = Regex::new(r"```repl\n([\s\S]*?)```").unwrap();
         let final_query_re = Regex::new(r"FINAL_ANSWER:(.*)").unwrap();
 
-        let metadata = self.build_metadata(prompt);
-        let mut history: Vec<Message> = vec![
-            Message::user(format!("SYSTEM INSTRUCTIONS FOR THIS TASK:\n{}\n\n{}", RLM_SYSTEM_PROMPT, metadata))
-        ];
-        let mut next_action_prompt = String::new();
-        let mut current_iteration = 0;
+        let RlmCheckpoint {
+            prompt,
+            context_path,
+            iteration: mut current_iteration,
+            mut history,
+            next_prompt: mut next_action_prompt,
+            ..
+        } = state;
+        let mut stalled = 0;
+        let mut last_output: Option<String> = None;
+        let mut goal = None;
 
         while current_iteration < self.config.max_iterations {
             if let Some(timeout) = self.config.max_timeout {
@@ -82,19 +366,20 @@ impl RlmEngine {
                 }
             }
             
-            if let Some(cb) = &self.config.on_progress {
-                cb(format!("RLM ITERATION {}/{}", current_iteration + 1, self.config.max_iterations));
-            }
+            self.report(
+                RlmPhase::Iteration,
+                current_iteration + 1,
+                self.config.max_iterations,
+                goal.take(),
+            );
 
             // To properly let Agent drive, we just call process
-            let step_prompt = if current_iteration == 0 {
-                metadata.clone()
-            } else {
-                next_action_prompt.clone()
-            };
-
-            let response_msg = self.agent.process(&mut history, &step_prompt).await?;
+            let response_msg = self
+                .agent
+                .process(&mut history, &next_action_prompt)
+                .await?;
             let response_text = response_msg.text();
+            goal = stated_goal(&response_text);
 
             // Extract code blocks
             let mut code_blocks = Vec::new();
@@ -108,13 +393,44 @@ impl RlmEngine {
                 // If it didn't write code, ask it to write code or conclude
                 next_action_prompt = "You didn't write any ` ```repl ` code blocks. To process the context, you must execute bash commands, or output a FINAL response. What is your next action?".to_string();
                 current_iteration += 1;
+                stalled += 1;
+                self.check_stalled(stalled)?;
+                self.checkpoint(
+                    &prompt,
+                    &context_path,
+                    current_iteration,
+                    &history,
+                    &next_action_prompt,
+                )
+                .await?;
                 continue;
             }
 
             // Execute code blocks sequentially
             let mut iteration_output = String::new();
-            for code in code_blocks {
+            for (index, code) in code_blocks.into_iter().enumerate() {
+                let call_id = format!("rlm-repl-{}.{}", current_iteration + 1, index + 1);
+                let (code, approval) = match self.approve(&code, &call_id).await {
+                    Ok(approved) => approved,
+                    Err(refusal) => {
+                        iteration_output
+                            .push_str(&format!("$ {}\n> not run: {refusal}\n\n", code.trim()));
+                        continue;
+                    }
+                };
+                let started = std::time::Instant::now();
                 let mut repl_result = self.repl.execute(&code).await?;
+                self.agent.audit_tool_call(
+                    "bash",
+                    &call_id,
+                    &serde_json::json!({ "command": code }),
+                    approval,
+                    Some(ToolOutcome {
+                        is_error: repl_result.exit_code != Some(0),
+                        exit_code: repl_result.exit_code.map(i64::from),
+                        duration_ms: Some(started.elapsed().as_millis() as u64),
+                    }),
+                );
                 
                 // Process potential `llm_query` responses returned via stdout
                 repl_result.stdout = self.process_llm_queries(&repl_result.stdout).await?;
@@ -134,14 +450,17 @@ impl RlmEngine {
                 }
             }
 
-            // Limit response size if stdout was massive
-            let mut final_out = iteration_output;
-            if final_out.len() > 10000 {
-                let trunc_msg = "\n...[output truncated due to length]...\n";
-                let start_part = &final_out[..5000];
-                let end_part = &final_out[final_out.len() - 5000..];
-                final_out = format!("{}{}{}", start_part, trunc_msg, end_part);
+            if last_output.as_deref() == Some(iteration_output.as_str()) {
+                stalled += 1;
+                self.check_stalled(stalled)?;
+            } else {
+                stalled = 0;
             }
+            let final_out = truncate_output(
+                &iteration_output,
+                self.config.iteration_output_tokens * CHARS_PER_TOKEN,
+            );
+            last_output = Some(iteration_output);
 
             next_action_prompt = format!(
                 "Execution Results:\n```\n{}\n```\nWhat is your next action? (Analyze the results or call FINAL())",
@@ -149,11 +468,248 @@ impl RlmEngine {
             );
             
             current_iteration += 1;
+            self.checkpoint(
+                &prompt,
+                &context_path,
+                current_iteration,
+                &history,
+                &next_action_prompt,
+            )
+            .await?;
         }
 
         Err(anyhow::anyhow!("RLM Max Iterations Reached without calling FINAL"))
     }
 
+    /// Run every branch in its own engine at once, each holding an agent
+    /// task slot, and merge their answers. Returns `None` when the task
+    /// budget has no slot left for any branch.
+    async fn explore(&self, prompt: &str, context_path: &str) -> anyhow::Result<Option<String>> {
+        let branches = self.config.branches;
+        let mut running = tokio::task::JoinSet::new();
+        for index in 0..branches {
+            let mut config = self.config.clone();
+            config.branches = 1;
+            config.on_checkpoint = None;
+            let agent = self.agent.clone();
+            let tokens_at_start = self.tokens_at_start;
+            let prompt = format!("{prompt}\n\n{}", branch_prompt(index, branches));
+            let context_path = context_path.to_string();
+            running.spawn(async move {
+                let Ok(_slot) = agent.acquire_task_slot().await else {
+                    return (index, None);
+                };
+                let mut engine = RlmEngine::new(config, agent);
+                engine.branch = Some((index + 1, branches));
+                engine.tokens_at_start = tokens_at_start;
+                (
+                    index,
+                    Some(engine.explore_one(&prompt, &context_path).await),
+                )
+            });
+        }
+
+        let mut results = Vec::new();
+        while let Some(joined) = running.join_next().await {
+            let (index, result) = joined?;
+            if let Some(result) = result {
+                results.push((index, result));
+            }
+        }
+        if results.is_empty() {
+            return Ok(None);
+        }
+        results.sort_by_key(|(index, _)| *index);
+
+        let mut answers = Vec::new();
+        let mut first_error = None;
+        for (index, result) in results {
+            match result {
+                Ok(answer) => answers.push(answer),
+                Err(e) => {
+                    self.report_branch(
+                        Some((index + 1, branches)),
+                        RlmPhase::BranchFailed,
+                        0,
+                        0,
+                        Some(e.to_string()),
+                    );
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match answers.len() {
+            0 => Err(first_error.unwrap_or_else(|| anyhow::anyhow!("every RLM branch failed"))),
+            1 => Ok(answers.pop()),
+            n => {
+                self.report(RlmPhase::Select, 0, n, None);
+                let merged = self
+                    .agent
+                    .process(&mut Vec::new(), &select_prompt(prompt, &answers))
+                    .await?;
+                Ok(Some(merged.text()))
+            }
+        }
+    }
+
+    /// Answer `prompt` from notes taken on each chunk of `files`, merging the
+    /// notes in rounds until one request can hold them all.
+    async fn map_reduce(
+        &self,
+        prompt: &str,
+        files: &[ContextFile],
+        chunk_chars: usize,
+    ) -> anyhow::Result<String> {
+        let start = std::time::Instant::now();
+        let chunks = chunk_context(files, chunk_chars);
+
+        let mut notes = Vec::new();
+        for chunk in &chunks {
+            self.check_timeout(start)?;
+            self.report(
+                RlmPhase::Chunk,
+                chunk.index + 1,
+                chunks.len(),
+                Some(chunk.span.clone()),
+            );
+            let reply = self
+                .agent
+                .process(&mut Vec::new(), &map_prompt(prompt, chunk, chunks.len()))
+                .await?
+                .text();
+            let reply = reply.trim();
+            if !reply.is_empty() && reply != NOTHING_RELEVANT {
+                notes.push(format!("Notes on {}:\n{reply}", chunk.span));
+            }
+        }
+
+        loop {
+            let groups = group_notes(notes, chunk_chars);
+            if groups.len() <= 1 {
+                self.check_timeout(start)?;
+                self.report(RlmPhase::Reduce, 0, 1, None);
+                let notes = groups.into_iter().next().unwrap_or_default();
+                let answer = self
+                    .agent
+                    .process(&mut Vec::new(), &reduce_prompt(prompt, &notes, true))
+                    .await?;
+                return Ok(answer.text());
+            }
+            notes = Vec::with_capacity(groups.len());
+            for (index, group) in groups.iter().enumerate() {
+                self.check_timeout(start)?;
+                self.report(RlmPhase::Reduce, index + 1, groups.len(), None);
+                let merged = self
+                    .agent
+                    .process(&mut Vec::new(), &reduce_prompt(prompt, group, false))
+                    .await?;
+                notes.push(merged.text());
+            }
+        }
+    }
+
+    /// Approve a code block as a `bash` call. Returns the code to run, which
+    /// the user may have edited, and how it was approved, or why it must not
+    /// run. Refusals go to the audit log here; runs once they finish.
+    async fn approve(
+        &self,
+        code: &str,
+        call_id: &str,
+    ) -> Result<(String, ApprovalDecision), String> {
+        let args = serde_json::json!({ "command": code });
+        let (approved, decision) = if self.agent.runtime_security().dry_run {
+            (
+                Err("dry run; REPL code is not executed".to_string()),
+                ApprovalDecision::Blocked,
+            )
+        } else {
+            self.agent.decide_tool_call("bash", &args).await
+        };
+        match approved {
+            Ok(args) => {
+                let code = args
+                    .get("command")
+                    .and_then(|command| command.as_str())
+                    .unwrap_or(code)
+                    .to_string();
+                Ok((code, decision))
+            }
+            Err(refusal) => {
+                self.agent
+                    .audit_tool_call("bash", call_id, &args, decision, None);
+                Err(refusal)
+            }
+        }
+    }
+
+    fn check_stalled(&self, stalled: usize) -> anyhow::Result<()> {
+        match self.config.max_stalled_iterations {
+            Some(limit) if stalled >= limit => Err(anyhow::anyhow!(
+                "RLM stopped after {stalled} iterations without new REPL output"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_timeout(&self, start: std::time::Instant) -> anyhow::Result<()> {
+        match self.config.max_timeout {
+            Some(timeout) if start.elapsed() > timeout => {
+                Err(anyhow::anyhow!("RLM Engine Timed out"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn report(&self, phase: RlmPhase, step: usize, total: usize, goal: Option<String>) {
+        self.report_branch(self.branch, phase, step, total, goal);
+    }
+
+    fn report_branch(
+        &self,
+        branch: Option<(usize, usize)>,
+        phase: RlmPhase,
+        step: usize,
+        total: usize,
+        goal: Option<String>,
+    ) {
+        let Some(callback) = &self.config.on_progress else {
+            return;
+        };
+        let (input, output) = self.agent.tokens_used();
+        callback(&RlmProgress {
+            phase,
+            step,
+            total,
+            branch,
+            input_tokens: input.saturating_sub(self.tokens_at_start.0),
+            output_tokens: output.saturating_sub(self.tokens_at_start.1),
+            goal,
+        });
+    }
+
+    /// Report the state after an iteration to `on_checkpoint`, if set.
+    async fn checkpoint(
+        &mut self,
+        prompt: &str,
+        context_path: &str,
+        iteration: usize,
+        history: &[Message],
+        next_prompt: &str,
+    ) -> anyhow::Result<()> {
+        let Some(callback) = self.config.on_checkpoint.clone() else {
+            return Ok(());
+        };
+        callback(&RlmCheckpoint {
+            prompt: prompt.to_string(),
+            context_path: context_path.to_string(),
+            iteration,
+            history: history.to_vec(),
+            next_prompt: next_prompt.to_string(),
+            repl: self.repl.snapshot().await?,
+        });
+        Ok(())
+    }
+
     fn build_metadata(&self, prompt: &str) -> String {
         format!(
             r#"TASK:
@@ -165,3 +721,113 @@ Begin by executing `context_preview()` and `context_length()` inside a repl bloc
         )
     }
 }
+
+/// The text files under `path`, or `None` when they can't be read.
+async fn read_context_async(path: &str) -> Option<Vec<ContextFile>> {
+    let path = PathBuf::from(path);
+    tokio::task::spawn_blocking(move || read_context(&path))
+        .await
+        .ok()?
+        .ok()
+}
+
+/// `output` cut to its first and last `max_chars / 2` characters when longer.
+fn truncate_output(output: &str, max_chars: usize) -> String {
+    if output.len() <= max_chars {
+        return output.to_string();
+    }
+    let mut head = max_chars / 2;
+    while !output.is_char_boundary(head) {
+        head -= 1;
+    }
+    let mut tail = output.len() - max_chars / 2;
+    while !output.is_char_boundary(tail) {
+        tail += 1;
+    }
+    format!(
+        "{}\n...[output truncated due to length]...\n{}",
+        &output[..head],
+        &output[tail..]
+    )
+}
+
+/// The first line of a model reply outside its code blocks, which is
+/// usually what the model means to do next.
+fn stated_goal(reply: &str) -> Option<String> {
+    let mut in_code = false;
+    for line in reply.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code || line.is_empty() {
+            continue;
+        }
+        let goal: String = line.chars().take(GOAL_CHARS).collect();
+        return Some(if goal.len() < line.len() {
+            format!("{goal}…")
+        } else {
+            goal
+        });
+    }
+    None
+}
+
+/// Split notes into groups of at most `max_chars` each. Every group but a
+/// lone last one takes at least two notes, so each reduce round shrinks.
+fn group_notes(notes: Vec<String>, max_chars: usize) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    let mut size = 0;
+    for note in notes {
+        match groups.last_mut() {
+            Some(group) if group.len() < 2 || size + note.len() <= max_chars => {
+                size += note.len();
+                group.push(note);
+            }
+            _ => {
+                size = note.len();
+                groups.push(vec![note]);
+            }
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_output_keeps_both_ends() {
+        assert_eq!(truncate_output("short", 10), "short");
+        let cut = truncate_output(&format!("{}{}", "a".repeat(10), "é".repeat(10)), 10);
+        assert!(cut.starts_with("aaaaa\n...[output truncated"));
+        assert!(cut.ends_with("]...\néé"));
+    }
+
+    #[test]
+    fn test_stated_goal_skips_code() {
+        let reply = "```repl\ncontext_preview\n```\n\nCount the ERROR lines next.\nThen stop.";
+        assert_eq!(
+            stated_goal(reply).as_deref(),
+            Some("Count the ERROR lines next.")
+        );
+        assert_eq!(stated_goal("```repl\nls\n```"), None);
+        let long = stated_goal(&"x".repeat(GOAL_CHARS + 5)).unwrap();
+        assert_eq!(long.chars().count(), GOAL_CHARS + 1);
+    }
+
+    #[test]
+    fn test_group_notes_always_shrinks() {
+        let notes = |sizes: &[usize]| sizes.iter().map(|&n| "n".repeat(n)).collect::<Vec<_>>();
+        assert!(group_notes(Vec::new(), 100).is_empty());
+
+        let groups = group_notes(notes(&[40, 40, 40, 40]), 100);
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2]);
+
+        // Oversized notes still pair up rather than looping forever.
+        let groups = group_notes(notes(&[500, 500, 500]), 100);
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [2, 1]);
+    }
+}
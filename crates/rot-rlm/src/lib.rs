@@ -1,9 +1,14 @@
+pub mod cache;
 pub mod context;
 pub mod repl;
 pub mod prompts;
 pub mod engine;
 
+pub use cache::RlmCache;
 pub use context::*;
-pub use repl::{ReplEnv, ReplResult};
+pub use engine::{
+    CheckpointCallback, ProgressCallback, RlmCheckpoint, RlmConfig, RlmEngine, RlmPhase,
+    RlmProgress,
+};
 pub use prompts::RLM_SYSTEM_PROMPT;
-pub use engine::{RlmConfig, RlmEngine};
+pub use repl::{ReplEnv, ReplResult, ReplSnapshot};
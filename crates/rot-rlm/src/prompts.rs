@@ -1,3 +1,5 @@
+use crate::context::ContextChunk;
+
 pub const RLM_SYSTEM_PROMPT: &str = r#"
 You are tasked with answering a query with an associated large context.
 You can access, transform, and analyze this context interactively in a REPL environment. You must use the REPL to break down the task, search for answers, or write scripts to transform the data before reaching a conclusion.
@@ -27,3 +29,61 @@ Always write your execution blocks in:
 
 Think step-by-step and execute IMMEDIATELY.
 "#;
+
+/// Reply a map pass gives when its chunk holds nothing for the task.
+pub const NOTHING_RELEVANT: &str = "NOTHING RELEVANT";
+
+/// Prompt for one map pass over a chunk of a context too large to read at once.
+pub fn map_prompt(task: &str, chunk: &ContextChunk, chunks: usize) -> String {
+    format!(
+        r#"You are reading part {part} of {chunks} of a context too large to read at once. Other parts are read separately, and your notes will be combined with theirs to answer the task.
+
+TASK:
+{task}
+
+Write down everything in this part that helps with the task: facts, partial answers, and short quotes, each with its file path and line numbers. Do not answer the task itself. If nothing here is relevant, reply exactly {NOTHING_RELEVANT}.
+
+{content}"#,
+        part = chunk.index + 1,
+        content = chunk.content,
+    )
+}
+
+/// Prompt combining notes from map passes. With `last` set the model answers
+/// the task; otherwise it merges the notes for a later round.
+pub fn reduce_prompt(task: &str, notes: &[String], last: bool) -> String {
+    let notes = if notes.is_empty() {
+        "(none of the parts had anything relevant)".to_string()
+    } else {
+        notes.join("\n\n")
+    };
+    let instruction = if last {
+        "Answer the task from these notes. Cite file paths and line numbers where the notes give them, and say so if the notes are not enough to answer."
+    } else {
+        "Merge these notes into one set of notes for the task, keeping every relevant fact with its file path and line numbers and dropping repetition. Do not answer the task yet."
+    };
+    format!(
+        "TASK:\n{task}\n\nNotes taken from separate parts of a context too large to read at once:\n\n{notes}\n\n{instruction}"
+    )
+}
+
+/// Note added to the task for one of several branches exploring it at once.
+pub fn branch_prompt(index: usize, branches: usize) -> String {
+    format!(
+        "EXPLORATION BRANCH {branch} OF {branches}: {branches} independent REPL sessions are working on this task at the same time, and their answers will be compared. Take your own line of attack; for example, branch 1 surveys broadly while later branches test the most specific hypotheses they can form. Verify what you conclude in the REPL before calling FINAL.",
+        branch = index + 1,
+    )
+}
+
+/// Prompt choosing among, or merging, the answers of several branches.
+pub fn select_prompt(task: &str, answers: &[String]) -> String {
+    let answers = answers
+        .iter()
+        .enumerate()
+        .map(|(index, answer)| format!("<answer branch=\"{}\">\n{answer}\n</answer>", index + 1))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    format!(
+        "TASK:\n{task}\n\nIndependent explorations of the task gave these answers:\n\n{answers}\n\nWrite the final answer to the task. Keep what the answers agree on, prefer the claims best supported by evidence where they disagree, and combine complementary findings. Reply with the answer only, without mentioning the branches."
+    )
+}
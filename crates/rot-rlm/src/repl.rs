@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use rot_sandbox::{SandboxMode, SandboxPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, ChildStdout, ChildStderr, Command};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout};
 
 pub struct ReplResult {
     pub stdout: String,
@@ -11,10 +12,23 @@ pub struct ReplResult {
     pub variables: HashMap<String, String>,
 }
 
+/// Shell state captured by [`ReplEnv::snapshot`]: the working directory plus
+/// the variables and functions defined since [`ReplEnv::init`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplSnapshot {
+    pub cwd: Option<String>,
+    /// `declare` output that recreates the variables and functions when sourced.
+    pub script: String,
+}
+
 pub struct ReplEnv {
     working_dir: PathBuf,
     temp_dir: PathBuf,
+    /// Sandbox the shell runs under; the working directory is its workspace.
+    policy: SandboxPolicy,
     variables: HashMap<String, String>,
+    /// Variable and function names present right after `init`, left out of snapshots.
+    baseline: HashSet<String>,
     
     // Process handles
     process: Option<Child>,
@@ -30,14 +44,25 @@ impl Default for ReplEnv {
 }
 
 impl ReplEnv {
+    /// A shell with no sandbox.
     pub fn new() -> Self {
+        Self::with_sandbox(SandboxPolicy {
+            mode: SandboxMode::DangerFullAccess,
+            network_access: true,
+        })
+    }
+
+    /// A shell run under `policy`, with the current directory as the workspace.
+    pub fn with_sandbox(policy: SandboxPolicy) -> Self {
         let temp_dir = std::env::temp_dir().join(format!("rot-repl-{}", ulid::Ulid::new()));
         std::fs::create_dir_all(&temp_dir).ok();
 
         Self {
             working_dir: std::env::current_dir().unwrap_or_default(),
             temp_dir,
+            policy,
             variables: HashMap::new(),
+            baseline: HashSet::new(),
             process: None,
             stdin: None,
             stdout: None,
@@ -45,16 +70,34 @@ impl ReplEnv {
         }
     }
 
+    /// Start the shell in `dir`, which also becomes the sandbox workspace.
+    pub fn with_working_dir(mut self, dir: PathBuf) -> Self {
+        self.working_dir = dir;
+        self
+    }
+
+    /// Start the shell with `$CONTEXT_FILE` pointing at `context_path`. The
+    /// files matching a glob are bundled into one file first.
     pub async fn init(&mut self, context_path: &str) -> anyhow::Result<()> {
+        let context_path = if crate::context::is_glob(context_path) {
+            let pattern = PathBuf::from(context_path);
+            let files = tokio::task::spawn_blocking(move || crate::context::read_context(&pattern))
+                .await??;
+            let bundle = self.temp_dir.join("context.txt");
+            tokio::fs::write(&bundle, crate::context::bundle_context(&files)).await?;
+            bundle.display().to_string()
+        } else {
+            context_path.to_string()
+        };
+
         // Start bash shell for REPL
-        let mut cmd = Command::new("/bin/bash");
-        cmd.args(["--noprofile", "--norc"])
-            .current_dir(&self.working_dir)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        let mut child = cmd.spawn()?;
+        let mut child = rot_sandbox::spawn_command(
+            "/bin/bash",
+            &["--noprofile".to_string(), "--norc".to_string()],
+            &self.working_dir,
+            &HashMap::new(),
+            &self.policy,
+        )?;
         
         let stdin = child.stdin.take().expect("Failed to open stdin");
         let stdout = child.stdout.take().expect("Failed to open stdout");
@@ -66,17 +109,84 @@ impl ReplEnv {
         self.stderr = Some(BufReader::new(stderr));
 
         // Generate and execute setup script inside the bash shell
-        let setup_script = self.generate_setup_script(context_path);
+        let setup_script = self.generate_setup_script(&context_path);
         let setup_path = self.temp_dir.join("setup.sh");
         tokio::fs::write(&setup_path, &setup_script).await?;
         
         // Execute the setup script internally
         let init_code = format!("source {}\n", setup_path.display());
         self.execute(&init_code).await?;
+        self.baseline = self.defined_names().await?.into_iter().flatten().collect();
 
         Ok(())
     }
 
+    /// Names of the shell's variables and of its functions.
+    async fn defined_names(&mut self) -> anyhow::Result<[Vec<String>; 2]> {
+        let vars = self.execute("compgen -v").await?.stdout;
+        let functions = self.execute("compgen -A function").await?.stdout;
+        Ok([vars, functions].map(|out| out.lines().map(str::to_string).collect()))
+    }
+
+    /// Capture the working directory and the variables and functions the
+    /// model defined, so [`ReplEnv::restore`] can rebuild them in a new shell.
+    pub async fn snapshot(&mut self) -> anyhow::Result<ReplSnapshot> {
+        let [vars, functions] = self.defined_names().await?;
+        let new = |names: Vec<String>| -> Vec<String> {
+            names
+                .into_iter()
+                .filter(|name| !self.baseline.contains(name) && !is_shell_internal(name))
+                .collect()
+        };
+        let (vars, functions) = (new(vars), new(functions));
+
+        let mut script = String::new();
+        if !vars.is_empty() {
+            script.push_str(
+                &self
+                    .execute(&format!("declare -p {}", vars.join(" ")))
+                    .await?
+                    .stdout,
+            );
+            script.push('\n');
+        }
+        if !functions.is_empty() {
+            script.push_str(
+                &self
+                    .execute(&format!("declare -f {}", functions.join(" ")))
+                    .await?
+                    .stdout,
+            );
+            script.push('\n');
+        }
+        let cwd = self.execute("pwd").await?.stdout.trim().to_string();
+        Ok(ReplSnapshot {
+            cwd: (!cwd.is_empty()).then_some(cwd),
+            script,
+        })
+    }
+
+    /// Recreate a [`ReplSnapshot`] in this shell, after `init`.
+    pub async fn restore(&mut self, snapshot: &ReplSnapshot) -> anyhow::Result<()> {
+        // Code runs at the top level of the shell, so `declare` makes globals.
+        let result = self.execute(&snapshot.script).await?;
+        if result.exit_code != Some(0) {
+            anyhow::bail!("failed to restore REPL state: {}", result.stderr.trim());
+        }
+        if let Some(cwd) = &snapshot.cwd {
+            let result = self
+                .execute(&format!("cd '{}'", cwd.replace('\'', "'\\''")))
+                .await?;
+            if result.exit_code != Some(0) {
+                anyhow::bail!(
+                    "failed to restore REPL directory {cwd}: {}",
+                    result.stderr.trim()
+                );
+            }
+        }
+        Ok(())
+    }
+
     pub async fn execute(&mut self, code: &str) -> anyhow::Result<ReplResult> {
         let stdin = self.stdin.as_mut().expect("REPL not initialized");
         let stdout = self.stdout.as_mut().expect("REPL not initialized");
@@ -199,6 +309,11 @@ impl Drop for ReplEnv {
     }
 }
 
+/// Variables bash maintains itself, which cannot or should not be restored.
+fn is_shell_internal(name: &str) -> bool {
+    name.starts_with("BASH") || matches!(name, "_" | "FUNCNAME" | "PIPESTATUS" | "LINENO")
+}
+
 async fn read_until_delim<R: tokio::io::AsyncRead + Unpin>(
     reader: &mut BufReader<R>,
     delim: &str,
@@ -52,4 +52,38 @@ mod tests {
         println!("VARS OUTPUT: {}", res_vars.stdout);
         assert!(res_vars.stdout.contains("TEST_VAR"));
     }
+
+    #[tokio::test]
+    async fn test_snapshot_restores_into_new_shell() {
+        let mut context_file = NamedTempFile::new().unwrap();
+        writeln!(context_file, "ctx").unwrap();
+        let path = context_file.path().to_str().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut repl = ReplEnv::new();
+        repl.init(path).await.unwrap();
+        repl.execute(&format!(
+            "MATCHES=3\nexport NOTE='it'\\''s'\nFILES=(a b)\ncount_lines() {{ wc -l < \"$1\"; }}\ncd '{}'",
+            dir.path().display()
+        ))
+        .await
+        .unwrap();
+        let snapshot = repl.snapshot().await.unwrap();
+        assert!(!snapshot.script.contains("CONTEXT_FILE"));
+
+        let mut resumed = ReplEnv::new();
+        resumed.init(path).await.unwrap();
+        resumed.restore(&snapshot).await.unwrap();
+        let res = resumed
+            .execute("echo \"$MATCHES|$NOTE|${FILES[1]}|$(pwd)\"; count_lines \"$CONTEXT_FILE\"")
+            .await
+            .unwrap();
+        assert_eq!(
+            res.stdout.trim(),
+            format!(
+                "3|it's|b|{}\n1",
+                dir.path().canonicalize().unwrap().display()
+            )
+        );
+    }
 }
@@ -325,6 +325,8 @@ async fn run_with_timeout(
     mut command: Command,
     timeout: Duration,
 ) -> Result<SandboxRunResult, SandboxError> {
+    // Dropping the future on timeout or cancellation must not leave the process running.
+    command.kill_on_drop(true);
     let output = tokio::time::timeout(timeout, command.output())
         .await
         .map_err(|_| SandboxError::Timeout(timeout.as_secs()))?
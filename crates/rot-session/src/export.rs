@@ -0,0 +1,292 @@
+//! Export of session transcripts as Markdown or JSON.
+//!
+//! Markdown is for reading and sharing: messages become sections, tool calls
+//! show their arguments, and tool outputs are folded into `<details>` blocks.
+//! JSON keeps every entry in the same schema as the session file.
+
+use crate::error::SessionError;
+use crate::format::SessionEntry;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File format of an exported transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Readable Markdown with collapsed tool outputs.
+    Markdown,
+    /// Pretty-printed JSON array of session entries.
+    Json,
+}
+
+impl ExportFormat {
+    /// Format named `md`, `markdown`, or `json`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Format implied by the extension of `path`, defaulting to Markdown.
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+            .unwrap_or(Self::Markdown)
+    }
+
+    /// File extension for this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Render `entries` in `format`.
+pub fn export(entries: &[SessionEntry], format: ExportFormat) -> Result<String, SessionError> {
+    match format {
+        ExportFormat::Markdown => Ok(to_markdown(entries)),
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(entries)? + "\n"),
+    }
+}
+
+/// Render `entries` as Markdown.
+pub fn to_markdown(entries: &[SessionEntry]) -> String {
+    let mut out = String::new();
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    let mut last_role: Option<&str> = None;
+
+    for entry in entries {
+        match entry {
+            SessionEntry::SessionStart {
+                id,
+                cwd,
+                model,
+                provider,
+                agent,
+                ..
+            } => {
+                out.push_str(&format!("# Session {id}\n\n"));
+                out.push_str(&format!("- Model: `{provider}:{model}`\n"));
+                if let Some(agent) = agent {
+                    out.push_str(&format!("- Agent: `{agent}`\n"));
+                }
+                out.push_str(&format!("- Directory: `{cwd}`\n\n"));
+            }
+            SessionEntry::Message { role, content, .. } => {
+                // Tool results are rendered from their `ToolResult` entries.
+                if role == "tool" {
+                    continue;
+                }
+                if last_role != Some(role.as_str()) {
+                    out.push_str(&format!("## {}\n\n", role_title(role)));
+                    last_role = Some(role);
+                }
+                for block in content.as_array().into_iter().flatten() {
+                    push_block(&mut out, block);
+                }
+            }
+            SessionEntry::ToolCall {
+                id,
+                name,
+                arguments,
+                ..
+            } => {
+                tool_names.insert(id, name);
+                let arguments = serde_json::to_string_pretty(arguments).unwrap_or_default();
+                out.push_str(&format!("**Tool call:** `{name}`\n\n"));
+                out.push_str(&fenced(&arguments, "json"));
+                out.push('\n');
+            }
+            SessionEntry::ToolResult {
+                call_id,
+                output,
+                is_error,
+                ..
+            } => {
+                let name = tool_names.get(call_id.as_str()).copied().unwrap_or("tool");
+                let label = if *is_error { "error" } else { "output" };
+                let lines = output.lines().count();
+                out.push_str(&format!(
+                    "<details>\n<summary>{name} {label} ({lines} lines)</summary>\n\n"
+                ));
+                out.push_str(&fenced(output, ""));
+                out.push_str("\n</details>\n\n");
+            }
+            SessionEntry::ChildSessionLink {
+                child_session_id,
+                agent,
+                prompt,
+                ..
+            } => {
+                let prompt = prompt.lines().next().unwrap_or_default();
+                out.push_str(&format!(
+                    "> Delegated to `{agent}` (session `{child_session_id}`): {prompt}\n\n"
+                ));
+            }
+            SessionEntry::Compaction { summary, .. } => {
+                out.push_str("> **Earlier turns were compacted:**\n>\n");
+                for line in summary.lines() {
+                    out.push_str(&format!("> {line}\n"));
+                }
+                out.push('\n');
+            }
+            SessionEntry::Usage { .. }
+            | SessionEntry::RlmCheckpoint { .. }
+            | SessionEntry::RunSummary { .. }
+            | SessionEntry::CwdChange { .. }
+            | SessionEntry::Branch { .. } => {}
+        }
+    }
+
+    out
+}
+
+fn role_title(role: &str) -> String {
+    let mut chars = role.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Append one serialized content block of a message.
+fn push_block(out: &mut String, block: &serde_json::Value) {
+    let field = |name: &str| block.get(name).and_then(|value| value.as_str());
+    match field("type") {
+        Some("text") => {
+            let text = field("text").unwrap_or_default().trim();
+            if !text.is_empty() {
+                out.push_str(text);
+                out.push_str("\n\n");
+            }
+        }
+        Some("image") => {
+            let mime_type = field("mime_type").unwrap_or("image");
+            out.push_str(&format!("_[image: {mime_type}]_\n\n"));
+        }
+        Some("thinking") => {
+            let thinking = field("thinking").unwrap_or_default().trim();
+            if !thinking.is_empty() {
+                out.push_str("<details>\n<summary>Thinking</summary>\n\n");
+                out.push_str(thinking);
+                out.push_str("\n\n</details>\n\n");
+            }
+        }
+        // Tool calls are rendered from their `ToolCall` entries.
+        _ => {}
+    }
+}
+
+/// `text` in a code fence longer than any backtick run inside it.
+fn fenced(text: &str, lang: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    format!("{fence}{lang}\n{}\n{fence}\n", text.trim_end_matches('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<SessionEntry> {
+        vec![
+            SessionEntry::SessionStart {
+                id: "s1".to_string(),
+                timestamp: 1,
+                cwd: "/repo".to_string(),
+                model: "gpt-4o".to_string(),
+                provider: "openai".to_string(),
+                parent_session_id: None,
+                parent_tool_call_id: None,
+                agent: None,
+            },
+            SessionEntry::Message {
+                id: "m1".to_string(),
+                parent_id: None,
+                timestamp: 2,
+                role: "user".to_string(),
+                content: serde_json::json!([{"type": "text", "text": "Run the tests"}]),
+            },
+            SessionEntry::Message {
+                id: "m2".to_string(),
+                parent_id: Some("m1".to_string()),
+                timestamp: 3,
+                role: "assistant".to_string(),
+                content: serde_json::json!([
+                    {"type": "tool_call", "id": "c1", "name": "bash", "arguments": {"command": "cargo test"}}
+                ]),
+            },
+            SessionEntry::ToolCall {
+                id: "c1".to_string(),
+                parent_id: "m2".to_string(),
+                timestamp: 3,
+                name: "bash".to_string(),
+                arguments: serde_json::json!({"command": "cargo test"}),
+            },
+            SessionEntry::Message {
+                id: "m3".to_string(),
+                parent_id: Some("m2".to_string()),
+                timestamp: 4,
+                role: "tool".to_string(),
+                content: serde_json::json!([
+                    {"type": "tool_result", "tool_call_id": "c1", "content": "ok\n```", "is_error": false}
+                ]),
+            },
+            SessionEntry::ToolResult {
+                id: "m3:tool_result:0".to_string(),
+                call_id: "c1".to_string(),
+                timestamp: 4,
+                output: "ok\n```".to_string(),
+                is_error: false,
+            },
+            SessionEntry::Message {
+                id: "m4".to_string(),
+                parent_id: Some("m3".to_string()),
+                timestamp: 5,
+                role: "assistant".to_string(),
+                content: serde_json::json!([{"type": "text", "text": "All tests pass."}]),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_markdown_export_collapses_tool_output() {
+        let markdown = to_markdown(&entries());
+        assert!(markdown.starts_with("# Session s1\n\n- Model: `openai:gpt-4o`\n"));
+        assert!(
+            markdown.contains("## User\n\nRun the tests\n\n## Assistant\n\n**Tool call:** `bash`")
+        );
+        assert!(
+            markdown.contains("<summary>bash output (2 lines)</summary>\n\n````\nok\n```\n````\n")
+        );
+        // Consecutive assistant messages share one heading.
+        assert_eq!(markdown.matches("## Assistant").count(), 1);
+        assert!(markdown.ends_with("All tests pass.\n\n"));
+    }
+
+    #[test]
+    fn test_json_export_round_trips_entries() {
+        let json = export(&entries(), ExportFormat::Json).unwrap();
+        let parsed: Vec<SessionEntry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), entries().len());
+
+        assert_eq!(ExportFormat::from_name("MD"), Some(ExportFormat::Markdown));
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out/chat.json")),
+            ExportFormat::Json
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("chat.txt")),
+            ExportFormat::Markdown
+        );
+    }
+}
@@ -76,6 +76,43 @@ pub enum SessionEntry {
         first_kept_id: String,
     },
 
+    /// Tokens used by one provider request.
+    #[serde(rename = "usage")]
+    Usage {
+        id: String,
+        timestamp: u64,
+        model: String,
+        input_tokens: usize,
+        output_tokens: usize,
+        /// Agent that made the request, when known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        agent: Option<String>,
+    },
+
+    /// State of an RLM run after one of its iterations, for resuming it.
+    #[serde(rename = "rlm_checkpoint")]
+    RlmCheckpoint {
+        id: String,
+        timestamp: u64,
+        state: serde_json::Value,
+    },
+
+    /// What one run cost: time, tokens, tools, files, and subagents.
+    #[serde(rename = "run_summary")]
+    RunSummary {
+        id: String,
+        timestamp: u64,
+        summary: serde_json::Value,
+    },
+
+    /// The directory tools run in changed, e.g. with `/cd`.
+    #[serde(rename = "cwd_change")]
+    CwdChange {
+        id: String,
+        timestamp: u64,
+        cwd: String,
+    },
+
     /// A branch point for alternative conversation paths.
     #[serde(rename = "branch")]
     Branch {
@@ -142,6 +179,10 @@ pub fn entry_id(entry: &SessionEntry) -> &str {
         SessionEntry::ToolResult { id, .. } => id,
         SessionEntry::ChildSessionLink { id, .. } => id,
         SessionEntry::Compaction { id, .. } => id,
+        SessionEntry::Usage { id, .. } => id,
+        SessionEntry::RlmCheckpoint { id, .. } => id,
+        SessionEntry::RunSummary { id, .. } => id,
+        SessionEntry::CwdChange { id, .. } => id,
         SessionEntry::Branch { id, .. } => id,
     }
 }
@@ -155,6 +196,10 @@ pub fn entry_timestamp(entry: &SessionEntry) -> u64 {
         SessionEntry::ToolResult { timestamp, .. } => *timestamp,
         SessionEntry::ChildSessionLink { timestamp, .. } => *timestamp,
         SessionEntry::Compaction { timestamp, .. } => *timestamp,
+        SessionEntry::Usage { timestamp, .. } => *timestamp,
+        SessionEntry::RlmCheckpoint { timestamp, .. } => *timestamp,
+        SessionEntry::RunSummary { timestamp, .. } => *timestamp,
+        SessionEntry::CwdChange { timestamp, .. } => *timestamp,
         SessionEntry::Branch { timestamp, .. } => *timestamp,
     }
 }
@@ -265,6 +310,25 @@ mod tests {
         assert_eq!(entry_id(&deserialized), "comp_1");
     }
 
+    #[test]
+    fn test_usage_entry() {
+        let entry = SessionEntry::Usage {
+            id: "usage_1".to_string(),
+            timestamp: 1234567895,
+            model: "claude-sonnet-4-20250514".to_string(),
+            input_tokens: 1200,
+            output_tokens: 80,
+            agent: None,
+        };
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains("\"type\":\"usage\""));
+        assert!(!json.contains("agent"));
+
+        let deserialized: SessionEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(entry_id(&deserialized), "usage_1");
+    }
+
     #[test]
     fn test_branch_entry() {
         let entry = SessionEntry::Branch {
@@ -1,9 +1,11 @@
 //! rot-session: JSONL-based session persistence and management.
 
 mod error;
+pub mod export;
 pub mod format;
 pub mod store;
 
 pub use error::SessionError;
+pub use export::ExportFormat;
 pub use format::{entry_id, entry_timestamp, SessionEntry, SessionMeta, SessionTree, SessionTreeNode};
 pub use store::{Session, SessionStore};
@@ -22,6 +22,21 @@ pub struct Session {
     pub current_leaf: String,
 }
 
+impl Session {
+    /// Directory tools last ran in: the latest `cwd_change`, else `cwd`.
+    /// `cwd` itself never changes, since it decides where the file is kept.
+    pub fn working_dir(&self) -> PathBuf {
+        self.entries
+            .iter()
+            .rev()
+            .find_map(|entry| match entry {
+                SessionEntry::CwdChange { cwd, .. } => Some(PathBuf::from(cwd)),
+                _ => None,
+            })
+            .unwrap_or_else(|| self.cwd.clone())
+    }
+}
+
 /// Persistent session storage.
 pub struct SessionStore {
     sessions_dir: PathBuf,
@@ -59,6 +74,13 @@ impl SessionStore {
             .join(format!("{id}.jsonl"))
     }
 
+    /// Path of the prompt history file shared by sessions in `cwd`.
+    pub fn history_path(&self, cwd: &Path) -> PathBuf {
+        self.sessions_dir
+            .join(Self::cwd_hash(cwd))
+            .join("prompt_history")
+    }
+
     /// Create a new session.
     pub async fn create(
         &self,
@@ -217,12 +239,37 @@ impl SessionStore {
 
     /// List all sessions for a working directory, most recent first.
     pub async fn list_all(&self, cwd: &Path) -> Result<Vec<SessionMeta>, SessionError> {
-        let dir = self.sessions_dir.join(Self::cwd_hash(cwd));
+        let mut sessions = self
+            .list_dir(&self.sessions_dir.join(Self::cwd_hash(cwd)))
+            .await?;
+        // Sort by most recent first
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(sessions)
+    }
+
+    /// List sessions from every working directory, most recent first.
+    pub async fn list_everywhere(&self) -> Result<Vec<SessionMeta>, SessionError> {
+        if !self.sessions_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut dirs = fs::read_dir(&self.sessions_dir).await?;
+        let mut sessions = Vec::new();
+        while let Some(entry) = dirs.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                sessions.extend(self.list_dir(&entry.path()).await?);
+            }
+        }
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(sessions)
+    }
+
+    /// Metadata for the readable session files in one working directory's folder.
+    async fn list_dir(&self, dir: &Path) -> Result<Vec<SessionMeta>, SessionError> {
         if !dir.exists() {
             return Ok(Vec::new());
         }
 
-        let mut entries = fs::read_dir(&dir).await?;
+        let mut entries = fs::read_dir(dir).await?;
         let mut sessions = Vec::new();
 
         while let Some(entry) = entries.next_entry().await? {
@@ -233,12 +280,25 @@ impl SessionStore {
                 }
             }
         }
-
-        // Sort by most recent first
-        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
         Ok(sessions)
     }
 
+    /// The most recently updated top-level session in `cwd` that has messages.
+    ///
+    /// Delegated child sessions are skipped; ties are broken by the newer ID.
+    pub async fn latest(&self, cwd: &Path) -> Result<Option<SessionMeta>, SessionError> {
+        Ok(self
+            .list_all(cwd)
+            .await?
+            .into_iter()
+            .filter(|meta| meta.parent_session_id.is_none() && meta.message_count > 0)
+            .max_by(|a, b| {
+                a.updated_at
+                    .cmp(&b.updated_at)
+                    .then_with(|| a.id.cmp(&b.id))
+            }))
+    }
+
     /// Build the parent/child session tree for a session or the latest session in the cwd.
     pub async fn tree(
         &self,
@@ -315,6 +375,7 @@ impl SessionStore {
             .iter()
             .filter(|l| l.contains("\"type\":\"message\""))
             .count();
+        let title = session_title(&lines);
 
         match first {
             SessionEntry::SessionStart {
@@ -330,7 +391,7 @@ impl SessionStore {
                 id,
                 created_at: timestamp,
                 updated_at: entry_timestamp(&last),
-                title: None,
+                title,
                 cwd,
                 model,
                 provider,
@@ -351,6 +412,31 @@ impl Default for SessionStore {
     }
 }
 
+/// Longest session title derived from the first prompt, in characters.
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Title for a session: the first line of its first user prompt.
+fn session_title(lines: &[&str]) -> Option<String> {
+    let content = lines
+        .iter()
+        .filter(|line| line.contains("\"role\":\"user\""))
+        .find_map(|line| match serde_json::from_str(line) {
+            Ok(SessionEntry::Message { role, content, .. }) if role == "user" => Some(content),
+            _ => None,
+        })?;
+    let text = content
+        .as_array()?
+        .iter()
+        .find_map(|block| block.get("text").and_then(|t| t.as_str()))?;
+    let first_line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    if first_line.chars().count() > MAX_TITLE_CHARS {
+        let cut: String = first_line.chars().take(MAX_TITLE_CHARS - 1).collect();
+        Some(format!("{cut}…"))
+    } else {
+        Some(first_line.to_string())
+    }
+}
+
 fn build_tree_node(
     id: &str,
     metas_by_id: &HashMap<String, SessionMeta>,
@@ -427,6 +513,29 @@ mod tests {
         assert_eq!(loaded.current_leaf, "msg1");
     }
 
+    #[tokio::test]
+    async fn test_working_dir_follows_cwd_changes() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::with_dir(dir.path());
+        let cwd = dir.path().join("project");
+        std::fs::create_dir_all(&cwd).unwrap();
+
+        let mut session = store.create(&cwd, "claude", "anthropic").await.unwrap();
+        assert_eq!(session.working_dir(), cwd);
+        for sub in ["crates/a", "crates/b"] {
+            let entry = SessionEntry::CwdChange {
+                id: sub.to_string(),
+                timestamp: 1000,
+                cwd: cwd.join(sub).display().to_string(),
+            };
+            store.append(&mut session, entry).await.unwrap();
+        }
+
+        let loaded = store.load(&cwd, &session.id).await.unwrap();
+        assert_eq!(loaded.working_dir(), cwd.join("crates/b"));
+        assert_eq!(loaded.cwd, cwd);
+    }
+
     #[tokio::test]
     async fn test_create_child_session() {
         let dir = TempDir::new().unwrap();
@@ -506,6 +615,93 @@ mod tests {
         assert_eq!(sessions.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_list_everywhere_spans_working_directories() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::with_dir(dir.path().join("sessions"));
+        assert!(store.list_everywhere().await.unwrap().is_empty());
+
+        store
+            .create(&dir.path().join("a"), "claude", "anthropic")
+            .await
+            .unwrap();
+        store
+            .create(&dir.path().join("b"), "gpt-4", "openai")
+            .await
+            .unwrap();
+
+        let sessions = store.list_everywhere().await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions
+            .iter()
+            .any(|s| s.cwd.ends_with('b') && s.model == "gpt-4"));
+    }
+
+    #[tokio::test]
+    async fn test_list_recent_titles_from_first_prompt() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::with_dir(dir.path());
+        let cwd = dir.path().join("project");
+        std::fs::create_dir_all(&cwd).unwrap();
+
+        let mut session = store.create(&cwd, "claude", "anthropic").await.unwrap();
+        store
+            .append(
+                &mut session,
+                SessionEntry::Message {
+                    id: "msg1".to_string(),
+                    parent_id: None,
+                    timestamp: 1000,
+                    role: "user".to_string(),
+                    content: serde_json::json!([{"type": "text", "text": "\nFix the parser\nmore detail"}]),
+                },
+            )
+            .await
+            .unwrap();
+        store.create(&cwd, "claude", "anthropic").await.unwrap();
+
+        let sessions = store.list_recent(&cwd, 10).await.unwrap();
+        let titled = sessions.iter().find(|s| s.id == session.id).unwrap();
+        assert_eq!(titled.title.as_deref(), Some("Fix the parser"));
+        assert_eq!(titled.message_count, 1);
+        assert!(sessions.iter().any(|s| s.title.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_latest_skips_empty_and_child_sessions() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::with_dir(dir.path());
+        let cwd = dir.path().join("project");
+        std::fs::create_dir_all(&cwd).unwrap();
+        assert!(store.latest(&cwd).await.unwrap().is_none());
+
+        let message = |id: &str| SessionEntry::Message {
+            id: id.to_string(),
+            parent_id: None,
+            timestamp: 1000,
+            role: "user".to_string(),
+            content: serde_json::json!([{"type": "text", "text": "hello"}]),
+        };
+        let mut session = store.create(&cwd, "claude", "anthropic").await.unwrap();
+        store.append(&mut session, message("msg1")).await.unwrap();
+        let mut child = store
+            .create_child(
+                &cwd,
+                "claude",
+                "anthropic",
+                &session.id,
+                None,
+                Some("review"),
+            )
+            .await
+            .unwrap();
+        store.append(&mut child, message("msg2")).await.unwrap();
+        store.create(&cwd, "claude", "anthropic").await.unwrap();
+
+        let latest = store.latest(&cwd).await.unwrap().unwrap();
+        assert_eq!(latest.id, session.id);
+    }
+
     #[tokio::test]
     async fn test_build_session_tree_from_child_focus() {
         let dir = TempDir::new().unwrap();
@@ -1,6 +1,7 @@
 //! Bash tool — shell command execution.
 
 use crate::error::ToolError;
+use crate::shell::{CommandPolicy, CommandVerdict};
 use crate::traits::{SandboxMode, Tool, ToolContext, ToolResult};
 use async_trait::async_trait;
 use rot_sandbox::{SandboxPolicy, run_shell_command};
@@ -13,12 +14,22 @@ const MAX_OUTPUT_BYTES: usize = 50 * 1024; // 50KB
 pub struct BashParams {
     /// Shell command to execute.
     pub command: String,
-    /// Optional timeout in seconds. Default: 120.
+    /// Optional timeout in seconds. Defaults to the configured tool timeout.
     #[serde(default)]
     pub timeout: Option<u64>,
 }
 
-pub struct BashTool;
+#[derive(Default)]
+pub struct BashTool {
+    commands: CommandPolicy,
+}
+
+impl BashTool {
+    /// A bash tool that classifies commands against `commands`.
+    pub fn new(commands: CommandPolicy) -> Self {
+        Self { commands }
+    }
+}
 
 #[async_trait]
 impl Tool for BashTool {
@@ -36,6 +47,13 @@ impl Tool for BashTool {
             .expect("schema serialization should not fail")
     }
 
+    fn command_verdict(&self, args: &serde_json::Value) -> CommandVerdict {
+        match args.get("command").and_then(|c| c.as_str()) {
+            Some(command) => self.commands.classify(command),
+            None => CommandVerdict::Unlisted,
+        }
+    }
+
     async fn execute(
         &self,
         args: serde_json::Value,
@@ -44,9 +62,26 @@ impl Tool for BashTool {
         let params: BashParams = serde_json::from_value(args)
             .map_err(|e| ToolError::InvalidParameters(e.to_string()))?;
 
+        if let CommandVerdict::Denied(pattern) = self.commands.classify(&params.command) {
+            return Err(ToolError::PermissionDenied(format!(
+                "command matches the denied pattern '{pattern}'"
+            )));
+        }
+
         let timeout_secs = params.timeout.unwrap_or(ctx.timeout.as_secs());
         let timeout = std::time::Duration::from_secs(timeout_secs);
 
+        if ctx.dry_run {
+            return Ok(crate::dry_run::simulated(
+                format!(
+                    "Would run in {}: {}",
+                    ctx.working_dir.display(),
+                    params.command
+                ),
+                Some(format!("timeout: {timeout_secs}s")),
+            ));
+        }
+
         let policy = SandboxPolicy {
             mode: match ctx.sandbox_mode {
                 SandboxMode::ReadOnly => rot_sandbox::SandboxMode::ReadOnly,
@@ -103,6 +138,7 @@ impl Tool for BashTool {
             output: text,
             metadata: serde_json::json!({"exit_code": exit_code}),
             is_error,
+            parts: Vec::new(),
         })
     }
 }
@@ -123,7 +159,7 @@ mod tests {
     #[tokio::test]
     async fn test_bash_echo() {
         let dir = TempDir::new().unwrap();
-        let result = BashTool
+        let result = BashTool::default()
             .execute(
                 serde_json::json!({"command": "echo hello"}),
                 &test_ctx(&dir),
@@ -135,10 +171,28 @@ mod tests {
         assert!(result.output.contains("hello"));
     }
 
+    #[tokio::test]
+    async fn test_bash_refuses_denied_commands() {
+        let dir = TempDir::new().unwrap();
+        let tool = BashTool::new(CommandPolicy {
+            allow: Vec::new(),
+            deny: vec!["touch".to_string()],
+        });
+        let result = tool
+            .execute(
+                serde_json::json!({"command": "echo ok && touch made"}),
+                &test_ctx(&dir),
+            )
+            .await;
+
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+        assert!(!dir.path().join("made").exists());
+    }
+
     #[tokio::test]
     async fn test_bash_exit_code() {
         let dir = TempDir::new().unwrap();
-        let result = BashTool
+        let result = BashTool::default()
             .execute(
                 serde_json::json!({"command": "exit 42"}),
                 &test_ctx(&dir),
@@ -153,7 +207,7 @@ mod tests {
     #[tokio::test]
     async fn test_bash_timeout() {
         let dir = TempDir::new().unwrap();
-        let result = BashTool
+        let result = BashTool::default()
             .execute(
                 serde_json::json!({"command": "sleep 30", "timeout": 1}),
                 &test_ctx(&dir),
@@ -166,4 +220,21 @@ mod tests {
             other => panic!("Expected Timeout, got: {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn test_bash_dry_run_does_not_execute() {
+        let dir = TempDir::new().unwrap();
+        let ctx = ToolContext {
+            dry_run: true,
+            ..test_ctx(&dir)
+        };
+        let result = BashTool::default()
+            .execute(serde_json::json!({"command": "touch marker"}), &ctx)
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("Would run"));
+        assert!(result.output.contains("touch marker"));
+        assert!(!dir.path().join("marker").exists());
+    }
 }
@@ -86,14 +86,27 @@ impl Tool for EditTool {
             content.replacen(&params.old_string, &params.new_string, 1)
         };
 
+        if ctx.dry_run {
+            return Ok(crate::dry_run::simulated(
+                format!("Would replace {count} occurrence(s) in {}", params.path),
+                Some(crate::dry_run::line_diff(
+                    &params.path,
+                    &content,
+                    &new_content,
+                )),
+            ));
+        }
+
         tokio::fs::write(&path, &new_content)
             .await
             .map_err(|e| ToolError::ExecutionError(format!("Failed to write file: {e}")))?;
 
-        Ok(ToolResult::success(format!(
-            "Replaced {count} occurrence(s) in {}",
-            params.path
-        )))
+        Ok(ToolResult::success_with_metadata(
+            format!("Replaced {count} occurrence(s) in {}", params.path),
+            serde_json::json!({
+                "diff": crate::dry_run::line_diff(&params.path, &content, &new_content),
+            }),
+        ))
     }
 }
 
@@ -129,6 +142,8 @@ mod tests {
         assert!(!result.is_error);
         let content = std::fs::read_to_string(dir.path().join("f.txt")).unwrap();
         assert_eq!(content, "goodbye world");
+        let diff = result.metadata["diff"].as_str().unwrap();
+        assert!(diff.contains("-hello world\n+goodbye world"));
     }
 
     #[tokio::test]
@@ -1,15 +1,32 @@
-//! Glob tool — file pattern matching with .gitignore awareness.
+//! Glob tool — file pattern matching with .gitignore and .rotignore awareness.
 
 use crate::error::ToolError;
 use crate::path_guard::workspace_root;
 use crate::traits::{Tool, ToolContext, ToolResult};
 use async_trait::async_trait;
-use ignore::WalkBuilder;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 const MAX_RESULTS: usize = 1000;
 
+/// Files under `root` as relative paths, honouring `.gitignore` and
+/// `.rotignore` and skipping `.git`.
+pub fn workspace_files(root: &Path) -> impl Iterator<Item = String> + '_ {
+    crate::rotignore::walker(root)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+        .filter_map(move |entry| {
+            entry
+                .path()
+                .strip_prefix(root)
+                .ok()
+                .map(|rel| rel.to_string_lossy().to_string())
+        })
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GlobParams {
     /// Glob pattern (e.g. `**/*.rs`, `src/**/*.ts`).
@@ -27,7 +44,7 @@ impl Tool for GlobTool {
         "Glob"
     }
     fn description(&self) -> &str {
-        "Find files matching a glob pattern. Respects .gitignore."
+        "Find files matching a glob pattern. Respects .gitignore and .rotignore."
     }
     fn parameters_schema(&self) -> serde_json::Value {
         serde_json::to_value(schemars::schema_for!(GlobParams))
@@ -46,25 +63,10 @@ impl Tool for GlobTool {
         let glob_pattern = glob::Pattern::new(&params.pattern)
             .map_err(|e| ToolError::InvalidParameters(format!("Invalid glob pattern: {e}")))?;
 
-        let walker = WalkBuilder::new(&root)
-            .git_ignore(true)
-            .hidden(false)
-            .build();
-
-        let mut matches: Vec<String> = Vec::new();
-        for entry in walker.flatten() {
-            if entry.file_type().is_some_and(|ft| ft.is_file()) {
-                if let Ok(rel) = entry.path().strip_prefix(&root) {
-                    let rel_str = rel.to_string_lossy();
-                    if glob_pattern.matches(&rel_str) {
-                        matches.push(rel_str.to_string());
-                        if matches.len() >= MAX_RESULTS {
-                            break;
-                        }
-                    }
-                }
-            }
-        }
+        let mut matches: Vec<String> = workspace_files(&root)
+            .filter(|rel| glob_pattern.matches(rel))
+            .take(MAX_RESULTS)
+            .collect();
 
         matches.sort();
 
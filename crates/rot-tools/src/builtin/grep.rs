@@ -4,7 +4,6 @@ use crate::error::ToolError;
 use crate::path_guard::workspace_root;
 use crate::traits::{Tool, ToolContext, ToolResult};
 use async_trait::async_trait;
-use ignore::WalkBuilder;
 use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -66,10 +65,7 @@ impl Tool for GrepTool {
         let before = params.before_context.unwrap_or(0);
         let after = params.after_context.unwrap_or(0);
 
-        let walker = WalkBuilder::new(&root)
-            .git_ignore(true)
-            .hidden(false)
-            .build();
+        let walker = crate::rotignore::walker(&root).build();
 
         let mut results: Vec<String> = Vec::new();
 
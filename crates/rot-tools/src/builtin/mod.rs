@@ -5,6 +5,8 @@ pub mod edit;
 pub mod glob;
 pub mod grep;
 pub mod read;
+pub mod read_more;
+pub mod resolve_conflicts;
 pub mod task;
 pub mod webfetch;
 pub mod write;
@@ -17,9 +19,13 @@ pub fn register_all(registry: &mut ToolRegistry) {
     registry.register(Arc::new(read::ReadTool));
     registry.register(Arc::new(write::WriteTool));
     registry.register(Arc::new(edit::EditTool));
-    registry.register(Arc::new(bash::BashTool));
+    registry.register(Arc::new(bash::BashTool::default()));
     registry.register(Arc::new(glob::GlobTool));
     registry.register(Arc::new(grep::GrepTool));
     registry.register(Arc::new(task::TaskTool));
     registry.register(Arc::new(webfetch::WebFetchTool));
+    registry.register(Arc::new(resolve_conflicts::ResolveConflictsTool));
+    registry.register(Arc::new(read_more::ReadMoreTool::new(
+        registry.output_pager(),
+    )));
 }
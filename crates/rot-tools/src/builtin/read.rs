@@ -55,9 +55,16 @@ impl Tool for ReadTool {
 
         let path = resolve_existing_path(Path::new(&params.path), &ctx.working_dir)?;
 
-        let content = tokio::fs::read_to_string(&path)
-            .await
-            .map_err(|e| ToolError::ExecutionError(format!("Failed to read file: {e}")))?;
+        let prefetched = match &ctx.read_cache {
+            Some(cache) => cache.take(&path).await,
+            None => None,
+        };
+        let content = match prefetched {
+            Some(content) => content,
+            None => tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to read file: {e}")))?,
+        };
 
         let lines: Vec<&str> = content.lines().collect();
         let total_lines = lines.len();
@@ -155,10 +162,7 @@ mod tests {
     async fn test_read_nonexistent() {
         let dir = TempDir::new().unwrap();
         let result = ReadTool
-            .execute(
-                serde_json::json!({"path": "nope.txt"}),
-                &test_ctx(&dir),
-            )
+            .execute(serde_json::json!({"path": "nope.txt"}), &test_ctx(&dir))
             .await;
 
         assert!(result.is_err());
@@ -180,4 +184,24 @@ mod tests {
             other => panic!("Expected PermissionDenied, got: {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn test_rotignored_path_blocked() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".rotignore"), "secrets/\n").unwrap();
+        fs::create_dir(dir.path().join("secrets")).unwrap();
+        fs::write(dir.path().join("secrets/key.txt"), "hunter2").unwrap();
+
+        let result = ReadTool
+            .execute(
+                serde_json::json!({"path": "secrets/key.txt"}),
+                &test_ctx(&dir),
+            )
+            .await;
+
+        match result.unwrap_err() {
+            ToolError::PermissionDenied(message) => assert!(message.contains(".rotignore")),
+            other => panic!("Expected PermissionDenied, got: {other:?}"),
+        }
+    }
 }
@@ -0,0 +1,89 @@
+//! Read more tool — the next page of a tool output cut by the output policy.
+
+use crate::error::ToolError;
+use crate::output::OutputPager;
+use crate::traits::{Tool, ToolContext, ToolResult};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ReadMoreParams {
+    /// Continuation token from a truncated output (e.g. `bash-3:20480`).
+    pub token: String,
+}
+
+pub struct ReadMoreTool {
+    pager: Arc<OutputPager>,
+}
+
+impl ReadMoreTool {
+    pub fn new(pager: Arc<OutputPager>) -> Self {
+        Self { pager }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadMoreTool {
+    fn name(&self) -> &str {
+        "read_more"
+    }
+    fn label(&self) -> &str {
+        "Read More"
+    }
+    fn description(&self) -> &str {
+        "Read the next page of a truncated tool output, using the continuation token given at its end."
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(ReadMoreParams))
+            .expect("schema serialization should not fail")
+    }
+
+    async fn execute(
+        &self,
+        args: serde_json::Value,
+        _ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: ReadMoreParams = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidParameters(e.to_string()))?;
+        let page = self
+            .pager
+            .page(&params.token)
+            .map_err(ToolError::InvalidParameters)?;
+        Ok(ToolResult::success_with_metadata(
+            page.text,
+            serde_json::json!({ "next": page.next }),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::OutputPolicy;
+
+    #[tokio::test]
+    async fn test_read_more_returns_the_next_page() {
+        let pager = Arc::new(OutputPager::new(OutputPolicy {
+            max_bytes: 0,
+            max_lines: 2,
+        }));
+        pager.limit("grep", "a\nb\nc\n".to_string());
+        let tool = ReadMoreTool::new(pager);
+        let ctx = ToolContext::default();
+
+        let result = tool
+            .execute(serde_json::json!({"token": "grep-1:4"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.output, "c\n");
+        assert_eq!(result.metadata["next"], serde_json::Value::Null);
+
+        let err = tool
+            .execute(serde_json::json!({"token": "grep-1:99"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidParameters(_)));
+    }
+}
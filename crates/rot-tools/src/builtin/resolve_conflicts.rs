@@ -0,0 +1,555 @@
+//! Resolve conflicts tool — list git conflict hunks and apply resolutions.
+//!
+//! `list` finds files with conflict markers and shows each hunk with both
+//! sides, and the merge base for `diff3`-style conflicts. `resolve` rewrites
+//! one file, taking a resolution for every hunk in it, and refuses to write
+//! a result that still contains conflict markers.
+
+use crate::error::ToolError;
+use crate::path_guard::{resolve_existing_path, resolve_path_for_write, workspace_root};
+use crate::traits::{SandboxMode, Tool, ToolContext, ToolResult};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Most hunks shown by one `list` call.
+const MAX_LISTED_HUNKS: usize = 50;
+
+/// Files larger than this are not scanned for markers.
+const MAX_SCAN_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictAction {
+    /// Show the conflict hunks in `path`, or in every file of the workspace.
+    #[default]
+    List,
+    /// Apply `resolutions` to every hunk in `path`.
+    Resolve,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionChoice {
+    /// Keep our side.
+    Ours,
+    /// Keep their side.
+    Theirs,
+    /// Keep our side followed by theirs.
+    Both,
+    /// Keep the merge base; only for diff3-style conflicts.
+    Base,
+    /// Replace the hunk with `content`.
+    Custom,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Resolution {
+    /// Hunk number as shown by `list`, starting at 1.
+    pub hunk: usize,
+    pub choice: ResolutionChoice,
+    /// Replacement text for a `custom` choice.
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ResolveConflictsParams {
+    /// `list` (default) or `resolve`.
+    #[serde(default)]
+    pub action: ConflictAction,
+    /// File to list or resolve, relative to the working directory. Required
+    /// for `resolve`; `list` scans the whole workspace without it.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// One resolution per hunk in the file, for `resolve`.
+    #[serde(default)]
+    pub resolutions: Vec<Resolution>,
+}
+
+/// One conflict between `<<<<<<<` and `>>>>>>>` markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictHunk {
+    /// Line of the `<<<<<<<` marker, starting at 1.
+    pub start_line: usize,
+    /// Line of the `>>>>>>>` marker.
+    pub end_line: usize,
+    pub ours_label: String,
+    pub theirs_label: String,
+    pub ours: String,
+    /// The merge base, present in diff3-style conflicts.
+    pub base: Option<String>,
+    pub theirs: String,
+}
+
+/// A file split into plain text and conflict hunks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Conflict(ConflictHunk),
+}
+
+/// Marker at the start of `line`, with the label after it.
+fn marker<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.trim_end_matches(['\n', '\r']).strip_prefix(marker)?;
+    if rest.is_empty() {
+        Some("")
+    } else {
+        rest.strip_prefix(' ')
+    }
+}
+
+fn parse(content: &str) -> Result<Vec<Segment>, String> {
+    enum Side {
+        Ours,
+        Base,
+        Theirs,
+    }
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut current: Option<(ConflictHunk, Side)> = None;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let number = index + 1;
+        let Some((hunk, side)) = current.as_mut() else {
+            match marker(line, "<<<<<<<") {
+                Some(label) => {
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    let hunk = ConflictHunk {
+                        start_line: number,
+                        end_line: number,
+                        ours_label: label.to_string(),
+                        theirs_label: String::new(),
+                        ours: String::new(),
+                        base: None,
+                        theirs: String::new(),
+                    };
+                    current = Some((hunk, Side::Ours));
+                }
+                None => text.push_str(line),
+            }
+            continue;
+        };
+        match side {
+            Side::Ours | Side::Base if marker(line, "=======") == Some("") => *side = Side::Theirs,
+            Side::Ours if marker(line, "|||||||").is_some() => {
+                hunk.base = Some(String::new());
+                *side = Side::Base;
+            }
+            Side::Theirs if marker(line, ">>>>>>>").is_some() => {
+                let (mut hunk, _) = current.take().expect("inside a conflict");
+                hunk.end_line = number;
+                hunk.theirs_label = marker(line, ">>>>>>>").unwrap_or_default().to_string();
+                segments.push(Segment::Conflict(hunk));
+            }
+            Side::Ours => hunk.ours.push_str(line),
+            Side::Base => hunk.base.get_or_insert_with(String::new).push_str(line),
+            Side::Theirs => hunk.theirs.push_str(line),
+        }
+    }
+    if let Some((hunk, _)) = current {
+        return Err(format!(
+            "unterminated conflict starting at line {}",
+            hunk.start_line
+        ));
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+/// Conflict hunks in `content`, in order.
+pub fn conflict_hunks(content: &str) -> Result<Vec<ConflictHunk>, String> {
+    Ok(parse(content)?
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Conflict(hunk) => Some(hunk),
+            Segment::Text(_) => None,
+        })
+        .collect())
+}
+
+/// `content` with every conflict hunk replaced as `resolutions` say. Each
+/// hunk needs exactly one resolution, and the result may not contain
+/// conflict markers.
+pub fn apply_resolutions(content: &str, resolutions: &[Resolution]) -> Result<String, String> {
+    let segments = parse(content)?;
+    let count = segments
+        .iter()
+        .filter(|segment| matches!(segment, Segment::Conflict(_)))
+        .count();
+    if count == 0 {
+        return Err("no conflicts to resolve".to_string());
+    }
+    for resolution in resolutions {
+        if resolution.hunk == 0 || resolution.hunk > count {
+            return Err(format!(
+                "hunk {} does not exist; the file has {count}",
+                resolution.hunk
+            ));
+        }
+    }
+    for hunk in 1..=count {
+        match resolutions.iter().filter(|r| r.hunk == hunk).count() {
+            0 => {
+                return Err(format!(
+                    "hunk {hunk} has no resolution; every hunk in the file needs one"
+                ))
+            }
+            1 => {}
+            _ => return Err(format!("hunk {hunk} has more than one resolution")),
+        }
+    }
+
+    let mut out = String::with_capacity(content.len());
+    let mut hunk_number = 0;
+    for segment in segments {
+        let hunk = match segment {
+            Segment::Text(text) => {
+                out.push_str(&text);
+                continue;
+            }
+            Segment::Conflict(hunk) => hunk,
+        };
+        hunk_number += 1;
+        let resolution = resolutions
+            .iter()
+            .find(|r| r.hunk == hunk_number)
+            .expect("checked above");
+        match resolution.choice {
+            ResolutionChoice::Ours => out.push_str(&hunk.ours),
+            ResolutionChoice::Theirs => out.push_str(&hunk.theirs),
+            ResolutionChoice::Both => {
+                out.push_str(&hunk.ours);
+                out.push_str(&hunk.theirs);
+            }
+            ResolutionChoice::Base => match &hunk.base {
+                Some(base) => out.push_str(base),
+                None => {
+                    return Err(format!(
+                        "hunk {hunk_number} has no merge base; base needs a diff3-style conflict"
+                    ))
+                }
+            },
+            ResolutionChoice::Custom => {
+                let custom = resolution.content.as_deref().ok_or_else(|| {
+                    format!("hunk {hunk_number} is resolved as custom but has no content")
+                })?;
+                out.push_str(custom);
+                if !custom.is_empty() && !custom.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    if let Some((index, _)) = out
+        .lines()
+        .enumerate()
+        .find(|(_, line)| marker(line, "<<<<<<<").is_some() || marker(line, ">>>>>>>").is_some())
+    {
+        return Err(format!(
+            "the resolved file still has a conflict marker at line {}",
+            index + 1
+        ));
+    }
+    Ok(out)
+}
+
+fn render_hunk(path: &str, number: usize, hunk: &ConflictHunk) -> String {
+    let label = |label: &str| {
+        if label.is_empty() {
+            String::new()
+        } else {
+            format!(" ({label})")
+        }
+    };
+    let mut out = format!(
+        "## {path} hunk {number} (lines {}-{})\n### ours{}\n{}",
+        hunk.start_line,
+        hunk.end_line,
+        label(&hunk.ours_label),
+        hunk.ours
+    );
+    if let Some(base) = &hunk.base {
+        out.push_str(&format!("### base\n{base}"));
+    }
+    out.push_str(&format!(
+        "### theirs{}\n{}",
+        label(&hunk.theirs_label),
+        hunk.theirs
+    ));
+    out
+}
+
+pub struct ResolveConflictsTool;
+
+impl ResolveConflictsTool {
+    /// Files under the workspace with a `<<<<<<<` line, sorted.
+    fn scan(root: &Path) -> Vec<(String, PathBuf)> {
+        let mut files: Vec<(String, PathBuf)> = crate::rotignore::walker(root)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+            .filter(|entry| entry.metadata().is_ok_and(|m| m.len() <= MAX_SCAN_BYTES))
+            .filter_map(|entry| {
+                let content = std::fs::read_to_string(entry.path()).ok()?;
+                content
+                    .lines()
+                    .any(|line| marker(line, "<<<<<<<").is_some())
+                    .then(|| {
+                        let rel = entry.path().strip_prefix(root).ok()?;
+                        Some((
+                            rel.to_string_lossy().to_string(),
+                            entry.path().to_path_buf(),
+                        ))
+                    })
+                    .flatten()
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn list(
+        &self,
+        params: &ResolveConflictsParams,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let files = match &params.path {
+            Some(path) => vec![(
+                path.clone(),
+                resolve_existing_path(Path::new(path), &ctx.working_dir)?,
+            )],
+            None => Self::scan(&workspace_root(&ctx.working_dir)?),
+        };
+
+        let mut sections = Vec::new();
+        let mut summary = Vec::new();
+        let mut total = 0;
+        for (rel, path) in &files {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to read {rel}: {e}")))?;
+            let hunks = conflict_hunks(&content)
+                .map_err(|e| ToolError::ExecutionError(format!("{rel}: {e}")))?;
+            if hunks.is_empty() {
+                continue;
+            }
+            summary.push(format!("{rel}: {} conflict(s)", hunks.len()));
+            for (i, hunk) in hunks.iter().enumerate() {
+                if total < MAX_LISTED_HUNKS {
+                    sections.push(render_hunk(rel, i + 1, hunk));
+                }
+                total += 1;
+            }
+        }
+
+        if total == 0 {
+            return Ok(ToolResult::success_with_metadata(
+                "No conflict markers found.",
+                serde_json::json!({"files": 0, "conflicts": 0}),
+            ));
+        }
+        let mut output = format!("{}\n\n{}", summary.join("\n"), sections.join("\n"));
+        if total > MAX_LISTED_HUNKS {
+            output.push_str(&format!(
+                "\n... ({} more hunks; list one file with `path` to see them)",
+                total - MAX_LISTED_HUNKS
+            ));
+        }
+        output.push_str(
+            "\nResolve a file with action `resolve`, its `path`, and one resolution per hunk: \
+             ours, theirs, both, base, or custom with `content`.",
+        );
+        Ok(ToolResult::success_with_metadata(
+            output,
+            serde_json::json!({"files": summary.len(), "conflicts": total}),
+        ))
+    }
+
+    async fn resolve(
+        &self,
+        params: &ResolveConflictsParams,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        if ctx.sandbox_mode == SandboxMode::ReadOnly {
+            return Err(ToolError::PermissionDenied(
+                "resolve_conflicts cannot resolve in read-only sandbox mode".to_string(),
+            ));
+        }
+        let rel = params.path.as_deref().ok_or_else(|| {
+            ToolError::InvalidParameters("`path` is required to resolve conflicts".to_string())
+        })?;
+        let path = if ctx.sandbox_mode == SandboxMode::WorkspaceWrite {
+            resolve_path_for_write(Path::new(rel), &ctx.working_dir)?
+        } else {
+            ctx.working_dir.join(rel)
+        };
+
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to read file: {e}")))?;
+        let resolved = apply_resolutions(&content, &params.resolutions)
+            .map_err(|e| ToolError::ExecutionError(format!("{rel}: {e}")))?;
+        let diff = crate::dry_run::line_diff(rel, &content, &resolved);
+        let count = params.resolutions.len();
+
+        if ctx.dry_run {
+            return Ok(crate::dry_run::simulated(
+                format!("Would resolve {count} conflict(s) in {rel}"),
+                Some(diff),
+            ));
+        }
+
+        tokio::fs::write(&path, &resolved)
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to write file: {e}")))?;
+        Ok(ToolResult::success_with_metadata(
+            format!("Resolved {count} conflict(s) in {rel}; no conflict markers remain."),
+            serde_json::json!({"diff": diff, "resolved": count}),
+        ))
+    }
+}
+
+#[async_trait]
+impl Tool for ResolveConflictsTool {
+    fn name(&self) -> &str {
+        "resolve_conflicts"
+    }
+    fn label(&self) -> &str {
+        "Resolve Conflicts"
+    }
+    fn description(&self) -> &str {
+        "Find git merge conflicts and resolve them. action=list shows every conflict hunk \
+         with both sides (and the base for diff3 conflicts), numbered per file. \
+         action=resolve rewrites one file given a resolution for each of its hunks \
+         (ours, theirs, both, base, or custom content) and fails if any markers would remain."
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(ResolveConflictsParams))
+            .expect("schema serialization should not fail")
+    }
+
+    async fn execute(
+        &self,
+        args: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: ResolveConflictsParams = serde_json::from_value(args)
+            .map_err(|e| ToolError::InvalidParameters(e.to_string()))?;
+        match params.action {
+            ConflictAction::List => self.list(&params, ctx),
+            ConflictAction::Resolve => self.resolve(&params, ctx).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CONFLICTED: &str = "fn main() {\n<<<<<<< HEAD\n    run(1);\n||||||| base\n    run(0);\n=======\n    run(2);\n>>>>>>> feature\n}\n<<<<<<< HEAD\na\n=======\nb\n>>>>>>> feature\n";
+
+    fn test_ctx(dir: &TempDir) -> ToolContext {
+        ToolContext {
+            working_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_and_apply_resolutions() {
+        let hunks = conflict_hunks(CONFLICTED).unwrap();
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].start_line, 2);
+        assert_eq!(hunks[0].end_line, 8);
+        assert_eq!(hunks[0].ours, "    run(1);\n");
+        assert_eq!(hunks[0].base.as_deref(), Some("    run(0);\n"));
+        assert_eq!(hunks[0].theirs_label, "feature");
+        assert_eq!(hunks[1].base, None);
+
+        let resolution = |hunk, choice, content: Option<&str>| Resolution {
+            hunk,
+            choice,
+            content: content.map(str::to_string),
+        };
+        let resolved = apply_resolutions(
+            CONFLICTED,
+            &[
+                resolution(1, ResolutionChoice::Custom, Some("    run(3);")),
+                resolution(2, ResolutionChoice::Both, None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(resolved, "fn main() {\n    run(3);\n}\na\nb\n");
+
+        let missing = apply_resolutions(CONFLICTED, &[resolution(1, ResolutionChoice::Ours, None)]);
+        assert!(missing.unwrap_err().contains("hunk 2 has no resolution"));
+        let no_base = apply_resolutions(
+            CONFLICTED,
+            &[
+                resolution(1, ResolutionChoice::Ours, None),
+                resolution(2, ResolutionChoice::Base, None),
+            ],
+        );
+        assert!(no_base.unwrap_err().contains("no merge base"));
+        let markers = apply_resolutions(
+            CONFLICTED,
+            &[
+                resolution(1, ResolutionChoice::Custom, Some("<<<<<<< HEAD\n")),
+                resolution(2, ResolutionChoice::Ours, None),
+            ],
+        );
+        assert!(markers.unwrap_err().contains("conflict marker at line 2"));
+        assert!(conflict_hunks("<<<<<<< HEAD\na\n")
+            .unwrap_err()
+            .contains("unterminated"));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_resolve_file() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), CONFLICTED).unwrap();
+        std::fs::write(dir.path().join("clean.rs"), "=======\n").unwrap();
+
+        let listed = ResolveConflictsTool
+            .execute(serde_json::json!({}), &test_ctx(&dir))
+            .await
+            .unwrap();
+        assert!(listed.output.starts_with("src/main.rs: 2 conflict(s)\n"));
+        assert!(listed.output.contains(
+            "## src/main.rs hunk 1 (lines 2-8)\n### ours (HEAD)\n    run(1);\n### base\n"
+        ));
+        assert_eq!(listed.metadata["conflicts"], 2);
+
+        let resolved = ResolveConflictsTool
+            .execute(
+                serde_json::json!({
+                    "action": "resolve",
+                    "path": "src/main.rs",
+                    "resolutions": [
+                        {"hunk": 1, "choice": "theirs"},
+                        {"hunk": 2, "choice": "ours"}
+                    ]
+                }),
+                &test_ctx(&dir),
+            )
+            .await
+            .unwrap();
+        assert!(resolved.output.contains("Resolved 2 conflict(s)"));
+        let content = std::fs::read_to_string(dir.path().join("src/main.rs")).unwrap();
+        assert_eq!(content, "fn main() {\n    run(2);\n}\na\n");
+
+        let none = ResolveConflictsTool
+            .execute(serde_json::json!({"action": "list"}), &test_ctx(&dir))
+            .await
+            .unwrap();
+        assert_eq!(none.output, "No conflict markers found.");
+    }
+}
@@ -1,7 +1,7 @@
 //! Task tool — delegate work to a subagent.
 
 use crate::error::ToolError;
-use crate::traits::{TaskRequest, Tool, ToolContext, ToolResult};
+use crate::traits::{TaskReport, TaskRequest, Tool, ToolContext, ToolResult};
 use async_trait::async_trait;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -12,6 +12,15 @@ pub struct TaskParams {
     pub agent: String,
     /// Prompt for the delegated task.
     pub prompt: String,
+    /// Workspace-relative files the subagent should see up front.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Relevant findings or constraints from the current conversation.
+    #[serde(default)]
+    pub context: Option<String>,
+    /// Expected format of the subagent's final answer.
+    #[serde(default)]
+    pub output_format: Option<String>,
 }
 
 pub struct TaskTool;
@@ -27,7 +36,9 @@ impl Tool for TaskTool {
     }
 
     fn description(&self) -> &str {
-        "Delegate a focused task to a subagent and return its final response."
+        "Delegate a focused task to a subagent and return its final response. \
+         Pass relevant files, context, and the expected output format so the subagent \
+         does not have to rediscover what you already know."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -58,14 +69,24 @@ impl Tool for TaskTool {
             .run_task(TaskRequest {
                 agent: params.agent,
                 prompt: params.prompt,
+                files: params.files,
+                context: params.context,
+                output_format: params.output_format,
             })
             .await?;
 
+        // Runners that do not report hand back their final text as is.
+        let output = if result.report == TaskReport::default() {
+            result.final_text
+        } else {
+            result.report.render()
+        };
         Ok(ToolResult::success_with_metadata(
-            result.final_text,
+            output,
             serde_json::json!({
                 "agent": result.agent,
                 "child_session_id": result.child_session_id,
+                "report": result.report,
             }),
         ))
     }
@@ -76,6 +97,7 @@ mod tests {
     use super::*;
     use crate::traits::{TaskExecution, TaskRunner};
     use async_trait::async_trait;
+    use std::collections::HashMap;
     use std::sync::Arc;
 
     struct MockTaskRunner;
@@ -86,6 +108,12 @@ mod tests {
             Ok(TaskExecution {
                 final_text: format!("handled by {}", request.agent),
                 child_session_id: Some("child-123".to_string()),
+                report: TaskReport {
+                    summary: format!("handled by {}", request.agent),
+                    files_changed: vec!["src/lib.rs".to_string()],
+                    follow_ups: vec!["add a test".to_string()],
+                    ..Default::default()
+                },
                 agent: request.agent,
             })
         }
@@ -107,8 +135,63 @@ mod tests {
             .unwrap();
 
         assert!(!result.is_error);
-        assert_eq!(result.output, "handled by review");
+        assert_eq!(
+            result.output,
+            "handled by review\n\nFiles changed:\n- src/lib.rs\n\nFollow-ups:\n- add a test"
+        );
         assert_eq!(result.metadata["child_session_id"], "child-123");
+        assert_eq!(result.metadata["report"]["files_changed"][0], "src/lib.rs");
+        assert_eq!(
+            result.metadata["report"]["commands_run"],
+            serde_json::json!([])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_briefing_includes_context_files_and_format() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "remember the cache").unwrap();
+
+        let request = TaskRequest {
+            files: vec!["notes.md".to_string(), "missing.rs".to_string()],
+            context: Some("user wants a minimal fix".to_string()),
+            output_format: Some("a bullet list".to_string()),
+            ..TaskRequest::new("review", "inspect changes")
+        };
+
+        let briefing = request.briefing(dir.path(), &HashMap::new()).await;
+        assert!(briefing.contains("user wants a minimal fix"));
+        assert!(briefing.contains("## File: notes.md\n```\nremember the cache"));
+        assert!(briefing.contains("## File: missing.rs\n(unavailable"));
+        assert!(briefing.contains("## Task\ninspect changes"));
+        assert!(briefing.ends_with("## Expected output\na bullet list"));
+    }
+
+    #[tokio::test]
+    async fn test_briefing_skips_ignored_and_refused_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join(".rotignore"), ".env\n").unwrap();
+        std::fs::write(dir.path().join(".env"), "TOKEN=secret").unwrap();
+        std::fs::write(dir.path().join("keys.txt"), "also secret").unwrap();
+
+        let request = TaskRequest {
+            files: vec![".env".to_string(), "keys.txt".to_string()],
+            ..TaskRequest::new("review", "inspect changes")
+        };
+        let refused = HashMap::from([("keys.txt".to_string(), "denied by a rule".to_string())]);
+        let briefing = request.briefing(dir.path(), &refused).await;
+        assert!(!briefing.contains("secret"));
+        assert!(briefing.contains("## File: .env\n(unavailable: Permission denied: Path '.env' is excluded by .rotignore)"));
+        assert!(briefing.contains("## File: keys.txt\n(unavailable: denied by a rule)"));
+    }
+
+    #[tokio::test]
+    async fn test_briefing_without_extras_is_prompt() {
+        let request = TaskRequest::new("review", "inspect changes");
+        let briefing = request
+            .briefing(std::path::Path::new("."), &HashMap::new())
+            .await;
+        assert_eq!(briefing, "inspect changes");
     }
 
     #[tokio::test]
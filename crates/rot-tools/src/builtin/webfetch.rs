@@ -1,7 +1,7 @@
 //! WebFetch tool — fetch URL content.
 
 use crate::error::ToolError;
-use crate::traits::{SandboxMode, Tool, ToolContext, ToolResult};
+use crate::traits::{Tool, ToolContext, ToolResult};
 use async_trait::async_trait;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -32,22 +32,49 @@ impl Tool for WebFetchTool {
             .expect("schema serialization should not fail")
     }
 
+    fn network_hosts(&self, args: &serde_json::Value) -> Vec<String> {
+        args.get("url")
+            .and_then(|url| url.as_str())
+            .and_then(url_host)
+            .into_iter()
+            .collect()
+    }
+
     async fn execute(
         &self,
         args: serde_json::Value,
         ctx: &ToolContext,
     ) -> Result<ToolResult, ToolError> {
-        if !ctx.network_access && ctx.sandbox_mode != SandboxMode::DangerFullAccess {
-            return Err(ToolError::PermissionDenied(
-                "webfetch is disabled because sandbox network access is off".to_string(),
-            ));
-        }
-
         let params: WebFetchParams = serde_json::from_value(args)
             .map_err(|e| ToolError::InvalidParameters(e.to_string()))?;
+        let host = url_host(&params.url).ok_or_else(|| {
+            ToolError::InvalidParameters(format!("Not an http(s) URL: {}", params.url))
+        })?;
+        if !ctx.allows_host(&host) {
+            return Err(ToolError::PermissionDenied(format!(
+                "webfetch cannot reach {host} because sandbox network access is off"
+            )));
+        }
+
+        // Redirects may only lead to hosts this call could reach directly.
+        let redirect_ctx = ctx.clone();
+        let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+            let allowed = attempt
+                .url()
+                .host_str()
+                .is_some_and(|host| redirect_ctx.allows_host(host));
+            if !allowed {
+                attempt.stop()
+            } else if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        });
 
         let client = reqwest::Client::builder()
             .timeout(ctx.timeout)
+            .redirect(redirects)
             .user_agent("rot/0.1")
             .build()
             .map_err(|e| ToolError::ExecutionError(format!("Failed to create HTTP client: {e}")))?;
@@ -97,11 +124,21 @@ impl Tool for WebFetchTool {
     }
 }
 
+/// The host of an http or https URL.
+fn url_host(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    url.host_str().map(str::to_ascii_lowercase)
+}
+
 // Note: WebFetch tests require network access and are kept minimal.
 // Integration tests with a mock server would be added separately.
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::traits::SandboxMode;
 
     #[test]
     fn test_webfetch_schema() {
@@ -123,4 +160,31 @@ mod tests {
             .await;
         assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
     }
+
+    #[tokio::test]
+    async fn test_webfetch_reaches_allowed_hosts_only() {
+        let tool = WebFetchTool;
+        assert_eq!(
+            tool.network_hosts(&serde_json::json!({"url": "https://Docs.RS/serde"})),
+            ["docs.rs"]
+        );
+        assert!(tool
+            .network_hosts(&serde_json::json!({"url": "file:///etc/passwd"}))
+            .is_empty());
+
+        let ctx = ToolContext {
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+            timeout: std::time::Duration::from_secs(5),
+            ..Default::default()
+        };
+        let refused = tool
+            .execute(serde_json::json!({"url": "http://localhost:1/"}), &ctx)
+            .await;
+        assert!(matches!(refused, Err(ToolError::PermissionDenied(_))));
+        // Allowed, so it gets as far as connecting.
+        let result = tool
+            .execute(serde_json::json!({"url": "http://127.0.0.1:1/"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::ExecutionError(_))));
+    }
 }
@@ -56,6 +56,24 @@ impl Tool for WriteTool {
             ctx.working_dir.join(&params.path)
         };
 
+        if ctx.dry_run {
+            let existing = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+            return Ok(crate::dry_run::simulated(
+                format!(
+                    "Would write {} bytes to {}",
+                    params.content.len(),
+                    params.path
+                ),
+                Some(crate::dry_run::line_diff(
+                    &params.path,
+                    &existing,
+                    &params.content,
+                )),
+            ));
+        }
+
+        let existing = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+
         // Create parent directories
         if let Some(parent) = path.parent() {
             tokio::fs::create_dir_all(parent).await.map_err(|e| {
@@ -71,7 +89,11 @@ impl Tool for WriteTool {
         let lines = params.content.lines().count();
         Ok(ToolResult::success_with_metadata(
             format!("Wrote {} bytes ({} lines) to {}", bytes, lines, params.path),
-            serde_json::json!({"bytes": bytes, "lines": lines}),
+            serde_json::json!({
+                "bytes": bytes,
+                "lines": lines,
+                "diff": crate::dry_run::line_diff(&params.path, &existing, &params.content),
+            }),
         ))
     }
 }
@@ -137,6 +159,31 @@ mod tests {
         assert_eq!(content, "new");
     }
 
+    #[tokio::test]
+    async fn test_write_dry_run_leaves_file_untouched() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("f.txt"), "old\n").unwrap();
+        let ctx = ToolContext {
+            dry_run: true,
+            ..test_ctx(&dir)
+        };
+
+        let result = WriteTool
+            .execute(
+                serde_json::json!({"path": "f.txt", "content": "new\n"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(result
+            .output
+            .starts_with("[dry-run] Would write 4 bytes to f.txt"));
+        assert!(result.output.contains("-old\n+new"));
+        let content = std::fs::read_to_string(dir.path().join("f.txt")).unwrap();
+        assert_eq!(content, "old\n");
+    }
+
     #[tokio::test]
     async fn test_write_denied_in_read_only_mode() {
         let dir = TempDir::new().unwrap();
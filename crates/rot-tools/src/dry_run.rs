@@ -0,0 +1,171 @@
+//! Simulated results for mutating tools when dry-run mode is active, and the
+//! diffs those tools report.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::traits::ToolResult;
+
+/// Lines of unchanged context shown around a changed region.
+const CONTEXT_LINES: usize = 3;
+
+/// Cap on diff lines included in a simulated result.
+const MAX_DIFF_LINES: usize = 200;
+
+/// Build the result returned in place of a real execution.
+pub fn simulated(summary: impl Into<String>, detail: Option<String>) -> ToolResult {
+    let summary = summary.into();
+    let output = match detail {
+        Some(detail) if !detail.is_empty() => format!("[dry-run] {summary}\n{detail}"),
+        _ => format!("[dry-run] {summary}"),
+    };
+    ToolResult::success_with_metadata(output, serde_json::json!({"dry_run": true}))
+}
+
+/// Render a compact unified-style diff between two versions of a file.
+///
+/// Only the single region between the common prefix and suffix is shown,
+/// which is enough to describe a write or edit without a full diff engine.
+pub fn line_diff(path: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_changed = &old[prefix..old.len() - suffix];
+    let new_changed = &new[prefix..new.len() - suffix];
+    if old_changed.is_empty() && new_changed.is_empty() {
+        return format!("--- {path}\n+++ {path}\n(no changes)");
+    }
+
+    let ctx_start = prefix.saturating_sub(CONTEXT_LINES);
+    let ctx_end = (old.len() - suffix + CONTEXT_LINES).min(old.len());
+    let old_hunk = ctx_end - ctx_start;
+    let new_hunk = old_hunk - old_changed.len() + new_changed.len();
+
+    let mut lines = vec![
+        format!("--- {path}"),
+        format!("+++ {path}"),
+        format!(
+            "@@ -{},{} +{},{} @@",
+            ctx_start + 1,
+            old_hunk,
+            ctx_start + 1,
+            new_hunk
+        ),
+    ];
+    lines.extend(old[ctx_start..prefix].iter().map(|l| format!(" {l}")));
+    lines.extend(old_changed.iter().map(|l| format!("-{l}")));
+    lines.extend(new_changed.iter().map(|l| format!("+{l}")));
+    lines.extend(
+        old[old.len() - suffix..ctx_end]
+            .iter()
+            .map(|l| format!(" {l}")),
+    );
+
+    if lines.len() > MAX_DIFF_LINES {
+        let omitted = lines.len() - MAX_DIFF_LINES;
+        lines.truncate(MAX_DIFF_LINES);
+        lines.push(format!("... ({omitted} more diff lines)"));
+    }
+    lines.join("\n")
+}
+
+/// Diff a `write`, `edit`, or `resolve_conflicts` call would make to a file
+/// under `working_dir`, for reviewing it before approval. `None` for other
+/// tools, malformed arguments, an edit whose `old_string` is not in the file,
+/// or resolutions that do not apply.
+pub fn change_preview(tool: &str, arguments: &Value, working_dir: &Path) -> Option<String> {
+    let path = arguments.get("path")?.as_str()?;
+    let existing = std::fs::read_to_string(working_dir.join(path)).ok();
+    let after = match tool {
+        "write" => arguments.get("content")?.as_str()?.to_string(),
+        "edit" => {
+            let content = existing.as_deref()?;
+            let old = arguments.get("old_string")?.as_str()?;
+            let new = arguments.get("new_string")?.as_str()?;
+            if old.is_empty() || !content.contains(old) {
+                return None;
+            }
+            let replace_all = arguments
+                .get("replace_all")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            if replace_all {
+                content.replace(old, new)
+            } else {
+                content.replacen(old, new, 1)
+            }
+        }
+        "resolve_conflicts" if arguments.get("action")?.as_str()? == "resolve" => {
+            let resolutions = serde_json::from_value::<
+                Vec<crate::builtin::resolve_conflicts::Resolution>,
+            >(arguments.get("resolutions")?.clone())
+            .ok()?;
+            crate::builtin::resolve_conflicts::apply_resolutions(existing.as_deref()?, &resolutions)
+                .ok()?
+        }
+        _ => return None,
+    };
+    Some(line_diff(path, existing.as_deref().unwrap_or(""), &after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff_shows_changed_region_with_context() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let after = "a\nb\nc\nd\nE\nf\ng\nh\n";
+        let diff = line_diff("f.txt", before, after);
+
+        assert!(diff.starts_with("--- f.txt\n+++ f.txt\n@@ -2,7 +2,7 @@"));
+        assert!(diff.contains("\n-e\n+E\n"));
+        assert!(!diff.contains(" a\n"));
+    }
+
+    #[test]
+    fn test_line_diff_new_file() {
+        let diff = line_diff("new.txt", "", "one\ntwo\n");
+        assert!(diff.contains("@@ -1,0 +1,2 @@"));
+        assert!(diff.ends_with("+one\n+two"));
+    }
+
+    #[test]
+    fn test_simulated_marks_metadata() {
+        let result = simulated("Would run: ls", None);
+        assert_eq!(result.output, "[dry-run] Would run: ls");
+        assert_eq!(result.metadata["dry_run"], true);
+        assert!(!result.is_error);
+    }
+
+    #[test]
+    fn test_change_preview_for_write_and_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+
+        let edit = serde_json::json!({"path": "a.txt", "old_string": "two", "new_string": "2"});
+        let diff = change_preview("edit", &edit, dir.path()).unwrap();
+        assert!(diff.contains("\n-two\n+2"));
+
+        let write = serde_json::json!({"path": "b.txt", "content": "new\n"});
+        let diff = change_preview("write", &write, dir.path()).unwrap();
+        assert!(diff.ends_with("+new"));
+
+        let missing = serde_json::json!({"path": "a.txt", "old_string": "zzz", "new_string": ""});
+        assert!(change_preview("edit", &missing, dir.path()).is_none());
+        assert!(change_preview("bash", &write, dir.path()).is_none());
+    }
+}
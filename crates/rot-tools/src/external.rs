@@ -82,6 +82,16 @@ impl Tool for CustomCommandTool {
         args: Value,
         ctx: &ToolContext,
     ) -> Result<ToolResult, ToolError> {
+        if ctx.dry_run {
+            return Ok(crate::dry_run::simulated(
+                format!("Would run custom tool '{}'", self.config.name),
+                Some(format!(
+                    "command: {}\narguments: {args}",
+                    self.config.command
+                )),
+            ));
+        }
+
         let args_json = serde_json::to_string(&args)
             .map_err(|e| ToolError::ExecutionError(format!("Failed to encode tool args: {e}")))?;
         let args_file = std::env::temp_dir().join(format!("rot-tool-{}.json", ulid::Ulid::new()));
@@ -157,6 +167,7 @@ impl Tool for CustomCommandTool {
                 "tool_type": "custom_command",
             }),
             is_error: !output.success,
+            parts: Vec::new(),
         })
     }
 }
@@ -1,18 +1,29 @@
-//! rot-tools: Built-in tools (read, write, edit, bash, glob, grep, webfetch).
+//! rot-tools: Built-in tools (read, write, edit, bash, glob, grep, webfetch,
+//! resolve_conflicts, read_more).
 
 pub mod builtin;
+mod dry_run;
+mod error;
 mod external;
 mod mcp;
-mod error;
+pub mod output;
 mod path_guard;
+pub mod prefetch;
 pub mod registry;
+pub mod rotignore;
+pub mod shell;
 pub mod traits;
 
 pub use builtin::register_all;
+pub use dry_run::change_preview;
 pub use error::ToolError;
 pub use external::{register_custom_tools, CustomToolConfig};
-pub use mcp::{register_mcp_tools, McpServerConfig};
+pub use mcp::{register_mcp_tools, McpServerConfig, McpServerHandle};
+pub use output::{OutputPager, OutputPolicy};
 pub use registry::ToolRegistry;
+pub use rot_mcp::McpServerState;
+pub use rot_provider::ToolResultPart;
+pub use shell::{CommandPolicy, CommandVerdict};
 pub use traits::{
-    SandboxMode, TaskExecution, TaskRequest, TaskRunner, Tool, ToolContext, ToolResult,
+    SandboxMode, TaskExecution, TaskReport, TaskRequest, TaskRunner, Tool, ToolContext, ToolResult,
 };
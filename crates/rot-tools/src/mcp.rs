@@ -3,7 +3,8 @@
 use crate::error::ToolError;
 use crate::traits::{SandboxMode, Tool, ToolContext, ToolResult};
 use async_trait::async_trait;
-use rot_mcp::{McpClient, StdioServerConfig};
+use rot_mcp::{McpClient, McpServerState, StdioServerConfig};
+use rot_provider::ToolResultPart;
 use rot_sandbox::SandboxPolicy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -12,6 +13,9 @@ use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Stderr lines from the server appended to a failed call's result.
+const STDERR_LINES_IN_ERRORS: usize = 10;
+
 fn default_startup_timeout_secs() -> u64 {
     20
 }
@@ -51,6 +55,27 @@ pub struct McpServerConfig {
     pub tool_timeout_secs: u64,
 }
 
+/// A connected MCP server, kept in the registry for status views.
+#[derive(Debug, Clone)]
+pub struct McpServerHandle {
+    pub name: String,
+    /// Exported names of the tools it provides.
+    pub tools: Vec<String>,
+    client: Arc<McpClient>,
+}
+
+impl McpServerHandle {
+    /// Whether the server process is still running.
+    pub fn state(&self) -> McpServerState {
+        self.client.state()
+    }
+
+    /// The last `limit` lines the server wrote to stderr.
+    pub fn recent_stderr(&self, limit: usize) -> Vec<String> {
+        self.client.recent_stderr(limit)
+    }
+}
+
 /// Register MCP tools from configured servers into a registry.
 pub async fn register_mcp_tools(
     registry: &mut crate::ToolRegistry,
@@ -86,6 +111,7 @@ pub async fn register_mcp_tools(
         .map_err(map_mcp_error)?;
 
         let client = Arc::new(client);
+        let mut exported = Vec::with_capacity(tools.len());
         for tool in tools {
             let exported_name = exported_tool_name(&config.name, &tool.name);
             if registry.has(&exported_name) {
@@ -93,6 +119,7 @@ pub async fn register_mcp_tools(
                     "Duplicate tool name '{exported_name}'"
                 )));
             }
+            exported.push(exported_name.clone());
             registry.register(Arc::new(McpTool {
                 exported_name,
                 server_name: config.name.clone(),
@@ -102,6 +129,11 @@ pub async fn register_mcp_tools(
                 client: Arc::clone(&client),
             }));
         }
+        registry.add_mcp_server(McpServerHandle {
+            name: config.name.clone(),
+            tools: exported,
+            client,
+        });
     }
 
     Ok(())
@@ -134,14 +166,41 @@ impl Tool for McpTool {
         self.input_schema.clone()
     }
 
-    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolResult, ToolError> {
-        let result = self
-            .client
-            .call_tool(&self.remote_name, args)
-            .await
-            .map_err(map_mcp_error)?;
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+        if ctx.dry_run {
+            return Ok(crate::dry_run::simulated(
+                format!(
+                    "Would call MCP tool '{}' on server '{}'",
+                    self.remote_name, self.server_name
+                ),
+                Some(format!("arguments: {args}")),
+            ));
+        }
+
+        let mark = self.client.stderr_mark();
+        let result = match self.client.call_tool(&self.remote_name, args).await {
+            Ok(result) => result,
+            Err(e) => {
+                let note = self.stderr_note(mark);
+                return Err(match map_mcp_error(e) {
+                    ToolError::Timeout(message) => ToolError::Timeout(message + &note),
+                    ToolError::PermissionDenied(message) => {
+                        ToolError::PermissionDenied(message + &note)
+                    }
+                    ToolError::ExecutionError(message) => {
+                        ToolError::ExecutionError(message + &note)
+                    }
+                    other => other,
+                });
+            }
+        };
+        let parts = result_parts(&result.raw_content, result.structured_content.as_ref());
+        let mut output = result.text;
+        if result.is_error {
+            output.push_str(&self.stderr_note(mark));
+        }
         Ok(ToolResult {
-            output: result.text,
+            output,
             metadata: serde_json::json!({
                 "tool_type": "mcp",
                 "server": self.server_name,
@@ -150,10 +209,59 @@ impl Tool for McpTool {
                 "raw_content": result.raw_content,
             }),
             is_error: result.is_error,
+            parts,
         })
     }
 }
 
+impl McpTool {
+    /// What the server wrote to stderr since `mark`, to add to an error.
+    fn stderr_note(&self, mark: u64) -> String {
+        let lines = self.client.stderr_since(mark, STDERR_LINES_IN_ERRORS);
+        if lines.is_empty() {
+            return String::new();
+        }
+        format!(
+            "\n\nRecent stderr from MCP server '{}':\n{}",
+            self.server_name,
+            lines.join("\n")
+        )
+    }
+}
+
+/// Images in an MCP tool result, and its structured content when the text
+/// does not already carry it, as blocks for the model.
+fn result_parts(content: &[Value], structured_content: Option<&Value>) -> Vec<ToolResultPart> {
+    let mut parts = Vec::new();
+    // With no content items, the text is the structured content itself.
+    if let Some(value) = structured_content.filter(|_| !content.is_empty()) {
+        let in_text = content.iter().any(|item| {
+            item["type"] == "text"
+                && item["text"]
+                    .as_str()
+                    .and_then(|text| serde_json::from_str::<Value>(text).ok())
+                    .is_some_and(|parsed| &parsed == value)
+        });
+        if !in_text {
+            parts.push(ToolResultPart::Json {
+                value: value.clone(),
+            });
+        }
+    }
+    for item in content {
+        if item["type"] != "image" {
+            continue;
+        }
+        if let (Some(data), Some(mime_type)) = (item["data"].as_str(), item["mimeType"].as_str()) {
+            parts.push(ToolResultPart::Image {
+                data: data.to_string(),
+                mime_type: mime_type.to_string(),
+            });
+        }
+    }
+    parts
+}
+
 fn validate_mcp_server_config(config: &McpServerConfig) -> Result<(), ToolError> {
     if config.name.is_empty() || !is_valid_name_component(&config.name) {
         return Err(ToolError::InvalidParameters(format!(
@@ -231,21 +339,33 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
-    fn test_server_script(dir: &TempDir) -> std::path::PathBuf {
+    const PONG: &str = r#"{"content":[{"type":"text","text":"pong"},{"type":"image","data":"aGVsbG8=","mimeType":"image/png"}],"structuredContent":{"ok":true},"isError":false}"#;
+
+    /// A server whose one tool answers with `call_result` after writing
+    /// `stderr` lines.
+    fn test_server_script(dir: &TempDir, call_result: &str, stderr: &[&str]) -> std::path::PathBuf {
         let path = dir.path().join("fake-mcp.sh");
-        std::fs::write(
-            &path,
+        let mut script = String::from(
             r#"#!/bin/sh
+echo 'fake server starting' >&2
 read line
 printf '%s\n' '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-06-18","capabilities":{"tools":{}},"serverInfo":{"name":"fake","version":"1.0.0"}}}'
 read line
 read line
 printf '%s\n' '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"echo","description":"Echo input","inputSchema":{"type":"object","properties":{"text":{"type":"string"}},"required":["text"]}}]}}'
 read line
-printf '%s\n' '{"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text":"pong"}],"structuredContent":{"ok":true},"isError":false}}'
 "#,
-        )
-        .unwrap();
+        );
+        for line in stderr {
+            script.push_str(&format!("echo '{line}' >&2\n"));
+        }
+        // Give the stderr reader time to see those lines first.
+        script.push_str("sleep 0.2\n");
+        script.push_str(&format!(
+            r#"printf '%s\n' '{{"jsonrpc":"2.0","id":3,"result":{call_result}}}'"#
+        ));
+        script.push('\n');
+        std::fs::write(&path, script).unwrap();
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
@@ -259,7 +379,7 @@ printf '%s\n' '{"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text
     #[tokio::test]
     async fn test_register_and_execute_mcp_tool() {
         let dir = TempDir::new().unwrap();
-        let script = test_server_script(&dir);
+        let script = test_server_script(&dir, PONG, &[]);
         let mut registry = crate::ToolRegistry::new();
 
         register_mcp_tools(
@@ -295,14 +415,26 @@ printf '%s\n' '{"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text
             .unwrap();
 
         assert!(!result.is_error);
-        assert_eq!(result.output, "pong");
+        assert_eq!(result.output, "pong\n[image: image/png]");
         assert_eq!(result.metadata["tool_type"], "mcp");
+        assert_eq!(
+            result.parts,
+            [
+                ToolResultPart::Json {
+                    value: serde_json::json!({"ok": true})
+                },
+                ToolResultPart::Image {
+                    data: "aGVsbG8=".to_string(),
+                    mime_type: "image/png".to_string(),
+                },
+            ]
+        );
     }
 
     #[tokio::test]
     async fn test_disabled_mcp_server_is_skipped() {
         let dir = TempDir::new().unwrap();
-        let script = test_server_script(&dir);
+        let script = test_server_script(&dir, PONG, &[]);
         let mut registry = crate::ToolRegistry::new();
 
         register_mcp_tools(
@@ -327,6 +459,63 @@ printf '%s\n' '{"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text
         assert!(!registry.has("mcp__fake__echo"));
     }
 
+    #[tokio::test]
+    async fn test_failed_mcp_call_shows_recent_stderr() {
+        let dir = TempDir::new().unwrap();
+        let script = test_server_script(
+            &dir,
+            r#"{"content":[{"type":"text","text":"request failed"}],"isError":true}"#,
+            &["auth failed: missing TOKEN", "auth failed: missing TOKEN"],
+        );
+        let mut registry = crate::ToolRegistry::new();
+        register_mcp_tools(
+            &mut registry,
+            &[McpServerConfig {
+                name: "fake".to_string(),
+                enabled: true,
+                command: script.display().to_string(),
+                args: Vec::new(),
+                cwd: None,
+                env: HashMap::new(),
+                startup_timeout_secs: 5,
+                tool_timeout_secs: 5,
+            }],
+            dir.path(),
+            SandboxMode::DangerFullAccess,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let server = &registry.mcp_servers()[0];
+        assert_eq!(server.tools, ["mcp__fake__echo"]);
+        assert_eq!(server.state(), McpServerState::Running);
+
+        let result = registry
+            .get("mcp__fake__echo")
+            .unwrap()
+            .execute(
+                serde_json::json!({"text":"hello"}),
+                &ToolContext {
+                    working_dir: dir.path().to_path_buf(),
+                    sandbox_mode: SandboxMode::DangerFullAccess,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_error);
+        assert_eq!(
+            result.output,
+            "request failed\n\nRecent stderr from MCP server 'fake':\nauth failed: missing TOKEN (x2)"
+        );
+        assert_eq!(
+            server.recent_stderr(10),
+            ["fake server starting", "auth failed: missing TOKEN (x2)"]
+        );
+    }
+
     #[test]
     fn test_resolve_server_cwd_relative_to_workspace() {
         let dir = TempDir::new().unwrap();
@@ -0,0 +1,255 @@
+//! Output limits shared by every tool, with paging through the rest.
+//!
+//! A tool result longer than the [`OutputPolicy`] allows is cut to its
+//! first page and ends with a continuation token such as `bash-3:20480`:
+//! the call's output id and the byte offset to go on from. The full output
+//! is kept by the [`OutputPager`], and the `read_more` tool returns the page
+//! at a token along with the token for the page after it. Pages end at a
+//! line break where one falls within the limits.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Outputs kept for paging; older ones are dropped.
+const MAX_STORED: usize = 32;
+
+/// How much of a tool's output goes to the model at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputPolicy {
+    /// Bytes per page; `0` sends outputs whole. Otherwise at least 4, so a
+    /// page always holds a whole UTF-8 character.
+    #[serde(deserialize_with = "deserialize_max_bytes")]
+    pub max_bytes: usize,
+    /// Lines per page; `0` means no line limit.
+    pub max_lines: usize,
+}
+
+impl Default for OutputPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: 32 * 1024,
+            max_lines: 1000,
+        }
+    }
+}
+
+fn deserialize_max_bytes<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let max_bytes = usize::deserialize(deserializer)?;
+    if (1..4).contains(&max_bytes) {
+        return Err(serde::de::Error::custom(
+            "max_bytes must be 0 or at least 4",
+        ));
+    }
+    Ok(max_bytes)
+}
+
+/// One page of a stored output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub text: String,
+    /// Token for the next page, if any output is left.
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Stored {
+    /// Outputs by id, oldest first.
+    outputs: VecDeque<(String, String)>,
+    next_id: usize,
+}
+
+/// Cuts long tool outputs to the [`OutputPolicy`] and keeps the rest for
+/// `read_more`.
+#[derive(Debug, Default)]
+pub struct OutputPager {
+    policy: Mutex<OutputPolicy>,
+    stored: Mutex<Stored>,
+}
+
+impl OutputPager {
+    pub fn new(policy: OutputPolicy) -> Self {
+        Self {
+            policy: Mutex::new(policy),
+            stored: Mutex::default(),
+        }
+    }
+
+    pub fn set_policy(&self, policy: OutputPolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+
+    /// `output` of a `tool` call, cut to its first page with a note on how
+    /// to read the rest when it is over the limits. Pages from `read_more`
+    /// already fit and pass through.
+    pub fn limit(&self, tool: &str, output: String) -> String {
+        if tool == "read_more" {
+            return output;
+        }
+        let end = self.page_end(&output, 0);
+        if end == output.len() {
+            return output;
+        }
+        let id = {
+            let mut stored = self.stored.lock().unwrap();
+            stored.next_id += 1;
+            let id = format!("{tool}-{}", stored.next_id);
+            stored.outputs.push_back((id.clone(), output.clone()));
+            while stored.outputs.len() > MAX_STORED {
+                stored.outputs.pop_front();
+            }
+            id
+        };
+        let mut page = output[..end].to_string();
+        page.push_str(&continuation(&output, 0, end, &id));
+        page
+    }
+
+    /// The page of a stored output at `token`.
+    pub fn page(&self, token: &str) -> Result<Page, String> {
+        let (id, offset) = token
+            .rsplit_once(':')
+            .and_then(|(id, offset)| Some((id, offset.parse::<usize>().ok()?)))
+            .ok_or_else(|| format!("invalid token '{token}': expected one like 'bash-3:20480'"))?;
+        let output = {
+            let stored = self.stored.lock().unwrap();
+            stored
+                .outputs
+                .iter()
+                .find(|(stored_id, _)| stored_id == id)
+                .map(|(_, output)| output.clone())
+                .ok_or_else(|| {
+                    format!("output '{id}' is no longer available; run the tool again")
+                })?
+        };
+        if offset >= output.len() || !output.is_char_boundary(offset) {
+            return Err(format!("offset {offset} is not within output '{id}'"));
+        }
+        let end = self.page_end(&output, offset);
+        let next = (end < output.len()).then(|| format!("{id}:{end}"));
+        let mut text = output[offset..end].to_string();
+        if end < output.len() {
+            text.push_str(&continuation(&output, offset, end, id));
+        }
+        Ok(Page { text, next })
+    }
+
+    /// Where the page of `output` starting at `start` ends.
+    fn page_end(&self, output: &str, start: usize) -> usize {
+        let policy = *self.policy.lock().unwrap();
+        let rest = &output[start..];
+        let mut end = rest.len();
+        if policy.max_lines > 0 {
+            if let Some((i, _)) = rest.match_indices('\n').nth(policy.max_lines - 1) {
+                end = end.min(i + 1);
+            }
+        }
+        if policy.max_bytes > 0 && end > policy.max_bytes {
+            let mut cut = policy.max_bytes;
+            while !rest.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            end = match rest[..cut].rfind('\n') {
+                Some(i) if i > 0 => i + 1,
+                _ => cut,
+            };
+        }
+        if end == 0 {
+            // Always make progress, by at least one whole character.
+            end = rest.chars().next().map_or(0, char::len_utf8);
+        }
+        start + end
+    }
+}
+
+/// The note ending a page from `start` to `end` of `output`.
+fn continuation(output: &str, start: usize, end: usize, id: &str) -> String {
+    let first_line = output[..start].matches('\n').count() + 1;
+    let last_line = first_line
+        + output[start..end]
+            .trim_end_matches('\n')
+            .matches('\n')
+            .count();
+    let total_lines = output.trim_end_matches('\n').matches('\n').count() + 1;
+    format!(
+        "\n\n... (output truncated: showing lines {first_line}-{last_line} of {total_lines}, \
+         bytes {start}-{end} of {}; call read_more with token \"{id}:{end}\" for the rest)",
+        output.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_output_is_paged_with_tokens() {
+        let pager = OutputPager::new(OutputPolicy {
+            max_bytes: 1024,
+            max_lines: 3,
+        });
+        assert_eq!(pager.limit("bash", "short\n".to_string()), "short\n");
+
+        let output: String = (1..=7).map(|n| format!("line {n}\n")).collect();
+        let first = pager.limit("bash", output);
+        assert!(first.starts_with(
+            "line 1\nline 2\nline 3\n\n\n... (output truncated: showing lines 1-3 of 7"
+        ));
+        assert!(first.ends_with("call read_more with token \"bash-1:21\" for the rest)"));
+
+        let second = pager.page("bash-1:21").unwrap();
+        assert!(second.text.starts_with("line 4\nline 5\nline 6\n\n\n"));
+        assert_eq!(second.next.as_deref(), Some("bash-1:42"));
+        let last = pager.page("bash-1:42").unwrap();
+        assert_eq!(
+            last,
+            Page {
+                text: "line 7\n".to_string(),
+                next: None
+            }
+        );
+
+        assert!(pager
+            .page("bash-9:0")
+            .unwrap_err()
+            .contains("no longer available"));
+        assert!(pager.page("bash-1").is_err());
+    }
+
+    #[test]
+    fn test_pages_end_at_line_breaks_within_the_byte_limit() {
+        let pager = OutputPager::new(OutputPolicy {
+            max_bytes: 10,
+            max_lines: 0,
+        });
+        let first = pager.limit("grep", "abcd\nefgh\nijkl\n".to_string());
+        assert!(first.starts_with("abcd\nefgh\n\n\n..."));
+
+        // A single long line is cut at the byte limit, on a char boundary.
+        let first = pager.limit("read", format!("a{}", "é".repeat(12)));
+        assert!(first.starts_with("aéééé\n\n..."));
+        assert_eq!(
+            pager.page("read-2:9").unwrap().next.as_deref(),
+            Some("read-2:19")
+        );
+    }
+
+    #[test]
+    fn test_pages_hold_at_least_one_character() {
+        let pager = OutputPager::new(OutputPolicy {
+            max_bytes: 1,
+            max_lines: 0,
+        });
+        let first = pager.limit("read", "😀é".to_string());
+        assert!(first.starts_with("😀\n\n..."));
+        assert_eq!(pager.page("read-1:4").unwrap().text, "é");
+
+        let parsed = serde_json::from_str::<OutputPolicy>(r#"{"max_bytes": 3}"#);
+        assert!(parsed.unwrap_err().to_string().contains("at least 4"));
+        assert!(serde_json::from_str::<OutputPolicy>(r#"{"max_bytes": 0}"#).is_ok());
+    }
+}
@@ -18,6 +18,7 @@ pub fn resolve_existing_path(path: &Path, working_dir: &Path) -> Result<PathBuf,
         .canonicalize()
         .map_err(|e| ToolError::ExecutionError(format!("Cannot resolve path: {e}")))?;
     ensure_within_workspace(path, &canonical, &workspace)?;
+    ensure_not_ignored(path, &canonical, &workspace)?;
     Ok(canonical)
 }
 
@@ -36,6 +37,7 @@ pub fn resolve_path_for_write(path: &Path, working_dir: &Path) -> Result<PathBuf
     };
 
     ensure_within_workspace(path, &resolved, &workspace)?;
+    ensure_not_ignored(path, &resolved, &workspace)?;
     Ok(resolved)
 }
 
@@ -57,6 +59,17 @@ fn ensure_within_workspace(original: &Path, resolved: &Path, workspace: &Path) -
     Ok(())
 }
 
+fn ensure_not_ignored(original: &Path, resolved: &Path, workspace: &Path) -> Result<(), ToolError> {
+    if crate::rotignore::is_ignored(workspace, resolved) {
+        return Err(ToolError::PermissionDenied(format!(
+            "Path '{}' is excluded by {}",
+            original.display(),
+            crate::rotignore::ROTIGNORE_FILE
+        )));
+    }
+    Ok(())
+}
+
 fn split_existing_ancestor(path: &Path) -> Result<(PathBuf, PathBuf), ToolError> {
     let mut existing = path.to_path_buf();
     let mut tail = PathBuf::new();
@@ -0,0 +1,195 @@
+//! Speculative reads of files the model names before it calls `read`.
+//!
+//! While a response streams, the agent passes the paths its text mentions to
+//! [`ReadCache::prefetch`], which reads them in the background. A later
+//! `read` of the same file takes the cached contents instead of going to
+//! disk, as long as the file's size and modification time still match.
+//! Entries are used once; unclaimed ones are evicted oldest first.
+
+use crate::path_guard::resolve_existing_path;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Files held at once; the oldest go first.
+const MAX_ENTRIES: usize = 64;
+/// Larger files are left for `read` to load.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug)]
+struct CachedFile {
+    content: String,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    files: HashMap<PathBuf, CachedFile>,
+    order: VecDeque<PathBuf>,
+}
+
+/// Files read ahead of the `read` calls expected to ask for them.
+#[derive(Debug, Default)]
+pub struct ReadCache {
+    entries: Mutex<Entries>,
+}
+
+impl ReadCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read `mention` in the background if it names a file in the
+    /// workspace of `working_dir` that `read` would be allowed to open.
+    /// Returns whether a read was started.
+    pub fn prefetch(self: &Arc<Self>, mention: &str, working_dir: &Path) -> bool {
+        let Ok(path) = resolve_existing_path(Path::new(mention), working_dir) else {
+            return false;
+        };
+        if self.entries.lock().unwrap().files.contains_key(&path) {
+            return false;
+        }
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            let Ok(metadata) = tokio::fs::metadata(&path).await else {
+                return;
+            };
+            if !metadata.is_file() || metadata.len() > MAX_FILE_BYTES {
+                return;
+            }
+            if let Ok(content) = tokio::fs::read_to_string(&path).await {
+                cache.insert(
+                    path,
+                    CachedFile {
+                        content,
+                        len: metadata.len(),
+                        modified: metadata.modified().ok(),
+                    },
+                );
+            }
+        });
+        true
+    }
+
+    fn insert(&self, path: PathBuf, file: CachedFile) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.files.insert(path.clone(), file).is_none() {
+            entries.order.push_back(path);
+        }
+        while entries.order.len() > MAX_ENTRIES {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.files.remove(&oldest);
+            }
+        }
+    }
+
+    /// Take the prefetched contents of `path`, if the file is unchanged
+    /// since it was read.
+    pub async fn take(&self, path: &Path) -> Option<String> {
+        let file = {
+            let mut entries = self.entries.lock().unwrap();
+            let file = entries.files.remove(path)?;
+            entries.order.retain(|p| p != path);
+            file
+        };
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        (metadata.len() == file.len && metadata.modified().ok() == file.modified)
+            .then_some(file.content)
+    }
+}
+
+/// Words in `text` that look like file paths, such as `src/lib.rs` or
+/// `` `Cargo.toml` ``, with quotes, brackets, and `:line` suffixes removed.
+pub fn mentioned_paths(text: &str) -> Vec<&str> {
+    text.split(|c: char| {
+        c.is_whitespace()
+            || matches!(
+                c,
+                '`' | '"' | '\'' | '(' | ')' | '[' | ']' | '<' | '>' | ','
+            )
+    })
+    .map(|word| word.trim_end_matches(['.', ':', ';', '!', '?']))
+    .map(|word| match word.split_once(':') {
+        Some((path, line))
+            if line
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == ':' || c == '-') =>
+        {
+            path
+        }
+        _ => word,
+    })
+    .filter(|word| {
+        word.len() > 2
+            && word.len() < 256
+            && !word.contains("://")
+            && (word.contains('/')
+                || word.rsplit_once('.').is_some_and(|(stem, ext)| {
+                    !stem.is_empty()
+                        && !ext.is_empty()
+                        && ext.chars().all(|c| c.is_ascii_alphanumeric())
+                }))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_mentioned_paths() {
+        let text = "Let me look at `src/main.rs` and Cargo.toml, then crates/core/lib.rs:42. \
+                    See https://example.com/a.rs or the config.";
+        assert_eq!(
+            mentioned_paths(text),
+            ["src/main.rs", "Cargo.toml", "crates/core/lib.rs"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefetched_file_is_taken_once_while_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap().join("ws");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("notes.md"), "first").unwrap();
+        std::fs::write(root.join("../outside.md"), "secret").unwrap();
+        let cache = Arc::new(ReadCache::new());
+
+        assert!(!cache.prefetch("missing.md", &root));
+        assert!(!cache.prefetch("../outside.md", &root));
+        assert!(cache.prefetch("notes.md", &root));
+        for _ in 0..100 {
+            if cache
+                .entries
+                .lock()
+                .unwrap()
+                .files
+                .contains_key(&root.join("notes.md"))
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(
+            cache.take(&root.join("notes.md")).await.as_deref(),
+            Some("first")
+        );
+        assert_eq!(cache.take(&root.join("notes.md")).await, None);
+
+        cache.insert(
+            root.join("notes.md"),
+            CachedFile {
+                content: "first".to_string(),
+                len: 5,
+                modified: None,
+            },
+        );
+        std::fs::write(root.join("notes.md"), "second!").unwrap();
+        assert_eq!(cache.take(&root.join("notes.md")).await, None);
+    }
+}
@@ -1,8 +1,13 @@
 //! Tool registry for managing available tools.
 
+use crate::builtin::bash::BashTool;
+use crate::mcp::McpServerHandle;
+use crate::output::{OutputPager, OutputPolicy};
+use crate::shell::CommandPolicy;
 use crate::traits::Tool;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Registry of available tools.
 ///
@@ -10,6 +15,10 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    default_timeout: Option<Duration>,
+    timeouts: HashMap<String, Duration>,
+    mcp_servers: Vec<McpServerHandle>,
+    pager: Arc<OutputPager>,
 }
 
 impl ToolRegistry {
@@ -17,6 +26,10 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            default_timeout: None,
+            timeouts: HashMap::new(),
+            mcp_servers: Vec::new(),
+            pager: Arc::new(OutputPager::new(OutputPolicy::default())),
         }
     }
 
@@ -25,6 +38,55 @@ impl ToolRegistry {
         self.tools.insert(tool.name().to_string(), tool);
     }
 
+    /// Set the execution timeout used for tools without a specific override.
+    pub fn set_default_timeout(&mut self, timeout: Duration) {
+        self.default_timeout = Some(timeout);
+    }
+
+    /// Override the execution timeout for one tool.
+    pub fn set_timeout(&mut self, name: impl Into<String>, timeout: Duration) {
+        self.timeouts.insert(name.into(), timeout);
+    }
+
+    /// Configured timeout for `name`, falling back to the registry default.
+    ///
+    /// Returns `None` when neither is set so callers keep their own default.
+    pub fn timeout_for(&self, name: &str) -> Option<Duration> {
+        self.timeouts.get(name).copied().or(self.default_timeout)
+    }
+
+    /// Classify `bash` commands against `commands`. Does nothing when the
+    /// `bash` tool is not registered.
+    pub fn set_command_policy(&mut self, commands: CommandPolicy) {
+        for entry in commands.invalid_entries() {
+            tracing::warn!("Ignoring bash command list entry '{entry}': not a command");
+        }
+        if self.tools.contains_key("bash") {
+            self.register(Arc::new(BashTool::new(commands)));
+        }
+    }
+
+    /// Set how much of each tool output is sent to the model at once.
+    pub fn set_output_policy(&mut self, policy: OutputPolicy) {
+        self.pager.set_policy(policy);
+    }
+
+    /// Pager that cuts long outputs and keeps the rest for `read_more`.
+    /// Clones of the registry share it.
+    pub fn output_pager(&self) -> Arc<OutputPager> {
+        self.pager.clone()
+    }
+
+    /// Record a connected MCP server whose tools were registered.
+    pub fn add_mcp_server(&mut self, server: McpServerHandle) {
+        self.mcp_servers.push(server);
+    }
+
+    /// Connected MCP servers, in the order they were started.
+    pub fn mcp_servers(&self) -> &[McpServerHandle] {
+        &self.mcp_servers
+    }
+
     /// Get a tool by name.
     pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
         self.tools.get(name).cloned()
@@ -144,6 +206,20 @@ mod tests {
         assert_eq!(defs[0]["description"], "A mock tool for testing");
     }
 
+    #[test]
+    fn test_registry_timeout_overrides() {
+        let mut registry = ToolRegistry::new();
+        assert_eq!(registry.timeout_for("bash"), None);
+
+        registry.set_default_timeout(Duration::from_secs(90));
+        registry.set_timeout("webfetch", Duration::from_secs(30));
+        assert_eq!(
+            registry.timeout_for("webfetch"),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(registry.timeout_for("bash"), Some(Duration::from_secs(90)));
+    }
+
     #[test]
     fn test_registry_empty() {
         let registry = ToolRegistry::new();
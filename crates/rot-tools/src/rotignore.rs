@@ -0,0 +1,109 @@
+//! `.rotignore` files: paths kept away from the agent.
+//!
+//! A `.rotignore` uses `.gitignore` syntax and may sit in any directory of
+//! the workspace. Directory walks (`glob`, `grep`, `@` mentions, RLM
+//! context) skip what it matches on top of `.gitignore`, and `read`,
+//! `write`, and `edit` refuse matching paths.
+
+use ignore::gitignore::Gitignore;
+use ignore::{Match, WalkBuilder};
+use std::path::Path;
+
+/// File name of an ignore file.
+pub const ROTIGNORE_FILE: &str = ".rotignore";
+
+/// A walk of `root` honouring `.gitignore` and `.rotignore`, hidden files included.
+pub fn walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .git_ignore(true)
+        .hidden(false)
+        .add_custom_ignore_filename(ROTIGNORE_FILE);
+    builder
+}
+
+/// Whether a `.rotignore` between `workspace` and `path` excludes `path`.
+/// Both must be absolute and normalized; files deeper in the tree override
+/// shallower ones, as with `.gitignore`.
+pub fn is_ignored(workspace: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(workspace) else {
+        return false;
+    };
+    let is_dir = path.is_dir();
+    let mut ignored = false;
+    let mut dir = workspace.to_path_buf();
+    let mut components = relative.components();
+    loop {
+        let file = dir.join(ROTIGNORE_FILE);
+        if file.is_file() {
+            let (matcher, _) = Gitignore::new(&file);
+            match matcher.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => ignored = true,
+                Match::Whitelist(_) => ignored = false,
+                Match::None => {}
+            }
+        }
+        match components.next() {
+            Some(component) if dir.join(component).is_dir() && dir.join(component) != path => {
+                dir.push(component);
+            }
+            _ => return ignored,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_rotignore_is_honoured_by_walks_and_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("fixtures/keep")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join(ROTIGNORE_FILE), "fixtures/\n*.csv\n").unwrap();
+        fs::write(root.join("src").join(ROTIGNORE_FILE), "!data.csv\n").unwrap();
+        for file in [
+            "src/main.rs",
+            "src/data.csv",
+            "big.csv",
+            "fixtures/a.json",
+            "fixtures/keep/b.json",
+        ] {
+            fs::write(root.join(file), "x").unwrap();
+        }
+
+        let mut walked: Vec<String> = walker(&root)
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+            .map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        walked.sort();
+        assert_eq!(
+            walked,
+            [
+                ".rotignore",
+                "src/.rotignore",
+                "src/data.csv",
+                "src/main.rs"
+            ]
+        );
+
+        assert!(is_ignored(&root, &root.join("fixtures")));
+        assert!(is_ignored(&root, &root.join("fixtures/keep/b.json")));
+        assert!(is_ignored(&root, &root.join("big.csv")));
+        assert!(is_ignored(&root, &root.join("new.csv")));
+        assert!(!is_ignored(&root, &root.join("src/data.csv")));
+        assert!(!is_ignored(&root, &root.join("src/main.rs")));
+    }
+}
@@ -1,9 +1,13 @@
 //! Tool trait definition and common types.
 
 use crate::error::ToolError;
+use crate::prefetch::ReadCache;
+use crate::shell::CommandVerdict;
 use async_trait::async_trait;
+use rot_provider::ToolResultPart;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -27,6 +31,92 @@ pub struct TaskRequest {
     pub agent: String,
     /// Prompt for the delegated task.
     pub prompt: String,
+    /// Workspace files whose contents are handed to the subagent up front.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Relevant excerpt of the parent conversation.
+    #[serde(default)]
+    pub context: Option<String>,
+    /// Expected shape of the subagent's final answer.
+    #[serde(default)]
+    pub output_format: Option<String>,
+}
+
+/// Per-file cap applied when pre-reading files for a delegated task.
+const MAX_TASK_FILE_BYTES: usize = 20 * 1024;
+
+impl TaskRequest {
+    /// Create a request with no extra context.
+    pub fn new(agent: impl Into<String>, prompt: impl Into<String>) -> Self {
+        Self {
+            agent: agent.into(),
+            prompt: prompt.into(),
+            files: Vec::new(),
+            context: None,
+            output_format: None,
+        }
+    }
+
+    /// Build the opening message for the subagent.
+    ///
+    /// Parent context and pre-read file contents are placed ahead of the task
+    /// prompt. Files are resolved like the `read` tool resolves them: inside
+    /// `working_dir` and not excluded by `.rotignore`. Files in `refused`,
+    /// which callers fill from their permission rules, are noted with the
+    /// reason instead of read; so are unreadable files, rather than failing
+    /// the whole task.
+    pub async fn briefing(&self, working_dir: &Path, refused: &HashMap<String, String>) -> String {
+        let mut sections = Vec::new();
+
+        if let Some(context) = self.context.as_deref().filter(|c| !c.trim().is_empty()) {
+            sections.push(format!("## Context from parent agent\n{}", context.trim()));
+        }
+
+        for file in &self.files {
+            let body = match refused.get(file) {
+                Some(reason) => Err(reason.clone()),
+                None => {
+                    match crate::path_guard::resolve_existing_path(Path::new(file), working_dir) {
+                        Ok(path) => tokio::fs::read_to_string(path)
+                            .await
+                            .map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    }
+                }
+            };
+            let section = match body {
+                Ok(content) if content.len() > MAX_TASK_FILE_BYTES => {
+                    let mut end = MAX_TASK_FILE_BYTES;
+                    while !content.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    format!(
+                        "## File: {file}\n```\n{}\n```\n(truncated at {} of {} bytes)",
+                        &content[..end],
+                        end,
+                        content.len()
+                    )
+                }
+                Ok(content) => format!("## File: {file}\n```\n{content}\n```"),
+                Err(e) => format!("## File: {file}\n(unavailable: {e})"),
+            };
+            sections.push(section);
+        }
+
+        if sections.is_empty() && self.output_format.is_none() {
+            return self.prompt.clone();
+        }
+
+        sections.push(format!("## Task\n{}", self.prompt));
+        if let Some(format) = self
+            .output_format
+            .as_deref()
+            .filter(|f| !f.trim().is_empty())
+        {
+            sections.push(format!("## Expected output\n{}", format.trim()));
+        }
+        sections.join("\n\n")
+    }
 }
 
 /// Result of delegated subagent execution.
@@ -38,6 +128,46 @@ pub struct TaskExecution {
     pub child_session_id: Option<String>,
     /// Agent that handled the task.
     pub agent: String,
+    /// What the subagent did, in a form the caller can rely on.
+    #[serde(default)]
+    pub report: TaskReport,
+}
+
+/// Structured account of a delegated task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskReport {
+    /// The subagent's answer, without its follow-ups.
+    pub summary: String,
+    /// Files the subagent wrote or edited.
+    #[serde(default)]
+    pub files_changed: Vec<String>,
+    /// Shell commands the subagent ran.
+    #[serde(default)]
+    pub commands_run: Vec<String>,
+    /// Work the subagent left for the caller.
+    #[serde(default)]
+    pub follow_ups: Vec<String>,
+}
+
+impl TaskReport {
+    /// The report as the text handed back to the calling agent.
+    pub fn render(&self) -> String {
+        let mut out = self.summary.clone();
+        for (heading, items) in [
+            ("Files changed", &self.files_changed),
+            ("Commands run", &self.commands_run),
+            ("Follow-ups", &self.follow_ups),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n\n{heading}:"));
+            for item in items {
+                out.push_str(&format!("\n- {item}"));
+            }
+        }
+        out
+    }
 }
 
 /// Callback interface used by the `task` tool.
@@ -60,12 +190,19 @@ pub struct ToolContext {
     pub sandbox_mode: SandboxMode,
     /// Whether outbound network access is allowed.
     pub network_access: bool,
+    /// Hosts this call may reach even with `network_access` off, because
+    /// the user allowed them.
+    pub allowed_hosts: Vec<String>,
+    /// Whether mutating tools should describe their effect instead of running.
+    pub dry_run: bool,
     /// Current delegated task depth.
     pub task_depth: usize,
     /// Maximum delegated task depth allowed.
     pub max_task_depth: usize,
     /// Optional delegated task runner for the `task` tool.
     pub task_runner: Option<Arc<dyn TaskRunner>>,
+    /// Files read ahead for `read`, when prefetching is on.
+    pub read_cache: Option<Arc<ReadCache>>,
 }
 
 impl std::fmt::Debug for ToolContext {
@@ -76,9 +213,12 @@ impl std::fmt::Debug for ToolContext {
             .field("timeout", &self.timeout)
             .field("sandbox_mode", &self.sandbox_mode)
             .field("network_access", &self.network_access)
+            .field("allowed_hosts", &self.allowed_hosts)
+            .field("dry_run", &self.dry_run)
             .field("task_depth", &self.task_depth)
             .field("max_task_depth", &self.max_task_depth)
             .field("has_task_runner", &self.task_runner.is_some())
+            .field("has_read_cache", &self.read_cache.is_some())
             .finish()
     }
 }
@@ -91,13 +231,28 @@ impl Default for ToolContext {
             timeout: Duration::from_secs(120),
             sandbox_mode: SandboxMode::WorkspaceWrite,
             network_access: false,
+            allowed_hosts: Vec::new(),
+            dry_run: false,
             task_depth: 0,
             max_task_depth: 1,
             task_runner: None,
+            read_cache: None,
         }
     }
 }
 
+impl ToolContext {
+    /// Whether this call may connect to `host`.
+    pub fn allows_host(&self, host: &str) -> bool {
+        self.network_access
+            || self.sandbox_mode == SandboxMode::DangerFullAccess
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+}
+
 /// Result of a tool execution.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResult {
@@ -107,6 +262,9 @@ pub struct ToolResult {
     pub metadata: serde_json::Value,
     /// Whether the result represents an error.
     pub is_error: bool,
+    /// Structured blocks sent to the model after `output`, such as images.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ToolResultPart>,
 }
 
 impl ToolResult {
@@ -116,6 +274,7 @@ impl ToolResult {
             output: output.into(),
             metadata: serde_json::Value::Null,
             is_error: false,
+            parts: Vec::new(),
         }
     }
 
@@ -125,6 +284,7 @@ impl ToolResult {
             output: output.into(),
             metadata,
             is_error: false,
+            parts: Vec::new(),
         }
     }
 
@@ -134,8 +294,15 @@ impl ToolResult {
             output: output.into(),
             metadata: serde_json::Value::Null,
             is_error: true,
+            parts: Vec::new(),
         }
     }
+
+    /// Attach structured blocks, such as images, for the model.
+    pub fn with_parts(mut self, parts: Vec<ToolResultPart>) -> Self {
+        self.parts = parts;
+        self
+    }
 }
 
 /// Trait that all tools must implement.
@@ -156,6 +323,18 @@ pub trait Tool: Send + Sync {
     /// JSON Schema describing the tool's parameters.
     fn parameters_schema(&self) -> serde_json::Value;
 
+    /// Hosts this call would connect to, so each can be approved before it
+    /// runs while sandbox network access is off.
+    fn network_hosts(&self, _args: &serde_json::Value) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Where this call's shell command stands with the configured allow and
+    /// deny lists. Only tools that run shell commands classify them.
+    fn command_verdict(&self, _args: &serde_json::Value) -> CommandVerdict {
+        CommandVerdict::Unlisted
+    }
+
     /// Execute the tool with the given arguments.
     async fn execute(
         &self,
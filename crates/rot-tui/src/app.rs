@@ -8,25 +8,37 @@
 
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use rot_core::permission::ApprovalResponse;
+use rot_core::usage::{UsageLedger, UsageTotals};
+use rot_provider::ModelInfo;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use crate::approval::{ApprovalPrompt, PreviewKind};
+use crate::help::{HelpLine, HelpOverlay};
+use crate::pager::Pager;
+
 // ── Theme (Tokyo Night) ───────────────────────────────────────────────
 
 const COLOR_USER: Color = Color::Rgb(42, 195, 222);      // Cyan #2ac3de
 const COLOR_ASSISTANT: Color = Color::Rgb(115, 218, 202); // Teal #73daca
-const COLOR_TOOL: Color = Color::Rgb(187, 154, 247);      // Purple #bb9af7
-const COLOR_ERROR: Color = Color::Rgb(247, 118, 142);     // Red #f7768e
-const COLOR_SYSTEM: Color = Color::Rgb(224, 175, 104);    // Yellow #e0af68
-const COLOR_THINKING: Color = Color::Rgb(86, 95, 137);    // Comment #565f89
-const COLOR_CODE_BG: Color = Color::Rgb(26, 27, 38);      // Background #1a1b26
-const COLOR_CODE_FG: Color = Color::Rgb(192, 202, 245);   // Foreground #c0caf5
-const COLOR_HEADER_BG: Color = Color::Rgb(22, 22, 30);    // Darker bg #16161e
-const COLOR_ACCENT: Color = Color::Rgb(122, 162, 247);    // Blue #7aa2f7
-const COLOR_BAR_BG: Color = Color::Rgb(22, 22, 30);       // Darker bg #16161e
-const COLOR_BAR_FG: Color = Color::Rgb(86, 95, 137);      // Comment #565f89
-const COLOR_BORDER: Color = Color::Rgb(41, 46, 66);       // Surface #292e42
-const COLOR_DIM: Color = Color::Rgb(86, 95, 137);         // Comment #565f89
-const COLOR_BANNER: Color = Color::Rgb(122, 162, 247);    // Blue #7aa2f7
+const COLOR_TOOL: Color = Color::Rgb(187, 154, 247); // Purple #bb9af7
+const COLOR_ERROR: Color = Color::Rgb(247, 118, 142); // Red #f7768e
+const COLOR_SYSTEM: Color = Color::Rgb(224, 175, 104); // Yellow #e0af68
+const COLOR_THINKING: Color = Color::Rgb(86, 95, 137); // Comment #565f89
+pub(crate) const COLOR_CODE_BG: Color = Color::Rgb(26, 27, 38); // Background #1a1b26
+pub(crate) const COLOR_CODE_FG: Color = Color::Rgb(192, 202, 245); // Foreground #c0caf5
+const COLOR_HEADER_BG: Color = Color::Rgb(22, 22, 30); // Darker bg #16161e
+pub(crate) const COLOR_ACCENT: Color = Color::Rgb(122, 162, 247); // Blue #7aa2f7
+const COLOR_BAR_BG: Color = Color::Rgb(22, 22, 30); // Darker bg #16161e
+const COLOR_BAR_FG: Color = Color::Rgb(86, 95, 137); // Comment #565f89
+pub(crate) const COLOR_BORDER: Color = Color::Rgb(41, 46, 66); // Surface #292e42
+pub(crate) const COLOR_DIM: Color = Color::Rgb(86, 95, 137); // Comment #565f89
+const COLOR_BANNER: Color = Color::Rgb(122, 162, 247); // Blue #7aa2f7
+const COLOR_DIFF_ADD: Color = Color::Rgb(158, 206, 106); // Green #9ece6a
+const COLOR_SELECTION: Color = Color::Rgb(40, 52, 87); // Selection #283457
 
 // ── ASCII Art ──────────────────────────────────────────────────────────
 
@@ -67,6 +79,7 @@ pub enum AppState {
     Streaming,
     Approval, // Paused for user permission
     Agents,   // Agent selection overlay
+    Sessions, // Session resume picker
     Config,   // Model & API Key overlay
     Error,
 }
@@ -83,26 +96,98 @@ impl Default for ConfigUiState {
     }
 }
 
-pub const AVAILABLE_MODELS: &[(&str, &str)] = &[
-    ("anthropic", "claude-3-7-sonnet-latest"),
-    ("anthropic", "claude-3-5-sonnet-latest"),
-    ("anthropic", "claude-3-5-haiku-latest"),
-    ("zai", "glm-5"),
-    ("zai", "glm-4.7"),
-    ("openai", "gpt-4o"),
-    ("openai", "gpt-4o-mini"),
+/// Built-in providers offered by the model picker, ahead of custom ones.
+pub use rot_core::BUILTIN_PROVIDERS;
+
+/// Models the picker offers for one provider.
+#[derive(Debug, Clone)]
+pub enum ModelListing {
+    /// Waiting for the provider's model list.
+    Loading,
+    /// Models reported by the provider's API.
+    Live(Vec<ModelInfo>),
+    /// The provider's built-in list, used when its API could not be queried.
+    Builtin {
+        models: Vec<ModelInfo>,
+        reason: String,
+    },
+    /// The provider could not be created.
+    Failed(String),
+}
+
+impl ModelListing {
+    /// Models to choose from; empty while loading or after a failure.
+    pub fn models(&self) -> &[ModelInfo] {
+        match self {
+            Self::Live(models) | Self::Builtin { models, .. } => models,
+            Self::Loading | Self::Failed(_) => &[],
+        }
+    }
+}
+
+/// Diffs up to this many lines are shown without expanding the block.
+const DIFF_EXPANDED_LINES: usize = 20;
+
+/// Cap on argument and output lines in an expanded tool block.
+const DETAIL_MAX_LINES: usize = 200;
+
+/// Argument names shown in a tool summary, in order of preference.
+const KEY_ARGUMENTS: &[&str] = &[
+    "path",
+    "command",
+    "pattern",
+    "url",
+    "agent",
+    "query",
+    "description",
 ];
 
+/// Input box prompt while Ctrl-R history search is open.
+const HISTORY_SEARCH_PROMPT: &str = "(history) ";
+
+/// Cap on workspace files listed for `@` completion.
+const MAX_WORKSPACE_FILES: usize = 20_000;
+
+/// Context use, in percent, at which the footer warns and suggests `/compact`.
+const CONTEXT_WARNING_PERCENT: f64 = 80.0;
+
+/// Most rows the input box grows to before it scrolls.
+const MAX_INPUT_ROWS: usize = 8;
+
+/// Latest reasoning lines shown while the live thinking region is collapsed.
+const THINKING_PREVIEW_LINES: usize = 3;
+
+/// Most task rows shown at once in the tasks panel.
+const TASK_PANEL_ROWS: usize = 6;
+
 pub const SLASH_COMMANDS: &[(&str, &str)] = &[
     ("/agents", "Switch agent"),
     ("/children", "Inspect delegated child runs"),
+    ("/tasks", "Toggle the background tasks panel"),
+    ("/select", "Release the mouse to select text"),
+    ("/sandbox", "Show or change the sandbox mode"),
+    ("/approval", "Show or change the approval policy"),
     ("/tools", "List loaded tools"),
+    ("/mcp", "Show MCP server status and recent stderr"),
     ("/tree", "Show session tree"),
+    ("/results", "List this session's tool results"),
+    ("/result", "Open a tool result in the pager"),
     ("/help", "Show help"),
     ("/clear", "Clear conversation"),
+    ("/edit", "Edit a previous message"),
+    ("/compact", "Summarize older turns to free context"),
+    ("/cost", "Show this session's tokens and spend"),
+    ("/usage", "Show spend across this project's sessions"),
+    ("/resume", "Resume a previous session"),
+    ("/export", "Export the conversation to Markdown or JSON"),
+    ("/attach", "Attach an image to the next message"),
     ("/models", "Switch model"),
     ("/model", "Switch model"),
     ("/rlm", "Toggle RLM"),
+    ("/context", "Show or set the RLM context"),
+    ("/cd", "Show or change the working directory"),
+    ("/thinking", "Show or hide model reasoning"),
+    ("/meta", "Show or hide message metadata"),
     ("/quit", "Exit app"),
     ("/exit", "Exit app"),
 ];
@@ -121,6 +206,17 @@ pub struct App {
     pub cursor_pos: usize,
     pub chat_lines: Vec<ChatLine>,
     pub streaming_text: String,
+    /// Whether prompts and replies show their metadata; toggled with `/meta`.
+    pub show_meta: bool,
+    /// Tokens used so far in the current turn, including subagent requests.
+    pub turn_tokens: usize,
+    /// Reasoning streamed during the current turn, not yet moved to the chat.
+    pub thinking_text: String,
+    /// Whether model reasoning is shown; toggled with `/thinking on|off`.
+    pub show_thinking: bool,
+    /// Whether reasoning is shown in full rather than its latest lines;
+    /// toggled with Ctrl+O.
+    pub thinking_expanded: bool,
     pub scroll_offset: u16,
     pub auto_scroll: bool,
     pub status: String,
@@ -130,21 +226,85 @@ pub struct App {
     pub thinking_tick: u16,
     pub total_input_tokens: usize,
     pub total_output_tokens: usize,
+    /// Usage of the latest provider request; together they fill the context window.
     pub last_input_tokens: usize,
     pub last_output_tokens: usize,
+    /// Spend in USD, `None` while no request has been priced.
+    pub total_cost: Option<f64>,
+    /// Whether the context warning was shown since usage last dropped below it.
+    context_warned: bool,
     pub response_start: Option<Instant>,
     pub last_elapsed: Option<Duration>,
     pub message_count: usize,
     pub showed_welcome: bool,
     /// Maximum scroll offset (computed during render).
     pub max_scroll: u16,
+    /// Cells available to input text per row (computed during render).
+    pub input_width: usize,
     
     // Approval state
-    pub pending_approval_tool: Option<String>,
-    pub pending_approval_args: Option<serde_json::Value>,
-    pub pending_approval_tx: Option<tokio::sync::oneshot::Sender<rot_core::permission::ApprovalResponse>>,
+    approval: Option<ApprovalPrompt>,
+    approval_tx: Option<tokio::sync::oneshot::Sender<ApprovalResponse>>,
     pub rlm_enabled: bool,
-    pub rlm_iterating: bool,
+    /// File, directory, or glob RLM runs read, relative to the workspace;
+    /// the workspace itself when unset. Starts from `rlm.context`.
+    pub rlm_context: Option<String>,
+    /// Latest progress of the RLM run in this turn, if any.
+    pub rlm_progress: Option<rot_rlm::RlmProgress>,
+    /// Agent loop iteration limit for this session.
+    pub max_iterations: usize,
+    /// Most recent agent loop progress while a turn is running.
+    pub iteration_progress: Option<rot_core::IterationProgress>,
+    /// Index of the user prompt being edited, if any.
+    pub editing_prompt: Option<usize>,
+    /// Follow-up messages submitted while a run is in progress.
+    pub queued_inputs: Arc<Mutex<VecDeque<String>>>,
+    /// Directory tools run in and `@` mentions resolve against; changed
+    /// with `/cd`. Shared with the agent's steering callback.
+    pub working_dir: Arc<Mutex<PathBuf>>,
+    /// Directory rot started in; `/cd` stays inside it.
+    pub workspace: PathBuf,
+    /// Model aliases from config, used by `/model NAME`.
+    pub model_aliases: HashMap<String, String>,
+    /// Names of the custom providers in config, sorted.
+    pub custom_providers: Vec<String>,
+    /// Model lists loaded for the picker, by provider.
+    pub model_listings: HashMap<String, ModelListing>,
+    /// Provider whose models the picker shows.
+    picker_provider: String,
+    /// Provider whose model list the runner should fetch next.
+    models_request: Option<String>,
+    /// Scrollback search started with `/` in Normal mode.
+    pub search: Option<SearchState>,
+    /// Workspace files for `@` completion, loaded when a mention starts.
+    pub workspace_files: Option<Vec<String>>,
+    /// Files matching the `@` mention under the cursor.
+    pub file_matches: Vec<String>,
+    pub file_menu_selected: usize,
+    /// Expandable chat line selected in Normal mode.
+    pub selected_chat: Option<usize>,
+    /// Chat line to bring into view on the next render.
+    scroll_to_chat: Option<usize>,
+    /// Prefix key (`d`, `c`, `y`, or `g`) waiting for its second key in Normal mode.
+    pending_key: Option<char>,
+    /// Chat lines selected with `v` in Normal mode.
+    pub visual: Option<VisualSelection>,
+    /// The `/help` overlay, drawn over whatever else is on screen.
+    pub help: Option<HelpOverlay>,
+    /// Full-screen pager opened with `/result N`, drawn over everything.
+    pub pager: Option<Pager>,
+    /// Active sandbox and approval settings, shown in the footer and help
+    /// overlay and changed with `/sandbox` and `/approval`.
+    pub security: rot_core::RuntimeSecurityConfig,
+    /// Security settings waiting for the user to confirm an escalation.
+    pub security_confirm: Option<rot_core::RuntimeSecurityConfig>,
+    /// `security` changed and the runner has not rebuilt the tools yet.
+    security_changed: bool,
+    /// Mouse capture is off so the terminal's own selection works; toggled
+    /// with Ctrl+S or `/select`.
+    pub select_mode: bool,
+    /// Chat as last rendered, for visual selection and half-page scrolling.
+    chat_view: ChatView,
     
     // Config state
     pub config_ui_state: ConfigUiState,
@@ -152,6 +312,108 @@ pub struct App {
     pub agent_changed: bool,
     pub slash_menu_selected: usize,
     pub agent_menu_selected: usize,
+    /// Images attached with `/attach`, sent with the next prompt.
+    pub pending_images: Vec<crate::images::ImageAttachment>,
+    /// Submitted prompts for this project.
+    pub history: crate::history::PromptHistory,
+    /// Entry of `history` shown in the input while browsing with Up/Down.
+    history_index: Option<usize>,
+    /// Ctrl-R search over `history`.
+    pub history_search: Option<HistorySearch>,
+    /// Sessions listed by the resume picker, most recent first.
+    pub session_choices: Vec<rot_session::SessionMeta>,
+    pub session_menu_selected: usize,
+    /// Delegated task runs this session, in start order.
+    pub tasks: Vec<TaskRun>,
+    /// Whether the tasks panel is shown above the input.
+    pub show_tasks: bool,
+    /// Task selected in the panel with `J`/`K` in Normal mode.
+    pub task_selected: Option<usize>,
+    /// Child session to show, set by Enter on a task and handled by the runner.
+    pub pending_child_view: Option<String>,
+    /// Tool calls of the running turn that have not finished yet.
+    pub running_tools: Vec<RunningTool>,
+    /// Tool calls of the running turn already shown as result lines.
+    finished_tools: usize,
+    /// Cancels the running turn; `None` when idle or already cancelled.
+    pub turn_cancel: Option<rot_core::CancellationToken>,
+    /// Whether the running turn's checkpoints are the conversation itself;
+    /// RLM turns run the agent on conversations of their own.
+    pub save_checkpoints: bool,
+    /// Tokens and spend of this session by model and agent, shown by `/cost`.
+    pub usage: UsageLedger,
+    /// Calls per tool in this session, shown by `/cost`.
+    pub tool_stats: BTreeMap<String, ToolStats>,
+}
+
+/// Calls made to one tool.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolStats {
+    pub calls: usize,
+    pub errors: usize,
+    pub duration: Duration,
+    /// Estimated tokens of output fed back to the model.
+    pub output_tokens: usize,
+}
+
+/// Rendered chat lines selected in visual mode, by index into the
+/// rendered lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisualSelection {
+    pub anchor: usize,
+    pub cursor: usize,
+}
+
+impl VisualSelection {
+    fn range(&self) -> std::ops::RangeInclusive<usize> {
+        self.anchor.min(self.cursor)..=self.anchor.max(self.cursor)
+    }
+}
+
+/// Plain text and geometry of the chat area from the last render.
+#[derive(Debug, Default)]
+struct ChatView {
+    /// Text of each rendered line, before wrapping.
+    text: Vec<String>,
+    /// First rendered line of each chat line.
+    chat_starts: Vec<usize>,
+    /// First rendered line in view.
+    top_line: usize,
+    height: u16,
+}
+
+/// A tool call in progress, shown with a spinner and elapsed time.
+#[derive(Debug, Clone)]
+pub struct RunningTool {
+    pub call_id: String,
+    /// Tool name and key argument, as "bash: cargo test".
+    pub summary: String,
+    pub started: Instant,
+}
+
+/// A delegated `task` run shown in the tasks panel.
+#[derive(Debug, Clone)]
+pub struct TaskRun {
+    pub id: String,
+    pub agent: String,
+    pub prompt: String,
+    pub child_session_id: Option<String>,
+    pub started: Instant,
+    /// Run time once the task has finished.
+    pub finished: Option<Duration>,
+    /// Tool the subagent is running or ran last.
+    pub current_tool: Option<String>,
+    pub error: Option<String>,
+}
+
+impl TaskRun {
+    pub fn is_running(&self) -> bool {
+        self.finished.is_none()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(|| self.started.elapsed())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -159,6 +421,59 @@ pub struct ChatLine {
     pub role: String,
     pub content: String,
     pub style: ChatStyle,
+    /// Unified diff shown under a tool line.
+    pub diff: Option<String>,
+    /// Full tool arguments and output, shown when expanded.
+    pub detail: Option<String>,
+    /// Whether the block is expanded; toggled with Enter/Space in Normal mode.
+    pub expanded: bool,
+    /// When and how the message was produced, shown with `/meta`.
+    pub meta: Option<MessageMeta>,
+}
+
+/// Metadata of a prompt or reply, shown in dim text under it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageMeta {
+    /// Unix time the message was sent or received.
+    pub timestamp: u64,
+    /// Time the reply took.
+    pub duration: Option<Duration>,
+    /// Tokens used producing the reply, including subagent requests.
+    pub tokens: Option<usize>,
+    /// Model that wrote the reply.
+    pub model: Option<String>,
+}
+
+impl MessageMeta {
+    /// The metadata line, as "3m ago · 8.4s · 3.2k tok · gpt-4o".
+    fn summary(&self, now: u64) -> String {
+        let mut parts = vec![format_age(now, self.timestamp)];
+        if let Some(duration) = self.duration {
+            parts.push(format_duration_ms(duration.as_millis() as u64));
+        }
+        if let Some(tokens) = self.tokens {
+            parts.push(format!("{} tok", App::format_number(tokens)));
+        }
+        if let Some(model) = &self.model {
+            parts.push(model.clone());
+        }
+        parts.join(" · ")
+    }
+}
+
+/// The current Unix time in seconds.
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl ChatLine {
+    /// Whether the line has hidden detail that can be toggled.
+    pub fn is_expandable(&self) -> bool {
+        self.detail.is_some() || self.diff.as_deref().is_some_and(|d| !diff_fits(d))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -172,6 +487,212 @@ pub enum ChatStyle {
     Welcome,
 }
 
+/// Scrollback search over the rendered chat.
+#[derive(Debug, Clone, Default)]
+pub struct SearchState {
+    pub query: String,
+    /// Whether the query is still being typed.
+    pub editing: bool,
+    /// Matches from the last render as `(line, start, end)` byte ranges.
+    pub matches: Vec<(usize, usize, usize)>,
+    /// Index into `matches` of the selected match.
+    pub current: usize,
+    /// Scroll to the selected match on the next render.
+    jump: bool,
+    /// Select the first match at or below the viewport before jumping.
+    from_view: bool,
+}
+
+/// Ctrl-R reverse search over the prompt history.
+#[derive(Debug, Clone, Default)]
+pub struct HistorySearch {
+    pub query: String,
+    /// Matching history indices, best first.
+    pub matches: Vec<usize>,
+    pub selected: usize,
+}
+
+/// Case-insensitive byte ranges of `query` in `text`.
+fn find_matches(text: &str, query: &str) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    // ASCII lowercasing keeps byte offsets aligned with `text`.
+    let haystack = text.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, m)| (start, start + m.len()))
+        .collect()
+}
+
+/// Restyle the parts of `line` covered by `ranges`; `current` marks the selected one.
+fn highlight_line<'a>(
+    line: Line<'a>,
+    ranges: &[(usize, usize)],
+    current: Option<usize>,
+) -> Line<'a> {
+    let match_style = Style::default().fg(Color::Black).bg(COLOR_SYSTEM);
+    let current_style = Style::default().fg(Color::Black).bg(COLOR_ACCENT).bold();
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for span in line.spans {
+        let text = span.content.to_string();
+        let span_end = offset + text.len();
+        let mut pos = 0;
+        for (i, &(start, end)) in ranges.iter().enumerate() {
+            let start = start.clamp(offset, span_end) - offset;
+            let end = end.clamp(offset, span_end) - offset;
+            if start >= end || start < pos {
+                continue;
+            }
+            if start > pos {
+                spans.push(Span::styled(text[pos..start].to_string(), span.style));
+            }
+            let style = if current == Some(i) {
+                current_style
+            } else {
+                match_style
+            };
+            spans.push(Span::styled(text[start..end].to_string(), style));
+            pos = end;
+        }
+        if pos < text.len() {
+            spans.push(Span::styled(text[pos..].to_string(), span.style));
+        }
+        offset = span_end;
+    }
+    Line::from(spans).style(line.style)
+}
+
+/// Tokens and spend as "12.3k in · 2.1k out · $0.0612"; spend is left out
+/// when unknown.
+pub(crate) fn format_totals(totals: &UsageTotals) -> String {
+    let mut text = format!(
+        "{} in · {} out",
+        App::format_number(totals.input_tokens),
+        App::format_number(totals.output_tokens)
+    );
+    if let Some(cost) = totals.cost {
+        text.push_str(&format!(" · ${cost:.4}"));
+    }
+    text
+}
+
+/// How long before `now` the Unix time `then` was, as "5m ago".
+pub(crate) fn format_age(now: u64, then: u64) -> String {
+    let secs = now.saturating_sub(then);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+/// Whether a diff is short enough to show in a collapsed block.
+/// A context window as "200k" or "1M".
+fn format_context_window(tokens: usize) -> String {
+    if tokens >= 1_000_000 && tokens % 1_000_000 == 0 {
+        format!("{}M", tokens / 1_000_000)
+    } else if tokens >= 1_000 {
+        format!("{}k", tokens / 1_000)
+    } else {
+        tokens.to_string()
+    }
+}
+
+fn diff_fits(diff: &str) -> bool {
+    diff.lines().count() <= DIFF_EXPANDED_LINES
+}
+
+/// The most telling argument of a tool call, shortened for a summary line.
+pub(crate) fn key_argument(arguments: &serde_json::Value) -> Option<String> {
+    let map = arguments.as_object()?;
+    let value = KEY_ARGUMENTS
+        .iter()
+        .find_map(|key| map.get(*key).and_then(|v| v.as_str()))
+        .or_else(|| map.values().find_map(|v| v.as_str()))?;
+    let first_line = value.lines().next().unwrap_or("");
+    if first_line.chars().count() > 60 || value.contains('\n') {
+        Some(format!(
+            "{}…",
+            first_line.chars().take(60).collect::<String>()
+        ))
+    } else {
+        Some(first_line.to_string())
+    }
+}
+
+/// A task run time as "42s" or "3m07s".
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{secs}s")
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+fn format_duration_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
+
+/// Keep the first `max` lines of `text`, noting how many were dropped.
+fn truncate_lines(text: &str, max: usize) -> String {
+    let total = text.lines().count();
+    if total <= max {
+        return text.to_string();
+    }
+    let mut kept: Vec<&str> = text.lines().take(max).collect();
+    let note = format!("… ({} more lines)", total - max);
+    kept.push(&note);
+    kept.join("\n")
+}
+
+/// Added and removed line counts in a unified diff.
+fn diff_stats(diff: &str) -> (usize, usize) {
+    diff.lines().fold((0, 0), |(added, removed), line| {
+        if line.starts_with("+++") || line.starts_with("---") {
+            (added, removed)
+        } else if line.starts_with('+') {
+            (added + 1, removed)
+        } else if line.starts_with('-') {
+            (added, removed + 1)
+        } else {
+            (added, removed)
+        }
+    })
+}
+
+/// Color one line of a unified diff.
+fn diff_line(line: &str) -> Line<'_> {
+    let style = if line.starts_with("+++") || line.starts_with("---") {
+        Style::default().fg(COLOR_DIM).bold()
+    } else if line.starts_with("@@") {
+        Style::default().fg(COLOR_ACCENT)
+    } else if line.starts_with('+') {
+        Style::default().fg(COLOR_DIFF_ADD)
+    } else if line.starts_with('-') {
+        Style::default().fg(COLOR_ERROR)
+    } else {
+        Style::default().fg(COLOR_DIM)
+    };
+    Line::from(Span::styled(line, style))
+}
+
+/// Rows `line` occupies when wrapped to `width` columns.
+fn wrapped_rows(line: &Line<'_>, width: u16) -> usize {
+    if width == 0 {
+        return 1;
+    }
+    line.width().div_ceil(width as usize).max(1)
+}
+
 // ── App Implementation ─────────────────────────────────────────────────
 
 impl App {
@@ -184,6 +705,11 @@ impl App {
             cursor_pos: 0,
             chat_lines: Vec::new(),
             streaming_text: String::new(),
+            show_meta: false,
+            turn_tokens: 0,
+            thinking_text: String::new(),
+            show_thinking: true,
+            thinking_expanded: false,
             scroll_offset: 0,
             auto_scroll: true,
             status: "Ready".to_string(),
@@ -195,21 +721,66 @@ impl App {
             total_output_tokens: 0,
             last_input_tokens: 0,
             last_output_tokens: 0,
+            total_cost: None,
+            context_warned: false,
             response_start: None,
             last_elapsed: None,
             message_count: 0,
             showed_welcome: false,
             max_scroll: 0,
-            pending_approval_tool: None,
-            pending_approval_args: None,
-            pending_approval_tx: None,
+            input_width: 80,
+            approval: None,
+            approval_tx: None,
             rlm_enabled: true,
-            rlm_iterating: false,
+            rlm_context: None,
+            rlm_progress: None,
+            max_iterations: rot_core::DEFAULT_MAX_ITERATIONS,
+            iteration_progress: None,
+            queued_inputs: Arc::new(Mutex::new(VecDeque::new())),
+            working_dir: Arc::new(Mutex::new(std::env::current_dir().unwrap_or_default())),
+            workspace: std::env::current_dir().unwrap_or_default(),
+            editing_prompt: None,
+            model_aliases: HashMap::new(),
+            custom_providers: Vec::new(),
+            model_listings: HashMap::new(),
+            picker_provider: provider.to_string(),
+            models_request: None,
+            search: None,
+            workspace_files: None,
+            file_matches: Vec::new(),
+            file_menu_selected: 0,
+            selected_chat: None,
+            scroll_to_chat: None,
+            pending_key: None,
+            visual: None,
+            help: None,
+            pager: None,
+            security: rot_core::RuntimeSecurityConfig::default(),
+            security_confirm: None,
+            security_changed: false,
+            select_mode: false,
+            chat_view: ChatView::default(),
             config_ui_state: ConfigUiState::default(),
             config_changed: false,
             agent_changed: false,
             slash_menu_selected: 0,
             agent_menu_selected: 0,
+            pending_images: Vec::new(),
+            history: crate::history::PromptHistory::default(),
+            history_index: None,
+            history_search: None,
+            session_choices: Vec::new(),
+            session_menu_selected: 0,
+            tasks: Vec::new(),
+            show_tasks: false,
+            task_selected: None,
+            pending_child_view: None,
+            running_tools: Vec::new(),
+            finished_tools: 0,
+            turn_cancel: None,
+            save_checkpoints: false,
+            usage: UsageLedger::default(),
+            tool_stats: BTreeMap::new(),
         }
     }
 
@@ -265,210 +836,1657 @@ impl App {
             role: role.to_string(),
             content: content.to_string(),
             style,
+            diff: None,
+            detail: None,
+            expanded: false,
+            meta: matches!(style, ChatStyle::User).then(|| MessageMeta {
+                timestamp: unix_now(),
+                ..MessageMeta::default()
+            }),
         });
         self.auto_scroll = true;
     }
 
-    pub fn start_timer(&mut self) {
-        self.response_start = Some(Instant::now());
+    /// Handle a key while the help overlay is open. Returns whether it was.
+    pub fn handle_help_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        let Some(help) = &mut self.help else {
+            return false;
+        };
+        if !help.handle_key(key) {
+            self.help = None;
+        }
+        true
     }
 
-    pub fn stop_timer(&mut self) {
-        if let Some(start) = self.response_start.take() {
-            self.last_elapsed = Some(start.elapsed());
-        }
+    /// Show `text` in the full-screen pager.
+    pub fn open_pager(&mut self, title: impl Into<String>, text: &str) {
+        self.help = None;
+        self.pager = Some(Pager::new(title, text));
     }
 
-    pub fn record_tokens(&mut self, input: usize, output: usize) {
-        self.last_input_tokens = input;
-        self.last_output_tokens = output;
-        self.total_input_tokens += input;
-        self.total_output_tokens += output;
+    /// Handle a key while the pager is open. Returns whether it was.
+    pub fn handle_pager_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        let Some(pager) = &mut self.pager else {
+            return false;
+        };
+        if !pager.handle_key(key) {
+            self.pager = None;
+        }
+        true
     }
 
-    pub fn handle_slash_command(&mut self, cmd: &str) -> bool {
-        let parts: Vec<&str> = cmd.trim().splitn(2, ' ').collect();
-        match parts[0] {
-            "/help" => {
-                self.push_chat(
-                    "system",
-                    "/agents     — switch active agent\n\
-                     /children   — list delegated child runs\n\
-                     /child ID   — inspect one child session\n\
-                     /tools      — list loaded tools\n\
-                     /tool NAME  — inspect one tool\n\
-                     /tree       — show current session tree\n\
-                     /help       — show this message\n\
-                     /clear      — clear conversation\n\
-                     /model      — show current model\n\
-                     /model NAME — switch model\n\
-                     /rlm        — toggle RLM engine on/off\n\
-                     /quit       — exit rot",
-                    ChatStyle::System,
-                );
-                true
-            }
-            "/clear" => {
-                self.chat_lines.clear();
-                self.message_count = 0;
-                self.total_input_tokens = 0;
-                self.total_output_tokens = 0;
-                self.push_chat("system", "Conversation cleared.", ChatStyle::System);
-                true
-            }
-            "/agents" => {
-                self.state = AppState::Agents;
-                self.sync_agent_menu_selection();
-                true
-            }
-            "/children" | "/tree" | "/tools" => false,
-            _ if cmd.starts_with("/child ") || cmd.starts_with("/tool ") => false,
-            "/models" | "/model" => {
-                self.state = AppState::Config;
-                self.config_ui_state = ConfigUiState::List(0);
-                true
-            }
-            "/rlm" => {
-                self.rlm_enabled = !self.rlm_enabled;
-                let state_str = if self.rlm_enabled { "ON" } else { "OFF" };
-                self.push_chat(
-                    "system",
-                    &format!("RLM Engine is now {}", state_str),
-                    ChatStyle::System,
-                );
-                true
-            }
-            "/quit" | "/exit" => {
-                self.running = false;
-                true
-            }
-            _ if cmd.starts_with('/') => {
-                self.push_chat(
-                    "system",
-                    &format!("Unknown: {}. Try /help", parts[0]),
-                    ChatStyle::System,
-                );
-                true
-            }
-            _ => false,
+    /// Switch to `security`, for the runner to rebuild the tools and agent.
+    fn set_security(&mut self, security: rot_core::RuntimeSecurityConfig) {
+        let note = if security == self.security {
+            "Security settings unchanged.".to_string()
+        } else {
+            let later = if self.state == AppState::Idle {
+                ""
+            } else {
+                " Takes effect once the current reply finishes."
+            };
+            format!(
+                "Sandbox mode {}, approval policy {}.{later}",
+                security.sandbox_mode.as_str(),
+                security.approval_policy.as_str()
+            )
+        };
+        if security != self.security {
+            self.security = security;
+            self.security_changed = true;
         }
+        self.push_chat("system", &note, ChatStyle::System);
     }
 
-    pub fn submit_input(&mut self) -> String {
-        let text = self.input.clone();
-        self.input.clear();
-        self.cursor_pos = 0;
-        self.slash_menu_selected = 0;
-        text
+    /// Whether `/sandbox` or `/approval` changed the security settings since
+    /// the last call.
+    pub fn take_security_change(&mut self) -> bool {
+        std::mem::take(&mut self.security_changed)
     }
 
-    pub fn all_agents(&self) -> Vec<rot_core::AgentProfile> {
-        rot_core::AgentRegistry::builtins().to_vec()
+    /// Handle a key while a sandbox escalation waits for confirmation: `y`
+    /// applies it and any other key cancels. Returns whether one was waiting.
+    pub fn handle_security_confirm_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::KeyCode;
+
+        let Some(security) = self.security_confirm.take() else {
+            return false;
+        };
+        if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+            self.set_security(security);
+        } else {
+            self.push_chat("system", "Sandbox mode unchanged.", ChatStyle::System);
+        }
+        true
     }
 
-    pub fn sync_agent_menu_selection(&mut self) {
-        let agents = self.all_agents();
-        self.agent_menu_selected = agents
-            .iter()
-            .position(|profile| profile.name == self.agent)
-            .unwrap_or(self.agent_menu_selected.min(agents.len().saturating_sub(1)));
+    /// Enter or leave select mode. The view stays put while selecting, so
+    /// streamed output does not move text out from under the selection.
+    pub fn toggle_select_mode(&mut self) {
+        self.select_mode = !self.select_mode;
+        self.auto_scroll = !self.select_mode && self.scroll_offset >= self.max_scroll;
     }
 
-    pub fn move_agent_selection_up(&mut self) {
-        let count = self.all_agents().len();
-        if count == 0 {
-            return;
+    /// Handle a key for select mode. Ctrl+S toggles it from anywhere; while
+    /// it is on, `Esc` leaves it, arrows (which many terminals send for the
+    /// mouse wheel) scroll the chat, and other keys are ignored.
+    pub fn handle_select_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        if key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.toggle_select_mode();
+            return true;
         }
-        if self.agent_menu_selected == 0 {
-            self.agent_menu_selected = count - 1;
-        } else {
-            self.agent_menu_selected -= 1;
+        if !self.select_mode {
+            return false;
+        }
+        match key.code {
+            KeyCode::Esc => self.toggle_select_mode(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.scroll_offset = self.scroll_offset.saturating_add(1).min(self.max_scroll);
+            }
+            _ => {}
         }
+        true
     }
 
-    pub fn move_agent_selection_down(&mut self) {
-        let count = self.all_agents().len();
-        if count == 0 {
-            return;
+    /// Add the reply that ends a turn, with its duration, tokens, and model.
+    pub fn push_reply(&mut self, text: &str) {
+        self.push_chat("rot", text, ChatStyle::Assistant);
+        let meta = MessageMeta {
+            timestamp: unix_now(),
+            duration: self.response_start.map(|start| start.elapsed()),
+            tokens: Some(self.turn_tokens),
+            model: Some(self.model.clone()),
+        };
+        if let Some(line) = self.chat_lines.last_mut() {
+            line.meta = Some(meta);
         }
-        self.agent_menu_selected = (self.agent_menu_selected + 1) % count;
     }
 
-    pub fn select_current_agent(&mut self) -> bool {
-        let agents = self.all_agents();
-        let Some(profile) = agents.get(self.agent_menu_selected).copied() else {
-            return false;
-        };
+    /// Set when the last chat line was sent, for transcripts loaded from disk.
+    fn stamp_last(&mut self, timestamp: u64) {
+        if let Some(line) = self.chat_lines.last_mut() {
+            line.meta = Some(MessageMeta {
+                timestamp,
+                ..MessageMeta::default()
+            });
+        }
+    }
 
-        let changed = self.agent != profile.name;
-        self.agent = profile.name.to_string();
-        self.state = AppState::Idle;
-        self.agent_changed = changed;
-        changed
+    /// Add streamed reasoning to the live thinking region.
+    pub fn push_thinking(&mut self, delta: &str) {
+        if self.show_thinking {
+            self.thinking_text.push_str(delta);
+            self.auto_scroll = true;
+        }
     }
 
-    pub fn parse_agent_mention(input: &str) -> Option<(String, String)> {
-        let trimmed = input.trim();
-        let rest = trimmed.strip_prefix('@')?;
-        let (name, prompt) = rest.split_once(char::is_whitespace)?;
-        let profile = rot_core::AgentRegistry::get(name)?;
-        let prompt = prompt.trim();
-        if prompt.is_empty() {
-            return None;
+    /// Add streamed reply text to the live region below the chat, which
+    /// follows it as it grows unless the user has scrolled up.
+    pub fn push_streaming_text(&mut self, delta: &str) {
+        if self.streaming_text.is_empty() {
+            self.flush_thinking();
+        }
+        self.streaming_text.push_str(delta);
+        if self.state == AppState::Thinking {
+            self.state = AppState::Streaming;
         }
-        Some((profile.name.to_string(), prompt.to_string()))
     }
 
-    pub fn insert_char(&mut self, c: char) {
-        self.input.insert(self.cursor_pos, c);
-        self.cursor_pos += c.len_utf8();
-        self.sync_slash_menu_selection();
+    /// Move text streamed before a tool call into the chat, so it stays
+    /// above the calls it introduced.
+    fn flush_streaming_text(&mut self) {
+        let text = std::mem::take(&mut self.streaming_text);
+        if !text.trim().is_empty() {
+            self.push_chat("rot", text.trim(), ChatStyle::Assistant);
+        }
+        if self.state == AppState::Streaming {
+            self.state = AppState::Thinking;
+        }
     }
 
-    pub fn insert_newline(&mut self) {
-        self.input.insert(self.cursor_pos, '\n');
-        self.cursor_pos += 1;
-        self.sync_slash_menu_selection();
+    /// Move the live reasoning into the chat as a collapsible block, so it
+    /// stays above the tool calls and reply that follow it.
+    pub fn flush_thinking(&mut self) {
+        let thinking = std::mem::take(&mut self.thinking_text);
+        let thinking = thinking.trim();
+        if thinking.is_empty() {
+            return;
+        }
+        self.chat_lines.push(ChatLine {
+            role: "thinking".to_string(),
+            content: format!("✻ Thought · {} lines", thinking.lines().count()),
+            style: ChatStyle::Thinking,
+            diff: None,
+            detail: Some(thinking.to_string()),
+            expanded: self.thinking_expanded,
+            meta: None,
+        });
     }
 
-    pub fn handle_config_key(
-        &mut self,
-        key_code: crossterm::event::KeyCode,
-        config_store: &rot_core::config::ConfigStore,
-    ) {
-        match &mut self.config_ui_state {
-            ConfigUiState::List(idx) => match key_code {
-                crossterm::event::KeyCode::Up => *idx = idx.saturating_sub(1),
-                crossterm::event::KeyCode::Down => {
-                    *idx = (*idx + 1).min(AVAILABLE_MODELS.len().saturating_sub(1))
-                }
-                crossterm::event::KeyCode::Enter => {
-                    let (provider, model) = AVAILABLE_MODELS[*idx];
-                    let mut config = config_store.load();
+    /// Handle Ctrl+O, which expands or collapses the live thinking region.
+    /// Returns whether the key was used.
+    pub fn handle_thinking_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
 
-                    let has_key = config.api_keys.get(provider).map(|s| !s.is_empty()).unwrap_or(false)
-                        || std::env::var(format!("{}_API_KEY", provider.to_uppercase())).is_ok();
+        if key.code == KeyCode::Char('o') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.thinking_expanded = !self.thinking_expanded;
+            true
+        } else {
+            false
+        }
+    }
 
-                    if has_key {
-                        config.provider = provider.to_string();
-                        config.model = model.to_string();
-                        let _ = config_store.save(&config);
-                        self.provider = provider.to_string();
-                        self.model = model.to_string();
-                        self.state = AppState::Idle;
-                        self.config_changed = true;
-                    } else {
-                        self.config_ui_state = ConfigUiState::InputKey {
-                            provider: provider.to_string(),
-                            model: model.to_string(),
-                            input: String::new(),
-                            cursor_pos: 0,
-                        };
+    /// Show a finished tool call as a one-line summary: name, key argument,
+    /// duration, and status. Arguments, output, and any diff are kept for
+    /// expanding the block.
+    pub fn push_tool_exchange(&mut self, exchange: &rot_core::message::ToolExchange) {
+        let mut summary = format!("↳ {}", exchange.name);
+        if let Some(arg) = key_argument(&exchange.arguments) {
+            summary.push_str(&format!(" {arg}"));
+        }
+        let diff = exchange
+            .metadata
+            .get("diff")
+            .and_then(|d| d.as_str())
+            .filter(|d| !d.ends_with("(no changes)"));
+        if let Some(diff) = diff {
+            let (added, removed) = diff_stats(diff);
+            summary.push_str(&format!(" (+{added} -{removed})"));
+        }
+        if let Some(ms) = exchange
+            .metadata
+            .get("duration_ms")
+            .and_then(|d| d.as_u64())
+        {
+            summary.push_str(&format!(" · {}", format_duration_ms(ms)));
+        }
+        let detail = exchange.output.as_ref().map(|output| {
+            let args = serde_json::to_string_pretty(&exchange.arguments).unwrap_or_default();
+            format!(
+                "args: {}\noutput:\n{}",
+                truncate_lines(&args, DETAIL_MAX_LINES),
+                truncate_lines(output, DETAIL_MAX_LINES)
+            )
+        });
+        if detail.is_some() {
+            summary.push_str(if exchange.is_error {
+                " · error"
+            } else {
+                " · ok"
+            });
+        }
+
+        let style = if exchange.is_error {
+            ChatStyle::Error
+        } else {
+            ChatStyle::Tool
+        };
+        self.push_chat("tool", &summary, style);
+        if let Some(line) = self.chat_lines.last_mut() {
+            line.diff = diff.map(str::to_string);
+            line.detail = detail;
+        }
+    }
+
+    /// Handle a Normal-mode key for expandable blocks. Returns whether it was used.
+    ///
+    /// `Tab`/`Shift+Tab` select the next or previous block and `Enter`/`Space`
+    /// toggle the selected one, or the latest block when none is selected.
+    pub fn handle_block_key(&mut self, key_code: crossterm::event::KeyCode) -> bool {
+        use crossterm::event::KeyCode;
+
+        let expandable: Vec<usize> = self
+            .chat_lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.is_expandable())
+            .map(|(idx, _)| idx)
+            .collect();
+        let Some(&last) = expandable.last() else {
+            return false;
+        };
+        let selected = self.selected_chat.filter(|idx| expandable.contains(idx));
+
+        match key_code {
+            KeyCode::Tab => {
+                let next = selected
+                    .and_then(|sel| expandable.iter().find(|&&idx| idx > sel))
+                    .unwrap_or(&expandable[0]);
+                self.select_chat(*next);
+            }
+            KeyCode::BackTab => {
+                let prev = selected
+                    .and_then(|sel| expandable.iter().rev().find(|&&idx| idx < sel))
+                    .unwrap_or(&last);
+                self.select_chat(*prev);
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let idx = selected.unwrap_or(last);
+                self.chat_lines[idx].expanded = !self.chat_lines[idx].expanded;
+                self.select_chat(idx);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Handle a Normal-mode copy key. Returns whether it was used.
+    ///
+    /// `[`/`]` select the previous or next message, `y` copies the selected
+    /// message (or the latest reply) and `Y` the last code block in it.
+    pub fn handle_copy_key(&mut self, key_code: crossterm::event::KeyCode) -> bool {
+        use crossterm::event::KeyCode;
+
+        let messages: Vec<usize> = self
+            .chat_lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line.style, ChatStyle::Welcome))
+            .map(|(idx, _)| idx)
+            .collect();
+        let Some(&last) = messages.last() else {
+            return false;
+        };
+        let selected = self.selected_chat.filter(|idx| messages.contains(idx));
+
+        match key_code {
+            KeyCode::Char(']') => {
+                let next = selected
+                    .and_then(|sel| messages.iter().find(|&&idx| idx > sel))
+                    .unwrap_or(&messages[0]);
+                self.select_chat(*next);
+            }
+            KeyCode::Char('[') => {
+                let prev = selected
+                    .and_then(|sel| messages.iter().rev().find(|&&idx| idx < sel))
+                    .unwrap_or(&last);
+                self.select_chat(*prev);
+            }
+            KeyCode::Char('y') => {
+                let idx = selected.or_else(|| self.latest_reply()).unwrap_or(last);
+                let text = self.chat_lines[idx].content.clone();
+                self.copy_to_clipboard(&text, "message");
+            }
+            KeyCode::Char('Y') => {
+                let candidates: Vec<usize> = match selected {
+                    Some(idx) => vec![idx],
+                    None => messages.iter().rev().copied().collect(),
+                };
+                let block = candidates.into_iter().find_map(|idx| {
+                    crate::markdown::code_blocks(&self.chat_lines[idx].content).pop()
+                });
+                match block {
+                    Some(block) => self.copy_to_clipboard(&block, "code block"),
+                    None => self.status = "No code block to copy".to_string(),
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Handle a Vim key in Normal mode: `w`/`b`/`0`/`$` and `dd`/`cc`/`yy`
+    /// on the input, `gg` and `Ctrl+U`/`Ctrl+D` on the chat, and `v` for
+    /// visual selection. Returns whether the key was used.
+    pub fn handle_vim_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crate::editor;
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let pending = self.pending_key.take();
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            let half = (self.chat_view.height / 2).max(1) as isize;
+            match key.code {
+                KeyCode::Char('u') => self.scroll_chat(-half),
+                KeyCode::Char('d') => self.scroll_chat(half),
+                _ => return false,
+            }
+            return true;
+        }
+        if self.visual.is_some() {
+            self.handle_visual_key(key.code, pending);
+            return true;
+        }
+
+        let cursor = self.cursor_pos;
+        match (pending, key.code) {
+            (Some('g'), KeyCode::Char('g')) => {
+                self.auto_scroll = false;
+                self.scroll_offset = 0;
+            }
+            (Some('d'), KeyCode::Char('d')) => self.delete_input_line(false),
+            (Some('c'), KeyCode::Char('c')) => self.delete_input_line(true),
+            (Some('y'), KeyCode::Char('y')) if !self.input.is_empty() => {
+                let line = editor::line_bounds(&self.input, cursor);
+                let text = self.input[line.start..line.end].to_string();
+                self.copy_to_clipboard(&text, "line");
+            }
+            // A single `y` still copies a message through `handle_copy_key`.
+            (_, KeyCode::Char('y')) => {
+                self.pending_key = Some('y');
+                return false;
+            }
+            (_, KeyCode::Char(c @ ('g' | 'd' | 'c'))) => self.pending_key = Some(c),
+            (_, KeyCode::Char('w')) => {
+                self.cursor_pos = editor::next_word_start(&self.input, cursor)
+            }
+            (_, KeyCode::Char('b')) => {
+                self.cursor_pos = editor::prev_word_start(&self.input, cursor)
+            }
+            (_, KeyCode::Char('0')) => {
+                self.cursor_pos = editor::line_bounds(&self.input, cursor).start
+            }
+            (_, KeyCode::Char('$')) => {
+                self.cursor_pos = editor::line_bounds(&self.input, cursor).end
+            }
+            (_, KeyCode::Char('v')) => self.start_visual(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_visual_key(&mut self, code: crossterm::event::KeyCode, pending: Option<char>) {
+        use crossterm::event::KeyCode;
+
+        let last = self.chat_view.text.len().saturating_sub(1);
+        let Some(visual) = self.visual.as_mut() else {
+            return;
+        };
+        match (pending, code) {
+            (_, KeyCode::Char('j') | KeyCode::Down) => {
+                visual.cursor = (visual.cursor + 1).min(last)
+            }
+            (_, KeyCode::Char('k') | KeyCode::Up) => {
+                visual.cursor = visual.cursor.saturating_sub(1)
+            }
+            (Some('g'), KeyCode::Char('g')) => visual.cursor = 0,
+            (_, KeyCode::Char('g')) => self.pending_key = Some('g'),
+            (_, KeyCode::Char('G')) => visual.cursor = last,
+            (_, KeyCode::Char('y')) => {
+                let range = visual.range();
+                let text = self.chat_view.text[range]
+                    .iter()
+                    .map(|line| line.strip_prefix(" ▌ ").unwrap_or(line).trim_end())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.visual = None;
+                self.copy_to_clipboard(&text, "selection");
+            }
+            (_, KeyCode::Esc | KeyCode::Char('v')) => {
+                self.visual = None;
+                self.status = "Ready".to_string();
+            }
+            _ => {}
+        }
+    }
+
+    /// Start a visual selection at the selected message, the latest reply
+    /// when following the chat, or the top of the view.
+    fn start_visual(&mut self) {
+        if self.chat_view.text.is_empty() {
+            return;
+        }
+        let anchor_chat = self
+            .selected_chat
+            .or_else(|| self.latest_reply().filter(|_| self.auto_scroll));
+        let start = anchor_chat
+            .and_then(|idx| self.chat_view.chat_starts.get(idx).copied())
+            .unwrap_or(self.chat_view.top_line);
+        self.visual = Some(VisualSelection {
+            anchor: start,
+            cursor: start,
+        });
+        self.status = "Visual: j/k extend, y yank, Esc cancel".to_string();
+    }
+
+    /// Scroll the chat by `delta` rows, or move the visual cursor by that many lines.
+    fn scroll_chat(&mut self, delta: isize) {
+        if let Some(visual) = self.visual.as_mut() {
+            let last = self.chat_view.text.len().saturating_sub(1);
+            visual.cursor = visual.cursor.saturating_add_signed(delta).min(last);
+            return;
+        }
+        let step = delta.unsigned_abs() as u16;
+        if delta < 0 {
+            self.auto_scroll = false;
+            self.scroll_offset = self.scroll_offset.saturating_sub(step);
+        } else {
+            self.scroll_offset = self.scroll_offset.saturating_add(step).min(self.max_scroll);
+        }
+    }
+
+    /// `dd` removes the input line under the cursor; `cc` empties it and
+    /// switches to Insert mode.
+    fn delete_input_line(&mut self, change: bool) {
+        let line = crate::editor::line_bounds(&self.input, self.cursor_pos);
+        let range = if change {
+            line.start..line.end
+        } else if line.end < self.input.len() {
+            line.start..line.end + 1
+        } else {
+            line.start.saturating_sub(1)..line.end
+        };
+        self.input.replace_range(range.clone(), "");
+        self.history_index = None;
+        self.cursor_pos = if change {
+            self.input_mode = InputMode::Insert;
+            range.start
+        } else {
+            crate::editor::line_bounds(&self.input, range.start.min(self.input.len())).start
+        };
+        self.sync_slash_menu_selection();
+        self.sync_file_menu();
+    }
+
+    /// Index of the most recent assistant message.
+    fn latest_reply(&self) -> Option<usize> {
+        self.chat_lines
+            .iter()
+            .rposition(|line| matches!(line.style, ChatStyle::Assistant))
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str, what: &str) {
+        self.status = match crate::clipboard::copy(text) {
+            Ok(()) => format!("Copied {what} ({} chars)", text.chars().count()),
+            Err(e) => format!("Copy failed: {e}"),
+        };
+    }
+
+    fn select_chat(&mut self, idx: usize) {
+        self.selected_chat = Some(idx);
+        self.scroll_to_chat = Some(idx);
+    }
+
+    pub fn start_timer(&mut self) {
+        self.response_start = Some(Instant::now());
+        self.turn_tokens = 0;
+    }
+
+    pub fn stop_timer(&mut self) {
+        if let Some(start) = self.response_start.take() {
+            self.last_elapsed = Some(start.elapsed());
+        }
+    }
+
+    /// Agent a usage report is attributed to: the subagent of its task, or
+    /// the active agent.
+    pub fn usage_agent(&self, task_id: Option<&str>) -> String {
+        task_id
+            .and_then(|id| self.tasks.iter().find(|task| task.id == id))
+            .map_or_else(|| self.agent.clone(), |task| task.agent.clone())
+    }
+
+    /// Add a usage report to the session ledger and footer totals. Only the
+    /// top-level agent's requests count towards context use.
+    pub fn record_usage(&mut self, report: &rot_core::UsageReport) {
+        let agent = self.usage_agent(report.task_id.as_deref());
+        self.usage.record(
+            &report.model,
+            &agent,
+            report.input_tokens,
+            report.output_tokens,
+        );
+        self.turn_tokens += report.input_tokens + report.output_tokens;
+        if report.task_id.is_none() {
+            self.record_tokens(report.input_tokens, report.output_tokens);
+        } else {
+            self.add_totals(&report.model, report.input_tokens, report.output_tokens);
+        }
+    }
+
+    fn add_totals(&mut self, model: &str, input: usize, output: usize) {
+        self.total_input_tokens += input;
+        self.total_output_tokens += output;
+        if let Some(pricing) = rot_provider::pricing_for(model) {
+            *self.total_cost.get_or_insert(0.0) += pricing.cost(input, output);
+        }
+    }
+
+    /// Add provider-reported usage. A report with input tokens starts a new
+    /// request; output-only reports extend the current one.
+    pub fn record_tokens(&mut self, input: usize, output: usize) {
+        if input > 0 {
+            self.last_input_tokens = input;
+            self.last_output_tokens = output;
+        } else {
+            self.last_output_tokens += output;
+        }
+        let model = self.model.clone();
+        self.add_totals(&model, input, output);
+
+        let context_pct = self.context_percent();
+        if context_pct < CONTEXT_WARNING_PERCENT {
+            self.context_warned = false;
+        } else if !self.context_warned {
+            self.context_warned = true;
+            self.push_chat(
+                "system",
+                &format!(
+                    "Context is {context_pct:.0}% full. Run /compact to summarize older turns."
+                ),
+                ChatStyle::System,
+            );
+        }
+    }
+
+    /// Report a finished `/compact` and estimate the context it left.
+    pub fn record_compaction(&mut self, compaction: Option<&rot_core::compaction::Compaction>) {
+        let Some(compaction) = compaction else {
+            self.push_chat("system", "Nothing to compact yet.", ChatStyle::System);
+            return;
+        };
+        self.push_chat(
+            "system",
+            &format!(
+                "Compacted {} message(s) into a summary, reclaiming about {} tokens (~{} → ~{}).",
+                compaction.removed_messages,
+                Self::format_number(compaction.reclaimed()),
+                Self::format_number(compaction.tokens_before),
+                Self::format_number(compaction.tokens_after),
+            ),
+            ChatStyle::System,
+        );
+        self.last_input_tokens = compaction.tokens_after;
+        self.last_output_tokens = 0;
+        self.context_warned = false;
+    }
+
+    fn reset_usage(&mut self) {
+        self.total_input_tokens = 0;
+        self.total_output_tokens = 0;
+        self.last_input_tokens = 0;
+        self.last_output_tokens = 0;
+        self.total_cost = None;
+        self.usage = UsageLedger::default();
+        self.tool_stats.clear();
+    }
+
+    /// Restore a resumed session's usage so `/cost` and the footer totals
+    /// include its earlier requests.
+    pub fn restore_usage(&mut self, usage: UsageLedger) {
+        self.total_input_tokens = usage.total.input_tokens;
+        self.total_output_tokens = usage.total.output_tokens;
+        self.total_cost = usage.total.cost;
+        self.usage = usage;
+    }
+
+    /// Body of `/cost`: this session's usage by model, agent, and tool.
+    pub fn cost_report(&self) -> String {
+        if self.usage.is_empty() && self.tool_stats.is_empty() {
+            return "No usage recorded in this session yet.".to_string();
+        }
+        let mut lines = vec![
+            format!(
+                "Session usage: {} over {} request(s)",
+                format_totals(&self.usage.total),
+                self.usage.total.requests
+            ),
+            String::new(),
+            "By model:".to_string(),
+        ];
+        for (model, totals) in &self.usage.by_model {
+            lines.push(format!("  {model}  {}", format_totals(totals)));
+        }
+        lines.push(String::new());
+        lines.push("By agent:".to_string());
+        for (agent, totals) in &self.usage.by_agent {
+            lines.push(format!("  @{agent}  {}", format_totals(totals)));
+        }
+        if !self.tool_stats.is_empty() {
+            lines.push(String::new());
+            lines.push("By tool:".to_string());
+            for (name, stats) in &self.tool_stats {
+                let failed = match stats.errors {
+                    0 => String::new(),
+                    n => format!(", {n} failed"),
+                };
+                lines.push(format!(
+                    "  {name}  {} call(s){failed} · {} · ~{} tokens of output",
+                    stats.calls,
+                    format_elapsed(stats.duration),
+                    Self::format_number(stats.output_tokens),
+                ));
+            }
+        }
+        if self.usage.total.cost.is_none() && !self.usage.is_empty() {
+            lines.push(String::new());
+            lines.push("Spend is unknown: no built-in price for the models used.".to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// Track agent loop progress, warning once when the iteration limit gets close.
+    pub fn record_iteration(&mut self, progress: rot_core::IterationProgress) {
+        let was_near_limit = self
+            .iteration_progress
+            .is_some_and(|previous| previous.is_near_limit());
+        if progress.is_near_limit() && !was_near_limit && progress.max_iterations > 1 {
+            self.push_chat(
+                "system",
+                &format!(
+                    "Approaching iteration limit: step {}/{} ({} left)",
+                    progress.iteration,
+                    progress.max_iterations,
+                    progress.remaining()
+                ),
+                ChatStyle::System,
+            );
+        }
+        self.iteration_progress = Some(progress);
+    }
+
+    /// Update the tasks panel from a delegated task event.
+    ///
+    /// The panel opens by itself when the first task of the session starts.
+    pub fn record_task_event(&mut self, event: rot_core::TaskEvent) {
+        use rot_core::TaskEvent;
+
+        match event {
+            TaskEvent::Started {
+                task_id,
+                agent,
+                prompt,
+                child_session_id,
+            } => {
+                if self.tasks.is_empty() {
+                    self.show_tasks = true;
+                }
+                self.tasks.push(TaskRun {
+                    id: task_id,
+                    agent,
+                    prompt,
+                    child_session_id,
+                    started: Instant::now(),
+                    finished: None,
+                    current_tool: None,
+                    error: None,
+                });
+            }
+            TaskEvent::ToolStarted { task_id, tool } => {
+                if let Some(task) = self.tasks.iter_mut().find(|task| task.id == task_id) {
+                    task.current_tool = Some(tool);
+                }
+            }
+            TaskEvent::Finished { task_id, error } => {
+                if let Some(task) = self.tasks.iter_mut().find(|task| task.id == task_id) {
+                    task.finished = Some(task.started.elapsed());
+                    task.error = error;
+                }
+            }
+        }
+    }
+
+    /// Track a tool call: show a progress line while it runs and replace it
+    /// with the result line when it finishes.
+    pub fn record_tool_event(&mut self, event: rot_core::ToolEvent) {
+        use rot_core::ToolEvent;
+
+        match event {
+            ToolEvent::Started {
+                call_id,
+                name,
+                arguments,
+            } => {
+                let summary = match key_argument(&arguments) {
+                    Some(arg) => format!("{name}: {arg}"),
+                    None => name,
+                };
+                self.flush_thinking();
+                self.flush_streaming_text();
+                self.running_tools.push(RunningTool {
+                    call_id,
+                    summary,
+                    started: Instant::now(),
+                });
+                self.auto_scroll = true;
+            }
+            ToolEvent::Finished { call_id, exchange } => {
+                let duration = self
+                    .running_tools
+                    .iter()
+                    .find(|tool| tool.call_id == call_id)
+                    .map(|tool| tool.started.elapsed())
+                    .unwrap_or_default();
+                let stats = self.tool_stats.entry(exchange.name.clone()).or_default();
+                stats.calls += 1;
+                stats.errors += usize::from(exchange.is_error);
+                stats.duration += duration;
+                stats.output_tokens += exchange
+                    .output
+                    .as_ref()
+                    .map_or(0, |output| output.len() / 4);
+                self.running_tools.retain(|tool| tool.call_id != call_id);
+                self.push_tool_exchange(&exchange);
+                self.finished_tools += 1;
+            }
+        }
+    }
+
+    /// Clear tool progress at the end of a turn, returning how many calls
+    /// were already shown as they finished.
+    pub fn end_tool_progress(&mut self) -> usize {
+        self.running_tools.clear();
+        std::mem::take(&mut self.finished_tools)
+    }
+
+    /// Ask the running turn to stop. Returns false when there is nothing to cancel.
+    ///
+    /// The app returns to Idle when the background task reports back.
+    pub fn cancel_turn(&mut self) -> bool {
+        let Some(cancel) = self.turn_cancel.take() else {
+            return false;
+        };
+        cancel.cancel();
+        self.status = "Cancelling…".to_string();
+        true
+    }
+
+    /// Return to Idle after a turn failed or was cancelled. Reply text that
+    /// had streamed stays in the chat. Follow-ups queued during the run go
+    /// back to the input box, introduced by `reason`.
+    pub fn end_interrupted_turn(&mut self, reason: &str) {
+        self.end_tool_progress();
+        self.stop_timer();
+        self.flush_thinking();
+        self.flush_streaming_text();
+        self.turn_cancel = None;
+        self.approval = None;
+        self.approval_tx = None;
+        self.state = AppState::Idle;
+        self.rlm_progress = None;
+        self.iteration_progress = None;
+        self.status = "Ready".to_string();
+
+        let pending = self.take_queued_inputs();
+        if !pending.is_empty() {
+            let mut restored = pending.join("\n");
+            if !self.input.is_empty() {
+                restored.push('\n');
+                restored.push_str(&self.input);
+            }
+            self.input = restored;
+            self.cursor_pos = self.input.len();
+            self.push_chat(
+                "system",
+                &format!(
+                    "{reason}; {} queued message(s) moved back to the input box.",
+                    pending.len()
+                ),
+                ChatStyle::System,
+            );
+        }
+    }
+
+    /// Show or hide the tasks panel.
+    pub fn toggle_tasks(&mut self) {
+        self.show_tasks = !self.show_tasks;
+        if !self.show_tasks {
+            self.task_selected = None;
+        }
+        self.status = if self.show_tasks && self.tasks.is_empty() {
+            "No delegated tasks yet".to_string()
+        } else {
+            String::new()
+        };
+    }
+
+    /// Handle a tasks panel key. Returns whether it was used.
+    ///
+    /// Ctrl+T toggles the panel in either mode. While it is shown in Normal
+    /// mode, `J`/`K` select a task, Enter opens its child transcript, and
+    /// Esc drops the selection.
+    pub fn handle_task_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        if key.code == KeyCode::Char('t') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.toggle_tasks();
+            return true;
+        }
+        let typing_search = self.search.as_ref().is_some_and(|search| search.editing);
+        if !self.show_tasks
+            || self.input_mode != InputMode::Normal
+            || typing_search
+            || self.tasks.is_empty()
+        {
+            return false;
+        }
+
+        let last = self.tasks.len() - 1;
+        match key.code {
+            KeyCode::Char('J') => {
+                self.task_selected = Some(self.task_selected.map_or(0, |idx| (idx + 1).min(last)));
+            }
+            KeyCode::Char('K') => {
+                self.task_selected =
+                    Some(self.task_selected.map_or(last, |idx| idx.saturating_sub(1)));
+            }
+            KeyCode::Enter => {
+                let Some(task) = self.task_selected.and_then(|idx| self.tasks.get(idx)) else {
+                    return false;
+                };
+                match &task.child_session_id {
+                    Some(id) => self.pending_child_view = Some(id.clone()),
+                    None => self.status = "This task has no saved transcript".to_string(),
+                }
+            }
+            KeyCode::Esc if self.task_selected.is_some() => self.task_selected = None,
+            _ => return false,
+        }
+        true
+    }
+
+    /// New value of a setting for `on`, `off`, or no argument (which flips
+    /// `current`). Reports other arguments and returns `None`.
+    fn toggle_argument(
+        &mut self,
+        command: &str,
+        arg: Option<&&str>,
+        current: bool,
+    ) -> Option<bool> {
+        match arg.map(|arg| arg.trim()) {
+            Some("on") => Some(true),
+            Some("off") => Some(false),
+            None | Some("") => Some(!current),
+            Some(other) => {
+                self.push_chat(
+                    "error",
+                    &format!("Unknown {command} option '{other}'; use on or off."),
+                    ChatStyle::Error,
+                );
+                None
+            }
+        }
+    }
+
+    pub fn handle_slash_command(&mut self, cmd: &str) -> bool {
+        let parts: Vec<&str> = cmd.trim().splitn(2, ' ').collect();
+        match parts[0] {
+            "/help" => {
+                self.help = Some(HelpOverlay::default());
+                true
+            }
+            "/select" => {
+                self.toggle_select_mode();
+                true
+            }
+            "/sandbox" => {
+                use rot_core::SandboxMode;
+
+                let arg = parts.get(1).map(|arg| arg.trim()).unwrap_or_default();
+                if arg.is_empty() {
+                    let network = if self.security.sandbox_network_access {
+                        "on"
+                    } else {
+                        "off"
+                    };
+                    self.push_chat(
+                        "system",
+                        &format!(
+                            "Sandbox mode: {} (network access {network}).\n\
+                             Use /sandbox read-only|workspace-write|danger-full-access to change it.",
+                            self.security.sandbox_mode.as_str()
+                        ),
+                        ChatStyle::System,
+                    );
+                    return true;
+                }
+                let Some(mode) = SandboxMode::from_name(arg) else {
+                    self.push_chat(
+                        "error",
+                        &format!(
+                            "Unknown sandbox mode '{arg}'; use read-only, workspace-write, or danger-full-access."
+                        ),
+                        ChatStyle::Error,
+                    );
+                    return true;
+                };
+                let security = rot_core::RuntimeSecurityConfig {
+                    sandbox_mode: mode,
+                    ..self.security.clone()
+                };
+                if mode == SandboxMode::DangerFullAccess && self.security.sandbox_mode != mode {
+                    self.security_confirm = Some(security);
+                } else {
+                    self.set_security(security);
+                }
+                true
+            }
+            "/approval" => {
+                let arg = parts.get(1).map(|arg| arg.trim()).unwrap_or_default();
+                if arg.is_empty() {
+                    self.push_chat(
+                        "system",
+                        &format!(
+                            "Approval policy: {}.\n\
+                             Use /approval untrusted|on-request|never to change it.",
+                            self.security.approval_policy.as_str()
+                        ),
+                        ChatStyle::System,
+                    );
+                    return true;
+                }
+                let Some(policy) = rot_core::ApprovalPolicy::from_name(arg) else {
+                    self.push_chat(
+                        "error",
+                        &format!(
+                            "Unknown approval policy '{arg}'; use untrusted, on-request, or never."
+                        ),
+                        ChatStyle::Error,
+                    );
+                    return true;
+                };
+                self.set_security(rot_core::RuntimeSecurityConfig {
+                    approval_policy: policy,
+                    ..self.security.clone()
+                });
+                true
+            }
+            "/tasks" => {
+                self.toggle_tasks();
+                true
+            }
+            "/thinking" => {
+                let Some(show) =
+                    self.toggle_argument("/thinking", parts.get(1), self.show_thinking)
+                else {
+                    return true;
+                };
+                self.show_thinking = show;
+                if !self.show_thinking {
+                    self.thinking_text.clear();
+                }
+                let note = if self.show_thinking {
+                    "Model reasoning is shown as it streams."
+                } else {
+                    "Model reasoning is hidden."
+                };
+                self.push_chat("system", note, ChatStyle::System);
+                true
+            }
+            "/meta" => {
+                let Some(show) = self.toggle_argument("/meta", parts.get(1), self.show_meta) else {
+                    return true;
+                };
+                self.show_meta = show;
+                let note = if show {
+                    "Showing time, duration, tokens, and model under messages."
+                } else {
+                    "Message metadata is hidden."
+                };
+                self.push_chat("system", note, ChatStyle::System);
+                true
+            }
+            "/cost" => {
+                self.push_chat("system", &self.cost_report(), ChatStyle::System);
+                true
+            }
+            "/clear" => {
+                self.chat_lines.clear();
+                self.selected_chat = None;
+                self.tasks.clear();
+                self.task_selected = None;
+                self.visual = None;
+                self.message_count = 0;
+                self.reset_usage();
+                self.push_chat("system", "Conversation cleared.", ChatStyle::System);
+                true
+            }
+            "/attach" if parts.len() > 1 && !parts[1].trim().is_empty() => {
+                let cwd = self.working_dir();
+                match crate::images::load_image(&cwd.join(parts[1].trim())) {
+                    Ok(image) => {
+                        self.push_chat(
+                            "system",
+                            &format!("Attached {}", image.chip()),
+                            ChatStyle::System,
+                        );
+                        self.pending_images.push(image);
+                    }
+                    Err(e) => self.push_chat("error", &e, ChatStyle::Error),
+                }
+                true
+            }
+            "/attach" => {
+                let message = if self.pending_images.is_empty() {
+                    "Usage: /attach PATH (png, jpeg, gif, webp)".to_string()
+                } else {
+                    format!("Removed {} attached image(s).", self.pending_images.len())
+                };
+                self.pending_images.clear();
+                self.push_chat("system", &message, ChatStyle::System);
+                true
+            }
+            "/agents" => {
+                self.state = AppState::Agents;
+                self.sync_agent_menu_selection();
+                true
+            }
+            "/children" | "/tree" | "/tools" | "/edit" | "/resume" | "/compact" | "/usage"
+            | "/export" | "/results" | "/result" | "/mcp" => false,
+            _ if cmd.starts_with("/child ")
+                || cmd.starts_with("/tool ")
+                || cmd.starts_with("/result ") =>
+            {
+                false
+            }
+            "/model" if parts.len() > 1 && !parts[1].trim().is_empty() => {
+                let resolved = rot_core::resolve_model_alias(&self.model_aliases, parts[1].trim());
+                if let Some(provider) = resolved.provider {
+                    self.provider = provider;
+                }
+                self.model = resolved.model;
+                self.config_changed = true;
+                true
+            }
+            "/models" | "/model" => {
+                self.open_model_picker();
+                true
+            }
+            "/rlm" => {
+                self.rlm_enabled = !self.rlm_enabled;
+                let state_str = if self.rlm_enabled { "ON" } else { "OFF" };
+                self.push_chat(
+                    "system",
+                    &format!("RLM Engine is now {}", state_str),
+                    ChatStyle::System,
+                );
+                true
+            }
+            "/context" if parts.len() > 1 && !parts[1].trim().is_empty() => {
+                let context = parts[1].trim();
+                let cwd = self.working_dir();
+                match rot_rlm::check_context(&cwd.join(context)) {
+                    Ok(()) => {
+                        self.rlm_context = Some(context.to_string());
+                        self.rlm_enabled = true;
+                        self.push_chat(
+                            "system",
+                            &format!("RLM runs now read {context}. RLM Engine is ON"),
+                            ChatStyle::System,
+                        );
                     }
+                    Err(e) => self.push_chat(
+                        "error",
+                        &format!("Cannot use {context} as RLM context: {e}"),
+                        ChatStyle::Error,
+                    ),
+                }
+                true
+            }
+            "/context" => {
+                self.push_chat(
+                    "system",
+                    &format!(
+                        "RLM context: {}. Use /context PATH|GLOB to change it.",
+                        self.rlm_context.as_deref().unwrap_or("the workspace")
+                    ),
+                    ChatStyle::System,
+                );
+                true
+            }
+            "/cd" if parts.len() > 1 && !parts[1].trim().is_empty() => false,
+            "/cd" => {
+                self.push_chat(
+                    "system",
+                    &format!(
+                        "Working directory: {}. Use /cd DIR to change it.",
+                        self.working_dir().display()
+                    ),
+                    ChatStyle::System,
+                );
+                true
+            }
+            "/quit" | "/exit" => {
+                self.running = false;
+                true
+            }
+            _ if cmd.starts_with('/') => {
+                self.push_chat(
+                    "system",
+                    &format!("Unknown: {}. Try /help", parts[0]),
+                    ChatStyle::System,
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn submit_input(&mut self) -> String {
+        let text = self.input.clone();
+        self.history.push(&text);
+        self.history_index = None;
+        self.input.clear();
+        self.cursor_pos = 0;
+        self.slash_menu_selected = 0;
+        self.file_matches.clear();
+        // The turn may create files; list them again on the next mention.
+        self.workspace_files = None;
+        text
+    }
+
+    /// Load a previous prompt into the input box for editing.
+    ///
+    /// Starts with the latest prompt; calling again while editing steps back
+    /// one prompt at a time. Returns `false` when there is nothing to edit.
+    pub fn begin_edit(&mut self, prompts: &[String]) -> bool {
+        if prompts.is_empty() {
+            self.push_chat("system", "Nothing to edit yet.", ChatStyle::System);
+            return false;
+        }
+        let nth = match self.editing_prompt {
+            Some(current) if current > 0 => current - 1,
+            Some(current) => current,
+            None => prompts.len() - 1,
+        }
+        .min(prompts.len() - 1);
+
+        self.editing_prompt = Some(nth);
+        self.input = prompts[nth].clone();
+        self.cursor_pos = self.input.len();
+        self.input_mode = InputMode::Insert;
+        self.status = format!(
+            "Editing message {}/{} — Enter to regenerate, Esc to cancel",
+            nth + 1,
+            prompts.len()
+        );
+        true
+    }
+
+    /// Abandon an in-progress edit and clear the input box.
+    pub fn cancel_edit(&mut self) {
+        if self.editing_prompt.take().is_some() {
+            self.input.clear();
+            self.cursor_pos = 0;
+            self.status = "Ready".to_string();
+        }
+    }
+
+    /// Whether the input box is editable in the current state.
+    pub fn accepts_input(&self) -> bool {
+        matches!(
+            self.state,
+            AppState::Idle | AppState::Thinking | AppState::Streaming
+        )
+    }
+
+    /// Queue the current input as a follow-up for the running turn.
+    ///
+    /// Returns `false` when the input box is empty.
+    pub fn queue_input(&mut self) -> bool {
+        let text = self.submit_input();
+        if text.trim().is_empty() {
+            return false;
+        }
+        self.push_chat("you", &text, ChatStyle::User);
+        self.message_count += 1;
+        self.queued_inputs.lock().unwrap().push_back(text);
+        true
+    }
+
+    /// Number of follow-ups still waiting to be delivered.
+    pub fn queued_count(&self) -> usize {
+        self.queued_inputs.lock().unwrap().len()
+    }
+
+    /// Directory tools run in.
+    pub fn working_dir(&self) -> PathBuf {
+        self.working_dir.lock().unwrap().clone()
+    }
+
+    /// Resolve `target` against the working directory and move there.
+    /// Refused outside the workspace, and while a turn is running, whose
+    /// tools already have their directory.
+    pub fn change_working_dir(&mut self, target: &str) -> Result<PathBuf, String> {
+        if matches!(self.state, AppState::Thinking | AppState::Streaming) {
+            return Err("Wait for the reply to finish before changing directory.".to_string());
+        }
+        let dir =
+            rot_core::agent::resolve_working_dir(&self.workspace, &self.working_dir(), target)?;
+        *self.working_dir.lock().unwrap() = dir.clone();
+        self.workspace_files = None;
+        Ok(dir)
+    }
+
+    /// Remove and return every follow-up that was not consumed by the agent.
+    pub fn take_queued_inputs(&mut self) -> Vec<String> {
+        self.queued_inputs.lock().unwrap().drain(..).collect()
+    }
+
+    pub fn all_agents(&self) -> Vec<rot_core::AgentProfile> {
+        rot_core::AgentRegistry::builtins().to_vec()
+    }
+
+    pub fn sync_agent_menu_selection(&mut self) {
+        let agents = self.all_agents();
+        self.agent_menu_selected = agents
+            .iter()
+            .position(|profile| profile.name == self.agent)
+            .unwrap_or(self.agent_menu_selected.min(agents.len().saturating_sub(1)));
+    }
+
+    pub fn move_agent_selection_up(&mut self) {
+        let count = self.all_agents().len();
+        if count == 0 {
+            return;
+        }
+        if self.agent_menu_selected == 0 {
+            self.agent_menu_selected = count - 1;
+        } else {
+            self.agent_menu_selected -= 1;
+        }
+    }
+
+    pub fn move_agent_selection_down(&mut self) {
+        let count = self.all_agents().len();
+        if count == 0 {
+            return;
+        }
+        self.agent_menu_selected = (self.agent_menu_selected + 1) % count;
+    }
+
+    pub fn select_current_agent(&mut self) -> bool {
+        let agents = self.all_agents();
+        let Some(profile) = agents.get(self.agent_menu_selected).copied() else {
+            return false;
+        };
+
+        let changed = self.agent != profile.name;
+        self.agent = profile.name.to_string();
+        self.state = AppState::Idle;
+        self.agent_changed = changed;
+        changed
+    }
+
+    /// Open the resume picker over `sessions`, or say there is nothing to resume.
+    pub fn open_session_picker(&mut self, sessions: Vec<rot_session::SessionMeta>) {
+        if sessions.is_empty() {
+            self.push_chat(
+                "system",
+                "No previous sessions in this directory.",
+                ChatStyle::System,
+            );
+            return;
+        }
+        self.session_choices = sessions;
+        self.session_menu_selected = 0;
+        self.state = AppState::Sessions;
+    }
+
+    pub fn move_session_selection_up(&mut self) {
+        let count = self.session_choices.len();
+        if count == 0 {
+            return;
+        }
+        self.session_menu_selected = (self.session_menu_selected + count - 1) % count;
+    }
+
+    pub fn move_session_selection_down(&mut self) {
+        let count = self.session_choices.len();
+        if count == 0 {
+            return;
+        }
+        self.session_menu_selected = (self.session_menu_selected + 1) % count;
+    }
+
+    /// Close the resume picker and return the chosen session, if any.
+    pub fn take_selected_session(&mut self) -> Option<rot_session::SessionMeta> {
+        self.state = AppState::Idle;
+        let choices = std::mem::take(&mut self.session_choices);
+        choices.into_iter().nth(self.session_menu_selected)
+    }
+
+    /// Replace the chat view with a resumed conversation, turn by turn:
+    /// the prompt, its tool calls, then the final reply.
+    pub fn load_transcript(&mut self, messages: &[rot_core::Message]) {
+        self.reset_usage();
+        self.show_transcript(messages);
+    }
+
+    /// Redraw the chat view from `messages` after an edit rewound them, so
+    /// the replaced prompt and the turns after it are no longer shown. Usage
+    /// already spent on those turns still counts.
+    pub fn rewind_transcript(&mut self, messages: &[rot_core::Message]) {
+        self.show_transcript(messages);
+    }
+
+    fn show_transcript(&mut self, messages: &[rot_core::Message]) {
+        self.chat_lines.clear();
+        self.selected_chat = None;
+        self.search = None;
+
+        let prompts = rot_core::message::user_prompt_indices(messages);
+        self.message_count = prompts.len();
+        for (n, &start) in prompts.iter().enumerate() {
+            let end = prompts.get(n + 1).copied().unwrap_or(messages.len());
+            let turn = &messages[start..end];
+            let prompt = turn[0].text();
+            self.push_chat(
+                "you",
+                crate::mentions::strip_attachments(&prompt),
+                ChatStyle::User,
+            );
+            self.stamp_last(turn[0].timestamp);
+            let images = turn[0]
+                .content
+                .iter()
+                .filter(|block| matches!(block, rot_core::ContentBlock::Image { .. }))
+                .count();
+            if images > 0 {
+                self.push_chat("system", &format!("[{images} image(s)]"), ChatStyle::System);
+            }
+            for exchange in rot_core::message::tool_exchanges(turn) {
+                self.push_tool_exchange(&exchange);
+            }
+            let reply = turn
+                .iter()
+                .rev()
+                .filter(|m| m.role == rot_core::Role::Assistant)
+                .map(|m| (m.text(), m.timestamp))
+                .find(|(text, _)| !text.trim().is_empty());
+            if let Some((reply, timestamp)) = reply {
+                self.push_chat("rot", &reply, ChatStyle::Assistant);
+                self.stamp_last(timestamp);
+            }
+        }
+    }
+
+    pub fn parse_agent_mention(input: &str) -> Option<(String, String)> {
+        let trimmed = input.trim();
+        let rest = trimmed.strip_prefix('@')?;
+        let (name, prompt) = rest.split_once(char::is_whitespace)?;
+        let profile = rot_core::AgentRegistry::get(name)?;
+        let prompt = prompt.trim();
+        if prompt.is_empty() {
+            return None;
+        }
+        Some((profile.name.to_string(), prompt.to_string()))
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.history_index = None;
+        self.input.insert(self.cursor_pos, c);
+        self.cursor_pos += c.len_utf8();
+        self.sync_slash_menu_selection();
+        self.sync_file_menu();
+    }
+
+    pub fn insert_newline(&mut self) {
+        self.history_index = None;
+        self.input.insert(self.cursor_pos, '\n');
+        self.cursor_pos += 1;
+        self.sync_slash_menu_selection();
+        self.sync_file_menu();
+    }
+
+    /// Insert pasted text where the user is typing. Bracketed paste delivers
+    /// it as one chunk, so newlines are kept instead of sending the message.
+    pub fn handle_paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if self.state == AppState::Approval {
+            if let Some(prompt) = &mut self.approval {
+                prompt.paste(&text);
+            }
+            return;
+        }
+        if let ConfigUiState::InputKey {
+            input, cursor_pos, ..
+        } = &mut self.config_ui_state
+        {
+            if self.state == AppState::Config {
+                let key = text.trim();
+                input.insert_str(*cursor_pos, key);
+                *cursor_pos += key.len();
+            }
+            return;
+        }
+        // Single-line fields take the paste as one line.
+        let line = text.trim().replace('\n', " ");
+        if let Some(search) = &mut self.history_search {
+            search.query.push_str(&line);
+            search.matches = self.history.search(&search.query);
+            search.selected = 0;
+            return;
+        }
+        if let Some(search) = self.search.as_mut().filter(|search| search.editing) {
+            search.query.push_str(&line);
+            return;
+        }
+        if !self.accepts_input() {
+            return;
+        }
+        self.history_index = None;
+        self.input.insert_str(self.cursor_pos, &text);
+        self.cursor_pos += text.len();
+        self.sync_slash_menu_selection();
+        self.sync_file_menu();
+    }
+
+    /// Providers offered by the model picker: built-in ones, then custom ones.
+    /// A custom provider named like a built-in one takes its place.
+    pub fn model_providers(&self) -> Vec<String> {
+        BUILTIN_PROVIDERS
+            .iter()
+            .map(|name| name.to_string())
+            .chain(
+                self.custom_providers
+                    .iter()
+                    .filter(|name| !BUILTIN_PROVIDERS.contains(&name.as_str()))
+                    .cloned(),
+            )
+            .collect()
+    }
+
+    /// Open the model picker on the current provider.
+    pub fn open_model_picker(&mut self) {
+        self.state = AppState::Config;
+        self.show_picker_provider(self.provider.clone());
+    }
+
+    /// Show `provider`'s models in the picker, requesting its list if it has
+    /// not been loaded yet.
+    fn show_picker_provider(&mut self, provider: String) {
+        if !self.model_listings.contains_key(&provider) {
+            self.model_listings
+                .insert(provider.clone(), ModelListing::Loading);
+            self.models_request = Some(provider.clone());
+        }
+        self.picker_provider = provider;
+        self.config_ui_state = ConfigUiState::List(self.picker_current_index());
+    }
+
+    fn cycle_picker_provider(&mut self, delta: isize) {
+        let providers = self.model_providers();
+        let current = providers
+            .iter()
+            .position(|name| *name == self.picker_provider)
+            .unwrap_or(0);
+        let next = (current as isize + delta).rem_euclid(providers.len() as isize) as usize;
+        self.show_picker_provider(providers[next].clone());
+    }
+
+    /// Models listed for the provider the picker shows.
+    pub fn picker_models(&self) -> &[ModelInfo] {
+        self.model_listings
+            .get(&self.picker_provider)
+            .map_or(&[], ModelListing::models)
+    }
+
+    /// Picker row of the current model, or the first row for other providers.
+    fn picker_current_index(&self) -> usize {
+        if self.picker_provider != self.provider {
+            return 0;
+        }
+        self.picker_models()
+            .iter()
+            .position(|m| m.id == self.model)
+            .unwrap_or(0)
+    }
+
+    /// Take the provider whose model list should be fetched, if any.
+    pub fn take_models_request(&mut self) -> Option<String> {
+        self.models_request.take()
+    }
+
+    /// Store a model list fetched for the picker.
+    pub fn set_model_listing(&mut self, provider: String, listing: ModelListing) {
+        let shown = provider == self.picker_provider;
+        self.model_listings.insert(provider, listing);
+        if shown
+            && self.state == AppState::Config
+            && matches!(self.config_ui_state, ConfigUiState::List(_))
+        {
+            self.config_ui_state = ConfigUiState::List(self.picker_current_index());
+        }
+    }
+
+    /// Models fetched from `provider`'s API, for building it with models
+    /// newer than its built-in list.
+    pub fn fetched_models(&self, provider: &str) -> Option<&[ModelInfo]> {
+        match self.model_listings.get(provider) {
+            Some(ModelListing::Live(models)) => Some(models),
+            _ => None,
+        }
+    }
+
+    /// Context window of the current model, from its listing when loaded.
+    fn context_window(&self) -> usize {
+        self.model_listings
+            .get(&self.provider)
+            .and_then(|listing| listing.models().iter().find(|m| m.id == self.model))
+            .map_or_else(|| get_context_window(&self.model), |m| m.context_window)
+    }
+
+    fn handle_model_list_key(
+        &mut self,
+        key_code: crossterm::event::KeyCode,
+        idx: usize,
+        config_store: &rot_core::config::ConfigStore,
+    ) {
+        use crossterm::event::KeyCode;
+
+        match key_code {
+            KeyCode::Up => self.config_ui_state = ConfigUiState::List(idx.saturating_sub(1)),
+            KeyCode::Down => {
+                let last = self.picker_models().len().saturating_sub(1);
+                self.config_ui_state = ConfigUiState::List((idx + 1).min(last));
+            }
+            KeyCode::Left | KeyCode::BackTab => self.cycle_picker_provider(-1),
+            KeyCode::Right | KeyCode::Tab => self.cycle_picker_provider(1),
+            KeyCode::Char('r') => {
+                self.model_listings.remove(&self.picker_provider);
+                self.show_picker_provider(self.picker_provider.clone());
+            }
+            KeyCode::Enter => {
+                let Some(model) = self.picker_models().get(idx).map(|m| m.id.clone()) else {
+                    return;
+                };
+                let provider = self.picker_provider.clone();
+                let mut config = config_store.load_global();
+
+                // Custom providers read their key from the environment, and
+                // self-hosted gateways often need none.
+                let has_key = self.custom_providers.contains(&provider)
+                    || config_store.has_api_key(&provider)
+                    || std::env::var(format!("{}_API_KEY", provider.to_uppercase())).is_ok();
+
+                if has_key {
+                    config.provider = provider.clone();
+                    config.model = model.clone();
+                    let _ = config_store.save(&config);
+                    self.provider = provider;
+                    self.model = model;
+                    self.state = AppState::Idle;
+                    self.config_changed = true;
+                } else {
+                    self.config_ui_state = ConfigUiState::InputKey {
+                        provider,
+                        model,
+                        input: String::new(),
+                        cursor_pos: 0,
+                    };
                 }
-                crossterm::event::KeyCode::Esc => self.state = AppState::Idle,
-                _ => {}
-            },
+            }
+            KeyCode::Esc => self.state = AppState::Idle,
+            _ => {}
+        }
+    }
+
+    pub fn handle_config_key(
+        &mut self,
+        key_code: crossterm::event::KeyCode,
+        config_store: &rot_core::config::ConfigStore,
+    ) {
+        if let ConfigUiState::List(idx) = self.config_ui_state {
+            self.handle_model_list_key(key_code, idx, config_store);
+            return;
+        }
+        match &mut self.config_ui_state {
+            ConfigUiState::List(_) => {}
             ConfigUiState::InputKey {
                 provider,
                 model,
@@ -479,39 +2497,31 @@ impl App {
                     input.insert(*cursor_pos, c);
                     *cursor_pos += c.len_utf8();
                 }
-                crossterm::event::KeyCode::Backspace => {
-                    if *cursor_pos > 0 {
-                        let prev = input[..*cursor_pos]
-                            .char_indices()
-                            .next_back()
-                            .map(|(i, _)| i)
-                            .unwrap_or(0);
-                        input.remove(prev);
-                        *cursor_pos = prev;
-                    }
+                crossterm::event::KeyCode::Backspace if *cursor_pos > 0 => {
+                    let prev = input[..*cursor_pos]
+                        .char_indices()
+                        .next_back()
+                        .map(|(i, _)| i)
+                        .unwrap_or(0);
+                    input.remove(prev);
+                    *cursor_pos = prev;
                 }
-                crossterm::event::KeyCode::Delete => {
-                    if *cursor_pos < input.len() {
-                        input.remove(*cursor_pos);
-                    }
+                crossterm::event::KeyCode::Delete if *cursor_pos < input.len() => {
+                    input.remove(*cursor_pos);
                 }
-                crossterm::event::KeyCode::Left => {
-                    if *cursor_pos > 0 {
-                        *cursor_pos = input[..*cursor_pos]
-                            .char_indices()
-                            .next_back()
-                            .map(|(i, _)| i)
-                            .unwrap_or(0);
-                    }
+                crossterm::event::KeyCode::Left if *cursor_pos > 0 => {
+                    *cursor_pos = input[..*cursor_pos]
+                        .char_indices()
+                        .next_back()
+                        .map(|(i, _)| i)
+                        .unwrap_or(0);
                 }
-                crossterm::event::KeyCode::Right => {
-                    if *cursor_pos < input.len() {
-                        *cursor_pos += input[*cursor_pos..]
-                            .chars()
-                            .next()
-                            .map(|c| c.len_utf8())
-                            .unwrap_or(0);
-                    }
+                crossterm::event::KeyCode::Right if *cursor_pos < input.len() => {
+                    *cursor_pos += input[*cursor_pos..]
+                        .chars()
+                        .next()
+                        .map(|c| c.len_utf8())
+                        .unwrap_or(0);
                 }
                 crossterm::event::KeyCode::Home => {
                     *cursor_pos = 0;
@@ -522,18 +2532,30 @@ impl App {
                 crossterm::event::KeyCode::Enter => {
                     let api_key = input.trim();
                     if !api_key.is_empty() {
-                        let mut config = config_store.load();
-                        config.api_keys.insert(provider.clone(), api_key.to_string());
+                        let location = config_store.save_api_key(provider, api_key);
+                        let mut config = config_store.load_global();
                         config.provider = provider.clone();
                         config.model = model.clone();
                         let _ = config_store.save(&config);
                         config_store.hydrate_env();
 
+                        let note = match location {
+                            Ok(rot_core::credentials::KeyLocation::Keyring) => {
+                                format!("Saved {provider} API key to the system keyring.")
+                            }
+                            Ok(rot_core::credentials::KeyLocation::ConfigFile) => format!(
+                                "No system keyring available; saved {provider} API key to ~/.rot/config.json."
+                            ),
+                            Err(e) => format!("Failed to save {provider} API key: {e}"),
+                        };
+                        // The list was the built-in fallback while the key was missing.
+                        self.model_listings.remove(provider.as_str());
                         self.provider = provider.clone();
                         self.model = model.clone();
                         self.state = AppState::Idle;
                         self.config_ui_state = ConfigUiState::List(0);
                         self.config_changed = true;
+                        self.push_chat("system", &note, ChatStyle::System);
                     }
                 }
                 crossterm::event::KeyCode::Esc => {
@@ -545,7 +2567,75 @@ impl App {
         }
     }
 
+    /// Move the cursor or delete forward in the input box. Up and Down move
+    /// between wrapped rows. Returns whether the key was used.
+    pub fn handle_editor_key(&mut self, code: crossterm::event::KeyCode) -> bool {
+        use crate::editor;
+        use crossterm::event::KeyCode;
+
+        let cursor = self.cursor_pos;
+        self.cursor_pos = match code {
+            KeyCode::Left => editor::prev_char(&self.input, cursor),
+            KeyCode::Right => editor::next_char(&self.input, cursor),
+            KeyCode::Home => editor::row_bounds(&self.input, self.input_width, cursor).start,
+            KeyCode::End => editor::row_bounds(&self.input, self.input_width, cursor).end,
+            KeyCode::Up => editor::move_vertically(&self.input, self.input_width, cursor, -1),
+            KeyCode::Down => editor::move_vertically(&self.input, self.input_width, cursor, 1),
+            KeyCode::Delete if cursor < self.input.len() => {
+                self.history_index = None;
+                self.input.remove(cursor);
+                self.sync_slash_menu_selection();
+                self.sync_file_menu();
+                cursor
+            }
+            KeyCode::Delete => cursor,
+            _ => return false,
+        };
+        if self.cursor_pos != cursor {
+            self.sync_file_menu();
+        }
+        true
+    }
+
+    /// Show the approval dialog for a tool call; the decision is sent on `tx`.
+    pub fn begin_approval(
+        &mut self,
+        tool: String,
+        args: serde_json::Value,
+        tx: tokio::sync::oneshot::Sender<ApprovalResponse>,
+    ) {
+        let cwd = self.working_dir();
+        self.help = None;
+        self.pager = None;
+        self.approval = Some(ApprovalPrompt::new(tool, args, &cwd));
+        self.approval_tx = Some(tx);
+        self.state = AppState::Approval;
+    }
+
+    /// Route a key to the approval dialog. Once the user decides, the
+    /// decision goes back to the waiting agent and the run resumes.
+    pub fn handle_approval_key(&mut self, key: crossterm::event::KeyEvent) {
+        let Some(prompt) = self.approval.as_mut() else {
+            return;
+        };
+        let Some(response) = prompt.handle_key(key) else {
+            return;
+        };
+        if let Some(tx) = self.approval_tx.take() {
+            let _ = tx.send(response);
+        }
+        self.approval = None;
+        self.state = AppState::Thinking;
+    }
+
+    /// Height of the input box, borders included, for its wrapped text.
+    fn input_height(&self) -> u16 {
+        let rows = crate::editor::wrap(&self.input, self.input_width).len();
+        rows.min(MAX_INPUT_ROWS) as u16 + 2
+    }
+
     pub fn backspace(&mut self) {
+        self.history_index = None;
         if self.cursor_pos > 0 {
             let prev = self.input[..self.cursor_pos]
                 .char_indices()
@@ -555,6 +2645,7 @@ impl App {
             self.input.remove(prev);
             self.cursor_pos = prev;
             self.sync_slash_menu_selection();
+            self.sync_file_menu();
         }
     }
 
@@ -615,6 +2706,223 @@ impl App {
         }
     }
 
+    /// Refresh the `@` file picker for the token under the cursor.
+    ///
+    /// `@name` at the start of the input is left to agent routing when `name`
+    /// is a known agent.
+    pub fn sync_file_menu(&mut self) {
+        let Some((start, query)) = crate::mentions::mention_at(&self.input, self.cursor_pos) else {
+            self.file_matches.clear();
+            return;
+        };
+        if start == 0 && rot_core::AgentRegistry::get(query).is_some() {
+            self.file_matches.clear();
+            return;
+        }
+        let cwd = self.working_dir();
+        let files = self.workspace_files.get_or_insert_with(|| {
+            rot_tools::builtin::glob::workspace_files(&cwd)
+                .take(MAX_WORKSPACE_FILES)
+                .collect()
+        });
+        self.file_matches = crate::mentions::fuzzy_files(files, query, 8);
+        self.file_menu_selected = self
+            .file_menu_selected
+            .min(self.file_matches.len().saturating_sub(1));
+    }
+
+    pub fn is_file_menu_active(&self) -> bool {
+        self.input_mode == InputMode::Insert
+            && self.accepts_input()
+            && !self.file_matches.is_empty()
+    }
+
+    pub fn move_file_selection_up(&mut self) {
+        let count = self.file_matches.len();
+        if count > 0 {
+            self.file_menu_selected = (self.file_menu_selected + count - 1) % count;
+        }
+    }
+
+    pub fn move_file_selection_down(&mut self) {
+        let count = self.file_matches.len();
+        if count > 0 {
+            self.file_menu_selected = (self.file_menu_selected + 1) % count;
+        }
+    }
+
+    /// Replace the `@` token under the cursor with the selected file.
+    pub fn accept_file_completion(&mut self) {
+        let Some(path) = self.file_matches.get(self.file_menu_selected).cloned() else {
+            return;
+        };
+        if let Some((start, _)) = crate::mentions::mention_at(&self.input, self.cursor_pos) {
+            let completion = format!("@{path} ");
+            self.input
+                .replace_range(start..self.cursor_pos, &completion);
+            self.cursor_pos = start + completion.len();
+        }
+        self.file_matches.clear();
+        self.file_menu_selected = 0;
+    }
+
+    /// Close the file picker until the input changes.
+    pub fn dismiss_file_menu(&mut self) {
+        self.file_matches.clear();
+    }
+
+    /// Whether Up/Down should move through the prompt history: the input is
+    /// empty or already shows a history entry.
+    pub fn is_history_key_active(&self) -> bool {
+        self.history_index.is_some() || self.input.is_empty()
+    }
+
+    /// Whether the input shows a history entry picked with Up.
+    pub fn is_browsing_history(&self) -> bool {
+        self.history_index.is_some()
+    }
+
+    /// Show the previous history entry. Only starts from an empty input;
+    /// returns whether the key was used.
+    pub fn history_prev(&mut self) -> bool {
+        let count = self.history.entries().len();
+        let idx = match self.history_index {
+            None if !self.input.is_empty() || count == 0 => return false,
+            None => count - 1,
+            Some(idx) => idx.saturating_sub(1),
+        };
+        self.show_history_entry(Some(idx));
+        true
+    }
+
+    /// Show the next history entry, or the empty input after the newest one.
+    pub fn history_next(&mut self) -> bool {
+        let Some(idx) = self.history_index else {
+            return false;
+        };
+        let next = Some(idx + 1).filter(|next| *next < self.history.entries().len());
+        self.show_history_entry(next);
+        true
+    }
+
+    fn show_history_entry(&mut self, idx: Option<usize>) {
+        self.input = idx
+            .and_then(|idx| self.history.entries().get(idx).cloned())
+            .unwrap_or_default();
+        self.cursor_pos = self.input.len();
+        self.history_index = idx;
+        self.sync_slash_menu_selection();
+        self.file_matches.clear();
+    }
+
+    /// Handle a key for Ctrl-R history search. Returns whether it was used.
+    ///
+    /// Ctrl-R opens the search and, while open, moves to the next older
+    /// match; `Enter`/`Tab` put the match in the input and `Esc` closes it.
+    pub fn handle_history_search_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let ctrl_r =
+            key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL);
+        let Some(search) = &mut self.history_search else {
+            if ctrl_r {
+                self.history_search = Some(HistorySearch {
+                    matches: self.history.search(""),
+                    ..Default::default()
+                });
+                return true;
+            }
+            return false;
+        };
+
+        let count = search.matches.len();
+        match key.code {
+            _ if ctrl_r => search.selected = (search.selected + 1) % count.max(1),
+            KeyCode::Up => search.selected = (search.selected + 1) % count.max(1),
+            KeyCode::Down => search.selected = (search.selected + count.max(1) - 1) % count.max(1),
+            KeyCode::Char(c) => {
+                search.query.push(c);
+                search.matches = self.history.search(&search.query);
+                search.selected = 0;
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                search.matches = self.history.search(&search.query);
+                search.selected = 0;
+            }
+            KeyCode::Enter | KeyCode::Tab => {
+                let idx = search.matches.get(search.selected).copied();
+                self.history_search = None;
+                if idx.is_some() {
+                    self.show_history_entry(idx);
+                }
+            }
+            KeyCode::Esc => self.history_search = None,
+            _ => {}
+        }
+        true
+    }
+
+    /// Handle a Normal-mode key for scrollback search. Returns whether it was used.
+    ///
+    /// `/` starts a query, `Enter` runs it, `n`/`N` move between matches, and
+    /// `Esc` closes the search.
+    pub fn handle_search_key(&mut self, key_code: crossterm::event::KeyCode) -> bool {
+        use crossterm::event::KeyCode;
+
+        let Some(search) = &mut self.search else {
+            if key_code == KeyCode::Char('/') {
+                self.search = Some(SearchState {
+                    editing: true,
+                    ..Default::default()
+                });
+                return true;
+            }
+            return false;
+        };
+
+        if search.editing {
+            match key_code {
+                KeyCode::Char(c) => search.query.push(c),
+                KeyCode::Backspace if search.query.is_empty() => self.search = None,
+                KeyCode::Backspace => {
+                    search.query.pop();
+                }
+                KeyCode::Enter if search.query.is_empty() => self.search = None,
+                KeyCode::Enter => {
+                    search.editing = false;
+                    search.jump = true;
+                    search.from_view = true;
+                }
+                KeyCode::Esc => self.search = None,
+                _ => {}
+            }
+            return true;
+        }
+
+        let count = search.matches.len();
+        match key_code {
+            KeyCode::Char('n') if count > 0 => {
+                search.current = (search.current + 1) % count;
+                search.jump = true;
+            }
+            KeyCode::Char('N') if count > 0 => {
+                search.current = (search.current + count - 1) % count;
+                search.jump = true;
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {}
+            KeyCode::Char('/') => {
+                self.search = Some(SearchState {
+                    editing: true,
+                    ..Default::default()
+                });
+            }
+            KeyCode::Esc => self.search = None,
+            _ => return false,
+        }
+        true
+    }
+
     pub fn tick(&mut self) {
         self.thinking_tick = self.thinking_tick.wrapping_add(1);
     }
@@ -634,22 +2942,30 @@ impl App {
         let inner = outer_block.inner(area);
         frame.render_widget(outer_block, area);
 
-        // Layout: header(1) | messages(flex) | input(3) | footer(1)
+        // Input rows lose two cells to the borders, two to the prompt, and
+        // one so the cursor fits after a full row.
+        self.input_width = inner.width.saturating_sub(5).max(1) as usize;
+
+        // Layout: header(1) | messages(flex) | tasks(0 when hidden) | input(3+) | footer(1)
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(1), // Header
                 Constraint::Min(5),   // Messages
-                Constraint::Length(3), // Input
+                Constraint::Length(self.tasks_panel_height()), // Background tasks
+                Constraint::Length(self.input_height()),       // Input, grows with wrapped rows
                 Constraint::Length(1), // Footer (provider + context + tokens + cost)
             ])
             .split(inner);
 
         self.render_header(frame, chunks[0]);
         self.render_messages(frame, chunks[1]);
-        self.render_input(frame, chunks[2]);
-        self.render_footer(frame, chunks[3]);
-        self.render_slash_menu(frame, chunks[2]);
+        self.render_tasks_panel(frame, chunks[2]);
+        self.render_input(frame, chunks[3]);
+        self.render_footer(frame, chunks[4]);
+        self.render_slash_menu(frame, chunks[3]);
+        self.render_file_menu(frame, chunks[3]);
+        self.render_history_menu(frame, chunks[3]);
 
         // Overlay dialog
         if self.state == AppState::Approval {
@@ -658,7 +2974,99 @@ impl App {
             self.render_config_dialog(frame, area);
         } else if self.state == AppState::Agents {
             self.render_agents_dialog(frame, area);
+        } else if self.state == AppState::Sessions {
+            self.render_sessions_dialog(frame, area);
+        }
+        if self.help.is_some() {
+            self.render_help_overlay(frame, area);
+        }
+        if self.security_confirm.is_some() {
+            self.render_security_confirm(frame, area);
         }
+        if self.pager.is_some() {
+            self.render_pager(frame, area);
+        }
+    }
+
+    fn render_pager(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(pager) = self.pager.as_mut() else {
+            return;
+        };
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .title(format!(" {} ", pager.title))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(COLOR_BANNER));
+        let inner = block.inner(area).inner(Margin::new(1, 0));
+        frame.render_widget(block, area);
+        if inner.height < 2 {
+            return;
+        }
+
+        let body = Rect {
+            height: inner.height - 1,
+            ..inner
+        };
+        let rows = pager.wrapped(body.width as usize);
+        let visible = body.height as usize;
+        pager.rows = rows.len();
+        pager.page = visible;
+        pager.scroll = pager.scroll.min(rows.len().saturating_sub(visible));
+        let shown: Vec<Line> = rows
+            .into_iter()
+            .skip(pager.scroll)
+            .take(visible)
+            .map(Line::raw)
+            .collect();
+        frame.render_widget(Paragraph::new(shown), body);
+
+        let last = (pager.scroll + visible).min(pager.rows);
+        let status = format!(
+            "rows {}–{last} of {} · j/k scroll · Space/b page · gg/G top/bottom · q close",
+            (pager.scroll + 1).min(last),
+            pager.rows
+        );
+        frame.render_widget(
+            Paragraph::new(Span::styled(status, Style::default().fg(COLOR_DIM))),
+            Rect {
+                y: inner.y + inner.height - 1,
+                height: 1,
+                ..inner
+            },
+        );
+    }
+
+    fn render_security_confirm(&self, frame: &mut Frame, area: Rect) {
+        let width = 64.min(area.width);
+        let height = 7.min(area.height);
+        let popup_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup_area);
+
+        let block = Block::default()
+            .title(" ⚠ Switch to danger-full-access? ")
+            .title_style(Style::default().fg(COLOR_ERROR).bold())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(COLOR_ERROR));
+        let text = vec![
+            Line::from("This turns the sandbox off: tools can read and write any file"),
+            Line::from("and reach the network."),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(" [y] ", Style::default().fg(COLOR_ERROR).bold()),
+                Span::raw("switch   "),
+                Span::styled(" any other key ", Style::default().fg(COLOR_DIM).bold()),
+                Span::raw("cancel"),
+            ]),
+        ];
+        frame.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: false }),
+            popup_area,
+        );
     }
 
     fn render_header(&self, frame: &mut Frame, area: Rect) {
@@ -673,6 +3081,7 @@ impl App {
             AppState::Streaming => "◉",
             AppState::Approval => "⚠",
             AppState::Agents => "◈",
+            AppState::Sessions => "↺",
             AppState::Config => "⚙",
             AppState::Error => "✖",
         };
@@ -682,7 +3091,7 @@ impl App {
             AppState::Thinking => COLOR_THINKING,
             AppState::Streaming => COLOR_ACCENT,
             AppState::Approval => COLOR_ERROR,
-            AppState::Agents => COLOR_BANNER,
+            AppState::Agents | AppState::Sessions => COLOR_BANNER,
             AppState::Config => COLOR_DIM,
             AppState::Error => COLOR_ERROR,
         };
@@ -698,15 +3107,48 @@ impl App {
 
         let mut header_spans = vec![
             Span::styled(format!(" {spinner} "), Style::default().fg(state_color)),
-            Span::styled(format!(" {}", self.status), Style::default().fg(COLOR_BAR_FG)),
+            Span::styled(
+                format!(" {}", self.status),
+                Style::default().fg(COLOR_BAR_FG),
+            ),
         ];
 
         let mut right_spans = vec![];
-        if self.rlm_iterating && self.state == AppState::Thinking {
+        if let Some(progress) = self
+            .rlm_progress
+            .as_ref()
+            .filter(|_| self.state == AppState::Thinking)
+        {
             let anim = match (self.thinking_tick / 3) % 4 {
                 0 => "⠋", 1 => "⠙", 2 => "⠸", _ => "⠴",
             };
-            right_spans.push(Span::styled(format!("RLM {}  ", anim), Style::default().fg(COLOR_THINKING)));
+            right_spans.push(Span::styled(
+                format!("RLM {anim} {}  ", Self::rlm_segment(progress)),
+                Style::default().fg(COLOR_THINKING),
+            ));
+        } else if self.rlm_enabled {
+            right_spans.push(Span::styled(
+                format!("RLM {}  ", self.rlm_context.as_deref().unwrap_or(".")),
+                Style::default().fg(COLOR_DIM),
+            ));
+        }
+        let queued = self.queued_count();
+        if queued > 0 {
+            right_spans.push(Span::styled(
+                format!("{queued} queued  "),
+                Style::default().fg(COLOR_ACCENT),
+            ));
+        }
+        if let Some(progress) = self.iteration_progress {
+            let color = if progress.is_near_limit() {
+                COLOR_ERROR
+            } else {
+                COLOR_DIM
+            };
+            right_spans.push(Span::styled(
+                format!("step {}/{}  ", progress.iteration, progress.max_iterations),
+                Style::default().fg(color),
+            ));
         }
         if !right_text.is_empty() {
             right_spans.push(Span::styled(right_text, Style::default().fg(COLOR_DIM)));
@@ -728,8 +3170,14 @@ impl App {
 
     fn render_messages(&mut self, frame: &mut Frame, area: Rect) {
         let mut lines: Vec<Line> = Vec::new();
-
-        for msg in &self.chat_lines {
+        // Columns left after the " ▌ " speaker bar.
+        let content_width = (area.width as usize).saturating_sub(3);
+
+        let now = unix_now();
+        // First rendered line of each chat line.
+        let mut chat_starts = Vec::with_capacity(self.chat_lines.len());
+        for (chat_idx, msg) in self.chat_lines.iter().enumerate() {
+            chat_starts.push(lines.len());
             match msg.style {
                 ChatStyle::Welcome => {
                     for line in msg.content.lines() {
@@ -760,9 +3208,54 @@ impl App {
 
                     // Left vertical bar to identify speaker
                     // Note: No more string roles like "you" or "rot".
-                    let bar_span = Span::styled("▌ ", Style::default().fg(role_color).bg(msg_bg));
+                    let bar_color = if self.selected_chat == Some(chat_idx) {
+                        COLOR_ACCENT
+                    } else {
+                        role_color
+                    };
+                    let bar_span = Span::styled("▌ ", Style::default().fg(bar_color).bg(msg_bg));
 
-                    let content_lines: Vec<&str> = msg.content.lines().collect();
+                    let mut content_lines: Vec<Line> = match msg.style {
+                        ChatStyle::Assistant => {
+                            crate::markdown::render(&msg.content, content_style, content_width)
+                        }
+                        _ => msg
+                            .content
+                            .lines()
+                            .map(|line| Line::from(Self::parse_markdown(line, content_style)))
+                            .collect(),
+                    };
+                    if msg.is_expandable() {
+                        let marker = if msg.expanded { " ▾" } else { " ▸" };
+                        if let Some(first) = content_lines.first_mut() {
+                            first
+                                .spans
+                                .push(Span::styled(marker, Style::default().fg(COLOR_DIM)));
+                        }
+                    }
+                    if msg.expanded {
+                        if let Some(detail) = &msg.detail {
+                            content_lines.extend(detail.lines().map(|line| {
+                                Line::from(Span::styled(line, Style::default().fg(COLOR_DIM)))
+                            }));
+                        }
+                    }
+                    if let Some(diff) = &msg.diff {
+                        if msg.expanded || diff_fits(diff) {
+                            content_lines.extend(diff.lines().map(diff_line));
+                        } else {
+                            content_lines.push(Line::from(Span::styled(
+                                format!("{}-line diff (Enter to expand)", diff.lines().count()),
+                                Style::default().fg(COLOR_DIM),
+                            )));
+                        }
+                    }
+                    if let Some(meta) = msg.meta.as_ref().filter(|_| self.show_meta) {
+                        content_lines.push(Line::from(Span::styled(
+                            meta.summary(now),
+                            Style::default().fg(COLOR_DIM),
+                        )));
+                    }
                     if content_lines.is_empty() {
                         lines.push(
                             Line::from(vec![
@@ -777,7 +3270,7 @@ impl App {
                                 Span::styled(" ", Style::default().bg(msg_bg)),
                                 bar_span.clone(),
                             ];
-                            spans.extend(Self::parse_markdown(content_line, content_style));
+                            spans.extend(content_line.spans);
                             lines.push(Line::from(spans).style(line_style));
                         }
                     }
@@ -786,28 +3279,76 @@ impl App {
             }
         }
 
+        // Reasoning streamed so far this turn
+        if self.show_thinking && !self.thinking_text.trim().is_empty() {
+            let thinking: Vec<&str> = self.thinking_text.trim().lines().collect();
+            let hidden = if self.thinking_expanded {
+                0
+            } else {
+                thinking.len().saturating_sub(THINKING_PREVIEW_LINES)
+            };
+            let hint = if self.thinking_expanded {
+                "Ctrl+O to collapse"
+            } else {
+                "Ctrl+O to expand"
+            };
+            let style = Style::default().fg(COLOR_THINKING).italic();
+            lines.push(Line::from(Span::styled(
+                format!(" ✻ thinking · {} lines · {hint}", thinking.len()),
+                style,
+            )));
+            if hidden > 0 {
+                lines.push(Line::from(Span::styled(
+                    format!(" │ … {hidden} earlier lines"),
+                    style,
+                )));
+            }
+            for line in &thinking[hidden..] {
+                lines.push(Line::from(Span::styled(format!(" │ {line}"), style)));
+            }
+        }
+
         // Streaming text
         if !self.streaming_text.is_empty() {
             let msg_bg = Color::Rgb(29, 32, 47);
             let line_style = Style::default().bg(msg_bg);
             let bar_span = Span::styled("▌ ", Style::default().fg(COLOR_ASSISTANT).bg(msg_bg));
 
-            let stream_lines: Vec<&str> = self.streaming_text.lines().collect();
+            let stream_lines =
+                crate::markdown::render(&self.streaming_text, Style::default(), content_width);
             for line in stream_lines {
                 let mut spans = vec![
                     Span::styled(" ", Style::default().bg(msg_bg)),
                     bar_span.clone(),
                 ];
-                spans.extend(Self::parse_markdown(line, Style::default()));
+                spans.extend(line.spans);
                 lines.push(Line::from(spans).style(line_style));
             }
         }
 
-        // Thinking indicator
-        if self.state == AppState::Thinking {
-            let spinner = match (self.thinking_tick / 3) % 4 {
-                0 => "⠋", 1 => "⠙", 2 => "⠸", _ => "⠴",
-            };
+        // Running tools replace the generic thinking indicator
+        let spinner = match (self.thinking_tick / 3) % 4 {
+            0 => "⠋",
+            1 => "⠙",
+            2 => "⠸",
+            _ => "⠴",
+        };
+        for tool in &self.running_tools {
+            lines.push(Line::from(vec![
+                Span::raw(" "),
+                Span::styled(
+                    format!(
+                        "{spinner} {} … {}",
+                        tool.summary,
+                        format_elapsed(tool.started.elapsed())
+                    ),
+                    Style::default().fg(COLOR_TOOL),
+                ),
+            ]));
+        }
+
+        // Thinking indicator
+        if self.state == AppState::Thinking && self.running_tools.is_empty() {
             let elapsed_str = self.response_start
                 .map(|s| format!(" {:.1}s", s.elapsed().as_secs_f64()))
                 .unwrap_or_default();
@@ -825,9 +3366,99 @@ impl App {
         let block = Block::default()
             .borders(Borders::NONE);
 
-        // Auto-scroll + clamp
         let inner_height = area.height;
-        let content_height = lines.len() as u16;
+        let rows: Vec<usize> = lines.iter().map(|l| wrapped_rows(l, area.width)).collect();
+
+        // Highlight search matches and scroll to the selected one
+        if let Some(search) = &mut self.search {
+            search.matches.clear();
+            for (idx, line) in lines.iter().enumerate() {
+                let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+                for (start, end) in find_matches(&text, &search.query) {
+                    search.matches.push((idx, start, end));
+                }
+            }
+            if search.from_view {
+                search.from_view = false;
+                let mut row = 0;
+                let first_visible = rows
+                    .iter()
+                    .position(|r| {
+                        row += r;
+                        row > self.scroll_offset as usize
+                    })
+                    .unwrap_or(0);
+                search.current = search
+                    .matches
+                    .iter()
+                    .position(|(line, _, _)| *line >= first_visible)
+                    .unwrap_or(0);
+            }
+            search.current = search.current.min(search.matches.len().saturating_sub(1));
+
+            let mut by_line: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+            for &(line, start, end) in &search.matches {
+                by_line.entry(line).or_default().push((start, end));
+            }
+            let selected = search.matches.get(search.current).copied();
+            for (idx, ranges) in by_line {
+                let current = selected
+                    .filter(|(line, _, _)| *line == idx)
+                    .and_then(|(_, start, _)| ranges.iter().position(|(s, _)| *s == start));
+                let line = std::mem::take(&mut lines[idx]);
+                lines[idx] = highlight_line(line, &ranges, current);
+            }
+
+            if search.jump {
+                search.jump = false;
+                if let Some((line, _, _)) = selected {
+                    self.auto_scroll = false;
+                    let row: usize = rows[..line].iter().sum();
+                    self.scroll_offset = (row as u16).saturating_sub(inner_height / 3);
+                }
+            }
+        }
+
+        if let Some(line) = self
+            .scroll_to_chat
+            .take()
+            .and_then(|idx| chat_starts.get(idx))
+        {
+            self.auto_scroll = false;
+            let row: usize = rows[..*line].iter().sum();
+            self.scroll_offset = (row as u16).saturating_sub(inner_height / 3);
+        }
+
+        self.chat_view.text = lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        if lines.is_empty() {
+            self.visual = None;
+        }
+        if let Some(visual) = &mut self.visual {
+            let last = lines.len() - 1;
+            visual.anchor = visual.anchor.min(last);
+            visual.cursor = visual.cursor.min(last);
+            for line in &mut lines[visual.range()] {
+                line.style = line.style.bg(COLOR_SELECTION);
+                for span in &mut line.spans {
+                    span.style = span.style.bg(COLOR_SELECTION);
+                }
+            }
+            // Keep the visual cursor in view.
+            let row = rows[..visual.cursor].iter().sum::<usize>() as u16;
+            if row < self.scroll_offset {
+                self.auto_scroll = false;
+                self.scroll_offset = row;
+            } else if row >= self.scroll_offset + inner_height {
+                self.auto_scroll = false;
+                self.scroll_offset = row + 1 - inner_height;
+            }
+        }
+
+        // Auto-scroll + clamp
+        let content_height = rows.iter().sum::<usize>() as u16;
         self.max_scroll = content_height.saturating_sub(inner_height);
 
         if self.auto_scroll && content_height > inner_height {
@@ -836,6 +3467,17 @@ impl App {
         // Clamp scroll to valid range
         self.scroll_offset = self.scroll_offset.min(self.max_scroll);
 
+        let mut row = 0;
+        self.chat_view.top_line = rows
+            .iter()
+            .position(|r| {
+                row += r;
+                row > self.scroll_offset as usize
+            })
+            .unwrap_or(0);
+        self.chat_view.chat_starts = chat_starts;
+        self.chat_view.height = inner_height;
+
         let paragraph = Paragraph::new(lines)
             .block(block)
             .wrap(Wrap { trim: false })
@@ -854,7 +3496,7 @@ impl App {
             },
             AppState::Thinking | AppState::Streaming => COLOR_BORDER,
             AppState::Approval | AppState::Error => COLOR_ERROR,
-            AppState::Agents => COLOR_BANNER,
+            AppState::Agents | AppState::Sessions => COLOR_BANNER,
             AppState::Config => COLOR_BORDER,
         };
 
@@ -863,19 +3505,50 @@ impl App {
             InputMode::Normal => "  ",
         };
 
-        let display_input = self.input.replace('\n', " ↵ ");
-        let input_text = format!("{prompt}{display_input}");
+        let searching = self.search.as_ref().filter(|s| s.editing);
+        let rows = crate::editor::wrap(&self.input, self.input_width);
+        let (cursor_row, cursor_col) =
+            crate::editor::cursor_position(&self.input, &rows, self.cursor_pos);
+        let visible = area.height.saturating_sub(2).max(1) as usize;
+        let first_row = (cursor_row + 1).saturating_sub(visible);
+        let input_text: Text = match (searching, &self.history_search) {
+            (Some(search), _) => format!("/{}", search.query).into(),
+            (None, Some(search)) => format!("{HISTORY_SEARCH_PROMPT}{}", search.query).into(),
+            (None, None) => rows
+                .iter()
+                .enumerate()
+                .skip(first_row)
+                .take(visible)
+                .map(|(n, row)| {
+                    let lead = if n == 0 { prompt } else { "  " };
+                    Line::from(format!("{lead}{}", &self.input[row.start..row.end]))
+                })
+                .collect::<Vec<_>>()
+                .into(),
+        };
 
-        let block = Block::default()
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(border_color))
             .border_type(ratatui::widgets::BorderType::Thick);
+        if !self.pending_images.is_empty() {
+            let chips: Vec<String> = self
+                .pending_images
+                .iter()
+                .map(|image| image.chip())
+                .collect();
+            block = block.title(Span::styled(
+                format!(" {} ", chips.join(" ")),
+                Style::default().fg(COLOR_TOOL),
+            ));
+        }
 
         let style = match self.state {
-            AppState::Idle => Style::default().fg(COLOR_CODE_FG),
-            AppState::Thinking | AppState::Streaming => Style::default().fg(COLOR_DIM),
+            AppState::Idle | AppState::Thinking | AppState::Streaming => {
+                Style::default().fg(COLOR_CODE_FG)
+            }
             AppState::Approval | AppState::Error => Style::default().fg(COLOR_ERROR),
-            AppState::Agents => Style::default().fg(COLOR_DIM),
+            AppState::Agents | AppState::Sessions => Style::default().fg(COLOR_DIM),
             AppState::Config => Style::default().fg(COLOR_DIM),
         };
 
@@ -883,45 +3556,43 @@ impl App {
         frame.render_widget(paragraph, area);
 
         // Cursor
-        if self.input_mode == InputMode::Insert && self.state == AppState::Idle {
-            let visible_pos: usize = self.input[..self.cursor_pos]
-                .chars()
-                .map(|c| if c == '\n' { 3 } else { 1 }) // ↵  = " ↵ " = 3 chars
-                .sum();
-            let x = area.x + visible_pos as u16 + 3; // +1 border +2 prompt
-            let y = area.y + 1;
+        if let Some(search) = searching {
+            let x = area.x + search.query.chars().count() as u16 + 2; // +1 border +1 slash
+            frame.set_cursor_position(Position::new(x, area.y + 1));
+        } else if let Some(search) = &self.history_search {
+            let prompt_width = HISTORY_SEARCH_PROMPT.chars().count() + search.query.chars().count();
+            frame.set_cursor_position(Position::new(area.x + 1 + prompt_width as u16, area.y + 1));
+        } else if (self.input_mode == InputMode::Insert || !self.input.is_empty())
+            && self.visual.is_none()
+            && self.accepts_input()
+        {
+            let x = area.x + cursor_col as u16 + 3; // +1 border +2 prompt
+            let y = area.y + 1 + (cursor_row - first_row) as u16;
             frame.set_cursor_position(Position::new(x, y));
         }
     }
 
     fn render_footer(&self, frame: &mut Frame, area: Rect) {
         let mode_str = match self.input_mode {
+            _ if self.select_mode => "SELECT",
+            _ if self.search.is_some() => "SEARCH",
+            _ if self.history_search.is_some() => "HISTORY",
+            _ if self.visual.is_some() => "VISUAL",
             InputMode::Insert => "INSERT",
             InputMode::Normal => "NORMAL",
         };
 
         let mode_color = match self.input_mode {
+            _ if self.select_mode => COLOR_SYSTEM,
             InputMode::Insert => COLOR_ACCENT,
             InputMode::Normal => COLOR_DIM,
         };
 
         // Calculate context/token/cost stats
         let total_tokens = self.total_input_tokens + self.total_output_tokens;
-        let context_window = get_context_window(&self.model);
-        let context_pct = if context_window > 0 {
-            (total_tokens as f64 / context_window as f64 * 100.0).min(100.0)
-        } else {
-            0.0
-        };
-        let cost = if self.provider == "anthropic" {
-            (self.total_input_tokens as f64 * 3.0 / 1_000_000.0)
-                + (self.total_output_tokens as f64 * 15.0 / 1_000_000.0)
-        } else {
-            (self.total_input_tokens as f64 * 0.5 / 1_000_000.0)
-                + (self.total_output_tokens as f64 * 0.5 / 1_000_000.0)
-        };
+        let context_pct = self.context_percent();
 
-        let pct_color = if context_pct > 80.0 {
+        let pct_color = if context_pct >= CONTEXT_WARNING_PERCENT {
             COLOR_ERROR
         } else if context_pct > 50.0 {
             COLOR_SYSTEM
@@ -931,7 +3602,10 @@ impl App {
 
         // Left side: MODE │ provider:model │ agent │ context% │ tokens │ cost
         let mut left = vec![
-            Span::styled(format!(" {mode_str} "), Style::default().fg(Color::Black).bg(mode_color).bold()),
+            Span::styled(
+                format!(" {mode_str} "),
+                Style::default().fg(Color::Black).bg(mode_color).bold(),
+            ),
             Span::styled(
                 format!("  {}:{}", self.provider, self.model),
                 Style::default().fg(COLOR_BAR_FG),
@@ -943,7 +3617,11 @@ impl App {
             ),
             Span::styled("  │  ", Style::default().fg(COLOR_BORDER)),
             Span::styled(
-                format!("{context_pct:.0}%"),
+                if context_pct >= CONTEXT_WARNING_PERCENT {
+                    format!("⚠ {context_pct:.0}% · /compact")
+                } else {
+                    format!("{context_pct:.0}%")
+                },
                 Style::default().fg(pct_color),
             ),
             Span::styled("  │  ", Style::default().fg(COLOR_BORDER)),
@@ -953,7 +3631,7 @@ impl App {
             ),
         ];
 
-        if cost > 0.0001 {
+        if let Some(cost) = self.total_cost {
             left.push(Span::styled("  │  ", Style::default().fg(COLOR_BORDER)));
             left.push(Span::styled(
                 format!("${cost:.4}"),
@@ -961,8 +3639,44 @@ impl App {
             ));
         }
 
-        let right_text = "/help ";
-        let used: usize = left.iter().map(|s| s.width()).sum::<usize>() + right_text.len();
+        let security_color =
+            if self.security.sandbox_mode == rot_core::SandboxMode::DangerFullAccess {
+                COLOR_ERROR
+            } else {
+                COLOR_DIM
+            };
+        left.push(Span::styled("  │  ", Style::default().fg(COLOR_BORDER)));
+        left.push(Span::styled(
+            format!(
+                "{} · {}",
+                self.security.sandbox_mode.as_str(),
+                self.security.approval_policy.as_str()
+            ),
+            Style::default().fg(security_color),
+        ));
+
+        if let Some(search) = self.search.as_ref().filter(|s| !s.editing) {
+            let status = if search.matches.is_empty() {
+                format!("/{}: no matches", search.query)
+            } else {
+                format!(
+                    "/{}: {}/{}",
+                    search.query,
+                    search.current + 1,
+                    search.matches.len()
+                )
+            };
+            left.push(Span::styled("  │  ", Style::default().fg(COLOR_BORDER)));
+            left.push(Span::styled(status, Style::default().fg(COLOR_SYSTEM)));
+        }
+
+        let right_text = if self.select_mode {
+            "select text with the mouse · Esc to return "
+        } else {
+            "/help "
+        };
+        let used: usize =
+            left.iter().map(|s| s.width()).sum::<usize>() + right_text.chars().count();
         let pad = (area.width as usize).saturating_sub(used);
 
         left.push(Span::raw(" ".repeat(pad)));
@@ -973,15 +3687,163 @@ impl App {
         frame.render_widget(bar, area);
     }
 
+    /// Share of the model's context window used by the latest request.
+    pub fn context_percent(&self) -> f64 {
+        let context_window = self.context_window();
+        if context_window == 0 {
+            return 0.0;
+        }
+        let used = self.last_input_tokens + self.last_output_tokens;
+        (used as f64 / context_window as f64 * 100.0).min(100.0)
+    }
+
     fn render_slash_menu(&self, frame: &mut Frame, input_area: Rect) {
-        let commands = self.filtered_slash_commands();
+        let commands: Vec<(String, String)> = self
+            .filtered_slash_commands()
+            .into_iter()
+            .map(|(name, desc)| (name.to_string(), desc.to_string()))
+            .collect();
+        self.render_popup_menu(frame, input_area, &commands, self.slash_menu_selected);
+    }
+
+    fn render_file_menu(&self, frame: &mut Frame, input_area: Rect) {
+        if !self.is_file_menu_active() {
+            return;
+        }
+        let files: Vec<(String, String)> = self
+            .file_matches
+            .iter()
+            .map(|path| (path.clone(), String::new()))
+            .collect();
+        self.render_popup_menu(frame, input_area, &files, self.file_menu_selected);
+    }
+
+    fn tasks_panel_height(&self) -> u16 {
+        if !self.show_tasks || self.tasks.is_empty() {
+            return 0;
+        }
+        self.tasks.len().min(TASK_PANEL_ROWS) as u16 + 2
+    }
+
+    /// Draw delegated task runs: status, agent, elapsed time, current tool,
+    /// child session, and prompt.
+    fn render_tasks_panel(&self, frame: &mut Frame, area: Rect) {
+        if area.height < 3 {
+            return;
+        }
+        let running = self.tasks.iter().filter(|task| task.is_running()).count();
+        let title = format!(
+            " tasks · {running} running · {} done ",
+            self.tasks.len() - running
+        );
+
+        let visible = self.tasks.len().min(TASK_PANEL_ROWS);
+        let start = match self.task_selected {
+            Some(selected) => selected
+                .saturating_sub(visible - 1)
+                .min(self.tasks.len() - visible),
+            None => self.tasks.len() - visible,
+        };
+        let lines: Vec<Line> = self.tasks[start..start + visible]
+            .iter()
+            .enumerate()
+            .map(|(offset, task)| {
+                let (icon, color) = match (&task.finished, &task.error) {
+                    (None, _) => (
+                        match (self.thinking_tick / 3) % 4 {
+                            0 => "⠋",
+                            1 => "⠙",
+                            2 => "⠸",
+                            _ => "⠴",
+                        },
+                        COLOR_SYSTEM,
+                    ),
+                    (Some(_), None) => ("✓", COLOR_DIFF_ADD),
+                    (Some(_), Some(_)) => ("✖", COLOR_ERROR),
+                };
+                let tool = match (&task.error, &task.current_tool) {
+                    (Some(error), _) => error.lines().next().unwrap_or("").to_string(),
+                    (None, Some(tool)) => tool.clone(),
+                    (None, None) => "—".to_string(),
+                };
+                let child = task
+                    .child_session_id
+                    .as_deref()
+                    .map(|id| id.chars().take(10).collect::<String>())
+                    .unwrap_or_else(|| "-".to_string());
+                let prompt: String = task
+                    .prompt
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .take(60)
+                    .collect();
+
+                let line = Line::from(vec![
+                    Span::styled(format!(" {icon} "), Style::default().fg(color)),
+                    Span::styled(
+                        format!("@{:<10} ", task.agent),
+                        Style::default().fg(COLOR_ACCENT),
+                    ),
+                    Span::styled(
+                        format!("{:>6}  ", format_elapsed(task.elapsed())),
+                        Style::default().fg(COLOR_DIM),
+                    ),
+                    Span::styled(format!("{:<12} ", tool), Style::default().fg(COLOR_TOOL)),
+                    Span::styled(format!("{child}  "), Style::default().fg(COLOR_DIM)),
+                    Span::styled(prompt, Style::default().fg(COLOR_BAR_FG)),
+                ]);
+                if self.task_selected == Some(start + offset) {
+                    line.style(Style::default().bg(COLOR_HEADER_BG).bold())
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(COLOR_BORDER))
+            .title(Span::styled(title, Style::default().fg(COLOR_DIM)));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn render_history_menu(&self, frame: &mut Frame, input_area: Rect) {
+        let Some(search) = &self.history_search else {
+            return;
+        };
+        let entries: Vec<(String, String)> = search
+            .matches
+            .iter()
+            .filter_map(|idx| self.history.entries().get(*idx))
+            .map(|prompt| {
+                let first_line = prompt.lines().next().unwrap_or("");
+                let first_line: String = first_line.chars().take(72).collect();
+                let more = if prompt.lines().count() > 1 {
+                    "…"
+                } else {
+                    ""
+                };
+                (first_line, more.to_string())
+            })
+            .collect();
+        self.render_popup_menu(frame, input_area, &entries, search.selected);
+    }
+
+    /// Draw a `(name, description)` picker just above the input box.
+    fn render_popup_menu(
+        &self,
+        frame: &mut Frame,
+        input_area: Rect,
+        commands: &[(String, String)],
+        selected: usize,
+    ) {
         if commands.is_empty() {
             return;
         }
 
-        let selected = self
-            .slash_menu_selected
-            .min(commands.len().saturating_sub(1));
+        let selected = selected.min(commands.len().saturating_sub(1));
         let visible_count = commands.len().min(8);
         let start = if commands.len() <= visible_count {
             0
@@ -1041,11 +3903,8 @@ impl App {
             };
 
             lines.push(Line::from(vec![
-                Span::styled(
-                    format!("{name:<width$}", width = max_name_len + 1),
-                    style,
-                ),
-                Span::styled(desc.to_string(), desc_style),
+                Span::styled(format!("{name:<width$}", width = max_name_len + 1), style),
+                Span::styled(desc.clone(), desc_style),
             ]));
         }
 
@@ -1053,72 +3912,243 @@ impl App {
         frame.render_widget(list, inner);
     }
 
-    fn render_approval_dialog(&self, frame: &mut Frame, area: Rect) {
-        use ratatui::layout::{Constraint, Direction, Layout};
-        use ratatui::widgets::Clear;
+    fn render_help_overlay(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(help) = self.help.as_mut() else {
+            return;
+        };
 
-        // Create a centered rect for the dialog
-        let vertical = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Percentage(30),
-                Constraint::Min(10), // height of dialog
-                Constraint::Percentage(30),
-            ])
-            .split(area);
+        let width = (area.width * 4 / 5).max(60).min(area.width);
+        let height = (area.height * 4 / 5).max(12).min(area.height);
+        let popup_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup_area);
 
-        let horizontal = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(20),
-                Constraint::Min(60), // width of dialog
-                Constraint::Percentage(20),
-            ])
-            .split(vertical[1]);
+        let block = Block::default()
+            .title(" Help (type to search · Esc close) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(COLOR_BANNER));
+        let inner = block.inner(popup_area).inner(Margin::new(1, 0));
+        frame.render_widget(block, popup_area);
+        if inner.height < 3 {
+            return;
+        }
 
-        let dialog_area = horizontal[1];
-        
-        // Clear background behind dialog
-        frame.render_widget(Clear, dialog_area);
+        let search = Line::from(vec![
+            Span::styled("Search: ", Style::default().fg(COLOR_DIM)),
+            Span::raw(help.query.as_str()),
+            Span::styled("▏", Style::default().fg(COLOR_ACCENT)),
+        ]);
+        frame.render_widget(Paragraph::new(search), Rect { height: 1, ..inner });
 
-        let tool_name = self.pending_approval_tool.as_deref().unwrap_or("unknown");
-        let args_str = self
-            .pending_approval_args
-            .as_ref()
-            .map(|a| serde_json::to_string_pretty(a).unwrap_or_default())
-            .unwrap_or_default();
+        let body = Rect {
+            y: inner.y + 2,
+            height: inner.height - 2,
+            ..inner
+        };
+        let rows = help.lines(&self.security);
+        let key_width = rows
+            .iter()
+            .filter_map(|row| match row {
+                HelpLine::Entry { key, .. } => Some(key.chars().count()),
+                HelpLine::Heading(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Line> = if rows.is_empty() {
+            vec![Line::from(Span::styled(
+                format!("Nothing matches \"{}\".", help.query),
+                Style::default().fg(COLOR_DIM),
+            ))]
+        } else {
+            rows.iter()
+                .map(|row| match row {
+                    HelpLine::Heading(title) => Line::from(Span::styled(
+                        title.as_str(),
+                        Style::default().fg(COLOR_ACCENT).bold(),
+                    )),
+                    HelpLine::Entry { key, description } => Line::from(vec![
+                        Span::styled(
+                            format!("  {key:<key_width$}  "),
+                            Style::default().fg(COLOR_CODE_FG),
+                        ),
+                        Span::raw(description.as_str()),
+                    ]),
+                })
+                .collect()
+        };
+        let visible = body.height as usize;
+        let scroll = help.scroll.min(lines.len().saturating_sub(visible));
+        help.scroll = scroll;
+        help.page = visible;
+        frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), body);
+    }
+
+    fn render_approval_dialog(&mut self, frame: &mut Frame, area: Rect) {
+        use ratatui::layout::{Constraint, Direction, Layout, Position};
+
+        let Some(prompt) = self.approval.as_mut() else {
+            return;
+        };
+
+        // Large enough for long commands and diffs, centered.
+        let width = (area.width * 4 / 5).max(60).min(area.width);
+        let height = (area.height * 4 / 5).max(12).min(area.height);
+        let dialog_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, dialog_area);
 
         let block = Block::default()
-            .title(format!(" ⚠ Permission Request: {} ", tool_name))
+            .title(format!(" ⚠ Permission Request: {} ", prompt.tool))
             .title_style(Style::default().fg(COLOR_ERROR).bold())
             .borders(Borders::ALL)
             .border_style(Style::default().fg(COLOR_ERROR))
             .border_type(ratatui::widgets::BorderType::Thick);
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
 
-        let mut lines = vec![
-            Line::from(Span::styled("rot wants to execute the following tool:", Style::default().fg(COLOR_CODE_FG))),
-            Line::from(""),
-        ];
-
-        for arg_line in args_str.lines() {
-            lines.push(Line::from(Span::styled(format!("  {}", arg_line), Style::default().fg(COLOR_DIM))));
-        }
-
-        lines.push(Line::from(""));
-        let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
-        frame.render_widget(paragraph, dialog_area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2),
+                Constraint::Min(1),
+                Constraint::Length(u16::from(prompt.error.is_some())),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+        let body = chunks[1].inner(Margin::new(1, 0));
+
+        let heading = match (&prompt.edit, prompt.kind) {
+            (Some(_), PreviewKind::Command) => "Edit the command, then press Enter to approve it:",
+            (Some(_), _) => "Edit the arguments (JSON), then press Enter to approve them:",
+            (None, PreviewKind::Command) => "rot wants to run this command:",
+            (None, PreviewKind::Diff) => "rot wants to make this change:",
+            (None, PreviewKind::Network) => "A tool wants to connect to a new host:",
+            (None, PreviewKind::Json) => "rot wants to execute the following tool:",
+        };
+        frame.render_widget(
+            Paragraph::new(Span::styled(heading, Style::default().fg(COLOR_CODE_FG))),
+            chunks[0],
+        );
 
-        let yes_btn = Span::styled(" [y]es ", Style::default().fg(COLOR_ASSISTANT).bold());
-        let always_btn = Span::styled(" [a]lways ", Style::default().fg(COLOR_SYSTEM).bold());
-        let no_btn = Span::styled(" [n]o ", Style::default().fg(COLOR_ERROR).bold());
-        let deny_btn = Span::styled(" [d]eny ", Style::default().fg(COLOR_DIM).bold());
+        let key = |label: &'static str, color: Color| {
+            Span::styled(label, Style::default().fg(color).bold())
+        };
+        let footer = if let Some(edit) = prompt.edit.as_mut() {
+            edit.width = body.width.max(1) as usize;
+            let rows = crate::editor::wrap(&edit.text, edit.width);
+            let (cursor_row, cursor_col) =
+                crate::editor::cursor_position(&edit.text, &rows, edit.cursor);
+            let visible = body.height.max(1) as usize;
+            let first_row = (cursor_row + 1).saturating_sub(visible);
+            let lines: Vec<Line> = rows
+                .iter()
+                .skip(first_row)
+                .take(visible)
+                .map(|row| {
+                    Line::from(Span::styled(
+                        &edit.text[row.start..row.end],
+                        Style::default().fg(COLOR_CODE_FG),
+                    ))
+                })
+                .collect();
+            frame.render_widget(Paragraph::new(lines), body);
+            frame.set_cursor_position(Position::new(
+                body.x + cursor_col as u16,
+                body.y + (cursor_row - first_row) as u16,
+            ));
+            Line::from(vec![
+                key(" Enter ", COLOR_ASSISTANT),
+                Span::raw("approve  "),
+                key(" Alt+Enter ", COLOR_SYSTEM),
+                Span::raw("newline  "),
+                key(" Esc ", COLOR_DIM),
+                Span::raw("back "),
+            ])
+        } else {
+            let lines: Vec<Line> = prompt
+                .preview
+                .iter()
+                .map(|line| match prompt.kind {
+                    PreviewKind::Diff => diff_line(line),
+                    // The command itself; its other arguments follow dimmed.
+                    PreviewKind::Command if line.starts_with('$') || line.starts_with("  ") => {
+                        Line::from(Span::styled(
+                            line.as_str(),
+                            Style::default().fg(COLOR_CODE_FG).bold(),
+                        ))
+                    }
+                    _ => Line::from(Span::styled(line.as_str(), Style::default().fg(COLOR_DIM))),
+                })
+                .collect();
+            let total: usize = lines
+                .iter()
+                .map(|line| wrapped_rows(line, body.width))
+                .sum();
+            let visible = body.height as usize;
+            let max_scroll = total.saturating_sub(visible);
+            let scroll = prompt.scroll.min(max_scroll);
+            frame.render_widget(
+                Paragraph::new(lines)
+                    .wrap(Wrap { trim: false })
+                    .scroll((scroll as u16, 0)),
+                body,
+            );
+            prompt.scroll = scroll;
+            prompt.page = visible;
+
+            let mut spans = if prompt.kind == PreviewKind::Network {
+                vec![
+                    Span::raw(" Allow network access? "),
+                    key(" [y]es ", COLOR_ASSISTANT),
+                    key(" [a]lways for this project ", COLOR_SYSTEM),
+                    key(" [n]o ", COLOR_ERROR),
+                    key(" [d]eny ", COLOR_DIM),
+                ]
+            } else {
+                vec![
+                    Span::raw(" Allow execution? "),
+                    key(" [y]es ", COLOR_ASSISTANT),
+                    key(" [a]lways ", COLOR_SYSTEM),
+                    key(" [n]o ", COLOR_ERROR),
+                    key(" [d]eny ", COLOR_DIM),
+                    key(" [e]dit ", COLOR_ACCENT),
+                ]
+            };
+            if max_scroll > 0 {
+                spans.push(Span::styled(
+                    format!(
+                        "  j/k scroll · {}-{} of {} ",
+                        scroll + 1,
+                        (scroll + visible).min(total),
+                        total
+                    ),
+                    Style::default().fg(COLOR_DIM),
+                ));
+            }
+            Line::from(spans)
+        };
 
-        let footer = Line::from(vec![
-            Span::raw(" Allow execution? "),
-            yes_btn, always_btn, no_btn, deny_btn,
-        ]);
-        let footer_p = Paragraph::new(footer).alignment(Alignment::Center);
-        frame.render_widget(footer_p, dialog_area); // Using dialog_area here instead of chunks[1] 
+        if let Some(error) = &prompt.error {
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    format!(" {error}"),
+                    Style::default().fg(COLOR_ERROR),
+                )),
+                chunks[2],
+            );
+        }
+        frame.render_widget(
+            Paragraph::new(footer).alignment(Alignment::Center),
+            chunks[3],
+        );
     }
 
     fn render_config_dialog(&self, frame: &mut Frame, area: Rect) {
@@ -1127,7 +4157,7 @@ impl App {
         use ratatui::widgets::{Block, Borders, Paragraph};
         use ratatui::layout::Alignment;
         let (desired_width, desired_height) = match self.config_ui_state {
-            ConfigUiState::List(_) => (50, 15),
+            ConfigUiState::List(_) => (area.width.saturating_sub(6).clamp(40, 84), 20),
             ConfigUiState::InputKey { .. } => (area.width.saturating_sub(6).clamp(40, 96), 12),
         };
 
@@ -1156,23 +4186,103 @@ impl App {
 
         match &self.config_ui_state {
             ConfigUiState::List(selected_idx) => {
-                let items: Vec<ratatui::widgets::ListItem> = AVAILABLE_MODELS
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Length(1), // providers
+                        Constraint::Length(1), // column headings
+                        Constraint::Min(1),    // models
+                        Constraint::Length(1), // status
+                    ])
+                    .split(inner);
+
+                let tabs: Vec<Span> = self
+                    .model_providers()
+                    .into_iter()
+                    .flat_map(|name| {
+                        let style = if name == self.picker_provider {
+                            Style::default().fg(COLOR_ACCENT).bold().reversed()
+                        } else {
+                            Style::default().fg(COLOR_DIM)
+                        };
+                        [Span::styled(format!(" {name} "), style), Span::raw(" ")]
+                    })
+                    .collect();
+                frame.render_widget(Paragraph::new(Line::from(tabs)), chunks[0]);
+
+                // Marker, id, then context and input/output price columns.
+                let id_width = (chunks[2].width as usize)
+                    .saturating_sub(3 + 9 + 10 + 10)
+                    .max(10);
+                let heading = format!(
+                    "   {:<id_width$}{:>9}{:>10}{:>10}",
+                    "Model", "Context", "$/M in", "$/M out"
+                );
+                frame.render_widget(
+                    Paragraph::new(Span::styled(heading, Style::default().fg(COLOR_DIM).bold())),
+                    chunks[1],
+                );
+
+                let price = |per_mtok: Option<f64>| {
+                    per_mtok.map_or("—".to_string(), |p| format!("${p:.2}"))
+                };
+                let items: Vec<ratatui::widgets::ListItem> = self
+                    .picker_models()
                     .iter()
                     .enumerate()
-                    .map(|(i, (p, m))| {
-                        let mut style = Style::default().fg(COLOR_CODE_FG);
-                        let prefix = if i == *selected_idx {
-                            style = style.fg(COLOR_ACCENT).bold();
-                            " ▶ "
+                    .map(|(i, m)| {
+                        let current = self.picker_provider == self.provider && m.id == self.model;
+                        let (prefix, style) = if i == *selected_idx {
+                            (" ▶ ", Style::default().fg(COLOR_ACCENT).bold())
+                        } else if current {
+                            (" ● ", Style::default().fg(COLOR_CODE_FG))
+                        } else {
+                            ("   ", Style::default().fg(COLOR_CODE_FG))
+                        };
+                        let id = if m.id.chars().count() > id_width {
+                            format!("{}…", m.id.chars().take(id_width - 1).collect::<String>())
                         } else {
-                            "   "
+                            m.id.clone()
                         };
-                        ratatui::widgets::ListItem::new(format!("{prefix}{} / {}", p, m)).style(style)
+                        let pricing = rot_provider::pricing_for(&m.id);
+                        ratatui::widgets::ListItem::new(format!(
+                            "{prefix}{id:<id_width$}{:>9}{:>10}{:>10}",
+                            format_context_window(m.context_window),
+                            price(pricing.map(|p| p.input_per_mtok)),
+                            price(pricing.map(|p| p.output_per_mtok)),
+                        ))
+                        .style(style)
                     })
                     .collect();
+                let mut list_state =
+                    ratatui::widgets::ListState::default().with_selected(Some(*selected_idx));
+                frame.render_stateful_widget(
+                    ratatui::widgets::List::new(items),
+                    chunks[2],
+                    &mut list_state,
+                );
 
-                let list = ratatui::widgets::List::new(items);
-                frame.render_widget(list, inner);
+                let hints = "←/→ provider · r refresh · Enter select";
+                let status = match self.model_listings.get(&self.picker_provider) {
+                    None | Some(ModelListing::Loading) => {
+                        Span::styled(" Loading models…", Style::default().fg(COLOR_DIM))
+                    }
+                    Some(ModelListing::Live(models)) => Span::styled(
+                        format!(" {} models · {hints}", models.len()),
+                        Style::default().fg(COLOR_DIM),
+                    ),
+                    Some(ModelListing::Builtin { reason, .. }) => Span::styled(
+                        format!(
+                            " Built-in list ({}) · {hints}",
+                            reason.lines().next().unwrap_or("")
+                        ),
+                        Style::default().fg(COLOR_SYSTEM),
+                    ),
+                    Some(ModelListing::Failed(e)) => {
+                        Span::styled(format!(" {e}"), Style::default().fg(COLOR_ERROR))
+                    }
+                };
+                frame.render_widget(Paragraph::new(status), chunks[3]);
             }
             ConfigUiState::InputKey { provider, input, cursor_pos, .. } => {
                 let esc_hint = Paragraph::new("esc")
@@ -1203,10 +4313,7 @@ impl App {
                 frame.render_widget(provider_label, chunks[0]);
 
                 let (input_text, input_style) = if input.is_empty() {
-                    (
-                        "API key".to_string(),
-                        Style::default().fg(COLOR_DIM),
-                    )
+                    ("API key".to_string(), Style::default().fg(COLOR_DIM))
                 } else {
                     (
                         input.clone(),
@@ -1263,7 +4370,11 @@ impl App {
             .enumerate()
             .map(|(idx, profile)| {
                 let selected = idx == self.agent_menu_selected;
-                let mode = if profile.is_subagent() { "subagent" } else { "primary" };
+                let mode = if profile.is_subagent() {
+                    "subagent"
+                } else {
+                    "primary"
+                };
                 let prefix = if selected { " ▶ " } else { "   " };
                 let style = if selected {
                     Style::default().fg(COLOR_ACCENT).bold()
@@ -1281,6 +4392,59 @@ impl App {
         frame.render_widget(List::new(items), inner);
     }
 
+    fn render_sessions_dialog(&self, frame: &mut Frame, area: Rect) {
+        use ratatui::widgets::{Clear, List, ListItem};
+
+        if self.session_choices.is_empty() {
+            return;
+        }
+
+        let height = (self.session_choices.len() as u16 + 4).min(16);
+        let popup_area = Rect {
+            x: area.x + (area.width.saturating_sub(80)) / 2,
+            y: area.y + (area.height.saturating_sub(height)) / 2,
+            width: 80.min(area.width),
+            height: height.min(area.height),
+        };
+
+        frame.render_widget(Clear, popup_area);
+
+        let block = Block::default()
+            .title(" Resume Session (Enter resume · Esc new session) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(COLOR_BANNER));
+        let inner = block.inner(popup_area);
+        frame.render_widget(block, popup_area);
+
+        let now = unix_now();
+        let title_width = (inner.width as usize).saturating_sub(40).max(10);
+        let items: Vec<ListItem> = self
+            .session_choices
+            .iter()
+            .enumerate()
+            .map(|(idx, meta)| {
+                let selected = idx == self.session_menu_selected;
+                let prefix = if selected { " ▶ " } else { "   " };
+                let style = if selected {
+                    Style::default().fg(COLOR_ACCENT).bold()
+                } else {
+                    Style::default().fg(COLOR_CODE_FG)
+                };
+                let title = meta.title.as_deref().unwrap_or("(untitled)");
+                let title: String = title.chars().take(title_width).collect();
+                ListItem::new(format!(
+                    "{prefix}{title:<title_width$} {:>8}  {:<16} {:>4} msgs",
+                    format_age(now, meta.updated_at),
+                    meta.model.chars().take(16).collect::<String>(),
+                    meta.message_count,
+                ))
+                .style(style)
+            })
+            .collect();
+
+        frame.render_widget(List::new(items), inner);
+    }
+
     // ── Markdown Parser ────────────────────────────────────────────────
 
     fn parse_markdown<'a>(text: &'a str, base: Style) -> Vec<Span<'a>> {
@@ -1348,6 +4512,29 @@ impl App {
         spans
     }
 
+    /// Header text for an RLM run: its step and the tokens used so far.
+    fn rlm_segment(progress: &rot_rlm::RlmProgress) -> String {
+        use rot_rlm::RlmPhase;
+        let step = match progress.phase {
+            RlmPhase::Iteration => format!("iter {}/{}", progress.step, progress.total),
+            RlmPhase::Chunk => format!("chunk {}/{}", progress.step, progress.total),
+            RlmPhase::Reduce if progress.step == 0 => "reduce".to_string(),
+            RlmPhase::Reduce => format!("reduce {}/{}", progress.step, progress.total),
+            RlmPhase::Select => format!("select {}", progress.total),
+            RlmPhase::BranchFailed => "branch failed".to_string(),
+            RlmPhase::Done => "done".to_string(),
+            RlmPhase::Cached => "cached".to_string(),
+        };
+        let branch = progress
+            .branch
+            .map(|(branch, branches)| format!("branch {branch}/{branches} "))
+            .unwrap_or_default();
+        format!(
+            "{branch}{step} · {} tok",
+            Self::format_number(progress.total_tokens())
+        )
+    }
+
     fn format_number(n: usize) -> String {
         if n >= 1_000_000 {
             format!("{:.1}M", n as f64 / 1_000_000.0)
@@ -1396,6 +4583,70 @@ mod tests {
         assert!(app.input.is_empty());
     }
 
+    #[test]
+    fn test_tool_diff_collapses_and_toggles() {
+        use crossterm::event::KeyCode;
+
+        let mut app = App::new("test", "test", "default");
+        assert!(!app.handle_block_key(KeyCode::Enter));
+
+        let long_diff = std::iter::once("@@ -1,30 +1,30 @@".to_string())
+            .chain((0..30).map(|i| format!("+line {i}")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        app.push_tool_exchange(&rot_core::message::ToolExchange {
+            name: "write".to_string(),
+            arguments: serde_json::json!({"path": "src/lib.rs"}),
+            output: Some("Wrote".to_string()),
+            is_error: false,
+            metadata: serde_json::json!({"diff": long_diff}),
+        });
+        app.push_tool_exchange(&rot_core::message::ToolExchange {
+            name: "edit".to_string(),
+            arguments: serde_json::json!({"path": "a.txt"}),
+            output: Some("Replaced".to_string()),
+            is_error: false,
+            metadata: serde_json::json!({"diff": "--- a.txt\n+++ a.txt\n-old\n+new"}),
+        });
+
+        assert_eq!(
+            app.chat_lines[0].content,
+            "↳ write src/lib.rs (+30 -0) · ok"
+        );
+        assert!(!diff_fits(app.chat_lines[0].diff.as_deref().unwrap()));
+        assert_eq!(app.chat_lines[1].content, "↳ edit a.txt (+1 -1) · ok");
+        assert!(diff_fits(app.chat_lines[1].diff.as_deref().unwrap()));
+
+        app.handle_block_key(KeyCode::Enter);
+        assert!(app.chat_lines[1].expanded);
+        app.handle_block_key(KeyCode::BackTab);
+        assert_eq!(app.selected_chat, Some(0));
+        app.handle_block_key(KeyCode::Char(' '));
+        assert!(app.chat_lines[0].expanded);
+    }
+
+    #[test]
+    fn test_tool_summary_shows_key_argument_and_duration() {
+        let mut app = App::new("test", "test", "default");
+        app.push_tool_exchange(&rot_core::message::ToolExchange {
+            name: "bash".to_string(),
+            arguments: serde_json::json!({"command": "cargo test\ncargo clippy", "timeout": 60}),
+            output: Some("error: failed".to_string()),
+            is_error: true,
+            metadata: serde_json::json!({"duration_ms": 2500}),
+        });
+
+        let line = &app.chat_lines[0];
+        assert_eq!(line.content, "↳ bash cargo test… · 2.5s · error");
+        assert!(matches!(line.style, ChatStyle::Error));
+        assert!(line.is_expandable());
+        assert!(line
+            .detail
+            .as_deref()
+            .unwrap()
+            .ends_with("output:\nerror: failed"));
+    }
+
     #[test]
     fn test_push_chat() {
         let mut app = App::new("test", "test", "default");
@@ -1416,7 +4667,16 @@ mod tests {
     fn test_slash_help() {
         let mut app = App::new("test", "test", "default");
         assert!(app.handle_slash_command("/help"));
-        assert_eq!(app.chat_lines.len(), 1);
+        assert!(app.chat_lines.is_empty());
+        assert!(app.help.is_some());
+
+        let esc = crossterm::event::KeyEvent::new(
+            crossterm::event::KeyCode::Esc,
+            crossterm::event::KeyModifiers::NONE,
+        );
+        assert!(app.handle_help_key(esc));
+        assert!(app.help.is_none());
+        assert!(!app.handle_help_key(esc));
     }
 
     #[test]
@@ -1427,6 +4687,70 @@ mod tests {
         assert_eq!(app.chat_lines.len(), 1);
     }
 
+    #[test]
+    fn test_change_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let mut app = App::new("test", "test", "default");
+        app.workspace = dir.path().to_path_buf();
+        let target = dir.path().join("sub").display().to_string();
+
+        assert!(!app.handle_slash_command(&format!("/cd {target}")));
+        let changed = app.change_working_dir(&target).unwrap();
+        assert_eq!(changed, dir.path().join("sub").canonicalize().unwrap());
+        assert_eq!(app.working_dir(), changed);
+        assert!(app.change_working_dir("missing").is_err());
+        assert!(app
+            .change_working_dir("../..")
+            .unwrap_err()
+            .contains("outside the workspace"));
+        assert_eq!(app.working_dir(), changed);
+
+        app.state = AppState::Thinking;
+        assert!(app.change_working_dir("..").is_err());
+    }
+
+    #[test]
+    fn test_slash_context_sets_rlm_context() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "# notes\n").unwrap();
+        let mut app = App::new("test", "test", "default");
+        app.rlm_enabled = false;
+
+        let missing = format!("/context {}/*.rs", dir.path().display());
+        assert!(app.handle_slash_command(&missing));
+        assert_eq!(app.rlm_context, None);
+        assert!(matches!(
+            app.chat_lines.last().unwrap().style,
+            ChatStyle::Error
+        ));
+
+        let glob = format!("{}/*.md", dir.path().display());
+        assert!(app.handle_slash_command(&format!("/context {glob}")));
+        assert_eq!(app.rlm_context.as_deref(), Some(glob.as_str()));
+        assert!(app.rlm_enabled);
+    }
+
+    #[test]
+    fn test_slash_model_resolves_alias() {
+        let mut app = App::new("claude", "anthropic", "default");
+        app.model_aliases
+            .insert("fast".to_string(), "zai/glm-4.7".to_string());
+
+        assert!(app.handle_slash_command("/model fast"));
+        assert_eq!(app.provider, "zai");
+        assert_eq!(app.model, "glm-4.7");
+        assert!(app.config_changed);
+        assert_eq!(app.state, AppState::Idle);
+
+        assert!(app.handle_slash_command("/model glm-5"));
+        assert_eq!(app.provider, "zai");
+        assert_eq!(app.model, "glm-5");
+
+        assert!(app.handle_slash_command("/model"));
+        assert_eq!(app.state, AppState::Config);
+    }
+
     #[test]
     fn test_slash_unknown() {
         let mut app = App::new("test", "test", "default");
@@ -1461,6 +4785,74 @@ mod tests {
         assert_eq!(spans.len(), 3);
     }
 
+    #[test]
+    fn test_file_mention_completion() {
+        let mut app = App::new("test", "test", "default");
+        app.workspace_files = Some(vec![
+            "src/main.rs".to_string(),
+            "docs/review.md".to_string(),
+        ]);
+        for c in "see @mai".chars() {
+            app.insert_char(c);
+        }
+        assert!(app.is_file_menu_active());
+        assert_eq!(app.file_matches, vec!["src/main.rs"]);
+
+        app.accept_file_completion();
+        assert_eq!(app.input, "see @src/main.rs ");
+        assert_eq!(app.cursor_pos, app.input.len());
+        assert!(!app.is_file_menu_active());
+
+        app.input = "@revie".to_string();
+        app.cursor_pos = app.input.len();
+        app.insert_char('w');
+        assert!(
+            !app.is_file_menu_active(),
+            "@review routes to the review agent"
+        );
+    }
+
+    #[test]
+    fn test_search_keys_cycle_matches() {
+        use crossterm::event::KeyCode;
+
+        let mut app = App::new("test", "test", "default");
+        assert!(!app.handle_search_key(KeyCode::Char('n')));
+        assert!(app.handle_search_key(KeyCode::Char('/')));
+        for c in "Foo".chars() {
+            app.handle_search_key(KeyCode::Char(c));
+        }
+        app.handle_search_key(KeyCode::Enter);
+        let search = app.search.as_mut().unwrap();
+        assert_eq!(search.query, "Foo");
+        assert!(!search.editing);
+
+        search.matches = vec![(0, 0, 3), (4, 2, 5)];
+        app.handle_search_key(KeyCode::Char('N'));
+        assert_eq!(app.search.as_ref().unwrap().current, 1);
+        app.handle_search_key(KeyCode::Char('n'));
+        assert_eq!(app.search.as_ref().unwrap().current, 0);
+        assert!(!app.handle_search_key(KeyCode::Char('j')));
+        app.handle_search_key(KeyCode::Esc);
+        assert!(app.search.is_none());
+    }
+
+    #[test]
+    fn test_search_highlights_across_spans() {
+        assert_eq!(find_matches("a FOO and foo", "foo"), vec![(2, 5), (10, 13)]);
+
+        let line = Line::from(vec![Span::raw("xfo"), Span::raw("oy foo")]);
+        let highlighted = highlight_line(line, &[(1, 4), (6, 9)], Some(1));
+        let parts: Vec<&str> = highlighted
+            .spans
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(parts, vec!["x", "fo", "o", "y ", "foo"]);
+        assert_eq!(highlighted.spans[4].style.bg, Some(COLOR_ACCENT));
+        assert_eq!(highlighted.spans[1].style.bg, Some(COLOR_SYSTEM));
+    }
+
     #[test]
     fn test_tokens() {
         let mut app = App::new("test", "test", "default");
@@ -1477,6 +4869,26 @@ mod tests {
         assert_eq!(App::format_number(1_500_000), "1.5M");
     }
 
+    #[test]
+    fn test_rlm_segment_shows_step_and_tokens() {
+        let mut progress = rot_rlm::RlmProgress {
+            phase: rot_rlm::RlmPhase::Iteration,
+            step: 3,
+            total: 30,
+            branch: None,
+            input_tokens: 12_000,
+            output_tokens: 300,
+            goal: None,
+        };
+        assert_eq!(App::rlm_segment(&progress), "iter 3/30 · 12.3k tok");
+        progress.phase = rot_rlm::RlmPhase::Chunk;
+        progress.branch = Some((2, 3));
+        assert_eq!(
+            App::rlm_segment(&progress),
+            "branch 2/3 chunk 3/30 · 12.3k tok"
+        );
+    }
+
     #[test]
     fn test_welcome_once() {
         let mut app = App::new("test", "test", "default");
@@ -1560,4 +4972,806 @@ mod tests {
         assert_eq!(parsed.0, "review");
         assert_eq!(parsed.1, "inspect this diff");
     }
+
+    #[test]
+    fn test_record_iteration_warns_once_near_limit() {
+        let mut app = App::new("test", "test", "default");
+        let progress = |iteration| rot_core::IterationProgress {
+            iteration,
+            max_iterations: 10,
+            input_tokens: 0,
+            output_tokens: 0,
+        };
+
+        app.record_iteration(progress(1));
+        let baseline = app.chat_lines.len();
+        app.record_iteration(progress(8));
+        app.record_iteration(progress(9));
+
+        assert_eq!(app.chat_lines.len(), baseline + 1);
+        assert!(app.chat_lines.last().unwrap().content.contains("8/10"));
+        assert_eq!(app.iteration_progress.unwrap().iteration, 9);
+    }
+
+    #[test]
+    fn test_queue_input_while_thinking() {
+        let mut app = App::new("test", "test", "default");
+        app.state = AppState::Thinking;
+        assert!(app.accepts_input());
+
+        assert!(!app.queue_input());
+        app.input = "also update the docs".to_string();
+        app.cursor_pos = app.input.len();
+        assert!(app.queue_input());
+
+        assert!(app.input.is_empty());
+        assert_eq!(app.queued_count(), 1);
+        assert_eq!(
+            app.take_queued_inputs(),
+            vec!["also update the docs".to_string()]
+        );
+        assert_eq!(app.queued_count(), 0);
+    }
+
+    #[test]
+    fn test_begin_edit_steps_back_through_prompts() {
+        let mut app = App::new("test", "test", "default");
+        let prompts = vec!["first".to_string(), "second".to_string()];
+
+        assert!(app.begin_edit(&prompts));
+        assert_eq!(app.editing_prompt, Some(1));
+        assert_eq!(app.input, "second");
+
+        assert!(app.begin_edit(&prompts));
+        assert_eq!(app.editing_prompt, Some(0));
+        assert_eq!(app.input, "first");
+
+        assert!(app.begin_edit(&prompts));
+        assert_eq!(app.editing_prompt, Some(0));
+
+        app.cancel_edit();
+        assert!(app.editing_prompt.is_none());
+        assert!(app.input.is_empty());
+        assert!(!app.begin_edit(&[]));
+    }
+
+    #[test]
+    fn test_slash_edit_is_reserved_for_runner() {
+        let mut app = App::new("test", "test", "default");
+        assert!(!app.handle_slash_command("/edit"));
+    }
+
+    #[test]
+    fn test_load_transcript_replays_turns() {
+        use rot_core::{ContentBlock, Message};
+
+        let mut app = App::new("test", "test", "default");
+        app.show_welcome();
+        let messages = vec![
+            Message::user("fix @src/lib.rs\n\n<file path=\"src/lib.rs\">\nfn f() {}\n</file>"),
+            Message::assistant(vec![ContentBlock::ToolCall {
+                id: "call-1".to_string(),
+                name: "read".to_string(),
+                arguments: serde_json::json!({"path": "src/lib.rs"}),
+            }]),
+            Message::tool_result("call-1", "fn f() {}", false),
+            Message::assistant(vec![ContentBlock::Text {
+                text: "Done.".to_string(),
+            }]),
+            Message::user("thanks"),
+        ];
+
+        app.load_transcript(&messages);
+        let lines: Vec<(&str, &str)> = app
+            .chat_lines
+            .iter()
+            .map(|l| (l.role.as_str(), l.content.as_str()))
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], ("you", "fix @src/lib.rs"));
+        assert!(lines[1].1.starts_with("↳ read src/lib.rs"));
+        assert_eq!(lines[2], ("rot", "Done."));
+        assert_eq!(lines[3], ("you", "thanks"));
+        assert_eq!(app.message_count, 2);
+    }
+
+    #[test]
+    fn test_rewind_transcript_drops_replaced_turns() {
+        use rot_core::{ContentBlock, Message};
+
+        let reply = |text: &str| {
+            Message::assistant(vec![ContentBlock::Text {
+                text: text.to_string(),
+            }])
+        };
+        let mut app = App::new("test", "test", "default");
+        let mut messages = vec![
+            Message::user("first"),
+            reply("one"),
+            Message::user("second"),
+            reply("two"),
+        ];
+        app.load_transcript(&messages);
+        app.total_input_tokens = 100;
+
+        rot_core::message::rewind_to_user_prompt(&mut messages, 1).unwrap();
+        app.rewind_transcript(&messages);
+        let lines: Vec<&str> = app.chat_lines.iter().map(|l| l.content.as_str()).collect();
+        assert_eq!(lines, ["first", "one"]);
+        assert_eq!(app.message_count, 1);
+        assert_eq!(app.total_input_tokens, 100);
+    }
+
+    #[test]
+    fn test_session_picker_selection() {
+        let mut app = App::new("test", "test", "default");
+        app.open_session_picker(Vec::new());
+        assert_eq!(app.state, AppState::Idle);
+
+        let meta = |id: &str| rot_session::SessionMeta {
+            id: id.to_string(),
+            created_at: 0,
+            updated_at: 0,
+            title: None,
+            cwd: ".".to_string(),
+            model: "test".to_string(),
+            provider: "test".to_string(),
+            parent_session_id: None,
+            agent: None,
+            message_count: 2,
+        };
+        app.open_session_picker(vec![meta("a"), meta("b")]);
+        assert_eq!(app.state, AppState::Sessions);
+        app.move_session_selection_up();
+        assert_eq!(
+            app.take_selected_session().map(|m| m.id),
+            Some("b".to_string())
+        );
+        assert_eq!(app.state, AppState::Idle);
+        assert!(app.session_choices.is_empty());
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(100, 90), "just now");
+        assert_eq!(format_age(7_200, 0), "2h ago");
+        assert_eq!(format_age(3 * 86_400, 0), "3d ago");
+    }
+
+    #[test]
+    fn test_history_up_down_from_empty_input() {
+        let mut app = App::new("test", "test", "default");
+        for prompt in ["first", "second"] {
+            app.input = prompt.to_string();
+            app.submit_input();
+        }
+
+        assert!(app.history_prev());
+        assert_eq!(app.input, "second");
+        assert!(app.history_prev());
+        assert!(app.history_prev());
+        assert_eq!(app.input, "first");
+        assert!(app.history_next());
+        assert!(app.history_next());
+        assert!(app.input.is_empty());
+        assert!(!app.history_next());
+
+        app.insert_char('x');
+        assert!(!app.history_prev());
+    }
+
+    #[test]
+    fn test_history_search_fills_input() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = App::new("test", "test", "default");
+        for prompt in ["run the tests", "refactor parser", "explain lib"] {
+            app.input = prompt.to_string();
+            app.submit_input();
+        }
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert!(!app.handle_history_search_key(key(KeyCode::Char('r'))));
+        assert!(
+            app.handle_history_search_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL))
+        );
+        for c in "parse".chars() {
+            assert!(app.handle_history_search_key(key(KeyCode::Char(c))));
+        }
+        assert!(app.handle_history_search_key(key(KeyCode::Enter)));
+        assert!(app.history_search.is_none());
+        assert_eq!(app.input, "refactor parser");
+    }
+
+    #[test]
+    fn test_slash_attach_queues_and_clears_images() {
+        let mut app = App::new("test", "test", "default");
+        assert!(app.handle_slash_command("/attach definitely-missing.png"));
+        assert!(app.pending_images.is_empty());
+        assert!(matches!(
+            app.chat_lines.last().unwrap().style,
+            ChatStyle::Error
+        ));
+
+        app.pending_images.push(crate::images::ImageAttachment {
+            name: "shot.png".to_string(),
+            size: 10,
+            block: rot_core::ContentBlock::Image {
+                data: String::new(),
+                mime_type: "image/png".to_string(),
+            },
+        });
+        assert!(app.handle_slash_command("/attach"));
+        assert!(app.pending_images.is_empty());
+    }
+
+    #[test]
+    fn test_copy_keys_select_messages() {
+        use crossterm::event::KeyCode;
+
+        let mut app = App::new("test", "test", "default");
+        app.show_welcome();
+        assert!(!app.handle_copy_key(KeyCode::Char('[')));
+
+        app.push_chat("you", "hello", ChatStyle::User);
+        app.push_chat("rot", "no code here", ChatStyle::Assistant);
+        assert!(app.handle_copy_key(KeyCode::Char('[')));
+        assert_eq!(app.selected_chat, Some(2));
+        assert!(app.handle_copy_key(KeyCode::Char('[')));
+        assert_eq!(app.selected_chat, Some(1));
+        assert!(app.handle_copy_key(KeyCode::Char(']')));
+        assert_eq!(app.selected_chat, Some(2));
+
+        assert!(app.handle_copy_key(KeyCode::Char('Y')));
+        assert_eq!(app.status, "No code block to copy");
+        assert!(!app.handle_copy_key(KeyCode::Char('x')));
+    }
+
+    #[test]
+    fn test_task_events_fill_tasks_panel() {
+        use rot_core::TaskEvent;
+
+        let mut app = App::new("test-model", "test-provider", "build");
+        assert!(!app.show_tasks);
+
+        app.record_task_event(TaskEvent::Started {
+            task_id: "t1".to_string(),
+            agent: "explore".to_string(),
+            prompt: "map the parser".to_string(),
+            child_session_id: Some("child-1".to_string()),
+        });
+        app.record_task_event(TaskEvent::ToolStarted {
+            task_id: "t1".to_string(),
+            tool: "grep".to_string(),
+        });
+        assert!(app.show_tasks);
+        assert!(app.tasks[0].is_running());
+        assert_eq!(app.tasks[0].current_tool.as_deref(), Some("grep"));
+
+        app.record_task_event(TaskEvent::Finished {
+            task_id: "t1".to_string(),
+            error: Some("timed out".to_string()),
+        });
+        assert!(!app.tasks[0].is_running());
+        assert_eq!(app.tasks[0].error.as_deref(), Some("timed out"));
+
+        assert!(app.handle_slash_command("/tasks"));
+        assert!(!app.show_tasks);
+    }
+
+    #[test]
+    fn test_task_panel_enter_opens_child_transcript() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use rot_core::TaskEvent;
+
+        let mut app = App::new("test-model", "test-provider", "build");
+        app.input_mode = InputMode::Normal;
+        for (id, child) in [("t1", Some("child-1")), ("t2", None)] {
+            app.record_task_event(TaskEvent::Started {
+                task_id: id.to_string(),
+                agent: "explore".to_string(),
+                prompt: "look around".to_string(),
+                child_session_id: child.map(str::to_string),
+            });
+        }
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert!(!app.handle_task_key(key(KeyCode::Enter)));
+        assert!(app.handle_task_key(key(KeyCode::Char('K'))));
+        assert_eq!(app.task_selected, Some(1));
+        assert!(app.handle_task_key(key(KeyCode::Enter)));
+        assert!(app.pending_child_view.is_none());
+        assert!(app.handle_task_key(key(KeyCode::Char('K'))));
+        assert!(app.handle_task_key(key(KeyCode::Enter)));
+        assert_eq!(app.pending_child_view.as_deref(), Some("child-1"));
+
+        assert!(app.handle_task_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL)));
+        assert!(!app.show_tasks);
+        assert!(!app.handle_task_key(key(KeyCode::Char('J'))));
+    }
+
+    #[test]
+    fn test_tool_events_turn_progress_into_result_lines() {
+        use rot_core::message::ToolExchange;
+        use rot_core::ToolEvent;
+
+        let mut app = App::new("test", "test", "default");
+        app.record_tool_event(ToolEvent::Started {
+            call_id: "call-1".to_string(),
+            name: "bash".to_string(),
+            arguments: serde_json::json!({"command": "cargo test"}),
+        });
+        assert_eq!(app.running_tools.len(), 1);
+        assert_eq!(app.running_tools[0].summary, "bash: cargo test");
+
+        let lines_before = app.chat_lines.len();
+        app.record_tool_event(ToolEvent::Finished {
+            call_id: "call-1".to_string(),
+            exchange: ToolExchange {
+                name: "bash".to_string(),
+                arguments: serde_json::json!({"command": "cargo test"}),
+                output: Some("ok".to_string()),
+                is_error: false,
+                metadata: serde_json::json!({"duration_ms": 43_000}),
+            },
+        });
+        assert!(app.running_tools.is_empty());
+        assert_eq!(app.chat_lines.len(), lines_before + 1);
+        assert!(app
+            .chat_lines
+            .last()
+            .unwrap()
+            .content
+            .starts_with("↳ bash cargo test · 43.0s"));
+
+        assert_eq!(app.end_tool_progress(), 1);
+        assert_eq!(app.end_tool_progress(), 0);
+    }
+
+    #[test]
+    fn test_cancel_turn_fires_token_once() {
+        let mut app = App::new("test", "test", "default");
+        assert!(!app.cancel_turn());
+
+        let cancel = rot_core::CancellationToken::new();
+        app.turn_cancel = Some(cancel.clone());
+        app.state = AppState::Thinking;
+        assert!(app.cancel_turn());
+        assert!(cancel.is_cancelled());
+        assert!(!app.cancel_turn());
+
+        app.push_streaming_text("Half an ans");
+        app.end_interrupted_turn("Run cancelled");
+        assert_eq!(app.chat_lines.last().unwrap().content, "Half an ans");
+
+        app.queued_inputs
+            .lock()
+            .unwrap()
+            .push_back("follow up".to_string());
+        app.end_interrupted_turn("Run cancelled");
+        assert_eq!(app.state, AppState::Idle);
+        assert_eq!(app.input, "follow up");
+        assert!(app
+            .chat_lines
+            .last()
+            .unwrap()
+            .content
+            .starts_with("Run cancelled; 1 queued"));
+    }
+
+    #[test]
+    fn test_usage_reports_drive_context_and_cost() {
+        let mut app = App::new("claude-sonnet-4-20250514", "anthropic", "default");
+        assert!(app.total_cost.is_none());
+
+        // One request reported as message start plus a final delta.
+        app.record_tokens(100_000, 1);
+        app.record_tokens(0, 999);
+        assert_eq!(app.last_output_tokens, 1_000);
+        assert_eq!(app.context_percent(), 50.5);
+        let cost = app.total_cost.unwrap();
+        assert!((cost - 0.315).abs() < 1e-9);
+
+        // A later request replaces the context figure but adds to the totals.
+        app.record_tokens(20_000, 0);
+        assert_eq!(app.context_percent(), 10.0);
+        assert_eq!(app.total_input_tokens, 120_000);
+
+        let mut unpriced = App::new("my-local-model", "openai", "default");
+        unpriced.record_tokens(10, 10);
+        assert!(unpriced.total_cost.is_none());
+    }
+
+    #[test]
+    fn test_context_warning_shown_once_per_crossing() {
+        let mut app = App::new("claude-sonnet-4-20250514", "anthropic", "default");
+        let warnings = |app: &App| {
+            app.chat_lines
+                .iter()
+                .filter(|line| line.content.contains("/compact to summarize"))
+                .count()
+        };
+
+        app.record_tokens(170_000, 0);
+        app.record_tokens(175_000, 0);
+        assert_eq!(warnings(&app), 1);
+
+        app.record_compaction(Some(&rot_core::compaction::Compaction {
+            summary: "summary".to_string(),
+            removed_messages: 12,
+            first_kept_id: None,
+            tokens_before: 175_000,
+            tokens_after: 4_000,
+        }));
+        assert!(app
+            .chat_lines
+            .last()
+            .unwrap()
+            .content
+            .contains("reclaiming about 171.0k tokens"));
+        assert!(app.context_percent() < CONTEXT_WARNING_PERCENT);
+
+        app.record_tokens(180_000, 0);
+        assert_eq!(warnings(&app), 2);
+    }
+
+    #[test]
+    fn test_cost_report_splits_subagent_usage() {
+        use rot_core::{TaskEvent, ToolEvent, UsageReport};
+
+        let mut app = App::new("claude-sonnet-4-20250514", "anthropic", "build");
+        assert_eq!(app.cost_report(), "No usage recorded in this session yet.");
+
+        app.record_task_event(TaskEvent::Started {
+            task_id: "t1".to_string(),
+            agent: "explore".to_string(),
+            prompt: "map the parser".to_string(),
+            child_session_id: None,
+        });
+        let report = |task_id: Option<&str>, input| UsageReport {
+            model: "claude-sonnet-4-20250514".to_string(),
+            task_id: task_id.map(str::to_string),
+            input_tokens: input,
+            output_tokens: 0,
+        };
+        app.record_usage(&report(None, 1_000));
+        app.record_usage(&report(Some("t1"), 50_000));
+        app.record_tool_event(ToolEvent::Started {
+            call_id: "c1".to_string(),
+            name: "bash".to_string(),
+            arguments: serde_json::json!({"command": "false"}),
+        });
+        app.record_tool_event(ToolEvent::Finished {
+            call_id: "c1".to_string(),
+            exchange: rot_core::message::ToolExchange {
+                name: "bash".to_string(),
+                arguments: serde_json::json!({"command": "false"}),
+                output: Some("x".repeat(400)),
+                is_error: true,
+                metadata: serde_json::Value::Null,
+            },
+        });
+
+        // Subagent requests add to spend but not to context use.
+        assert_eq!(app.last_input_tokens, 1_000);
+        assert_eq!(app.total_input_tokens, 51_000);
+        assert_eq!(app.usage.by_agent["explore"].input_tokens, 50_000);
+        let report = app.cost_report();
+        assert!(report.contains("@build  1.0k in · 0 out · $0.0030"));
+        assert!(report.contains("@explore  50.0k in"));
+        assert!(report.contains("bash  1 call(s), 1 failed"));
+        assert!(report.contains("~100 tokens of output"));
+    }
+
+    #[test]
+    fn test_input_editor_moves_within_wrapped_rows() {
+        use crossterm::event::KeyCode;
+
+        let mut app = App::new("test", "test", "default");
+        app.input_width = 4;
+        for c in "abcdef".chars() {
+            app.insert_char(c);
+        }
+        app.insert_newline();
+        app.insert_char('g');
+        assert_eq!(app.input_height(), 5);
+
+        // "abcd" | "ef" | "g", cursor after "g".
+        assert!(app.handle_editor_key(KeyCode::Up));
+        assert_eq!(app.cursor_pos, 5);
+        assert!(app.handle_editor_key(KeyCode::Home));
+        assert_eq!(app.cursor_pos, 4);
+        assert!(app.handle_editor_key(KeyCode::Left));
+        assert!(app.handle_editor_key(KeyCode::Delete));
+        assert_eq!(app.input, "abcef\ng");
+        assert!(app.handle_editor_key(KeyCode::End));
+        assert_eq!(app.cursor_pos, 4);
+        assert!(!app.handle_editor_key(KeyCode::Tab));
+
+        app.input = "x\n".repeat(20);
+        assert_eq!(app.input_height(), MAX_INPUT_ROWS as u16 + 2);
+    }
+
+    #[test]
+    fn test_vim_keys_edit_input_and_scroll_chat() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let key = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let mut app = App::new("test", "test", "default");
+        app.input_mode = InputMode::Normal;
+        app.input = "first line\nsecond line".to_string();
+        app.cursor_pos = 0;
+
+        assert!(app.handle_vim_key(key('w')));
+        assert_eq!(app.cursor_pos, 6);
+        assert!(app.handle_vim_key(key('$')));
+        assert_eq!(app.cursor_pos, 10);
+        assert!(app.handle_vim_key(key('b')));
+        assert!(app.handle_vim_key(key('0')));
+        assert_eq!(app.cursor_pos, 0);
+
+        assert!(app.handle_vim_key(key('d')));
+        assert!(app.handle_vim_key(key('d')));
+        assert_eq!(app.input, "second line");
+        assert!(app.handle_vim_key(key('c')));
+        assert!(app.handle_vim_key(key('c')));
+        assert_eq!(app.input, "");
+        assert_eq!(app.input_mode, InputMode::Insert);
+
+        app.input_mode = InputMode::Normal;
+        app.chat_view.height = 10;
+        app.max_scroll = 40;
+        app.scroll_offset = 20;
+        assert!(app.handle_vim_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL)));
+        assert_eq!(app.scroll_offset, 25);
+        assert!(app.handle_vim_key(key('g')));
+        assert!(app.handle_vim_key(key('g')));
+        assert_eq!(app.scroll_offset, 0);
+        assert!(!app.auto_scroll);
+    }
+
+    #[test]
+    fn test_visual_selection_extends_by_line() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let key = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let mut app = App::new("test", "test", "default");
+        app.input_mode = InputMode::Normal;
+        assert!(app.handle_vim_key(key('v')));
+        assert!(app.visual.is_none());
+
+        app.chat_view.text = vec![" ▌ one".into(), " ▌ two".into(), String::new()];
+        app.chat_view.top_line = 1;
+        app.auto_scroll = false;
+        assert!(app.handle_vim_key(key('v')));
+        assert!(app.handle_vim_key(key('k')));
+        assert_eq!(app.visual.unwrap().range(), 0..=1);
+        assert!(app.handle_vim_key(key('G')));
+        assert_eq!(app.visual.unwrap().range(), 1..=2);
+        assert!(app.handle_vim_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)));
+        assert!(app.visual.is_none());
+    }
+
+    #[test]
+    fn test_approval_dialog_sends_edited_arguments() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = App::new("test", "test", "default");
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let (tx, mut rx) = tokio::sync::oneshot::channel();
+        app.begin_approval("bash".to_string(), serde_json::json!({"command": "ls"}), tx);
+        assert_eq!(app.state, AppState::Approval);
+
+        app.handle_approval_key(key(KeyCode::Char('e')));
+        app.handle_approval_key(key(KeyCode::Char(' ')));
+        app.handle_approval_key(key(KeyCode::Char('-')));
+        app.handle_approval_key(key(KeyCode::Char('a')));
+        assert_eq!(app.state, AppState::Approval);
+        app.handle_approval_key(key(KeyCode::Enter));
+
+        assert_eq!(app.state, AppState::Thinking);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            ApprovalResponse::AllowEdited(serde_json::json!({"command": "ls -a"}))
+        );
+    }
+
+    #[test]
+    fn test_model_picker_lists_models_per_provider() {
+        use crossterm::event::KeyCode;
+
+        let model = |id: &str, context_window| ModelInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            context_window,
+            max_output_tokens: 8_192,
+            supports_thinking: false,
+            supports_tools: true,
+            supports_images: true,
+        };
+        let store = rot_core::config::ConfigStore::with_paths(
+            std::env::temp_dir().join("rot-picker-test.json"),
+            None,
+        );
+        let mut app = App::new("claude-sonnet-4-20250514", "anthropic", "default");
+        app.open_model_picker();
+        assert_eq!(app.take_models_request().as_deref(), Some("anthropic"));
+        assert!(app.picker_models().is_empty());
+
+        app.set_model_listing(
+            "anthropic".to_string(),
+            ModelListing::Live(vec![
+                model("claude-opus-5", 500_000),
+                model("claude-sonnet-4-20250514", 200_000),
+            ]),
+        );
+        assert_eq!(app.config_ui_state, ConfigUiState::List(1));
+
+        app.handle_config_key(KeyCode::Right, &store);
+        assert_eq!(app.take_models_request().as_deref(), Some("zai"));
+        app.handle_config_key(KeyCode::Left, &store);
+        assert_eq!(app.take_models_request(), None);
+        assert_eq!(app.picker_models().len(), 2);
+
+        app.model = "claude-opus-5".to_string();
+        app.last_input_tokens = 250_000;
+        assert_eq!(app.context_percent(), 50.0);
+        assert_eq!(format_context_window(1_000_000), "1M");
+        assert_eq!(format_context_window(32_768), "32k");
+    }
+
+    #[test]
+    fn test_thinking_streams_then_collapses_into_chat() {
+        use rot_core::ToolEvent;
+
+        let mut app = App::new("test", "test", "default");
+        app.push_thinking("Check the tests\nfirst.");
+        assert_eq!(app.thinking_text, "Check the tests\nfirst.");
+
+        let lines_before = app.chat_lines.len();
+        app.record_tool_event(ToolEvent::Started {
+            call_id: "call-1".to_string(),
+            name: "bash".to_string(),
+            arguments: serde_json::json!({"command": "cargo test"}),
+        });
+        assert!(app.thinking_text.is_empty());
+        assert_eq!(app.chat_lines.len(), lines_before + 1);
+        let thought = app.chat_lines.last().unwrap();
+        assert_eq!(thought.content, "✻ Thought · 2 lines");
+        assert_eq!(thought.detail.as_deref(), Some("Check the tests\nfirst."));
+        assert!(!thought.expanded);
+
+        assert!(app.handle_slash_command("/thinking off"));
+        assert!(!app.show_thinking);
+        app.push_thinking("hidden");
+        assert!(app.thinking_text.is_empty());
+        assert!(app.handle_slash_command("/thinking"));
+        assert!(app.show_thinking);
+    }
+
+    #[test]
+    fn test_streamed_text_moves_into_chat_before_tool_calls() {
+        use rot_core::ToolEvent;
+
+        let mut app = App::new("test", "test", "default");
+        app.state = AppState::Thinking;
+        app.push_streaming_text("Running the **tests**:\n```sh\ncargo te");
+        assert_eq!(app.state, AppState::Streaming);
+        assert!(!crate::markdown::render(&app.streaming_text, Style::default(), 40).is_empty());
+
+        let lines_before = app.chat_lines.len();
+        app.record_tool_event(ToolEvent::Started {
+            call_id: "call-1".to_string(),
+            name: "bash".to_string(),
+            arguments: serde_json::json!({"command": "cargo test"}),
+        });
+        assert!(app.streaming_text.is_empty());
+        assert_eq!(app.state, AppState::Thinking);
+        assert_eq!(app.chat_lines.len(), lines_before + 1);
+        assert_eq!(
+            app.chat_lines.last().unwrap().content,
+            "Running the **tests**:\n```sh\ncargo te"
+        );
+    }
+
+    #[test]
+    fn test_paste_keeps_newlines_in_input() {
+        let mut app = App::new("test", "test", "default");
+        app.insert_char('>');
+        app.handle_paste("thread 'main' panicked\r\n  at src/main.rs:4\r\n");
+        assert_eq!(app.input, ">thread 'main' panicked\n  at src/main.rs:4\n");
+        assert_eq!(app.cursor_pos, app.input.len());
+        assert!(app.chat_lines.iter().all(|line| line.role != "user"));
+
+        app.history_search = Some(HistorySearch::default());
+        app.handle_paste("cargo\ntest\n");
+        assert_eq!(app.history_search.as_ref().unwrap().query, "cargo test");
+    }
+
+    #[test]
+    fn test_select_mode_freezes_view_and_swallows_keys() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = App::new("test", "test", "default");
+        app.max_scroll = 10;
+        app.scroll_offset = 10;
+        assert!(app.handle_select_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)));
+        assert!(app.select_mode);
+        assert!(!app.auto_scroll);
+
+        assert!(app.handle_select_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE)));
+        assert_eq!(app.scroll_offset, 9);
+        assert!(app.handle_select_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE)));
+        assert!(app.input.is_empty());
+
+        assert!(app.handle_select_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)));
+        assert!(!app.select_mode);
+        assert!(!app.handle_select_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)));
+
+        assert!(app.handle_slash_command("/select"));
+        assert!(app.select_mode);
+    }
+
+    #[test]
+    fn test_reply_metadata_records_turn_usage() {
+        let mut app = App::new("gpt-4o", "openai", "default");
+        app.push_chat("you", "hi", ChatStyle::User);
+        assert!(app.chat_lines.last().unwrap().meta.is_some());
+
+        app.start_timer();
+        app.record_usage(&rot_core::UsageReport {
+            model: "gpt-4o".to_string(),
+            input_tokens: 1_000,
+            output_tokens: 200,
+            task_id: None,
+        });
+        app.push_reply("hello");
+        let meta = app.chat_lines.last().unwrap().meta.clone().unwrap();
+        assert_eq!(meta.tokens, Some(1_200));
+        assert_eq!(meta.model.as_deref(), Some("gpt-4o"));
+        assert!(meta.duration.is_some());
+
+        let summary = meta.summary(meta.timestamp + 120);
+        assert!(summary.starts_with("2m ago · "));
+        assert!(summary.ends_with(" · 1.2k tok · gpt-4o"));
+
+        assert!(!app.show_meta);
+        assert!(app.handle_slash_command("/meta on"));
+        assert!(app.show_meta);
+        assert!(app.handle_slash_command("/meta maybe"));
+        assert!(app.show_meta);
+    }
+
+    #[test]
+    fn test_sandbox_escalation_needs_confirmation() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use rot_core::{ApprovalPolicy, SandboxMode};
+
+        let mut app = App::new("test", "test", "default");
+        assert!(app.handle_slash_command("/approval never"));
+        assert_eq!(app.security.approval_policy, ApprovalPolicy::Never);
+        assert!(app.take_security_change());
+        assert!(!app.take_security_change());
+
+        assert!(app.handle_slash_command("/sandbox danger-full-access"));
+        assert_eq!(app.security.sandbox_mode, SandboxMode::WorkspaceWrite);
+        assert!(
+            app.handle_security_confirm_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE))
+        );
+        assert_eq!(app.security.sandbox_mode, SandboxMode::WorkspaceWrite);
+        assert!(!app.take_security_change());
+
+        assert!(app.handle_slash_command("/sandbox danger-full-access"));
+        assert!(
+            app.handle_security_confirm_key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE))
+        );
+        assert_eq!(app.security.sandbox_mode, SandboxMode::DangerFullAccess);
+        assert!(app.take_security_change());
+
+        // Lowering the sandbox needs no confirmation.
+        assert!(app.handle_slash_command("/sandbox read-only"));
+        assert_eq!(app.security.sandbox_mode, SandboxMode::ReadOnly);
+        assert!(app.handle_slash_command("/sandbox none"));
+        assert_eq!(app.chat_lines.last().unwrap().role, "error");
+    }
 }
@@ -0,0 +1,319 @@
+//! State of the tool approval dialog: a scrollable preview of the call and
+//! an editor for changing its arguments before approving.
+//!
+//! Shell calls preview as the exact command line, `write` and `edit` as the
+//! diff they would make, requests to reach a new host as that host, and
+//! everything else as pretty-printed JSON.
+
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rot_core::permission::{ApprovalResponse, NETWORK_APPROVAL};
+use serde_json::Value;
+
+use crate::editor;
+
+/// How the preview lines of an [`ApprovalPrompt`] should be styled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewKind {
+    /// A shell command, `$`-prefixed, followed by its other arguments.
+    Command,
+    /// A unified diff of a file change.
+    Diff,
+    /// A host a tool wants to connect to; there is nothing to edit.
+    Network,
+    /// Pretty-printed JSON arguments.
+    Json,
+}
+
+/// Text being edited in the approval dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgumentEdit {
+    pub text: String,
+    /// Byte offset of the cursor in `text`.
+    pub cursor: usize,
+    /// Cells available per row (computed during render).
+    pub width: usize,
+}
+
+/// A tool call waiting for the user's decision.
+#[derive(Debug)]
+pub struct ApprovalPrompt {
+    pub tool: String,
+    pub args: Value,
+    pub kind: PreviewKind,
+    pub preview: Vec<String>,
+    /// First preview row shown.
+    pub scroll: usize,
+    /// Preview rows visible in the last render, for paging.
+    pub page: usize,
+    /// Set while the user edits the command or arguments.
+    pub edit: Option<ArgumentEdit>,
+    /// Why the last edit could not be submitted.
+    pub error: Option<String>,
+}
+
+impl ApprovalPrompt {
+    /// Build the prompt for `tool`, previewing file changes against `working_dir`.
+    pub fn new(tool: String, args: Value, working_dir: &Path) -> Self {
+        let (kind, preview) = if tool == NETWORK_APPROVAL {
+            let field = |key: &str| {
+                args.get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let lines = vec![
+                format!("host: {}", field("host")),
+                format!("tool: {}", field("tool")),
+            ];
+            (PreviewKind::Network, lines)
+        } else if let Some(command) = shell_command(&tool, &args) {
+            let mut lines: Vec<String> = command
+                .lines()
+                .enumerate()
+                .map(|(idx, line)| {
+                    if idx == 0 {
+                        format!("$ {line}")
+                    } else {
+                        format!("  {line}")
+                    }
+                })
+                .collect();
+            if lines.is_empty() {
+                lines.push("$".to_string());
+            }
+            if let Some(map) = args.as_object() {
+                lines.extend(map.iter().filter(|(key, _)| *key != "command").map(
+                    |(key, value)| match value.as_str() {
+                        Some(text) => format!("{key}: {text}"),
+                        None => format!("{key}: {value}"),
+                    },
+                ));
+            }
+            (PreviewKind::Command, lines)
+        } else if let Some(diff) = rot_tools::change_preview(&tool, &args, working_dir) {
+            (
+                PreviewKind::Diff,
+                diff.lines().map(str::to_string).collect(),
+            )
+        } else {
+            let json = serde_json::to_string_pretty(&args).unwrap_or_default();
+            (
+                PreviewKind::Json,
+                json.lines().map(str::to_string).collect(),
+            )
+        };
+        Self {
+            tool,
+            args,
+            kind,
+            preview,
+            scroll: 0,
+            page: 1,
+            edit: None,
+            error: None,
+        }
+    }
+
+    /// Handle a key press; returns the user's decision once they make one.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ApprovalResponse> {
+        if self.edit.is_some() {
+            return self.handle_edit_key(key);
+        }
+        let page = self.page.max(1);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => return Some(ApprovalResponse::AllowOnce),
+            KeyCode::Char('a') | KeyCode::Char('A') => return Some(ApprovalResponse::AllowAlways),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                return Some(ApprovalResponse::DenyOnce)
+            }
+            KeyCode::Char('d') if ctrl => self.scroll += page / 2,
+            KeyCode::Char('u') if ctrl => self.scroll = self.scroll.saturating_sub(page / 2),
+            KeyCode::Char('d') | KeyCode::Char('D') => return Some(ApprovalResponse::DenyAlways),
+            KeyCode::Char('e') | KeyCode::Char('E') if self.kind != PreviewKind::Network => {
+                self.start_edit()
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.scroll += 1,
+            KeyCode::Char('k') | KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll += page,
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll = 0,
+            // Clamped to the last page during render.
+            KeyCode::Char('G') | KeyCode::End => self.scroll = usize::MAX,
+            _ => {}
+        }
+        None
+    }
+
+    fn start_edit(&mut self) {
+        let text = match shell_command(&self.tool, &self.args) {
+            Some(command) => command.to_string(),
+            None => serde_json::to_string_pretty(&self.args).unwrap_or_default(),
+        };
+        self.edit = Some(ArgumentEdit {
+            cursor: text.len(),
+            text,
+            width: 80,
+        });
+        self.error = None;
+    }
+
+    fn handle_edit_key(&mut self, key: KeyEvent) -> Option<ApprovalResponse> {
+        let edit = self.edit.as_mut()?;
+        let cursor = edit.cursor;
+        match key.code {
+            KeyCode::Esc => {
+                self.edit = None;
+                self.error = None;
+            }
+            KeyCode::Enter
+                if key
+                    .modifiers
+                    .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
+            {
+                edit.text.insert(cursor, '\n');
+                edit.cursor += 1;
+            }
+            KeyCode::Enter => return self.submit_edit(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                edit.text.insert(cursor, c);
+                edit.cursor += c.len_utf8();
+            }
+            KeyCode::Backspace if cursor > 0 => {
+                let prev = editor::prev_char(&edit.text, cursor);
+                edit.text.replace_range(prev..cursor, "");
+                edit.cursor = prev;
+            }
+            KeyCode::Delete if cursor < edit.text.len() => {
+                let next = editor::next_char(&edit.text, cursor);
+                edit.text.replace_range(cursor..next, "");
+            }
+            KeyCode::Left => edit.cursor = editor::prev_char(&edit.text, cursor),
+            KeyCode::Right => edit.cursor = editor::next_char(&edit.text, cursor),
+            KeyCode::Home => edit.cursor = editor::row_bounds(&edit.text, edit.width, cursor).start,
+            KeyCode::End => edit.cursor = editor::row_bounds(&edit.text, edit.width, cursor).end,
+            KeyCode::Up => {
+                edit.cursor = editor::move_vertically(&edit.text, edit.width, cursor, -1)
+            }
+            KeyCode::Down => {
+                edit.cursor = editor::move_vertically(&edit.text, edit.width, cursor, 1)
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Insert pasted text into the argument editor, if it is open.
+    pub fn paste(&mut self, text: &str) {
+        if let Some(edit) = &mut self.edit {
+            edit.text.insert_str(edit.cursor, text);
+            edit.cursor += text.len();
+        }
+    }
+
+    /// Approve with the edited arguments, or explain why they are unusable.
+    fn submit_edit(&mut self) -> Option<ApprovalResponse> {
+        let text = &self.edit.as_ref()?.text;
+        let edited = if shell_command(&self.tool, &self.args).is_some() {
+            if text.trim().is_empty() {
+                self.error = Some("The command is empty.".to_string());
+                return None;
+            }
+            let mut args = self.args.clone();
+            args["command"] = Value::String(text.clone());
+            args
+        } else {
+            match serde_json::from_str::<Value>(text) {
+                Ok(args) if args.is_object() => args,
+                Ok(_) => {
+                    self.error = Some("Arguments must be a JSON object.".to_string());
+                    return None;
+                }
+                Err(e) => {
+                    self.error = Some(format!("Invalid JSON: {e}"));
+                    return None;
+                }
+            }
+        };
+        if edited == self.args {
+            Some(ApprovalResponse::AllowOnce)
+        } else {
+            Some(ApprovalResponse::AllowEdited(edited))
+        }
+    }
+}
+
+/// The command line of a `bash` call.
+fn shell_command<'a>(tool: &str, args: &'a Value) -> Option<&'a str> {
+    if tool == "bash" {
+        args.get("command")?.as_str()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_edit_bash_command_before_approving() {
+        let args = serde_json::json!({"command": "rm -rf build", "timeout": 30});
+        let mut prompt = ApprovalPrompt::new("bash".to_string(), args, Path::new("."));
+        assert_eq!(prompt.kind, PreviewKind::Command);
+        assert_eq!(prompt.preview, vec!["$ rm -rf build", "timeout: 30"]);
+
+        assert_eq!(prompt.handle_key(key(KeyCode::Char('e'))), None);
+        for _ in 0.." build".len() {
+            prompt.handle_key(key(KeyCode::Backspace));
+        }
+        for c in " target".chars() {
+            prompt.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(
+            prompt.handle_key(key(KeyCode::Enter)),
+            Some(ApprovalResponse::AllowEdited(
+                serde_json::json!({"command": "rm -rf target", "timeout": 30})
+            ))
+        );
+    }
+
+    #[test]
+    fn test_edited_json_must_parse() {
+        let args = serde_json::json!({"url": "https://example.com"});
+        let mut prompt = ApprovalPrompt::new("webfetch".to_string(), args, Path::new("."));
+        assert_eq!(prompt.kind, PreviewKind::Json);
+
+        prompt.handle_key(key(KeyCode::Char('e')));
+        prompt.handle_key(key(KeyCode::Char(',')));
+        assert_eq!(prompt.handle_key(key(KeyCode::Enter)), None);
+        assert!(prompt.error.as_deref().unwrap().starts_with("Invalid JSON"));
+
+        prompt.handle_key(key(KeyCode::Backspace));
+        assert_eq!(
+            prompt.handle_key(key(KeyCode::Enter)),
+            Some(ApprovalResponse::AllowOnce)
+        );
+    }
+
+    #[test]
+    fn test_network_request_shows_host_and_cannot_be_edited() {
+        let args = serde_json::json!({"host": "docs.rs", "tool": "webfetch"});
+        let mut prompt = ApprovalPrompt::new(NETWORK_APPROVAL.to_string(), args, Path::new("."));
+        assert_eq!(prompt.kind, PreviewKind::Network);
+        assert_eq!(prompt.preview, vec!["host: docs.rs", "tool: webfetch"]);
+
+        assert_eq!(prompt.handle_key(key(KeyCode::Char('e'))), None);
+        assert!(prompt.edit.is_none());
+        assert_eq!(
+            prompt.handle_key(key(KeyCode::Char('a'))),
+            Some(ApprovalResponse::AllowAlways)
+        );
+    }
+}
@@ -0,0 +1,31 @@
+//! System clipboard access.
+//!
+//! Mouse capture keeps the terminal from selecting text, so copying writes an
+//! OSC 52 escape sequence, which most terminals (including over SSH) turn
+//! into a clipboard write.
+
+use base64::Engine;
+use std::io::Write;
+
+/// Copy `text` to the system clipboard through the terminal.
+pub fn copy(text: &str) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52(text).as_bytes())?;
+    stdout.flush()
+}
+
+/// OSC 52 sequence that sets the clipboard to `text`.
+fn osc52(text: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    format!("\x1b]52;c;{encoded}\x07")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52("hi"), "\x1b]52;c;aGk=\x07");
+    }
+}
@@ -0,0 +1,238 @@
+//! Terminal restoration and crash reports.
+//!
+//! [`TerminalGuard`] puts the terminal into raw mode with the alternate
+//! screen and mouse capture, and undoes all of it when dropped, however
+//! `run_tui` exits. A panic hook does the same before the panic message is
+//! printed, so it lands on a usable terminal, and writes a crash report with
+//! the backtrace, the session id, and the last events the TUI handled to
+//! `~/.local/share/rot/crashes/`.
+
+use crate::event::TermEvent;
+use crossterm::event::{
+    DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+    EnableFocusChange, EnableMouseCapture,
+};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::ExecutableCommand;
+use std::collections::VecDeque;
+use std::io::stdout;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, Once};
+use std::thread::ThreadId;
+
+/// Events kept for the crash report.
+const EVENT_LIMIT: usize = 50;
+
+/// Whether the terminal is in TUI mode and needs restoring.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// What the crash report says about the TUI.
+struct CrashContext {
+    session_id: Option<String>,
+    /// The thread drawing the TUI; a panic elsewhere leaves the terminal alone.
+    tui_thread: Option<ThreadId>,
+    events: VecDeque<String>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    session_id: None,
+    tui_thread: None,
+    events: VecDeque::new(),
+});
+
+/// The context, even if a panic poisoned it.
+fn context() -> MutexGuard<'static, CrashContext> {
+    CONTEXT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Directory crash reports are written to.
+pub fn crash_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rot")
+        .join("crashes")
+}
+
+/// Holds the terminal in TUI mode; restores it when dropped.
+pub(crate) struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    /// Enter raw mode, the alternate screen, mouse capture, focus reporting,
+    /// and bracketed paste, and install the panic hook.
+    pub(crate) fn enter() -> std::io::Result<Self> {
+        install_panic_hook();
+        context().tui_thread = Some(std::thread::current().id());
+        // Created first, so a setup step that fails still undoes the others.
+        let guard = Self { _private: () };
+        ACTIVE.store(true, Ordering::SeqCst);
+        enable_raw_mode()?;
+        stdout().execute(EnterAlternateScreen)?;
+        stdout().execute(EnableMouseCapture)?;
+        stdout().execute(EnableFocusChange)?;
+        stdout().execute(EnableBracketedPaste)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+        context().tui_thread = None;
+    }
+}
+
+/// Leave TUI mode, once. Errors are ignored: this runs while exiting or
+/// panicking, with nothing better to do about them.
+fn restore_terminal() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let _ = disable_raw_mode();
+    let _ = stdout().execute(DisableBracketedPaste);
+    let _ = stdout().execute(DisableFocusChange);
+    let _ = stdout().execute(DisableMouseCapture);
+    let _ = stdout().execute(LeaveAlternateScreen);
+}
+
+/// Name the session a crash report belongs to.
+pub(crate) fn set_session(id: &str) {
+    context().session_id = Some(id.to_string());
+}
+
+/// Remember `event` for the crash report, dropping the oldest past the limit.
+pub(crate) fn record(event: impl Into<String>) {
+    let mut context = context();
+    if context.events.len() == EVENT_LIMIT {
+        context.events.pop_front();
+    }
+    context.events.push_back(event.into());
+}
+
+/// A terminal event as the crash report lists it; pasted text is only
+/// counted, and ticks are not listed.
+pub(crate) fn describe(event: &TermEvent) -> Option<String> {
+    let described = match event {
+        TermEvent::Key(key) if key.modifiers.is_empty() => format!("key {:?}", key.code),
+        TermEvent::Key(key) => format!("key {:?} {:?}", key.modifiers, key.code),
+        TermEvent::MouseScroll(delta) => format!("scroll {delta}"),
+        TermEvent::Paste(text) => format!("paste ({} chars)", text.chars().count()),
+        TermEvent::Resize(width, height) => format!("resize {width}x{height}"),
+        TermEvent::Focus(focused) => format!("focus {focused}"),
+        TermEvent::Tick => return None,
+    };
+    Some(described)
+}
+
+/// Chain a hook that restores the terminal and writes a crash report in
+/// front of the existing one. Installed once per process.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let on_tui_thread = context().tui_thread == Some(std::thread::current().id());
+            if on_tui_thread {
+                restore_terminal();
+            }
+            let message = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "(no message)".to_string());
+            let location = info
+                .location()
+                .map(|location| location.to_string())
+                .unwrap_or_default();
+            let report = {
+                let context = context();
+                format_report(&message, &location, &context)
+            };
+            match write_report(&report) {
+                Ok(path) if on_tui_thread => {
+                    eprintln!(
+                        "rot crashed. A crash report was written to {}",
+                        path.display()
+                    );
+                }
+                Ok(path) => tracing::error!("panic; crash report written to {}", path.display()),
+                Err(e) => tracing::error!("failed to write crash report: {e}"),
+            }
+            previous(info);
+        }));
+    });
+}
+
+fn format_report(message: &str, location: &str, context: &CrashContext) -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let thread = std::thread::current();
+    let mut report = format!(
+        "rot {} crash report\n\ntime: {} (unix {timestamp})\nsession: {}\nthread: {}\npanic: {message}\nlocation: {location}\n",
+        env!("CARGO_PKG_VERSION"),
+        rot_core::usage::utc_date(timestamp),
+        context.session_id.as_deref().unwrap_or("(none)"),
+        thread.name().unwrap_or("(unnamed)"),
+    );
+    report.push_str("\nlast events, oldest first:\n");
+    for event in &context.events {
+        report.push_str(&format!("  {event}\n"));
+    }
+    report.push_str(&format!(
+        "\nbacktrace:\n{}\n",
+        std::backtrace::Backtrace::force_capture()
+    ));
+    report
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    let dir = crash_dir();
+    std::fs::create_dir_all(&dir)?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("crash-{timestamp}-{}.txt", std::process::id()));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_crash_report_lists_context() {
+        let context = CrashContext {
+            session_id: Some("01ABC".to_string()),
+            tui_thread: None,
+            events: ["key Enter", "agent response"].map(str::to_string).into(),
+        };
+        let report = format_report("index out of bounds", "src/app.rs:10:5", &context);
+        assert!(report.contains("session: 01ABC\n"));
+        assert!(report.contains("panic: index out of bounds\nlocation: src/app.rs:10:5\n"));
+        assert!(report.contains("oldest first:\n  key Enter\n  agent response\n"));
+        assert!(report.contains("\nbacktrace:\n"));
+
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(
+            describe(&TermEvent::Key(ctrl_c)).unwrap(),
+            "key KeyModifiers(CONTROL) Char('c')"
+        );
+        assert_eq!(
+            describe(&TermEvent::Paste("secret token".to_string())).unwrap(),
+            "paste (12 chars)"
+        );
+        assert_eq!(describe(&TermEvent::Tick), None);
+    }
+}
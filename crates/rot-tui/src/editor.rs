@@ -0,0 +1,209 @@
+//! Soft wrapping and cursor movement for the multi-line input box.
+//!
+//! Positions are byte offsets into the input; columns are terminal cells.
+
+use ratatui::text::Span;
+
+/// One screen row of the input: the byte range it shows, without the
+/// newline that ended it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Row {
+    pub start: usize,
+    pub end: usize,
+}
+
+fn char_width(c: char) -> usize {
+    let mut buf = [0; 4];
+    Span::raw(&*c.encode_utf8(&mut buf)).width()
+}
+
+/// Split `text` into rows at newlines and wherever a line would exceed
+/// `width` cells. Always returns at least one row.
+pub fn wrap(text: &str, width: usize) -> Vec<Row> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (idx, c) in text.char_indices() {
+        if c == '\n' {
+            rows.push(Row { start, end: idx });
+            start = idx + 1;
+            used = 0;
+            continue;
+        }
+        let w = char_width(c);
+        if used + w > width && used > 0 {
+            rows.push(Row { start, end: idx });
+            start = idx;
+            used = 0;
+        }
+        used += w;
+    }
+    rows.push(Row {
+        start,
+        end: text.len(),
+    });
+    rows
+}
+
+/// Row index and column of `cursor`. A cursor at a soft wrap belongs to
+/// the row that starts there.
+pub fn cursor_position(text: &str, rows: &[Row], cursor: usize) -> (usize, usize) {
+    let row = rows
+        .iter()
+        .rposition(|row| row.start <= cursor)
+        .unwrap_or(0);
+    let start = rows[row].start;
+    let col = text[start..cursor.min(rows[row].end).max(start)]
+        .chars()
+        .map(char_width)
+        .sum();
+    (row, col)
+}
+
+/// Byte offset in `row` closest to column `col` without passing it.
+pub fn offset_at_column(text: &str, row: Row, col: usize) -> usize {
+    let mut used = 0;
+    for (idx, c) in text[row.start..row.end].char_indices() {
+        let w = char_width(c);
+        if used + w > col {
+            return row.start + idx;
+        }
+        used += w;
+    }
+    row.end
+}
+
+/// Cursor one row up (`delta < 0`) or down, keeping its column. Moving past
+/// the first or last row goes to the start or end of the text.
+pub fn move_vertically(text: &str, width: usize, cursor: usize, delta: isize) -> usize {
+    let rows = wrap(text, width);
+    let (row, col) = cursor_position(text, &rows, cursor);
+    match row.checked_add_signed(delta) {
+        Some(target) if target < rows.len() => offset_at_column(text, rows[target], col),
+        Some(_) => text.len(),
+        None => 0,
+    }
+}
+
+/// Start and end of the row holding `cursor`.
+pub fn row_bounds(text: &str, width: usize, cursor: usize) -> Row {
+    let rows = wrap(text, width);
+    let (row, _) = cursor_position(text, &rows, cursor);
+    rows[row]
+}
+
+/// Offset of the character before `pos`, or 0.
+pub fn prev_char(text: &str, pos: usize) -> usize {
+    text[..pos]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(idx, _)| idx)
+}
+
+/// Offset just past the character at `pos`, or the end of the text.
+pub fn next_char(text: &str, pos: usize) -> usize {
+    text[pos..]
+        .chars()
+        .next()
+        .map_or(text.len(), |c| pos + c.len_utf8())
+}
+
+/// Byte range of the newline-delimited line holding `pos`, without its newline.
+pub fn line_bounds(text: &str, pos: usize) -> Row {
+    let start = text[..pos].rfind('\n').map_or(0, |idx| idx + 1);
+    let end = text[pos..].find('\n').map_or(text.len(), |idx| pos + idx);
+    Row { start, end }
+}
+
+/// Vim word classes: whitespace, word characters, and other punctuation.
+fn class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+/// Start of the next word after `pos` (Vim `w`), or the end of the text.
+pub fn next_word_start(text: &str, pos: usize) -> usize {
+    let mut chars = text[pos..]
+        .char_indices()
+        .map(|(idx, c)| (pos + idx, class(c)));
+    let Some((_, current)) = chars.next() else {
+        return text.len();
+    };
+    let mut in_word = current != 0;
+    for (idx, cls) in chars {
+        if cls == 0 {
+            in_word = false;
+        } else if !in_word || cls != current {
+            return idx;
+        }
+    }
+    text.len()
+}
+
+/// Start of the word before `pos` (Vim `b`), or 0.
+pub fn prev_word_start(text: &str, pos: usize) -> usize {
+    let mut start = None;
+    for (idx, c) in text[..pos].char_indices().rev() {
+        let cls = class(c);
+        match start {
+            None if cls == 0 => {}
+            None => start = Some((idx, cls)),
+            Some((_, word)) if cls == word => start = Some((idx, cls)),
+            Some(_) => break,
+        }
+    }
+    start.map_or(0, |(idx, _)| idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_breaks_at_newlines_and_width() {
+        let rows = wrap("abcdef\ngh", 4);
+        assert_eq!(
+            rows,
+            vec![
+                Row { start: 0, end: 4 },
+                Row { start: 4, end: 6 },
+                Row { start: 7, end: 9 },
+            ]
+        );
+        assert_eq!(wrap("", 10), vec![Row { start: 0, end: 0 }]);
+        assert_eq!(wrap("ab\n", 10).len(), 2);
+    }
+
+    #[test]
+    fn test_vertical_moves_keep_column() {
+        let text = "abcdef\ngh";
+        // "abcd" | "ef" | "gh"
+        assert_eq!(cursor_position(text, &wrap(text, 4), 4), (1, 0));
+        assert_eq!(move_vertically(text, 4, 3, 1), 6);
+        assert_eq!(move_vertically(text, 4, 5, 1), 8);
+        assert_eq!(move_vertically(text, 4, 8, -2), 1);
+        assert_eq!(move_vertically(text, 4, 2, -1), 0);
+        assert_eq!(move_vertically(text, 4, 8, 1), text.len());
+        assert_eq!(row_bounds(text, 4, 5), Row { start: 4, end: 6 });
+    }
+
+    #[test]
+    fn test_word_motions() {
+        let text = "let x = foo.bar(1);\nnext";
+        assert_eq!(next_word_start(text, 0), 4);
+        assert_eq!(next_word_start(text, 8), 11);
+        assert_eq!(next_word_start(text, 11), 12);
+        assert_eq!(next_word_start(text, 17), 20);
+        assert_eq!(prev_word_start(text, 20), 17);
+        assert_eq!(prev_word_start(text, 12), 11);
+        assert_eq!(prev_word_start(text, 4), 0);
+        assert_eq!(line_bounds(text, 21), Row { start: 20, end: 24 });
+        assert_eq!(line_bounds(text, 19), Row { start: 0, end: 19 });
+    }
+}
@@ -10,8 +10,12 @@ pub enum TermEvent {
     Key(KeyEvent),
     /// Mouse scroll.
     MouseScroll(i16),
+    /// Text pasted while bracketed paste is enabled.
+    Paste(String),
     /// Terminal was resized.
     Resize(u16, u16),
+    /// Terminal gained (`true`) or lost (`false`) focus.
+    Focus(bool),
     /// No event (tick).
     Tick,
 }
@@ -26,8 +30,10 @@ pub fn poll_event(timeout: Duration) -> std::io::Result<TermEvent> {
                 MouseEventKind::ScrollDown => Ok(TermEvent::MouseScroll(3)),
                 _ => Ok(TermEvent::Tick),
             },
+            Event::Paste(text) => Ok(TermEvent::Paste(text)),
             Event::Resize(w, h) => Ok(TermEvent::Resize(w, h)),
-            _ => Ok(TermEvent::Tick),
+            Event::FocusGained => Ok(TermEvent::Focus(true)),
+            Event::FocusLost => Ok(TermEvent::Focus(false)),
         }
     } else {
         Ok(TermEvent::Tick)
@@ -0,0 +1,261 @@
+//! The `/help` overlay: keybindings by mode, slash commands, and the active
+//! security settings, filtered by a search query typed into the overlay.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rot_core::RuntimeSecurityConfig;
+
+/// Keybindings grouped by the mode they apply in.
+const KEYBINDINGS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Insert mode",
+        &[
+            ("Enter", "send the message"),
+            ("Shift+Enter / Alt+Enter", "insert a newline"),
+            (
+                "Up / Down",
+                "move between lines; at an empty prompt, cycle earlier prompts",
+            ),
+            ("Home / End", "jump to the start or end of the line"),
+            ("Ctrl+R", "search prompt history"),
+            ("@", "mention a file or route to an agent"),
+            ("/", "open the slash command menu"),
+            ("Esc", "switch to normal mode"),
+        ],
+    ),
+    (
+        "Normal mode",
+        &[
+            ("i", "switch to insert mode"),
+            ("j / k", "scroll down or up"),
+            ("gg / G", "jump to the top or bottom"),
+            ("Ctrl+U / Ctrl+D", "scroll half a page up or down"),
+            (
+                "w / b, 0 / $",
+                "move the input cursor by word or to the line ends",
+            ),
+            ("dd / cc / yy", "delete, change, or copy the input line"),
+            ("v", "select chat lines; y copies them"),
+            (
+                "/",
+                "search the conversation; n / N for the next or previous match",
+            ),
+            (
+                "Tab / Shift+Tab",
+                "select a tool call; Enter / Space expand it",
+            ),
+            ("[ / ]", "select the previous or next message"),
+            ("y / Y", "copy the message or its last code block"),
+            (
+                "J / K",
+                "select a background task; Enter opens its transcript",
+            ),
+            ("e", "edit the last prompt"),
+            ("?", "show this help"),
+            ("q", "quit"),
+        ],
+    ),
+    (
+        "Any mode",
+        &[
+            ("Esc", "cancel the running reply"),
+            ("Ctrl+C", "cancel the running reply; quit when idle"),
+            ("Ctrl+T", "show or hide the background tasks panel"),
+            ("Ctrl+O", "expand or collapse the live reasoning"),
+            ("Ctrl+S", "release the mouse to select text; Esc to return"),
+        ],
+    ),
+    (
+        "Approval dialog",
+        &[
+            ("y / a", "allow once or always"),
+            ("n / d", "deny once or always"),
+            ("e", "edit the command or arguments before approving"),
+            ("j / k, PgUp / PgDn", "scroll the preview"),
+        ],
+    ),
+];
+
+/// Slash commands with their arguments.
+const COMMANDS: &[(&str, &str)] = &[
+    ("/agents", "switch the active agent"),
+    ("/children", "list delegated child runs"),
+    ("/child ID", "inspect one child session"),
+    ("/tasks", "toggle the background tasks panel"),
+    ("/select", "release the mouse to select text"),
+    ("/sandbox [MODE]", "show or change the sandbox mode"),
+    ("/approval [POLICY]", "show or change the approval policy"),
+    ("/tools", "list loaded tools"),
+    ("/tool NAME", "inspect one tool"),
+    (
+        "/mcp [status]",
+        "MCP servers: state, tools, and recent stderr",
+    ),
+    ("/tree", "show the current session tree"),
+    ("/results", "list this session's tool results"),
+    ("/result N", "open tool result N in a full-screen pager"),
+    ("/help", "show this help"),
+    ("/clear", "clear the conversation"),
+    (
+        "/edit",
+        "edit the last message and regenerate (again for earlier)",
+    ),
+    ("/compact", "summarize older turns to free context"),
+    (
+        "/cost",
+        "this session's tokens and spend by model, agent, and tool",
+    ),
+    ("/usage", "spend across this project's saved sessions"),
+    ("/resume", "resume a previous session in this directory"),
+    (
+        "/export [md|json] [PATH]",
+        "write the conversation to a file",
+    ),
+    (
+        "/attach PATH",
+        "attach an image to the next message (/attach to clear)",
+    ),
+    ("/models", "pick a model from each provider's list"),
+    (
+        "/model [NAME]",
+        "show the model, or switch to a model or alias",
+    ),
+    ("/rlm", "toggle the RLM engine"),
+    (
+        "/context [PATH|GLOB]",
+        "show or set what RLM runs read, e.g. src/**/*.rs",
+    ),
+    ("/cd [DIR]", "show or change the directory tools run in"),
+    ("/thinking on|off", "show or hide model reasoning"),
+    (
+        "/meta on|off",
+        "show time, duration, tokens, and model under messages",
+    ),
+    ("/quit", "exit rot"),
+];
+
+/// One row of the overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HelpLine {
+    Heading(String),
+    Entry { key: String, description: String },
+}
+
+/// State of the open help overlay.
+#[derive(Debug, Default)]
+pub struct HelpOverlay {
+    /// Filter typed into the overlay.
+    pub query: String,
+    /// First row shown.
+    pub scroll: usize,
+    /// Rows visible in the last render, for paging.
+    pub page: usize,
+}
+
+impl HelpOverlay {
+    /// Handle a key press. Returns `false` once the overlay should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let page = self.page.max(1);
+        match key.code {
+            KeyCode::Esc if !self.query.is_empty() => {
+                self.query.clear();
+                self.scroll = 0;
+            }
+            KeyCode::Esc | KeyCode::Enter => return false,
+            KeyCode::Char('?') if self.query.is_empty() => return false,
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.query.push(c);
+                self.scroll = 0;
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.scroll = 0;
+            }
+            KeyCode::Down => self.scroll += 1,
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown => self.scroll += page,
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::Home => self.scroll = 0,
+            // Clamped to the last page during render.
+            KeyCode::End => self.scroll = usize::MAX,
+            _ => {}
+        }
+        true
+    }
+
+    /// Rows matching the query. A matching heading keeps its whole section.
+    pub fn lines(&self, security: &RuntimeSecurityConfig) -> Vec<HelpLine> {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let security_entries = [
+            ("Sandbox mode", security.sandbox_mode.as_str()),
+            ("Approval policy", security.approval_policy.as_str()),
+            ("Network access", on_off(security.sandbox_network_access)),
+            ("Dry run", on_off(security.dry_run)),
+        ];
+        let sections = KEYBINDINGS
+            .iter()
+            .map(|(title, entries)| (*title, entries.to_vec()))
+            .chain([("Slash commands", COMMANDS.to_vec())])
+            .chain([("Security", security_entries.to_vec())]);
+
+        let query = self.query.to_lowercase();
+        let matches = |text: &str| text.to_lowercase().contains(&query);
+        let mut lines = Vec::new();
+        for (title, entries) in sections {
+            let whole_section = matches(title);
+            let shown: Vec<_> = entries
+                .into_iter()
+                .filter(|(key, description)| whole_section || matches(key) || matches(description))
+                .collect();
+            if shown.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(HelpLine::Heading(String::new()));
+            }
+            lines.push(HelpLine::Heading(title.to_string()));
+            lines.extend(shown.into_iter().map(|(key, description)| HelpLine::Entry {
+                key: key.to_string(),
+                description: description.to_string(),
+            }));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_query_filters_entries_and_esc_clears_then_closes() {
+        let mut help = HelpOverlay::default();
+        let security = RuntimeSecurityConfig::default();
+        let all = help.lines(&security);
+        assert!(all.contains(&HelpLine::Entry {
+            key: "Sandbox mode".to_string(),
+            description: "workspace-write".to_string(),
+        }));
+
+        for c in "ctrl+t".chars() {
+            assert!(help.handle_key(key(KeyCode::Char(c))));
+        }
+        assert_eq!(
+            help.lines(&security),
+            vec![
+                HelpLine::Heading("Any mode".to_string()),
+                HelpLine::Entry {
+                    key: "Ctrl+T".to_string(),
+                    description: "show or hide the background tasks panel".to_string(),
+                },
+            ]
+        );
+
+        assert!(help.handle_key(key(KeyCode::Esc)));
+        assert_eq!(help.lines(&security).len(), all.len());
+        assert!(!help.handle_key(key(KeyCode::Esc)));
+    }
+}
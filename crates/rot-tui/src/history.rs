@@ -0,0 +1,147 @@
+//! Per-project history of submitted prompts.
+//!
+//! Each prompt is stored as one JSON string per line so multi-line prompts
+//! survive the round trip. The file is shared by every session in the same
+//! working directory.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Most prompts kept in memory and on disk.
+const MAX_ENTRIES: usize = 1000;
+
+/// Submitted prompts, oldest first.
+#[derive(Debug, Default)]
+pub struct PromptHistory {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+}
+
+impl PromptHistory {
+    /// Load the history stored at `path`; a missing or unreadable file gives an empty history.
+    pub fn load(path: PathBuf) -> Self {
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        let mut entries: Vec<String> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let line_count = content.lines().count();
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+
+        let history = Self {
+            path: Some(path),
+            entries,
+        };
+        if line_count > history.entries.len() {
+            history.rewrite();
+        }
+        history
+    }
+
+    /// Prompts, oldest first.
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Record a submitted prompt unless it is blank or repeats the last one.
+    pub fn push(&mut self, prompt: &str) {
+        if prompt.trim().is_empty() || self.entries.last().is_some_and(|last| last == prompt) {
+            return;
+        }
+        self.entries.push(prompt.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        let line = serde_json::to_string(prompt).unwrap_or_default();
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{line}"));
+        if let Err(e) = result {
+            tracing::warn!("Failed to save prompt history to {}: {e}", path.display());
+        }
+    }
+
+    /// Indices of entries matching `query` fuzzily, best first, newest
+    /// first among equals, each distinct prompt once.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let mut seen = std::collections::HashSet::new();
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| seen.insert(entry.as_str()))
+            .filter_map(|(idx, entry)| {
+                crate::mentions::fuzzy_score(entry, query).map(|score| (score, idx))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.cmp(&a.1)));
+        scored.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    /// Replace the file with the entries kept in memory.
+    fn rewrite(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let content: String = self
+            .entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect();
+        if let Err(e) = std::fs::write(path, content) {
+            tracing::warn!(
+                "Failed to compact prompt history at {}: {e}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_round_trip_skips_repeats() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompt_history");
+
+        let mut history = PromptHistory::load(path.clone());
+        history.push("fix the parser");
+        history.push("fix the parser");
+        history.push("   ");
+        history.push("explain\nsrc/lib.rs");
+
+        let reloaded = PromptHistory::load(path);
+        assert_eq!(
+            reloaded.entries(),
+            ["fix the parser", "explain\nsrc/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn test_history_search_prefers_closer_and_newer_matches() {
+        let mut history = PromptHistory::default();
+        for prompt in [
+            "run tests",
+            "refactor parser",
+            "run the tests again",
+            "run tests",
+        ] {
+            history.entries.push(prompt.to_string());
+        }
+
+        let matches = history.search("run tests");
+        assert_eq!(matches, vec![3, 2]);
+        assert!(history.search("zzz").is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! Image attachments for vision-capable models.
+//!
+//! Images are attached with `/attach path` or by mentioning an image file
+//! as `@path`, and are sent as `ContentBlock::Image` after the prompt text.
+
+use base64::Engine;
+use rot_core::ContentBlock;
+use std::path::Path;
+
+/// Largest image attached, in bytes. Providers reject larger uploads.
+const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// An image waiting to be sent with the next prompt.
+#[derive(Debug, Clone)]
+pub struct ImageAttachment {
+    /// File name shown in the chat.
+    pub name: String,
+    pub size: u64,
+    pub block: ContentBlock,
+}
+
+impl ImageAttachment {
+    /// Placeholder shown in the chat and input box.
+    pub fn chip(&self) -> String {
+        format!("[image: {} · {} KB]", self.name, self.size.div_ceil(1024))
+    }
+}
+
+/// MIME type of a supported image file, from its extension.
+pub fn image_mime(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Read and encode the image at `path`.
+pub fn load_image(path: &Path) -> Result<ImageAttachment, String> {
+    let Some(mime_type) = image_mime(path) else {
+        return Err(format!(
+            "{} is not a supported image (png, jpeg, gif, webp)",
+            path.display()
+        ));
+    };
+    let size = path
+        .metadata()
+        .map_err(|e| format!("Cannot read {}: {e}", path.display()))?
+        .len();
+    if size > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "{} is {} KB; images over {} KB cannot be attached",
+            path.display(),
+            size / 1024,
+            MAX_IMAGE_BYTES / 1024
+        ));
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string());
+    Ok(ImageAttachment {
+        name,
+        size,
+        block: ContentBlock::Image {
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+            mime_type: mime_type.to_string(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_image_encodes_supported_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("shot.PNG");
+        std::fs::write(&png, b"hello").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"text").unwrap();
+
+        let image = load_image(&png).unwrap();
+        assert_eq!(image.chip(), "[image: shot.PNG · 1 KB]");
+        match image.block {
+            ContentBlock::Image { data, mime_type } => {
+                assert_eq!(data, "aGVsbG8=");
+                assert_eq!(mime_type, "image/png");
+            }
+            other => panic!("expected image block, got {other:?}"),
+        }
+        assert!(load_image(&dir.path().join("notes.txt")).is_err());
+        assert!(load_image(&dir.path().join("missing.png")).is_err());
+    }
+}
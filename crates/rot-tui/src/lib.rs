@@ -1,7 +1,18 @@
 //! rot-tui: Terminal user interface for rot.
 
 pub mod app;
+mod approval;
+mod clipboard;
+mod crash;
+mod editor;
 pub mod event;
+mod help;
+pub mod history;
+pub mod images;
+mod markdown;
+mod mentions;
+mod notify;
+mod pager;
 pub mod runner;
 
 pub use app::{App, AppState, ChatStyle};
@@ -0,0 +1,539 @@
+//! Block-level markdown rendering for chat messages.
+//!
+//! Assistant responses are parsed with pulldown-cmark and turned into styled
+//! ratatui lines: headings, lists, blockquotes, tables, rules, and fenced code
+//! blocks with a background. Inline emphasis, code, and links are styled
+//! within each line; wrapping is left to the paragraph widget.
+
+use crate::app::{COLOR_ACCENT, COLOR_BORDER, COLOR_CODE_BG, COLOR_CODE_FG, COLOR_DIM};
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::prelude::*;
+
+/// Width used for rules and code backgrounds when the area is unknown.
+const FALLBACK_WIDTH: usize = 80;
+
+/// Render `text` as markdown. `width` is the columns available for content,
+/// used to size rules and code block backgrounds; `0` means unknown.
+pub fn render(text: &str, base: Style, width: usize) -> Vec<Line<'static>> {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer::new(base, width);
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+/// Contents of the fenced and indented code blocks in `text`, in order.
+pub fn code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<String> = None;
+    for event in Parser::new(text) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => current = Some(String::new()),
+            Event::Text(chunk) => {
+                if let Some(block) = &mut current {
+                    block.push_str(&chunk);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+struct List {
+    /// Next number for ordered lists.
+    next: Option<u64>,
+    /// Width of the current item marker, used to indent continuation lines.
+    indent: usize,
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    cell: String,
+}
+
+struct Renderer {
+    width: usize,
+    lines: Vec<Line<'static>>,
+    current: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    lists: Vec<List>,
+    /// Marker for the list item whose first line has not been emitted yet.
+    marker: Option<String>,
+    quote_depth: usize,
+    code: Option<(String, String)>,
+    table: Option<Table>,
+    /// Link destinations and the span index where each link's text starts.
+    links: Vec<(String, usize)>,
+}
+
+impl Renderer {
+    fn new(base: Style, width: usize) -> Self {
+        Self {
+            width: if width == 0 { FALLBACK_WIDTH } else { width },
+            lines: Vec::new(),
+            current: Vec::new(),
+            styles: vec![base],
+            lists: Vec::new(),
+            marker: None,
+            quote_depth: 0,
+            code: None,
+            table: None,
+            links: Vec::new(),
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    fn pop_style(&mut self) {
+        if self.styles.len() > 1 {
+            self.styles.pop();
+        }
+    }
+
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => {
+                if let Some(table) = &mut self.table {
+                    table.cell.push_str(&code);
+                } else {
+                    self.current.push(Span::styled(
+                        code.into_string(),
+                        Style::default().fg(COLOR_CODE_FG).bg(COLOR_CODE_BG),
+                    ));
+                }
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.text(&html),
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.gap();
+                let rule = "─".repeat(self.width.saturating_sub(self.prefix_width()));
+                let mut spans = self.prefix();
+                spans.push(Span::styled(rule, Style::default().fg(COLOR_BORDER)));
+                self.lines.push(Line::from(spans));
+            }
+            Event::TaskListMarker(checked) => {
+                let mark = if checked { "[x] " } else { "[ ] " };
+                self.current
+                    .push(Span::styled(mark, Style::default().fg(COLOR_ACCENT)));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph if self.lists.is_empty() => self.gap(),
+            Tag::Heading { level, .. } => {
+                self.gap();
+                let style = match level {
+                    HeadingLevel::H1 => Style::default().fg(COLOR_ACCENT).bold().underlined(),
+                    HeadingLevel::H2 => Style::default().fg(COLOR_ACCENT).bold(),
+                    _ => Style::default().bold(),
+                };
+                self.push_style(style);
+            }
+            Tag::BlockQuote(_) => {
+                self.gap();
+                self.quote_depth += 1;
+                self.push_style(Style::default().fg(COLOR_DIM).italic());
+            }
+            Tag::CodeBlock(kind) => {
+                self.gap();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => {
+                        lang.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((lang, String::new()));
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.gap();
+                } else {
+                    self.flush();
+                }
+                self.lists.push(List {
+                    next: start,
+                    indent: 2,
+                });
+            }
+            Tag::Item => {
+                self.flush();
+                let depth = self.lists.len();
+                if let Some(list) = self.lists.last_mut() {
+                    let marker = match &mut list.next {
+                        Some(n) => {
+                            *n += 1;
+                            format!("{}. ", *n - 1)
+                        }
+                        None => match depth {
+                            1 => "• ".to_string(),
+                            2 => "◦ ".to_string(),
+                            _ => "▪ ".to_string(),
+                        },
+                    };
+                    list.indent = marker.chars().count();
+                    self.marker = Some(marker);
+                }
+            }
+            Tag::Table(alignments) => {
+                self.gap();
+                self.table = Some(Table {
+                    alignments,
+                    ..Default::default()
+                });
+            }
+            Tag::Emphasis => self.push_style(Style::default().italic()),
+            Tag::Strong => self.push_style(Style::default().bold()),
+            Tag::Strikethrough => self.push_style(Style::default().crossed_out()),
+            Tag::Link { dest_url, .. } => {
+                self.links
+                    .push((dest_url.into_string(), self.current.len()));
+                self.push_style(Style::default().fg(COLOR_ACCENT).underlined());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.flush(),
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.pop_style();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.pop_style();
+            }
+            TagEnd::CodeBlock => {
+                if let Some((lang, code)) = self.code.take() {
+                    self.code_block(&lang, &code);
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::Item => self.flush(),
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header = std::mem::take(&mut table.row);
+                }
+            }
+            TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::TableCell => {
+                if let Some(table) = &mut self.table {
+                    let cell = std::mem::take(&mut table.cell);
+                    table.row.push(cell.trim().to_string());
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.table_block(table);
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => self.pop_style(),
+            TagEnd::Link => {
+                self.pop_style();
+                if let Some((dest, start)) = self.links.pop() {
+                    let label: String = self.current[start.min(self.current.len())..]
+                        .iter()
+                        .map(|s| s.content.as_ref())
+                        .collect();
+                    if !dest.is_empty() && label != dest && !dest.starts_with('#') {
+                        self.current.push(Span::styled(
+                            format!(" ({dest})"),
+                            Style::default().fg(COLOR_DIM),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some((_, code)) = &mut self.code {
+            code.push_str(text);
+        } else if let Some(table) = &mut self.table {
+            table.cell.push_str(text);
+        } else {
+            // Html blocks and raw text can span lines.
+            let mut parts = text.split('\n').peekable();
+            while let Some(part) = parts.next() {
+                if !part.is_empty() {
+                    self.current
+                        .push(Span::styled(part.to_string(), self.style()));
+                }
+                if parts.peek().is_some() {
+                    self.flush();
+                }
+            }
+        }
+    }
+
+    /// Quote bars and list indentation for the next line.
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        for _ in 0..self.quote_depth {
+            spans.push(Span::styled("│ ", Style::default().fg(COLOR_DIM)));
+        }
+        let marker = self.marker.take();
+        let depth = self.lists.len();
+        for (i, list) in self.lists.iter().enumerate() {
+            match &marker {
+                Some(marker) if i + 1 == depth => {
+                    spans.push(Span::styled(
+                        marker.clone(),
+                        Style::default().fg(COLOR_ACCENT),
+                    ));
+                }
+                _ => spans.push(Span::raw(" ".repeat(list.indent))),
+            }
+        }
+        spans
+    }
+
+    fn prefix_width(&self) -> usize {
+        self.quote_depth * 2 + self.lists.iter().map(|l| l.indent).sum::<usize>()
+    }
+
+    /// Emit the pending line, if any.
+    fn flush(&mut self) {
+        if self.current.is_empty() && self.marker.is_none() {
+            return;
+        }
+        let mut spans = self.prefix();
+        spans.append(&mut self.current);
+        self.lines.push(Line::from(spans));
+    }
+
+    /// Separate blocks with one blank line.
+    fn gap(&mut self) {
+        self.flush();
+        if self
+            .lines
+            .last()
+            .is_some_and(|l| l.width() > self.prefix_width())
+        {
+            let spans = self.prefix_without_marker();
+            self.lines.push(Line::from(spans));
+        }
+    }
+
+    fn prefix_without_marker(&mut self) -> Vec<Span<'static>> {
+        let marker = self.marker.take();
+        let spans = self.prefix();
+        self.marker = marker;
+        spans
+    }
+
+    fn code_block(&mut self, lang: &str, code: &str) {
+        let code_style = Style::default().fg(COLOR_CODE_FG).bg(COLOR_CODE_BG);
+        let code_lines: Vec<&str> = code
+            .strip_suffix('\n')
+            .unwrap_or(code)
+            .split('\n')
+            .collect();
+        let longest = code_lines
+            .iter()
+            .map(|l| Line::from(*l).width())
+            .max()
+            .unwrap_or(0);
+        let fill = self
+            .width
+            .saturating_sub(self.prefix_width())
+            .max(longest + 2);
+
+        if !lang.is_empty() {
+            let mut spans = self.prefix();
+            spans.push(Span::styled(
+                format!("{:<fill$}", format!(" {lang}")),
+                Style::default().fg(COLOR_DIM).bg(COLOR_CODE_BG),
+            ));
+            self.lines.push(Line::from(spans));
+        }
+        for line in code_lines {
+            let mut spans = self.prefix();
+            let pad = fill.saturating_sub(Line::from(line).width() + 1);
+            spans.push(Span::styled(
+                format!(" {line}{}", " ".repeat(pad)),
+                code_style,
+            ));
+            self.lines.push(Line::from(spans));
+        }
+    }
+
+    fn table_block(&mut self, table: Table) {
+        let columns = table
+            .rows
+            .iter()
+            .chain(std::iter::once(&table.header))
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                table
+                    .rows
+                    .iter()
+                    .chain(std::iter::once(&table.header))
+                    .filter_map(|row| row.get(col))
+                    .map(|cell| Line::from(cell.as_str()).width())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let border = Style::default().fg(COLOR_BORDER);
+        let base = self.style();
+        let row_line = |this: &mut Self, row: &[String], style: Style| {
+            let mut spans = this.prefix();
+            for (col, width) in widths.iter().enumerate() {
+                if col > 0 {
+                    spans.push(Span::styled(" │ ", border));
+                }
+                let cell = row.get(col).map(String::as_str).unwrap_or("");
+                let text = match table.alignments.get(col) {
+                    Some(Alignment::Right) => format!("{cell:>width$}"),
+                    Some(Alignment::Center) => format!("{cell:^width$}"),
+                    _ => format!("{cell:<width$}"),
+                };
+                spans.push(Span::styled(text, style));
+            }
+            this.lines.push(Line::from(spans));
+        };
+
+        if !table.header.is_empty() {
+            row_line(self, &table.header, base.bold());
+            let mut spans = self.prefix();
+            let rule = widths
+                .iter()
+                .map(|w| "─".repeat(*w))
+                .collect::<Vec<_>>()
+                .join("─┼─");
+            spans.push(Span::styled(rule, border));
+            self.lines.push(Line::from(spans));
+        }
+        for row in &table.rows {
+            row_line(self, row, base);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Line<'static>> {
+        if let Some((lang, code)) = self.code.take() {
+            self.code_block(&lang, &code);
+        }
+        self.flush();
+        while self.lines.last().is_some_and(|l| l.width() == 0) {
+            self.lines.pop();
+        }
+        self.lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(lines: &[Line<'_>]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| {
+                l.spans
+                    .iter()
+                    .map(|s| s.content.as_ref())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_headings_lists_and_quotes() {
+        let lines = render(
+            "# Title\n\nSome **bold** text.\n\n- one\n- two\n  1. nested\n\n> quoted",
+            Style::default(),
+            40,
+        );
+        assert_eq!(
+            plain(&lines),
+            vec![
+                "Title",
+                "",
+                "Some bold text.",
+                "",
+                "• one",
+                "• two",
+                "  1. nested",
+                "",
+                "│ quoted"
+            ]
+        );
+        assert!(lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert!(lines[2].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_render_code_fence_with_background() {
+        let lines = render("```rust\nfn main() {}\n```", Style::default(), 20);
+        assert_eq!(plain(&lines), vec![" rust", " fn main() {}"]);
+        let code = &lines[1].spans[0];
+        assert_eq!(code.style.bg, Some(COLOR_CODE_BG));
+        assert_eq!(code.content.chars().count(), 20);
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let lines = render(
+            "| name | size |\n| --- | ---: |\n| a | 1 |\n| long | 100 |",
+            Style::default(),
+            40,
+        );
+        assert_eq!(
+            plain(&lines),
+            vec!["name │ size", "─────┼─────", "a    │    1", "long │  100"]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_extracts_fences() {
+        let text =
+            "Run:\n\n```bash\ncargo test\n```\n\nthen `inline` and\n\n```\nfn main() {}\n```\n";
+        assert_eq!(code_blocks(text), vec!["cargo test\n", "fn main() {}\n"]);
+        assert!(code_blocks("no code here").is_empty());
+    }
+}
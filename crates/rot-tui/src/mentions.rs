@@ -0,0 +1,195 @@
+//! `@path` file mentions in the input box.
+//!
+//! Typing `@` opens a fuzzy picker over workspace files. When the message is
+//! sent, each mentioned file that resolves inside the workspace is attached
+//! to the prompt; images are sent as image blocks, and files that are too
+//! large or not text are left as a reference for the agent to open with the
+//! `read` tool.
+
+use crate::images::{image_mime, load_image, ImageAttachment};
+use std::path::{Path, PathBuf};
+
+/// Largest file attached inline, in bytes.
+const MAX_ATTACH_BYTES: u64 = 100 * 1024;
+
+/// The `@` token ending at `cursor`, as its byte offset and the text after `@`.
+pub fn mention_at(input: &str, cursor: usize) -> Option<(usize, &str)> {
+    let before = input.get(..cursor)?;
+    let start = before
+        .rfind(char::is_whitespace)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    before[start..]
+        .strip_prefix('@')
+        .map(|query| (start, query))
+}
+
+/// Up to `limit` of `files` that fuzzily match `query`, best first.
+pub fn fuzzy_files(files: &[String], query: &str, limit: usize) -> Vec<String> {
+    let mut scored: Vec<(i64, &String)> = files
+        .iter()
+        .filter_map(|file| fuzzy_score(file, query).map(|score| (score, file)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, f)| f.clone())
+        .collect()
+}
+
+/// Score `candidate` against `query` as a case-insensitive subsequence.
+///
+/// Consecutive matches and matches in the file name score higher; shorter
+/// paths break ties.
+pub(crate) fn fuzzy_score(candidate: &str, query: &str) -> Option<i64> {
+    let candidate_lower = candidate.to_lowercase();
+    let name_start = candidate_lower.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let mut score = 0i64;
+    let mut last: Option<usize> = None;
+    let mut chars = candidate_lower.char_indices();
+    for q in query.to_lowercase().chars() {
+        let (idx, _) = chars.by_ref().find(|(_, c)| *c == q)?;
+        score += match last {
+            Some(prev) if idx == prev + 1 => 8,
+            _ => 1,
+        };
+        if idx >= name_start {
+            score += 2;
+        }
+        last = Some(idx);
+    }
+    if candidate_lower.contains(&query.to_lowercase()) {
+        score += 20;
+    }
+    Some(score * 100 - candidate.len() as i64)
+}
+
+/// Files mentioned as `@path` in `prompt` that resolve inside `root` and are
+/// not excluded by `.rotignore`, as the path written and the canonical path,
+/// without repeats.
+fn mentioned_paths(prompt: &str, root: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(root) = root.canonicalize() else {
+        return Vec::new();
+    };
+    let mut found: Vec<(String, PathBuf)> = Vec::new();
+    for token in prompt.split_whitespace() {
+        let Some(mention) = token.strip_prefix('@') else {
+            continue;
+        };
+        // Allow trailing punctuation after a path, as in "see @src/lib.rs."
+        let trimmed = mention.trim_end_matches(|c: char| ",.;:!?)]}'\"".contains(c));
+        let Some((rel, path)) = [mention, trimmed].into_iter().find_map(|rel| {
+            let path = root.join(rel).canonicalize().ok()?;
+            let allowed = path.is_file()
+                && path.starts_with(&root)
+                && !rot_tools::rotignore::is_ignored(&root, &path);
+            allowed.then(|| (rel.to_string(), path))
+        }) else {
+            continue;
+        };
+        if !found.iter().any(|(seen, _)| *seen == rel) {
+            found.push((rel, path));
+        }
+    }
+    found
+}
+
+/// Append the contents of every `@path` mentioned in `prompt` that resolves
+/// to a file inside `root`. Images are left to [`mentioned_images`].
+pub fn attach_files(prompt: &str, root: &Path) -> String {
+    let mut out = prompt.to_string();
+    for (rel, path) in mentioned_paths(prompt, root) {
+        if image_mime(&path).is_some() {
+            continue;
+        }
+        let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+        let content = (size <= MAX_ATTACH_BYTES)
+            .then(|| std::fs::read_to_string(&path).ok())
+            .flatten();
+        match content {
+            Some(content) => {
+                out.push_str(&format!("\n\n<file path=\"{rel}\">\n{content}"));
+                if !content.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str("</file>");
+            }
+            None => out.push_str(&format!(
+                "\n\n<file path=\"{rel}\" omitted=\"too large or not text; use the read tool\"/>"
+            )),
+        }
+    }
+    out
+}
+
+/// Load every image file mentioned as `@path` in `prompt` inside `root`.
+pub fn mentioned_images(prompt: &str, root: &Path) -> Vec<Result<ImageAttachment, String>> {
+    mentioned_paths(prompt, root)
+        .into_iter()
+        .filter(|(_, path)| image_mime(path).is_some())
+        .map(|(_, path)| load_image(&path))
+        .collect()
+}
+
+/// `prompt` without the file blocks `attach_files` appended to it.
+pub fn strip_attachments(prompt: &str) -> &str {
+    prompt.split("\n\n<file path=\"").next().unwrap_or(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mention_at_cursor() {
+        assert_eq!(mention_at("look at @src/ma", 15), Some((8, "src/ma")));
+        assert_eq!(mention_at("@", 1), Some((0, "")));
+        assert_eq!(mention_at("email a@b", 9), None);
+        assert_eq!(mention_at("@src done", 9), None);
+    }
+
+    #[test]
+    fn test_fuzzy_files_prefers_file_name_matches() {
+        let files = vec![
+            "src/main.rs".to_string(),
+            "docs/maintenance.md".to_string(),
+            "crates/rot-tui/src/app.rs".to_string(),
+        ];
+        assert_eq!(fuzzy_files(&files, "main", 5)[0], "src/main.rs");
+        assert_eq!(
+            fuzzy_files(&files, "tuiapp", 5),
+            vec!["crates/rot-tui/src/app.rs"]
+        );
+        assert!(fuzzy_files(&files, "zzz", 5).is_empty());
+    }
+
+    #[test]
+    fn test_attach_files_inside_workspace_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("repo");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn f() {}").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "token").unwrap();
+
+        let prompt = "explain @src/lib.rs, not @../secret.txt or @missing.rs";
+        let out = attach_files(prompt, &root);
+        assert!(out.starts_with(prompt));
+        assert!(out.contains("<file path=\"src/lib.rs\">\npub fn f() {}\n</file>"));
+        assert!(!out.contains("token"));
+        assert_eq!(out.matches("<file").count(), 1);
+        assert_eq!(strip_attachments(&out), prompt);
+    }
+
+    #[test]
+    fn test_image_mentions_are_sent_as_images() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("shot.png"), b"png").unwrap();
+
+        let prompt = "what is wrong in @shot.png?";
+        assert_eq!(attach_files(prompt, dir.path()), prompt);
+        let images = mentioned_images(prompt, dir.path());
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].as_ref().unwrap().name, "shot.png");
+    }
+}
@@ -0,0 +1,118 @@
+//! Terminal bell and desktop notifications for runs that need attention.
+//!
+//! Focus comes from terminal focus reporting. Terminals without it always
+//! count as focused, so they are only alerted with `when_focused` set.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use rot_core::config::NotificationSettings;
+
+/// Title of desktop notifications.
+const TITLE: &str = "rot";
+
+/// Sends alerts according to the notification settings and terminal focus.
+#[derive(Debug)]
+pub struct Notifier {
+    settings: NotificationSettings,
+    focused: bool,
+}
+
+impl Notifier {
+    pub fn new(settings: NotificationSettings) -> Self {
+        Self {
+            settings,
+            focused: true,
+        }
+    }
+
+    /// Replace the settings after a config reload.
+    pub fn set_settings(&mut self, settings: NotificationSettings) {
+        self.settings = settings;
+    }
+
+    /// Record a terminal focus change.
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn should_notify(&self) -> bool {
+        !self.focused || self.settings.when_focused
+    }
+
+    /// Ring the bell and show a desktop notification with `message`, as configured.
+    pub fn notify(&self, message: &str) {
+        if !self.should_notify() {
+            return;
+        }
+        if self.settings.bell {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|()| stdout.flush());
+        }
+        if self.settings.desktop {
+            show_desktop_notification(message);
+        }
+    }
+}
+
+fn show_desktop_notification(message: &str) {
+    let Some(mut command) = desktop_command(message) else {
+        return;
+    };
+    let spawned = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match spawned {
+        // Reap the helper without blocking the UI.
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => tracing::warn!("Failed to show desktop notification: {e}"),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_command(message: &str) -> Option<Command> {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(format!(
+        "display notification {} with title {}",
+        quote(message),
+        quote(TITLE)
+    ));
+    Some(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn desktop_command(message: &str) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command.arg(TITLE).arg(message);
+    Some(command)
+}
+
+#[cfg(not(unix))]
+fn desktop_command(_message: &str) -> Option<Command> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notifies_only_when_unfocused_by_default() {
+        let mut notifier = Notifier::new(NotificationSettings::default());
+        assert!(!notifier.should_notify());
+        notifier.set_focused(false);
+        assert!(notifier.should_notify());
+
+        notifier.set_focused(true);
+        notifier.set_settings(NotificationSettings {
+            when_focused: true,
+            ..NotificationSettings::default()
+        });
+        assert!(notifier.should_notify());
+    }
+}
@@ -0,0 +1,123 @@
+//! Full-screen pager for long text, such as a past tool result opened with
+//! `/result N`.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// State of the open pager.
+#[derive(Debug)]
+pub struct Pager {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// Rows `lines` wrapped to in the last render.
+    pub rows: usize,
+    /// First row shown.
+    pub scroll: usize,
+    /// Rows visible in the last render, for paging.
+    pub page: usize,
+    /// Whether `g` was pressed, waiting for a second `g`.
+    pending_g: bool,
+}
+
+impl Pager {
+    pub fn new(title: impl Into<String>, text: &str) -> Self {
+        let lines: Vec<String> = text
+            .lines()
+            .map(|line| line.replace('\t', "    "))
+            .collect();
+        Self {
+            title: title.into(),
+            rows: lines.len(),
+            lines,
+            scroll: 0,
+            page: 0,
+            pending_g: false,
+        }
+    }
+
+    /// Handle a key press. Returns `false` once the pager should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let page = self.page.max(1);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let pending_g = std::mem::take(&mut self.pending_g);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return false,
+            KeyCode::Char('d') if ctrl => self.scroll += page / 2,
+            KeyCode::Char('u') if ctrl => self.scroll = self.scroll.saturating_sub(page / 2),
+            KeyCode::Char('f') if ctrl => self.scroll += page,
+            KeyCode::Char('b') if ctrl => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Enter => self.scroll += 1,
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll += page,
+            KeyCode::PageUp | KeyCode::Char('b') => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::Char('g') if pending_g => self.scroll = 0,
+            KeyCode::Char('g') => self.pending_g = true,
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End | KeyCode::Char('G') => self.scroll = self.max_scroll(),
+            _ => {}
+        }
+        self.scroll = self.scroll.min(self.max_scroll());
+        true
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.rows.saturating_sub(self.page.max(1))
+    }
+
+    /// `lines` cut into rows of at most `width` characters.
+    pub fn wrapped(&self, width: usize) -> Vec<String> {
+        let width = width.max(1);
+        let mut rows = Vec::with_capacity(self.lines.len());
+        for line in &self.lines {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                rows.push(String::new());
+            }
+            rows.extend(
+                chars
+                    .chunks(width)
+                    .map(|chunk| chunk.iter().collect::<String>()),
+            );
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_pager_scrolls_within_text() {
+        let text = (1..=100)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut pager = Pager::new("bash", &text);
+        pager.page = 20;
+
+        assert!(pager.handle_key(key(KeyCode::Char('j'))));
+        assert_eq!(pager.scroll, 1);
+        pager.handle_key(key(KeyCode::PageDown));
+        assert_eq!(pager.scroll, 21);
+        pager.handle_key(key(KeyCode::Char('G')));
+        assert_eq!(pager.scroll, 80);
+        pager.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(pager.scroll, 80);
+        pager.handle_key(key(KeyCode::Char('g')));
+        pager.handle_key(key(KeyCode::Char('g')));
+        assert_eq!(pager.scroll, 0);
+        pager.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(pager.scroll, 0);
+
+        assert_eq!(
+            Pager::new("read", "abcdefg\n\nxy").wrapped(3),
+            ["abc", "def", "g", "", "xy"]
+        );
+        assert!(!pager.handle_key(key(KeyCode::Char('q'))));
+        assert!(!pager.handle_key(key(KeyCode::Esc)));
+    }
+}
@@ -4,30 +4,33 @@
 //! responsive, showing thinking animation and streaming text while
 //! the LLM generates its response.
 
-use crate::app::{App, AppState, ChatStyle, ConfigUiState, InputMode};
+use crate::app::{App, AppState, ChatStyle, ConfigUiState, InputMode, ModelListing};
 use crate::event::{is_quit, poll_event, TermEvent};
-use crossterm::event::{EnableMouseCapture, DisableMouseCapture, KeyCode, KeyModifiers};
-use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
-};
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture, KeyCode, KeyModifiers};
 use crossterm::ExecutableCommand;
 use ratatui::prelude::*;
+use rot_core::hooks::{HookEvent, HookPayload, Hooks};
 use rot_core::permission::ApprovalResponse;
 use rot_core::{Agent, AgentConfig, AgentRegistry, ContentBlock, Message};
 use rot_session::{Session, SessionEntry};
 use tokio::sync::{mpsc, oneshot};
 
+use std::collections::VecDeque;
 use std::io::stdout;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// How often the config files are checked for edits.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Messages sent from the background processing task back to the TUI.
 enum AgentEvent {
     Response {
         text: String,
-        tool_names: Vec<String>,
-        input_tokens: usize,
-        output_tokens: usize,
+        /// Agent a leading `@name` routed the prompt to.
+        routed_agent: Option<String>,
+        /// Tool calls made during the turn.
+        tools: Vec<rot_core::message::ToolExchange>,
     },
     /// Agent is requesting permission to run a tool.
     ApprovalRequest {
@@ -37,47 +40,152 @@ enum AgentEvent {
     },
     /// Agent encountered an error.
     Error(String),
-    /// Iterative progress update from background task.
-    Progress(String),
+    /// The turn stopped after the user cancelled it.
+    Cancelled,
+    /// Progress of an RLM run.
+    Progress(rot_rlm::RlmProgress),
+    /// Agent loop started a new iteration.
+    Iteration(rot_core::IterationProgress),
+    /// A delegated task started, ran a tool, or finished.
+    Task(rot_core::TaskEvent),
+    /// A tool call started or finished.
+    Tool(rot_core::ToolEvent),
+    /// Token usage reported by the provider for one request.
+    Usage(rot_core::UsageReport),
+    /// Reasoning text streamed by the model.
+    Thinking(String),
+    /// Reply text streamed by the model.
+    Text(String),
+    /// The conversation of the running turn, before its next request.
+    Checkpoint(Vec<Message>),
+    /// A provider request failed and will be retried.
+    Retrying {
+        attempt: u32,
+        max_retries: u32,
+        delay_ms: u64,
+        error: String,
+    },
+    /// A model list requested by the picker was loaded.
+    ModelsLoaded {
+        provider: String,
+        listing: ModelListing,
+    },
+    /// `/compact` finished; `messages` is the compacted conversation.
+    Compacted {
+        compaction: Option<rot_core::compaction::Compaction>,
+        messages: Vec<Message>,
+    },
+}
+
+impl AgentEvent {
+    /// The event as a crash report lists it; streamed text is not listed.
+    fn describe(&self) -> Option<String> {
+        let described = match self {
+            AgentEvent::Response { .. } => "agent response".to_string(),
+            AgentEvent::ApprovalRequest { tool_name, .. } => {
+                format!("approval request for {tool_name}")
+            }
+            AgentEvent::Error(message) => format!("agent error: {message}"),
+            AgentEvent::Cancelled => "turn cancelled".to_string(),
+            AgentEvent::Progress(_) => "rlm progress".to_string(),
+            AgentEvent::Iteration(_) => "agent iteration".to_string(),
+            AgentEvent::Task(_) => "task event".to_string(),
+            AgentEvent::Tool(rot_core::ToolEvent::Started { name, .. }) => {
+                format!("tool {name} started")
+            }
+            AgentEvent::Tool(rot_core::ToolEvent::Finished { exchange, .. }) => {
+                format!("tool {} finished", exchange.name)
+            }
+            AgentEvent::Usage(_) => "usage report".to_string(),
+            AgentEvent::Checkpoint(_) => "checkpoint".to_string(),
+            AgentEvent::Retrying { error, .. } => format!("provider retry: {error}"),
+            AgentEvent::Thinking(_) | AgentEvent::Text(_) => return None,
+            AgentEvent::ModelsLoaded { provider, .. } => format!("models loaded for {provider}"),
+            AgentEvent::Compacted { .. } => "compacted".to_string(),
+        };
+        Some(described)
+    }
 }
 
 /// Run the TUI application.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_tui(
     provider: Box<dyn rot_provider::Provider>,
-    tools: rot_tools::ToolRegistry,
+    mut tools: rot_tools::ToolRegistry,
     session_store: rot_session::SessionStore,
     model: &str,
     provider_name: &str,
     agent_name: &str,
     system_prompt: String,
-    runtime_security: rot_core::RuntimeSecurityConfig,
+    mut runtime_security: rot_core::RuntimeSecurityConfig,
+    max_iterations: usize,
+    resume: Option<Session>,
 ) -> std::io::Result<()> {
-    // Setup terminal
-    enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?;
-    stdout().execute(EnableMouseCapture)?;
+    // Setup terminal; the guard restores it however this function exits.
+    let terminal_guard = crate::crash::TerminalGuard::enter()?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
 
     let mut app = App::new(model, provider_name, agent_name);
+    app.security = runtime_security.clone();
+    if provider_name == "openrouter" {
+        // Its catalogue was fetched at launch; rebuilds keep every listed model.
+        app.set_model_listing(
+            provider_name.to_string(),
+            ModelListing::Live(provider.models()),
+        );
+    }
+    app.max_iterations = max_iterations;
 
     // Show welcome banner
     app.show_welcome();
 
-    // Create session
+    // Create the session, or continue the one given
     let cwd = std::env::current_dir()?;
-    let session = session_store
-        .create(&cwd, model, provider_name)
-        .await
-        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    let resuming = resume.is_some();
+    let mut session = match resume {
+        Some(session) => session,
+        None => session_store
+            .create(&cwd, model, provider_name)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?,
+    };
+    crate::crash::set_session(&session.id);
+    app.history = crate::history::PromptHistory::load(session_store.history_path(&cwd));
+    let restored = if resuming {
+        restore_session(&mut app, &session, &session.id)
+    } else {
+        Vec::new()
+    };
 
     // Build agent (shared for background tasks)
-    let config = agent_config(agent_name, Some(system_prompt));
+    let config = agent_config(agent_name, Some(system_prompt), app.max_iterations);
 
     // Channel for agent results
     let (tx, mut rx) = mpsc::unbounded_channel::<AgentEvent>();
 
     let config_store = rot_core::config::ConfigStore::new();
+    let mut loaded_config = config_store.load();
+    rot_core::mcp_json::add_trusted_project_mcp_servers(&mut loaded_config, &cwd);
+    let mut config_warnings = config_store.warnings();
+    for warning in &config_warnings {
+        app.push_chat(
+            "system",
+            &format!("Config warning: {warning}"),
+            ChatStyle::System,
+        );
+    }
+    app.model_aliases = loaded_config.aliases.clone();
+    app.custom_providers = custom_provider_names(&loaded_config);
+    app.rlm_enabled = loaded_config.rlm.enabled;
+    app.rlm_context = loaded_config.rlm.context.clone();
+    let mut notifier = crate::notify::Notifier::new(loaded_config.notifications.clone());
+    let mut hooks = Arc::new(Hooks::new(loaded_config.hooks.clone()));
+    let mut config_watcher = rot_core::ConfigWatcher::new(&config_store);
+    if let Some(mcp_json) = rot_core::mcp_json::project_mcp_json(&cwd) {
+        config_watcher = config_watcher.watch(mcp_json);
+    }
+    let mut last_config_check = Instant::now();
+    let mut reload_pending = false;
 
     // We clone tx to use it inside the on_approval callback
     let approval_tx = tx.clone();
@@ -91,12 +199,25 @@ pub async fn run_tui(
         runtime_security_for_agent,
         session.id.clone(),
         approval_tx_clone.clone(),
+        app.queued_inputs.clone(),
+        app.working_dir.clone(),
+    );
+    let mut rlm_runtime = build_rlm_runtime(
+        &mut app,
+        &loaded_config,
+        &tools,
+        &runtime_security,
+        &session.id,
+        &approval_tx,
     );
     
-    let messages: Arc<Mutex<Vec<Message>>> = Arc::new(Mutex::new(Vec::new()));
+    // Number of `messages` already written to the session file.
+    let mut persisted = restored.len();
+    let messages: Arc<Mutex<Vec<Message>>> = Arc::new(Mutex::new(restored));
 
     // Check if the current provider needs an API key on first launch
-    let has_key = config_store.load().api_keys.get(app.provider.as_str()).map(|s| !s.is_empty()).unwrap_or(false)
+    let has_key = loaded_config.providers.contains_key(&app.provider)
+        || config_store.has_api_key(&app.provider)
         || std::env::var(format!("{}_API_KEY", app.provider.to_uppercase())).is_ok();
 
     if !has_key {
@@ -107,84 +228,404 @@ pub async fn run_tui(
             input: String::new(),
             cursor_pos: 0,
         };
+    } else if !resuming {
+        let sessions = resumable_sessions(&session_store, &cwd, &session.id).await;
+        if !sessions.is_empty() {
+            app.open_session_picker(sessions);
+        }
     }
 
     // Main loop
+    let mut mouse_captured = true;
     while app.running {
+        // Select mode hands the mouse back to the terminal.
+        if mouse_captured == app.select_mode {
+            if app.select_mode {
+                stdout().execute(DisableMouseCapture)?;
+            } else {
+                stdout().execute(EnableMouseCapture)?;
+            }
+            mouse_captured = !app.select_mode;
+        }
         terminal.draw(|frame| app.render(frame))?;
 
         // Check for agent completion (non-blocking)
         while let Ok(event) = rx.try_recv() {
+            if let Some(described) = event.describe() {
+                crate::crash::record(described);
+            }
             match event {
                 AgentEvent::Response {
                     text,
-                    tool_names,
-                    input_tokens,
-                    output_tokens,
+                    routed_agent,
+                    tools,
                 } => {
                     // Show tool calls before the response
-                    for name in &tool_names {
-                        app.push_chat("tool", &format!("↳ {name}"), ChatStyle::Tool);
+                    if let Some(name) = routed_agent {
+                        app.push_chat("tool", &format!("↳ @{name}"), ChatStyle::Tool);
+                    }
+                    // Calls already shown as they finished are skipped.
+                    for exchange in tools.iter().skip(app.end_tool_progress()) {
+                        app.push_tool_exchange(exchange);
+                    }
+                    app.flush_thinking();
+                    app.push_reply(&text);
+                    if let Err(e) =
+                        persist_messages(&session_store, &mut session, &messages, &mut persisted)
+                            .await
+                    {
+                        app.push_chat("error", &e, ChatStyle::Error);
                     }
-                    app.push_chat("rot", &text, ChatStyle::Assistant);
                     app.stop_timer();
-                    app.record_tokens(input_tokens, output_tokens);
+                    app.turn_cancel = None;
                     app.state = AppState::Idle;
-                    app.rlm_iterating = false;
+                    app.rlm_progress = None;
+                    app.iteration_progress = None;
                     app.status = "Ready".to_string();
                     app.streaming_text.clear();
+
+                    hooks.fire_in_background(
+                        hook_payload(&app, &agent, HookEvent::RunFinished).with_summary(&text),
+                    );
+
+                    // Follow-ups the agent did not pick up mid-run start the next turn.
+                    let follow_ups = app.take_queued_inputs();
+                    if follow_ups.is_empty() {
+                        notifier.notify("Run finished");
+                    } else {
+                        start_turn(
+                            &mut app,
+                            agent.clone(),
+                            &rlm_runtime,
+                            &hooks,
+                            &messages,
+                            &tx,
+                            follow_ups.join("\n\n"),
+                            None,
+                        );
+                    }
                 }
                 AgentEvent::ApprovalRequest { tool_name, args, tx } => {
-                    app.state = AppState::Approval;
-                    app.pending_approval_tool = Some(tool_name);
-                    app.pending_approval_args = Some(args);
-                    app.pending_approval_tx = Some(tx);
+                    notifier.notify(&format!("Approval needed for {tool_name}"));
+                    hooks.fire_in_background(
+                        hook_payload(&app, &agent, HookEvent::ApprovalNeeded)
+                            .with_summary(&format!("{tool_name} {args}"))
+                            .with_tool(&tool_name),
+                    );
+                    app.begin_approval(tool_name, args, tx);
                 }
                 AgentEvent::Error(e) => {
                     app.push_chat("error", &e, ChatStyle::Error);
-                    app.stop_timer();
-                    app.state = AppState::Idle;
-                    app.rlm_iterating = false;
-                    app.status = "Ready".to_string();
-                    app.streaming_text.clear();
+                    if let Err(e) =
+                        persist_messages(&session_store, &mut session, &messages, &mut persisted)
+                            .await
+                    {
+                        app.push_chat("error", &e, ChatStyle::Error);
+                    }
+                    app.end_interrupted_turn("Run failed");
+                    notifier.notify("Run failed");
+                    hooks.fire_in_background(
+                        hook_payload(&app, &agent, HookEvent::RunFailed).with_summary(&e),
+                    );
+                }
+                AgentEvent::Cancelled => {
+                    app.push_chat("system", "Request cancelled.", ChatStyle::System);
+                    if let Err(e) =
+                        persist_messages(&session_store, &mut session, &messages, &mut persisted)
+                            .await
+                    {
+                        app.push_chat("error", &e, ChatStyle::Error);
+                    }
+                    app.end_interrupted_turn("Run cancelled");
+                }
+                AgentEvent::Progress(progress) => {
+                    app.rlm_progress = Some(progress);
+                }
+                AgentEvent::Iteration(progress) => {
+                    app.record_iteration(progress);
+                }
+                AgentEvent::Task(event) => {
+                    app.record_task_event(event);
+                }
+                AgentEvent::Tool(event) => {
+                    app.record_tool_event(event);
+                }
+                AgentEvent::Thinking(delta) => {
+                    app.push_thinking(&delta);
+                }
+                AgentEvent::Text(delta) => {
+                    app.push_streaming_text(&delta);
+                }
+                AgentEvent::Checkpoint(snapshot) => {
+                    // Saved as the turn runs, so a crash or kill loses little.
+                    if app.save_checkpoints {
+                        let start = persisted.min(snapshot.len());
+                        match append_messages(&session_store, &mut session, &snapshot[start..])
+                            .await
+                        {
+                            Ok(()) => persisted = persisted.max(snapshot.len()),
+                            Err(e) => tracing::warn!("{e}"),
+                        }
+                    }
                 }
-                AgentEvent::Progress(_msg) => {
-                    app.rlm_iterating = true;
+                AgentEvent::Retrying {
+                    attempt,
+                    max_retries,
+                    delay_ms,
+                    error,
+                } => {
+                    let wait = format!("{:.1}s", delay_ms as f64 / 1000.0);
+                    app.push_chat(
+                        "system",
+                        &format!("Provider error, retrying in {wait} (retry {attempt} of {max_retries}): {error}"),
+                        ChatStyle::System,
+                    );
+                    app.status = format!("Retrying in {wait}…");
+                }
+                AgentEvent::Usage(report) => {
+                    app.record_usage(&report);
+                    let entry = rot_core::usage::usage_entry(
+                        &report,
+                        &app.usage_agent(report.task_id.as_deref()),
+                    );
+                    if let Err(e) = session_store.append(&mut session, entry).await {
+                        tracing::warn!("Failed to save token usage: {e}");
+                    }
+                }
+                AgentEvent::ModelsLoaded { provider, listing } => {
+                    app.set_model_listing(provider, listing);
+                }
+                AgentEvent::Compacted {
+                    compaction,
+                    messages: compacted,
+                } => {
+                    if let Some(compaction) = &compaction {
+                        let entry = SessionEntry::Compaction {
+                            id: rot_core::MessageId::new().as_str().to_string(),
+                            timestamp: std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs(),
+                            summary: compaction.summary.clone(),
+                            first_kept_id: compaction.first_kept_id.clone().unwrap_or_default(),
+                        };
+                        if let Err(e) = session_store.append(&mut session, entry).await {
+                            app.push_chat(
+                                "error",
+                                &format!("Failed to save compaction: {e}"),
+                                ChatStyle::Error,
+                            );
+                        }
+                        persisted = compacted.len();
+                        *messages.lock().unwrap() = compacted;
+                    }
+                    app.record_compaction(compaction.as_ref());
+                    app.end_interrupted_turn("Compaction finished");
                 }
             }
         }
 
+        // Rebuild the agent after a model switch from the picker or `/model NAME`.
+        if app.config_changed {
+            app.config_changed = false;
+            match create_provider(&app.provider, &app.model, app.fetched_models(&app.provider)) {
+                Ok(new_provider) => {
+                    let config = agent_config(&app.agent, None, app.max_iterations);
+                    agent = build_agent(
+                        new_provider,
+                        tools.clone(),
+                        config,
+                        runtime_security.clone(),
+                        session.id.clone(),
+                        approval_tx.clone(),
+                        app.queued_inputs.clone(),
+                        app.working_dir.clone(),
+                    );
+                    app.push_chat(
+                        "system",
+                        &format!("Switched model to {} / {}", app.provider, app.model),
+                        ChatStyle::System,
+                    );
+                }
+                Err(e) => {
+                    app.push_chat(
+                        "error",
+                        &format!("Failed to switch model: {}", e),
+                        ChatStyle::Error,
+                    );
+                }
+            }
+        }
+
+        // Load model lists the picker asked for.
+        if let Some(provider_name) = app.take_models_request() {
+            let tx = approval_tx.clone();
+            tokio::spawn(async move {
+                let listing = list_models(&provider_name).await;
+                let _ = tx.send(AgentEvent::ModelsLoaded {
+                    provider: provider_name,
+                    listing,
+                });
+            });
+        }
+
+        // Apply config file edits once the agent is idle.
+        if last_config_check.elapsed() >= CONFIG_POLL_INTERVAL {
+            last_config_check = Instant::now();
+            reload_pending |= config_watcher.poll();
+        }
+        if reload_pending && app.state == AppState::Idle {
+            reload_pending = false;
+            let mut new_config = config_store.load();
+            let mcp_notes =
+                rot_core::mcp_json::add_trusted_project_mcp_servers(&mut new_config, &cwd);
+            let warnings = config_store.warnings();
+            for warning in warnings.iter().filter(|w| !config_warnings.contains(w)) {
+                app.push_chat(
+                    "system",
+                    &format!("Config warning: {warning}"),
+                    ChatStyle::System,
+                );
+            }
+            config_warnings = warnings;
+            let diff = rot_core::ConfigDiff::between(&loaded_config, &new_config);
+            if !diff.is_empty() {
+                if diff.security {
+                    runtime_security = rot_core::ConfigDiff::apply_security(
+                        &loaded_config,
+                        &new_config,
+                        &runtime_security,
+                    );
+                    app.security = runtime_security.clone();
+                }
+                if new_config.max_iterations != loaded_config.max_iterations {
+                    app.max_iterations = new_config.max_iterations.max(1);
+                }
+                app.model_aliases = new_config.aliases.clone();
+                app.custom_providers = custom_provider_names(&new_config);
+                app.model_listings.clear();
+
+                let mut notes = diff.summary.clone();
+                notes.extend(mcp_notes);
+                if diff.tools || diff.security {
+                    match rot_core::build_tool_registry(&new_config, &runtime_security, &cwd).await
+                    {
+                        Ok(new_tools) => tools = new_tools,
+                        Err(e) => notes.push(format!("tools not reloaded: {e}")),
+                    }
+                }
+                match create_provider(&app.provider, &app.model, app.fetched_models(&app.provider))
+                {
+                    Ok(new_provider) => {
+                        let config = agent_config(&app.agent, None, app.max_iterations);
+                        agent = build_agent(
+                            new_provider,
+                            tools.clone(),
+                            config,
+                            runtime_security.clone(),
+                            session.id.clone(),
+                            approval_tx.clone(),
+                            app.queued_inputs.clone(),
+                            app.working_dir.clone(),
+                        );
+                    }
+                    Err(e) => notes.push(format!("agent not rebuilt: {e}")),
+                }
+                notifier.set_settings(new_config.notifications.clone());
+                if new_config.hooks != loaded_config.hooks {
+                    hooks = Arc::new(Hooks::new(new_config.hooks.clone()));
+                }
+                if new_config.rlm.enabled != loaded_config.rlm.enabled {
+                    app.rlm_enabled = new_config.rlm.enabled;
+                }
+                if new_config.rlm.context != loaded_config.rlm.context {
+                    app.rlm_context = new_config.rlm.context.clone();
+                }
+                rlm_runtime = build_rlm_runtime(
+                    &mut app,
+                    &new_config,
+                    &tools,
+                    &runtime_security,
+                    &session.id,
+                    &approval_tx,
+                );
+                app.push_chat(
+                    "system",
+                    &format!("Config reloaded:\n- {}", notes.join("\n- ")),
+                    ChatStyle::System,
+                );
+            }
+            loaded_config = new_config;
+        }
+
+        // Apply /sandbox and /approval changes once the agent is idle.
+        if app.state == AppState::Idle && app.take_security_change() {
+            runtime_security = app.security.clone();
+            match rot_core::build_tool_registry(&loaded_config, &runtime_security, &cwd).await {
+                Ok(new_tools) => tools = new_tools,
+                Err(e) => app.push_chat(
+                    "error",
+                    &format!("Tools not rebuilt for the new security settings: {e}"),
+                    ChatStyle::Error,
+                ),
+            }
+            match create_provider(&app.provider, &app.model, app.fetched_models(&app.provider)) {
+                Ok(new_provider) => {
+                    let config = agent_config(&app.agent, None, app.max_iterations);
+                    agent = build_agent(
+                        new_provider,
+                        tools.clone(),
+                        config,
+                        runtime_security.clone(),
+                        session.id.clone(),
+                        approval_tx.clone(),
+                        app.queued_inputs.clone(),
+                        app.working_dir.clone(),
+                    );
+                }
+                Err(e) => app.push_chat(
+                    "error",
+                    &format!("Agent not rebuilt: {e}"),
+                    ChatStyle::Error,
+                ),
+            }
+            rlm_runtime = build_rlm_runtime(
+                &mut app,
+                &loaded_config,
+                &tools,
+                &runtime_security,
+                &session.id,
+                &approval_tx,
+            );
+        }
+
         // Animate thinking dots
         if app.state == AppState::Thinking || app.state == AppState::Streaming {
             app.tick();
         }
 
-        match poll_event(Duration::from_millis(80))? {
+        let event = poll_event(Duration::from_millis(80))?;
+        if let Some(described) = crate::crash::describe(&event) {
+            crate::crash::record(described);
+        }
+        match event {
             TermEvent::Key(key) => {
                 if is_quit(&key) {
-                    app.running = false;
+                    // The first Ctrl+C stops a running turn; otherwise it quits.
+                    if !app.cancel_turn() {
+                        app.running = false;
+                    }
+                    continue;
+                }
+                if app.handle_security_confirm_key(key)
+                    || app.handle_select_key(key)
+                    || app.handle_help_key(key)
+                    || app.handle_pager_key(key)
+                {
                     continue;
                 }
                 if app.state == AppState::Approval {
-                    let response = match key.code {
-                        KeyCode::Char('y') | KeyCode::Char('Y') => Some(ApprovalResponse::AllowOnce),
-                        KeyCode::Char('a') | KeyCode::Char('A') => Some(ApprovalResponse::AllowAlways),
-                        KeyCode::Char('n') | KeyCode::Char('N') => Some(ApprovalResponse::DenyOnce),
-                        KeyCode::Char('d') | KeyCode::Char('D') => Some(ApprovalResponse::DenyAlways),
-                        // Also treat Esc as "No"
-                        KeyCode::Esc => Some(ApprovalResponse::DenyOnce),
-                        _ => None,
-                    };
-
-                    if let Some(resp) = response {
-                        // Send response back to the waiting agent
-                        if let Some(res_tx) = app.pending_approval_tx.take() {
-                            let _ = res_tx.send(resp);
-                        }
-                        app.state = AppState::Thinking; // resume thinking state
-                        app.pending_approval_tool = None;
-                        app.pending_approval_args = None;
-                    }
+                    app.handle_approval_key(key);
                     continue; // Skip normal key handling while in approval mode
                 }
 
@@ -195,11 +636,16 @@ pub async fn run_tui(
                         KeyCode::Enter => {
                             let changed = app.select_current_agent();
                             if changed {
-                                match create_provider(&app.provider, &app.model) {
+                                match create_provider(
+                                    &app.provider,
+                                    &app.model,
+                                    app.fetched_models(&app.provider),
+                                ) {
                                     Ok(new_provider) => {
                                         let profile = AgentRegistry::get(&app.agent)
                                             .unwrap_or_else(AgentRegistry::default_agent);
-                                        let config = agent_config(profile.name, None);
+                                        let config =
+                                            agent_config(profile.name, None, app.max_iterations);
                                         agent = build_agent(
                                             new_provider,
                                             tools.clone(),
@@ -207,6 +653,8 @@ pub async fn run_tui(
                                             runtime_security.clone(),
                                             session.id.clone(),
                                             approval_tx.clone(),
+                                            app.queued_inputs.clone(),
+                                            app.working_dir.clone(),
                                         );
                                         app.push_chat(
                                             "system",
@@ -232,33 +680,157 @@ pub async fn run_tui(
 
                 if app.state == AppState::Config {
                     app.handle_config_key(key.code, &config_store);
+                    continue;
+                }
 
-                    if app.config_changed {
-                        app.config_changed = false;
-                                match create_provider(&app.provider, &app.model) {
-                                    Ok(new_provider) => {
-                                        let config = agent_config(&app.agent, None);
-                                        agent = build_agent(
-                                            new_provider,
-                                            tools.clone(),
-                                            config,
-                                            runtime_security.clone(),
-                                            session.id.clone(),
-                                            approval_tx.clone(),
-                                );
-                                app.push_chat(
-                                    "system",
-                                    &format!("Switched model to {} / {}", app.provider, app.model),
-                                    ChatStyle::System,
-                                );
-                            }
-                            Err(e) => {
-                                app.push_chat(
-                                    "error",
-                                    &format!("Failed to switch model: {}", e),
-                                    ChatStyle::Error,
-                                );
+                if app.state == AppState::Sessions {
+                    match key.code {
+                        KeyCode::Up => app.move_session_selection_up(),
+                        KeyCode::Down => app.move_session_selection_down(),
+                        KeyCode::Enter => {
+                            let Some(meta) = app.take_selected_session() else {
+                                continue;
+                            };
+                            let resumed = match session_store.load(&cwd, &meta.id).await {
+                                Ok(resumed) => resumed,
+                                Err(e) => {
+                                    app.push_chat(
+                                        "error",
+                                        &format!("Failed to resume session {}: {}", meta.id, e),
+                                        ChatStyle::Error,
+                                    );
+                                    continue;
+                                }
+                            };
+                            match create_provider(
+                                &app.provider,
+                                &app.model,
+                                app.fetched_models(&app.provider),
+                            ) {
+                                Ok(new_provider) => {
+                                    let config = agent_config(&app.agent, None, app.max_iterations);
+                                    agent = build_agent(
+                                        new_provider,
+                                        tools.clone(),
+                                        config,
+                                        runtime_security.clone(),
+                                        resumed.id.clone(),
+                                        approval_tx.clone(),
+                                        app.queued_inputs.clone(),
+                                        app.working_dir.clone(),
+                                    );
+                                }
+                                Err(e) => {
+                                    app.push_chat(
+                                        "error",
+                                        &format!("Failed to resume session {}: {}", meta.id, e),
+                                        ChatStyle::Error,
+                                    );
+                                    continue;
+                                }
                             }
+                            rlm_runtime = build_rlm_runtime(
+                                &mut app,
+                                &loaded_config,
+                                &tools,
+                                &runtime_security,
+                                &resumed.id,
+                                &approval_tx,
+                            );
+                            let restored = restore_session(
+                                &mut app,
+                                &resumed,
+                                meta.title.as_deref().unwrap_or(&meta.id),
+                            );
+                            persisted = restored.len();
+                            *messages.lock().unwrap() = restored;
+                            session = resumed;
+                            crate::crash::set_session(&session.id);
+                        }
+                        KeyCode::Esc => {
+                            app.take_selected_session();
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                if app.accepts_input() && app.handle_thinking_key(key) {
+                    continue;
+                }
+                if app.accepts_input() && app.handle_task_key(key) {
+                    if let Some(child_id) = app.pending_child_view.take() {
+                        handle_session_inspection_command(
+                            &mut app,
+                            &tools,
+                            &session_store,
+                            &cwd,
+                            &session.id,
+                            &format!("/child {child_id}"),
+                        )
+                        .await;
+                    }
+                    continue;
+                }
+
+                if app.input_mode == InputMode::Normal
+                    && app.accepts_input()
+                    && (app.handle_search_key(key.code)
+                        || app.handle_vim_key(key)
+                        || app.handle_block_key(key.code)
+                        || app.handle_copy_key(key.code))
+                {
+                    continue;
+                }
+
+                if app.input_mode == InputMode::Insert
+                    && app.accepts_input()
+                    && app.handle_history_search_key(key)
+                {
+                    continue;
+                }
+
+                if matches!(app.state, AppState::Thinking | AppState::Streaming)
+                    && key.code == KeyCode::Esc
+                    && !app.is_file_menu_active()
+                    && app.cancel_turn()
+                {
+                    continue;
+                }
+
+                if matches!(app.state, AppState::Thinking | AppState::Streaming)
+                    && app.input_mode == InputMode::Insert
+                {
+                    match key.code {
+                        KeyCode::Enter | KeyCode::Tab if app.is_file_menu_active() => {
+                            app.accept_file_completion();
+                        }
+                        KeyCode::Up if app.is_file_menu_active() => app.move_file_selection_up(),
+                        KeyCode::Down if app.is_file_menu_active() => {
+                            app.move_file_selection_down()
+                        }
+                        KeyCode::Esc if app.is_file_menu_active() => app.dismiss_file_menu(),
+                        KeyCode::Up if app.is_history_key_active() => {
+                            app.history_prev();
+                        }
+                        KeyCode::Down if app.is_browsing_history() => {
+                            app.history_next();
+                        }
+                        KeyCode::Enter
+                            if key
+                                .modifiers
+                                .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
+                        {
+                            app.insert_newline();
+                        }
+                        KeyCode::Enter => {
+                            app.queue_input();
+                        }
+                        KeyCode::Backspace => app.backspace(),
+                        KeyCode::Char(c) => app.insert_char(c),
+                        KeyCode::Esc => app.input_mode = InputMode::Normal,
+                        code => {
+                            app.handle_editor_key(code);
                         }
                     }
                     continue;
@@ -267,15 +839,148 @@ pub async fn run_tui(
                 if app.state == AppState::Idle {
                     match app.input_mode {
                         InputMode::Insert => match key.code {
+                            KeyCode::Enter | KeyCode::Tab if app.is_file_menu_active() => {
+                                app.accept_file_completion();
+                            }
+                            KeyCode::Up if app.is_file_menu_active() => {
+                                app.move_file_selection_up()
+                            }
+                            KeyCode::Down if app.is_file_menu_active() => {
+                                app.move_file_selection_down();
+                            }
+                            KeyCode::Esc if app.is_file_menu_active() => app.dismiss_file_menu(),
+                            KeyCode::Up if app.is_history_key_active() => {
+                                app.history_prev();
+                            }
+                            KeyCode::Down if app.is_browsing_history() => {
+                                app.history_next();
+                            }
                             KeyCode::Enter => {
-                                // Shift+Enter = newline, plain Enter = send
-                                if key.modifiers.contains(KeyModifiers::SHIFT) {
+                                // Shift+Enter or Alt+Enter = newline, plain Enter = send
+                                if key
+                                    .modifiers
+                                    .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT)
+                                {
                                     app.insert_newline();
                                     continue;
                                 }
 
+                                if let Some(nth) = app.editing_prompt.take() {
+                                    let input = app.submit_input();
+                                    if input.trim().is_empty() {
+                                        app.status = "Ready".to_string();
+                                        continue;
+                                    }
+
+                                    let branch_from = {
+                                        let mut msgs = messages.lock().unwrap();
+                                        rot_core::message::rewind_to_user_prompt(&mut msgs, nth)
+                                            .map(|_| {
+                                                persisted = msgs.len();
+                                                app.rewind_transcript(&msgs);
+                                                msgs.last()
+                                                    .map(|m| m.id.as_str().to_string())
+                                                    .unwrap_or_else(|| session.id.clone())
+                                            })
+                                    };
+                                    let Some(branch_from) = branch_from else {
+                                        app.push_chat(
+                                            "error",
+                                            "That message is no longer in the conversation.",
+                                            ChatStyle::Error,
+                                        );
+                                        continue;
+                                    };
+
+                                    if let Err(e) = session_store
+                                        .append(
+                                            &mut session,
+                                            SessionEntry::Branch {
+                                                id: rot_core::MessageId::new().as_str().to_string(),
+                                                from_id: branch_from,
+                                                timestamp: std::time::SystemTime::now()
+                                                    .duration_since(std::time::UNIX_EPOCH)
+                                                    .unwrap_or_default()
+                                                    .as_secs(),
+                                                label: Some(format!("edit of message {}", nth + 1)),
+                                            },
+                                        )
+                                        .await
+                                    {
+                                        app.push_chat(
+                                            "error",
+                                            &format!("Failed to record branch: {}", e),
+                                            ChatStyle::Error,
+                                        );
+                                    }
+
+                                    app.push_chat(
+                                        "system",
+                                        &format!(
+                                            "Edited message {}; regenerating from there.",
+                                            nth + 1
+                                        ),
+                                        ChatStyle::System,
+                                    );
+                                    app.message_count += 1;
+                                    app.push_chat("you", &input, ChatStyle::User);
+                                    start_turn(
+                                        &mut app,
+                                        agent.clone(),
+                                        &rlm_runtime,
+                                        &hooks,
+                                        &messages,
+                                        &tx,
+                                        input,
+                                        None,
+                                    );
+                                    continue;
+                                }
+
                                 if app.is_slash_menu_active() {
                                     if let Some(selected) = app.selected_slash_command() {
+                                        if selected == "/edit" {
+                                            app.input.clear();
+                                            app.cursor_pos = 0;
+                                            app.sync_slash_menu_selection();
+                                            begin_edit(&mut app, &messages);
+                                            continue;
+                                        }
+                                        if selected == "/compact" {
+                                            app.input.clear();
+                                            app.cursor_pos = 0;
+                                            app.sync_slash_menu_selection();
+                                            start_compaction(
+                                                &mut app,
+                                                agent.clone(),
+                                                &messages,
+                                                &tx,
+                                            );
+                                            continue;
+                                        }
+                                        if selected == "/resume" {
+                                            app.input.clear();
+                                            app.cursor_pos = 0;
+                                            app.sync_slash_menu_selection();
+                                            let sessions = resumable_sessions(
+                                                &session_store,
+                                                &cwd,
+                                                &session.id,
+                                            )
+                                            .await;
+                                            app.open_session_picker(sessions);
+                                            continue;
+                                        }
+                                        if selected == "/export" {
+                                            app.input.clear();
+                                            app.cursor_pos = 0;
+                                            app.sync_slash_menu_selection();
+                                            export_transcript(
+                                                &mut app, "", &session, &messages, &cwd,
+                                            )
+                                            .await;
+                                            continue;
+                                        }
                                         if handle_session_inspection_command(
                                             &mut app,
                                             &tools,
@@ -318,6 +1023,50 @@ pub async fn run_tui(
                                     continue;
                                 }
 
+                                if input.trim() == "/edit" {
+                                    begin_edit(&mut app, &messages);
+                                    continue;
+                                }
+
+                                if input.trim() == "/compact" {
+                                    start_compaction(&mut app, agent.clone(), &messages, &tx);
+                                    continue;
+                                }
+
+                                if let Some(target) = input.trim().strip_prefix("/cd ") {
+                                    change_working_dir(
+                                        &mut app,
+                                        target,
+                                        &agent,
+                                        &rlm_runtime,
+                                        &session_store,
+                                        &mut session,
+                                    )
+                                    .await;
+                                    continue;
+                                }
+
+                                if input.trim() == "/resume" {
+                                    let sessions =
+                                        resumable_sessions(&session_store, &cwd, &session.id).await;
+                                    app.open_session_picker(sessions);
+                                    continue;
+                                }
+
+                                if let Some(args) = input.trim().strip_prefix("/export") {
+                                    if args.is_empty() || args.starts_with(' ') {
+                                        export_transcript(
+                                            &mut app,
+                                            args.trim(),
+                                            &session,
+                                            &messages,
+                                            &cwd,
+                                        )
+                                        .await;
+                                        continue;
+                                    }
+                                }
+
                                 // Handle slash commands locally
                                 if app.handle_slash_command(input.trim()) {
                                     continue;
@@ -327,11 +1076,19 @@ pub async fn run_tui(
                                     if let Some((mentioned_agent, prompt)) =
                                         App::parse_agent_mention(&input)
                                     {
-                                        match create_provider(&app.provider, &app.model) {
+                                        match create_provider(
+                                            &app.provider,
+                                            &app.model,
+                                            app.fetched_models(&app.provider),
+                                        ) {
                                             Ok(provider) => {
                                                 let profile = AgentRegistry::get(&mentioned_agent)
                                                     .unwrap_or_else(AgentRegistry::default_agent);
-                                                let config = agent_config(profile.name, None);
+                                                let config = agent_config(
+                                                    profile.name,
+                                                    None,
+                                                    app.max_iterations,
+                                                );
                                                 (
                                                     build_agent(
                                                         provider,
@@ -340,6 +1097,8 @@ pub async fn run_tui(
                                                         runtime_security.clone(),
                                                         session.id.clone(),
                                                         approval_tx.clone(),
+                                                        app.queued_inputs.clone(),
+                                                        app.working_dir.clone(),
                                                     ),
                                                     prompt,
                                                     Some(profile.name.to_string()),
@@ -361,125 +1120,38 @@ pub async fn run_tui(
                                 // Regular message — send to agent
                                 app.message_count += 1;
                                 app.push_chat("you", &input, ChatStyle::User);
-                                app.state = AppState::Thinking;
-                                app.status = if app.rlm_enabled { "RLM Thinking...".to_string() } else { "Thinking...".to_string() };
-                                app.streaming_text.clear();
-                                app.thinking_tick = 0;
-                                app.start_timer();
-
-                                // Spawn agent processing in background
-                                let messages_clone = messages.clone();
-                                let tx_clone = tx.clone();
-                                let progress_tx = tx.clone();
-                                let input_owned = prompt_for_run.clone();
-                                let routed_agent_name = routed_agent_name.clone();
-                                let is_rlm = app.rlm_enabled;
-                                let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-
-                                tokio::spawn(async move {
-                                    let execution_agent = agent_for_run;
-                                    if is_rlm {
-                                        let rlm_config = rot_rlm::RlmConfig {
-                                            on_progress: Some(Arc::new(move |msg: String| {
-                                                let _ = progress_tx.send(AgentEvent::Progress(msg));
-                                            })),
-                                            ..Default::default()
-                                        };
-                                        
-                                        let mut engine = rot_rlm::RlmEngine::new(rlm_config, execution_agent);
-                                        let result = engine.process(&input_owned, cwd.to_str().unwrap_or(".")).await;
-                                        
-                                        match result {
-                                            Ok(ans) => {
-                                                let _ = tx_clone.send(AgentEvent::Response {
-                                                    text: ans,
-                                                    tool_names: routed_agent_name
-                                                        .map(|name| vec![format!("@{}", name), "RLM Loop".to_string()])
-                                                        .unwrap_or_else(|| vec!["RLM Loop".to_string()]),
-                                                    input_tokens: 0,
-                                                    output_tokens: 0, // Need accurate count later
-                                                });
-                                            }
-                                            Err(e) => {
-                                                let _ = tx_clone.send(AgentEvent::Error(format!("RLM Error: {}", e)));
-                                            }
-                                        }
-                                    } else {
-                                        let mut msgs = messages_clone.lock().unwrap().clone();
-                                        let result =
-                                            execution_agent.process(&mut msgs, &input_owned).await;
-
-                                        // Update shared messages
-                                        *messages_clone.lock().unwrap() = msgs;
-
-                                        let event = match result {
-                                        Ok(response) => {
-                                            let text = response
-                                                .content
-                                                .iter()
-                                                .filter_map(|c| {
-                                                    if let ContentBlock::Text { text } = c {
-                                                        Some(text.as_str())
-                                                    } else {
-                                                        None
-                                                    }
-                                                })
-                                                .collect::<Vec<_>>()
-                                                .join("\n");
-
-                                            let mut tool_names: Vec<String> = response
-                                                .content
-                                                .iter()
-                                                .filter_map(|c| {
-                                                    if let ContentBlock::ToolCall {
-                                                        name, ..
-                                                    } = c
-                                                    {
-                                                        Some(name.clone())
-                                                    } else {
-                                                        None
-                                                    }
-                                                })
-                                                .collect();
-                                            if let Some(name) = routed_agent_name {
-                                                tool_names.insert(0, format!("@{}", name));
-                                            }
-
-                                            // Estimate tokens from text (~4 chars/token)
-                                            let est_output = text.len() / 4;
-
-                                            AgentEvent::Response {
-                                                text,
-                                                tool_names,
-                                                input_tokens: 0,
-                                                output_tokens: est_output,
-                                            }
-                                        }
-                                        Err(e) => AgentEvent::Error(e.to_string()),
-                                    };
-
-                                    let _ = tx_clone.send(event);
-                                    } // End if !is_rlm
-                                });
+                                start_turn(
+                                    &mut app,
+                                    agent_for_run,
+                                    &rlm_runtime,
+                                    &hooks,
+                                    &messages,
+                                    &tx,
+                                    prompt_for_run,
+                                    routed_agent_name,
+                                );
                             }
                             KeyCode::Backspace => app.backspace(),
-                            KeyCode::Up => {
-                                if app.is_slash_menu_active() {
-                                    app.move_slash_selection_up();
-                                }
+                            KeyCode::Up if app.is_slash_menu_active() => {
+                                app.move_slash_selection_up();
                             }
-                            KeyCode::Down => {
-                                if app.is_slash_menu_active() {
-                                    app.move_slash_selection_down();
-                                }
+                            KeyCode::Down if app.is_slash_menu_active() => {
+                                app.move_slash_selection_down();
                             }
                             KeyCode::Char(c) => app.insert_char(c),
+                            KeyCode::Esc if app.editing_prompt.is_some() => app.cancel_edit(),
                             KeyCode::Esc => app.input_mode = InputMode::Normal,
-                            _ => {}
+                            code => {
+                                app.handle_editor_key(code);
+                            }
                         },
                         InputMode::Normal => match key.code {
                             KeyCode::Char('i') => app.input_mode = InputMode::Insert,
+                            KeyCode::Char('e') => begin_edit(&mut app, &messages),
                             KeyCode::Char('q') => app.running = false,
+                            KeyCode::Char('?') => {
+                                app.help = Some(crate::help::HelpOverlay::default())
+                            }
                             KeyCode::Char('k') | KeyCode::Up => {
                                 app.auto_scroll = false;
                                 app.scroll_offset = app.scroll_offset.saturating_sub(1);
@@ -503,18 +1175,423 @@ pub async fn run_tui(
                     app.scroll_offset = app.scroll_offset.saturating_add(delta as u16).min(app.max_scroll);
                 }
             }
+            TermEvent::Paste(text) => app.handle_paste(&text),
+            TermEvent::Focus(focused) => notifier.set_focused(focused),
             TermEvent::Resize(_, _) => {}
             TermEvent::Tick => {}
         }
     }
 
-    // Cleanup
-    disable_raw_mode()?;
-    stdout().execute(DisableMouseCapture)?;
-    stdout().execute(LeaveAlternateScreen)?;
+    drop(terminal_guard);
     Ok(())
 }
 
+/// Most sessions offered by the resume picker.
+const RESUME_PICKER_LIMIT: usize = 20;
+
+/// Recent top-level sessions in `cwd` that have messages, excluding `current_id`.
+async fn resumable_sessions(
+    session_store: &rot_session::SessionStore,
+    cwd: &std::path::Path,
+    current_id: &str,
+) -> Vec<rot_session::SessionMeta> {
+    let sessions = session_store.list_all(cwd).await.unwrap_or_default();
+    sessions
+        .into_iter()
+        .filter(|meta| {
+            meta.id != current_id && meta.parent_session_id.is_none() && meta.message_count > 0
+        })
+        .take(RESUME_PICKER_LIMIT)
+        .collect()
+}
+
+/// Show `session`'s transcript and token use in `app` and move to the
+/// directory its last `/cd` left it in. Returns its messages for the agent.
+fn restore_session(app: &mut App, session: &Session, label: &str) -> Vec<Message> {
+    let restored = rot_core::transcript::from_session_entries(&session.entries);
+    let resumed_dir = session.working_dir();
+    if resumed_dir.is_dir() {
+        *app.working_dir.lock().unwrap() = resumed_dir;
+        app.workspace_files = None;
+    }
+    app.load_transcript(&restored);
+    app.restore_usage(rot_core::usage::session_usage(&session.entries, &app.agent));
+    app.push_chat(
+        "system",
+        &format!("Resumed session {label} ({} messages)", restored.len()),
+        ChatStyle::System,
+    );
+    restored
+}
+
+/// Append messages added since the last call to the session file.
+async fn persist_messages(
+    session_store: &rot_session::SessionStore,
+    session: &mut Session,
+    messages: &Arc<Mutex<Vec<Message>>>,
+    persisted: &mut usize,
+) -> Result<(), String> {
+    let (new_messages, total) = {
+        let msgs = messages.lock().unwrap();
+        (msgs[(*persisted).min(msgs.len())..].to_vec(), msgs.len())
+    };
+    append_messages(session_store, session, &new_messages).await?;
+    *persisted = total;
+    Ok(())
+}
+
+/// Append `messages` to the session file with their tool call and result
+/// entries.
+async fn append_messages(
+    session_store: &rot_session::SessionStore,
+    session: &mut Session,
+    messages: &[Message],
+) -> Result<(), String> {
+    let entries = rot_core::transcript::to_session_entries(messages)
+        .map_err(|e| format!("Failed to save conversation: {e}"))?;
+    for entry in entries {
+        session_store
+            .append(session, entry)
+            .await
+            .map_err(|e| format!("Failed to save conversation: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Format and file for `/export [md|json] [path]`. A path without a format
+/// picks one from its extension; the default is `rot-<session id>.md`.
+fn export_target(args: &str, session_id: &str) -> (rot_session::ExportFormat, std::path::PathBuf) {
+    use rot_session::ExportFormat;
+
+    let (first, rest) = args.split_once(' ').unwrap_or((args, ""));
+    let (format, path) = match ExportFormat::from_name(first) {
+        Some(format) => (Some(format), rest.trim()),
+        None => (None, args),
+    };
+    if path.is_empty() {
+        let format = format.unwrap_or(ExportFormat::Markdown);
+        let path = format!("rot-{session_id}.{}", format.extension());
+        return (format, path.into());
+    }
+    let path = std::path::PathBuf::from(path);
+    (
+        format.unwrap_or_else(|| ExportFormat::from_path(&path)),
+        path,
+    )
+}
+
+/// Write the current conversation, tool calls and outputs included, to a
+/// Markdown or JSON file and report where it went.
+async fn export_transcript(
+    app: &mut App,
+    args: &str,
+    session: &Session,
+    messages: &Arc<Mutex<Vec<Message>>>,
+    cwd: &std::path::Path,
+) {
+    let (format, path) = export_target(args, &session.id);
+    let path = cwd.join(path);
+    let conversation = messages.lock().unwrap().clone();
+    let result = rot_core::transcript::to_session_entries(&conversation)
+        .map_err(rot_session::SessionError::from)
+        .and_then(|entries| {
+            let start = session
+                .entries
+                .iter()
+                .find(|entry| matches!(entry, SessionEntry::SessionStart { .. }))
+                .cloned();
+            let entries: Vec<SessionEntry> = start.into_iter().chain(entries).collect();
+            rot_session::export::export(&entries, format)
+        });
+    let written = match result {
+        Ok(text) => tokio::fs::write(&path, text)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match written {
+        Ok(()) => app.push_chat(
+            "system",
+            &format!("Exported the conversation to {}", path.display()),
+            ChatStyle::System,
+        ),
+        Err(e) => app.push_chat(
+            "error",
+            &format!("Failed to export transcript to {}: {e}", path.display()),
+            ChatStyle::Error,
+        ),
+    }
+}
+
+/// Load a previous user prompt from the conversation into the editor.
+fn begin_edit(app: &mut App, messages: &Arc<Mutex<Vec<Message>>>) {
+    let prompts = {
+        let msgs = messages.lock().unwrap();
+        rot_core::message::user_prompt_indices(&msgs)
+            .into_iter()
+            .map(|idx| msgs[idx].text())
+            .collect::<Vec<_>>()
+    };
+    app.begin_edit(&prompts);
+}
+
+/// Put the app into the thinking state and run one turn in the background.
+/// RLM settings for the session and the agent RLM runs use when `rlm.model`
+/// selects a different model than the session.
+struct RlmRuntime {
+    settings: rot_core::config::RlmSettings,
+    agent: Option<Arc<Agent>>,
+}
+
+/// `/cd DIR`: run tools in DIR from the next turn on, and record the change
+/// in the session so resuming it returns there.
+async fn change_working_dir(
+    app: &mut App,
+    target: &str,
+    agent: &Agent,
+    rlm: &RlmRuntime,
+    session_store: &rot_session::SessionStore,
+    session: &mut Session,
+) {
+    let dir = match app.change_working_dir(target) {
+        Ok(dir) => dir,
+        Err(e) => {
+            app.push_chat(
+                "error",
+                &format!("Cannot change directory: {e}"),
+                ChatStyle::Error,
+            );
+            return;
+        }
+    };
+    agent.set_working_dir(dir.clone());
+    if let Some(rlm_agent) = &rlm.agent {
+        rlm_agent.set_working_dir(dir.clone());
+    }
+    let entry = SessionEntry::CwdChange {
+        id: rot_core::MessageId::new().as_str().to_string(),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        cwd: dir.display().to_string(),
+    };
+    if let Err(e) = session_store.append(session, entry).await {
+        app.push_chat(
+            "error",
+            &format!("Failed to save session: {e}"),
+            ChatStyle::Error,
+        );
+    }
+    app.push_chat(
+        "system",
+        &format!("Working directory: {}", dir.display()),
+        ChatStyle::System,
+    );
+}
+
+/// Hook payload for `event` with the session's usage and spend so far.
+fn hook_payload(app: &App, agent: &Agent, event: HookEvent) -> HookPayload {
+    HookPayload::new(
+        event,
+        agent.session_id().unwrap_or_default(),
+        agent.model_label(),
+    )
+    .with_usage(
+        app.total_input_tokens,
+        app.total_output_tokens,
+        app.total_cost,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_turn(
+    app: &mut App,
+    agent: Arc<Agent>,
+    rlm: &RlmRuntime,
+    hooks: &Arc<Hooks>,
+    messages: &Arc<Mutex<Vec<Message>>>,
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+    prompt: String,
+    routed_agent_name: Option<String>,
+) {
+    hooks
+        .fire_in_background(hook_payload(app, &agent, HookEvent::RunStarted).with_summary(&prompt));
+    app.state = AppState::Thinking;
+    app.status = if app.rlm_enabled {
+        "RLM Thinking...".to_string()
+    } else {
+        "Thinking...".to_string()
+    };
+    app.streaming_text.clear();
+    app.thinking_text.clear();
+    app.thinking_tick = 0;
+    app.start_timer();
+    let cancel = rot_core::CancellationToken::new();
+    app.turn_cancel = Some(cancel.clone());
+
+    // Spawn agent processing in background
+    let messages_clone = messages.clone();
+    let tx_clone = tx.clone();
+    let progress_tx = tx.clone();
+    let cwd = app.working_dir();
+    let input_owned = crate::mentions::attach_files(&prompt, &cwd);
+    let is_rlm = app.rlm_enabled;
+    app.save_checkpoints = !is_rlm;
+    let mut attachments = std::mem::take(&mut app.pending_images);
+    for image in crate::mentions::mentioned_images(&prompt, &cwd) {
+        match image {
+            Ok(image) => attachments.push(image),
+            Err(e) => app.push_chat("error", &e, ChatStyle::Error),
+        }
+    }
+    if !attachments.is_empty() {
+        let chips: Vec<String> = attachments.iter().map(|image| image.chip()).collect();
+        app.push_chat("system", &chips.join(" "), ChatStyle::System);
+        if is_rlm {
+            app.push_chat(
+                "system",
+                "RLM mode sends text only; images were not sent. Use /rlm to turn it off.",
+                ChatStyle::System,
+            );
+        }
+    }
+    let images: Vec<ContentBlock> = attachments.into_iter().map(|image| image.block).collect();
+    // `/context` replaces the configured context for the rest of the session.
+    let context_path = match &app.rlm_context {
+        Some(context) => cwd.join(context),
+        None => cwd.clone(),
+    };
+    let mut rlm_config = rot_rlm::RlmConfig::from_settings(&rlm.settings);
+    let execution_agent = match (&rlm.agent, is_rlm) {
+        (Some(rlm_agent), true) => rlm_agent.clone(),
+        _ => agent,
+    };
+
+    tokio::spawn(async move {
+        if is_rlm {
+            let last_progress: Arc<Mutex<Option<rot_rlm::RlmProgress>>> = Arc::default();
+            let progress_sink = last_progress.clone();
+            rlm_config.on_progress = Some(Arc::new(move |progress: &rot_rlm::RlmProgress| {
+                *progress_sink.lock().unwrap() = Some(progress.clone());
+                let _ = progress_tx.send(AgentEvent::Progress(progress.clone()));
+            }));
+
+            let mut engine = rot_rlm::RlmEngine::new(rlm_config, execution_agent);
+            let result = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    let _ = tx_clone.send(AgentEvent::Cancelled);
+                    return;
+                }
+                result = engine.process(&input_owned, context_path.to_str().unwrap_or(".")) => result,
+            };
+
+            match result {
+                Ok(ans) => {
+                    let _ = tx_clone.send(AgentEvent::Response {
+                        text: ans,
+                        routed_agent: routed_agent_name,
+                        tools: vec![rot_core::message::ToolExchange {
+                            name: "RLM Loop".to_string(),
+                            arguments: serde_json::Value::Null,
+                            output: last_progress.lock().unwrap().as_ref().map(|done| {
+                                if done.phase == rot_rlm::RlmPhase::Cached {
+                                    "cached answer".to_string()
+                                } else {
+                                    format!(
+                                        "{} input / {} output tokens",
+                                        done.input_tokens, done.output_tokens
+                                    )
+                                }
+                            }),
+                            is_error: false,
+                            metadata: serde_json::Value::Null,
+                        }],
+                    });
+                }
+                Err(e) => {
+                    let _ = tx_clone.send(AgentEvent::Error(format!("RLM Error: {}", e)));
+                }
+            }
+        } else {
+            let mut msgs = messages_clone.lock().unwrap().clone();
+            let turn_start = msgs.len();
+            let result = execution_agent
+                .process_cancellable(&mut msgs, &input_owned, images, cancel)
+                .await;
+            let tools = rot_core::message::tool_exchanges(&msgs[turn_start.min(msgs.len())..]);
+
+            // Update shared messages
+            *messages_clone.lock().unwrap() = msgs;
+
+            let event = match result {
+                Ok(response) => {
+                    let text = response
+                        .content
+                        .iter()
+                        .filter_map(|c| {
+                            if let ContentBlock::Text { text } = c {
+                                Some(text.as_str())
+                            } else {
+                                None
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+
+                    AgentEvent::Response {
+                        text,
+                        routed_agent: routed_agent_name,
+                        tools,
+                    }
+                }
+                Err(rot_core::AgentProcessError::Cancelled) => AgentEvent::Cancelled,
+                Err(e) => AgentEvent::Error(e.to_string()),
+            };
+
+            let _ = tx_clone.send(event);
+        } // End if !is_rlm
+    });
+}
+
+/// Summarize older turns in the background, keeping the latest turn when
+/// there is more than one.
+fn start_compaction(
+    app: &mut App,
+    agent: Arc<Agent>,
+    messages: &Arc<Mutex<Vec<Message>>>,
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+) {
+    let mut msgs = messages.lock().unwrap().clone();
+    let keep_turns = rot_core::message::user_prompt_indices(&msgs)
+        .len()
+        .saturating_sub(1)
+        .min(1);
+    app.state = AppState::Thinking;
+    app.status = "Compacting…".to_string();
+    app.thinking_tick = 0;
+    app.start_timer();
+    let cancel = rot_core::CancellationToken::new();
+    app.turn_cancel = Some(cancel.clone());
+
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let event = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => AgentEvent::Cancelled,
+            result = agent.compact(&mut msgs, keep_turns) => match result {
+                Ok(compaction) => AgentEvent::Compacted {
+                    compaction,
+                    messages: msgs,
+                },
+                Err(e) => AgentEvent::Error(e.to_string()),
+            },
+        };
+        let _ = tx.send(event);
+    });
+}
+
 async fn handle_session_inspection_command(
     app: &mut App,
     tools: &rot_tools::ToolRegistry,
@@ -536,6 +1613,18 @@ async fn handle_session_inspection_command(
         return true;
     }
 
+    if trimmed == "/usage" {
+        match render_project_usage(session_store, cwd, &app.agent).await {
+            Ok(summary) => app.push_chat("system", &summary, ChatStyle::System),
+            Err(error) => app.push_chat(
+                "error",
+                &format!("Failed to read project usage: {}", error),
+                ChatStyle::Error,
+            ),
+        }
+        return true;
+    }
+
     if trimmed == "/tree" {
         match render_session_tree_summary(session_store, cwd, session_id).await {
             Ok(summary) => app.push_chat("system", &summary, ChatStyle::System),
@@ -553,6 +1642,54 @@ async fn handle_session_inspection_command(
         return true;
     }
 
+    if matches!(trimmed, "/mcp" | "/mcp status") {
+        app.push_chat(
+            "system",
+            &render_mcp_status(tools.mcp_servers()),
+            ChatStyle::System,
+        );
+        return true;
+    }
+
+    if matches!(trimmed, "/results" | "/result") || trimmed.starts_with("/result ") {
+        let results = match session_store.load(cwd, session_id).await {
+            Ok(session) => session_tool_results(&session.entries),
+            Err(error) => {
+                app.push_chat(
+                    "error",
+                    &format!("Failed to read tool results: {}", error),
+                    ChatStyle::Error,
+                );
+                return true;
+            }
+        };
+        match trimmed.strip_prefix("/result ").map(str::trim) {
+            None => app.push_chat(
+                "system",
+                &render_tool_results_summary(&results),
+                ChatStyle::System,
+            ),
+            Some(index) => match index.trim_start_matches('#').parse::<usize>() {
+                Ok(n) if (1..=results.len()).contains(&n) => {
+                    let exchange = &results[n - 1];
+                    app.open_pager(
+                        tool_result_title(n, exchange),
+                        exchange.output.as_deref().unwrap_or(""),
+                    );
+                }
+                _ => app.push_chat(
+                    "error",
+                    &format!(
+                        "No tool result {index}; this session has {}. Use /results to list them.",
+                        results.len()
+                    ),
+                    ChatStyle::Error,
+                ),
+            },
+        }
+        return true;
+    }
+
     if let Some(child_id) = trimmed.strip_prefix("/child ").map(str::trim) {
         match render_child_session_detail(session_store, cwd, session_id, child_id).await {
             Ok(detail) => app.push_chat("system", &detail, ChatStyle::System),
@@ -576,6 +1713,54 @@ async fn handle_session_inspection_command(
     false
 }
 
+/// Every tool call with a result recorded in `entries`, oldest first,
+/// including calls on abandoned branches and ones compacted away.
+fn session_tool_results(entries: &[SessionEntry]) -> Vec<rot_core::message::ToolExchange> {
+    // Only the content blocks matter for pairing calls with results.
+    let messages: Vec<Message> = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            SessionEntry::Message { content, .. } => serde_json::from_value(content.clone())
+                .ok()
+                .map(Message::assistant),
+            _ => None,
+        })
+        .collect();
+    rot_core::message::tool_exchanges(&messages)
+        .into_iter()
+        .filter(|exchange| exchange.output.is_some())
+        .collect()
+}
+
+fn tool_result_title(index: usize, exchange: &rot_core::message::ToolExchange) -> String {
+    match crate::app::key_argument(&exchange.arguments) {
+        Some(argument) => format!("#{index} {}  {argument}", exchange.name),
+        None => format!("#{index} {}", exchange.name),
+    }
+}
+
+fn render_tool_results_summary(results: &[rot_core::message::ToolExchange]) -> String {
+    if results.is_empty() {
+        return "No tool results in this session yet.".to_string();
+    }
+    let mut lines = vec![format!("Tool results ({})", results.len()), String::new()];
+    for (i, exchange) in results.iter().enumerate() {
+        let output = exchange.output.as_deref().unwrap_or("");
+        let mut line = format!(
+            "{}  · {} line(s)",
+            truncate_line(&tool_result_title(i + 1, exchange), 72),
+            output.lines().count()
+        );
+        if exchange.is_error {
+            line.push_str(" · error");
+        }
+        lines.push(line);
+    }
+    lines.push(String::new());
+    lines.push("Use /result <N> to open one in the pager.".to_string());
+    lines.join("\n")
+}
+
 fn render_tools_summary(tools: &rot_tools::ToolRegistry) -> String {
     let mut names = tools.names();
     names.sort();
@@ -594,6 +1779,42 @@ fn render_tools_summary(tools: &rot_tools::ToolRegistry) -> String {
     lines.join("\n")
 }
 
+/// Stderr lines shown per server by `/mcp status`.
+const MCP_STATUS_STDERR_LINES: usize = 10;
+
+fn render_mcp_status(servers: &[rot_tools::McpServerHandle]) -> String {
+    if servers.is_empty() {
+        return "No MCP servers are running. Add them under mcp_servers in config.".to_string();
+    }
+    let mut lines = vec![format!("MCP servers ({})", servers.len())];
+    for server in servers {
+        let state = match server.state() {
+            rot_tools::McpServerState::Running => "running".to_string(),
+            rot_tools::McpServerState::Busy => "busy".to_string(),
+            rot_tools::McpServerState::Exited(Some(code)) => format!("exited with code {code}"),
+            rot_tools::McpServerState::Exited(None) => "exited".to_string(),
+        };
+        lines.push(String::new());
+        lines.push(format!(
+            "{} · {state} · {} tool(s)",
+            server.name,
+            server.tools.len()
+        ));
+        let stderr = server.recent_stderr(MCP_STATUS_STDERR_LINES);
+        if stderr.is_empty() {
+            lines.push("  no stderr output".to_string());
+        } else {
+            lines.push("  recent stderr:".to_string());
+            lines.extend(
+                stderr
+                    .iter()
+                    .map(|line| format!("    {}", truncate_line(line, 100))),
+            );
+        }
+    }
+    lines.join("\n")
+}
+
 fn render_tool_detail(tools: &rot_tools::ToolRegistry, name: &str) -> Result<String, String> {
     let tool = tools
         .get(name)
@@ -617,6 +1838,8 @@ fn tool_kind(name: &str) -> &'static str {
     } else if matches!(
         name,
         "read" | "write" | "edit" | "bash" | "glob" | "grep" | "task" | "webfetch"
+            | "resolve_conflicts"
+            | "read_more"
     ) {
         "builtin"
     } else {
@@ -624,6 +1847,81 @@ fn tool_kind(name: &str) -> &'static str {
     }
 }
 
+async fn render_project_usage(
+    session_store: &rot_session::SessionStore,
+    cwd: &std::path::Path,
+    default_agent: &str,
+) -> Result<String, String> {
+    let metas = session_store
+        .list_all(cwd)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut sessions = Vec::new();
+    for meta in metas {
+        let Ok(session) = session_store.load(cwd, &meta.id).await else {
+            continue;
+        };
+        let usage = rot_core::usage::session_usage(&session.entries, default_agent);
+        if !usage.is_empty() {
+            sessions.push((meta, usage));
+        }
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Ok(format_project_usage(&sessions, now))
+}
+
+/// Sessions listed by `/usage`, most expensive first.
+const USAGE_TOP_SESSIONS: usize = 5;
+
+fn format_project_usage(
+    sessions: &[(rot_session::SessionMeta, rot_core::usage::UsageLedger)],
+    now: u64,
+) -> String {
+    if sessions.is_empty() {
+        return "No token usage recorded for this project yet.".to_string();
+    }
+
+    let mut project = rot_core::usage::UsageLedger::default();
+    for (_, usage) in sessions {
+        project.merge(usage);
+    }
+
+    let mut lines = vec![
+        format!(
+            "Project usage: {} across {} session(s)",
+            crate::app::format_totals(&project.total),
+            sessions.len()
+        ),
+        String::new(),
+        "By model:".to_string(),
+    ];
+    for (model, totals) in &project.by_model {
+        lines.push(format!("  {model}  {}", crate::app::format_totals(totals)));
+    }
+
+    let mut ranked: Vec<_> = sessions.iter().collect();
+    ranked.sort_by(|(_, a), (_, b)| {
+        let cost = |usage: &rot_core::usage::UsageLedger| usage.total.cost.unwrap_or(0.0);
+        cost(b)
+            .total_cmp(&cost(a))
+            .then_with(|| b.total.input_tokens.cmp(&a.total.input_tokens))
+    });
+    lines.push(String::new());
+    lines.push("Top sessions:".to_string());
+    for (meta, usage) in ranked.into_iter().take(USAGE_TOP_SESSIONS) {
+        lines.push(format!(
+            "  {}  {}  {}",
+            meta.title.as_deref().unwrap_or(&meta.id),
+            crate::app::format_age(now, meta.updated_at),
+            crate::app::format_totals(&usage.total),
+        ));
+    }
+    lines.join("\n")
+}
+
 async fn render_child_sessions_summary(
     session_store: &rot_session::SessionStore,
     cwd: &std::path::Path,
@@ -655,19 +1953,25 @@ async fn render_child_sessions_summary(
         "Delegated child sessions:".to_string(),
         String::new(),
     ];
+    let reports = task_reports(&session.entries);
 
     for (child_session_id, agent, prompt) in links {
-        let preview = match session_store.load(cwd, &child_session_id).await {
-            Ok(child) => child_session_preview(&child),
-            Err(_) => "(child session unavailable)".to_string(),
-        };
         lines.push(format!(
             "{}  @{}  {}",
             child_session_id,
             agent,
             truncate_line(&prompt, 56)
         ));
-        lines.push(format!("  {}", truncate_line(&preview, 84)));
+        match reports.get(&child_session_id) {
+            Some(report) => append_task_report_lines(&mut lines, report),
+            None => {
+                let preview = match session_store.load(cwd, &child_session_id).await {
+                    Ok(child) => child_session_preview(&child),
+                    Err(_) => "(child session unavailable)".to_string(),
+                };
+                lines.push(format!("  {}", truncate_line(&preview, 84)));
+            }
+        }
         lines.push(String::new());
     }
 
@@ -675,6 +1979,48 @@ async fn render_child_sessions_summary(
     Ok(lines.join("\n"))
 }
 
+/// Reports of the parent's finished `task` calls, by child session id.
+fn task_reports(
+    entries: &[SessionEntry],
+) -> std::collections::HashMap<String, rot_tools::TaskReport> {
+    let messages = rot_core::transcript::from_session_entries(entries);
+    rot_core::message::tool_exchanges(&messages)
+        .into_iter()
+        .filter(|exchange| exchange.name == "task" && !exchange.is_error)
+        .filter_map(|exchange| {
+            let child = exchange
+                .metadata
+                .get("child_session_id")?
+                .as_str()?
+                .to_string();
+            let report = serde_json::from_value(exchange.metadata.get("report")?.clone()).ok()?;
+            Some((child, report))
+        })
+        .collect()
+}
+
+fn append_task_report_lines(lines: &mut Vec<String>, report: &rot_tools::TaskReport) {
+    let summary = report
+        .summary
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    lines.push(format!("  {}", truncate_line(summary, 84)));
+    let files = if report.files_changed.is_empty() {
+        "no files changed".to_string()
+    } else {
+        truncate_line(&format!("changed {}", report.files_changed.join(", ")), 60)
+    };
+    lines.push(format!(
+        "  {files} · {} command(s) · {} follow-up(s)",
+        report.commands_run.len(),
+        report.follow_ups.len()
+    ));
+    for follow_up in &report.follow_ups {
+        lines.push(format!("    - {}", truncate_line(follow_up, 80)));
+    }
+}
+
 async fn render_child_session_detail(
     session_store: &rot_session::SessionStore,
     cwd: &std::path::Path,
@@ -822,6 +2168,10 @@ fn format_child_session_detail(session: &Session) -> String {
             }
             SessionEntry::ChildSessionLink { .. }
             | SessionEntry::Compaction { .. }
+            | SessionEntry::Usage { .. }
+            | SessionEntry::RlmCheckpoint { .. }
+            | SessionEntry::RunSummary { .. }
+            | SessionEntry::CwdChange { .. }
             | SessionEntry::Branch { .. } => {}
         }
     }
@@ -859,34 +2209,173 @@ fn truncate_line(value: &str, max_chars: usize) -> String {
 }
 
 // Helper to rebuild provider mid-session when configuration details change
-fn create_provider(provider_name: &str, model: &str) -> std::result::Result<Box<dyn rot_provider::Provider>, String> {
-    use rot_provider::Provider;
+/// Environment variable holding a built-in provider's API key.
+fn builtin_key_var(provider_name: &str) -> Option<&'static str> {
     match provider_name {
-        "anthropic" => {
-            let api_key = std::env::var("ANTHROPIC_API_KEY")
-                .map_err(|_| "ANTHROPIC_API_KEY not set".to_string())?;
-            let mut provider = rot_provider::AnthropicProvider::new(api_key);
-            provider.set_model(model).map_err(|e| e.to_string())?;
-            Ok(Box::new(provider))
-        }
-        "zai" => {
-            let api_key = std::env::var("ZAI_API_KEY")
-                .map_err(|_| "ZAI_API_KEY not set".to_string())?;
-            let mut provider = rot_provider::new_zai_provider(api_key);
-            provider.set_model(model).map_err(|e| e.to_string())?;
-            Ok(Box::new(provider))
-        }
-        "openai" => {
-            let api_key = std::env::var("OPENAI_API_KEY")
-                .map_err(|_| "OPENAI_API_KEY not set".to_string())?;
-            let mut provider = rot_provider::new_openai_provider(api_key);
-            provider.set_model(model).map_err(|e| e.to_string())?;
-            Ok(Box::new(provider))
+        "anthropic" => Some("ANTHROPIC_API_KEY"),
+        "zai" => Some("ZAI_API_KEY"),
+        "openai" => Some("OPENAI_API_KEY"),
+        "openrouter" => Some("OPENROUTER_API_KEY"),
+        _ => None,
+    }
+}
+
+/// The unset API key variable of a built-in provider. Custom providers may
+/// run without a key.
+fn missing_api_key(provider_name: &str) -> Option<&'static str> {
+    if rot_core::ConfigStore::new()
+        .load()
+        .providers
+        .contains_key(provider_name)
+    {
+        return None;
+    }
+    builtin_key_var(provider_name).filter(|var| std::env::var(var).is_err())
+}
+
+/// Build a provider with its default model. `models`, when given, replaces
+/// its model list so models newer than the built-in list can be selected.
+/// A missing API key is left empty.
+fn new_provider(
+    provider_name: &str,
+    models: Option<&[rot_provider::ModelInfo]>,
+) -> std::result::Result<Box<dyn rot_provider::Provider>, String> {
+    let config = rot_core::ConfigStore::new().load();
+    if let Some(custom) = config.providers.get(provider_name) {
+        let mut custom = custom.clone();
+        for model in models.unwrap_or_default() {
+            if !custom.models.iter().any(|m| m.id == model.id) {
+                custom
+                    .models
+                    .push(rot_core::custom_provider::CustomModelConfig {
+                        id: model.id.clone(),
+                        name: Some(model.name.clone()),
+                        context_window: model.context_window,
+                        max_output_tokens: model.max_output_tokens,
+                        supports_tools: model.supports_tools,
+                        supports_thinking: model.supports_thinking,
+                        supports_images: model.supports_images,
+                    });
+            }
         }
-        other => Err(format!("Unknown provider: {}", other)),
+        return custom.build(provider_name, None).map_err(|e| e.to_string());
+    }
+    let api_key = builtin_key_var(provider_name)
+        .and_then(|var| std::env::var(var).ok())
+        .unwrap_or_default();
+    match (provider_name, models) {
+        ("anthropic", None) => Ok(Box::new(rot_provider::AnthropicProvider::new(api_key))),
+        ("anthropic", Some(models)) => Ok(Box::new(
+            rot_provider::AnthropicProvider::new(api_key).with_models(models.to_vec()),
+        )),
+        ("zai", None) => Ok(Box::new(rot_provider::new_zai_provider(api_key))),
+        ("zai", Some(models)) => Ok(Box::new(
+            rot_provider::new_zai_provider(api_key).with_models(models.to_vec()),
+        )),
+        ("openai", None) => Ok(Box::new(rot_provider::new_openai_provider(api_key))),
+        ("openai", Some(models)) => Ok(Box::new(
+            rot_provider::new_openai_provider(api_key).with_models(models.to_vec()),
+        )),
+        ("openrouter", None) => Ok(Box::new(rot_provider::new_openrouter_provider(
+            api_key,
+            &config.openrouter_routing,
+        ))),
+        ("openrouter", Some(models)) => Ok(Box::new(
+            rot_provider::new_openrouter_provider(api_key, &config.openrouter_routing)
+                .with_models(models.to_vec()),
+        )),
+        (other, _) => Err(format!("Unknown provider: {}", other)),
+    }
+}
+
+fn create_provider(
+    provider_name: &str,
+    model: &str,
+    models: Option<&[rot_provider::ModelInfo]>,
+) -> std::result::Result<Box<dyn rot_provider::Provider>, String> {
+    if let Some(var) = missing_api_key(provider_name) {
+        return Err(format!("{var} not set"));
+    }
+    let mut provider = new_provider(provider_name, models)?;
+    provider.set_model(model).map_err(|e| e.to_string())?;
+    Ok(provider)
+}
+
+/// Models for the picker: the provider's live list, or its built-in list
+/// with the reason the live one is unavailable.
+async fn list_models(provider_name: &str) -> ModelListing {
+    let provider = match new_provider(provider_name, None) {
+        Ok(provider) => provider,
+        Err(e) => return ModelListing::Failed(e),
+    };
+    if let Some(var) = missing_api_key(provider_name) {
+        return ModelListing::Builtin {
+            models: provider.models(),
+            reason: format!("{var} not set"),
+        };
+    }
+    match provider.fetch_models().await {
+        Ok(models) if !models.is_empty() => ModelListing::Live(models),
+        Ok(_) => ModelListing::Builtin {
+            models: provider.models(),
+            reason: "the provider listed no models".to_string(),
+        },
+        Err(e) => ModelListing::Builtin {
+            models: provider.models(),
+            reason: e.to_string(),
+        },
     }
 }
 
+/// Names of the providers declared in config, sorted.
+fn custom_provider_names(config: &rot_core::Config) -> Vec<String> {
+    let mut names: Vec<String> = config.providers.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Resolve the RLM settings and, when `rlm.model` is set, build its agent.
+fn build_rlm_runtime(
+    app: &mut App,
+    config: &rot_core::Config,
+    tools: &rot_tools::ToolRegistry,
+    runtime_security: &rot_core::RuntimeSecurityConfig,
+    session_id: &str,
+    approval_tx: &mpsc::UnboundedSender<AgentEvent>,
+) -> RlmRuntime {
+    let settings = config.rlm.clone();
+    let agent = settings.model.as_deref().and_then(|name| {
+        let model_ref = config.resolve_model(name);
+        let provider_name = model_ref.provider.unwrap_or_else(|| app.provider.clone());
+        match create_provider(
+            &provider_name,
+            &model_ref.model,
+            app.fetched_models(&provider_name),
+        ) {
+            Ok(provider) => Some(build_agent(
+                provider,
+                tools.clone(),
+                agent_config(&app.agent, None, app.max_iterations),
+                runtime_security.clone(),
+                session_id.to_string(),
+                approval_tx.clone(),
+                app.queued_inputs.clone(),
+                app.working_dir.clone(),
+            )),
+            Err(e) => {
+                app.push_chat(
+                    "error",
+                    &format!("RLM model '{name}' unavailable, using session model: {e}"),
+                    ChatStyle::Error,
+                );
+                None
+            }
+        }
+    });
+    RlmRuntime { settings, agent }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn build_agent(
     provider: Box<dyn rot_provider::Provider>,
     tools: rot_tools::ToolRegistry,
@@ -894,10 +2383,71 @@ fn build_agent(
     runtime_security: rot_core::RuntimeSecurityConfig,
     session_id: String,
     approval_tx: mpsc::UnboundedSender<AgentEvent>,
+    queued_inputs: Arc<Mutex<VecDeque<String>>>,
+    working_dir: Arc<Mutex<std::path::PathBuf>>,
 ) -> Arc<Agent> {
+    let iteration_tx = approval_tx.clone();
+    let task_tx = approval_tx.clone();
+    let tool_tx = approval_tx.clone();
+    let usage_tx = approval_tx.clone();
+    let thinking_tx = approval_tx.clone();
+    let text_tx = approval_tx.clone();
+    let checkpoint_tx = approval_tx.clone();
+    let retry_tx = approval_tx.clone();
+    let start_dir = working_dir.lock().unwrap().clone();
     Arc::new(
         Agent::new(provider, tools, config, runtime_security)
             .with_session_id(session_id)
+            .with_working_dir(start_dir)
+            .with_host_allowlist(rot_core::network::HostAllowlist::new(
+                &std::env::current_dir().unwrap_or_default(),
+            ))
+            .on_iteration(Box::new(move |progress| {
+                let _ = iteration_tx.send(AgentEvent::Iteration(*progress));
+            }))
+            .on_task(Box::new(move |event| {
+                let _ = task_tx.send(AgentEvent::Task(event.clone()));
+            }))
+            .on_tool(Box::new(move |event| {
+                let _ = tool_tx.send(AgentEvent::Tool(event.clone()));
+            }))
+            .on_usage(Box::new(move |report| {
+                let _ = usage_tx.send(AgentEvent::Usage(report.clone()));
+            }))
+            .on_thinking(Box::new(move |delta| {
+                let _ = thinking_tx.send(AgentEvent::Thinking(delta.to_string()));
+            }))
+            .on_text(Box::new(move |delta| {
+                let _ = text_tx.send(AgentEvent::Text(delta.to_string()));
+            }))
+            .on_checkpoint(Box::new(move |messages| {
+                let _ = checkpoint_tx.send(AgentEvent::Checkpoint(messages.to_vec()));
+            }))
+            .on_event(Box::new(move |event| {
+                if let rot_provider::StreamEvent::Retrying {
+                    attempt,
+                    max_retries,
+                    delay_ms,
+                    error,
+                } = event
+                {
+                    let _ = retry_tx.send(AgentEvent::Retrying {
+                        attempt: *attempt,
+                        max_retries: *max_retries,
+                        delay_ms: *delay_ms,
+                        error: error.clone(),
+                    });
+                }
+            }))
+            .with_steering(Box::new(move || {
+                let cwd = working_dir.lock().unwrap().clone();
+                queued_inputs
+                    .lock()
+                    .unwrap()
+                    .drain(..)
+                    .map(|input| crate::mentions::attach_files(&input, &cwd))
+                    .collect()
+            }))
             .on_approval(Box::new(move |tool_name, args| {
                 let tx_clone = approval_tx.clone();
                 let tool_name = tool_name.to_string();
@@ -915,7 +2465,11 @@ fn build_agent(
     )
 }
 
-fn agent_config(agent_name: &str, initial_system_prompt: Option<String>) -> AgentConfig {
+fn agent_config(
+    agent_name: &str,
+    initial_system_prompt: Option<String>,
+    max_iterations: usize,
+) -> AgentConfig {
     let system_prompt = if let Some(system_prompt) = initial_system_prompt {
         system_prompt
     } else if agent_name.eq_ignore_ascii_case("default") {
@@ -927,10 +2481,15 @@ fn agent_config(agent_name: &str, initial_system_prompt: Option<String>) -> Agen
             .to_string()
     };
 
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let config = rot_core::ConfigStore::new().load();
     AgentConfig {
         agent_name: agent_name.to_string(),
-        system_prompt: Some(system_prompt),
-        ..Default::default()
+        system_prompt: Some(rot_core::project::with_instructions(system_prompt, &cwd)),
+        max_iterations,
+        // Any agent may run RLM once it is toggled on.
+        task_policy: config.rlm.task_policy(config.tasks.policy()),
+        ..AgentConfig::from_config(&config)
     }
 }
 
@@ -944,7 +2503,7 @@ mod tests {
         rot_tools::register_all(&mut tools);
 
         let summary = render_tools_summary(&tools);
-        assert!(summary.contains("Loaded tools (8)"));
+        assert!(summary.contains("Loaded tools (10)"));
         assert!(summary.contains("read [builtin]"));
     }
 
@@ -1005,6 +2564,155 @@ mod tests {
         assert!(formatted.contains("assistant done"));
     }
 
+    #[test]
+    fn test_task_reports_show_in_child_summary() {
+        let message = |id: &str, role: &str, content: serde_json::Value| SessionEntry::Message {
+            id: id.to_string(),
+            parent_id: None,
+            timestamp: 1,
+            role: role.to_string(),
+            content,
+        };
+        let entries = vec![
+            message(
+                "msg-1",
+                "assistant",
+                serde_json::json!([{"type":"tool_call","id":"call-1","name":"task","arguments":{"prompt":"fix it"}}]),
+            ),
+            message(
+                "msg-2",
+                "tool",
+                serde_json::json!([{"type":"tool_result","tool_call_id":"call-1","content":"Fixed.","is_error":false,
+                    "metadata":{"child_session_id":"child-1","report":{
+                        "summary":"Fixed the parser.","files_changed":["src/lib.rs"],
+                        "commands_run":["cargo test"],"follow_ups":["add a fuzz test"]}}}]),
+            ),
+        ];
+
+        let reports = task_reports(&entries);
+        let report = &reports["child-1"];
+        assert_eq!(report.files_changed, ["src/lib.rs"]);
+
+        let mut lines = Vec::new();
+        append_task_report_lines(&mut lines, report);
+        assert_eq!(
+            lines,
+            [
+                "  Fixed the parser.",
+                "  changed src/lib.rs · 1 command(s) · 1 follow-up(s)",
+                "    - add a fuzz test",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_restore_session_loads_transcript_usage_and_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let message = |id: &str, role: &str, content: serde_json::Value| SessionEntry::Message {
+            id: id.to_string(),
+            parent_id: None,
+            timestamp: 1,
+            role: role.to_string(),
+            content,
+        };
+        let session = Session {
+            id: "01J".to_string(),
+            file_path: dir.path().join("01J.jsonl"),
+            cwd: std::path::PathBuf::from("/"),
+            entries: vec![
+                message(
+                    "msg-1",
+                    "user",
+                    serde_json::json!([{"type":"text","text":"hello"}]),
+                ),
+                message(
+                    "msg-2",
+                    "assistant",
+                    serde_json::json!([{"type":"text","text":"hi there"}]),
+                ),
+                SessionEntry::Usage {
+                    id: "usage-1".to_string(),
+                    timestamp: 1,
+                    model: "gpt-4o".to_string(),
+                    input_tokens: 1200,
+                    output_tokens: 300,
+                    agent: None,
+                },
+                SessionEntry::CwdChange {
+                    id: "cwd-1".to_string(),
+                    timestamp: 1,
+                    cwd: dir.path().display().to_string(),
+                },
+            ],
+            current_leaf: "msg-2".to_string(),
+        };
+        let mut app = App::new("gpt-4o", "openai", "default");
+
+        let restored = restore_session(&mut app, &session, "01J");
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored[1].text(), "hi there");
+        assert_eq!(app.total_input_tokens, 1200);
+        assert_eq!(app.total_output_tokens, 300);
+        assert_eq!(*app.working_dir.lock().unwrap(), dir.path());
+        assert!(app
+            .chat_lines
+            .iter()
+            .any(|line| line.content.contains("hi there")));
+        assert_eq!(
+            app.chat_lines.last().unwrap().content,
+            "Resumed session 01J (2 messages)"
+        );
+    }
+
+    #[test]
+    fn test_session_tool_results_lists_every_result() {
+        let message = |id: &str, role: &str, content: serde_json::Value| SessionEntry::Message {
+            id: id.to_string(),
+            parent_id: None,
+            timestamp: 1,
+            role: role.to_string(),
+            content,
+        };
+        let entries = vec![
+            message(
+                "msg-1",
+                "assistant",
+                serde_json::json!([
+                    {"type":"tool_call","id":"call-1","name":"bash","arguments":{"command":"cargo test"}},
+                    {"type":"tool_call","id":"call-2","name":"read","arguments":{"path":"src/lib.rs"}}
+                ]),
+            ),
+            message(
+                "msg-2",
+                "tool",
+                serde_json::json!([{"type":"tool_result","tool_call_id":"call-1","content":"ok\nall passed","is_error":false}]),
+            ),
+            message(
+                "msg-3",
+                "tool",
+                serde_json::json!([{"type":"tool_result","tool_call_id":"call-2","content":"missing","is_error":true}]),
+            ),
+            SessionEntry::Compaction {
+                id: "c-1".to_string(),
+                timestamp: 2,
+                summary: "earlier work".to_string(),
+                first_kept_id: "msg-4".to_string(),
+            },
+        ];
+
+        let results = session_tool_results(&entries);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].output.as_deref(), Some("ok\nall passed"));
+
+        let summary = render_tool_results_summary(&results);
+        assert!(summary.contains("#1 bash  cargo test  · 2 line(s)\n"));
+        assert!(summary.contains("#2 read  src/lib.rs  · 1 line(s) · error\n"));
+        assert_eq!(
+            render_tool_results_summary(&[]),
+            "No tool results in this session yet."
+        );
+    }
+
     #[test]
     fn test_append_session_tree_lines_renders_children() {
         let tree = rot_session::SessionTreeNode {
@@ -1042,4 +2750,57 @@ mod tests {
         assert!(lines.iter().any(|line| line.contains("root @root")));
         assert!(lines.iter().any(|line| line.contains("└─ > child @review")));
     }
+
+    #[test]
+    fn test_format_project_usage_ranks_sessions_by_spend() {
+        let session = |id: &str, input| {
+            let meta = rot_session::SessionMeta {
+                id: id.to_string(),
+                created_at: 0,
+                updated_at: 0,
+                title: None,
+                cwd: ".".to_string(),
+                model: "claude-sonnet-4-20250514".to_string(),
+                provider: "anthropic".to_string(),
+                parent_session_id: None,
+                agent: None,
+                message_count: 2,
+            };
+            let mut usage = rot_core::usage::UsageLedger::default();
+            usage.record("claude-sonnet-4-20250514", "build", input, 0);
+            (meta, usage)
+        };
+
+        let summary =
+            format_project_usage(&[session("cheap", 1_000), session("big", 1_000_000)], 60);
+        assert!(summary.starts_with("Project usage: 1.0M in · 0 out · $3.0030 across 2 session(s)"));
+        let big = summary.find("  big  1m ago").unwrap();
+        assert!(big < summary.find("  cheap  ").unwrap());
+        assert_eq!(
+            format_project_usage(&[], 0),
+            "No token usage recorded for this project yet."
+        );
+    }
+
+    #[test]
+    fn test_export_target_defaults_and_extensions() {
+        use rot_session::ExportFormat;
+
+        assert_eq!(
+            export_target("", "s1"),
+            (ExportFormat::Markdown, "rot-s1.md".into())
+        );
+        assert_eq!(
+            export_target("json", "s1"),
+            (ExportFormat::Json, "rot-s1.json".into())
+        );
+        assert_eq!(
+            export_target("md notes/chat log.txt", "s1"),
+            (ExportFormat::Markdown, "notes/chat log.txt".into())
+        );
+        assert_eq!(
+            export_target("out/chat.json", "s1"),
+            (ExportFormat::Json, "out/chat.json".into())
+        );
+    }
 }